@@ -0,0 +1,91 @@
+//! Compares recording migrations one statement at a time with the batched VersionStore
+//! writes. Needs an ODBC driver for the target database:
+//!
+//!     cargo bench --bench version_store
+//!     DERIDDL_BENCH_DIALECT=postgres DERIDDL_BENCH_CONN="Driver={PostgreSQL Unicode};..." cargo bench --bench version_store
+//!
+//! Without DERIDDL_BENCH_CONN a SQLite database in the temp directory is used.
+//! DERIDDL_BENCH_MIGRATIONS sets how many migrations are recorded (default 200).
+
+use deriddl_rs::executor::ConnectionError;
+use deriddl_rs::model::Migration;
+use deriddl_rs::tracker::{schema_init, Target, TrackerSql, VersionStore};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+fn main() {
+    let dialect = std::env::var("DERIDDL_BENCH_DIALECT").unwrap_or_else(|_| "sqlite".to_string());
+    let conn = std::env::var("DERIDDL_BENCH_CONN").unwrap_or_else(|_| {
+        let path = std::env::temp_dir().join("deriddl_bench.db");
+        format!("Driver=SQLite3;Database={};", path.display())
+    });
+    let count: u32 = std::env::var("DERIDDL_BENCH_MIGRATIONS").ok().and_then(|n| n.parse().ok()).unwrap_or(200);
+    let target = Target::new(&conn).with_sql(TrackerSql::for_dialect(Some(&dialect)));
+
+    if let Err(e) = prepare(&target) {
+        eprintln!("Cannot prepare schema_migrations on {}: {}", dialect, e);
+        std::process::exit(1);
+    }
+    let migrations: Vec<Migration> = (1..=count)
+        .map(|version| {
+            Migration::new(version, "bench".to_string(), PathBuf::from(format!("{:04}_bench.sql", version)), "SELECT 1;".to_string())
+        })
+        .collect();
+    let migrations: Vec<&Migration> = migrations.iter().collect();
+
+    let one_by_one = measure(&target, |store| {
+        for migration in &migrations {
+            store.record_migration_start(migration)?;
+            store.record_migration_success(migration, 1)?;
+        }
+        Ok(())
+    });
+    // The way `apply --atomic` records its batch, over one connection of its own
+    let batched = measure(&target, |store| {
+        let manager = target.connection_manager()?;
+        let mut executor = manager.executor(&target.conn)?;
+        for query in store.migrations_start_sql(&migrations)? {
+            executor.execute_query(&query)?;
+        }
+        for migration in &migrations {
+            store.defer_migration_success(migration, 1);
+        }
+        store.flush_deferred(&mut executor).map(|_| ())
+    });
+
+    println!("{} migrations on {}", count, dialect);
+    println!("  one by one: {:>8.1}ms", one_by_one.as_secs_f64() * 1000.0);
+    println!("  batched:    {:>8.1}ms ({:.1}x)", batched.as_secs_f64() * 1000.0, one_by_one.as_secs_f64() / batched.as_secs_f64());
+}
+
+fn prepare(target: &Target) -> Result<(), ConnectionError> {
+    if !schema_init::check_migration_table_exists(target)? {
+        schema_init::init_migration_table(target)?;
+    }
+    clear(target)
+}
+
+/// Removes the records a previous measurement left behind
+fn clear(target: &Target) -> Result<(), ConnectionError> {
+    let manager = target.connection_manager()?;
+    let mut executor = manager.executor(&target.conn)?;
+    executor.execute_query("DELETE FROM schema_migrations WHERE filename LIKE '%_bench.sql'")
+}
+
+fn measure(
+    target: &Target,
+    record: impl FnOnce(&mut VersionStore) -> Result<(), ConnectionError>,
+) -> Duration {
+    let run = || -> Result<Duration, ConnectionError> {
+        let mut store = target.version_store()?;
+        let start = Instant::now();
+        record(&mut store)?;
+        let elapsed = start.elapsed();
+        clear(target)?;
+        Ok(elapsed)
+    };
+    run().unwrap_or_else(|e| {
+        eprintln!("Benchmark failed: {}", e);
+        std::process::exit(1);
+    })
+}
@@ -1,4 +1,11 @@
 use clap::{Parser, Subcommand};
+use clap_complete::Shell;
+use crate::orchestrator::codegen::TypesLanguage;
+use crate::orchestrator::restore_files::VersionRange;
+use crate::orchestrator::sandbox::SandboxEngine;
+use crate::orchestrator::self_update::ReleaseChannel;
+use crate::orchestrator::schema_docs::DocsFormat;
+use crate::orchestrator::sql::SqlOutputFormat;
 
 /// CLI entry point for deriddl
 #[derive(Parser, Debug)]
@@ -17,6 +24,42 @@ pub struct Cli {
     #[arg(long, global = true)]
     pub verbose: bool,
 
+    /// Fail on unknown keys in configuration files instead of warning about them
+    #[arg(long, global = true)]
+    pub strict_config: bool,
+
+    /// Override a configuration setting for this run, e.g. `--set migrations.validate_sql=false`;
+    /// repeatable and applied after every configuration file. Given before the command, since
+    /// `new --set` sets template variables.
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    pub set: Vec<String>,
+
+    /// Only show warnings, errors and final summaries
+    #[arg(long, short, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Disable colored output (also disabled when NO_COLOR is set or stdout is not a terminal)
+    #[arg(long, global = true)]
+    pub no_color: bool,
+
+    /// Load migrations from a git ref (git://<repo>#<tag>), an HTTPS or S3 tar archive, or a
+    /// directory; configured migration paths are resolved inside it
+    #[arg(long, global = true)]
+    pub source: Option<String>,
+
+    /// Unity Catalog catalog holding schema_migrations and the migrated schema (Databricks);
+    /// overrides `[dialect.databricks] catalog`
+    #[arg(long, global = true)]
+    pub catalog: Option<String>,
+
+    /// Answer yes to confirmation prompts
+    #[arg(long, short, global = true)]
+    pub yes: bool,
+
+    /// Never prompt; fail when a confirmation would be needed (also when stdin is not a terminal)
+    #[arg(long, global = true)]
+    pub non_interactive: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -36,6 +79,78 @@ pub enum Commands {
         /// Preview changes without applying
         #[arg(long)]
         dry_run: bool,
+
+        /// Append every executed statement to this SQL file
+        #[arg(long)]
+        sql_log: Option<String>,
+
+        /// Write the SQL that would run to this file instead of executing it
+        #[arg(long, value_name = "FILE", conflicts_with = "dry_run")]
+        script_only: Option<String>,
+
+        /// Target name confirming destructive migrations against a protected target
+        #[arg(long, value_name = "TARGET")]
+        confirm: Option<String>,
+
+        /// Signed plan (from `plan --export-plan` and `approve`) the pending set must match
+        #[arg(long, value_name = "FILE")]
+        approved_plan: Option<String>,
+
+        /// Apply exactly the plan exported with `plan --export`; refuse if the migration
+        /// directory or the pending set changed since
+        #[arg(long, value_name = "FILE")]
+        plan_file: Option<String>,
+
+        /// Run as a Kubernetes Job or init container: wait for the database, hold a lease
+        /// lock, never prompt, log JSON and touch a liveness file (see [kubernetes] config)
+        #[arg(long, conflicts_with_all = ["dry_run", "script_only"])]
+        k8s_mode: bool,
+
+        /// With --k8s-mode, take over the migration lock when its holder stopped sending
+        /// heartbeats (a crashed pod) instead of waiting for its lease to expire
+        #[arg(long, requires = "k8s_mode")]
+        steal_lock: bool,
+
+        /// Release to record the applied migrations under (default: git tag at HEAD)
+        #[arg(long, value_name = "NAME")]
+        release: Option<String>,
+
+        /// Only apply migrations owned by this team (see owners.toml)
+        #[arg(long, value_name = "TEAM")]
+        only_owner: Option<String>,
+
+        /// Apply only these pending migrations (version, identifier such as R__views, or
+        /// file name; repeatable or comma-separated), e.g. to hotfix one change out of a batch
+        #[arg(long, value_name = "MIGRATION", value_delimiter = ',', conflicts_with = "plan_file")]
+        only: Vec<String>,
+
+        /// With --only, apply even when earlier versions are still pending
+        #[arg(long, requires = "only")]
+        allow_gaps: bool,
+
+        /// Continue the interrupted run with this id (see `status`) from its next migration,
+        /// without asking again for confirmations given when it started
+        #[arg(long, value_name = "RUN_ID", conflicts_with_all = ["only", "only_owner", "plan_file"])]
+        resume: Option<String>,
+
+        /// Apply all pending migrations or none: in one transaction on dialects with
+        /// transactional DDL, elsewhere by running the down SQL of the applied ones when one fails
+        #[arg(long, conflicts_with_all = ["script_only", "resume"])]
+        atomic: bool,
+
+        /// Apply the migrations in a bundle written by `deriddl bundle` (a path, or an
+        /// https://, s3:// or gs:// URL) after verifying its manifest, instead of --path
+        #[arg(long, value_name = "BUNDLE")]
+        bundle: Option<String>,
+
+        /// Apply to every target (each config/<target>.toml except default and local),
+        /// each in its own process with its own lock, ending with a summary table
+        #[arg(long, conflicts_with_all = ["conn", "k8s_mode"])]
+        all_targets: bool,
+
+        /// With --all-targets, how many targets to apply to at the same time
+        #[arg(long, value_name = "N", default_value_t = 1, requires = "all_targets")]
+        parallel: usize,
     },
 
     /// Show applied and pending migrations
@@ -47,6 +162,34 @@ pub enum Commands {
         /// Path to .sql migration files
         #[arg(long, default_value = "./migrations")]
         path: String,
+
+        /// Only show migrations owned by this team (see owners.toml)
+        #[arg(long, value_name = "TEAM")]
+        owner: Option<String>,
+
+        /// Also write pending/failed counts, last applied version and apply time to this
+        /// file in node_exporter textfile-collector format (e.g. /var/lib/node_exporter/deriddl.prom)
+        #[arg(long, value_name = "FILE")]
+        metrics: Option<String>,
+    },
+
+    /// Create the next versioned migration file, optionally from a template
+    New {
+        /// Description used in the file name, e.g. add_users_email_index
+        name: String,
+
+        /// Directory to create the migration in
+        #[arg(long, default_value = "./migrations")]
+        path: String,
+
+        /// Template to start from: create_table, add_column, add_index_concurrently,
+        /// backfill, or a <name>.sql.hbs in migrations.templates_dir (.deriddl/templates)
+        #[arg(long)]
+        template: Option<String>,
+
+        /// Value for a template variable; variables without one are asked for
+        #[arg(long = "set", visible_alias = "var", value_name = "KEY=VALUE", value_parser = parse_var)]
+        vars: Vec<(String, String)>,
     },
 
     /// Initialize schema_migrations table
@@ -65,6 +208,29 @@ pub enum Commands {
         /// Path to .sql migration files
         #[arg(long, default_value = "./migrations")]
         path: String,
+
+        /// Write the pending migrations to a JSON plan file for review and approval
+        #[arg(long, visible_alias = "export", value_name = "FILE")]
+        export_plan: Option<String>,
+
+        /// Run EXPLAIN (not EXPLAIN ANALYZE) on pending DML and show estimated rows and
+        /// costs (postgres, mysql, sqlite)
+        #[arg(long)]
+        explain: bool,
+    },
+
+    /// Sign an exported plan so it can be applied to protected targets
+    Approve {
+        /// Plan file written by `plan --export-plan`
+        plan: String,
+
+        /// Reviewer private key file (hex-encoded Ed25519 seed)
+        #[arg(long)]
+        key: String,
+
+        /// Create a new private key at --key before signing
+        #[arg(long)]
+        generate_key: bool,
     },
 
     /// Check system readiness and dependencies
@@ -78,6 +244,25 @@ pub enum Commands {
         dialect: String,
     },
 
+    /// Diagnose configuration, drivers, migrations and the migration table
+    Doctor {
+        /// ODBC connection string
+        #[arg(long)]
+        conn: Option<String>,
+
+        /// Path to .sql migration files (defaults to migrations.path)
+        #[arg(long)]
+        path: Option<String>,
+
+        /// SQL dialect to check (defaults to migrations.dialect)
+        #[arg(long)]
+        dialect: Option<String>,
+
+        /// Print the report as JSON, e.g. to attach to a support ticket
+        #[arg(long)]
+        json: bool,
+    },
+
     /// Validate migration integrity and checksums
     Validate {
         /// ODBC connection string
@@ -107,13 +292,30 @@ pub enum Commands {
         #[arg(long, conflicts_with = "steps")]
         to_version: Option<u32>,
 
+        /// Roll back the migrations applied as this release
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["steps", "to_version"])]
+        release: Option<String>,
+
+        /// Restore the previous definition of this repeatable migration (e.g. R__reporting_view),
+        /// kept when `migrations.repeatable_history` is enabled
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["steps", "to_version", "release"])]
+        repeatable: Option<String>,
+
         /// Preview rollback without applying
         #[arg(long)]
         dry_run: bool,
 
-        /// Skip confirmation prompt for destructive operations
+        /// Skip confirmation prompt for destructive operations (same as --yes)
         #[arg(long)]
         force: bool,
+
+        /// Append every executed statement to this SQL file
+        #[arg(long)]
+        sql_log: Option<String>,
+
+        /// Target name confirming a rollback against a protected target
+        #[arg(long, value_name = "TARGET")]
+        confirm: Option<String>,
     },
 
     /// Create baseline for existing database
@@ -139,187 +341,1123 @@ pub enum Commands {
         dry_run: bool,
     },
 
-    /// Generate configuration file
-    Config {
-        /// Output path for config file
-        #[arg(long, default_value = "config.toml")]
-        output: String,
-
-        /// Create environment-specific config
+    /// Record migrations as applied without running them
+    MarkApplied {
+        /// ODBC connection string
         #[arg(long)]
-        env: Option<String>,
-    },
-}
+        conn: Option<String>,
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use clap::Parser;
+        /// Path to .sql migration files
+        #[arg(long, default_value = "./migrations")]
+        path: String,
 
-    #[test]
-    fn test_cli_help() {
-        let result = Cli::try_parse_from(["deriddl_rs", "--help"]);
-        assert!(result.is_err()); // Help exits with error
-    }
+        /// Version of the pending migration to mark as applied
+        #[arg(long, required_unless_present = "all_pending", conflicts_with = "all_pending")]
+        version: Option<u32>,
 
-    #[test]
-    fn test_cli_version() {
-        let result = Cli::try_parse_from(["deriddl_rs", "--version"]);
-        assert!(result.is_err()); // Version exits with error
-    }
+        /// Mark every pending migration as applied
+        #[arg(long)]
+        all_pending: bool,
 
-    #[test]
-    fn test_apply_command_defaults() {
-        let cli = Cli::try_parse_from(["deriddl_rs", "apply"]).unwrap();
-        match cli.command {
-            Commands::Apply {
-                conn,
-                path,
-                dry_run,
-            } => {
-                assert_eq!(conn, None);
-                assert_eq!(path, "./migrations");
-                assert!(!dry_run);
-            }
-            _ => panic!("Expected Apply command"),
-        }
-    }
+        /// Show what would be marked without writing to schema_migrations
+        #[arg(long)]
+        dry_run: bool,
 
-    #[test]
-    fn test_apply_command_with_flags() {
-        let cli = Cli::try_parse_from([
-            "deriddl_rs",
-            "apply",
-            "--conn",
-            "Driver={SQLite3};Database=test.db;",
-            "--path",
-            "./custom-migrations",
-            "--dry-run",
-        ])
-        .unwrap();
+        /// Record the migrations as permanently skipped (never run against this target)
+        /// instead of applied externally
+        #[arg(long)]
+        skip: bool,
+    },
 
-        match cli.command {
-            Commands::Apply {
-                conn,
-                path,
-                dry_run,
-            } => {
-                assert_eq!(conn, Some("Driver={SQLite3};Database=test.db;".to_string()));
-                assert_eq!(path, "./custom-migrations");
-                assert!(dry_run);
-            }
-            _ => panic!("Expected Apply command"),
-        }
-    }
+    /// Write detached .sig signatures for migration files
+    Sign {
+        /// Path to .sql migration files
+        #[arg(long, default_value = "./migrations")]
+        path: String,
 
-    #[test]
-    fn test_status_command_defaults() {
-        let cli = Cli::try_parse_from(["deriddl_rs", "status"]).unwrap();
-        match cli.command {
-            Commands::Status { conn, path } => {
-                assert_eq!(conn, None);
-                assert_eq!(path, "./migrations");
-            }
-            _ => panic!("Expected Status command"),
-        }
-    }
+        /// Private key file (hex-encoded Ed25519 seed)
+        #[arg(long)]
+        key: String,
+    },
 
-    #[test]
-    fn test_init_command() {
-        let cli = Cli::try_parse_from(["deriddl_rs", "init"]).unwrap();
-        match cli.command {
-            Commands::Init { conn } => {
-                assert_eq!(conn, None);
-            }
-            _ => panic!("Expected Init command"),
-        }
-    }
+    /// Check that this branch's migrations merge cleanly onto the history of a target
+    /// database: version collisions, gaps and out-of-order migrations
+    CheckBranch {
+        /// ODBC connection string of the target to check against
+        #[arg(long, value_name = "CONN")]
+        against: Option<String>,
 
-    #[test]
-    fn test_plan_command() {
-        let cli = Cli::try_parse_from(["deriddl_rs", "plan", "--conn", "test"]).unwrap();
-        match cli.command {
-            Commands::Plan { conn, path } => {
-                assert_eq!(conn, Some("test".to_string()));
-                assert_eq!(path, "./migrations");
-            }
-            _ => panic!("Expected Plan command"),
-        }
-    }
+        /// Path to .sql migration files
+        #[arg(long, default_value = "./migrations")]
+        path: String,
+    },
 
-    #[test]
-    fn test_health_command_defaults() {
-        let cli = Cli::try_parse_from(["deriddl_rs", "health"]).unwrap();
-        match cli.command {
-            Commands::Health { path, dialect } => {
-                assert_eq!(path, "./migrations");
-                assert_eq!(dialect, "postgres");
-            }
-            _ => panic!("Expected Health command"),
-        }
-    }
+    /// Move files that share a version number to the next free version, keeping the
+    /// earliest-authored file at the version. Without a flag the renames are only listed.
+    Renumber {
+        /// Path to .sql migration files
+        #[arg(long, default_value = "./migrations")]
+        path: String,
 
-    #[test]
-    fn test_health_command_custom_dialect() {
-        let cli = Cli::try_parse_from([
-            "deriddl_rs",
-            "health",
-            "--dialect",
-            "mysql",
-            "--path",
-            "./sql",
-        ])
-        .unwrap();
+        /// Ask before renaming each file
+        #[arg(long, conflicts_with = "auto")]
+        interactive: bool,
 
-        match cli.command {
-            Commands::Health { path, dialect } => {
-                assert_eq!(path, "./sql");
-                assert_eq!(dialect, "mysql");
-            }
-            _ => panic!("Expected Health command"),
-        }
-    }
+        /// Rename every file without asking
+        #[arg(long)]
+        auto: bool,
+    },
 
-    #[test]
-    fn test_config_command_defaults() {
-        let cli = Cli::try_parse_from(["deriddl_rs", "config"]).unwrap();
-        match cli.command {
-            Commands::Config { output, env } => {
-                assert_eq!(output, "config.toml");
-                assert_eq!(env, None);
-            }
-            _ => panic!("Expected Config command"),
-        }
-    }
+    /// Record the filename, version and checksum of every versioned migration in a lockfile
+    Lock {
+        /// Path to .sql migration files
+        #[arg(long, default_value = "./migrations")]
+        path: String,
 
-    #[test]
-    fn test_config_command_with_env() {
-        let cli = Cli::try_parse_from([
-            "deriddl_rs",
-            "config",
-            "--output",
-            "custom.toml",
-            "--env",
-            "dev",
-        ])
-        .unwrap();
+        /// Lockfile to write (default: validation.lockfile, deriddl.lock)
+        #[arg(long)]
+        output: Option<String>,
+    },
 
-        match cli.command {
-            Commands::Config { output, env } => {
-                assert_eq!(output, "custom.toml");
-                assert_eq!(env, Some("dev".to_string()));
-            }
-            _ => panic!("Expected Config command"),
-        }
-    }
+    /// Show everything known about a single migration
+    Info {
+        /// Migration version, identifier (e.g. analytics:3, R__views) or file name
+        target: String,
 
-    #[test]
-    fn test_global_config_flag() {
-        let cli = Cli::try_parse_from(["deriddl_rs", "--config", "custom-config.toml", "health"])
-            .unwrap();
+        /// ODBC connection string
+        #[arg(long)]
+        conn: Option<String>,
 
-        assert_eq!(cli.config, Some("custom-config.toml".to_string()));
-        assert!(matches!(cli.command, Commands::Health { .. }));
+        /// Path to .sql migration files
+        #[arg(long, default_value = "./migrations")]
+        path: String,
+    },
+
+    /// List the applied migrations that created or altered a table or column
+    Blame {
+        /// Table name, optionally schema-qualified
+        table: String,
+
+        /// Only show changes to this column
+        #[arg(long)]
+        column: Option<String>,
+
+        /// ODBC connection string
+        #[arg(long)]
+        conn: Option<String>,
+
+        /// Path to .sql migration files
+        #[arg(long, default_value = "./migrations")]
+        path: String,
+    },
+
+    /// List applies, failures and rollbacks recorded in schema_migrations
+    History {
+        /// ODBC connection string
+        #[arg(long)]
+        conn: Option<String>,
+
+        /// Only show rollbacks
+        #[arg(long)]
+        rolled_back: bool,
+
+        /// Only show events of this version
+        #[arg(long)]
+        version: Option<u32>,
+
+        /// Only show events on or after this day (YYYY-MM-DD)
+        #[arg(long, value_name = "DATE")]
+        since: Option<String>,
+    },
+
+    /// Pack the migration directory into an archive with a manifest of file checksums
+    Bundle {
+        /// Path to .sql migration files
+        #[arg(long, default_value = "./migrations")]
+        path: String,
+
+        /// Archive to write, or an s3:// or gs:// URL to upload it to; compression follows
+        /// the extension (.tar.zst, .tar.gz, .tar)
+        #[arg(long, default_value = "migrations.tar.zst")]
+        output: String,
+    },
+
+    /// Compare the migrations applied on several targets and report the ones that lag
+    SyncStatus {
+        /// Targets to compare, as --env names with a config/<target>.toml each
+        #[arg(long, value_delimiter = ',', required = true)]
+        targets: Vec<String>,
+
+        /// Print the comparison as JSON, e.g. for dashboards
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Compare row counts and checksums of tables between this target and another one
+    CompareData {
+        /// Tables to compare, optionally schema-qualified
+        #[arg(long, value_delimiter = ',', required = true)]
+        tables: Vec<String>,
+
+        /// Target to compare against: an --env name with a config/<target>.toml, or an
+        /// ODBC connection string for a database of the same dialect
+        #[arg(long)]
+        against: String,
+
+        /// ODBC connection string
+        #[arg(long)]
+        conn: Option<String>,
+
+        /// Print the comparison as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Write a static HTML report of migration history, pending changes and drift
+    Report {
+        /// ODBC connection string
+        #[arg(long)]
+        conn: Option<String>,
+
+        /// Path to .sql migration files
+        #[arg(long, default_value = "./migrations")]
+        path: String,
+
+        /// HTML file to write
+        #[arg(long, short, default_value = "report.html")]
+        output: String,
+    },
+
+    /// Generate an ER diagram and table documentation from the current database
+    Docs {
+        /// ODBC connection string
+        #[arg(long)]
+        conn: Option<String>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value = "markdown")]
+        format: DocsFormat,
+
+        /// File to write; prints to stdout when omitted
+        #[arg(long, short)]
+        output: Option<String>,
+    },
+
+    /// Recreate migration files from the content archived with `tracker.store_content`
+    RestoreFiles {
+        /// ODBC connection string
+        #[arg(long)]
+        conn: Option<String>,
+
+        /// Versions to restore, e.g. 1..50, 10.. or 7 (default: every applied migration)
+        #[arg(long, value_parser = VersionRange::parse)]
+        versions: Option<VersionRange>,
+
+        /// Directory to write the files to
+        #[arg(long, default_value = "./migrations")]
+        out: String,
+
+        /// Replace files that already exist
+        #[arg(long)]
+        overwrite: bool,
+    },
+
+    /// Generate code from the migrated schema
+    Gen {
+        #[command(subcommand)]
+        command: GenCommand,
+    },
+
+    /// Apply all migrations to a throwaway database and open a SQL prompt on it
+    Sandbox {
+        /// Path to .sql migration files
+        #[arg(long, default_value = "./migrations")]
+        path: String,
+
+        /// Database engine for the sandbox
+        #[arg(long, value_enum, default_value = "sqlite")]
+        engine: SandboxEngine,
+
+        /// Print the connection string and wait instead of opening the SQL prompt
+        #[arg(long)]
+        print_conn: bool,
+
+        /// Keep the SQLite sandbox file instead of deleting it on exit
+        #[arg(long)]
+        keep: bool,
+    },
+
+    /// Apply each migration and its down SQL to a scratch database and report
+    /// down scripts that don't restore the previous schema
+    CheckReversibility {
+        /// Path to .sql migration files
+        #[arg(long, default_value = "./migrations")]
+        path: String,
+
+        /// Engine of the scratch database
+        #[arg(long, value_enum, default_value = "sqlite")]
+        engine: SandboxEngine,
+
+        /// Empty database to check against instead of a scratch one (it is modified!)
+        #[arg(long)]
+        conn: Option<String>,
+
+        /// Fail on migrations that have no down SQL
+        #[arg(long)]
+        require_down: bool,
+    },
+
+    /// Run arbitrary SQL over the configured ODBC connection and print the results
+    Sql {
+        /// SQL to run; several statements may be separated by `;`
+        #[arg(required_unless_present = "file", conflicts_with = "file")]
+        query: Option<String>,
+
+        /// Read the SQL from a file instead
+        #[arg(long, short)]
+        file: Option<String>,
+
+        /// Database connection string (ODBC format)
+        #[arg(long)]
+        conn: Option<String>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value = "table")]
+        format: SqlOutputFormat,
+    },
+
+    /// Open an interactive SQL console on the configured target
+    Console {
+        /// Database connection string (ODBC format)
+        #[arg(long)]
+        conn: Option<String>,
+    },
+
+    /// List the registered dialects and which one a connection string is detected as
+    Dialects {
+        /// Also list feature flags, optional SQL capabilities and column types
+        #[arg(long)]
+        detail: bool,
+
+        /// Connection string to run dialect detection on; nothing connects to it
+        #[arg(long)]
+        conn: Option<String>,
+    },
+
+    /// Replace this binary with the newest GitHub release, after verifying its checksum and signature
+    SelfUpdate {
+        /// Releases to consider
+        #[arg(long, value_enum, default_value = "stable")]
+        channel: ReleaseChannel,
+
+        /// Hex ed25519 public key the release binary must be signed with (`<asset>.sig`);
+        /// defaults to the key embedded at build time
+        #[arg(long, value_name = "HEX")]
+        public_key: Option<String>,
+
+        /// Only report whether a newer release exists
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Generate a shell completion script
+    Completions {
+        /// Shell to generate completions for
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+
+    /// Generate man pages
+    Manpages {
+        /// Directory to write man pages to
+        #[arg(long, default_value = "./man")]
+        out_dir: String,
+    },
+
+    /// Generate configuration file
+    Config {
+        /// Output path for config file
+        #[arg(long, default_value = "config.toml")]
+        output: String,
+
+        /// Create environment-specific config
+        #[arg(long)]
+        env: Option<String>,
+
+        /// Print the JSON Schema of the configuration files instead, for editors
+        #[arg(long)]
+        schema: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum GenCommand {
+    /// Emit a struct or interface per table of the target's schema
+    Types {
+        /// Language of the definitions
+        #[arg(long, value_enum)]
+        lang: TypesLanguage,
+
+        /// ODBC connection string
+        #[arg(long, conflicts_with = "shadow")]
+        conn: Option<String>,
+
+        /// Build the schema by applying the migrations to a scratch database instead of
+        /// reading the target
+        #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "sqlite")]
+        shadow: Option<SandboxEngine>,
+
+        /// Path to .sql migration files, for --shadow
+        #[arg(long, default_value = "./migrations")]
+        path: String,
+
+        /// File to write; prints to stdout when omitted
+        #[arg(long, short)]
+        output: Option<String>,
+    },
+}
+
+/// Parses a `--set KEY=VALUE` argument
+fn parse_var(arg: &str) -> Result<(String, String), String> {
+    arg.split_once('=')
+        .map(|(key, value)| (key.trim().to_string(), value.to_string()))
+        .filter(|(key, _)| !key.is_empty())
+        .ok_or_else(|| format!("expected KEY=VALUE, got '{}'", arg))
+}
+
+impl Commands {
+    /// The `--conn` given to the command, if it takes one
+    pub fn conn(&self) -> Option<&str> {
+        match self {
+            Commands::Apply { conn, .. }
+            | Commands::Status { conn, .. }
+            | Commands::Init { conn, .. }
+            | Commands::Plan { conn, .. }
+            | Commands::Doctor { conn, .. }
+            | Commands::Validate { conn, .. }
+            | Commands::Rollback { conn, .. }
+            | Commands::Baseline { conn, .. }
+            | Commands::MarkApplied { conn, .. }
+            | Commands::Info { conn, .. }
+            | Commands::Blame { conn, .. }
+            | Commands::History { conn, .. }
+            | Commands::CompareData { conn, .. }
+            | Commands::Report { conn, .. }
+            | Commands::Docs { conn, .. }
+            | Commands::RestoreFiles { conn, .. }
+            | Commands::Gen { command: GenCommand::Types { conn, .. } }
+            | Commands::CheckReversibility { conn, .. }
+            | Commands::Sql { conn, .. }
+            | Commands::Console { conn, .. }
+            | Commands::Dialects { conn, .. } => conn.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// The `--path` given to the command, unless it is the default `./migrations`
+    pub fn explicit_path(&self) -> Option<&str> {
+        let path = match self {
+            Commands::Apply { path, .. }
+            | Commands::Status { path, .. }
+            | Commands::New { path, .. }
+            | Commands::Plan { path, .. }
+            | Commands::Health { path, .. }
+            | Commands::Validate { path, .. }
+            | Commands::Rollback { path, .. }
+            | Commands::MarkApplied { path, .. }
+            | Commands::Sign { path, .. }
+            | Commands::CheckBranch { path, .. }
+            | Commands::Renumber { path, .. }
+            | Commands::Lock { path, .. }
+            | Commands::Info { path, .. }
+            | Commands::Blame { path, .. }
+            | Commands::Bundle { path, .. }
+            | Commands::Report { path, .. }
+            | Commands::Sandbox { path, .. }
+            | Commands::CheckReversibility { path, .. }
+            | Commands::Gen { command: GenCommand::Types { path, .. } } => path.as_str(),
+            Commands::Doctor { path, .. } => path.as_deref()?,
+            _ => return None,
+        };
+        (path != "./migrations").then_some(path)
+    }
+
+    /// Commands that only read the database; they run in read-only mode
+    pub fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            Commands::Status { .. }
+                | Commands::Plan { .. }
+                | Commands::Validate { .. }
+                | Commands::Info { .. }
+                | Commands::Blame { .. }
+                | Commands::History { .. }
+                | Commands::SyncStatus { .. }
+                | Commands::CompareData { .. }
+                | Commands::Report { .. }
+                | Commands::Docs { .. }
+                | Commands::RestoreFiles { .. }
+                | Commands::Gen { command: GenCommand::Types { shadow: None, .. } }
+                | Commands::CheckBranch { .. }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_cli_help() {
+        let result = Cli::try_parse_from(["deriddl_rs", "--help"]);
+        assert!(result.is_err()); // Help exits with error
+    }
+
+    #[test]
+    fn test_cli_version() {
+        let result = Cli::try_parse_from(["deriddl_rs", "--version"]);
+        assert!(result.is_err()); // Version exits with error
+    }
+
+    #[test]
+    fn test_apply_command_defaults() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "apply"]).unwrap();
+        match cli.command {
+            Commands::Apply {
+                conn,
+                path,
+                dry_run,
+                ..
+            } => {
+                assert_eq!(conn, None);
+                assert_eq!(path, "./migrations");
+                assert!(!dry_run);
+            }
+            _ => panic!("Expected Apply command"),
+        }
+    }
+
+    #[test]
+    fn test_apply_command_with_flags() {
+        let cli = Cli::try_parse_from([
+            "deriddl_rs",
+            "apply",
+            "--conn",
+            "Driver={SQLite3};Database=test.db;",
+            "--path",
+            "./custom-migrations",
+            "--dry-run",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Apply {
+                conn,
+                path,
+                dry_run,
+                ..
+            } => {
+                assert_eq!(conn, Some("Driver={SQLite3};Database=test.db;".to_string()));
+                assert_eq!(path, "./custom-migrations");
+                assert!(dry_run);
+            }
+            _ => panic!("Expected Apply command"),
+        }
+    }
+
+    #[test]
+    fn test_apply_k8s_mode() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "apply", "--k8s-mode"]).unwrap();
+        match cli.command {
+            Commands::Apply { k8s_mode, steal_lock, .. } => assert!(k8s_mode && !steal_lock),
+            _ => panic!("Expected Apply command"),
+        }
+
+        assert!(Cli::try_parse_from(["deriddl_rs", "apply", "--k8s-mode", "--dry-run"]).is_err());
+        assert!(Cli::try_parse_from(["deriddl_rs", "apply", "--steal-lock"]).is_err());
+        assert!(Cli::try_parse_from(["deriddl_rs", "apply", "--k8s-mode", "--steal-lock"]).is_ok());
+    }
+
+    #[test]
+    fn test_apply_atomic() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "apply", "--atomic"]).unwrap();
+        assert!(matches!(cli.command, Commands::Apply { atomic: true, .. }));
+        assert!(Cli::try_parse_from(["deriddl_rs", "apply", "--atomic", "--resume", "8f0c"]).is_err());
+        assert!(Cli::try_parse_from(["deriddl_rs", "apply", "--atomic", "--script-only", "deploy.sql"]).is_err());
+    }
+
+    #[test]
+    fn test_apply_script_only() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "apply", "--script-only", "deploy.sql"]).unwrap();
+        match cli.command {
+            Commands::Apply { script_only, .. } => {
+                assert_eq!(script_only, Some("deploy.sql".to_string()));
+            }
+            _ => panic!("Expected Apply command"),
+        }
+
+        assert!(
+            Cli::try_parse_from(["deriddl_rs", "apply", "--script-only", "x.sql", "--dry-run"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_release_flags() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "apply", "--release", "2024.06"]).unwrap();
+        match cli.command {
+            Commands::Apply { release, .. } => assert_eq!(release, Some("2024.06".to_string())),
+            _ => panic!("Expected Apply command"),
+        }
+
+        let cli = Cli::try_parse_from(["deriddl_rs", "rollback", "--release", "2024.06"]).unwrap();
+        match cli.command {
+            Commands::Rollback { release, .. } => assert_eq!(release, Some("2024.06".to_string())),
+            _ => panic!("Expected Rollback command"),
+        }
+
+        assert!(Cli::try_parse_from(["deriddl_rs", "rollback", "--release", "2024.06", "--steps", "2"]).is_err());
+
+        let cli = Cli::try_parse_from(["deriddl_rs", "rollback", "--repeatable", "R__reporting_view"]).unwrap();
+        match cli.command {
+            Commands::Rollback { repeatable, .. } => assert_eq!(repeatable, Some("R__reporting_view".to_string())),
+            _ => panic!("Expected Rollback command"),
+        }
+        assert!(Cli::try_parse_from(["deriddl_rs", "rollback", "--repeatable", "R__v", "--release", "2024.06"]).is_err());
+    }
+
+    #[test]
+    fn test_only_selection() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "apply", "--only", "0007,R__views", "--allow-gaps"]).unwrap();
+        match cli.command {
+            Commands::Apply { only, allow_gaps, .. } => {
+                assert_eq!(only, vec!["0007".to_string(), "R__views".to_string()]);
+                assert!(allow_gaps);
+            }
+            _ => panic!("Expected Apply command"),
+        }
+        assert!(Cli::try_parse_from(["deriddl_rs", "apply", "--allow-gaps"]).is_err());
+    }
+
+    #[test]
+    fn test_history_filters() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "history", "--rolled-back", "--since", "2024-06-01"]).unwrap();
+        match cli.command {
+            Commands::History { rolled_back, version, since, .. } => {
+                assert!(rolled_back);
+                assert_eq!(version, None);
+                assert_eq!(since, Some("2024-06-01".to_string()));
+            }
+            _ => panic!("Expected History command"),
+        }
+    }
+
+    #[test]
+    fn test_owner_filters() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "apply", "--only-owner", "analytics"]).unwrap();
+        match cli.command {
+            Commands::Apply { only_owner, .. } => assert_eq!(only_owner, Some("analytics".to_string())),
+            _ => panic!("Expected Apply command"),
+        }
+
+        let cli = Cli::try_parse_from(["deriddl_rs", "status", "--owner", "core"]).unwrap();
+        match cli.command {
+            Commands::Status { owner, .. } => assert_eq!(owner, Some("core".to_string())),
+            _ => panic!("Expected Status command"),
+        }
+    }
+
+    #[test]
+    fn test_status_command_defaults() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "status"]).unwrap();
+        match cli.command {
+            Commands::Status { conn, path, owner, metrics } => {
+                assert_eq!(conn, None);
+                assert_eq!(path, "./migrations");
+                assert_eq!(owner, None);
+                assert_eq!(metrics, None);
+            }
+            _ => panic!("Expected Status command"),
+        }
+    }
+
+    #[test]
+    fn test_init_command() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "init"]).unwrap();
+        match cli.command {
+            Commands::Init { conn } => {
+                assert_eq!(conn, None);
+            }
+            _ => panic!("Expected Init command"),
+        }
+    }
+
+    #[test]
+    fn test_plan_command() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "plan", "--conn", "test"]).unwrap();
+        match cli.command {
+            Commands::Plan { conn, path, .. } => {
+                assert_eq!(conn, Some("test".to_string()));
+                assert_eq!(path, "./migrations");
+            }
+            _ => panic!("Expected Plan command"),
+        }
+    }
+
+    #[test]
+    fn test_health_command_defaults() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "health"]).unwrap();
+        match cli.command {
+            Commands::Health { path, dialect } => {
+                assert_eq!(path, "./migrations");
+                assert_eq!(dialect, "postgres");
+            }
+            _ => panic!("Expected Health command"),
+        }
+    }
+
+    #[test]
+    fn test_health_command_custom_dialect() {
+        let cli = Cli::try_parse_from([
+            "deriddl_rs",
+            "health",
+            "--dialect",
+            "mysql",
+            "--path",
+            "./sql",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Health { path, dialect } => {
+                assert_eq!(path, "./sql");
+                assert_eq!(dialect, "mysql");
+            }
+            _ => panic!("Expected Health command"),
+        }
+    }
+
+    #[test]
+    fn test_mark_applied_requires_version_or_all_pending() {
+        assert!(Cli::try_parse_from(["deriddl_rs", "mark-applied"]).is_err());
+        assert!(
+            Cli::try_parse_from(["deriddl_rs", "mark-applied", "--version", "3", "--all-pending"])
+                .is_err()
+        );
+
+        let cli = Cli::try_parse_from(["deriddl_rs", "mark-applied", "--version", "3"]).unwrap();
+        match cli.command {
+            Commands::MarkApplied { version, all_pending, .. } => {
+                assert_eq!(version, Some(3));
+                assert!(!all_pending);
+            }
+            _ => panic!("Expected MarkApplied command"),
+        }
+    }
+
+    #[test]
+    fn test_approve_command() {
+        let cli = Cli::try_parse_from([
+            "deriddl_rs",
+            "approve",
+            "plan.json",
+            "--key",
+            "reviewer.key",
+        ])
+        .unwrap();
+        match cli.command {
+            Commands::Approve { plan, key, generate_key } => {
+                assert_eq!(plan, "plan.json");
+                assert_eq!(key, "reviewer.key");
+                assert!(!generate_key);
+            }
+            _ => panic!("Expected Approve command"),
+        }
+    }
+
+    #[test]
+    fn test_info_command() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "info", "0003"]).unwrap();
+        match cli.command {
+            Commands::Info { target, conn, path } => {
+                assert_eq!(target, "0003");
+                assert_eq!(conn, None);
+                assert_eq!(path, "./migrations");
+            }
+            _ => panic!("Expected Info command"),
+        }
+    }
+
+    #[test]
+    fn test_check_branch_command() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "check-branch", "--against", "Driver=x"]).unwrap();
+        match cli.command {
+            Commands::CheckBranch { against, path } => {
+                assert_eq!(against.as_deref(), Some("Driver=x"));
+                assert_eq!(path, "./migrations");
+            }
+            _ => panic!("Expected CheckBranch command"),
+        }
+    }
+
+    #[test]
+    fn test_renumber_flags() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "renumber", "--auto"]).unwrap();
+        match cli.command {
+            Commands::Renumber { path, interactive, auto } => {
+                assert_eq!(path, "./migrations");
+                assert!(auto && !interactive);
+            }
+            _ => panic!("Expected Renumber command"),
+        }
+        assert!(Cli::try_parse_from(["deriddl_rs", "renumber", "--auto", "--interactive"]).is_err());
+    }
+
+    #[test]
+    fn test_lock_command() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "lock", "--output", "db.lock"]).unwrap();
+        match cli.command {
+            Commands::Lock { path, output } => {
+                assert_eq!(path, "./migrations");
+                assert_eq!(output.as_deref(), Some("db.lock"));
+            }
+            _ => panic!("Expected Lock command"),
+        }
+    }
+
+    #[test]
+    fn test_bundle_flags() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "bundle", "--output", "release.tar.gz"]).unwrap();
+        match cli.command {
+            Commands::Bundle { path, output } => {
+                assert_eq!(path, "./migrations");
+                assert_eq!(output, "release.tar.gz");
+            }
+            _ => panic!("Expected Bundle command"),
+        }
+
+        let cli = Cli::try_parse_from(["deriddl_rs", "apply", "--bundle", "s3://artifacts/migrations.tar.zst"]).unwrap();
+        match cli.command {
+            Commands::Apply { bundle, .. } => assert_eq!(bundle.as_deref(), Some("s3://artifacts/migrations.tar.zst")),
+            _ => panic!("Expected Apply command"),
+        }
+    }
+
+    #[test]
+    fn test_confirmation_flags_are_global() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "rollback", "-y", "--non-interactive"]).unwrap();
+        assert!(cli.yes);
+        assert!(cli.non_interactive);
+    }
+
+    #[test]
+    fn test_plan_export_and_plan_file_flags() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "plan", "--export", "plan.json", "--explain"]).unwrap();
+        match cli.command {
+            Commands::Plan { export_plan, explain, .. } => {
+                assert_eq!(export_plan.as_deref(), Some("plan.json"));
+                assert!(explain);
+            }
+            _ => panic!("Expected Plan command"),
+        }
+
+        let cli = Cli::try_parse_from(["deriddl_rs", "apply", "--plan-file", "plan.json"]).unwrap();
+        match cli.command {
+            Commands::Apply { plan_file, .. } => assert_eq!(plan_file.as_deref(), Some("plan.json")),
+            _ => panic!("Expected Apply command"),
+        }
+    }
+
+    #[test]
+    fn test_sync_status_command() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "sync-status", "--targets", "oltp,lake", "--json"]).unwrap();
+        match cli.command {
+            Commands::SyncStatus { targets, json } => {
+                assert_eq!(targets, vec!["oltp", "lake"]);
+                assert!(json);
+            }
+            _ => panic!("Expected SyncStatus command"),
+        }
+    }
+
+    #[test]
+    fn test_compare_data_command() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "compare-data", "--tables", "users,app.orders", "--against", "staging"]).unwrap();
+        assert!(cli.command.is_read_only());
+        match cli.command {
+            Commands::CompareData { tables, against, conn, json } => {
+                assert_eq!(tables, vec!["users", "app.orders"]);
+                assert_eq!(against, "staging");
+                assert!(conn.is_none());
+                assert!(!json);
+            }
+            _ => panic!("Expected CompareData command"),
+        }
+    }
+
+    #[test]
+    fn test_dialects_command() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "dialects", "--detail", "--conn", "Driver={PostgreSQL};Server=db"]).unwrap();
+        match cli.command {
+            Commands::Dialects { detail, conn } => {
+                assert!(detail);
+                assert_eq!(conn.as_deref(), Some("Driver={PostgreSQL};Server=db"));
+            }
+            _ => panic!("Expected Dialects command"),
+        }
+    }
+
+    #[test]
+    fn test_command_conn() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "status", "--conn", "Driver={MySQL};Server=db"]).unwrap();
+        assert_eq!(cli.command.conn(), Some("Driver={MySQL};Server=db"));
+        let cli = Cli::try_parse_from(["deriddl_rs", "sync-status", "--targets", "oltp,lake"]).unwrap();
+        assert_eq!(cli.command.conn(), None);
+    }
+
+    #[test]
+    fn test_blame_command() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "blame", "users", "--column", "email"]).unwrap();
+        match cli.command {
+            Commands::Blame { table, column, conn, path } => {
+                assert_eq!(table, "users");
+                assert_eq!(column, Some("email".to_string()));
+                assert_eq!(conn, None);
+                assert_eq!(path, "./migrations");
+            }
+            _ => panic!("Expected Blame command"),
+        }
+    }
+
+    #[test]
+    fn test_report_command() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "report", "--output", "out/report.html"]).unwrap();
+        match cli.command {
+            Commands::Report { conn, path, output } => {
+                assert_eq!(conn, None);
+                assert_eq!(path, "./migrations");
+                assert_eq!(output, "out/report.html");
+            }
+            _ => panic!("Expected Report command"),
+        }
+    }
+
+    #[test]
+    fn test_docs_command() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "docs", "--format", "dot"]).unwrap();
+        match cli.command {
+            Commands::Docs { conn, format, output } => {
+                assert_eq!(conn, None);
+                assert_eq!(format, DocsFormat::Dot);
+                assert_eq!(output, None);
+            }
+            _ => panic!("Expected Docs command"),
+        }
+
+        assert!(Cli::try_parse_from(["deriddl_rs", "docs", "--format", "svg"]).is_err());
+    }
+
+    #[test]
+    fn test_self_update_command() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "self-update", "--channel", "prerelease", "--check"]).unwrap();
+        match cli.command {
+            Commands::SelfUpdate { channel, public_key, check } => {
+                assert_eq!(channel, ReleaseChannel::Prerelease);
+                assert_eq!(public_key, None);
+                assert!(check);
+            }
+            _ => panic!("Expected SelfUpdate command"),
+        }
+
+        let cli = Cli::try_parse_from(["deriddl_rs", "self-update"]).unwrap();
+        assert!(matches!(cli.command, Commands::SelfUpdate { channel: ReleaseChannel::Stable, check: false, .. }));
+        assert!(Cli::try_parse_from(["deriddl_rs", "self-update", "--channel", "nightly"]).is_err());
+    }
+
+    #[test]
+    fn test_restore_files_command() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "restore-files", "--versions", "1..50", "--out", "./recovered"]).unwrap();
+        assert!(cli.command.is_read_only());
+        match cli.command {
+            Commands::RestoreFiles { conn, versions, out, overwrite } => {
+                assert_eq!(conn, None);
+                assert_eq!(versions, Some(VersionRange { start: Some(1), end: Some(50) }));
+                assert_eq!(out, "./recovered");
+                assert!(!overwrite);
+            }
+            _ => panic!("Expected RestoreFiles command"),
+        }
+
+        assert!(Cli::try_parse_from(["deriddl_rs", "restore-files", "--versions", "50..1"]).is_err());
+    }
+
+    #[test]
+    fn test_gen_types_command() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "gen", "types", "--lang", "typescript", "--shadow"]).unwrap();
+        assert!(!cli.command.is_read_only());
+        match cli.command {
+            Commands::Gen { command: GenCommand::Types { lang, conn, shadow, path, output } } => {
+                assert_eq!(lang, TypesLanguage::Typescript);
+                assert_eq!(conn, None);
+                assert_eq!(shadow, Some(SandboxEngine::Sqlite));
+                assert_eq!(path, "./migrations");
+                assert_eq!(output, None);
+            }
+            _ => panic!("Expected Gen Types command"),
+        }
+
+        let cli = Cli::try_parse_from(["deriddl_rs", "gen", "types", "--lang", "rust"]).unwrap();
+        assert!(cli.command.is_read_only());
+        assert!(Cli::try_parse_from(["deriddl_rs", "gen", "types", "--lang", "rust", "--shadow", "--conn", "x"]).is_err());
+        assert!(Cli::try_parse_from(["deriddl_rs", "gen", "types"]).is_err());
+    }
+
+    #[test]
+    fn test_sandbox_command() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "sandbox", "--print-conn"]).unwrap();
+        match cli.command {
+            Commands::Sandbox { path, engine, print_conn, keep } => {
+                assert_eq!(path, "./migrations");
+                assert_eq!(engine, SandboxEngine::Sqlite);
+                assert!(print_conn);
+                assert!(!keep);
+            }
+            _ => panic!("Expected Sandbox command"),
+        }
+    }
+
+    #[test]
+    fn test_sql_command() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "sql", "SELECT 1", "--format", "json"]).unwrap();
+        match cli.command {
+            Commands::Sql { query, file, conn, format } => {
+                assert_eq!(query.as_deref(), Some("SELECT 1"));
+                assert_eq!(file, None);
+                assert_eq!(conn, None);
+                assert_eq!(format, SqlOutputFormat::Json);
+            }
+            _ => panic!("Expected Sql command"),
+        }
+
+        assert!(Cli::try_parse_from(["deriddl_rs", "sql"]).is_err());
+        assert!(Cli::try_parse_from(["deriddl_rs", "sql", "SELECT 1", "--file", "q.sql"]).is_err());
+    }
+
+    #[test]
+    fn test_console_command() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "console", "--conn", "DSN=dev"]).unwrap();
+        match cli.command {
+            Commands::Console { conn } => assert_eq!(conn.as_deref(), Some("DSN=dev")),
+            _ => panic!("Expected Console command"),
+        }
+    }
+
+    #[test]
+    fn test_check_reversibility_command() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "check-reversibility", "--require-down"]).unwrap();
+        match cli.command {
+            Commands::CheckReversibility { path, engine, conn, require_down } => {
+                assert_eq!(path, "./migrations");
+                assert_eq!(engine, SandboxEngine::Sqlite);
+                assert_eq!(conn, None);
+                assert!(require_down);
+            }
+            _ => panic!("Expected CheckReversibility command"),
+        }
+    }
+
+    #[test]
+    fn test_doctor_command() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "doctor", "--json", "--dialect", "mysql"]).unwrap();
+        match cli.command {
+            Commands::Doctor { conn, path, dialect, json } => {
+                assert_eq!(conn, None);
+                assert_eq!(path, None);
+                assert_eq!(dialect.as_deref(), Some("mysql"));
+                assert!(json);
+            }
+            _ => panic!("Expected Doctor command"),
+        }
+    }
+
+    #[test]
+    fn test_completions_command() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "completions", "zsh"]).unwrap();
+        match cli.command {
+            Commands::Completions { shell } => assert_eq!(shell, Shell::Zsh),
+            _ => panic!("Expected Completions command"),
+        }
+
+        assert!(Cli::try_parse_from(["deriddl_rs", "completions", "tcsh"]).is_err());
+    }
+
+    #[test]
+    fn test_config_command_defaults() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "config"]).unwrap();
+        match cli.command {
+            Commands::Config { output, env, schema } => {
+                assert_eq!(output, "config.toml");
+                assert_eq!(env, None);
+                assert!(!schema);
+            }
+            _ => panic!("Expected Config command"),
+        }
+    }
+
+    #[test]
+    fn test_config_schema_and_strict_config() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "--strict-config", "config", "--schema"]).unwrap();
+        assert!(cli.strict_config);
+        assert!(matches!(cli.command, Commands::Config { schema: true, .. }));
+    }
+
+    #[test]
+    fn test_config_command_with_env() {
+        let cli = Cli::try_parse_from([
+            "deriddl_rs",
+            "config",
+            "--output",
+            "custom.toml",
+            "--env",
+            "dev",
+        ])
+        .unwrap();
+
+        match cli.command {
+            Commands::Config { output, env, .. } => {
+                assert_eq!(output, "custom.toml");
+                assert_eq!(env, Some("dev".to_string()));
+            }
+            _ => panic!("Expected Config command"),
+        }
+    }
+
+    #[test]
+    fn test_global_config_flag() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "--config", "custom-config.toml", "health"])
+            .unwrap();
+
+        assert_eq!(cli.config, Some("custom-config.toml".to_string()));
+        assert!(matches!(cli.command, Commands::Health { .. }));
+    }
+
+    #[test]
+    fn test_set_overrides_are_repeatable() {
+        let cli = Cli::try_parse_from([
+            "deriddl_rs",
+            "--set",
+            "migrations.validate_sql=false",
+            "--set",
+            "database.timeout=5",
+            "status",
+        ])
+        .unwrap();
+        assert_eq!(cli.set, vec!["migrations.validate_sql=false", "database.timeout=5"]);
+
+        let cli = Cli::try_parse_from(["deriddl_rs", "--set", "migrations.path=db", "new", "x", "--set", "table=users"]).unwrap();
+        assert_eq!(cli.set, vec!["migrations.path=db"]);
+        assert!(matches!(cli.command, Commands::New { vars, .. } if vars == vec![("table".to_string(), "users".to_string())]));
     }
 
     #[test]
@@ -330,6 +1468,48 @@ mod tests {
         assert!(matches!(cli.command, Commands::Status { .. }));
     }
 
+    #[test]
+    fn test_global_output_flags() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "status", "--quiet", "--no-color"]).unwrap();
+        assert!(cli.quiet);
+        assert!(cli.no_color);
+
+        assert!(Cli::try_parse_from(["deriddl_rs", "-q", "--verbose", "status"]).is_err());
+    }
+
+    #[test]
+    fn test_apply_all_targets_flags() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "apply", "--all-targets", "--parallel", "4"]).unwrap();
+        match cli.command {
+            Commands::Apply { all_targets, parallel, .. } => assert_eq!((all_targets, parallel), (true, 4)),
+            _ => panic!("Expected Apply command"),
+        }
+        assert!(Cli::try_parse_from(["deriddl_rs", "apply", "--parallel", "4"]).is_err());
+        assert!(Cli::try_parse_from(["deriddl_rs", "apply", "--all-targets", "--conn", "x"]).is_err());
+    }
+
+    #[test]
+    fn test_new_command_vars() {
+        let cli = Cli::try_parse_from(["deriddl_rs", "new", "add_email", "--template", "add_column", "--set", "table=users"]).unwrap();
+        match cli.command {
+            Commands::New { name, template, vars, .. } => {
+                assert_eq!(name, "add_email");
+                assert_eq!(template.as_deref(), Some("add_column"));
+                assert_eq!(vars, vec![("table".to_string(), "users".to_string())]);
+            }
+            _ => panic!("Expected New command"),
+        }
+        assert!(Cli::try_parse_from(["deriddl_rs", "new", "x", "--var", "table"]).is_err());
+        assert!(Cli::try_parse_from(["deriddl_rs", "new", "x", "--set", "=users"]).is_err());
+    }
+
+    #[test]
+    fn test_read_only_commands() {
+        assert!(Cli::try_parse_from(["deriddl_rs", "status"]).unwrap().command.is_read_only());
+        assert!(Cli::try_parse_from(["deriddl_rs", "history"]).unwrap().command.is_read_only());
+        assert!(!Cli::try_parse_from(["deriddl_rs", "apply"]).unwrap().command.is_read_only());
+    }
+
     #[test]
     fn test_invalid_command() {
         let result = Cli::try_parse_from(["deriddl_rs", "invalid-command"]);
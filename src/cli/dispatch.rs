@@ -1,100 +1,320 @@
-use crate::cli::args::{Cli, Commands};
-use crate::model::Config;
+use crate::cli::args::{Cli, Commands, GenCommand};
+use crate::cli::exit_code::{CommandError, ExitCode};
+use crate::executor::{redact_connection_string, ConnectionSettings};
+use crate::model::config::{native_path, MigrationSource, MigrationsConfig};
+use crate::model::{BehaviorConfig, Config, ConfigFiles};
 use crate::orchestrator;
-use log::{debug, error, info};
+use crate::orchestrator::apply::ApplyOutcome;
+use crate::orchestrator::codegen::GenTypesOptions;
+use crate::orchestrator::migration_loader::LoaderOptions;
+use crate::orchestrator::prompt::prompt_for;
+use crate::orchestrator::guard::ProtectionGuard;
+use crate::orchestrator::k8s::K8sOptions;
+use crate::orchestrator::lock::LockPolicy;
+use crate::orchestrator::renumber::RenumberMode;
+use crate::orchestrator::reversibility::ReversibilityOptions;
+use crate::orchestrator::sandbox::SandboxOptions;
+use crate::orchestrator::script::ScriptOptions;
+use crate::orchestrator::summary::SummaryTimer;
+use crate::tracker::{Target, TrackerSql};
+use log::{debug, error, info, warn};
 
-pub fn handle(cli: Cli) {
-    // Load configuration
-    let config = match Config::load(cli.config.as_deref(), cli.env.as_deref()) {
-        Ok(config) => config,
-        Err(e) => {
-            error!("Failed to load configuration: {}", e);
-            std::process::exit(1);
-        }
+/// Runs a command and returns the process exit status. Failures are returned for the
+/// caller to report; nothing here exits the process, so held resources such as a fetched
+/// `--source` are cleaned up.
+pub fn handle(cli: Cli) -> Result<i32, CommandError> {
+    // Resumed runs keep their id, so every record and log line of the run shares it
+    let run_id = match &cli.command {
+        Commands::Apply { resume: Some(run_id), .. } => run_id.clone(),
+        _ => crate::model::run::random_uuid(),
     };
+    crate::logger::set_run_id(&run_id);
+    // Documentation commands work from the CLI definition alone and need no configuration
+    match &cli.command {
+        Commands::Completions { shell } => {
+            crate::cli::docs::write_completions(*shell, &mut std::io::stdout());
+            return Ok(ExitCode::Success.code());
+        }
+        Commands::Config { schema: true, .. } => {
+            match serde_json::to_string_pretty(&Config::json_schema()) {
+                Ok(schema) => println!("{}", schema),
+                Err(e) => return Err(CommandError::new(format!("Failed to serialize the config schema: {}", e), ExitCode::Failure)),
+            }
+            return Ok(ExitCode::Success.code());
+        }
+        Commands::Manpages { out_dir } => {
+            info!("Running MANPAGES command");
+            let pages = crate::cli::docs::write_manpages(std::path::Path::new(out_dir))
+                .map_err(|e| CommandError::new(format!("Failed to generate man pages: {}", e), ExitCode::Failure))?;
+            info!("Generated {} man pages in {}", pages.len(), out_dir);
+            return Ok(ExitCode::Success.code());
+        }
+        // Needs no configuration, so a binary rejected by required_version can still update
+        Commands::SelfUpdate { channel, public_key, check } => {
+            info!("Running SELF-UPDATE command");
+            orchestrator::run_self_update(*channel, public_key.as_deref(), *check)
+                .map_err(|e| CommandError::failed("Self-update", e))?;
+            return Ok(ExitCode::Success.code());
+        }
+        // Doctor reports configuration problems itself instead of failing to load
+        Commands::Doctor { conn, path, dialect, json } => {
+            if *json {
+                // Keep stdout parseable; every finding is part of the JSON report
+                log::set_max_level(log::LevelFilter::Off);
+            }
+            info!("Running DOCTOR command");
+            let report = orchestrator::doctor::run_doctor(&orchestrator::doctor::DoctorOptions {
+                config_path: cli.config.as_deref(),
+                environment: cli.env.as_deref(),
+                conn: conn.as_deref(),
+                path: path.as_deref(),
+                dialect: dialect.as_deref(),
+                json: *json,
+            });
+            let code = if report.status == orchestrator::health::HealthStatus::Fail {
+                ExitCode::Failure
+            } else {
+                ExitCode::Success
+            };
+            return Ok(code.code());
+        }
+        _ => {}
+    }
+
+    // Load configuration
+    let config_files = ConfigFiles { path: cli.config.as_deref(), strict: cli.strict_config };
+    let mut config = Config::load(config_files, cli.env.as_deref())
+        .and_then(|config| config.with_overrides(&cli.set))
+        .and_then(|config| config.check_required_version().map(|()| config))
+        .map_err(|e| CommandError::new(format!("Failed to load configuration: {}", e), ExitCode::Failure))?;
 
     debug!("Loaded configuration: {:?}", config);
+    if let Err(e) = crate::logger::apply_config(&config.logging) {
+        warn!("Failed to open log file {:?}: {}", config.logging.file, e);
+    }
+
+    let detection_conn = cli.command.conn().or(config.database.connection_string.as_deref()).map(str::to_string);
+    let dialect = match crate::dialects::resolve_dialect(
+        None,
+        detection_conn.as_deref(),
+        Some(&config.migrations.dialect),
+        config.migrations.dialect_strict,
+    ) {
+        Ok(dialect) => Some(dialect),
+        // `dialects` reports an unknown or ambiguous dialect itself
+        Err(_) if matches!(cli.command, Commands::Dialects { .. }) => None,
+        Err(e) => return Err(CommandError::new(format!("Invalid dialect configuration: {}", e), ExitCode::Failure)),
+    };
+    if config.migrations.dialect.eq_ignore_ascii_case(crate::dialects::AUTO_DIALECT)
+        && !matches!(cli.command, Commands::Dialects { .. })
+    {
+        // Everything downstream (tracker SQL, validation, ...) resolves the dialect by name
+        if let Some(dialect) = &dialect {
+            config.migrations.dialect = dialect.name().to_string();
+        }
+    }
+    if config.database.connection_string.is_none() && cli.command.conn().is_none() {
+        match dialect.as_ref().and_then(|d| d.connection_string_from(&config.dialect)) {
+            Some(Ok(conn)) => {
+                debug!("Using the connection described by [dialect.{}]", config.migrations.dialect);
+                config.database.connection_string = Some(conn);
+            }
+            Some(Err(e)) => warn!("⚠️  Could not build a connection string from [dialect.{}]: {}", config.migrations.dialect, e),
+            None => {}
+        }
+    }
+    if config.database.schema.is_none() {
+        config.database.schema = dialect.as_ref().and_then(|d| d.default_schema(&config.dialect));
+    }
+    // Informational commands never change the database, so they are safe with a read-only credential
+    let read_only = cli.command.is_read_only();
+    let session =
+        crate::executor::SessionSettings::for_config(&config, dialect.as_deref(), cli.catalog.as_deref(), read_only, &run_id);
+    let connection =
+        ConnectionSettings::for_config(&config, dialect.as_deref(), session.statements.clone(), read_only, &run_id);
+    let tracker_sql = TrackerSql::for_dialect(Some(&config.migrations.dialect))
+        .with_location(session.catalog.clone(), session.schema.clone());
+    // The database a command works on, connected to the way this run connects everywhere
+    let target_for = |conn: &str| Target::new(conn).with_connection(connection.clone()).with_sql(tracker_sql.clone());
+    if cli.source.is_some()
+        && let Some(path) = cli.command.explicit_path()
+    {
+        return Err(CommandError::new(
+            format!("--source cannot be combined with --path {}; configure migrations.path relative to the source instead", path),
+            ExitCode::Failure,
+        ));
+    }
+    // Held until the command finishes; a fetched copy is deleted when dropped
+    let _source = match cli.source.as_deref() {
+        Some(spec) => {
+            let fetched = orchestrator::source::provider_for(spec)
+                .and_then(|provider| {
+                    info!("Loading migrations from {}", provider.describe());
+                    provider.fetch()
+                })
+                .map_err(|e| CommandError::new(format!("Failed to fetch migration source: {}", e), ExitCode::Failure))?;
+            relocate_migration_paths(&mut config.migrations, fetched.root());
+            Some(fetched)
+        }
+        None => None,
+    };
+    let loader_options = LoaderOptions::from_config(&config);
 
     match cli.command {
         Commands::Apply {
             conn,
             path,
             dry_run,
+            sql_log,
+            script_only,
+            confirm,
+            approved_plan,
+            plan_file,
+            k8s_mode,
+            steal_lock,
+            release,
+            only_owner,
+            only,
+            allow_gaps,
+            resume,
+            atomic,
+            bundle,
+            all_targets,
+            parallel,
         } => {
+            if all_targets {
+                info!("Running APPLY command on all targets");
+                let config_dir = std::path::Path::new(Config::ENVIRONMENT_CONFIG_DIR);
+                let targets = orchestrator::multi_target::discover_targets(config_dir)
+                    .map_err(|e| CommandError::failed("Apply", e))?;
+                let args = orchestrator::multi_target::child_args(std::env::args().skip(1));
+                let runs = orchestrator::multi_target::run_all_targets(&targets, parallel, &args)
+                    .map_err(|e| CommandError::failed("Apply", e))?;
+                return Ok(ExitCode::combine(runs.iter().map(|run| run.exit_code)));
+            }
+            if k8s_mode {
+                crate::logger::use_json_format();
+            }
+            crate::executor::install_interrupt_handler();
             info!("Running APPLY command");
-            let final_conn = conn
-                .or(config.database.connection_string)
-                .unwrap_or_else(|| {
-                    error!("No connection string provided via --conn flag or config file");
-                    std::process::exit(1);
-                });
-            let final_path = if path == "./migrations" {
-                &config.migrations.path
-            } else {
-                &path
+            let summary = SummaryTimer::start("apply", cli.env.as_deref(), &run_id);
+            let lock = LockPolicy::from_config(&config);
+            let k8s = k8s_mode.then(|| K8sOptions { steal_lock, ..K8sOptions::from_config(&config) });
+            let final_conn = connection_string(conn, &config)?;
+            let target = target_for(&final_conn);
+            // Held until apply finishes; the unpacked bundle is deleted when dropped
+            let bundle = bundle
+                .map(|spec| orchestrator::bundle::open_bundle(&spec))
+                .transpose()
+                .map_err(|e| CommandError::failed("Apply", e))?;
+            let path = match &bundle {
+                Some(bundle) => bundle.root().to_string_lossy().to_string(),
+                None => path,
             };
+            let final_path = migrations_path(&path, &config);
             let final_dry_run = dry_run || config.behavior.default_dry_run;
+            let final_sql_log = sql_log.or(config.logging.sql_log.clone());
+            let script = script_only.as_deref().map(|output| ScriptOptions {
+                output: std::path::Path::new(output),
+                dialect: Some(&config.migrations.dialect),
+            });
 
-            debug!("Connection: {}", final_conn);
+            debug!("Connection: {}", redact_connection_string(&final_conn));
             debug!("Migrations path: {}", final_path);
             debug!("Dry run mode: {}", final_dry_run);
-            if let Err(e) = orchestrator::run_apply(&final_conn, final_path, final_dry_run) {
-                error!("Apply command failed: {}", e);
-                std::process::exit(1);
+            let guard = ProtectionGuard {
+                approved_plan: approved_plan.map(std::path::PathBuf::from),
+                plan_file: plan_file.map(std::path::PathBuf::from),
+                non_interactive: k8s_mode || cli.non_interactive,
+                ..protection_guard(&config.behavior, cli.env.as_deref(), confirm, cli.yes, cli.non_interactive)
+            };
+            let loader = loader_for_path(&loader_options, &path);
+            let only = (!only.is_empty()).then_some(orchestrator::apply::OnlySelection { targets: only, allow_gaps });
+            let maintenance = target.sql.maintenance(&config.dialect);
+            let apply = || {
+                let options = orchestrator::apply::ApplyOptions {
+                    dry_run: final_dry_run,
+                    environment: cli.env.as_deref(),
+                    sql_log: final_sql_log.as_deref().map(std::path::Path::new),
+                    script: script.as_ref(),
+                    release: release.as_deref(),
+                    owner: only_owner.as_deref(),
+                    only: only.as_ref(),
+                    resume: resume.as_deref(),
+                    atomic,
+                    on_failure: config.behavior.on_failure,
+                    repeatable_history: config.migrations.repeatable_history,
+                    store_content: config.tracker.store_content,
+                    ..orchestrator::apply::ApplyOptions::new(&loader, &guard, &lock)
+                };
+                orchestrator::run_apply(&target, final_path, &options)
+                .inspect(|outcome| {
+                    if matches!(outcome, ApplyOutcome::Applied(applied) if *applied > 0) {
+                        orchestrator::apply::run_maintenance(&target, &maintenance);
+                    }
+                })
+            };
+
+            if let Some(options) = k8s {
+                let result = orchestrator::k8s::run_k8s_apply(&target, &options, apply);
+                summary.finish(&result);
+                options.write_termination_log(&result, &run_id);
+                // Kubernetes mode exits with the configured [kubernetes] exit codes
+                let status = options.exit_code(&result);
+                result.map_err(|e| CommandError::classified("Apply", e).with_status(status))?;
+                return Ok(status);
+            }
+            let result = apply();
+            summary.finish(&result);
+            if let ApplyOutcome::DryRun(pending) = result.map_err(|e| CommandError::classified("Apply", e))?
+                && pending > 0
+            {
+                return Ok(ExitCode::PendingOnly.code());
             }
         }
 
-        Commands::Status { conn, path } => {
+        Commands::Status { conn, path, owner, metrics } => {
             info!("Running STATUS command");
-            let final_conn = conn
-                .or(config.database.connection_string)
-                .unwrap_or_else(|| {
-                    error!("No connection string provided via --conn flag or config file");
-                    std::process::exit(1);
-                });
-            let final_path = if path == "./migrations" {
-                &config.migrations.path
-            } else {
-                &path
-            };
+            let final_conn = connection_string(conn, &config)?;
+            let final_path = migrations_path(&path, &config);
 
-            debug!("Connection: {}", final_conn);
+            debug!("Connection: {}", redact_connection_string(&final_conn));
             debug!("Migrations path: {}", final_path);
-            if let Err(e) = orchestrator::run_status(&final_conn, final_path) {
-                error!("Status command failed: {}", e);
-                std::process::exit(1);
+            match orchestrator::run_status(
+                &target_for(&final_conn),
+                final_path,
+                &loader_for_path(&loader_options, &path),
+                cli.env.as_deref(),
+                owner.as_deref(),
+                metrics.as_deref().map(std::path::Path::new),
+            ) {
+                Ok(0) => {}
+                Ok(_) => return Ok(ExitCode::PendingOnly.code()),
+                Err(e) => return Err(CommandError::classified("Status", e)),
             }
         }
 
-        Commands::Plan { conn, path } => {
+        Commands::Plan { conn, path, export_plan, explain } => {
             info!("Running PLAN command");
-            let final_conn = conn
-                .or(config.database.connection_string)
-                .unwrap_or_else(|| {
-                    error!("No connection string provided via --conn flag or config file");
-                    std::process::exit(1);
-                });
-            let final_path = if path == "./migrations" {
-                &config.migrations.path
-            } else {
-                &path
-            };
+            let final_conn = connection_string(conn, &config)?;
+            let final_path = migrations_path(&path, &config);
 
-            debug!("Connection: {}", final_conn);
+            debug!("Connection: {}", redact_connection_string(&final_conn));
             debug!("Migrations path: {}", final_path);
-            if let Err(e) = orchestrator::run_plan(&final_conn, final_path) {
-                error!("Plan command failed: {}", e);
-                std::process::exit(1);
-            }
+            orchestrator::run_plan(
+                &target_for(&final_conn),
+                final_path,
+                &loader_for_path(&loader_options, &path),
+                cli.env.as_deref(),
+                export_plan.as_deref().map(std::path::Path::new),
+                explain.then_some(config.migrations.dialect.as_str()),
+            )
+            .map_err(|e| CommandError::classified("Plan", e))?;
         }
 
         Commands::Health { path, dialect } => {
             info!("Running HEALTH command");
-            let final_path = if path == "./migrations" {
-                &config.migrations.path
-            } else {
-                &path
-            };
+            let final_path = migrations_path(&path, &config);
             let final_dialect = if dialect == "postgres" {
                 &config.migrations.dialect
             } else {
@@ -105,79 +325,70 @@ pub fn handle(cli: Cli) {
             debug!("SQL dialect: {}", final_dialect);
 
             if !std::path::Path::new(final_path).exists() {
-                error!("Migrations path does not exist: {}", final_path);
-                std::process::exit(1);
+                return Err(CommandError::new(
+                    format!("Migrations path does not exist: {}", final_path),
+                    ExitCode::Failure,
+                ));
             }
 
-            orchestrator::run_health(final_path, final_dialect);
+            orchestrator::run_health(final_path, final_dialect, &loader_for_path(&loader_options, &path));
         }
 
         Commands::Validate { conn, path } => {
             info!("Running VALIDATE command");
-            let final_conn = conn
-                .or(config.database.connection_string)
-                .unwrap_or_else(|| {
-                    error!("No connection string provided via --conn flag or config file");
-                    std::process::exit(1);
-                });
-            let final_path = if path == "./migrations" {
-                &config.migrations.path
-            } else {
-                &path
-            };
+            let final_conn = connection_string(conn, &config)?;
+            let final_path = migrations_path(&path, &config);
 
-            debug!("Connection: {}", final_conn);
+            debug!("Connection: {}", redact_connection_string(&final_conn));
             debug!("Migrations path: {}", final_path);
-            if let Err(e) = orchestrator::run_validate(&final_conn, final_path) {
-                error!("Validate command failed: {}", e);
-                std::process::exit(1);
-            }
+            let summary = SummaryTimer::start("validate", cli.env.as_deref(), &run_id);
+            let result = orchestrator::run_validate(&target_for(&final_conn), final_path, &loader_for_path(&loader_options, &path));
+            summary.finish(&result);
+            result.map_err(|e| CommandError::classified("Validate", e))?;
         }
 
-        Commands::Rollback { conn, path, steps, to_version, dry_run, force } => {
+        Commands::Rollback { conn, path, steps, to_version, release, repeatable, dry_run, force, sql_log, confirm } => {
+            crate::executor::install_interrupt_handler();
             info!("Running ROLLBACK command");
-            let final_conn = conn
-                .or(config.database.connection_string)
-                .unwrap_or_else(|| {
-                    error!("No connection string provided via --conn flag or config file");
-                    std::process::exit(1);
-                });
-            let final_path = if path == "./migrations" {
-                &config.migrations.path
-            } else {
-                &path
-            };
+            let final_conn = connection_string(conn, &config)?;
+            let final_path = migrations_path(&path, &config);
             let final_dry_run = dry_run || config.behavior.default_dry_run;
-            let require_confirmation = config.behavior.require_confirmation && !force;
+            let prompt = prompt_for(cli.yes || force || !config.behavior.require_confirmation, cli.non_interactive);
+            let final_sql_log = sql_log.or(config.logging.sql_log.clone());
 
-            debug!("Connection: {}", final_conn);
+            debug!("Connection: {}", redact_connection_string(&final_conn));
             debug!("Migrations path: {}", final_path);
             debug!("Steps: {}", steps);
             debug!("To version: {:?}", to_version);
+            debug!("Release: {:?}", release);
             debug!("Dry run mode: {}", final_dry_run);
             debug!("Force mode: {}", force);
-            
-            if let Err(e) = orchestrator::run_rollback(
-                &final_conn,
-                final_path,
-                steps,
-                to_version,
-                final_dry_run,
-                require_confirmation,
-            ) {
-                error!("Rollback command failed: {}", e);
-                std::process::exit(1);
+
+            let summary = SummaryTimer::start("rollback", cli.env.as_deref(), &run_id);
+            let target = target_for(&final_conn);
+            let loader = loader_for_path(&loader_options, &path);
+            let guard = protection_guard(&config.behavior, cli.env.as_deref(), confirm, cli.yes, cli.non_interactive);
+            let options = orchestrator::rollback::RollbackOptions {
+                dry_run: final_dry_run,
+                sql_log: final_sql_log.as_deref().map(std::path::Path::new),
+                ..orchestrator::rollback::RollbackOptions::new(&loader, &guard, prompt.as_ref())
+            };
+            if let Some(name) = repeatable {
+                let result = orchestrator::rollback::run_rollback_repeatable(&target, final_path, &name, &options);
+                summary.finish(&result);
+                result.map_err(|e| CommandError::classified("Rollback", e))?;
+                return Ok(ExitCode::Success.code());
             }
+
+            let strategy = orchestrator::rollback::RollbackStrategy::from_flags(steps, to_version, release.as_deref());
+            let result = orchestrator::run_rollback(&target, final_path, strategy, &options);
+            summary.finish(&result);
+            result.map_err(|e| CommandError::classified("Rollback", e))?;
         }
 
         Commands::Baseline { conn, version, description, from_schema, dry_run } => {
             info!("Running BASELINE command");
-            let final_conn = conn
-                .or(config.database.connection_string)
-                .unwrap_or_else(|| {
-                    error!("No connection string provided via --conn flag or config file");
-                    std::process::exit(1);
-                });
+            let final_conn = connection_string(conn, &config)?;
             
             // Use config defaults if not provided via CLI
             let final_description = if description.is_empty() {
@@ -186,49 +397,362 @@ pub fn handle(cli: Cli) {
                 description.as_str()
             };
             
-            let require_confirmation = config.baseline.require_confirmation;
+            let prompt = prompt_for(cli.yes || !config.baseline.require_confirmation, cli.non_interactive);
             let final_from_schema = from_schema || config.baseline.auto_generate_schema;
 
-            debug!("Connection: {}", final_conn);
+            debug!("Connection: {}", redact_connection_string(&final_conn));
             debug!("Baseline version: {}", version);
             debug!("Description: {}", final_description);
             debug!("From schema: {}", final_from_schema);
             debug!("Dry run: {}", dry_run);
             
-            if let Err(e) = orchestrator::run_baseline(
-                &final_conn,
+            orchestrator::run_baseline(
+                &target_for(&final_conn),
                 version,
                 final_description,
                 final_from_schema,
                 dry_run,
-                require_confirmation,
-            ) {
-                error!("Baseline command failed: {}", e);
-                std::process::exit(1);
-            }
+                prompt.as_ref(),
+            )
+            .map_err(|e| CommandError::failed("Baseline", e))?;
+        }
+
+        Commands::New { name, path, template, vars } => {
+            info!("Running NEW command");
+            let final_path = migrations_path(&path, &config);
+            // --yes answers confirmations, not template variables
+            let prompt = prompt_for(false, cli.non_interactive);
+            orchestrator::scaffold::run_new(
+                &orchestrator::scaffold::NewMigration {
+                    path: final_path,
+                    name: &name,
+                    template: template.as_deref(),
+                    templates_dir: std::path::Path::new(&config.migrations.templates_dir),
+                    vars: &vars,
+                    dialect: &config.migrations.dialect,
+                },
+                &loader_options,
+                prompt.as_ref(),
+            )
+            .map_err(|e| CommandError::failed("New", e))?;
         }
 
         Commands::Init { conn } => {
             info!("Running INIT command");
-            let final_conn = conn
-                .or(config.database.connection_string)
-                .unwrap_or_else(|| {
-                    error!("No connection string provided via --conn flag or config file");
-                    std::process::exit(1);
-                });
-
-            debug!("Connection: {}", final_conn);
+            let final_conn = connection_string(conn, &config)?;
+
+            debug!("Connection: {}", redact_connection_string(&final_conn));
             
-            if let Err(e) = crate::tracker::schema_init::init_migration_table_with_config(
-                &final_conn, 
-                Some(&config.migrations.dialect)
-            ) {
-                error!("Init command failed: {}", e);
-                std::process::exit(1);
+            crate::tracker::schema_init::init_migration_table_with_config(
+                &target_for(&final_conn),
+                Some(&config.migrations.dialect),
+                config.migrations.dialect_strict,
+            )
+            .map_err(|e| CommandError::failed("Init", e))?;
+        }
+
+        Commands::MarkApplied { conn, path, version, all_pending, dry_run, skip } => {
+            info!("Running MARK-APPLIED command");
+            let final_conn = connection_string(conn, &config)?;
+            let final_path = migrations_path(&path, &config);
+
+            debug!("Connection: {}", redact_connection_string(&final_conn));
+            debug!("Migrations path: {}", final_path);
+            debug!("All pending: {}", all_pending);
+            orchestrator::run_mark_applied(
+                &target_for(&final_conn),
+                final_path,
+                &loader_for_path(&loader_options, &path),
+                version,
+                dry_run,
+                cli.env.as_deref(),
+                skip,
+            )
+            .map_err(|e| CommandError::failed("Mark-applied", e))?;
+        }
+
+        Commands::Approve { plan, key, generate_key } => {
+            info!("Running APPROVE command");
+            orchestrator::run_approve(
+                std::path::Path::new(&plan),
+                std::path::Path::new(&key),
+                generate_key,
+            )
+            .map_err(|e| CommandError::failed("Approve", e))?;
+        }
+
+        Commands::Sign { path, key } => {
+            info!("Running SIGN command");
+            let final_path = migrations_path(&path, &config);
+
+            debug!("Migrations path: {}", final_path);
+            orchestrator::run_sign(
+                final_path,
+                &loader_for_path(&loader_options, &path),
+                std::path::Path::new(&key),
+            )
+            .map_err(|e| CommandError::failed("Sign", e))?;
+        }
+
+        Commands::CheckBranch { against, path } => {
+            info!("Running CHECK-BRANCH command");
+            let final_conn = against.or_else(|| config.database.connection_string.clone()).ok_or_else(|| {
+                CommandError::new("No connection string provided via --against flag or config file", ExitCode::Failure)
+            })?;
+            let final_path = migrations_path(&path, &config);
+
+            match orchestrator::check_branch::run_check_branch(&target_for(&final_conn), final_path, &loader_for_path(&loader_options, &path)) {
+                Ok(issues) if issues.is_empty() => {}
+                Ok(_) => return Ok(ExitCode::ValidationFailed.code()),
+                Err(e) => return Err(CommandError::classified("Check-branch", e)),
             }
         }
 
-        Commands::Config { output, env } => {
+        Commands::Renumber { path, interactive, auto } => {
+            info!("Running RENUMBER command");
+            let final_path = migrations_path(&path, &config);
+            // `--yes` answers every rename
+            let mode = if auto || (interactive && cli.yes) {
+                RenumberMode::Auto
+            } else if interactive {
+                RenumberMode::Interactive
+            } else {
+                RenumberMode::Preview
+            };
+
+            debug!("Migrations path: {}", final_path);
+            let prompt = prompt_for(cli.yes, cli.non_interactive);
+            orchestrator::renumber::run_renumber(final_path, &loader_for_path(&loader_options, &path), mode, prompt.as_ref())
+                .map_err(|e| CommandError::failed("Renumber", e))?;
+        }
+
+        Commands::Lock { path, output } => {
+            info!("Running LOCK command");
+            let final_path = migrations_path(&path, &config);
+            let output = output.map_or_else(|| loader_options.lockfile.path.clone(), std::path::PathBuf::from);
+
+            debug!("Migrations path: {}", final_path);
+            orchestrator::lockfile::run_lock(final_path, &loader_for_path(&loader_options, &path), &output)
+                .map_err(|e| CommandError::failed("Lock", e))?;
+        }
+
+        Commands::Info { target, conn, path } => {
+            info!("Running INFO command");
+            let final_conn = conn.or(config.database.connection_string.clone());
+            let final_path = migrations_path(&path, &config);
+
+            debug!("Migrations path: {}", final_path);
+            orchestrator::run_info(
+                final_conn.as_deref().map(target_for).as_ref(),
+                final_path,
+                &loader_for_path(&loader_options, &path),
+                &target,
+            )
+            .map_err(|e| CommandError::failed("Info", e))?;
+        }
+
+        Commands::Blame { table, column, conn, path } => {
+            info!("Running BLAME command");
+            let final_conn = connection_string(conn, &config)?;
+            let final_path = migrations_path(&path, &config);
+
+            debug!("Migrations path: {}", final_path);
+            orchestrator::run_blame(
+                &target_for(&final_conn),
+                final_path,
+                &loader_for_path(&loader_options, &path),
+                &table,
+                column.as_deref(),
+            )
+            .map_err(|e| CommandError::failed("Blame", e))?;
+        }
+
+        Commands::History { conn, rolled_back, version, since } => {
+            info!("Running HISTORY command");
+            let final_conn = connection_string(conn, &config)?;
+
+            let filter = orchestrator::history::HistoryFilter { rolled_back, version, since };
+            orchestrator::run_history(&target_for(&final_conn), &filter).map_err(|e| CommandError::failed("History", e))?;
+        }
+
+        Commands::Bundle { path, output } => {
+            info!("Running BUNDLE command");
+            let final_path = migrations_path(&path, &config);
+
+            debug!("Migrations path: {}", final_path);
+            orchestrator::bundle::run_bundle(
+                final_path,
+                &loader_for_path(&loader_options, &path),
+                &output,
+            )
+            .map_err(|e| CommandError::failed("Bundle", e))?;
+        }
+
+        Commands::SyncStatus { targets, json } => {
+            if json {
+                // Keep stdout parseable; the per-target results are part of the JSON report
+                log::set_max_level(log::LevelFilter::Off);
+            }
+            info!("Running SYNC-STATUS command");
+            match orchestrator::run_sync_status(config_files, &targets, json, &run_id) {
+                Ok(report) if report.in_sync => {}
+                Ok(_) => return Ok(ExitCode::Failure.code()),
+                Err(e) => return Err(CommandError::failed("Sync-status", e)),
+            }
+        }
+
+        Commands::CompareData { tables, against, conn, json } => {
+            if json {
+                // Keep stdout parseable; the per-table results are part of the JSON report
+                log::set_max_level(log::LevelFilter::Off);
+            }
+            info!("Running COMPARE-DATA command");
+            let current = orchestrator::compare_data::DataTarget {
+                name: cli.env.clone().unwrap_or_else(|| "current".to_string()),
+                conn: connection_string(conn, &config)?,
+                dialect: config.migrations.dialect.clone(),
+                connection: connection.clone(),
+            };
+            let against =
+                orchestrator::compare_data::DataTarget::resolve(config_files, &against, &config.migrations.dialect, &run_id)
+                    .map_err(|e| CommandError::failed("Compare-data", e))?;
+            match orchestrator::run_compare_data(&current, &against, &tables, json) {
+                Ok(report) if report.matches => {}
+                Ok(_) => return Ok(ExitCode::Failure.code()),
+                Err(e) => return Err(CommandError::failed("Compare-data", e)),
+            }
+        }
+
+        Commands::Report { conn, path, output } => {
+            info!("Running REPORT command");
+            let final_conn = connection_string(conn, &config)?;
+            let final_path = migrations_path(&path, &config);
+
+            debug!("Migrations path: {}", final_path);
+            orchestrator::run_report(
+                &target_for(&final_conn),
+                final_path,
+                &loader_for_path(&loader_options, &path),
+                cli.env.as_deref(),
+                std::path::Path::new(&output),
+            )
+            .map_err(|e| CommandError::failed("Report", e))?;
+        }
+
+        Commands::Docs { conn, format, output } => {
+            info!("Running DOCS command");
+            let final_conn = connection_string(conn, &config)?;
+
+            orchestrator::run_docs(
+                &target_for(&final_conn),
+                Some(&config.migrations.dialect),
+                format,
+                output.as_deref().map(std::path::Path::new),
+            )
+            .map_err(|e| CommandError::failed("Docs", e))?;
+        }
+
+        Commands::RestoreFiles { conn, versions, out, overwrite } => {
+            info!("Running RESTORE-FILES command");
+            let final_conn = connection_string(conn, &config)?;
+
+            orchestrator::run_restore_files(&target_for(&final_conn), versions, std::path::Path::new(&out), overwrite)
+                .map_err(|e| CommandError::failed("Restore-files", e))?;
+        }
+
+        Commands::Gen { command: GenCommand::Types { lang, conn, shadow, path, output } } => {
+            info!("Running GEN TYPES command");
+            let final_path = migrations_path(&path, &config);
+            let conn = match shadow {
+                Some(_) => None,
+                None => Some(connection_string(conn, &config)?),
+            };
+
+            orchestrator::run_gen_types(
+                final_path,
+                &loader_for_path(&loader_options, &path),
+                cli.env.as_deref(),
+                &GenTypesOptions {
+                    lang,
+                    conn,
+                    dialect: Some(config.migrations.dialect.clone()),
+                    shadow,
+                },
+                &connection,
+                output.as_deref().map(std::path::Path::new),
+            )
+            .map_err(|e| CommandError::failed("Gen types", e))?;
+        }
+
+        Commands::Sandbox { path, engine, print_conn, keep } => {
+            info!("Running SANDBOX command");
+            let final_path = migrations_path(&path, &config);
+
+            debug!("Migrations path: {}", final_path);
+            orchestrator::run_sandbox(
+                final_path,
+                &loader_for_path(&loader_options, &path),
+                cli.env.as_deref(),
+                &SandboxOptions { engine, print_conn, keep },
+                &connection,
+            )
+            .map_err(|e| CommandError::failed("Sandbox", e))?;
+        }
+
+        Commands::CheckReversibility { path, engine, conn, require_down } => {
+            info!("Running CHECK-REVERSIBILITY command");
+            let final_path = migrations_path(&path, &config);
+
+            debug!("Migrations path: {}", final_path);
+            orchestrator::run_check_reversibility(
+                final_path,
+                &loader_for_path(&loader_options, &path),
+                cli.env.as_deref(),
+                &ReversibilityOptions {
+                    engine,
+                    conn,
+                    dialect: Some(config.migrations.dialect.clone()),
+                    require_down,
+                },
+                &connection,
+            )
+            .map_err(|e| CommandError::new(format!("Reversibility check failed: {}", e), ExitCode::Failure))?;
+        }
+
+        Commands::Sql { query, file, conn, format } => {
+            // Kept at debug so it doesn't end up in JSON output
+            debug!("Running SQL command");
+            let final_conn = connection_string(conn, &config)?;
+            let sql = match (query, file) {
+                (Some(query), _) => query,
+                (None, Some(file)) => std::fs::read_to_string(&file)
+                    .map_err(|e| CommandError::new(format!("Failed to read {}: {}", file, e), ExitCode::Failure))?,
+                (None, None) => unreachable!("clap requires a query or --file"),
+            };
+
+            orchestrator::run_sql(&target_for(&final_conn), &sql, format)
+                .map_err(|e| CommandError::failed("SQL", e))?;
+        }
+
+        Commands::Console { conn } => {
+            info!("Running CONSOLE command");
+            let final_conn = connection_string(conn, &config)?;
+
+            orchestrator::run_console(&target_for(&final_conn), Some(&config.migrations.dialect))
+                .map_err(|e| CommandError::failed("Console", e))?;
+        }
+
+        Commands::Completions { .. } | Commands::Manpages { .. } | Commands::Doctor { .. } | Commands::SelfUpdate { .. } => {
+            unreachable!("documentation, doctor and self-update commands are handled before configuration is loaded")
+        }
+
+        Commands::Dialects { detail, conn } => {
+            info!("Running DIALECTS command");
+            let conn = conn.or(config.database.connection_string.clone());
+            orchestrator::run_dialects(detail, conn.as_deref(), &config.migrations.dialect);
+        }
+
+        Commands::Config { output, env, .. } => {
             info!("Running CONFIG command");
             debug!("Output path: {}", output);
 
@@ -249,10 +773,81 @@ pub fn handle(cli: Cli) {
                     }
                 }
                 Err(e) => {
-                    error!("Failed to generate configuration file: {}", e);
-                    std::process::exit(1);
+                    return Err(CommandError::new(
+                        format!("Failed to generate configuration file: {}", e),
+                        ExitCode::Failure,
+                    ));
                 }
             }
         }
     }
+    Ok(ExitCode::Success.code())
+}
+
+fn protection_guard(
+    behavior: &BehaviorConfig,
+    env: Option<&str>,
+    confirm: Option<String>,
+    assume_yes: bool,
+    non_interactive: bool,
+) -> ProtectionGuard {
+    ProtectionGuard {
+        target: env.map(str::to_string),
+        protected_targets: behavior.protected_targets.clone(),
+        confirm,
+        approved_plan: None,
+        plan_file: None,
+        approval_keys: behavior.approval_keys.clone(),
+        non_interactive,
+        assume_yes,
+    }
+}
+
+/// Points the configured migration directories into a fetched `--source`
+fn relocate_migration_paths(migrations: &mut MigrationsConfig, root: &std::path::Path) {
+    let inside = |path: &str| root.join(native_path(path)).to_string_lossy().to_string();
+    migrations.path = inside(&migrations.path);
+    for source in &mut migrations.paths {
+        match source {
+            MigrationSource::Path(path) | MigrationSource::Namespaced { path, .. } => *path = inside(path),
+        }
+    }
+}
+
+fn connection_string(conn: Option<String>, config: &Config) -> Result<String, CommandError> {
+    conn.or_else(|| config.database.connection_string.clone()).ok_or_else(|| {
+        CommandError::new("No connection string provided via --conn flag or config file", ExitCode::Failure)
+    })
+}
+
+/// The configured `migrations.path` unless `--path` was given
+fn migrations_path<'a>(path: &'a str, config: &'a Config) -> &'a str {
+    if path == "./migrations" {
+        &config.migrations.path
+    } else {
+        path
+    }
+}
+
+/// An explicit `--path` overrides the configured `migrations.paths` sources
+fn loader_for_path(options: &LoaderOptions, path: &str) -> LoaderOptions {
+    let mut options = options.clone();
+    if path != "./migrations" {
+        options.sources.clear();
+    }
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_failures_are_returned_instead_of_exiting() {
+        let cli = Cli::parse_from(["deriddl", "health", "--path", "/nonexistent/migrations"]);
+        let e = handle(cli).unwrap_err();
+        assert_eq!(e.code, ExitCode::Failure);
+        assert!(e.message.contains("does not exist"));
+    }
 }
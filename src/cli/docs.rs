@@ -0,0 +1,25 @@
+use crate::cli::args::Cli;
+use clap::CommandFactory;
+use clap_complete::Shell;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Writes a shell completion script generated from the CLI definition
+pub fn write_completions(shell: Shell, out: &mut dyn Write) {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, out);
+}
+
+/// Generates man pages for the CLI and every subcommand into `out_dir`
+pub fn write_manpages(out_dir: &Path) -> io::Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(out_dir)?;
+    clap_mangen::generate_to(Cli::command(), out_dir)?;
+
+    let mut pages: Vec<PathBuf> = std::fs::read_dir(out_dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<_>>()?;
+    pages.retain(|p| p.extension().is_some_and(|ext| ext == "1"));
+    pages.sort();
+    Ok(pages)
+}
@@ -0,0 +1,258 @@
+use crate::executor::ConnectionError;
+use crate::logger;
+use crate::orchestrator::apply::ApplyError;
+use crate::orchestrator::check_branch::CheckBranchError;
+use crate::orchestrator::k8s::K8sError;
+use crate::orchestrator::lock::is_lock_timeout;
+use crate::orchestrator::plan::PlanError;
+use crate::orchestrator::rollback::RollbackError;
+use crate::orchestrator::status::StatusError;
+use crate::orchestrator::validate::ValidateError;
+use crate::tracker::lease::LeaseError;
+use std::fmt::Display;
+
+/// Process exit codes scripts can branch on. The numbers are part of the CLI contract
+/// and must not change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitCode {
+    Success = 0,
+    /// Any failure without a more specific code, e.g. bad arguments or configuration
+    Failure = 1,
+    /// The command succeeded and migrations are pending (`status`, `apply --dry-run`)
+    PendingOnly = 2,
+    /// Migration files failed validation, or do not match the lockfile or the target's history
+    ValidationFailed = 3,
+    /// The database could not be reached
+    ConnectionFailed = 4,
+    /// Another session holds the migration lock or a lock a migration needs
+    LockHeld = 5,
+    /// A migration or rollback statement failed
+    MigrationFailed = 6,
+    /// `apply` or `rollback` was stopped by SIGINT or SIGTERM (see [`INTERRUPTED_EXIT_CODE`](crate::executor::interrupt::INTERRUPTED_EXIT_CODE))
+    Interrupted = 130,
+}
+
+impl ExitCode {
+    pub fn code(self) -> i32 {
+        self as i32
+    }
+
+    /// Identifier used in JSON output
+    pub fn name(self) -> &'static str {
+        match self {
+            ExitCode::Success => "success",
+            ExitCode::Failure => "failure",
+            ExitCode::PendingOnly => "pending_only",
+            ExitCode::ValidationFailed => "validation_failed",
+            ExitCode::ConnectionFailed => "connection_failed",
+            ExitCode::LockHeld => "lock_held",
+            ExitCode::MigrationFailed => "migration_failed",
+            ExitCode::Interrupted => "interrupted",
+        }
+    }
+
+    /// Exit code of a run made of several runs (`apply --all-targets`): success when all
+    /// succeeded, pending-only when the others did, otherwise the most severe failure.
+    /// A run killed by a signal (None) counts as a failure.
+    pub fn combine(codes: impl IntoIterator<Item = Option<i32>>) -> i32 {
+        codes
+            .into_iter()
+            .map(|code| code.unwrap_or(ExitCode::Failure.code()))
+            .max_by_key(|&code| match code {
+                0 => 0,
+                2 => 1,
+                // Failure and unknown codes rank below the specific failure classes
+                1 => 2,
+                code => code.max(2) + 1,
+            })
+            .unwrap_or(ExitCode::Success.code())
+    }
+}
+
+/// Maps an error to the exit code of its failure class
+pub trait FailureClass {
+    fn exit_code(&self) -> ExitCode;
+}
+
+impl FailureClass for ConnectionError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            ConnectionError::Odbc(_) | ConnectionError::ConnectionFailed(_) | ConnectionError::SessionSetup(..) => {
+                ExitCode::ConnectionFailed
+            }
+            ConnectionError::Interrupted => ExitCode::Interrupted,
+            e if is_lock_timeout(&e.to_string()) => ExitCode::LockHeld,
+            _ => ExitCode::Failure,
+        }
+    }
+}
+
+impl FailureClass for ApplyError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            ApplyError::LoadFailed(_)
+            | ApplyError::ValidationFailed(_)
+            | ApplyError::InvalidFiles(_)
+            | ApplyError::Planning(_)
+            | ApplyError::Signature(_)
+            | ApplyError::Lockfile(_)
+            | ApplyError::Plan(_)
+            | ApplyError::RenamedMigrations(_)
+            | ApplyError::NotAtomic(_)
+            | ApplyError::Masking(..) => ExitCode::ValidationFailed,
+            ApplyError::Connection(e) => e.exit_code(),
+            ApplyError::MigrationFailed(_, message) if is_lock_timeout(message) => ExitCode::LockHeld,
+            ApplyError::MigrationFailed(..) | ApplyError::MigrationsFailed(_) | ApplyError::GuardFailed(..) => {
+                ExitCode::MigrationFailed
+            }
+            ApplyError::Unconfirmed(_) | ApplyError::ScriptFailed(_) | ApplyError::Resume(..) => ExitCode::Failure,
+            ApplyError::Interrupted(..) => ExitCode::Interrupted,
+        }
+    }
+}
+
+impl FailureClass for K8sError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            K8sError::DatabaseUnavailable(_) => ExitCode::ConnectionFailed,
+            K8sError::Lock(LeaseError::Timeout(_) | LeaseError::StaleHolder(..) | LeaseError::Lost) => ExitCode::LockHeld,
+            K8sError::Lock(LeaseError::Connection(e)) => e.exit_code(),
+            K8sError::Apply(e) => e.exit_code(),
+        }
+    }
+}
+
+impl FailureClass for ValidateError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            ValidateError::Connection(e) => e.exit_code(),
+            ValidateError::LoadFailed(_) | ValidateError::ValidationFailed(_) | ValidateError::Lockfile(_) => {
+                ExitCode::ValidationFailed
+            }
+        }
+    }
+}
+
+impl FailureClass for StatusError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            StatusError::Connection(e) => e.exit_code(),
+            StatusError::LoadFailed(_) => ExitCode::ValidationFailed,
+            StatusError::Metrics(..) => ExitCode::Failure,
+        }
+    }
+}
+
+impl FailureClass for PlanError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            PlanError::Connection(e) => e.exit_code(),
+            PlanError::LoadFailed(_) | PlanError::Planning(_) => ExitCode::ValidationFailed,
+            PlanError::Export(_) => ExitCode::Failure,
+        }
+    }
+}
+
+impl FailureClass for RollbackError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            RollbackError::Connection(e) => e.exit_code(),
+            RollbackError::Interrupted(_) => ExitCode::Interrupted,
+            RollbackError::Migration(message) if is_lock_timeout(message) => ExitCode::LockHeld,
+            RollbackError::Migration(_) => ExitCode::MigrationFailed,
+            RollbackError::NoRollbackSql(_)
+            | RollbackError::InvalidTargetVersion(_)
+            | RollbackError::UnknownRelease(_)
+            | RollbackError::RepeatableMigrationRollback(_)
+            | RollbackError::UnknownRepeatable(_)
+            | RollbackError::NoPreviousDefinition(_) => ExitCode::ValidationFailed,
+            RollbackError::NoMigrationsToRollback
+            | RollbackError::Cancelled
+            | RollbackError::Unconfirmed(_)
+            | RollbackError::Prompt(_) => {
+                ExitCode::Failure
+            }
+        }
+    }
+}
+
+impl FailureClass for CheckBranchError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            CheckBranchError::Connection(e) => e.exit_code(),
+            CheckBranchError::LoadFailed(_) => ExitCode::ValidationFailed,
+        }
+    }
+}
+
+/// Why a command failed and which exit code it ends the process with
+#[derive(Debug)]
+pub struct CommandError {
+    pub message: String,
+    pub code: ExitCode,
+    /// Process exit status; the code's number unless configured otherwise (`apply --k8s-mode`)
+    pub status: i32,
+}
+
+impl CommandError {
+    pub fn new(message: impl Into<String>, code: ExitCode) -> Self {
+        Self { message: message.into(), code, status: code.code() }
+    }
+
+    /// `<command> command failed: <error>`, with the exit code of the error's failure class
+    pub fn classified<E: FailureClass + Display>(command: &str, e: E) -> Self {
+        Self::new(format!("{} command failed: {}", command, e), e.exit_code())
+    }
+
+    /// `<command> command failed: <error>`, for errors without a more specific failure class
+    pub fn failed(command: &str, e: impl Display) -> Self {
+        Self::new(format!("{} command failed: {}", command, e), ExitCode::Failure)
+    }
+
+    pub fn with_status(mut self, status: i32) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Logs the failure to stderr; JSON logs also carry the failure class as `error_code`
+    pub fn report(&self) {
+        logger::log_failure(&self.message, self.code.name());
+    }
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::interrupt::INTERRUPTED_EXIT_CODE;
+    use crate::orchestrator::lockfile::LockfileError;
+
+    #[test]
+    fn test_errors_map_to_their_failure_class() {
+        assert_eq!(ExitCode::PendingOnly.code(), 2);
+        assert_eq!(ApplyError::Connection(ConnectionError::ConnectionFailed("refused".into())).exit_code(), ExitCode::ConnectionFailed);
+        assert_eq!(ApplyError::Lockfile(LockfileError::Mismatch(vec![])).exit_code(), ExitCode::ValidationFailed);
+        assert_eq!(ApplyError::MigrationFailed("0003".into(), "syntax error".into()).exit_code(), ExitCode::MigrationFailed);
+        assert_eq!(
+            ApplyError::MigrationFailed("0003".into(), "Lock wait timeout exceeded".into()).exit_code(),
+            ExitCode::LockHeld
+        );
+        assert_eq!(K8sError::Lock(LeaseError::Timeout(60)).exit_code(), ExitCode::LockHeld);
+        assert_eq!(ExitCode::LockHeld.name(), "lock_held");
+        assert_eq!(ApplyError::Interrupted("during 0003".into(), "run".into()).exit_code().code(), INTERRUPTED_EXIT_CODE);
+        assert_eq!(ExitCode::combine([Some(0), Some(2), Some(0)]), 2);
+        assert_eq!(ExitCode::combine([Some(2), None, Some(0)]), 1);
+        assert_eq!(ExitCode::combine([Some(1), Some(6), Some(4)]), 6);
+        assert_eq!(ExitCode::combine([]), 0);
+
+        let e = CommandError::classified("Validate", ValidateError::ValidationFailed(vec!["gap".into()]));
+        assert_eq!(e.status, 3);
+        assert_eq!(e.to_string(), "Validate command failed: Validation failed with 1 errors");
+        assert_eq!(e.with_status(9).status, 9);
+    }
+}
@@ -1,2 +1,4 @@
 pub mod args;
 pub mod dispatch;
+pub mod docs;
+pub mod exit_code;
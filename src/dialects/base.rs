@@ -1,3 +1,5 @@
+use crate::dialects::settings::DialectSettings;
+use crate::executor::splitter::StatementSplitting;
 use serde::{Deserialize, Serialize};
 
 /// Configuration metadata for a database dialect
@@ -29,6 +31,9 @@ pub struct DetectionConfig {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct FeatureConfig {
     pub supports_transactions: bool,
+    /// DDL can be rolled back, so several migrations fit in one transaction (`apply --atomic`)
+    #[serde(default)]
+    pub supports_transactional_ddl: bool,
     pub supports_savepoints: bool,
     pub supports_schemas: bool,
     pub supports_sequences: bool,
@@ -55,6 +60,11 @@ pub struct TypeMappings {
     pub applied_at: String,
     pub execution_time_ms: String,
     pub success: String,
+    pub release: String,
+    pub git_commit: String,
+    pub git_branch: String,
+    pub run_id: String,
+    pub status: String,
 }
 
 /// Result of dialect detection
@@ -65,6 +75,17 @@ pub struct DetectionResult {
     pub matched_pattern: String,
 }
 
+/// What the planner expects a statement to do, read from its EXPLAIN output
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExplainEstimate {
+    /// Estimated rows read or written
+    pub rows: Option<u64>,
+    /// Estimated total cost, in the planner's own units
+    pub cost: Option<f64>,
+    /// The plan reads the whole table instead of using an index
+    pub full_scan: bool,
+}
+
 /// Base trait that all database dialects must implement
 pub trait DatabaseDialect: Send + Sync {
     /// Get the dialect configuration
@@ -85,13 +106,41 @@ pub trait DatabaseDialect: Send + Sync {
     
     /// Generate SQL for creating the schema_migrations table
     fn create_migrations_table_sql(&self) -> String;
+
+    /// Indexes created alongside schema_migrations so status and history queries on large
+    /// histories don't scan the whole table. `init` treats failures as non-fatal, so
+    /// dialects without `IF NOT EXISTS` for indexes may fail here on an existing table.
+    fn create_migrations_indexes_sql(&self) -> Vec<String> {
+        migration_table_indexes_sql(self, true)
+    }
     
     /// Generate SQL for querying schema information
     fn schema_introspection_queries(&self) -> Vec<String>;
     
-    /// Generate SQL for listing tables (excluding system tables)
-    fn list_tables_sql(&self) -> String;
-    
+    /// SQL listing the columns of every user table, one row per column:
+    /// schema, table, column, data type, nullable (`YES`/`NO`) and primary key (`1`/`0`),
+    /// ordered by table and column position
+    fn columns_sql(&self) -> String {
+        information_schema_columns_sql(
+            "c.table_schema NOT IN ('information_schema', 'pg_catalog', 'mysql', 'performance_schema', 'sys')",
+        )
+    }
+
+    /// SQL listing foreign keys, one row per column: constraint name, schema, table,
+    /// column, referenced schema, referenced table and referenced column
+    fn foreign_keys_sql(&self) -> String {
+        "SELECT rc.constraint_name, kcu.table_schema, kcu.table_name, kcu.column_name, \
+         ref.table_schema, ref.table_name, ref.column_name \
+         FROM information_schema.referential_constraints rc \
+         JOIN information_schema.key_column_usage kcu \
+           ON kcu.constraint_schema = rc.constraint_schema AND kcu.constraint_name = rc.constraint_name \
+         JOIN information_schema.key_column_usage ref \
+           ON ref.constraint_schema = rc.unique_constraint_schema AND ref.constraint_name = rc.unique_constraint_name \
+          AND ref.ordinal_position = kcu.position_in_unique_constraint \
+         ORDER BY kcu.table_schema, kcu.table_name, rc.constraint_name, kcu.ordinal_position"
+            .to_string()
+    }
+
     /// Quote an identifier according to dialect rules
     fn quote_identifier(&self, identifier: &str) -> String {
         let quote = &self.config().sql.quote_identifier;
@@ -114,6 +163,180 @@ pub trait DatabaseDialect: Send + Sync {
     fn boolean_false(&self) -> &str {
         &self.config().sql.boolean_false
     }
+
+    /// Predicate that holds when the boolean column or expression is true, comparing
+    /// against the dialect's boolean literal (`= TRUE` on Postgres, `= 1` on SQLite)
+    fn is_true_sql(&self, expression: &str) -> String {
+        format!("{} = {}", expression, self.boolean_true())
+    }
+
+    /// SQL that sets the session lock wait timeout, or restores the server default when
+    /// `timeout_ms` is None. Returns None when the dialect has no such setting.
+    fn lock_timeout_sql(&self, _timeout_ms: Option<u64>) -> Option<String> {
+        None
+    }
+
+    /// Statement making `schema` (a schema, or a database on MySQL) the current one for the
+    /// session. Returns None when the dialect cannot select one.
+    fn use_schema_sql(&self, _schema: &str) -> Option<String> {
+        None
+    }
+
+    /// Statement making `catalog` the current one for the session, run before
+    /// [`use_schema_sql`](Self::use_schema_sql). Returns None when the dialect has no catalogs.
+    fn use_catalog_sql(&self, _catalog: &str) -> Option<String> {
+        None
+    }
+
+    /// Statement setting the session's native query tag `key` to `value`, which governance
+    /// tooling reads back from the query history. Returns None when the dialect has none.
+    fn query_tag_sql(&self, _key: &str, _value: &str) -> Option<String> {
+        None
+    }
+
+    /// Statement setting the session's default transaction isolation level, given as SQL
+    /// (e.g. `READ COMMITTED`). Returns None when the dialect cannot set it.
+    fn isolation_level_sql(&self, _level: &str) -> Option<String> {
+        None
+    }
+
+    /// Statement making the session's transactions read-only. Returns None when the dialect
+    /// cannot set it.
+    fn read_only_sql(&self) -> Option<String> {
+        None
+    }
+
+    /// EXPLAIN for `statement` that only plans it, never runs it. Returns None when the
+    /// dialect has no such statement.
+    fn explain_sql(&self, _statement: &str) -> Option<String> {
+        None
+    }
+
+    /// Reads the rows returned by [`explain_sql`](Self::explain_sql)
+    fn explain_estimate(&self, _plan: &[Vec<String>]) -> ExplainEstimate {
+        ExplainEstimate::default()
+    }
+
+    /// Deterministic hex digest of a text expression, used by `-- deriddl:mask` strategies
+    /// that must keep equal values equal. Returns None when the dialect has no hash function.
+    fn hash_sql(&self, _expression: &str) -> Option<String> {
+        None
+    }
+
+    /// Concatenation of string expressions
+    fn concat_sql(&self, parts: &[String]) -> String {
+        parts.join(" || ")
+    }
+
+    /// Column type for SQL text of any length, e.g. stored migration definitions
+    fn text_type(&self) -> &str {
+        "TEXT"
+    }
+
+    /// Query returning one row for `table` (normalized, optionally schema-qualified): its
+    /// estimated row count and total size in bytes, either may be NULL. Returns None when
+    /// the dialect has no catalog to ask.
+    fn table_size_sql(&self, _table: &str) -> Option<String> {
+        None
+    }
+
+    /// Query returning one value for `table` (normalized, optionally schema-qualified): an
+    /// order-independent checksum of its rows, built from `columns` (quoted, in table order).
+    /// Only comparable between targets of the same dialect. Returns None when the dialect
+    /// has no hash function.
+    fn table_checksum_sql(&self, _table: &str, _columns: &[String]) -> Option<String> {
+        None
+    }
+
+    /// Statements from the dialect's `[dialect.<name>]` section run on every new connection
+    fn session_setup_sql(&self, _settings: &DialectSettings) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Lock wait timeout from the dialect's `[dialect.<name>]` section, used when
+    /// `database.lock_timeout_ms` is unset
+    fn default_lock_timeout_ms(&self, _settings: &DialectSettings) -> Option<u64> {
+        None
+    }
+
+    /// Catalog from the dialect's `[dialect.<name>]` section, used without `--catalog`
+    fn default_catalog(&self, _settings: &DialectSettings) -> Option<String> {
+        None
+    }
+
+    /// Schema from the dialect's `[dialect.<name>]` section, used when `database.schema`
+    /// is unset
+    fn default_schema(&self, _settings: &DialectSettings) -> Option<String> {
+        None
+    }
+
+    /// Connection string built from the dialect's `[dialect.<name>]` section, used when
+    /// `database.connection_string` is unset. Returns None when the section does not
+    /// describe a connection.
+    fn connection_string_from(&self, _settings: &DialectSettings) -> Option<Result<String, String>> {
+        None
+    }
+
+    /// Maintenance statements run on `migration_table` (quoted, qualified) after apply
+    /// changed it, as enabled by the dialect's `[dialect.<name>]` section
+    fn post_run_maintenance_sql(&self, _settings: &DialectSettings, _migration_table: &str) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// How migration SQL is split into statements; dialects whose routine bodies contain
+    /// `;` override this
+    fn statement_splitting(&self) -> StatementSplitting {
+        StatementSplitting::Semicolon
+    }
+}
+
+/// Secondary indexes on schema_migrations as (name suffix, columns). Uniqueness is left to the
+/// migration_id primary key: namespaced sources may reuse a (migration_type, version) pair.
+const MIGRATION_TABLE_INDEXES: [(&str, &[&str]); 4] = [
+    ("version", &["version"]),
+    ("applied_at", &["applied_at"]),
+    ("success", &["success"]),
+    ("type_version", &["migration_type", "version"]),
+];
+
+/// `CREATE INDEX` statements for [`MIGRATION_TABLE_INDEXES`], quoted for `dialect`
+pub(crate) fn migration_table_indexes_sql<D: DatabaseDialect + ?Sized>(dialect: &D, if_not_exists: bool) -> Vec<String> {
+    MIGRATION_TABLE_INDEXES
+        .iter()
+        .map(|(suffix, columns)| {
+            let columns: Vec<String> = columns.iter().map(|column| dialect.quote_identifier(column)).collect();
+            format!(
+                "CREATE INDEX {}{} ON {} ({})",
+                if if_not_exists { "IF NOT EXISTS " } else { "" },
+                dialect.quote_identifier(&format!("idx_schema_migrations_{}", suffix)),
+                dialect.quote_identifier("schema_migrations"),
+                columns.join(", ")
+            )
+        })
+        .collect()
+}
+
+/// Column listing over information_schema shared by dialects that support it, restricted
+/// by `schema_filter` (a condition on `c.table_schema`). Views and deriddl's own tables are excluded.
+pub(crate) fn information_schema_columns_sql(schema_filter: &str) -> String {
+    format!(
+        "SELECT c.table_schema, c.table_name, c.column_name, c.data_type, c.is_nullable, \
+         CASE WHEN pk.column_name IS NULL THEN 0 ELSE 1 END \
+         FROM information_schema.columns c \
+         JOIN information_schema.tables t \
+           ON t.table_schema = c.table_schema AND t.table_name = c.table_name AND t.table_type <> 'VIEW' \
+         LEFT JOIN (\
+           SELECT kcu.table_schema, kcu.table_name, kcu.column_name \
+           FROM information_schema.table_constraints tc \
+           JOIN information_schema.key_column_usage kcu \
+             ON kcu.constraint_schema = tc.constraint_schema AND kcu.constraint_name = tc.constraint_name \
+            AND kcu.table_name = tc.table_name \
+           WHERE tc.constraint_type = 'PRIMARY KEY'\
+         ) pk ON pk.table_schema = c.table_schema AND pk.table_name = c.table_name AND pk.column_name = c.column_name \
+         WHERE {} AND c.table_name NOT IN ('schema_migrations', 'deriddl_lock', 'deriddl_runs') \
+         ORDER BY c.table_schema, c.table_name, c.ordinal_position",
+        schema_filter
+    )
 }
 
 /// Error types for dialect operations
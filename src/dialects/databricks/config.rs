@@ -1,8 +1,23 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use super::drivers::DatabricksDriverConfig;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct DatabricksConfig {
+    /// Unity Catalog catalog selected with `USE CATALOG` and qualifying schema_migrations
+    /// (e.g. "main"); `--catalog` overrides it
+    #[serde(default)]
+    pub catalog: Option<String>,
+
+    /// Schema within the catalog, used when `database.schema` is unset
+    #[serde(default)]
+    pub schema: Option<String>,
+
+    /// Run OPTIMIZE and VACUUM on schema_migrations after apply, compacting the small
+    /// files every recorded migration adds
+    #[serde(default)]
+    pub optimize_history: bool,
+
     /// ODBC connection parameters for Databricks
     #[serde(default)]
     pub odbc: DatabricksOdbcConfig,
@@ -12,7 +27,7 @@ pub struct DatabricksConfig {
     pub drivers: DatabricksDriverConfig,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DatabricksOdbcConfig {
     /// ODBC driver path (e.g., "/opt/simba/spark/lib/64/libsparkodbc64.so")
     pub driver_path: Option<String>,
@@ -27,6 +42,10 @@ pub struct DatabricksOdbcConfig {
     /// HTTP path to cluster/warehouse (e.g., "/sql/1.0/warehouses/abcd1234efgh5678")
     pub http_path: Option<String>,
     
+    /// SQL warehouse ID, used to build http_path when that is unset (e.g., "abcd1234efgh5678")
+    #[serde(default)]
+    pub warehouse_id: Option<String>,
+    
     /// Authentication method configuration
     #[serde(default)]
     pub auth: DatabricksAuthConfig,
@@ -51,7 +70,7 @@ pub struct DatabricksOdbcConfig {
     pub logging: DatabricksLoggingConfig,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DatabricksAuthConfig {
     /// Authentication mechanism
     /// 3 = Personal Access Token, 11 = OAuth 2.0
@@ -81,7 +100,7 @@ pub struct DatabricksAuthConfig {
     pub auth_scope: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct DatabricksLoggingConfig {
     /// Log level (1-6)
     pub log_level: Option<u8>,
@@ -118,15 +137,6 @@ fn default_auth_mech() -> u8 {
 }
 
 // Default implementations
-impl Default for DatabricksConfig {
-    fn default() -> Self {
-        Self {
-            odbc: DatabricksOdbcConfig::default(),
-            drivers: DatabricksDriverConfig::default(),
-        }
-    }
-}
-
 impl Default for DatabricksOdbcConfig {
     fn default() -> Self {
         Self {
@@ -134,6 +144,7 @@ impl Default for DatabricksOdbcConfig {
             host: None,
             port: default_databricks_port(),
             http_path: None,
+            warehouse_id: None,
             auth: DatabricksAuthConfig::default(),
             ssl: default_ssl_enabled(),
             thrift_transport: default_thrift_transport(),
@@ -158,14 +169,3 @@ impl Default for DatabricksAuthConfig {
         }
     }
 }
-
-impl Default for DatabricksLoggingConfig {
-    fn default() -> Self {
-        Self {
-            log_level: None,
-            log_path: None,
-            log_file_count: None,
-            log_file_size: None,
-        }
-    }
-}
\ No newline at end of file
@@ -1,6 +1,9 @@
-use crate::dialects::base::{DatabaseDialect, DialectConfig, DetectionResult};
+use crate::executor::splitter::StatementSplitting;
+use crate::dialects::base::{information_schema_columns_sql, DatabaseDialect, DialectConfig, DetectionResult};
+use crate::dialects::settings::DialectSettings;
 use super::config::{DatabricksOdbcConfig, DatabricksConfig};
 use super::drivers::{DatabricksDriverConfig, DriverInfo};
+use regex::Regex;
 use std::sync::OnceLock;
 
 static CONFIG: OnceLock<DialectConfig> = OnceLock::new();
@@ -25,23 +28,64 @@ impl DatabaseDialect for DatabricksDialect {
         self.config
     }
     
-    fn detect(&self, _connection_string: &str) -> Option<DetectionResult> {
-        // Detection not used - dialect selection is config-based
-        None
+    fn detect(&self, connection_string: &str) -> Option<DetectionResult> {
+        let conn_lower = connection_string.to_lowercase();
+        let mut confidence = 0.0f32;
+        let mut matched_pattern = String::new();
+
+        // Check connection patterns
+        for pattern in &self.config.detection.connection_patterns {
+            if let Ok(re) = Regex::new(pattern)
+                && re.is_match(&conn_lower)
+            {
+                confidence = 0.9;
+                matched_pattern = pattern.clone();
+                break;
+            }
+        }
+
+        // Check driver patterns
+        if confidence == 0.0 {
+            for pattern in &self.config.detection.driver_patterns {
+                if let Ok(re) = Regex::new(pattern)
+                    && re.is_match(connection_string)
+                {
+                    confidence = 0.8;
+                    matched_pattern = pattern.clone();
+                    break;
+                }
+            }
+        }
+
+        if confidence > 0.0 {
+            Some(DetectionResult {
+                dialect_name: self.name().to_string(),
+                confidence,
+                matched_pattern,
+            })
+        } else {
+            None
+        }
     }
     
     fn create_migrations_table_sql(&self) -> String {
         let types = &self.config.types;
+        let q = |identifier: &str| self.quote_identifier(identifier);
         format!(
-            r#"CREATE TABLE IF NOT EXISTS schema_migrations (
-    migration_id {} NOT NULL,
-    migration_type {} NOT NULL,
-    version {},
-    filename {} NOT NULL,
-    checksum {} NOT NULL,
-    applied_at {} NOT NULL,
-    execution_time_ms {} NOT NULL,
-    success {} NOT NULL
+            r#"CREATE TABLE IF NOT EXISTS {table} (
+    {migration_id} {} NOT NULL,
+    {migration_type} {} NOT NULL,
+    {version} {},
+    {filename} {} NOT NULL,
+    {checksum} {} NOT NULL,
+    {applied_at} {} NOT NULL,
+    {execution_time_ms} {} NOT NULL,
+    {success} {} NOT NULL,
+    {release} {},
+    {git_commit} {},
+    {git_branch} {},
+    {run_id} {},
+    {status} {}
 ) USING DELTA"#,
             types.migration_id,
             types.migration_type,
@@ -50,7 +94,26 @@ impl DatabaseDialect for DatabricksDialect {
             types.checksum,
             types.applied_at,
             types.execution_time_ms,
-            types.success
+            types.success,
+            types.release,
+            types.git_commit,
+            types.git_branch,
+            types.run_id,
+            types.status,
+            table = q("schema_migrations"),
+            migration_id = q("migration_id"),
+            migration_type = q("migration_type"),
+            version = q("version"),
+            filename = q("filename"),
+            checksum = q("checksum"),
+            applied_at = q("applied_at"),
+            execution_time_ms = q("execution_time_ms"),
+            success = q("success"),
+            release = q("release"),
+            git_commit = q("git_commit"),
+            git_branch = q("git_branch"),
+            run_id = q("run_id"),
+            status = q("status")
         )
     }
     
@@ -64,10 +127,76 @@ impl DatabaseDialect for DatabricksDialect {
             "SHOW TABLE EXTENDED LIKE '*'".to_string(),
         ]
     }
-    
-    fn list_tables_sql(&self) -> String {
-        // Use Spark SQL syntax to list tables, excluding schema_migrations
-        "SHOW TABLES LIKE '*' WHERE NOT isTemporary AND tableName != 'schema_migrations'".to_string()
+
+    fn use_schema_sql(&self, schema: &str) -> Option<String> {
+        Some(format!("USE SCHEMA {}", self.quote_identifier(schema)))
+    }
+
+    fn use_catalog_sql(&self, catalog: &str) -> Option<String> {
+        Some(format!("USE CATALOG {}", self.quote_identifier(catalog)))
+    }
+
+    fn query_tag_sql(&self, key: &str, value: &str) -> Option<String> {
+        Some(format!("SET QUERY_TAGS['{}'] = '{}'", key.replace('\'', "''"), value.replace('\'', "''")))
+    }
+
+    fn columns_sql(&self) -> String {
+        // Only the catalog selected with `USE CATALOG`
+        information_schema_columns_sql(
+            "c.table_catalog = current_catalog() AND c.table_schema <> 'information_schema'",
+        )
+    }
+
+    fn create_migrations_indexes_sql(&self) -> Vec<String> {
+        // Delta tables have no secondary indexes; liquid clustering lets lookups by type,
+        // version and apply time skip files instead
+        vec!["ALTER TABLE schema_migrations CLUSTER BY (migration_type, version, applied_at)".to_string()]
+    }
+
+    fn statement_splitting(&self) -> StatementSplitting {
+        // SQL scripting bodies (CREATE FUNCTION/PROCEDURE ... BEGIN ... END) contain `;`
+        StatementSplitting::BeginEnd
+    }
+
+    fn text_type(&self) -> &str {
+        "STRING"
+    }
+
+    fn hash_sql(&self, expression: &str) -> Option<String> {
+        Some(format!("md5(CAST({} AS STRING))", expression))
+    }
+
+    fn default_catalog(&self, settings: &DialectSettings) -> Option<String> {
+        settings.databricks.as_ref().and_then(|databricks| databricks.catalog.clone())
+    }
+
+    fn default_schema(&self, settings: &DialectSettings) -> Option<String> {
+        settings.databricks.as_ref().and_then(|databricks| databricks.schema.clone())
+    }
+
+    fn post_run_maintenance_sql(&self, settings: &DialectSettings, migration_table: &str) -> Vec<String> {
+        if !settings.databricks.as_ref().is_some_and(|databricks| databricks.optimize_history) {
+            return Vec::new();
+        }
+        // VACUUM keeps the default retention, so time travel over recent history still works
+        vec![format!("OPTIMIZE {}", migration_table), format!("VACUUM {}", migration_table)]
+    }
+
+    fn connection_string_from(&self, settings: &DialectSettings) -> Option<Result<String, String>> {
+        let config = settings.databricks.as_ref().filter(|config| config.odbc.host.is_some())?;
+        Some(match config.odbc.driver_path {
+            Some(_) => Self::build_connection_string(&config.odbc),
+            None => Self::build_connection_string_with_drivers(config),
+        })
+    }
+
+    fn table_checksum_sql(&self, table: &str, columns: &[String]) -> Option<String> {
+        // DECIMAL so the sum of 64-bit hashes cannot overflow under ANSI mode
+        Some(format!(
+            "SELECT SUM(CAST(xxhash64({}) AS DECIMAL(38, 0))) FROM {}",
+            columns.join(", "),
+            table
+        ))
     }
 }
 
@@ -106,8 +235,9 @@ impl DatabricksDialect {
             .ok_or("driver_path is required for Databricks ODBC connection")?;
         let host = config.host.as_ref()
             .ok_or("host is required for Databricks ODBC connection")?;
-        let http_path = config.http_path.as_ref()
-            .ok_or("http_path is required for Databricks ODBC connection")?;
+        let http_path = config.http_path.clone()
+            .or_else(|| config.warehouse_id.as_ref().map(|id| format!("/sql/1.0/warehouses/{}", id)))
+            .ok_or("http_path or warehouse_id is required for Databricks ODBC connection")?;
 
         let mut connection_parts = Vec::new();
 
@@ -347,4 +477,44 @@ impl DatabricksDialect {
             available: true,
         })
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_databricks_connection_strings() {
+        let dialect = DatabricksDialect::new();
+
+        let detected = dialect
+            .detect("Driver={Databricks ODBC Driver};Host=dbc-1234.cloud.databricks.com;HTTPPath=/sql/1.0/warehouses/abc")
+            .unwrap();
+        assert_eq!(detected.dialect_name, dialect.name());
+        assert_eq!(detected.confidence, 0.9);
+
+        let detected = dialect.detect("Driver=/opt/simba/spark/lib/64/libsparkodbc_sb64.so;Host=h").unwrap();
+        assert_eq!(detected.matched_pattern, ".*spark.*");
+
+        assert!(dialect.detect("Driver={PostgreSQL Unicode};Server=db;Database=app").is_none());
+    }
+    #[test]
+    fn test_optimize_history_maintains_schema_migrations_after_apply() {
+        let dialect = DatabricksDialect::new();
+        let table = "`main`.`ops`.`schema_migrations`";
+        assert!(dialect.post_run_maintenance_sql(&DialectSettings::default(), table).is_empty());
+
+        let settings: DialectSettings = toml::from_str("[databricks]\ncatalog = \"main\"\n").unwrap();
+        assert!(!settings.databricks.as_ref().unwrap().optimize_history);
+        assert!(dialect.post_run_maintenance_sql(&settings, table).is_empty());
+
+        let settings: DialectSettings = toml::from_str("[databricks]\noptimize_history = true\n").unwrap();
+        assert_eq!(
+            dialect.post_run_maintenance_sql(&settings, table),
+            vec!["OPTIMIZE `main`.`ops`.`schema_migrations`", "VACUUM `main`.`ops`.`schema_migrations`"]
+        );
+        // Other dialects have no maintenance, whatever the Databricks section says
+        let postgres = crate::dialects::get_dialect("postgres").unwrap();
+        assert!(postgres.post_run_maintenance_sql(&settings, "\"schema_migrations\"").is_empty());
+    }
+}
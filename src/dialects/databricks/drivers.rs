@@ -1,9 +1,10 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 /// Databricks ODBC driver configuration and management
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DatabricksDriverConfig {
     /// Available driver configurations
     #[serde(default)]
@@ -22,7 +23,7 @@ pub struct DatabricksDriverConfig {
 }
 
 /// Information about a specific ODBC driver
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DriverInfo {
     /// Display name for the driver
     pub name: String,
@@ -48,7 +49,7 @@ pub struct DriverInfo {
 }
 
 /// Known ODBC driver vendors for Databricks
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub enum DriverVendor {
     /// Official Databricks ODBC driver
     Databricks,
@@ -59,7 +60,7 @@ pub enum DriverVendor {
 }
 
 /// Driver capabilities and feature support
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DriverCapabilities {
     /// Supports Arrow format (performance optimization)
     #[serde(default)]
@@ -196,11 +197,6 @@ impl DatabricksDriverConfig {
         available_drivers
     }
     
-    /// Search for a driver in configured search paths
-    fn search_for_driver(&self, driver_filename: &Path) -> Option<PathBuf> {
-        Self::search_for_driver_in_paths(driver_filename, &self.search_paths)
-    }
-    
     /// Search for a driver in given search paths (static helper)
     fn search_for_driver_in_paths(driver_filename: &Path, search_paths: &[PathBuf]) -> Option<PathBuf> {
         let filename = driver_filename.file_name()?;
@@ -227,12 +223,11 @@ impl DatabricksDriverConfig {
     /// Get the preferred driver or first available driver
     pub fn get_driver(&self) -> Option<&DriverInfo> {
         // Try preferred driver first
-        if let Some(ref preferred) = self.preferred_driver {
-            if let Some(driver) = self.drivers.get(preferred) {
-                if driver.available {
-                    return Some(driver);
-                }
-            }
+        if let Some(ref preferred) = self.preferred_driver
+            && let Some(driver) = self.drivers.get(preferred)
+            && driver.available
+        {
+            return Some(driver);
         }
         
         // Fall back to first available driver
@@ -311,6 +306,16 @@ fn default_true() -> bool {
     true
 }
 
+impl std::fmt::Display for DriverVendor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DriverVendor::Databricks => write!(f, "Databricks"),
+            DriverVendor::Simba => write!(f, "Simba Technologies"),
+            DriverVendor::Custom(name) => write!(f, "{}", name),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -342,13 +347,3 @@ mod tests {
         assert_eq!(DriverVendor::Custom("Test".to_string()).to_string(), "Test");
     }
 }
-
-impl std::fmt::Display for DriverVendor {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            DriverVendor::Databricks => write!(f, "Databricks"),
-            DriverVendor::Simba => write!(f, "Simba Technologies"),
-            DriverVendor::Custom(name) => write!(f, "{}", name),
-        }
-    }
-}
\ No newline at end of file
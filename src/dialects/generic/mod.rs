@@ -7,6 +7,12 @@ pub struct GenericDialect {
     config: &'static DialectConfig,
 }
 
+impl Default for GenericDialect {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl GenericDialect {
     pub fn new() -> Self {
         let config = CONFIG.get_or_init(|| {
@@ -34,16 +40,22 @@ impl DatabaseDialect for GenericDialect {
     
     fn create_migrations_table_sql(&self) -> String {
         let types = &self.config.types;
+        let q = |identifier: &str| self.quote_identifier(identifier);
         format!(
-            r#"CREATE TABLE IF NOT EXISTS schema_migrations (
-    migration_id {} PRIMARY KEY NOT NULL,
-    migration_type {} NOT NULL DEFAULT 'versioned',
-    version INTEGER,
-    filename {} NOT NULL,
-    checksum {} NOT NULL,
-    applied_at {} NOT NULL DEFAULT {},
-    execution_time_ms {} NOT NULL,
-    success {} NOT NULL DEFAULT {}
+            r#"CREATE TABLE IF NOT EXISTS {table} (
+    {migration_id} {} PRIMARY KEY NOT NULL,
+    {migration_type} {} NOT NULL DEFAULT 'versioned',
+    {version} INTEGER,
+    {filename} {} NOT NULL,
+    {checksum} {} NOT NULL,
+    {applied_at} {} NOT NULL DEFAULT {},
+    {execution_time_ms} {} NOT NULL,
+    {success} {} NOT NULL DEFAULT {},
+    {release} {},
+    {git_commit} {},
+    {git_branch} {},
+    {run_id} {},
+    {status} {}
 )"#,
             types.migration_id,
             types.migration_type,
@@ -53,7 +65,26 @@ impl DatabaseDialect for GenericDialect {
             self.current_timestamp(),
             types.execution_time_ms,
             types.success,
-            self.boolean_true()
+            self.boolean_true(),
+            types.release,
+            types.git_commit,
+            types.git_branch,
+            types.run_id,
+            types.status,
+            table = q("schema_migrations"),
+            migration_id = q("migration_id"),
+            migration_type = q("migration_type"),
+            version = q("version"),
+            filename = q("filename"),
+            checksum = q("checksum"),
+            applied_at = q("applied_at"),
+            execution_time_ms = q("execution_time_ms"),
+            success = q("success"),
+            release = q("release"),
+            git_commit = q("git_commit"),
+            git_branch = q("git_branch"),
+            run_id = q("run_id"),
+            status = q("status")
         )
     }
     
@@ -63,8 +94,4 @@ impl DatabaseDialect for GenericDialect {
             "SELECT table_name FROM information_schema.tables WHERE table_schema = 'public'".to_string(),
         ]
     }
-    
-    fn list_tables_sql(&self) -> String {
-        "SELECT table_name FROM information_schema.tables WHERE table_schema = 'public' AND table_name != 'schema_migrations'".to_string()
-    }
 }
\ No newline at end of file
@@ -5,6 +5,7 @@
 
 pub mod base;
 pub mod registry;
+pub mod settings;
 
 // Dialect modules
 pub mod postgres;
@@ -14,8 +15,13 @@ pub mod databricks;
 pub mod generic;
 
 // Re-export main types
-pub use base::{DatabaseDialect, DialectError};
+pub use base::{DatabaseDialect, DialectError, ExplainEstimate};
 pub use registry::get_registry;
+pub use settings::DialectSettings;
+pub use mysql::MysqlConfig;
+pub use postgres::PostgresConfig;
+
+use log::{info, warn};
 
 // Re-export dialect-specific config types
 pub use databricks::{
@@ -29,27 +35,61 @@ pub fn get_dialect(name: &str) -> Option<std::sync::Arc<dyn DatabaseDialect>> {
     registry.get(name)
 }
 
-/// Get dialect by name with config fallback (no auto-detection)
+/// `migrations.dialect` value that detects the dialect from the connection string
+pub const AUTO_DIALECT: &str = "auto";
+
+/// Get dialect by name with config fallback. A name of [`AUTO_DIALECT`] detects it from
+/// the connection string instead; an ambiguous detection is an error, and one below
+/// [`registry::MIN_DETECTION_CONFIDENCE`] falls back like an unknown name.
 pub fn get_dialect_with_config(
     explicit_name: Option<&str>, 
-    _connection_string: Option<&str>,
+    connection_string: Option<&str>,
     config_dialect: Option<&str>
+) -> Result<std::sync::Arc<dyn DatabaseDialect>, DialectError> {
+    resolve_dialect(explicit_name, connection_string, config_dialect, false)
+}
+
+/// Like [`get_dialect_with_config`], but when `strict` (`migrations.dialect_strict`) a
+/// name that resolves to nothing fails instead of falling back to the generic dialect
+pub fn resolve_dialect(
+    explicit_name: Option<&str>,
+    connection_string: Option<&str>,
+    config_dialect: Option<&str>,
+    strict: bool,
 ) -> Result<std::sync::Arc<dyn DatabaseDialect>, DialectError> {
     let registry = get_registry().lock().unwrap();
     
     // Priority: explicit name > config dialect > generic fallback
-    if let Some(name) = explicit_name {
-        if let Some(dialect) = registry.get(name) {
+    for name in [explicit_name, config_dialect].into_iter().flatten() {
+        if name.eq_ignore_ascii_case(AUTO_DIALECT) {
+            let Some(conn) = connection_string else {
+                warn!("⚠️  Dialect '{}' needs a connection string to detect from", AUTO_DIALECT);
+                continue;
+            };
+            let (dialect, result) = registry.detect_with_result(conn)?;
+            if result.confidence >= registry::MIN_DETECTION_CONFIDENCE {
+                info!(
+                    "Detected dialect {} from the connection string (pattern '{}', confidence {:.1})",
+                    dialect.name(),
+                    result.matched_pattern,
+                    result.confidence
+                );
+                return Ok(dialect);
+            }
+            warn!("⚠️  No dialect detected from the connection string with enough confidence");
+        } else if let Some(dialect) = registry.get(name) {
             return Ok(dialect);
         }
     }
     
-    if let Some(config_name) = config_dialect {
-        if let Some(dialect) = registry.get(config_name) {
-            return Ok(dialect);
-        }
+    if strict {
+        return Err(DialectError::NotFound(format!(
+            "no dialect for {}; set migrations.dialect to one of: {} (migrations.dialect_strict forbids the generic fallback)",
+            explicit_name.or(config_dialect).map_or("this target".to_string(), |name| format!("'{}'", name)),
+            registry.list_dialects().join(", ")
+        )));
     }
-    
+
     // Fallback to generic
     registry.get("generic").ok_or_else(|| DialectError::NotFound("No dialect available".to_string()))
 }
@@ -0,0 +1,24 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// `[dialect.mysql]`: session defaults for MySQL and MariaDB targets
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct MysqlConfig {
+    /// innodb_lock_wait_timeout before each migration when `database.lock_timeout_ms` is unset
+    #[serde(default)]
+    pub lock_timeout_ms: Option<u64>,
+
+    /// sql_mode of every session, e.g. `STRICT_TRANS_TABLES,NO_ZERO_DATE`
+    #[serde(default)]
+    pub sql_mode: Option<String>,
+}
+
+impl MysqlConfig {
+    /// `SET` statements run on every new connection
+    pub fn session_setup_sql(&self) -> Vec<String> {
+        self.sql_mode
+            .iter()
+            .map(|mode| format!("SET SESSION sql_mode = '{}'", mode.replace('\'', "''")))
+            .collect()
+    }
+}
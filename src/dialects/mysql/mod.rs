@@ -1,13 +1,25 @@
-use crate::dialects::base::{DatabaseDialect, DialectConfig, DetectionResult};
+use crate::executor::splitter::StatementSplitting;
+use crate::dialects::base::{information_schema_columns_sql, migration_table_indexes_sql, DatabaseDialect, DialectConfig, DetectionResult, ExplainEstimate};
+use crate::dialects::settings::DialectSettings;
 use regex::Regex;
 use std::sync::OnceLock;
 
+mod config;
+
+pub use config::MysqlConfig;
+
 static CONFIG: OnceLock<DialectConfig> = OnceLock::new();
 
 pub struct MysqlDialect {
     config: &'static DialectConfig,
 }
 
+impl Default for MysqlDialect {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl MysqlDialect {
     pub fn new() -> Self {
         let config = CONFIG.get_or_init(|| {
@@ -31,34 +43,32 @@ impl DatabaseDialect for MysqlDialect {
         
         // Check connection patterns
         for pattern in &self.config.detection.connection_patterns {
-            if let Ok(re) = Regex::new(pattern) {
-                if re.is_match(&conn_lower) {
-                    confidence = 0.9;
-                    matched_pattern = pattern.clone();
-                    break;
-                }
+            if let Ok(re) = Regex::new(pattern)
+                && re.is_match(&conn_lower)
+            {
+                confidence = 0.9;
+                matched_pattern = pattern.clone();
+                break;
             }
         }
         
         // Check driver patterns
         if confidence == 0.0 {
             for pattern in &self.config.detection.driver_patterns {
-                if let Ok(re) = Regex::new(pattern) {
-                    if re.is_match(connection_string) {
-                        confidence = 0.8;
-                        matched_pattern = pattern.clone();
-                        break;
-                    }
+                if let Ok(re) = Regex::new(pattern)
+                    && re.is_match(connection_string)
+                {
+                    confidence = 0.8;
+                    matched_pattern = pattern.clone();
+                    break;
                 }
             }
         }
         
         // Fallback to simple string matching
-        if confidence == 0.0 {
-            if conn_lower.contains("mysql") || conn_lower.contains("mariadb") {
-                confidence = 0.7;
-                matched_pattern = "mysql|mariadb".to_string();
-            }
+        if confidence == 0.0 && (conn_lower.contains("mysql") || conn_lower.contains("mariadb")) {
+            confidence = 0.7;
+            matched_pattern = "mysql|mariadb".to_string();
         }
         
         if confidence > 0.0 {
@@ -74,16 +84,22 @@ impl DatabaseDialect for MysqlDialect {
     
     fn create_migrations_table_sql(&self) -> String {
         let types = &self.config.types;
+        let q = |identifier: &str| self.quote_identifier(identifier);
         format!(
-            r#"CREATE TABLE IF NOT EXISTS schema_migrations (
-    migration_id {} PRIMARY KEY NOT NULL,
-    migration_type {} NOT NULL DEFAULT 'versioned',
-    version INTEGER,
-    filename {} NOT NULL,
-    checksum {} NOT NULL,
-    applied_at {} NOT NULL DEFAULT {},
-    execution_time_ms {} NOT NULL,
-    success {} NOT NULL DEFAULT {}
+            r#"CREATE TABLE IF NOT EXISTS {table} (
+    {migration_id} {} PRIMARY KEY NOT NULL,
+    {migration_type} {} NOT NULL DEFAULT 'versioned',
+    {version} INTEGER,
+    {filename} {} NOT NULL,
+    {checksum} {} NOT NULL,
+    {applied_at} {} NOT NULL DEFAULT {},
+    {execution_time_ms} {} NOT NULL,
+    {success} {} NOT NULL DEFAULT {},
+    {release} {},
+    {git_commit} {},
+    {git_branch} {},
+    {run_id} {},
+    {status} {}
 )"#,
             types.migration_id,
             types.migration_type,
@@ -93,7 +109,26 @@ impl DatabaseDialect for MysqlDialect {
             self.current_timestamp(),
             types.execution_time_ms,
             types.success,
-            self.boolean_true()
+            self.boolean_true(),
+            types.release,
+            types.git_commit,
+            types.git_branch,
+            types.run_id,
+            types.status,
+            table = q("schema_migrations"),
+            migration_id = q("migration_id"),
+            migration_type = q("migration_type"),
+            version = q("version"),
+            filename = q("filename"),
+            checksum = q("checksum"),
+            applied_at = q("applied_at"),
+            execution_time_ms = q("execution_time_ms"),
+            success = q("success"),
+            release = q("release"),
+            git_commit = q("git_commit"),
+            git_branch = q("git_branch"),
+            run_id = q("run_id"),
+            status = q("status")
         )
     }
     
@@ -105,8 +140,109 @@ impl DatabaseDialect for MysqlDialect {
             "SELECT TABLE_SCHEMA, TABLE_NAME FROM INFORMATION_SCHEMA.VIEWS WHERE TABLE_SCHEMA NOT IN ('information_schema', 'mysql', 'performance_schema', 'sys')".to_string(),
         ]
     }
-    
-    fn list_tables_sql(&self) -> String {
-        "SELECT TABLE_NAME FROM INFORMATION_SCHEMA.TABLES WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME != 'schema_migrations'".to_string()
+
+    fn columns_sql(&self) -> String {
+        information_schema_columns_sql("c.table_schema = DATABASE()")
+    }
+
+    fn foreign_keys_sql(&self) -> String {
+        // MySQL records the referenced column directly in KEY_COLUMN_USAGE
+        "SELECT CONSTRAINT_NAME, TABLE_SCHEMA, TABLE_NAME, COLUMN_NAME, \
+         REFERENCED_TABLE_SCHEMA, REFERENCED_TABLE_NAME, REFERENCED_COLUMN_NAME \
+         FROM INFORMATION_SCHEMA.KEY_COLUMN_USAGE \
+         WHERE REFERENCED_TABLE_NAME IS NOT NULL AND TABLE_SCHEMA = DATABASE() \
+         ORDER BY TABLE_NAME, CONSTRAINT_NAME, ORDINAL_POSITION"
+            .to_string()
+    }
+
+    fn lock_timeout_sql(&self, timeout_ms: Option<u64>) -> Option<String> {
+        // innodb_lock_wait_timeout is in whole seconds, with a minimum of 1
+        Some(match timeout_ms {
+            Some(ms) => format!("SET SESSION innodb_lock_wait_timeout = {}", ms.div_ceil(1000).max(1)),
+            None => "SET SESSION innodb_lock_wait_timeout = DEFAULT".to_string(),
+        })
+    }
+
+    fn use_schema_sql(&self, schema: &str) -> Option<String> {
+        Some(format!("USE {}", self.quote_identifier(schema)))
+    }
+
+    fn isolation_level_sql(&self, level: &str) -> Option<String> {
+        Some(format!("SET SESSION TRANSACTION ISOLATION LEVEL {}", level))
+    }
+
+    fn read_only_sql(&self) -> Option<String> {
+        Some("SET SESSION TRANSACTION READ ONLY".to_string())
     }
-}
\ No newline at end of file
+
+    fn hash_sql(&self, expression: &str) -> Option<String> {
+        Some(format!("MD5({})", expression))
+    }
+
+    fn session_setup_sql(&self, settings: &DialectSettings) -> Vec<String> {
+        settings.mysql.as_ref().map(MysqlConfig::session_setup_sql).unwrap_or_default()
+    }
+
+    fn default_lock_timeout_ms(&self, settings: &DialectSettings) -> Option<u64> {
+        settings.mysql.as_ref().and_then(|mysql| mysql.lock_timeout_ms)
+    }
+
+    fn concat_sql(&self, parts: &[String]) -> String {
+        // `||` is logical OR unless PIPES_AS_CONCAT is set
+        format!("CONCAT({})", parts.join(", "))
+    }
+
+    fn table_checksum_sql(&self, table: &str, columns: &[String]) -> Option<String> {
+        // CHECKSUM TABLE would be cheaper but is refused in read-only sessions. CONCAT_WS
+        // skips NULLs, so the NULL pattern is appended to tell NULL apart from ''.
+        let nulls: Vec<String> = columns.iter().map(|column| format!("ISNULL({})", column)).collect();
+        Some(format!(
+            "SELECT SUM(CRC32(CONCAT_WS('|', {}, CONCAT({})))) FROM {}",
+            columns.join(", "),
+            nulls.join(", "),
+            table
+        ))
+    }
+
+    fn text_type(&self) -> &str {
+        // TEXT stops at 64 KB
+        "LONGTEXT"
+    }
+
+    fn table_size_sql(&self, table: &str) -> Option<String> {
+        let (schema, name) = match table.split_once('.') {
+            Some((schema, name)) => (format!("'{}'", schema.replace('\'', "''")), name),
+            None => ("DATABASE()".to_string(), table),
+        };
+        // TABLE_ROWS is an InnoDB estimate
+        Some(format!(
+            "SELECT TABLE_ROWS, DATA_LENGTH + INDEX_LENGTH FROM INFORMATION_SCHEMA.TABLES \
+             WHERE TABLE_SCHEMA = {} AND TABLE_NAME = '{}'",
+            schema,
+            name.replace('\'', "''")
+        ))
+    }
+
+    fn explain_sql(&self, statement: &str) -> Option<String> {
+        Some(format!("EXPLAIN {}", statement))
+    }
+
+    fn explain_estimate(&self, plan: &[Vec<String>]) -> ExplainEstimate {
+        // Tabular EXPLAIN: access type in column 5 (`ALL` is a full scan), estimated rows in column 10
+        ExplainEstimate {
+            rows: plan.iter().filter_map(|row| row.get(9)?.parse().ok()).max(),
+            cost: None,
+            full_scan: plan.iter().any(|row| row.get(4).is_some_and(|access| access == "ALL")),
+        }
+    }
+
+    fn create_migrations_indexes_sql(&self) -> Vec<String> {
+        // MySQL has no CREATE INDEX IF NOT EXISTS
+        migration_table_indexes_sql(self, false)
+    }
+
+    fn statement_splitting(&self) -> StatementSplitting {
+        // Procedures and triggers are written as in the mysql client, between DELIMITER lines
+        StatementSplitting::Delimiter
+    }
+}
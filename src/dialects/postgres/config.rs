@@ -0,0 +1,32 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// `[dialect.postgres]`: session defaults for PostgreSQL targets
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PostgresConfig {
+    /// lock_timeout before each migration when `database.lock_timeout_ms` is unset
+    #[serde(default)]
+    pub lock_timeout_ms: Option<u64>,
+
+    /// statement_timeout of every session
+    #[serde(default)]
+    pub statement_timeout_ms: Option<u64>,
+
+    /// application_name of every session, shown in pg_stat_activity
+    #[serde(default)]
+    pub application_name: Option<String>,
+}
+
+impl PostgresConfig {
+    /// `SET` statements run on every new connection
+    pub fn session_setup_sql(&self) -> Vec<String> {
+        let mut statements = Vec::new();
+        if let Some(ms) = self.statement_timeout_ms {
+            statements.push(format!("SET statement_timeout = '{}ms'", ms));
+        }
+        if let Some(name) = &self.application_name {
+            statements.push(format!("SET application_name = '{}'", name.replace('\'', "''")));
+        }
+        statements
+    }
+}
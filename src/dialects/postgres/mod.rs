@@ -1,13 +1,24 @@
-use crate::dialects::base::{DatabaseDialect, DialectConfig, DetectionResult};
+use crate::dialects::base::{DatabaseDialect, DialectConfig, DetectionResult, ExplainEstimate};
+use crate::dialects::settings::DialectSettings;
 use regex::Regex;
 use std::sync::OnceLock;
 
+mod config;
+
+pub use config::PostgresConfig;
+
 static CONFIG: OnceLock<DialectConfig> = OnceLock::new();
 
 pub struct PostgresDialect {
     config: &'static DialectConfig,
 }
 
+impl Default for PostgresDialect {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl PostgresDialect {
     pub fn new() -> Self {
         let config = CONFIG.get_or_init(|| {
@@ -31,34 +42,32 @@ impl DatabaseDialect for PostgresDialect {
         
         // Check connection patterns
         for pattern in &self.config.detection.connection_patterns {
-            if let Ok(re) = Regex::new(pattern) {
-                if re.is_match(&conn_lower) {
-                    confidence = 0.9;
-                    matched_pattern = pattern.clone();
-                    break;
-                }
+            if let Ok(re) = Regex::new(pattern)
+                && re.is_match(&conn_lower)
+            {
+                confidence = 0.9;
+                matched_pattern = pattern.clone();
+                break;
             }
         }
         
         // Check driver patterns
         if confidence == 0.0 {
             for pattern in &self.config.detection.driver_patterns {
-                if let Ok(re) = Regex::new(pattern) {
-                    if re.is_match(connection_string) {
-                        confidence = 0.8;
-                        matched_pattern = pattern.clone();
-                        break;
-                    }
+                if let Ok(re) = Regex::new(pattern)
+                    && re.is_match(connection_string)
+                {
+                    confidence = 0.8;
+                    matched_pattern = pattern.clone();
+                    break;
                 }
             }
         }
         
         // Fallback to simple string matching
-        if confidence == 0.0 {
-            if conn_lower.contains("postgresql") || conn_lower.contains("postgres") {
-                confidence = 0.7;
-                matched_pattern = "postgresql|postgres".to_string();
-            }
+        if confidence == 0.0 && (conn_lower.contains("postgresql") || conn_lower.contains("postgres")) {
+            confidence = 0.7;
+            matched_pattern = "postgresql|postgres".to_string();
         }
         
         if confidence > 0.0 {
@@ -74,16 +83,22 @@ impl DatabaseDialect for PostgresDialect {
     
     fn create_migrations_table_sql(&self) -> String {
         let types = &self.config.types;
+        let q = |identifier: &str| self.quote_identifier(identifier);
         format!(
-            r#"CREATE TABLE IF NOT EXISTS schema_migrations (
-    migration_id {} PRIMARY KEY NOT NULL,
-    migration_type {} NOT NULL DEFAULT 'versioned',
-    version INTEGER,
-    filename {} NOT NULL,
-    checksum {} NOT NULL,
-    applied_at {} NOT NULL DEFAULT {},
-    execution_time_ms {} NOT NULL,
-    success {} NOT NULL DEFAULT {}
+            r#"CREATE TABLE IF NOT EXISTS {table} (
+    {migration_id} {} PRIMARY KEY NOT NULL,
+    {migration_type} {} NOT NULL DEFAULT 'versioned',
+    {version} INTEGER,
+    {filename} {} NOT NULL,
+    {checksum} {} NOT NULL,
+    {applied_at} {} NOT NULL DEFAULT {},
+    {execution_time_ms} {} NOT NULL,
+    {success} {} NOT NULL DEFAULT {},
+    {release} {},
+    {git_commit} {},
+    {git_branch} {},
+    {run_id} {},
+    {status} {}
 )"#,
             types.migration_id,
             types.migration_type,
@@ -93,7 +108,26 @@ impl DatabaseDialect for PostgresDialect {
             self.current_timestamp(),
             types.execution_time_ms,
             types.success,
-            self.boolean_true()
+            self.boolean_true(),
+            types.release,
+            types.git_commit,
+            types.git_branch,
+            types.run_id,
+            types.status,
+            table = q("schema_migrations"),
+            migration_id = q("migration_id"),
+            migration_type = q("migration_type"),
+            version = q("version"),
+            filename = q("filename"),
+            checksum = q("checksum"),
+            applied_at = q("applied_at"),
+            execution_time_ms = q("execution_time_ms"),
+            success = q("success"),
+            release = q("release"),
+            git_commit = q("git_commit"),
+            git_branch = q("git_branch"),
+            run_id = q("run_id"),
+            status = q("status")
         )
     }
     
@@ -107,8 +141,68 @@ impl DatabaseDialect for PostgresDialect {
             "SELECT schemaname, sequencename FROM pg_sequences WHERE schemaname NOT IN ('information_schema', 'pg_catalog')".to_string(),
         ]
     }
-    
-    fn list_tables_sql(&self) -> String {
-        "SELECT tablename FROM pg_tables WHERE schemaname = 'public' AND tablename != 'schema_migrations'".to_string()
+
+    fn lock_timeout_sql(&self, timeout_ms: Option<u64>) -> Option<String> {
+        Some(match timeout_ms {
+            Some(ms) => format!("SET lock_timeout = '{}ms'", ms),
+            None => "SET lock_timeout TO DEFAULT".to_string(),
+        })
+    }
+
+    fn use_schema_sql(&self, schema: &str) -> Option<String> {
+        Some(format!("SET search_path TO {}", self.quote_identifier(schema)))
+    }
+
+    fn isolation_level_sql(&self, level: &str) -> Option<String> {
+        Some(format!("SET SESSION CHARACTERISTICS AS TRANSACTION ISOLATION LEVEL {}", level))
     }
-}
\ No newline at end of file
+
+    fn read_only_sql(&self) -> Option<String> {
+        Some("SET SESSION CHARACTERISTICS AS TRANSACTION READ ONLY".to_string())
+    }
+
+    fn hash_sql(&self, expression: &str) -> Option<String> {
+        Some(format!("md5({}::text)", expression))
+    }
+
+    fn session_setup_sql(&self, settings: &DialectSettings) -> Vec<String> {
+        settings.postgres.as_ref().map(PostgresConfig::session_setup_sql).unwrap_or_default()
+    }
+
+    fn default_lock_timeout_ms(&self, settings: &DialectSettings) -> Option<u64> {
+        settings.postgres.as_ref().and_then(|postgres| postgres.lock_timeout_ms)
+    }
+
+    fn table_size_sql(&self, table: &str) -> Option<String> {
+        // reltuples is the planner's estimate (-1 before the first ANALYZE); counting would scan the table
+        Some(format!(
+            "SELECT GREATEST(c.reltuples, 0)::bigint, pg_total_relation_size(c.oid) FROM pg_class c WHERE c.oid = to_regclass('{}')",
+            table.replace('\'', "''")
+        ))
+    }
+
+    fn table_checksum_sql(&self, table: &str, _columns: &[String]) -> Option<String> {
+        // The first 32 bits of each row's md5, summed; the row's text form covers every column
+        Some(format!(
+            "SELECT SUM(('x' || SUBSTR(md5(t::text), 1, 8))::bit(32)::int::bigint) FROM {} t",
+            table
+        ))
+    }
+
+    fn explain_sql(&self, statement: &str) -> Option<String> {
+        Some(format!("EXPLAIN {}", statement))
+    }
+
+    fn explain_estimate(&self, plan: &[Vec<String>]) -> ExplainEstimate {
+        // The top node, e.g. `Update on users  (cost=0.00..35.50 rows=2550 width=10)`, carries the totals
+        static TOTALS: OnceLock<Regex> = OnceLock::new();
+        let totals = TOTALS.get_or_init(|| Regex::new(r"cost=[\d.]+\.\.([\d.]+) rows=(\d+)").unwrap());
+        let lines: Vec<&str> = plan.iter().filter_map(|row| row.first().map(String::as_str)).collect();
+        let top = lines.iter().find_map(|line| totals.captures(line));
+        ExplainEstimate {
+            rows: top.as_ref().and_then(|c| c[2].parse().ok()),
+            cost: top.as_ref().and_then(|c| c[1].parse().ok()),
+            full_scan: lines.iter().any(|line| line.contains("Seq Scan")),
+        }
+    }
+}
@@ -3,6 +3,10 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use log::{debug, warn};
 
+/// Lowest detection confidence `dialect = "auto"` accepts; the generic dialect matches
+/// everything with 0.1
+pub const MIN_DETECTION_CONFIDENCE: f32 = 0.5;
+
 /// Central registry for all available database dialects
 pub struct DialectRegistry {
     dialects: HashMap<String, Arc<dyn DatabaseDialect>>,
@@ -47,8 +51,8 @@ impl DialectRegistry {
         None
     }
     
-    /// Detect dialect from connection string
-    pub fn detect(&self, connection_string: &str) -> Result<Arc<dyn DatabaseDialect>, DialectError> {
+    /// Every dialect whose detection matches the connection string, highest confidence first
+    pub fn detection_candidates(&self, connection_string: &str) -> Vec<(Arc<dyn DatabaseDialect>, DetectionResult)> {
         let mut candidates: Vec<(Arc<dyn DatabaseDialect>, DetectionResult)> = Vec::new();
         
         debug!("Detecting dialect for connection string (length: {})", connection_string.len());
@@ -61,26 +65,63 @@ impl DialectRegistry {
             }
         }
         
+        // Sort by confidence (highest first), then by name so ties list the same way every run
+        candidates.sort_by(|a, b| {
+            b.1.confidence
+                .partial_cmp(&a.1.confidence)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.0.name().cmp(b.0.name()))
+        });
+        candidates
+    }
+
+    /// Detect dialect from connection string
+    pub fn detect(&self, connection_string: &str) -> Result<Arc<dyn DatabaseDialect>, DialectError> {
+        self.detect_with_result(connection_string).map(|(dialect, _)| dialect)
+    }
+
+    /// Detects the dialect along with the pattern that matched. The `Driver` of an ODBC
+    /// connection string is tried on its own first: hosts and database names may contain
+    /// anything (a `delta-db` host is not Databricks), the driver names the database.
+    pub fn detect_with_result(&self, connection_string: &str) -> Result<(Arc<dyn DatabaseDialect>, DetectionResult), DialectError> {
+        if let Some(driver) = odbc_driver(connection_string) {
+            match Self::select(self.detection_candidates(driver)) {
+                Ok((dialect, result)) if result.confidence >= MIN_DETECTION_CONFIDENCE => {
+                    debug!("Selected dialect {} from ODBC driver '{}'", dialect.name(), driver);
+                    return Ok((dialect, result));
+                }
+                _ => debug!("ODBC driver '{}' does not identify a dialect", driver),
+            }
+        }
+        Self::select(self.detection_candidates(connection_string))
+    }
+
+    /// The most confident candidate, unless a second one comes within 0.1 of it
+    fn select(candidates: Vec<(Arc<dyn DatabaseDialect>, DetectionResult)>) -> Result<(Arc<dyn DatabaseDialect>, DetectionResult), DialectError> {
         if candidates.is_empty() {
             warn!("No dialect detected for connection string");
             return Err(DialectError::NotFound("No matching dialect found".to_string()));
         }
         
-        // Sort by confidence (highest first)
-        candidates.sort_by(|a, b| b.1.confidence.partial_cmp(&a.1.confidence).unwrap_or(std::cmp::Ordering::Equal));
-        
         // Check for ambiguity (multiple high-confidence matches)
         if candidates.len() > 1 && (candidates[0].1.confidence - candidates[1].1.confidence).abs() < 0.1 {
             let names: Vec<String> = candidates.iter().map(|(d, _)| d.name().to_string()).collect();
             return Err(DialectError::Ambiguous(names));
         }
         
-        let selected = &candidates[0];
+        let selected = candidates.into_iter().next().expect("candidates is not empty");
         debug!("Selected dialect: {} (confidence: {})", selected.0.name(), selected.1.confidence);
         
-        Ok(selected.0.clone())
+        Ok(selected)
     }
     
+    /// All registered dialects, ordered by name
+    pub fn all(&self) -> Vec<Arc<dyn DatabaseDialect>> {
+        let mut dialects: Vec<Arc<dyn DatabaseDialect>> = self.dialects.values().cloned().collect();
+        dialects.sort_by(|a, b| a.name().cmp(b.name()));
+        dialects
+    }
+
     /// List all registered dialect names
     pub fn list_dialects(&self) -> Vec<String> {
         self.dialects.keys().cloned().collect()
@@ -102,6 +143,16 @@ impl Default for DialectRegistry {
     }
 }
 
+/// Value of the `Driver` key of an ODBC connection string, without braces
+fn odbc_driver(connection_string: &str) -> Option<&str> {
+    connection_string.split(';').find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case("driver")
+            .then(|| value.trim().trim_start_matches('{').trim_end_matches('}'))
+    })
+}
+
 use std::sync::{Mutex, OnceLock};
 
 /// Global registry instance
@@ -126,4 +177,25 @@ fn create_default_registry() -> DialectRegistry {
     registry.register(Arc::new(crate::dialects::generic::GenericDialect::new()));
     
     registry
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_odbc_driver_decides_before_hosts_and_names() {
+        let registry = create_default_registry();
+        // `.*delta.*` and `.*postgres.*` both match the whole string
+        let conn = "Driver={PostgreSQL Unicode};Server=delta-db;Database=app";
+        assert!(matches!(registry.detect("Server=delta-db;Database=postgres"), Err(DialectError::Ambiguous(_))));
+        let (dialect, result) = registry.detect_with_result(conn).unwrap();
+        assert_eq!(dialect.name(), "PostgreSQL");
+        assert!(result.confidence >= MIN_DETECTION_CONFIDENCE);
+        assert_eq!(odbc_driver("DSN=x; driver = {Simba Spark ODBC Driver} ;Host=h"), Some("Simba Spark ODBC Driver"));
+
+        // Only the generic fallback matches
+        let (dialect, result) = registry.detect_with_result("DSN=warehouse").unwrap();
+        assert_eq!(dialect.name(), "Generic");
+        assert!(result.confidence < MIN_DETECTION_CONFIDENCE);
+    }
+}
@@ -0,0 +1,34 @@
+use crate::dialects::databricks::DatabricksConfig;
+use crate::dialects::mysql::MysqlConfig;
+use crate::dialects::postgres::PostgresConfig;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// `[dialect.<name>]` sections of the configuration. Each dialect reads its own section
+/// when connecting; sections of other dialects are ignored.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct DialectSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub postgres: Option<PostgresConfig>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mysql: Option<MysqlConfig>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub databricks: Option<DatabricksConfig>,
+}
+
+impl DialectSettings {
+    /// Sections set in `other` replace the ones here
+    pub fn merge(&mut self, other: DialectSettings) {
+        if other.postgres.is_some() {
+            self.postgres = other.postgres;
+        }
+        if other.mysql.is_some() {
+            self.mysql = other.mysql;
+        }
+        if other.databricks.is_some() {
+            self.databricks = other.databricks;
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::dialects::base::{DatabaseDialect, DetectionResult, DialectConfig};
+use crate::dialects::base::{DatabaseDialect, DetectionResult, DialectConfig, ExplainEstimate};
 use regex::Regex;
 use std::sync::OnceLock;
 
@@ -8,6 +8,12 @@ pub struct SqliteDialect {
     config: &'static DialectConfig,
 }
 
+impl Default for SqliteDialect {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SqliteDialect {
     pub fn new() -> Self {
         let config = CONFIG.get_or_init(|| {
@@ -31,34 +37,32 @@ impl DatabaseDialect for SqliteDialect {
 
         // Check connection patterns
         for pattern in &self.config.detection.connection_patterns {
-            if let Ok(re) = Regex::new(pattern) {
-                if re.is_match(&conn_lower) {
-                    confidence = 0.9;
-                    matched_pattern = pattern.clone();
-                    break;
-                }
+            if let Ok(re) = Regex::new(pattern)
+                && re.is_match(&conn_lower)
+            {
+                confidence = 0.9;
+                matched_pattern = pattern.clone();
+                break;
             }
         }
 
         // Check driver patterns
         if confidence == 0.0 {
             for pattern in &self.config.detection.driver_patterns {
-                if let Ok(re) = Regex::new(pattern) {
-                    if re.is_match(connection_string) {
-                        confidence = 0.8;
-                        matched_pattern = pattern.clone();
-                        break;
-                    }
+                if let Ok(re) = Regex::new(pattern)
+                    && re.is_match(connection_string)
+                {
+                    confidence = 0.8;
+                    matched_pattern = pattern.clone();
+                    break;
                 }
             }
         }
 
         // Fallback to simple string matching
-        if confidence == 0.0 {
-            if conn_lower.contains("sqlite") {
-                confidence = 0.7;
-                matched_pattern = "sqlite".to_string();
-            }
+        if confidence == 0.0 && conn_lower.contains("sqlite") {
+            confidence = 0.7;
+            matched_pattern = "sqlite".to_string();
         }
 
         if confidence > 0.0 {
@@ -74,16 +78,22 @@ impl DatabaseDialect for SqliteDialect {
 
     fn create_migrations_table_sql(&self) -> String {
         let types = &self.config.types;
+        let q = |identifier: &str| self.quote_identifier(identifier);
         format!(
-            r#"CREATE TABLE IF NOT EXISTS schema_migrations (
-    migration_id {} PRIMARY KEY NOT NULL,
-    migration_type {} NOT NULL DEFAULT 'versioned',
-    version INTEGER,
-    filename {} NOT NULL,
-    checksum {} NOT NULL,
-    applied_at {} NOT NULL DEFAULT {},
-    execution_time_ms {} NOT NULL,
-    success {} NOT NULL DEFAULT {}
+            r#"CREATE TABLE IF NOT EXISTS {table} (
+    {migration_id} {} PRIMARY KEY NOT NULL,
+    {migration_type} {} NOT NULL DEFAULT 'versioned',
+    {version} INTEGER,
+    {filename} {} NOT NULL,
+    {checksum} {} NOT NULL,
+    {applied_at} {} NOT NULL DEFAULT {},
+    {execution_time_ms} {} NOT NULL,
+    {success} {} NOT NULL DEFAULT {},
+    {release} {},
+    {git_commit} {},
+    {git_branch} {},
+    {run_id} {},
+    {status} {}
 )"#,
             types.migration_id,
             types.migration_type,
@@ -93,7 +103,26 @@ impl DatabaseDialect for SqliteDialect {
             self.current_timestamp(),
             types.execution_time_ms,
             types.success,
-            self.boolean_true()
+            self.boolean_true(),
+            types.release,
+            types.git_commit,
+            types.git_branch,
+            types.run_id,
+            types.status,
+            table = q("schema_migrations"),
+            migration_id = q("migration_id"),
+            migration_type = q("migration_type"),
+            version = q("version"),
+            filename = q("filename"),
+            checksum = q("checksum"),
+            applied_at = q("applied_at"),
+            execution_time_ms = q("execution_time_ms"),
+            success = q("success"),
+            release = q("release"),
+            git_commit = q("git_commit"),
+            git_branch = q("git_branch"),
+            run_id = q("run_id"),
+            status = q("status")
         )
     }
 
@@ -108,8 +137,43 @@ impl DatabaseDialect for SqliteDialect {
         ]
     }
 
-    fn list_tables_sql(&self) -> String {
-        "SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%' AND name != 'schema_migrations'".to_string()
+    fn columns_sql(&self) -> String {
+        "SELECT 'main', m.name, p.name, p.type, CASE WHEN p.\"notnull\" = 0 THEN 'YES' ELSE 'NO' END, \
+         CASE WHEN p.pk > 0 THEN 1 ELSE 0 END \
+         FROM sqlite_master m JOIN pragma_table_info(m.name) p \
+         WHERE m.type = 'table' AND m.name NOT LIKE 'sqlite_%' AND m.name NOT IN ('schema_migrations', 'deriddl_lock', 'deriddl_runs') \
+         ORDER BY m.name, p.cid"
+            .to_string()
+    }
+
+    fn foreign_keys_sql(&self) -> String {
+        // SQLite foreign keys are unnamed; name them after the table and key id
+        "SELECT m.name || '_fk' || f.id, 'main', m.name, f.\"from\", 'main', f.\"table\", f.\"to\" \
+         FROM sqlite_master m JOIN pragma_foreign_key_list(m.name) f \
+         WHERE m.type = 'table' AND m.name NOT LIKE 'sqlite_%' \
+         ORDER BY m.name, f.id, f.seq"
+            .to_string()
+    }
+
+    fn table_size_sql(&self, table: &str) -> Option<String> {
+        // SQLite keeps no row estimate; counting is cheap at the sizes SQLite is used for
+        let table: Vec<String> = table.split('.').map(|part| self.quote_identifier(part)).collect();
+        Some(format!("SELECT COUNT(*), NULL FROM {}", table.join(".")))
+    }
+
+    fn explain_sql(&self, statement: &str) -> Option<String> {
+        Some(format!("EXPLAIN QUERY PLAN {}", statement))
+    }
+
+    fn explain_estimate(&self, plan: &[Vec<String>]) -> ExplainEstimate {
+        // SQLite estimates no rows; `SCAN users` reads the table, `SEARCH users USING INDEX` does not
+        ExplainEstimate {
+            full_scan: plan
+                .iter()
+                .filter_map(|row| row.last())
+                .any(|detail| detail.starts_with("SCAN ") && !detail.contains(" USING ")),
+            ..Default::default()
+        }
     }
 }
 
@@ -0,0 +1,80 @@
+use log::{debug, warn};
+use odbc_api::handles::{AsStatementRef, Statement};
+use odbc_api::sys::{HStmt, SQLCancel, SqlReturn};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// A statement handle handed to the watchdog thread. SQLCancel is the one ODBC call meant
+/// to be made from another thread while the statement executes.
+struct CancelHandle(HStmt);
+
+unsafe impl Send for CancelHandle {}
+
+/// Cancels a statement with SQLCancel when it is still running after the timeout. The
+/// statement must outlive the watchdog, so [`Watchdog::finish`] is called before it is dropped.
+pub(crate) struct Watchdog {
+    stop: Sender<()>,
+    thread: JoinHandle<bool>,
+}
+
+impl Watchdog {
+    pub(crate) fn start(statement: &mut impl AsStatementRef, timeout: Duration) -> Self {
+        let handle = CancelHandle(statement.as_stmt_ref().as_sys());
+        Self::spawn(timeout, move || {
+            let handle = handle;
+            let result = unsafe { SQLCancel(handle.0) };
+            if result != SqlReturn::SUCCESS && result != SqlReturn::SUCCESS_WITH_INFO {
+                debug!("SQLCancel returned {:?}", result);
+            }
+        })
+    }
+
+    /// Runs `cancel` unless [`Watchdog::finish`] is called within `timeout`
+    fn spawn(timeout: Duration, cancel: impl FnOnce() + Send + 'static) -> Self {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let thread = thread::spawn(move || match stopped.recv_timeout(timeout) {
+            Err(RecvTimeoutError::Timeout) => {
+                warn!("⚠️  Statement still running after {}ms, cancelling it", timeout.as_millis());
+                cancel();
+                true
+            }
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => false,
+        });
+        Self { stop, thread }
+    }
+
+    /// Stops the watchdog; true when it cancelled the statement
+    pub(crate) fn finish(self) -> bool {
+        let _ = self.stop.send(());
+        self.thread.join().unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    fn watchdog(timeout: Duration) -> (Watchdog, Arc<AtomicBool>) {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let flag = cancelled.clone();
+        (Watchdog::spawn(timeout, move || flag.store(true, Ordering::SeqCst)), cancelled)
+    }
+
+    #[test]
+    fn test_statements_finishing_in_time_are_not_cancelled() {
+        let (watchdog, cancelled) = watchdog(Duration::from_secs(60));
+        assert!(!watchdog.finish());
+        assert!(!cancelled.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_statements_running_past_the_timeout_are_cancelled() {
+        let (watchdog, cancelled) = watchdog(Duration::from_millis(10));
+        thread::sleep(Duration::from_millis(200));
+        assert!(cancelled.load(Ordering::SeqCst));
+        assert!(watchdog.finish());
+    }
+}
@@ -1,8 +1,138 @@
 use log::{debug, error, info};
+use super::cancel::Watchdog;
+use super::interrupt::check_interrupted;
+use super::read_only;
+use super::splitter::{StatementSplitter, StatementSplitting};
+use super::typed::{self, TypedRow};
 use odbc_api::{
-    buffers::TextRowSet, Connection, ConnectionOptions, Cursor, Environment, Error as OdbcError,
+    buffers::{ColumnarAnyBuffer, TextRowSet}, handles::StatementImpl, Connection, ConnectionOptions, Cursor, Environment,
+    Error as OdbcError, Prepared, ResultSetMetadata,
 };
+use crate::dialects::DatabaseDialect;
+use crate::model::config::QueryTagConfig;
+use crate::model::Config;
+use crate::model::run::random_uuid;
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
+
+/// Session tags set with `database.query_tag.session`: the run for the whole session, the
+/// migration while it runs
+pub const RUN_TAG: &str = "deriddl_run_id";
+pub const MIGRATION_TAG: &str = "deriddl_migration";
+
+/// ODBC attributes set on every connection (`[database.odbc]`); None keeps the driver default
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConnectionAttributes {
+    pub login_timeout_sec: Option<u32>,
+    pub packet_size: Option<u32>,
+    pub autocommit: Option<bool>,
+}
+
+impl ConnectionAttributes {
+    /// In manual-commit mode deriddl commits statements it runs outside a transaction itself
+    fn manual_commit(&self) -> bool {
+        self.autocommit == Some(false)
+    }
+}
+
+/// How a run connects and sends statements, built once from the configuration and handed
+/// to every `ConnectionManager` and the executors it opens
+#[derive(Debug, Clone)]
+pub struct ConnectionSettings {
+    /// Statements (e.g. `SET ROLE migrator`) run on each new connection
+    pub session_setup: Vec<String>,
+    pub attributes: ConnectionAttributes,
+    /// How statements are tagged with the run (`[database.query_tag]`)
+    pub query_tag: QueryTagConfig,
+    /// `database.statement_timeout_ms`, after which a running statement is cancelled
+    pub statement_timeout: Option<Duration>,
+    /// Refuse statements that could change the database, for informational commands
+    pub read_only: bool,
+    /// How SQL is cut into statements, as the dialect needs
+    pub statement_splitting: StatementSplitting,
+    /// Random UUID (version 4) identifying the run, or the one `apply --resume` continues
+    pub run_id: String,
+}
+
+impl Default for ConnectionSettings {
+    /// A new run with the driver's defaults
+    fn default() -> Self {
+        Self {
+            session_setup: Vec::new(),
+            attributes: ConnectionAttributes::default(),
+            query_tag: QueryTagConfig::default(),
+            statement_timeout: None,
+            read_only: false,
+            statement_splitting: StatementSplitting::default(),
+            run_id: random_uuid(),
+        }
+    }
+}
+
+impl ConnectionSettings {
+    /// How run `run_id` connects to the target described by `config`, running
+    /// `session_setup` (see [`super::SessionSettings::for_config`]) on each new connection.
+    /// Read-only runs always autocommit.
+    pub fn for_config(
+        config: &Config,
+        dialect: Option<&dyn DatabaseDialect>,
+        session_setup: Vec<String>,
+        read_only: bool,
+        run_id: &str,
+    ) -> Self {
+        let odbc = &config.database.odbc;
+        Self {
+            session_setup,
+            attributes: ConnectionAttributes {
+                login_timeout_sec: odbc.login_timeout_secs,
+                packet_size: odbc.packet_size,
+                autocommit: if read_only { Some(true) } else { odbc.autocommit },
+            },
+            query_tag: config.database.query_tag,
+            statement_timeout: config.database.statement_timeout_ms.map(Duration::from_millis),
+            read_only,
+            statement_splitting: dialect.map(|d| d.statement_splitting()).unwrap_or_default(),
+            run_id: run_id.to_string(),
+        }
+    }
+}
+
+/// The connection string with the values of credential attributes (`PWD`, `Password`,
+/// `Auth_AccessToken`, client secrets, ...) replaced by `***`, for logging
+pub fn redact_connection_string(connection_string: &str) -> String {
+    let mut redacted = String::with_capacity(connection_string.len());
+    let mut rest = connection_string;
+    while !rest.is_empty() {
+        let Some((key, after_key)) = rest.split_once('=') else {
+            redacted.push_str(rest);
+            break;
+        };
+        // Braced values may contain semicolons: PWD={a;b}
+        let value_len = match after_key.strip_prefix('{') {
+            Some(braced) => braced.find('}').map_or(after_key.len(), |end| end + 2),
+            None => after_key.find(';').unwrap_or(after_key.len()),
+        };
+        let (value, after_value) = after_key.split_at(value_len);
+        let name = key.trim().to_ascii_lowercase();
+        let secret = ["pwd", "password", "secret", "token"].iter().any(|marker| name.contains(marker));
+        redacted.push_str(key);
+        redacted.push('=');
+        redacted.push_str(if secret && !value.is_empty() { "***" } else { value });
+        match after_value.find(';') {
+            Some(end) => {
+                redacted.push_str(&after_value[..=end]);
+                rest = &after_value[end + 1..];
+            }
+            None => {
+                redacted.push_str(after_value);
+                break;
+            }
+        }
+    }
+    redacted
+}
 
 #[derive(Debug, thiserror::Error)]
 pub enum ConnectionError {
@@ -18,20 +148,47 @@ pub enum ConnectionError {
     #[error("Transaction failed: {0}")]
     TransactionFailed(String),
 
+    #[error("Session setup statement '{0}' failed: {1}")]
+    SessionSetup(String, String),
+
+    #[error("Refusing to run '{0}': this command is read-only")]
+    ReadOnly(String),
+
+    #[error("Interrupted by a signal")]
+    Interrupted,
+
+    #[error("Statement cancelled after exceeding database.statement_timeout_ms ({0}ms)")]
+    StatementTimeout(u64),
+
     #[error("Other error: {0}")]
     Other(String),
 }
 
 pub struct ConnectionManager {
     environment: Arc<Environment>,
+    settings: Arc<ConnectionSettings>,
 }
 
 impl ConnectionManager {
+    /// Connects with the default settings, as a run of its own
     pub fn new() -> Result<Self, ConnectionError> {
+        Self::with_settings(ConnectionSettings::default())
+    }
+
+    /// Opens connections and executors with `settings`
+    pub fn with_settings(settings: ConnectionSettings) -> Result<Self, ConnectionError> {
         let environment = Environment::new()?;
-        Ok(Self {
-            environment: Arc::new(environment),
-        })
+        Ok(Self { environment: Arc::new(environment), settings: Arc::new(settings) })
+    }
+
+    pub fn settings(&self) -> &ConnectionSettings {
+        &self.settings
+    }
+
+    /// Connects to `connection_string` and wraps the connection in an executor with this
+    /// manager's settings
+    pub fn executor(&self, connection_string: &str) -> Result<DatabaseExecutor<'_>, ConnectionError> {
+        Ok(DatabaseExecutor::new(self.connect(connection_string)?, self.settings.clone()))
     }
 
     pub fn connect(&self, connection_string: &str) -> Result<Connection<'_>, ConnectionError> {
@@ -40,18 +197,45 @@ impl ConnectionManager {
             connection_string.len()
         );
 
+        let attributes = self.settings.attributes;
+        let options = ConnectionOptions {
+            login_timeout_sec: attributes.login_timeout_sec,
+            packet_size: attributes.packet_size,
+        };
         let connection = self
             .environment
-            .connect_with_connection_string(connection_string, ConnectionOptions::default())
+            .connect_with_connection_string(connection_string, options)
             .map_err(|e| {
                 error!("Failed to connect to database: {}", e);
                 ConnectionError::ConnectionFailed(e.to_string())
             })?;
+        if let Some(autocommit) = attributes.autocommit {
+            debug!("Setting autocommit to {}", autocommit);
+            connection.set_autocommit(autocommit)?;
+        }
+
+        for statement in &self.settings.session_setup {
+            debug!("Running session setup: {}", statement);
+            connection.execute(statement, (), None).map_err(|e| {
+                error!("Session setup statement '{}' failed: {}", statement, e);
+                ConnectionError::SessionSetup(statement.clone(), e.to_string())
+            })?;
+        }
 
-        info!("Successfully connected to database");
+        debug!("Successfully connected to database");
         Ok(connection)
     }
 
+    /// Names of the ODBC drivers registered with the driver manager
+    pub fn installed_drivers(&self) -> Result<Vec<String>, ConnectionError> {
+        Ok(self
+            .environment
+            .drivers()?
+            .into_iter()
+            .map(|driver| driver.description)
+            .collect())
+    }
+
     pub fn test_connection(&self, connection_string: &str) -> Result<(), ConnectionError> {
         debug!("Testing database connection");
         let connection = self.connect(connection_string)?;
@@ -72,65 +256,258 @@ impl ConnectionManager {
         let mut row_set_cursor = cursor.bind_buffer(&mut buffer)?;
         let _row_set = row_set_cursor.fetch()?;
 
-        info!("Database connection test successful");
+        debug!("Database connection test successful");
         Ok(())
     }
 }
 
+/// Column names and rows of a statement that produced a result set
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// What the driver reported for a single executed statement
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatementOutcome {
+    /// Rows inserted, updated or deleted, when the driver reports a count
+    pub rows_affected: Option<usize>,
+    /// Result sets the statement produced (e.g. a procedure with several SELECTs); they are
+    /// drained and discarded unless the statement is captured
+    pub result_sets: usize,
+    /// First rows of the first result set of a statement registered with
+    /// [`DatabaseExecutor::capture_results`]
+    pub captured: Option<QueryResult>,
+}
+
 pub struct DatabaseExecutor<'a> {
     connection: Connection<'a>,
+    settings: Arc<ConnectionSettings>,
+    /// Statements whose results are kept, with the number of rows to keep
+    captures: HashMap<String, usize>,
+    /// Commit after each statement block, because autocommit is off and no transaction is open
+    commit_each: bool,
+    /// Comment prefixed to every statement sent, see [`ConnectionSettings::query_tag`]
+    comment: Option<String>,
+    /// Start no statement once SIGINT or SIGTERM was received
+    interruptible: bool,
 }
 
 impl<'a> DatabaseExecutor<'a> {
-    pub fn new(connection: Connection<'a>) -> Self {
-        Self { connection }
+    pub fn new(connection: Connection<'a>, settings: Arc<ConnectionSettings>) -> Self {
+        let mut executor = Self {
+            connection,
+            commit_each: settings.attributes.manual_commit(),
+            settings,
+            captures: HashMap::new(),
+            comment: None,
+            interruptible: false,
+        };
+        executor.comment = executor.run_comment(None);
+        executor
+    }
+
+    pub fn settings(&self) -> &ConnectionSettings {
+        &self.settings
+    }
+
+    fn run_comment(&self, migration: Option<&str>) -> Option<String> {
+        if !self.settings.query_tag.comment {
+            return None;
+        }
+        let run_id = &self.settings.run_id;
+        Some(match migration {
+            Some(migration) => format!("deriddl run_id={} migration={}", run_id, migration),
+            None => format!("deriddl run_id={}", run_id),
+        })
+    }
+
+    /// Attributes the statements that follow to `migration`, or to the run alone with None:
+    /// in their comment, and with `dialect`'s session tag when that is enabled
+    pub fn tag_migration(&mut self, migration: Option<&str>, dialect: Option<&dyn DatabaseDialect>) -> Result<(), ConnectionError> {
+        self.comment = self.run_comment(migration);
+        if !self.settings.query_tag.session {
+            return Ok(());
+        }
+        match dialect.and_then(|d| d.query_tag_sql(MIGRATION_TAG, migration.unwrap_or_default())) {
+            Some(sql) => self.execute_query(&sql),
+            None => Ok(()),
+        }
+    }
+
+    /// `sql` as sent to the driver, behind the run comment when there is one
+    fn tagged<'q>(&self, sql: &'q str) -> Cow<'q, str> {
+        match &self.comment {
+            Some(comment) => Cow::Owned(format!("-- {}\n{}", comment, sql)),
+            None => Cow::Borrowed(sql),
+        }
+    }
+
+    fn check_writable(&self, statement: &str) -> Result<(), ConnectionError> {
+        if self.settings.read_only && !read_only::is_read_statement(statement) {
+            let summary: String = statement.split_whitespace().collect::<Vec<_>>().join(" ").chars().take(80).collect();
+            return Err(ConnectionError::ReadOnly(summary));
+        }
+        Ok(())
+    }
+
+    fn commit_if_manual(&mut self) -> Result<(), ConnectionError> {
+        if self.commit_each {
+            self.connection.commit()?;
+        }
+        Ok(())
+    }
+
+    /// Keeps up to the given number of rows of these statements' results in their
+    /// [`StatementOutcome`], replacing earlier registrations. Statements are matched by
+    /// their text as split from the SQL.
+    pub fn capture_results(&mut self, statements: impl IntoIterator<Item = (String, usize)>) {
+        self.captures = statements.into_iter().collect();
     }
 
-    fn split_sql_statements(sql: &str) -> Vec<String> {
-        sql.lines()
-            .map(str::trim)
-            .filter(|line| !line.starts_with("--") && !line.is_empty())
-            .collect::<Vec<&str>>()
-            .join(" ")
-            .split(';')
-            .map(str::trim)
-            .filter(|stmt| !stmt.is_empty())
-            .map(String::from)
-            .collect()
+    /// Makes every later statement fail with [`ConnectionError::Interrupted`] once a signal
+    /// was received (see [`install_interrupt_handler`](super::install_interrupt_handler)),
+    /// for executors running migrations. Tracking writes use their own executors and still work.
+    pub fn stop_on_interrupt(&mut self) {
+        self.interruptible = true;
+    }
+
+    /// A splitter cutting SQL into statements the way this executor does
+    pub fn splitter(&self) -> StatementSplitter {
+        StatementSplitter::new(self.settings.statement_splitting)
     }
 
     pub fn execute_query(&mut self, query: &str) -> Result<(), ConnectionError> {
+        self.execute_query_with(query, |_, _| {})
+    }
+
+    /// Executes a query block, calling `on_executed` with each statement's outcome after it succeeds
+    pub fn execute_query_with<F>(&mut self, query: &str, mut on_executed: F) -> Result<(), ConnectionError>
+    where
+        F: FnMut(&str, &StatementOutcome),
+    {
         debug!("Executing query block");
 
-        for stmt in Self::split_sql_statements(query) {
-            let stmt_ref: &str = stmt.as_str();
-            debug!("Executing SQL statement: {}", stmt);
-
-            let mut prepared = self
-                .connection
-                .prepare(stmt_ref)
-                .map_err(|e| ConnectionError::QueryFailed(e.to_string()))?;
-
-            match prepared.execute(()) {
-                Ok(Some(mut cursor)) => {
-                    let mut buffer = TextRowSet::for_cursor(100, &mut cursor, Some(4096))?;
-                    let mut row_set_cursor = cursor.bind_buffer(&mut buffer)?;
-                    while row_set_cursor.fetch()?.is_some() {
-                        // Consume results
+        for stmt in self.splitter().split(query) {
+            let outcome = self.execute_statement(&stmt)?;
+            on_executed(&stmt, &outcome);
+        }
+
+        self.commit_if_manual()
+    }
+
+    /// Executes statements as they are produced, e.g. by a [`StatementReader`](super::StatementReader)
+    /// streaming a large file, calling `on_executed` with each statement's outcome after it succeeds
+    pub fn execute_statements_with<I, F>(&mut self, statements: I, mut on_executed: F) -> Result<(), ConnectionError>
+    where
+        I: IntoIterator<Item = std::io::Result<String>>,
+        F: FnMut(&str, &StatementOutcome),
+    {
+        debug!("Executing streamed statements");
+
+        for stmt in statements {
+            let stmt = stmt.map_err(|e| ConnectionError::Other(format!("Failed to read SQL: {}", e)))?;
+            let outcome = self.execute_statement(&stmt)?;
+            on_executed(&stmt, &outcome);
+        }
+
+        self.commit_if_manual()
+    }
+
+    /// Sends `sql` to the driver as a single batch without splitting it, for scripts the
+    /// driver handles natively, calling `on_executed` with the outcome after it succeeds
+    pub fn execute_batch_with<F>(&mut self, sql: &str, mut on_executed: F) -> Result<(), ConnectionError>
+    where
+        F: FnMut(&str, &StatementOutcome),
+    {
+        debug!("Executing raw batch");
+
+        let sql = sql.trim();
+        if !sql.is_empty() {
+            let outcome = self.execute_statement(sql)?;
+            on_executed(sql, &outcome);
+        }
+
+        self.commit_if_manual()
+    }
+
+    fn execute_statement(&mut self, stmt: &str) -> Result<StatementOutcome, ConnectionError> {
+        debug!("Executing SQL statement: {}", stmt);
+        self.check_writable(stmt)?;
+        if self.interruptible {
+            check_interrupted()?;
+        }
+
+        let mut prepared = self
+            .connection
+            .prepare(&self.tagged(stmt))
+            .map_err(|e| ConnectionError::QueryFailed(e.to_string()))?;
+
+        // Cancelled with SQLCancel once database.statement_timeout_ms passes
+        let timeout = self.settings.statement_timeout;
+        let watchdog = timeout.map(|timeout| Watchdog::start(&mut prepared, timeout));
+        let result = self.run_prepared(&mut prepared, stmt);
+        let cancelled = watchdog.is_some_and(Watchdog::finish);
+        match (result, timeout) {
+            (Err(e), Some(timeout)) if cancelled => {
+                error!("Statement cancelled after {}ms: {}", timeout.as_millis(), e);
+                Err(ConnectionError::StatementTimeout(timeout.as_millis() as u64))
+            }
+            (result, _) => result,
+        }
+    }
+
+    fn run_prepared(&self, prepared: &mut Prepared<StatementImpl<'_>>, stmt: &str) -> Result<StatementOutcome, ConnectionError> {
+        let mut outcome = StatementOutcome::default();
+        {
+            let mut next = prepared.execute(()).map_err(|e| {
+                error!("Statement execution failed: {}", e);
+                ConnectionError::QueryFailed(e.to_string())
+            })?;
+            let mut capture = self.captures.get(stmt.trim()).copied();
+            // Drain every result set so drivers returning several don't leave the statement busy
+            while let Some(mut cursor) = next {
+                outcome.result_sets += 1;
+                let mut captured = match capture.take() {
+                    Some(max_rows) => Some((max_rows, cursor.column_names()?.collect::<Result<Vec<String>, _>>()?)),
+                    None => None,
+                };
+                let mut buffer = TextRowSet::for_cursor(100, &mut cursor, Some(4096))?;
+                let mut row_set_cursor = cursor.bind_buffer(&mut buffer)?;
+                let mut rows: Vec<Vec<String>> = Vec::new();
+                while let Some(row_set) = row_set_cursor.fetch()? {
+                    let Some((max_rows, _)) = &captured else {
+                        continue;
+                    };
+                    for row_index in 0..row_set.num_rows().min(max_rows.saturating_sub(rows.len())) {
+                        rows.push(
+                            (0..row_set.num_cols())
+                                .map(|col_index| {
+                                    row_set
+                                        .at(col_index, row_index)
+                                        .map(|v| String::from_utf8_lossy(v).to_string())
+                                        .unwrap_or_else(|| "NULL".to_string())
+                                })
+                                .collect(),
+                        );
                     }
-                    debug!("Statement executed successfully with results");
                 }
-                Ok(None) => {
-                    debug!("Statement executed successfully (no results)");
-                }
-                Err(e) => {
-                    error!("Statement execution failed: {}", e);
-                    return Err(ConnectionError::QueryFailed(e.to_string()));
+                if let Some((_, columns)) = captured.take() {
+                    outcome.captured = Some(QueryResult { columns, rows });
                 }
+                let (cursor, _) = row_set_cursor.unbind()?;
+                next = cursor.more_results()?;
             }
         }
 
-        Ok(())
+        if outcome.result_sets == 0 {
+            outcome.rows_affected = prepared.row_count()?;
+            debug!("Statement executed successfully ({:?} rows affected)", outcome.rows_affected);
+        } else {
+            debug!("Statement executed successfully with {} result set(s)", outcome.result_sets);
+        }
+        Ok(outcome)
     }
 
     pub fn execute_transaction<F>(&mut self, operations: F) -> Result<(), ConnectionError>
@@ -139,6 +516,25 @@ impl<'a> DatabaseExecutor<'a> {
     {
         debug!("Starting transaction");
 
+        // With autocommit off the driver already holds a transaction open; commit once at the end
+        if self.commit_each {
+            self.commit_each = false;
+            let result = operations(self);
+            self.commit_each = true;
+            return match result {
+                Ok(()) => {
+                    self.connection.commit()?;
+                    info!("Transaction committed successfully");
+                    Ok(())
+                }
+                Err(e) => {
+                    error!("Transaction operations failed: {}, rolling back", e);
+                    self.connection.rollback()?;
+                    Err(ConnectionError::TransactionFailed(e.to_string()))
+                }
+            };
+        }
+
         // Begin transaction (most databases auto-commit by default)
         self.execute_query("BEGIN TRANSACTION").or_else(|_| {
             // Some databases use different syntax
@@ -172,10 +568,11 @@ impl<'a> DatabaseExecutor<'a> {
 
     pub fn query_single_value(&mut self, query: &str) -> Result<Option<String>, ConnectionError> {
         debug!("Querying single value: {}", query);
+        self.check_writable(query)?;
 
         let mut prepared = self
             .connection
-            .prepare(query)
+            .prepare(&self.tagged(query))
             .map_err(|e| ConnectionError::QueryFailed(e.to_string()))?;
 
         let mut cursor = prepared
@@ -186,14 +583,13 @@ impl<'a> DatabaseExecutor<'a> {
         let mut buffer = TextRowSet::for_cursor(1, &mut cursor, Some(4096))?;
         let mut row_set_cursor = cursor.bind_buffer(&mut buffer)?;
 
-        if let Some(row_set) = row_set_cursor.fetch()? {
-            if row_set.num_rows() > 0 {
-                if let Some(value) = row_set.at(0, 0) {
-                    let result = String::from_utf8_lossy(value).to_string();
-                    debug!("Query returned single value: {}", result);
-                    return Ok(Some(result));
-                }
-            }
+        if let Some(row_set) = row_set_cursor.fetch()?
+            && row_set.num_rows() > 0
+            && let Some(value) = row_set.at(0, 0)
+        {
+            let result = String::from_utf8_lossy(value).to_string();
+            debug!("Query returned single value: {}", result);
+            return Ok(Some(result));
         }
 
         debug!("Query returned no value");
@@ -202,10 +598,11 @@ impl<'a> DatabaseExecutor<'a> {
 
     pub fn query_rows(&mut self, query: &str) -> Result<Vec<Vec<String>>, ConnectionError> {
         debug!("Querying multiple rows: {}", query);
+        self.check_writable(query)?;
 
         let mut prepared = self
             .connection
-            .prepare(query)
+            .prepare(&self.tagged(query))
             .map_err(|e| ConnectionError::QueryFailed(e.to_string()))?;
 
         let mut cursor = prepared
@@ -234,5 +631,103 @@ impl<'a> DatabaseExecutor<'a> {
         debug!("Query returned {} rows", results.len());
         Ok(results)
     }
+
+    /// Fetches rows with each column bound to a buffer matching its ODBC type, so booleans,
+    /// integers and timestamps arrive typed instead of as driver-formatted text
+    pub fn query_typed(&mut self, query: &str) -> Result<Vec<TypedRow>, ConnectionError> {
+        debug!("Querying typed rows: {}", query);
+        self.check_writable(query)?;
+
+        let mut prepared = self
+            .connection
+            .prepare(&self.tagged(query))
+            .map_err(|e| ConnectionError::QueryFailed(e.to_string()))?;
+
+        let mut cursor = prepared
+            .execute(())
+            .map_err(|e| ConnectionError::QueryFailed(e.to_string()))?
+            .ok_or_else(|| ConnectionError::QueryFailed("Query returned no cursor".to_string()))?;
+
+        let mut descs = Vec::new();
+        for column in 1..=cursor.num_result_cols()? as u16 {
+            descs.push(typed::buffer_desc(cursor.col_data_type(column)?));
+        }
+        let buffer = ColumnarAnyBuffer::from_descs(100, descs);
+        let mut row_set_cursor = cursor.bind_buffer(buffer)?;
+        let mut results = Vec::new();
+
+        while let Some(row_set) = row_set_cursor.fetch()? {
+            for row_index in 0..row_set.num_rows() {
+                results.push(TypedRow(
+                    (0..row_set.num_cols())
+                        .map(|col_index| typed::read_value(row_set.column(col_index), row_index))
+                        .collect(),
+                ));
+            }
+        }
+
+        debug!("Query returned {} rows", results.len());
+        Ok(results)
+    }
+
+    /// Executes a single ad-hoc statement, returning its result set with column names,
+    /// or None for statements that produce no rows (DDL, INSERT, ...)
+    pub fn query_with_columns(&mut self, query: &str) -> Result<Option<QueryResult>, ConnectionError> {
+        debug!("Executing ad-hoc statement: {}", query);
+        self.check_writable(query)?;
+
+        let mut prepared = self
+            .connection
+            .prepare(&self.tagged(query))
+            .map_err(|e| ConnectionError::QueryFailed(e.to_string()))?;
+
+        let Some(mut cursor) = prepared
+            .execute(())
+            .map_err(|e| ConnectionError::QueryFailed(e.to_string()))?
+        else {
+            return Ok(None);
+        };
+
+        let columns = cursor.column_names()?.collect::<Result<Vec<String>, _>>()?;
+        let mut buffer = TextRowSet::for_cursor(100, &mut cursor, Some(4096))?;
+        let mut row_set_cursor = cursor.bind_buffer(&mut buffer)?;
+        let mut rows = Vec::new();
+
+        while let Some(row_set) = row_set_cursor.fetch()? {
+            for row_index in 0..row_set.num_rows() {
+                rows.push(
+                    (0..row_set.num_cols())
+                        .map(|col_index| {
+                            row_set
+                                .at(col_index, row_index)
+                                .map(|v| String::from_utf8_lossy(v).to_string())
+                                .unwrap_or_else(|| "NULL".to_string())
+                        })
+                        .collect(),
+                );
+            }
+        }
+
+        Ok(Some(QueryResult { columns, rows }))
+    }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redacts_credentials_in_connection_strings() {
+        assert_eq!(
+            redact_connection_string("Driver={PostgreSQL Unicode};Server=db;Uid=migrator;Pwd=hunter2;"),
+            "Driver={PostgreSQL Unicode};Server=db;Uid=migrator;Pwd=***;"
+        );
+        assert_eq!(redact_connection_string("DSN=prod;PASSWORD={a;b=c}"), "DSN=prod;PASSWORD=***");
+        assert_eq!(
+            redact_connection_string("Driver=Databricks;UID=token;PWD=dapi123;Auth_AccessToken=eyJ;Auth_Client_Secret=s3"),
+            "Driver=Databricks;UID=token;PWD=***;Auth_AccessToken=***;Auth_Client_Secret=***"
+        );
+        assert_eq!(redact_connection_string("Driver=SQLite3;Database=test.db"), "Driver=SQLite3;Database=test.db");
+    }
+}
@@ -0,0 +1,42 @@
+use super::ConnectionError;
+use log::{error, warn};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+
+/// Exit status of a run stopped by SIGINT or SIGTERM (128 + SIGINT, as shells report Ctrl-C)
+pub const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Installs the SIGINT/SIGTERM handler for `apply` and `rollback`. The first signal lets the
+/// in-flight statement finish and makes the next statement fail, so the migration's
+/// transaction is rolled back, the failure recorded and the locks released on the way out.
+/// A second signal exits immediately.
+pub fn install_interrupt_handler() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let installed = ctrlc::set_handler(|| {
+            if INTERRUPTED.swap(true, Ordering::SeqCst) {
+                error!("Interrupted again, exiting without cleaning up");
+                std::process::exit(INTERRUPTED_EXIT_CODE);
+            }
+            warn!("⚠️  Interrupted, stopping after the current statement (send the signal again to exit immediately)");
+        });
+        if let Err(e) = installed {
+            warn!("⚠️  Could not install the interrupt handler: {}", e);
+        }
+    });
+}
+
+/// Whether SIGINT or SIGTERM was received
+pub fn is_interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Fails once a signal was received, so no further statement is started
+pub(crate) fn check_interrupted() -> Result<(), ConnectionError> {
+    if is_interrupted() {
+        return Err(ConnectionError::Interrupted);
+    }
+    Ok(())
+}
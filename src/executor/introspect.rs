@@ -0,0 +1,233 @@
+use crate::dialects::DatabaseDialect;
+use crate::executor::{ConnectionError, ConnectionManager};
+use log::{debug, warn};
+
+/// Tables, columns and foreign keys of the current database, read through the
+/// dialect's catalog queries
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SchemaModel {
+    pub tables: Vec<TableInfo>,
+    pub foreign_keys: Vec<ForeignKey>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableInfo {
+    pub schema: String,
+    pub name: String,
+    pub columns: Vec<ColumnInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnInfo {
+    pub name: String,
+    pub data_type: String,
+    pub nullable: bool,
+    pub primary_key: bool,
+}
+
+/// A foreign key; composite keys list their columns pairwise in order
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForeignKey {
+    pub name: String,
+    pub schema: String,
+    pub table: String,
+    pub columns: Vec<String>,
+    pub referenced_schema: String,
+    pub referenced_table: String,
+    pub referenced_columns: Vec<String>,
+}
+
+impl SchemaModel {
+    /// Builds the model from the rows of [`DatabaseDialect::columns_sql`] and
+    /// [`DatabaseDialect::foreign_keys_sql`]. Rows are expected grouped by table.
+    pub fn from_rows(column_rows: &[Vec<String>], foreign_key_rows: &[Vec<String>]) -> Self {
+        let mut model = SchemaModel::default();
+
+        for row in column_rows.iter().filter(|row| row.len() >= 6) {
+            let column = ColumnInfo {
+                name: row[2].clone(),
+                data_type: row[3].clone(),
+                nullable: row[4].eq_ignore_ascii_case("YES"),
+                primary_key: row[5].trim() == "1",
+            };
+            match model.tables.last_mut() {
+                Some(table) if table.schema == row[0] && table.name == row[1] => table.columns.push(column),
+                _ => model.tables.push(TableInfo {
+                    schema: row[0].clone(),
+                    name: row[1].clone(),
+                    columns: vec![column],
+                }),
+            }
+        }
+
+        for row in foreign_key_rows.iter().filter(|row| row.len() >= 7) {
+            match model.foreign_keys.last_mut() {
+                Some(key) if key.name == row[0] && key.schema == row[1] && key.table == row[2] => {
+                    key.columns.push(row[3].clone());
+                    key.referenced_columns.push(row[6].clone());
+                }
+                _ => model.foreign_keys.push(ForeignKey {
+                    name: row[0].clone(),
+                    schema: row[1].clone(),
+                    table: row[2].clone(),
+                    columns: vec![row[3].clone()],
+                    referenced_schema: row[4].clone(),
+                    referenced_table: row[5].clone(),
+                    referenced_columns: vec![row[6].clone()],
+                }),
+            }
+        }
+
+        model
+    }
+
+    /// Differences from `self` to `other`, one line per changed table, column or
+    /// foreign key; empty when the schemas match
+    pub fn differences(&self, other: &SchemaModel) -> Vec<String> {
+        let mut differences = Vec::new();
+
+        for table in &self.tables {
+            let Some(counterpart) = other.table(&table.schema, &table.name) else {
+                differences.push(format!("table {} is missing", table.name));
+                continue;
+            };
+            for column in &table.columns {
+                match counterpart.columns.iter().find(|c| c.name == column.name) {
+                    None => differences.push(format!("column {}.{} is missing", table.name, column.name)),
+                    Some(c) if c != column => differences.push(format!(
+                        "column {}.{} changed from {} to {}",
+                        table.name,
+                        column.name,
+                        column.describe(),
+                        c.describe()
+                    )),
+                    Some(_) => {}
+                }
+            }
+            for column in &counterpart.columns {
+                if !table.columns.iter().any(|c| c.name == column.name) {
+                    differences.push(format!("column {}.{} was added", table.name, column.name));
+                }
+            }
+        }
+        for table in &other.tables {
+            if self.table(&table.schema, &table.name).is_none() {
+                differences.push(format!("table {} was added", table.name));
+            }
+        }
+
+        for key in &self.foreign_keys {
+            if !other.foreign_keys.iter().any(|k| k.same_columns(key)) {
+                differences.push(format!("foreign key {} on {} is missing", key.name, key.table));
+            }
+        }
+        for key in &other.foreign_keys {
+            if !self.foreign_keys.iter().any(|k| k.same_columns(key)) {
+                differences.push(format!("foreign key {} on {} was added", key.name, key.table));
+            }
+        }
+
+        differences
+    }
+
+    fn table(&self, schema: &str, name: &str) -> Option<&TableInfo> {
+        self.tables.iter().find(|t| t.schema == schema && t.name == name)
+    }
+}
+
+impl ColumnInfo {
+    fn describe(&self) -> String {
+        format!(
+            "{}{}{}",
+            self.data_type,
+            if self.nullable { " NULL" } else { " NOT NULL" },
+            if self.primary_key { " PRIMARY KEY" } else { "" }
+        )
+    }
+}
+
+impl ForeignKey {
+    /// Compares what the key links, ignoring its name (SQLite names are positional)
+    fn same_columns(&self, other: &ForeignKey) -> bool {
+        self.schema == other.schema
+            && self.table == other.table
+            && self.columns == other.columns
+            && self.referenced_table == other.referenced_table
+            && self.referenced_columns == other.referenced_columns
+    }
+}
+
+/// Reads the schema of the database behind `conn`. Foreign keys are optional:
+/// when the catalog query fails (e.g. on engines without constraints) they are left out.
+pub fn introspect_schema(manager: &ConnectionManager, conn: &str, dialect: &dyn DatabaseDialect) -> Result<SchemaModel, ConnectionError> {
+    let mut executor = manager.executor(conn)?;
+
+    let column_rows = executor.query_rows(&dialect.columns_sql())?;
+    let foreign_key_rows = executor
+        .query_rows(&dialect.foreign_keys_sql())
+        .unwrap_or_else(|e| {
+            warn!("Could not read foreign keys, the diagram will have no relationships: {}", e);
+            Vec::new()
+        });
+
+    let model = SchemaModel::from_rows(&column_rows, &foreign_key_rows);
+    debug!(
+        "Introspected {} tables and {} foreign keys",
+        model.tables.len(),
+        model.foreign_keys.len()
+    );
+    Ok(model)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_groups_columns_and_composite_keys() {
+        let columns = vec![
+            row(&["public", "orders", "id", "integer", "NO", "1"]),
+            row(&["public", "orders", "user_id", "integer", "YES", "0"]),
+            row(&["public", "users", "id", "integer", "NO", "1"]),
+        ];
+        let keys = vec![
+            row(&["orders_user_fk", "public", "orders", "user_id", "public", "users", "id"]),
+            row(&["orders_user_fk", "public", "orders", "tenant_id", "public", "users", "tenant_id"]),
+        ];
+
+        let model = SchemaModel::from_rows(&columns, &keys);
+        assert_eq!(model.tables.len(), 2);
+        assert_eq!(model.tables[0].columns.len(), 2);
+        assert!(model.tables[0].columns[0].primary_key);
+        assert!(model.tables[0].columns[1].nullable);
+        assert_eq!(model.foreign_keys.len(), 1);
+        assert_eq!(model.foreign_keys[0].columns, vec!["user_id", "tenant_id"]);
+    }
+
+    #[test]
+    fn test_differences_between_snapshots() {
+        let before = SchemaModel::from_rows(&[row(&["main", "users", "id", "INTEGER", "NO", "1"])], &[]);
+        let after = SchemaModel::from_rows(
+            &[
+                row(&["main", "users", "id", "INTEGER", "YES", "1"]),
+                row(&["main", "users", "email", "TEXT", "YES", "0"]),
+                row(&["main", "posts", "id", "INTEGER", "NO", "1"]),
+            ],
+            &[],
+        );
+
+        assert!(before.differences(&before).is_empty());
+        assert_eq!(
+            before.differences(&after),
+            vec![
+                "column users.id changed from INTEGER NOT NULL PRIMARY KEY to INTEGER NULL PRIMARY KEY",
+                "column users.email was added",
+                "table posts was added",
+            ]
+        );
+    }
+}
@@ -1,6 +1,19 @@
+pub mod cancel;
 pub mod connection;
+pub mod interrupt;
+pub mod introspect;
+pub mod read_only;
 pub mod runner;
+pub mod session;
+pub mod splitter;
+pub mod stream;
+pub mod typed;
 
-pub use connection::{ConnectionManager, ConnectionError, DatabaseExecutor};
+pub use connection::{redact_connection_string, ConnectionAttributes, ConnectionManager, ConnectionError, ConnectionSettings, DatabaseExecutor, QueryResult, StatementOutcome};
+pub use interrupt::{install_interrupt_handler, is_interrupted};
+pub use introspect::{introspect_schema, SchemaModel};
+pub use session::SessionSettings;
+pub use splitter::StatementSplitting;
+pub use stream::StatementReader;
 
 // TODO: Add exports when structs are implemented
\ No newline at end of file
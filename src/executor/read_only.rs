@@ -0,0 +1,122 @@
+/// Statement keywords that only read; everything else is refused by executors in
+/// read-only mode (see [`super::ConnectionSettings::read_only`])
+const READ_KEYWORDS: &[&str] = &["SELECT", "WITH", "SHOW", "EXPLAIN", "DESCRIBE", "DESC", "VALUES", "PRAGMA"];
+
+/// Keywords that make a query write when they appear anywhere in it, e.g. a data-modifying
+/// CTE (`WITH d AS (DELETE ... RETURNING *) SELECT ...`) or `SELECT ... INTO`
+const WRITE_KEYWORDS: &[&str] =
+    &["INSERT", "UPDATE", "DELETE", "MERGE", "UPSERT", "INTO", "CREATE", "DROP", "ALTER", "TRUNCATE", "GRANT", "REVOKE", "COPY"];
+
+/// Returns true if `statement` only reads: it starts with a query keyword, is not an
+/// `EXPLAIN ANALYZE` (which runs the statement), for `PRAGMA` does not assign, and has no
+/// write keyword outside string literals, quoted identifiers and comments. Leading
+/// comments and parentheses are skipped.
+pub fn is_read_statement(statement: &str) -> bool {
+    let mut rest = statement.trim_start();
+    loop {
+        if let Some(comment) = rest.strip_prefix("--") {
+            rest = comment.split_once('\n').map_or("", |(_, after)| after).trim_start();
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            rest = comment.split_once("*/").map_or("", |(_, after)| after).trim_start();
+        } else if let Some(inner) = rest.strip_prefix('(') {
+            rest = inner.trim_start();
+        } else {
+            break;
+        }
+    }
+    let keyword: String = rest.chars().take_while(|c| c.is_ascii_alphabetic()).collect::<String>().to_uppercase();
+    match keyword.as_str() {
+        "PRAGMA" => return !rest.contains('='),
+        "EXPLAIN" => {
+            let options = rest[keyword.len()..].trim_start().trim_start_matches('(').trim_start();
+            return !options.to_uppercase().starts_with("ANALYZE");
+        }
+        _ => {}
+    }
+    READ_KEYWORDS.contains(&keyword.as_str()) && !keywords(rest).any(|word| WRITE_KEYWORDS.contains(&word.as_str()))
+}
+
+/// The upper-cased words of `sql` outside string literals, quoted identifiers, dollar
+/// quotes and comments
+fn keywords(sql: &str) -> impl Iterator<Item = String> + '_ {
+    let mut words = Vec::new();
+    let mut chars = sql.char_indices().peekable();
+    let mut word = String::new();
+    while let Some((at, c)) = chars.next() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            word.push(c.to_ascii_uppercase());
+            continue;
+        }
+        if !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        let rest = &sql[at..];
+        let skip = match c {
+            '\'' | '"' | '`' => closing(rest, c),
+            '[' => closing(rest, ']'),
+            '-' if rest.starts_with("--") => rest.find('\n').unwrap_or(rest.len()),
+            '/' if rest.starts_with("/*") => rest[2..].find("*/").map_or(rest.len(), |end| end + 4),
+            '$' => match rest[1..].find('$') {
+                Some(end) if rest[1..=end].chars().all(|c| c.is_ascii_alphanumeric() || c == '_') => {
+                    let tag = &rest[..end + 2];
+                    rest[tag.len()..].find(tag).map_or(rest.len(), |body| tag.len() + body + tag.len())
+                }
+                _ => 1,
+            },
+            _ => 1,
+        };
+        // Skip what was consumed beyond this character
+        while chars.peek().is_some_and(|(next, _)| *next < at + skip) {
+            chars.next();
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words.into_iter()
+}
+
+/// Length of the quoted text at the start of `rest`, up to and including the closing
+/// `quote`; a doubled quote is an escaped one
+fn closing(rest: &str, quote: char) -> usize {
+    let mut at = 1;
+    while let Some(offset) = rest[at..].find(quote) {
+        at += offset + quote.len_utf8();
+        if !rest[at..].starts_with(quote) || quote == ']' {
+            return at;
+        }
+        at += quote.len_utf8();
+    }
+    rest.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_queries_are_read_statements() {
+        assert!(is_read_statement("SELECT COUNT(*) FROM schema_migrations"));
+        assert!(is_read_statement("  -- latest\n/* v */ (select 1) UNION (select 2)"));
+        assert!(is_read_statement("EXPLAIN ALTER TABLE users ADD COLUMN age INT"));
+        assert!(!is_read_statement("EXPLAIN ANALYZE DELETE FROM users"));
+        assert!(is_read_statement("PRAGMA table_info(users)"));
+        assert!(!is_read_statement("PRAGMA user_version = 3"));
+        assert!(!is_read_statement("CREATE TABLE schema_migrations (id INT)"));
+        assert!(!is_read_statement("insert into users values (1)"));
+        assert!(!is_read_statement("SELECTION"));
+        assert!(!is_read_statement("EXPLAIN (ANALYZE) DELETE FROM users"));
+    }
+
+    #[test]
+    fn test_writes_hidden_in_queries_are_refused() {
+        assert!(!is_read_statement("WITH d AS (DELETE FROM users RETURNING *) SELECT * FROM d"));
+        assert!(!is_read_statement("with moved as (update users set active = false returning id) select count(*) from moved"));
+        assert!(!is_read_statement("SELECT * INTO users_copy FROM users"));
+        assert!(!is_read_statement("SELECT id FROM users INTO OUTFILE '/tmp/users'"));
+        assert!(is_read_statement("WITH recent AS (SELECT * FROM users) SELECT id FROM recent"));
+        assert!(is_read_statement("SELECT 'DELETE FROM users' AS example, \"update\" FROM notes -- insert later"));
+        assert!(is_read_statement("SELECT $$ drop table users $$, [into], updated_at /* delete */ FROM notes"));
+        assert!(is_read_statement("SELECT 'it''s; delete' FROM notes"));
+    }
+}
@@ -0,0 +1,155 @@
+use super::connection::RUN_TAG;
+use crate::dialects::DatabaseDialect;
+use crate::model::Config;
+use log::{debug, warn};
+
+/// What a connection to one target runs when it opens, and the catalog and schema its
+/// schema_migrations lives in
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionSettings {
+    pub statements: Vec<String>,
+    /// Set only when the dialect could select it with a session statement
+    pub catalog: Option<String>,
+    pub schema: Option<String>,
+}
+
+impl SessionSettings {
+    /// The session of the target described by `config`: its catalog (`catalog` overrides
+    /// the dialect's default), schema, isolation level, read-only mode, query tag of run
+    /// `run_id`, the dialect's own setup and `database.session_setup`, in that order.
+    /// Settings the dialect cannot express are warned about and left out.
+    pub fn for_config(
+        config: &Config,
+        dialect: Option<&dyn DatabaseDialect>,
+        catalog: Option<&str>,
+        read_only: bool,
+        run_id: &str,
+    ) -> Self {
+        let name = &config.migrations.dialect;
+        let mut settings = Self::default();
+        let catalog = catalog.map(str::to_string).or_else(|| dialect.and_then(|d| d.default_catalog(&config.dialect)));
+        if let Some(catalog) = catalog {
+            match dialect.and_then(|d| d.use_catalog_sql(&catalog)) {
+                Some(sql) => {
+                    settings.statements.push(sql);
+                    settings.catalog = Some(catalog);
+                }
+                None => warn!("⚠️  The {} dialect has no catalogs, ignoring --catalog", name),
+            }
+        }
+        let schema = config.database.schema.clone().or_else(|| dialect.and_then(|d| d.default_schema(&config.dialect)));
+        if let Some(schema) = schema {
+            match dialect.and_then(|d| d.use_schema_sql(&schema)) {
+                Some(sql) => {
+                    settings.statements.push(sql);
+                    settings.schema = Some(schema);
+                }
+                None => warn!("⚠️  The {} dialect cannot select a schema, ignoring database.schema", name),
+            }
+        }
+        if let Some(level) = config.database.odbc.isolation_level {
+            match dialect.and_then(|d| d.isolation_level_sql(level.as_sql())) {
+                Some(sql) => settings.statements.push(sql),
+                None => warn!("⚠️  The {} dialect cannot set an isolation level, ignoring database.odbc.isolation_level", name),
+            }
+        }
+        if read_only {
+            match dialect.and_then(|d| d.read_only_sql()) {
+                Some(sql) => settings.statements.push(sql),
+                None => debug!("The {} dialect cannot make sessions read-only; only queries are sent", name),
+            }
+        }
+        if config.database.query_tag.session {
+            match dialect.and_then(|d| d.query_tag_sql(RUN_TAG, run_id)) {
+                Some(sql) => settings.statements.push(sql),
+                None => warn!("⚠️  The {} dialect has no session query tags, ignoring database.query_tag.session", name),
+            }
+        }
+        if let Some(dialect) = dialect {
+            settings.statements.extend(dialect.session_setup_sql(&config.dialect));
+        }
+        settings.statements.extend(config.database.session_setup.iter().cloned());
+        settings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialects;
+
+    #[test]
+    fn test_session_settings_follow_the_targets_config() {
+        let mut config = Config::default();
+        config.migrations.dialect = "postgres".to_string();
+        config.database.schema = Some("billing".to_string());
+        config.database.session_setup = vec!["SET ROLE migrator".to_string()];
+        let postgres = dialects::get_dialect("postgres").unwrap();
+
+        let settings = SessionSettings::for_config(&config, Some(postgres.as_ref()), None, false, "run");
+        assert_eq!(settings.statements, vec!["SET search_path TO \"billing\"", "SET ROLE migrator"]);
+        assert_eq!(settings.schema.as_deref(), Some("billing"));
+        // Postgres has no catalogs, so --catalog is not kept for qualifying schema_migrations
+        let settings = SessionSettings::for_config(&config, Some(postgres.as_ref()), Some("main"), true, "run");
+        assert_eq!(settings.catalog, None);
+        assert_eq!(
+            settings.statements,
+            vec![
+                "SET search_path TO \"billing\"",
+                "SET SESSION CHARACTERISTICS AS TRANSACTION READ ONLY",
+                "SET ROLE migrator"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_session_settings_without_a_dialect_keep_only_configured_statements() {
+        let mut config = Config::default();
+        config.database.schema = Some("billing".to_string());
+        config.database.session_setup = vec!["SET ROLE migrator".to_string()];
+
+        let settings = SessionSettings::for_config(&config, None, Some("main"), true, "run");
+        assert_eq!(settings, SessionSettings { statements: vec!["SET ROLE migrator".to_string()], catalog: None, schema: None });
+    }
+    #[test]
+    fn test_unity_catalog_settings_place_schema_migrations() {
+        let config: Config = toml::from_str(
+            r#"
+[migrations]
+dialect = "databricks"
+
+[dialect.databricks]
+catalog = "main"
+schema = "ops"
+"#,
+        )
+        .unwrap();
+        let databricks = dialects::get_dialect("databricks").unwrap();
+        let located = |settings: &SessionSettings| {
+            crate::tracker::TrackerSql::new(databricks.clone())
+                .with_location(settings.catalog.clone(), settings.schema.clone())
+                .count()
+        };
+
+        let settings = SessionSettings::for_config(&config, Some(databricks.as_ref()), None, false, "run");
+        assert_eq!(settings.statements, vec!["USE CATALOG `main`", "USE SCHEMA `ops`"]);
+        assert_eq!(located(&settings), "SELECT COUNT(*) FROM `main`.`ops`.`schema_migrations`");
+
+        // --catalog wins over [dialect.databricks] catalog, database.schema over its schema
+        let mut overridden = config.clone();
+        overridden.database.schema = Some("billing".to_string());
+        let settings = SessionSettings::for_config(&overridden, Some(databricks.as_ref()), Some("dev"), false, "run");
+        assert_eq!(settings.statements, vec!["USE CATALOG `dev`", "USE SCHEMA `billing`"]);
+        assert_eq!(located(&settings), "SELECT COUNT(*) FROM `dev`.`billing`.`schema_migrations`");
+
+        // Without a schema the catalog is only selected, so the table name stays bare
+        let mut catalog_only = config.clone();
+        catalog_only.dialect.databricks.as_mut().unwrap().schema = None;
+        let settings = SessionSettings::for_config(&catalog_only, Some(databricks.as_ref()), None, false, "run");
+        assert_eq!(settings.statements, vec!["USE CATALOG `main`"]);
+        assert_eq!(located(&settings), "SELECT COUNT(*) FROM `schema_migrations`");
+
+        // Introspection follows the selected catalog
+        assert!(databricks.columns_sql().contains("c.table_catalog = current_catalog()"));
+    }
+}
@@ -0,0 +1,221 @@
+/// How migration SQL is cut into the statements sent to the driver one at a time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StatementSplitting {
+    /// Every `;` ends a statement
+    #[default]
+    Semicolon,
+    /// MySQL client style: `DELIMITER $$` lines change the terminator, so procedure
+    /// bodies can contain `;`. The DELIMITER lines themselves are not sent.
+    Delimiter,
+    /// Spark/Databricks SQL scripting: `;` inside `BEGIN ... END` blocks does not end
+    /// the statement
+    BeginEnd,
+}
+
+/// Splits SQL fed to it line by line. `--` lines and blank lines are dropped.
+/// In [`StatementSplitting::Semicolon`] mode lines are joined with spaces; the other
+/// modes keep line breaks so trailing `--` comments in routine bodies stay harmless,
+/// and ignore terminators inside quotes and comments.
+#[derive(Debug)]
+pub struct StatementSplitter {
+    mode: StatementSplitting,
+    delimiter: String,
+    current: String,
+    quote: Option<char>,
+    /// Open BEGIN/CASE blocks
+    depth: usize,
+    pending_begin: bool,
+    pending_end: bool,
+}
+
+impl StatementSplitter {
+    pub fn new(mode: StatementSplitting) -> Self {
+        Self {
+            mode,
+            delimiter: ";".to_string(),
+            current: String::new(),
+            quote: None,
+            depth: 0,
+            pending_begin: false,
+            pending_end: false,
+        }
+    }
+
+    /// Splits a whole block of SQL
+    pub fn split(mut self, sql: &str) -> Vec<String> {
+        let mut statements: Vec<String> = sql.lines().flat_map(|line| self.push_line(line)).collect();
+        statements.extend(self.finish());
+        statements
+    }
+
+    /// Consumes one line, returning the statements it completes
+    pub fn push_line(&mut self, line: &str) -> Vec<String> {
+        let line = line.trim().trim_start_matches('\u{feff}');
+        if self.quote.is_none() && (line.is_empty() || line.starts_with("--")) {
+            return Vec::new();
+        }
+        if self.mode == StatementSplitting::Delimiter
+            && self.quote.is_none()
+            && self.current.trim().is_empty()
+            && let Some(delimiter) = delimiter_command(line)
+        {
+            self.delimiter = delimiter.to_string();
+            return Vec::new();
+        }
+
+        if !self.current.is_empty() {
+            self.current.push(if self.mode == StatementSplitting::Semicolon { ' ' } else { '\n' });
+        }
+        let start = self.current.len();
+        self.current.push_str(line);
+
+        if self.mode == StatementSplitting::Semicolon {
+            let mut segments: Vec<String> = self.current.split(';').map(str::to_string).collect();
+            self.current = segments.pop().unwrap_or_default();
+            return segments
+                .into_iter()
+                .map(|statement| statement.trim().to_string())
+                .filter(|statement| !statement.is_empty())
+                .collect();
+        }
+        self.scan(start)
+    }
+
+    /// The statement left over at the end of the input, if any
+    pub fn finish(&mut self) -> Option<String> {
+        let statement = std::mem::take(&mut self.current).trim().to_string();
+        (!statement.is_empty()).then_some(statement)
+    }
+
+    /// Looks for terminators in the text appended at `start`, tracking quotes and blocks
+    fn scan(&mut self, start: usize) -> Vec<String> {
+        let text = std::mem::take(&mut self.current);
+        let mut statements = Vec::new();
+        let mut from = 0;
+        let mut word = String::new();
+        let mut chars = text[start..].char_indices().map(|(i, c)| (i + start, c)).peekable();
+
+        while let Some((i, c)) = chars.next() {
+            if let Some(quote) = self.quote {
+                if c == '\\' && quote != '`' && self.mode == StatementSplitting::Delimiter {
+                    chars.next();
+                } else if c == quote {
+                    self.quote = None;
+                }
+                continue;
+            }
+            if c.is_alphanumeric() || c == '_' {
+                word.push(c);
+                continue;
+            }
+            if !word.is_empty() {
+                self.keyword(&word);
+                word.clear();
+            }
+
+            match c {
+                '\'' | '"' | '`' => self.quote = Some(c),
+                '-' if chars.peek().is_some_and(|(_, next)| *next == '-') => break,
+                _ if self.mode == StatementSplitting::Delimiter && text[i..].starts_with(&self.delimiter) => {
+                    push_statement(&mut statements, &text[from..i]);
+                    from = i + self.delimiter.len();
+                    while chars.peek().is_some_and(|(next, _)| *next < from) {
+                        chars.next();
+                    }
+                }
+                ';' if self.mode == StatementSplitting::BeginEnd => {
+                    if self.pending_end {
+                        self.depth = self.depth.saturating_sub(1);
+                    }
+                    // A bare `BEGIN;` starts a transaction, not a block
+                    self.pending_begin = false;
+                    self.pending_end = false;
+                    if self.depth == 0 {
+                        push_statement(&mut statements, &text[from..i]);
+                        from = i + 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+        if !word.is_empty() {
+            self.keyword(&word);
+        }
+
+        self.current = text[from..].to_string();
+        statements
+    }
+
+    /// Tracks block nesting for [`StatementSplitting::BeginEnd`]. `END IF`, `END WHILE` and
+    /// similar close constructs that never opened a block; `CASE` expressions do open one.
+    fn keyword(&mut self, word: &str) {
+        if self.mode != StatementSplitting::BeginEnd {
+            return;
+        }
+        let word = word.to_ascii_uppercase();
+        if std::mem::take(&mut self.pending_end) {
+            match word.as_str() {
+                "IF" | "WHILE" | "LOOP" | "REPEAT" | "FOR" => return,
+                "CASE" => {
+                    self.depth = self.depth.saturating_sub(1);
+                    return;
+                }
+                _ => self.depth = self.depth.saturating_sub(1),
+            }
+        }
+        if std::mem::take(&mut self.pending_begin) {
+            if matches!(word.as_str(), "TRANSACTION" | "WORK") {
+                return;
+            }
+            self.depth += 1;
+        }
+        match word.as_str() {
+            "BEGIN" => self.pending_begin = true,
+            "CASE" => self.depth += 1,
+            "END" => self.pending_end = true,
+            _ => {}
+        }
+    }
+}
+
+fn push_statement(statements: &mut Vec<String>, statement: &str) {
+    let statement = statement.trim();
+    if !statement.is_empty() {
+        statements.push(statement.to_string());
+    }
+}
+
+/// The new terminator of a `DELIMITER $$` line
+fn delimiter_command(line: &str) -> Option<&str> {
+    let (command, delimiter) = line.split_once(char::is_whitespace)?;
+    let delimiter = delimiter.trim();
+    (command.eq_ignore_ascii_case("DELIMITER") && !delimiter.is_empty()).then_some(delimiter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_routine_bodies_stay_in_one_statement() {
+        let mysql = "DELIMITER $$\nCREATE PROCEDURE touch(IN id INT)\nBEGIN\n  UPDATE t SET v = ';' WHERE t.id = id; -- keep\n  SELECT 1;\nEND$$\nDELIMITER ;\nCALL touch(1);";
+        assert_eq!(
+            StatementSplitter::new(StatementSplitting::Delimiter).split(mysql),
+            vec![
+                "CREATE PROCEDURE touch(IN id INT)\nBEGIN\nUPDATE t SET v = ';' WHERE t.id = id; -- keep\nSELECT 1;\nEND",
+                "CALL touch(1)",
+            ]
+        );
+
+        let databricks = "CREATE FUNCTION f(x INT) RETURNS INT\nBEGIN\n  IF x > 0 THEN\n    RETURN CASE WHEN x > 9 THEN 9 ELSE x END;\n  END IF;\n  RETURN 0;\nEND;\nSELECT f(1);";
+        let statements = StatementSplitter::new(StatementSplitting::BeginEnd).split(databricks);
+        assert_eq!(statements.len(), 2);
+        assert!(statements[0].starts_with("CREATE FUNCTION") && statements[0].ends_with("RETURN 0;\nEND"));
+        assert_eq!(statements[1], "SELECT f(1)");
+
+        assert_eq!(
+            StatementSplitter::new(StatementSplitting::Semicolon).split("CREATE TABLE t (\n  id INT\n);\nINSERT INTO t VALUES (1)"),
+            vec!["CREATE TABLE t ( id INT )", "INSERT INTO t VALUES (1)"]
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use super::splitter::StatementSplitter;
+use crate::model::migration::is_section_marker;
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+
+/// Reads SQL statements one at a time from a migration file, so large data migrations
+/// never have to be held in memory. Splitting matches the in-memory executor: `--` lines
+/// are dropped and statements end where the given [`StatementSplitter`] (the
+/// executor's, see [`super::DatabaseExecutor::splitter`]) decides. Reading stops at a
+/// down-section marker that follows an up-section marker.
+pub struct StatementReader<R: BufRead> {
+    reader: R,
+    line: String,
+    splitter: StatementSplitter,
+    ready: VecDeque<String>,
+    in_up_section: bool,
+    finished: bool,
+}
+
+impl<R: BufRead> StatementReader<R> {
+    pub fn new(reader: R, splitter: StatementSplitter) -> Self {
+        Self {
+            reader,
+            line: String::new(),
+            splitter,
+            ready: VecDeque::new(),
+            in_up_section: false,
+            finished: false,
+        }
+    }
+
+    /// Consumes the next line, queueing any statements it completes.
+    /// Returns false once the up section is exhausted.
+    fn read_line(&mut self) -> io::Result<bool> {
+        self.line.clear();
+        if self.reader.read_line(&mut self.line)? == 0 {
+            return Ok(false);
+        }
+
+        let line = self.line.trim().trim_start_matches('\u{feff}');
+        if self.in_up_section && is_section_marker(line, true) {
+            return Ok(false);
+        }
+        if is_section_marker(line, false) {
+            self.in_up_section = true;
+        }
+        self.ready.extend(self.splitter.push_line(line));
+        Ok(true)
+    }
+}
+
+impl<R: BufRead> Iterator for StatementReader<R> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(statement) = self.ready.pop_front() {
+                return Some(Ok(statement));
+            }
+            if self.finished {
+                return None;
+            }
+            match self.read_line() {
+                Ok(true) => {}
+                Ok(false) => {
+                    self.finished = true;
+                    self.ready.extend(self.splitter.finish());
+                }
+                Err(e) => {
+                    self.finished = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statement_reader_splits_and_stops_at_down_marker() {
+        let sql = "-- UP\n-- downloaded seed data\nCREATE TABLE t (\n  id INT\n);\nINSERT INTO t VALUES (1); INSERT INTO t VALUES (2)\n-- DOWN\nDROP TABLE t;\n";
+        let statements: Vec<String> = StatementReader::new(sql.as_bytes(), StatementSplitter::new(Default::default()))
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            statements,
+            vec![
+                "CREATE TABLE t ( id INT )",
+                "INSERT INTO t VALUES (1)",
+                "INSERT INTO t VALUES (2)",
+            ]
+        );
+    }
+}
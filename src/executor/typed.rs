@@ -0,0 +1,135 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use odbc_api::buffers::{AnySlice, BufferDesc};
+use odbc_api::sys::Timestamp;
+use odbc_api::{Bit, DataType};
+
+/// Longest text value fetched per cell, matching the text row sets used elsewhere
+const MAX_TEXT_LEN: usize = 4096;
+
+/// A cell fetched into a buffer chosen from its column's ODBC type
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Timestamp(NaiveDateTime),
+    Text(String),
+}
+
+/// A row of [`SqlValue`]s with accessors that convert between compatible types
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedRow(pub Vec<SqlValue>);
+
+impl TypedRow {
+    /// The value rendered as text; None only for NULL or a missing column
+    pub fn get_string(&self, index: usize) -> Option<String> {
+        match self.0.get(index)? {
+            SqlValue::Null => None,
+            SqlValue::Bool(value) => Some(value.to_string()),
+            SqlValue::Int(value) => Some(value.to_string()),
+            SqlValue::Float(value) => Some(value.to_string()),
+            SqlValue::Timestamp(value) => Some(value.format("%Y-%m-%d %H:%M:%S%.f").to_string()),
+            SqlValue::Text(value) => Some(value.clone()),
+        }
+    }
+
+    /// Integer columns, or text holding an integer (drivers that report expressions as text)
+    pub fn get_i64(&self, index: usize) -> Option<i64> {
+        match self.0.get(index)? {
+            SqlValue::Int(value) => Some(*value),
+            SqlValue::Bool(value) => Some(i64::from(*value)),
+            SqlValue::Text(value) => value.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Bit columns, or integers where any non-zero value is true
+    pub fn get_bool(&self, index: usize) -> Option<bool> {
+        match self.0.get(index)? {
+            SqlValue::Bool(value) => Some(*value),
+            _ => self.get_i64(index).map(|value| value != 0),
+        }
+    }
+
+    /// Timestamp columns, or text in the ODBC canonical `YYYY-MM-DD HH:MM:SS[.fff]` form.
+    /// Values are taken to be UTC.
+    pub fn get_datetime(&self, index: usize) -> Option<DateTime<Utc>> {
+        match self.0.get(index)? {
+            SqlValue::Timestamp(value) => Some(value.and_utc()),
+            SqlValue::Text(value) => NaiveDateTime::parse_from_str(value.trim(), "%Y-%m-%d %H:%M:%S%.f")
+                .ok()
+                .map(|value| value.and_utc()),
+            _ => None,
+        }
+    }
+}
+
+/// Buffer a column of `data_type` is fetched into. Types without a typed
+/// [`SqlValue`] are fetched as text.
+pub(crate) fn buffer_desc(data_type: DataType) -> BufferDesc {
+    match data_type {
+        DataType::Bit => BufferDesc::Bit { nullable: true },
+        DataType::TinyInt | DataType::SmallInt | DataType::Integer | DataType::BigInt => {
+            BufferDesc::I64 { nullable: true }
+        }
+        DataType::Numeric { precision, scale: 0 } | DataType::Decimal { precision, scale: 0 } if precision < 19 => {
+            BufferDesc::I64 { nullable: true }
+        }
+        DataType::Real | DataType::Float { .. } | DataType::Double => BufferDesc::F64 { nullable: true },
+        DataType::Timestamp { .. } => BufferDesc::Timestamp { nullable: true },
+        _ => BufferDesc::Text { max_str_len: MAX_TEXT_LEN },
+    }
+}
+
+/// Reads the value at `row` from a column bound with a [`buffer_desc`] buffer
+pub(crate) fn read_value(column: AnySlice<'_>, row: usize) -> SqlValue {
+    let value = match column {
+        AnySlice::NullableBit(values) => values.get(row).map(|bit: &Bit| SqlValue::Bool(bit.0 != 0)),
+        AnySlice::NullableI64(values) => values.get(row).map(|value| SqlValue::Int(*value)),
+        AnySlice::NullableF64(values) => values.get(row).map(|value| SqlValue::Float(*value)),
+        AnySlice::NullableTimestamp(values) => values.get(row).and_then(|value| timestamp(value).map(SqlValue::Timestamp)),
+        AnySlice::Text(values) => values
+            .get(row)
+            .map(|value| SqlValue::Text(String::from_utf8_lossy(value).to_string())),
+        _ => None,
+    };
+    value.unwrap_or(SqlValue::Null)
+}
+
+fn timestamp(value: &Timestamp) -> Option<NaiveDateTime> {
+    NaiveDate::from_ymd_opt(value.year.into(), value.month.into(), value.day.into())?.and_hms_nano_opt(
+        value.hour.into(),
+        value.minute.into(),
+        value.second.into(),
+        value.fraction,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accessors_convert_between_compatible_types() {
+        let row = TypedRow(vec![
+            SqlValue::Bool(true),
+            SqlValue::Text("0".to_string()),
+            SqlValue::Int(42),
+            SqlValue::Text("2024-03-01 12:30:00.250".to_string()),
+            SqlValue::Null,
+            SqlValue::Text("yes".to_string()),
+        ]);
+
+        assert_eq!(row.get_bool(0), Some(true));
+        assert_eq!(row.get_bool(1), Some(false));
+        assert_eq!(row.get_i64(2), Some(42));
+        assert_eq!(
+            row.get_datetime(3).unwrap().to_rfc3339(),
+            "2024-03-01T12:30:00.250+00:00"
+        );
+        assert_eq!(row.get_string(4), None);
+        assert_eq!(row.get_bool(5), None);
+        assert_eq!(row.get_string(9), None);
+    }
+}
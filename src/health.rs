@@ -0,0 +1,186 @@
+//! Migration health for an application's own health endpoint.
+//!
+//! ```no_run
+//! let report = deriddl_rs::health::check(&std::env::var("DATABASE_URL").unwrap(), "migrations");
+//! let status = if report.is_healthy() { 200 } else { 503 };
+//! println!("{} {}", status, serde_json::to_string(&report).unwrap());
+//! ```
+//!
+//! Nothing is printed: details only go to the `log` facade at debug level, so the host
+//! application's logger decides what to show. The database is only read.
+
+use crate::executor::ConnectionError;
+use crate::model::{Migration, MigrationType};
+use crate::orchestrator::migration_loader::LoaderOptions;
+use crate::orchestrator::MigrationLoader;
+use crate::tracker::version_store::AppliedMigration;
+use crate::tracker::{schema_init, Target};
+use serde::Serialize;
+
+/// Overall state, from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthState {
+    /// Every migration is applied and matches its file
+    Up,
+    /// Migrations are pending, or applied ones were modified or deleted since
+    Degraded,
+    /// A migration failed, or the database or migrations could not be read
+    Down,
+}
+
+/// What [`check`] found, serializable as the body of a health endpoint
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HealthReport {
+    pub state: HealthState,
+    /// Migrations `apply` would run
+    pub pending: usize,
+    /// Migrations recorded as failed
+    pub failed: Vec<String>,
+    /// Applied migrations whose file was modified since
+    pub modified: Vec<String>,
+    /// Applied migrations whose file no longer exists
+    pub missing: Vec<String>,
+    /// Why the check could not run, when the state is down for that reason
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl HealthReport {
+    /// Returns true if nothing is pending, failed or drifted
+    pub fn is_healthy(&self) -> bool {
+        self.state == HealthState::Up
+    }
+
+    /// Returns true if applied migrations were modified or deleted since
+    pub fn has_drift(&self) -> bool {
+        !self.modified.is_empty() || !self.missing.is_empty()
+    }
+
+    fn unavailable(error: String) -> Self {
+        Self {
+            state: HealthState::Down,
+            pending: 0,
+            failed: Vec::new(),
+            modified: Vec::new(),
+            missing: Vec::new(),
+            error: Some(error),
+        }
+    }
+}
+
+/// Compares the migrations in `path` with schema_migrations on `conn`
+pub fn check(conn: &str, path: &str) -> HealthReport {
+    check_with_options(conn, path, &LoaderOptions::default())
+}
+
+/// Like [`check`], loading migrations with the given options (recursive sources,
+/// checksum normalization, skip list, ...)
+pub fn check_with_options(conn: &str, path: &str, loader: &LoaderOptions) -> HealthReport {
+    check_target(&Target::new(conn), path, loader)
+}
+
+/// Like [`check_with_options`] on a configured target, e.g. with schema_migrations in
+/// another schema
+pub fn check_target(target: &Target, path: &str, loader: &LoaderOptions) -> HealthReport {
+    let migrations = match MigrationLoader::load_migrations_with_options(path, loader) {
+        Ok(migrations) => migrations,
+        Err(e) => return HealthReport::unavailable(format!("Failed to load migrations from {}: {}", path, e)),
+    };
+    match database_state(target, &migrations) {
+        Ok((applied, baseline, pending)) => summarize(&migrations, &applied, baseline, pending),
+        Err(e) => HealthReport::unavailable(e.to_string()),
+    }
+}
+
+/// The schema_migrations records, the baseline version and the number of pending migrations
+fn database_state(
+    target: &Target,
+    migrations: &[Migration],
+) -> Result<(Vec<AppliedMigration>, Option<u32>, usize), ConnectionError> {
+    if !schema_init::check_migration_table_exists(target)? {
+        log::debug!("schema_migrations does not exist, every migration is pending");
+        return Ok((Vec::new(), None, migrations.len()));
+    }
+    let mut version_store = target.version_store()?;
+    let pending = version_store.get_pending_migrations(migrations)?.len();
+    Ok((version_store.get_applied_migrations()?, version_store.get_baseline_version()?, pending))
+}
+
+/// Builds the report from the migration files, the schema_migrations records, the
+/// baseline version and the number of pending migrations
+pub fn summarize(
+    migrations: &[Migration],
+    applied: &[AppliedMigration],
+    baseline: Option<u32>,
+    pending: usize,
+) -> HealthReport {
+    let mut failed = Vec::new();
+    let mut modified = Vec::new();
+    let mut missing = Vec::new();
+    for record in applied {
+        let file = migrations.iter().find(|m| m.identifier() == record.migration_id);
+        if !record.success {
+            failed.push(record.filename.clone());
+        } else if let Some(file) = file {
+            // Changed repeatables are pending, not drift
+            if file.migration_type == MigrationType::Versioned && !file.matches_checksum(&record.checksum) {
+                modified.push(record.filename.clone());
+            }
+        } else if record.migration_type == MigrationType::Versioned && record.version != baseline {
+            missing.push(record.filename.clone());
+        }
+    }
+
+    let state = if !failed.is_empty() {
+        HealthState::Down
+    } else if pending > 0 || !modified.is_empty() || !missing.is_empty() {
+        HealthState::Degraded
+    } else {
+        HealthState::Up
+    };
+    log::debug!(
+        "Migration health {:?}: {} pending, {} failed, {} modified, {} missing",
+        state,
+        pending,
+        failed.len(),
+        modified.len(),
+        missing.len()
+    );
+    HealthReport { state, pending, failed, modified, missing, error: None }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(migration: &Migration, checksum: &str, success: bool) -> AppliedMigration {
+        AppliedMigration { checksum: checksum.to_string(), success, ..AppliedMigration::of(migration) }
+    }
+
+    #[test]
+    fn test_summarize_reports_pending_failed_and_drift() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("0001_users.sql"), "CREATE TABLE users (id INT);").unwrap();
+        std::fs::write(dir.path().join("0002_orders.sql"), "CREATE TABLE orders (id INT);").unwrap();
+        let migrations = MigrationLoader::load_migrations(dir.path().to_str().unwrap()).unwrap();
+
+        let applied: Vec<_> = migrations.iter().map(|m| record(m, &m.checksum, true)).collect();
+        let report = summarize(&migrations, &applied, None, 0);
+        assert!(report.is_healthy());
+
+        let report = summarize(&migrations, &applied[..1], None, 1);
+        assert_eq!((report.state, report.pending), (HealthState::Degraded, 1));
+
+        let mut orphan = record(&migrations[1], &migrations[1].checksum, true);
+        orphan.migration_id = "0003".to_string();
+        orphan.filename = "0003_gone.sql".to_string();
+        let drifted = vec![record(&migrations[0], "edited", true), record(&migrations[1], &migrations[1].checksum, false), orphan];
+        let report = summarize(&migrations, &drifted, None, 0);
+        assert_eq!(report.state, HealthState::Down);
+        assert_eq!(report.failed, vec!["0002_orders.sql"]);
+        assert_eq!(report.modified, vec!["0001_users.sql"]);
+        assert_eq!(report.missing, vec!["0003_gone.sql"]);
+        assert!(report.has_drift());
+    }
+}
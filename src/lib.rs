@@ -1,6 +1,9 @@
 pub mod cli;
 pub mod dialects;
 pub mod executor;
+pub mod health;
+pub mod logger;
 pub mod model;
 pub mod orchestrator;
 pub mod tracker;
+pub mod testing;
@@ -1,16 +1,95 @@
+use crate::model::{LogRotation, LoggingConfig};
+use chrono::{Local, NaiveDate, Utc};
 use env_logger::{Builder, Target};
-use log::Level;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::str::FromStr;
 use std::env;
-use std::io::Write;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
 
-pub fn setup_logger(verbose: bool) {
-    let level = if verbose { Level::Debug } else { Level::Info };
+/// Log target for end-of-command summaries, which stay visible with `--quiet`
+pub const SUMMARY_TARGET: &str = "summary";
+
+static COLORED: AtomicBool = AtomicBool::new(false);
+static JSON_FORMAT: AtomicBool = AtomicBool::new(false);
+static LOG_FILE: OnceLock<Mutex<RotatingFile>> = OnceLock::new();
+static FILE_LEVEL: OnceLock<LevelFilter> = OnceLock::new();
+static RUN_ID: OnceLock<String> = OnceLock::new();
+
+/// Sends records to stdout through env_logger, and to the log file once one is configured
+struct DeriddlLogger {
+    stdout: env_logger::Logger,
+}
+
+impl Log for DeriddlLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.stdout.enabled(metadata) || (LOG_FILE.get().is_some() && metadata.level() <= file_level())
+    }
+
+    fn log(&self, record: &Record) {
+        if self.stdout.matches(record) {
+            self.stdout.log(record);
+        }
+
+        if LOG_FILE.get().is_some() && record.level() <= file_level() {
+            write_log_file(record.level(), &record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {
+        self.stdout.flush();
+        if let Some(file) = LOG_FILE.get()
+            && let Ok(mut file) = file.lock()
+        {
+            let _ = file.file.flush();
+        }
+    }
+}
+
+/// Tags every record with the id of the run. The logger serves the whole process, so it
+/// keeps its own copy of [`crate::executor::ConnectionSettings::run_id`]; only the first
+/// call takes effect.
+pub fn set_run_id(run_id: &str) {
+    let _ = RUN_ID.set(run_id.to_string());
+}
+
+fn run_id() -> &'static str {
+    RUN_ID.get().map_or("-", String::as_str)
+}
+
+pub fn setup_logger(verbose: bool, quiet: bool, no_color: bool) {
+    let level = if verbose {
+        Level::Debug
+    } else if quiet {
+        Level::Warn
+    } else {
+        Level::Info
+    };
+
+    COLORED.store(color_supported(no_color), Ordering::Relaxed);
 
     let mut builder = Builder::new();
     builder.filter(None, level.to_level_filter());
+    if quiet {
+        builder.filter(Some(SUMMARY_TARGET), Level::Info.to_level_filter());
+    }
     builder.target(Target::Stdout);
 
     builder.format(|buf, record| {
+        if JSON_FORMAT.load(Ordering::Relaxed) {
+            let line = serde_json::json!({
+                "timestamp": Utc::now().to_rfc3339(),
+                "level": record.level().as_str().to_lowercase(),
+                "target": record.target(),
+                "run_id": run_id(),
+                "message": record.args().to_string(),
+            });
+            return writeln!(buf, "{}", line);
+        }
+
         let emoji = match record.level() {
             Level::Error => "❌ ",
             Level::Warn => "⚠️  ",
@@ -18,12 +97,246 @@ pub fn setup_logger(verbose: bool) {
             Level::Debug => "",
             Level::Trace => "",
         };
-        writeln!(buf, "{}{}", emoji, record.args())
+        let color = match record.level() {
+            Level::Error => Some("\x1b[31m"),
+            Level::Warn => Some("\x1b[33m"),
+            Level::Debug | Level::Trace => Some("\x1b[2m"),
+            Level::Info => None,
+        };
+        match color {
+            Some(color) if COLORED.load(Ordering::Relaxed) => {
+                writeln!(buf, "{}{}{}\x1b[0m", color, emoji, record.args())
+            }
+            _ => writeln!(buf, "{}{}", emoji, record.args()),
+        }
     });
 
     if env::var("RUST_LOG").is_ok() {
         builder.parse_default_env();
     }
 
-    builder.init();
+    let stdout = builder.build();
+    let max_level = stdout.filter();
+    if log::set_boxed_logger(Box::new(DeriddlLogger { stdout })).is_ok() {
+        log::set_max_level(max_level);
+    }
+}
+
+/// Applies the `[logging]` config once it has been loaded. Config can only turn color
+/// off; it is never enabled when `--no-color`, `NO_COLOR` or a non-TTY stdout disabled it.
+/// When `logging.file` is set, records down to `logging.level` are also written to that file.
+pub fn apply_config(config: &LoggingConfig) -> io::Result<()> {
+    if !config.colored {
+        COLORED.store(false, Ordering::Relaxed);
+    }
+    if config.format.eq_ignore_ascii_case("json") {
+        use_json_format();
+    }
+
+    if let Some(path) = &config.file {
+        let file = RotatingFile::open(
+            PathBuf::from(path),
+            config.rotation,
+            u64::from(config.max_size_mb) * 1024 * 1024,
+            config.max_files,
+        )?;
+        let level = LevelFilter::from_str(&config.level).unwrap_or(LevelFilter::Info);
+        if LOG_FILE.set(Mutex::new(file)).is_ok() {
+            let _ = FILE_LEVEL.set(level);
+            log::set_max_level(log::max_level().max(level));
+        }
+    }
+
+    Ok(())
+}
+
+/// Switches stdout to one JSON object per record, for log collectors such as those
+/// reading container output
+pub fn use_json_format() {
+    JSON_FORMAT.store(true, Ordering::Relaxed);
+}
+
+/// Whether text output may carry ANSI colors; never with JSON records
+pub fn colored() -> bool {
+    COLORED.load(Ordering::Relaxed) && !JSON_FORMAT.load(Ordering::Relaxed)
+}
+
+/// Logs the summary ending a command with the summary target, so it shows with `--quiet`.
+/// JSON records carry the summary's fields as `summary`.
+pub fn log_summary(message: &str, summary: &impl serde::Serialize) {
+    if !JSON_FORMAT.load(Ordering::Relaxed) {
+        log::info!(target: SUMMARY_TARGET, "{}", message);
+        return;
+    }
+    if log::max_level() >= LevelFilter::Info {
+        let line = serde_json::json!({
+            "timestamp": Utc::now().to_rfc3339(),
+            "level": "info",
+            "target": SUMMARY_TARGET,
+            "run_id": run_id(),
+            "message": message,
+            "summary": summary,
+        });
+        println!("{}", line);
+    }
+    write_log_file(Level::Info, message);
+}
+
+/// Writes the error that ended a command to stderr, keeping stdout for command output,
+/// and to the log file. JSON records also carry `error_code`.
+pub fn log_failure(message: &str, error_code: &str) {
+    if JSON_FORMAT.load(Ordering::Relaxed) {
+        let line = serde_json::json!({
+            "timestamp": Utc::now().to_rfc3339(),
+            "level": "error",
+            "target": env!("CARGO_CRATE_NAME"),
+            "run_id": run_id(),
+            "message": message,
+            "error_code": error_code,
+        });
+        eprintln!("{}", line);
+    } else if COLORED.load(Ordering::Relaxed) && std::io::stderr().is_terminal() {
+        eprintln!("\x1b[31m❌ {}\x1b[0m", message);
+    } else {
+        eprintln!("❌ {}", message);
+    }
+    write_log_file(Level::Error, message);
+}
+
+/// Lowest level written to the log file, `logging.level` once a file is configured
+fn file_level() -> LevelFilter {
+    FILE_LEVEL.get().copied().unwrap_or(LevelFilter::Info)
+}
+
+fn write_log_file(level: Level, message: &str) {
+    if level > file_level() {
+        return;
+    }
+    if let Some(file) = LOG_FILE.get()
+        && let Ok(mut file) = file.lock()
+    {
+        let line = format!("{} {:<5} {} {}\n", Local::now().format("%Y-%m-%d %H:%M:%S%.3f"), level, run_id(), message);
+        // Logging must never abort a migration run
+        let _ = file.write_line(&line);
+    }
+}
+
+fn color_supported(no_color: bool) -> bool {
+    let no_color_env = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    !no_color && !no_color_env && std::io::stdout().is_terminal()
+}
+
+/// Append-only log file that rotates by size or by date
+pub struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    rotation: LogRotation,
+    max_bytes: u64,
+    max_files: u32,
+    size: u64,
+    date: NaiveDate,
+}
+
+impl RotatingFile {
+    pub fn open(
+        path: PathBuf,
+        rotation: LogRotation,
+        max_bytes: u64,
+        max_files: u32,
+    ) -> io::Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let metadata = file.metadata()?;
+        let date = metadata
+            .modified()
+            .map(|modified| chrono::DateTime::<Local>::from(modified).date_naive())
+            .unwrap_or_else(|_| Local::now().date_naive());
+
+        Ok(Self {
+            path,
+            file,
+            rotation,
+            max_bytes,
+            max_files,
+            size: metadata.len(),
+            date,
+        })
+    }
+
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let today = Local::now().date_naive();
+        let needs_rotation = match self.rotation {
+            LogRotation::Size => self.size > 0 && self.size + line.len() as u64 > self.max_bytes,
+            LogRotation::Daily => today != self.date,
+            LogRotation::Never => false,
+        };
+        if needs_rotation {
+            self.rotate()?;
+        }
+
+        self.file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        self.date = today;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        match self.rotation {
+            LogRotation::Size => {
+                // deriddl.log.4 -> deriddl.log.5, ..., deriddl.log -> deriddl.log.1
+                let max_files = self.max_files.max(1);
+                let _ = fs::remove_file(suffixed(&self.path, &max_files.to_string()));
+                for index in (1..max_files).rev() {
+                    let from = suffixed(&self.path, &index.to_string());
+                    if from.exists() {
+                        fs::rename(&from, suffixed(&self.path, &(index + 1).to_string()))?;
+                    }
+                }
+                fs::rename(&self.path, suffixed(&self.path, "1"))?;
+            }
+            LogRotation::Daily => {
+                let dated = suffixed(&self.path, &self.date.format("%Y-%m-%d").to_string());
+                fs::rename(&self.path, dated)?;
+            }
+            LogRotation::Never => return Ok(()),
+        }
+
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn suffixed(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_size_rotation_keeps_max_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("deriddl.log");
+        let mut file = RotatingFile::open(path.clone(), LogRotation::Size, 10, 2).unwrap();
+
+        for i in 0..4 {
+            file.write_line(&format!("line {:04}\n", i)).unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "line 0003\n");
+        assert_eq!(fs::read_to_string(suffixed(&path, "1")).unwrap(), "line 0002\n");
+        assert_eq!(fs::read_to_string(suffixed(&path, "2")).unwrap(), "line 0001\n");
+        assert!(!suffixed(&path, "3").exists());
+    }
 }
@@ -1,17 +1,14 @@
-mod cli;
-mod dialects;
-mod executor;
-mod logger;
-mod model;
-mod orchestrator;
-mod tracker;
-
 use clap::Parser;
-use cli::args::Cli;
-use cli::dispatch::handle;
+use deriddl_rs::cli::args::Cli;
+use deriddl_rs::cli::dispatch::handle;
+use deriddl_rs::logger;
 
 fn main() {
     let cli = Cli::parse();
-    logger::setup_logger(cli.verbose);
-    handle(cli);
+    logger::setup_logger(cli.verbose, cli.quiet, cli.no_color);
+    let status = handle(cli).unwrap_or_else(|e| {
+        e.report();
+        e.status
+    });
+    std::process::exit(status);
 }
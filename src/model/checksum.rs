@@ -0,0 +1,379 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// How migration checksums are computed (`validation.checksum`, or a custom strategy),
+/// shared by the loader and the migrations it loads. SHA-256 by default.
+#[derive(Clone)]
+pub struct SharedChecksumStrategy(Arc<dyn ChecksumStrategy>);
+
+impl SharedChecksumStrategy {
+    pub fn new(strategy: impl ChecksumStrategy + 'static) -> Self {
+        Self(Arc::new(strategy))
+    }
+}
+
+impl Default for SharedChecksumStrategy {
+    fn default() -> Self {
+        Self::new(Sha256Checksum)
+    }
+}
+
+impl Deref for SharedChecksumStrategy {
+    type Target = dyn ChecksumStrategy;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
+
+impl std::fmt::Debug for SharedChecksumStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Hash function of a [`ChecksumStrategy`], fed normalized content in chunks
+pub trait ChecksumHasher {
+    fn write(&mut self, bytes: &[u8]);
+    fn finish(self: Box<Self>) -> String;
+}
+
+/// How the checksums recorded in schema_migrations are computed, so projects moving from
+/// another tool can keep the checksums it recorded
+pub trait ChecksumStrategy: Send + Sync {
+    /// Name in `validation.checksum`
+    fn name(&self) -> &'static str;
+
+    fn hasher(&self) -> Box<dyn ChecksumHasher>;
+
+    /// Checksum of `content` after `normalization`
+    fn checksum(&self, content: &str, normalization: ChecksumNormalization) -> String {
+        let mut hasher = self.hasher();
+        match normalization {
+            ChecksumNormalization::Strict => hasher.write(content.as_bytes()),
+            ChecksumNormalization::Whitespace => hasher.write(collapse_whitespace(content).as_bytes()),
+            ChecksumNormalization::Semantic => hasher.write(split_statements(content).join(";\n").as_bytes()),
+        }
+        hasher.finish()
+    }
+}
+
+/// Built-in checksum strategies, as named in `validation.checksum`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumAlgorithm {
+    #[default]
+    Sha256,
+    /// CRC32 as a signed integer, the way Flyway records it
+    Crc32,
+    /// deriddl's original 64-bit hash, for histories recorded before checksum strategies
+    Hash,
+}
+
+impl ChecksumAlgorithm {
+    pub fn strategy(self) -> SharedChecksumStrategy {
+        match self {
+            ChecksumAlgorithm::Sha256 => SharedChecksumStrategy::new(Sha256Checksum),
+            ChecksumAlgorithm::Crc32 => SharedChecksumStrategy::new(Crc32Checksum),
+            ChecksumAlgorithm::Hash => SharedChecksumStrategy::new(HashChecksum),
+        }
+    }
+}
+
+/// Hex-encoded SHA-256, the default
+pub struct Sha256Checksum;
+
+impl ChecksumStrategy for Sha256Checksum {
+    fn name(&self) -> &'static str {
+        "sha256"
+    }
+
+    fn hasher(&self) -> Box<dyn ChecksumHasher> {
+        Box::new(Sha256::new())
+    }
+}
+
+impl ChecksumHasher for Sha256 {
+    fn write(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+
+    fn finish(self: Box<Self>) -> String {
+        hex::encode(Digest::finalize(*self))
+    }
+}
+
+/// CRC32 (IEEE) of the content as a signed decimal integer
+pub struct Crc32Checksum;
+
+impl ChecksumStrategy for Crc32Checksum {
+    fn name(&self) -> &'static str {
+        "crc32"
+    }
+
+    fn hasher(&self) -> Box<dyn ChecksumHasher> {
+        Box::new(crc32fast::Hasher::new())
+    }
+}
+
+impl ChecksumHasher for crc32fast::Hasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+
+    fn finish(self: Box<Self>) -> String {
+        ((*self).finalize() as i32).to_string()
+    }
+}
+
+/// deriddl's original hash, the same as [`compute_checksum`]
+pub struct HashChecksum;
+
+impl ChecksumStrategy for HashChecksum {
+    fn name(&self) -> &'static str {
+        "hash"
+    }
+
+    fn hasher(&self) -> Box<dyn ChecksumHasher> {
+        Box::new(DefaultHasher::new())
+    }
+
+    fn checksum(&self, content: &str, normalization: ChecksumNormalization) -> String {
+        compute_checksum(content, normalization)
+    }
+}
+
+impl ChecksumHasher for DefaultHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        Hasher::write(self, bytes);
+    }
+
+    fn finish(mut self: Box<Self>) -> String {
+        // `str` hashes as its bytes followed by a 0xff terminator
+        self.write_u8(0xff);
+        format!("{:x}", Hasher::finish(&*self))
+    }
+}
+
+/// How migration content is normalized before its checksum is computed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumNormalization {
+    /// Hash the content exactly as written
+    #[default]
+    Strict,
+    /// Ignore line endings and differences in whitespace
+    Whitespace,
+    /// Ignore comments and whitespace, hashing the list of SQL statements
+    Semantic,
+}
+
+/// Computes the checksum of migration content with deriddl's original hash, using the given
+/// normalization. Migration checksums go through [`checksum_strategy`].
+pub fn compute_checksum(content: &str, normalization: ChecksumNormalization) -> String {
+    let mut hasher = DefaultHasher::new();
+    match normalization {
+        ChecksumNormalization::Strict => content.hash(&mut hasher),
+        ChecksumNormalization::Whitespace => collapse_whitespace(content).hash(&mut hasher),
+        ChecksumNormalization::Semantic => split_statements(content).hash(&mut hasher),
+    }
+    format!("{:x}", hasher.finish())
+}
+
+/// Incrementally computes the same checksum as [`ChecksumStrategy::checksum`] over trimmed
+/// content, so files too large to hold in memory can be hashed while they are read.
+/// Semantic normalization needs every statement up front and is not supported.
+pub struct StreamingChecksum {
+    hasher: Box<dyn ChecksumHasher>,
+    normalization: ChecksumNormalization,
+    started: bool,
+    pending_whitespace: String,
+}
+
+impl StreamingChecksum {
+    /// Returns `None` for normalizations that cannot be computed incrementally
+    pub fn new(strategy: &dyn ChecksumStrategy, normalization: ChecksumNormalization) -> Option<Self> {
+        if normalization == ChecksumNormalization::Semantic {
+            return None;
+        }
+        Some(Self {
+            hasher: strategy.hasher(),
+            normalization,
+            started: false,
+            pending_whitespace: String::new(),
+        })
+    }
+
+    /// Feeds the next chunk of content. Leading and trailing whitespace of the
+    /// whole stream is ignored, matching `content.trim()`.
+    pub fn update(&mut self, chunk: &str) {
+        for c in chunk.chars() {
+            if c.is_whitespace() {
+                if self.started {
+                    self.pending_whitespace.push(c);
+                }
+                continue;
+            }
+            if !self.pending_whitespace.is_empty() {
+                match self.normalization {
+                    ChecksumNormalization::Whitespace => self.hasher.write(b" "),
+                    _ => self.hasher.write(self.pending_whitespace.as_bytes()),
+                }
+                self.pending_whitespace.clear();
+            }
+            self.started = true;
+            let mut buffer = [0u8; 4];
+            self.hasher.write(c.encode_utf8(&mut buffer).as_bytes());
+        }
+    }
+
+    pub fn finish(self) -> String {
+        self.hasher.finish()
+    }
+}
+
+/// Collapses every run of whitespace (including CR/LF) into a single space
+fn collapse_whitespace(content: &str) -> String {
+    content.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Splits SQL into statements with comments removed and whitespace collapsed.
+/// Comment markers and semicolons inside quoted strings or identifiers are left alone.
+pub(crate) fn split_statements(content: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => {
+                current.push(c);
+                while let Some(next) = chars.next() {
+                    current.push(next);
+                    if next == c {
+                        // Doubled quotes are escapes, keep scanning the literal
+                        if chars.peek() == Some(&c) {
+                            current.push(chars.next().unwrap_or(c));
+                        } else {
+                            break;
+                        }
+                    }
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        break;
+                    }
+                }
+                current.push(' ');
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = '\0';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+                current.push(' ');
+            }
+            ';' => {
+                let statement = collapse_whitespace(&current);
+                if !statement.is_empty() {
+                    statements.push(statement);
+                }
+                current.clear();
+            }
+            c if c.is_whitespace() => current.push(' '),
+            c => current.push(c),
+        }
+    }
+
+    let statement = collapse_whitespace(&current);
+    if !statement.is_empty() {
+        statements.push(statement);
+    }
+    statements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_whitespace_ignores_line_endings() {
+        let unix = "CREATE TABLE t (\n  id INT\n);\n";
+        let windows = "CREATE TABLE t (\r\n    id INT\r\n);\r\n";
+        assert_ne!(
+            compute_checksum(unix, ChecksumNormalization::Strict),
+            compute_checksum(windows, ChecksumNormalization::Strict)
+        );
+        assert_eq!(
+            compute_checksum(unix, ChecksumNormalization::Whitespace),
+            compute_checksum(windows, ChecksumNormalization::Whitespace)
+        );
+    }
+
+    #[test]
+    fn test_semantic_ignores_comments_but_not_literals() {
+        let original = "CREATE TABLE t (id INT);\nINSERT INTO t VALUES (1);";
+        let commented = "-- create the table\nCREATE TABLE t (id INT); /* seed */\nINSERT INTO t VALUES (1);";
+        let changed_literal = "CREATE TABLE t (id INT);\nINSERT INTO t VALUES ('-- 1');";
+
+        assert_eq!(
+            compute_checksum(original, ChecksumNormalization::Semantic),
+            compute_checksum(commented, ChecksumNormalization::Semantic)
+        );
+        assert_ne!(
+            compute_checksum(original, ChecksumNormalization::Semantic),
+            compute_checksum(changed_literal, ChecksumNormalization::Semantic)
+        );
+        assert_eq!(
+            split_statements(changed_literal),
+            vec!["CREATE TABLE t (id INT)", "INSERT INTO t VALUES ('-- 1')"]
+        );
+    }
+
+    #[test]
+    fn test_streaming_checksum_matches_in_memory() {
+        let content = "  CREATE TABLE t (\r\n  id INT\r\n);\n\nINSERT INTO t VALUES (1);\n\n";
+        for algorithm in [ChecksumAlgorithm::Sha256, ChecksumAlgorithm::Crc32, ChecksumAlgorithm::Hash] {
+            let strategy = algorithm.strategy();
+            for normalization in [ChecksumNormalization::Strict, ChecksumNormalization::Whitespace] {
+                let mut streaming = StreamingChecksum::new(&*strategy, normalization).unwrap();
+                for line in content.split_inclusive('\n') {
+                    streaming.update(line);
+                }
+                assert_eq!(streaming.finish(), strategy.checksum(content.trim(), normalization), "{}", strategy.name());
+            }
+        }
+        assert_eq!(HashChecksum.checksum(content, ChecksumNormalization::Semantic), compute_checksum(content, ChecksumNormalization::Semantic));
+        assert!(StreamingChecksum::new(&Sha256Checksum, ChecksumNormalization::Semantic).is_none());
+    }
+
+    #[test]
+    fn test_checksum_strategies() {
+        let sql = "CREATE TABLE t (id INT);";
+        assert_eq!(
+            Sha256Checksum.checksum("abc", ChecksumNormalization::Strict),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(Crc32Checksum.checksum("123456789", ChecksumNormalization::Strict), (0xCBF43926u32 as i32).to_string());
+        assert_eq!(
+            Sha256Checksum.checksum(sql, ChecksumNormalization::Whitespace),
+            Sha256Checksum.checksum("CREATE  TABLE t\n(id INT);", ChecksumNormalization::Whitespace)
+        );
+        assert_eq!(
+            Crc32Checksum.checksum(sql, ChecksumNormalization::Semantic),
+            Crc32Checksum.checksum("-- users\nCREATE TABLE t (id INT)", ChecksumNormalization::Semantic)
+        );
+        assert_eq!(HashChecksum.checksum(sql, ChecksumNormalization::Strict), compute_checksum(sql, ChecksumNormalization::Strict));
+    }
+}
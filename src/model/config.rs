@@ -1,10 +1,30 @@
-use log::debug;
+use crate::dialects::DialectSettings;
+use crate::model::{ChecksumAlgorithm, ChecksumNormalization, MigrationIdScheme};
+use log::{debug, warn};
+use schemars::generate::SchemaSettings;
+use schemars::transform::RecursiveTransform;
+use schemars::{JsonSchema, Schema};
+use semver::{Version, VersionReq};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+/// Where [`Config::load`] reads configuration from
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConfigFiles<'a> {
+    /// Base configuration file (`--config`); the standard locations when None
+    pub path: Option<&'a str>,
+    /// Unknown keys fail instead of being warned about (`--strict-config`)
+    pub strict: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
 pub struct Config {
+    /// Versions of deriddl this configuration works with, as a semver requirement such as
+    /// `">=0.6, <0.8"`; other versions refuse to run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required_version: Option<String>,
+
     #[serde(default)]
     pub database: DatabaseConfig,
 
@@ -22,9 +42,20 @@ pub struct Config {
 
     #[serde(default)]
     pub baseline: BaselineConfig,
+
+    #[serde(default)]
+    pub kubernetes: KubernetesConfig,
+
+    #[serde(default)]
+    pub tracker: TrackerConfig,
+
+    /// `[dialect.postgres]`, `[dialect.mysql]`, `[dialect.databricks]`: options only the
+    /// named dialect understands
+    #[serde(default)]
+    pub dialect: DialectSettings,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DatabaseConfig {
     pub connection_string: Option<String>,
 
@@ -33,9 +64,106 @@ pub struct DatabaseConfig {
 
     #[serde(default = "default_max_retries")]
     pub max_retries: u32,
+
+    /// Statements run on every new connection, e.g. `SET ROLE migrator`
+    #[serde(default)]
+    pub session_setup: Vec<String>,
+
+    /// Schema (Postgres, Databricks) or database (MySQL) made current on every connection
+    /// and holding schema_migrations, so services sharing a cluster keep separate histories
+    #[serde(default)]
+    pub schema: Option<String>,
+
+    /// Lock wait timeout applied before each migration (lock_timeout / innodb_lock_wait_timeout)
+    #[serde(default)]
+    pub lock_timeout_ms: Option<u64>,
+
+    /// Times a migration is retried after failing to acquire a lock
+    #[serde(default)]
+    pub lock_retries: u32,
+
+    /// Statements still running after this long are cancelled (SQLCancel) and fail
+    #[serde(default)]
+    pub statement_timeout_ms: Option<u64>,
+
+    /// Delay before retrying a lock timeout, multiplied by the attempt number
+    #[serde(default = "default_lock_retry_delay_ms")]
+    pub lock_retry_delay_ms: u64,
+
+    /// Warn before apply ALTERs, UPDATEs or DELETEs from a table with more estimated rows
+    #[serde(default)]
+    pub large_table_rows: Option<u64>,
+
+    /// Warn before apply ALTERs, UPDATEs or DELETEs from a table larger than this many MB
+    #[serde(default)]
+    pub large_table_mb: Option<u64>,
+
+    /// Connection attributes for drivers whose defaults don't suit migrations
+    #[serde(default)]
+    pub odbc: OdbcConfig,
+
+    /// Tags attributing executed statements to the deriddl run, for warehouse governance
+    #[serde(default)]
+    pub query_tag: QueryTagConfig,
+}
+
+/// `[database.query_tag]`: how statements are attributed to the run (and migration) that
+/// issued them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct QueryTagConfig {
+    /// Prefix every statement with `-- deriddl run_id=<uuid> migration=<file>`
+    #[serde(default)]
+    pub comment: bool,
+
+    /// Also set the dialect's native session tag (Databricks `SET QUERY_TAGS`)
+    #[serde(default)]
+    pub session: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// `[database.odbc]`: connection attributes set on every connection. Unset values keep
+/// the driver's default.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct OdbcConfig {
+    /// Seconds to wait for a login before giving up (SQL_ATTR_LOGIN_TIMEOUT)
+    #[serde(default)]
+    pub login_timeout_secs: Option<u32>,
+
+    /// Network packet size in bytes (SQL_ATTR_PACKET_SIZE); not all drivers support it
+    #[serde(default)]
+    pub packet_size: Option<u32>,
+
+    /// Auto-commit mode. With `false`, deriddl commits each statement it runs outside a
+    /// migration's transaction itself.
+    #[serde(default)]
+    pub autocommit: Option<bool>,
+
+    /// Transaction isolation level of every session
+    #[serde(default)]
+    pub isolation_level: Option<IsolationLevel>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IsolationLevel {
+    ReadUncommitted,
+    ReadCommitted,
+    RepeatableRead,
+    Serializable,
+}
+
+impl IsolationLevel {
+    /// The level as written in SQL, e.g. `READ COMMITTED`
+    pub fn as_sql(self) -> &'static str {
+        match self {
+            IsolationLevel::ReadUncommitted => "READ UNCOMMITTED",
+            IsolationLevel::ReadCommitted => "READ COMMITTED",
+            IsolationLevel::RepeatableRead => "REPEATABLE READ",
+            IsolationLevel::Serializable => "SERIALIZABLE",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct MigrationsConfig {
     #[serde(default = "default_migrations_path")]
     pub path: String,
@@ -43,14 +171,131 @@ pub struct MigrationsConfig {
     #[serde(default = "default_dialect")]
     pub dialect: String,
 
+    /// Fail when `dialect` names no registered dialect instead of falling back to the
+    /// generic one, whose SQL may be invalid for the target
+    #[serde(default)]
+    pub dialect_strict: bool,
+
     #[serde(default = "default_validate_sql")]
     pub validate_sql: bool,
 
     #[serde(default = "default_file_pattern")]
     pub file_pattern: String,
+
+    /// Load migrations from subdirectories as well as the top-level directory
+    #[serde(default)]
+    pub recursive: bool,
+
+    /// Multiple migration sources applied in order; overrides `path` when non-empty
+    #[serde(default)]
+    pub paths: Vec<MigrationSource>,
+
+    /// Keep every applied definition of repeatable migrations (compressed, in
+    /// deriddl_repeatable_history) so `rollback --repeatable` can restore the previous one
+    #[serde(default)]
+    pub repeatable_history: bool,
+
+    /// Migrations (versions like `7`, identifiers like `analytics:3`, or file names) that
+    /// never run here: `apply` records them as skipped instead of executing them
+    #[serde(default)]
+    pub skip: Vec<String>,
+
+    /// Directory of `<name>.sql.hbs` (or `<name>.sql`) handlebars templates for
+    /// `new --template`; they take precedence over the built-in templates of the same name
+    #[serde(default = "default_templates_dir")]
+    pub templates_dir: String,
+
+    /// How migrations are identified in schema_migrations: version, filename or uuid
+    #[serde(default)]
+    pub id_strategy: MigrationIdScheme,
+}
+
+/// A migration source directory with an optional namespace.
+/// Accepts either a plain path string or `{ path = "...", namespace = "..." }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum MigrationSource {
+    Path(String),
+    Namespaced {
+        path: String,
+        #[serde(default)]
+        namespace: Option<String>,
+    },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl MigrationSource {
+    pub fn path(&self) -> &str {
+        match self {
+            MigrationSource::Path(path) => path,
+            MigrationSource::Namespaced { path, .. } => path,
+        }
+    }
+
+    pub fn namespace(&self) -> Option<&str> {
+        match self {
+            MigrationSource::Path(_) => None,
+            MigrationSource::Namespaced { namespace, .. } => namespace.as_deref(),
+        }
+    }
+}
+
+/// Dotted TOML key of a setting, e.g. `dialect.postgres.application_name`
+fn config_key(path: &serde_ignored::Path) -> String {
+    use serde_ignored::Path;
+    match path {
+        Path::Root => String::new(),
+        Path::Map { parent: Path::Root, key } => key.clone(),
+        Path::Map { parent, key } => format!("{}.{}", config_key(parent), key),
+        Path::Seq { parent, index } => format!("{}[{}]", config_key(parent), index),
+        Path::Some { parent } | Path::NewtypeStruct { parent } | Path::NewtypeVariant { parent } => config_key(parent),
+    }
+}
+
+/// Sets the value at `path` in a TOML document, creating the sections on the way
+fn set_key(document: &mut toml::Value, path: &[&str], value: toml::Value) -> Result<(), String> {
+    let Some((last, sections)) = path.split_last() else {
+        return Err("empty key".to_string());
+    };
+    let mut table = document.as_table_mut().ok_or("the configuration is not a table")?;
+    for section in sections {
+        table = table
+            .entry(section.to_string())
+            .or_insert_with(|| toml::Value::Table(Default::default()))
+            .as_table_mut()
+            .ok_or_else(|| format!("{} is not a section", section))?;
+    }
+    table.insert(last.to_string(), value);
+    Ok(())
+}
+
+/// Converts a configured path into one the current platform can open. Verbatim `\\?\`
+/// prefixes are dropped and, except for drive-letter or UNC paths, backslashes are
+/// treated as separators on Unix so `db\migrations` works on every platform.
+pub fn native_path(path: &str) -> PathBuf {
+    let path = path.trim();
+    let path = match path.strip_prefix(r"\\?\UNC\") {
+        Some(share) => format!(r"\\{}", share),
+        None => path.strip_prefix(r"\\?\").unwrap_or(path).to_string(),
+    };
+
+    if cfg!(windows) || is_windows_absolute(&path) {
+        PathBuf::from(path)
+    } else {
+        PathBuf::from(path.replace('\\', "/"))
+    }
+}
+
+/// Returns true for `C:\...`, `C:/...` and `\\server\share` paths
+fn is_windows_absolute(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    let drive = bytes.len() >= 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes[2] == b'\\' || bytes[2] == b'/');
+    drive || path.starts_with(r"\\")
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct LoggingConfig {
     #[serde(default = "default_log_level")]
     pub level: String,
@@ -60,9 +305,41 @@ pub struct LoggingConfig {
 
     #[serde(default = "default_log_format")]
     pub format: String,
+
+    /// Also write log records down to `level` (executed statements need "debug") to this file
+    #[serde(default)]
+    pub file: Option<String>,
+
+    /// When to rotate the log file: "size", "daily" or "never"
+    #[serde(default)]
+    pub rotation: LogRotation,
+
+    /// Rotate the log file once it grows beyond this size (size rotation)
+    #[serde(default = "default_log_max_size_mb")]
+    pub max_size_mb: u32,
+
+    /// Number of rotated log files to keep (size rotation)
+    #[serde(default = "default_log_max_files")]
+    pub max_files: u32,
+
+    /// Append every statement executed by apply/rollback to this SQL file
+    #[serde(default)]
+    pub sql_log: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    /// Rotate to `<file>.1`, `<file>.2`, ... once `max_size_mb` is exceeded
+    #[default]
+    Size,
+    /// Rotate to `<file>.<YYYY-MM-DD>` when the date changes
+    Daily,
+    /// Never rotate; the file grows indefinitely
+    Never,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct BehaviorConfig {
     #[serde(default)]
     pub auto_create_migrations_dir: bool,
@@ -72,9 +349,34 @@ pub struct BehaviorConfig {
 
     #[serde(default)]
     pub default_dry_run: bool,
+
+    /// Targets (`--env` names) where destructive commands require `--confirm <target>`
+    #[serde(default)]
+    pub protected_targets: Vec<String>,
+
+    /// Hex-encoded reviewer public keys; when set, apply on a protected target needs `--approved-plan`
+    #[serde(default)]
+    pub approval_keys: Vec<String>,
+
+    /// What apply does when a migration fails
+    #[serde(default)]
+    pub on_failure: FailurePolicy,
+}
+
+/// `behavior.on_failure`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum FailurePolicy {
+    /// Stop at the failed migration
+    #[default]
+    Halt,
+    /// Record the failure and go on with the later migrations that don't depend on it
+    SkipAndContinue,
+    /// Run the down SQL of the migrations this run applied, newest first, then stop
+    RollbackBatch,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ValidationConfig {
     #[serde(default = "default_enable_sqlglot")]
     pub enable_sqlglot: bool,
@@ -84,9 +386,46 @@ pub struct ValidationConfig {
 
     #[serde(default = "default_max_file_size_mb")]
     pub max_file_size_mb: u32,
+
+    /// Files larger than this are streamed from disk statement by statement during apply
+    /// instead of being loaded into memory
+    #[serde(default)]
+    pub stream_threshold_mb: Option<u32>,
+
+    /// Fail `apply` when a migration file is too large, binary or not valid UTF-8
+    #[serde(default = "default_fail_on_invalid_files")]
+    pub fail_on_invalid_files: bool,
+
+    /// How migration content is normalized before checksumming: strict, whitespace or semantic
+    #[serde(default)]
+    pub checksum_normalization: ChecksumNormalization,
+
+    /// How migration checksums are computed: sha256, crc32 or hash (deriddl's original one)
+    #[serde(default)]
+    pub checksum: ChecksumAlgorithm,
+
+    /// Refuse to apply migrations without a valid detached `.sig` file
+    #[serde(default)]
+    pub require_signatures: bool,
+
+    /// Hex-encoded Ed25519 public keys trusted to sign migration files
+    #[serde(default)]
+    pub signature_keys: Vec<String>,
+
+    /// GPG keyring used to verify non-Ed25519 `.sig` files
+    #[serde(default)]
+    pub gpg_keyring: Option<String>,
+
+    /// Lockfile written by `deriddl lock` and checked by apply and validate when present
+    #[serde(default = "default_lockfile")]
+    pub lockfile: String,
+
+    /// Refuse to apply or validate when the lockfile does not exist
+    #[serde(default)]
+    pub require_lockfile: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct BaselineConfig {
     /// Default baseline version for new environments
     pub default_version: Option<u32>,
@@ -108,6 +447,62 @@ pub struct BaselineConfig {
     pub allow_on_existing_migrations: bool,
 }
 
+/// What deriddl keeps in the database besides schema_migrations
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct TrackerConfig {
+    /// Archive the content of every applied migration in schema_migrations_content,
+    /// for diffs on checksum mismatches and recreating lost files. Off by default, as
+    /// it stores each migration again.
+    #[serde(default)]
+    pub store_content: bool,
+}
+
+/// Behavior of `apply --k8s-mode`, for Kubernetes Jobs and init containers
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct KubernetesConfig {
+    /// How long to wait for the database to accept connections
+    #[serde(default = "default_wait_for_db_secs")]
+    pub wait_for_db_secs: u64,
+
+    /// Lease held on the deriddl_lock table; renewed while migrations run
+    #[serde(default = "default_lock_lease_secs")]
+    pub lock_lease_secs: u64,
+
+    /// How long to wait for another replica to release the lock
+    #[serde(default = "default_lock_wait_secs")]
+    pub lock_wait_secs: u64,
+
+    /// How often the lock holder writes a heartbeat to deriddl_lock
+    #[serde(default = "default_heartbeat_secs")]
+    pub heartbeat_secs: u64,
+
+    /// A holder without a heartbeat for this long is reported as crashed and can be
+    /// taken over with `apply --steal-lock`
+    #[serde(default = "default_stale_lock_secs")]
+    pub stale_lock_secs: u64,
+
+    /// File touched while apply runs, for exec liveness probes
+    #[serde(default = "default_liveness_file")]
+    pub liveness_file: String,
+
+    #[serde(default = "default_liveness_interval_secs")]
+    pub liveness_interval_secs: u64,
+
+    /// Where the outcome is written for `kubectl describe pod`
+    #[serde(default = "default_termination_log")]
+    pub termination_log: Option<String>,
+
+    #[serde(default)]
+    pub exit_code_applied: i32,
+
+    /// Defaults to 0 since Kubernetes treats any other code as a failed Job or init container
+    #[serde(default)]
+    pub exit_code_nothing_to_do: i32,
+
+    #[serde(default = "default_exit_code_failed")]
+    pub exit_code_failed: i32,
+}
+
 // Default values
 fn default_timeout() -> u32 {
     30
@@ -115,6 +510,9 @@ fn default_timeout() -> u32 {
 fn default_max_retries() -> u32 {
     3
 }
+fn default_lock_retry_delay_ms() -> u64 {
+    2000
+}
 fn default_migrations_path() -> String {
     "./migrations".to_string()
 }
@@ -127,6 +525,9 @@ fn default_validate_sql() -> bool {
 fn default_file_pattern() -> String {
     r"^\d{4}_.*\.sql$".to_string()
 }
+fn default_templates_dir() -> String {
+    "./.deriddl/templates".to_string()
+}
 fn default_log_level() -> String {
     "info".to_string()
 }
@@ -136,6 +537,12 @@ fn default_colored() -> bool {
 fn default_log_format() -> String {
     "pretty".to_string()
 }
+fn default_log_max_size_mb() -> u32 {
+    10
+}
+fn default_log_max_files() -> u32 {
+    5
+}
 fn default_require_confirmation() -> bool {
     true
 }
@@ -145,12 +552,45 @@ fn default_enable_sqlglot() -> bool {
 fn default_max_file_size_mb() -> u32 {
     10
 }
+fn default_fail_on_invalid_files() -> bool {
+    true
+}
+fn default_lockfile() -> String {
+    "deriddl.lock".to_string()
+}
 fn default_baseline_description() -> String {
     "Database baseline".to_string()
 }
 fn default_require_baseline_confirmation() -> bool {
     true
 }
+fn default_wait_for_db_secs() -> u64 {
+    300
+}
+fn default_lock_lease_secs() -> u64 {
+    60
+}
+fn default_lock_wait_secs() -> u64 {
+    600
+}
+fn default_heartbeat_secs() -> u64 {
+    20
+}
+fn default_stale_lock_secs() -> u64 {
+    45
+}
+fn default_liveness_file() -> String {
+    "/tmp/deriddl-alive".to_string()
+}
+fn default_liveness_interval_secs() -> u64 {
+    10
+}
+fn default_termination_log() -> Option<String> {
+    Some("/dev/termination-log".to_string())
+}
+fn default_exit_code_failed() -> i32 {
+    1
+}
 
 
 impl Default for DatabaseConfig {
@@ -159,6 +599,16 @@ impl Default for DatabaseConfig {
             connection_string: None,
             timeout: default_timeout(),
             max_retries: default_max_retries(),
+            session_setup: Vec::new(),
+            schema: None,
+            lock_timeout_ms: None,
+            statement_timeout_ms: None,
+            lock_retries: 0,
+            lock_retry_delay_ms: default_lock_retry_delay_ms(),
+            large_table_rows: None,
+            large_table_mb: None,
+            odbc: OdbcConfig::default(),
+            query_tag: QueryTagConfig::default(),
         }
     }
 }
@@ -168,8 +618,15 @@ impl Default for MigrationsConfig {
         Self {
             path: default_migrations_path(),
             dialect: default_dialect(),
+            dialect_strict: false,
             validate_sql: default_validate_sql(),
             file_pattern: default_file_pattern(),
+            recursive: false,
+            repeatable_history: false,
+            paths: Vec::new(),
+            skip: Vec::new(),
+            templates_dir: default_templates_dir(),
+            id_strategy: MigrationIdScheme::default(),
         }
     }
 }
@@ -180,6 +637,11 @@ impl Default for LoggingConfig {
             level: default_log_level(),
             colored: default_colored(),
             format: default_log_format(),
+            file: None,
+            rotation: LogRotation::default(),
+            max_size_mb: default_log_max_size_mb(),
+            max_files: default_log_max_files(),
+            sql_log: None,
         }
     }
 }
@@ -190,6 +652,9 @@ impl Default for BehaviorConfig {
             auto_create_migrations_dir: false,
             require_confirmation: default_require_confirmation(),
             default_dry_run: false,
+            protected_targets: Vec::new(),
+            approval_keys: Vec::new(),
+            on_failure: FailurePolicy::default(),
         }
     }
 }
@@ -200,6 +665,15 @@ impl Default for ValidationConfig {
             enable_sqlglot: default_enable_sqlglot(),
             strict_validation: false,
             max_file_size_mb: default_max_file_size_mb(),
+            stream_threshold_mb: None,
+            fail_on_invalid_files: default_fail_on_invalid_files(),
+            checksum_normalization: ChecksumNormalization::default(),
+            checksum: ChecksumAlgorithm::default(),
+            require_signatures: false,
+            signature_keys: Vec::new(),
+            gpg_keyring: None,
+            lockfile: default_lockfile(),
+            require_lockfile: false,
         }
     }
 }
@@ -216,35 +690,56 @@ impl Default for BaselineConfig {
     }
 }
 
+impl Default for KubernetesConfig {
+    fn default() -> Self {
+        Self {
+            wait_for_db_secs: default_wait_for_db_secs(),
+            lock_lease_secs: default_lock_lease_secs(),
+            lock_wait_secs: default_lock_wait_secs(),
+            heartbeat_secs: default_heartbeat_secs(),
+            stale_lock_secs: default_stale_lock_secs(),
+            liveness_file: default_liveness_file(),
+            liveness_interval_secs: default_liveness_interval_secs(),
+            termination_log: default_termination_log(),
+            exit_code_applied: 0,
+            exit_code_nothing_to_do: 0,
+            exit_code_failed: default_exit_code_failed(),
+        }
+    }
+}
+
+/// Local overrides applied on top of every other configuration file
+pub(crate) const LOCAL_CONFIG_PATH: &str = "config/local.toml";
+
 impl Config {
     /// Load configuration from file with environment override support
-    pub fn load(config_path: Option<&str>, environment: Option<&str>) -> Result<Self, ConfigError> {
+    pub fn load(files: ConfigFiles, environment: Option<&str>) -> Result<Self, ConfigError> {
         let mut config = Config::default();
 
         // Load base configuration file
-        if let Some(path) = config_path {
-            config = Self::load_from_file(path)?;
+        if let Some(path) = files.path {
+            config = Self::read_file(path, files.strict)?;
         } else {
             // Try loading from standard locations
             for standard_path in Self::standard_config_paths() {
                 if standard_path.exists() {
                     debug!("Loading config from: {}", standard_path.display());
-                    config = Self::load_from_file(standard_path.to_str().unwrap())?;
+                    config = Self::read_file(standard_path.to_str().unwrap(), files.strict)?;
                     break;
                 }
             }
         }
 
         // Load environment-specific overrides
-        if let Some(env) = environment {
-            if let Ok(env_config) = Self::load_environment_config(env) {
-                debug!("Applying environment config for: {}", env);
-                config = config.merge(env_config);
-            }
+        if let Some(env) = environment
+            && let Ok(env_config) = Self::read_file(&Self::environment_config_path(env), files.strict)
+        {
+            debug!("Applying environment config for: {}", env);
+            config = config.merge(env_config);
         }
 
         // Load local overrides (always last)
-        if let Ok(local_config) = Self::load_from_file("config/local.toml") {
+        if let Ok(local_config) = Self::read_file(LOCAL_CONFIG_PATH, files.strict) {
             debug!("Applying local config overrides");
             config = config.merge(local_config);
         }
@@ -252,22 +747,109 @@ impl Config {
         Ok(config)
     }
 
-    /// Load configuration from a specific file
+    /// Fails when `required_version` does not accept the running version of deriddl, so
+    /// an outdated binary stops before it misreads a newer configuration
+    pub fn check_required_version(&self) -> Result<(), ConfigError> {
+        let Some(required) = &self.required_version else {
+            return Ok(());
+        };
+        let requirement = VersionReq::parse(required)
+            .map_err(|e| ConfigError::InvalidRequiredVersion(required.clone(), e.to_string()))?;
+        let running = Version::parse(env!("CARGO_PKG_VERSION")).expect("package version is semver");
+        if requirement.matches(&running) {
+            return Ok(());
+        }
+        Err(ConfigError::UnsupportedVersion(required.clone(), running.to_string()))
+    }
+
+    /// Load configuration from a specific file. Unknown keys, usually typos that would
+    /// otherwise silently leave the default in place, are warned about.
     pub fn load_from_file(path: &str) -> Result<Self, ConfigError> {
+        Self::read_file(path, false)
+    }
+
+    /// Like [`Self::load_from_file`], but unknown keys fail when `strict`
+    fn read_file(path: &str, strict: bool) -> Result<Self, ConfigError> {
+        let (config, unknown) = Self::parse_file(path)?;
+        if unknown.is_empty() {
+            return Ok(config);
+        }
+        if strict {
+            return Err(ConfigError::UnknownKeys(path.to_string(), unknown.join(", ")));
+        }
+        for key in &unknown {
+            warn!("⚠️  Ignoring unknown key '{}' in {}", key, path);
+        }
+        Ok(config)
+    }
+
+    /// Parses a configuration file, returning it with the keys no setting reads
+    pub(crate) fn parse_file(path: &str) -> Result<(Self, Vec<String>), ConfigError> {
         let content = fs::read_to_string(path)
             .map_err(|e| ConfigError::FileRead(path.to_string(), e.to_string()))?;
 
-        toml::from_str(&content).map_err(|e| ConfigError::Parse(path.to_string(), e.to_string()))
+        let mut unknown = Vec::new();
+        let config = serde_ignored::deserialize(toml::Deserializer::new(&content), |key| unknown.push(config_key(&key)))
+            .map_err(|e| ConfigError::Parse(path.to_string(), e.to_string()))?;
+        Ok((config, unknown))
     }
 
-    /// Load environment-specific configuration
-    fn load_environment_config(environment: &str) -> Result<Self, ConfigError> {
-        let env_path = format!("config/{}.toml", environment);
-        Self::load_from_file(&env_path)
+    /// Applies `--set key=value` overrides on top of every configuration layer. Values are
+    /// TOML (`false`, `5000`, `["a", "b"]`); anything else is taken as a string.
+    pub fn with_overrides(self, overrides: &[String]) -> Result<Self, ConfigError> {
+        overrides.iter().try_fold(self, |config, setting| config.with_override(setting))
+    }
+
+    fn with_override(self, setting: &str) -> Result<Self, ConfigError> {
+        let invalid = |reason: String| ConfigError::Override(setting.to_string(), reason);
+        let (key, raw) = setting.split_once('=').ok_or_else(|| invalid("expected key=value".to_string()))?;
+        let (key, raw) = (key.trim(), raw.trim());
+        let path: Vec<&str> = key.split('.').collect();
+        if path.iter().any(|part| part.is_empty()) {
+            return Err(invalid(format!("'{}' is not a dotted key", key)));
+        }
+        let current = toml::Value::try_from(&self).map_err(|e| invalid(e.to_string()))?;
+
+        // `--set migrations.dialect=mysql` is meant as a string, and so is a value that
+        // parses as TOML of the wrong type, e.g. a numeric baseline description
+        let parsed = format!("value = {}", raw).parse::<toml::Table>().ok().and_then(|mut table| table.remove("value"));
+        let mut error = String::new();
+        for value in parsed.into_iter().chain([toml::Value::String(raw.to_string())]) {
+            let mut document = current.clone();
+            set_key(&mut document, &path, value).map_err(invalid)?;
+            let mut unknown = Vec::new();
+            match serde_ignored::deserialize(document, |key| unknown.push(config_key(&key))) {
+                Ok(_) if !unknown.is_empty() => return Err(invalid(format!("unknown key {}", unknown.join(", ")))),
+                Ok(config) => return Ok(config),
+                Err(e) => error = e.to_string(),
+            }
+        }
+        Err(invalid(error.trim().replace('\n', " ")))
+    }
+
+    /// JSON Schema of the configuration files, for editor completion and validation.
+    /// Unlike loading, the schema rejects unknown keys so editors flag typos.
+    pub fn json_schema() -> Schema {
+        SchemaSettings::default()
+            .with_transform(RecursiveTransform(|schema: &mut Schema| {
+                if schema.get("properties").is_some() && schema.get("additionalProperties").is_none() {
+                    schema.insert("additionalProperties".to_string(), false.into());
+                }
+            }))
+            .into_generator()
+            .into_root_schema_for::<Config>()
+    }
+
+    /// Directory holding `default.toml`, `local.toml` and one override file per environment
+    pub(crate) const ENVIRONMENT_CONFIG_DIR: &'static str = "config";
+
+    /// Path of the override file applied for `--env <environment>`
+    pub(crate) fn environment_config_path(environment: &str) -> String {
+        format!("{}/{}.toml", Self::ENVIRONMENT_CONFIG_DIR, environment)
     }
 
     /// Get standard configuration file paths in order of precedence
-    fn standard_config_paths() -> Vec<PathBuf> {
+    pub(crate) fn standard_config_paths() -> Vec<PathBuf> {
         vec![
             PathBuf::from("config.toml"),
             PathBuf::from("config/default.toml"),
@@ -276,33 +858,106 @@ impl Config {
 
     /// Merge this config with another, with the other taking precedence
     pub fn merge(mut self, other: Self) -> Self {
+        if other.required_version.is_some() {
+            self.required_version = other.required_version;
+        }
+
         // Merge database config
         if other.database.connection_string.is_some() {
             self.database.connection_string = other.database.connection_string;
         }
         self.database.timeout = other.database.timeout;
         self.database.max_retries = other.database.max_retries;
+        if !other.database.session_setup.is_empty() {
+            self.database.session_setup = other.database.session_setup;
+        }
+        if other.database.schema.is_some() {
+            self.database.schema = other.database.schema;
+        }
+        if other.database.lock_timeout_ms.is_some() {
+            self.database.lock_timeout_ms = other.database.lock_timeout_ms;
+        }
+        if other.database.statement_timeout_ms.is_some() {
+            self.database.statement_timeout_ms = other.database.statement_timeout_ms;
+        }
+        self.database.lock_retries = other.database.lock_retries;
+        self.database.lock_retry_delay_ms = other.database.lock_retry_delay_ms;
+        if other.database.large_table_rows.is_some() {
+            self.database.large_table_rows = other.database.large_table_rows;
+        }
+        if other.database.large_table_mb.is_some() {
+            self.database.large_table_mb = other.database.large_table_mb;
+        }
+        self.database.query_tag = other.database.query_tag;
+        let odbc = other.database.odbc;
+        self.database.odbc = OdbcConfig {
+            login_timeout_secs: odbc.login_timeout_secs.or(self.database.odbc.login_timeout_secs),
+            packet_size: odbc.packet_size.or(self.database.odbc.packet_size),
+            autocommit: odbc.autocommit.or(self.database.odbc.autocommit),
+            isolation_level: odbc.isolation_level.or(self.database.odbc.isolation_level),
+        };
 
         // Merge migrations config
         self.migrations.path = other.migrations.path;
         self.migrations.dialect = other.migrations.dialect;
+        self.migrations.dialect_strict = other.migrations.dialect_strict;
         self.migrations.validate_sql = other.migrations.validate_sql;
         self.migrations.file_pattern = other.migrations.file_pattern;
+        self.migrations.recursive = other.migrations.recursive;
+        self.migrations.repeatable_history = other.migrations.repeatable_history;
+        self.migrations.paths = other.migrations.paths;
+        self.migrations.skip = other.migrations.skip;
+        self.migrations.templates_dir = other.migrations.templates_dir;
+        self.migrations.id_strategy = other.migrations.id_strategy;
 
         // Merge logging config
         self.logging.level = other.logging.level;
         self.logging.colored = other.logging.colored;
         self.logging.format = other.logging.format;
+        if other.logging.file.is_some() {
+            self.logging.file = other.logging.file;
+        }
+        self.logging.rotation = other.logging.rotation;
+        self.logging.max_size_mb = other.logging.max_size_mb;
+        self.logging.max_files = other.logging.max_files;
+        if other.logging.sql_log.is_some() {
+            self.logging.sql_log = other.logging.sql_log;
+        }
 
         // Merge behavior config
         self.behavior.auto_create_migrations_dir = other.behavior.auto_create_migrations_dir;
         self.behavior.require_confirmation = other.behavior.require_confirmation;
         self.behavior.default_dry_run = other.behavior.default_dry_run;
+        self.behavior.protected_targets = other.behavior.protected_targets;
+        self.behavior.approval_keys = other.behavior.approval_keys;
+        self.behavior.on_failure = other.behavior.on_failure;
 
         // Merge validation config
         self.validation.enable_sqlglot = other.validation.enable_sqlglot;
         self.validation.strict_validation = other.validation.strict_validation;
         self.validation.max_file_size_mb = other.validation.max_file_size_mb;
+        if other.validation.stream_threshold_mb.is_some() {
+            self.validation.stream_threshold_mb = other.validation.stream_threshold_mb;
+        }
+        self.validation.fail_on_invalid_files = other.validation.fail_on_invalid_files;
+        self.validation.checksum_normalization = other.validation.checksum_normalization;
+        self.validation.checksum = other.validation.checksum;
+        self.validation.require_signatures = other.validation.require_signatures;
+        self.validation.signature_keys = other.validation.signature_keys;
+        if other.validation.gpg_keyring.is_some() {
+            self.validation.gpg_keyring = other.validation.gpg_keyring;
+        }
+        self.validation.lockfile = other.validation.lockfile;
+        self.validation.require_lockfile = other.validation.require_lockfile;
+
+        // Merge kubernetes config
+        self.kubernetes = other.kubernetes;
+
+        // Merge tracker config
+        self.tracker = other.tracker;
+
+        // Merge dialect sections
+        self.dialect.merge(other.dialect);
 
         self
     }
@@ -328,6 +983,18 @@ pub enum ConfigError {
     #[error("Failed to parse config file '{0}': {1}")]
     Parse(String, String),
 
+    #[error("Unknown keys in config file '{0}': {1}")]
+    UnknownKeys(String, String),
+
+    #[error("Invalid --set '{0}': {1}")]
+    Override(String, String),
+
+    #[error("Invalid required_version '{0}': {1}")]
+    InvalidRequiredVersion(String, String),
+
+    #[error("This configuration requires deriddl {0}, but this is deriddl {1}; install a matching version")]
+    UnsupportedVersion(String, String),
+
     #[error("Failed to write config file '{0}': {1}")]
     FileWrite(String, String),
 
@@ -355,6 +1022,7 @@ mod tests {
         assert_eq!(config.migrations.dialect, "postgres");
         assert!(config.migrations.validate_sql);
         assert_eq!(config.migrations.file_pattern, r"^\d{4}_.*\.sql$");
+        assert!(!config.migrations.recursive);
 
         // Test logging defaults
         assert_eq!(config.logging.level, "info");
@@ -370,6 +1038,8 @@ mod tests {
         assert!(config.validation.enable_sqlglot);
         assert!(!config.validation.strict_validation);
         assert_eq!(config.validation.max_file_size_mb, 10);
+        assert!(config.validation.fail_on_invalid_files);
+        assert_eq!(config.validation.checksum_normalization, ChecksumNormalization::Strict);
         
         // Test baseline defaults
         assert_eq!(config.baseline.default_version, None);
@@ -377,6 +1047,7 @@ mod tests {
         assert!(!config.baseline.auto_generate_schema);
         assert!(config.baseline.require_confirmation);
         assert!(!config.baseline.allow_on_existing_migrations);
+        assert!(!config.tracker.store_content);
     }
 
     #[test]
@@ -414,16 +1085,21 @@ validate_sql = false
 level = "debug"
 colored = false
 format = "json"
+file = "logs/deriddl.log"
+rotation = "daily"
 
 [behavior]
 auto_create_migrations_dir = true
 require_confirmation = false
 default_dry_run = true
+on_failure = "skip-and-continue"
 
 [validation]
 enable_sqlglot = false
 strict_validation = true
 max_file_size_mb = 20
+checksum_normalization = "semantic"
+checksum = "crc32"
         "#;
 
         let config: Config = toml::from_str(toml_content).unwrap();
@@ -437,12 +1113,18 @@ max_file_size_mb = 20
         assert_eq!(config.logging.level, "debug");
         assert!(!config.logging.colored);
         assert_eq!(config.logging.format, "json");
+        assert_eq!(config.logging.file.as_deref(), Some("logs/deriddl.log"));
+        assert_eq!(config.logging.rotation, LogRotation::Daily);
         assert!(config.behavior.auto_create_migrations_dir);
         assert!(!config.behavior.require_confirmation);
+        assert_eq!(config.behavior.on_failure, FailurePolicy::SkipAndContinue);
         assert!(config.behavior.default_dry_run);
         assert!(!config.validation.enable_sqlglot);
         assert!(config.validation.strict_validation);
         assert_eq!(config.validation.max_file_size_mb, 20);
+        assert_eq!(config.validation.checksum_normalization, ChecksumNormalization::Semantic);
+        assert_eq!(config.validation.checksum, ChecksumAlgorithm::Crc32);
+        assert_eq!(config.migrations.id_strategy, MigrationIdScheme::Version);
     }
 
     #[test]
@@ -467,6 +1149,110 @@ dialect = "sqlite"
         assert_eq!(config.logging.level, "info");
     }
 
+    #[test]
+    fn test_config_migration_sources() {
+        let toml_content = r#"
+[migrations]
+paths = [
+    "./migrations/core",
+    { path = "./migrations/analytics", namespace = "analytics" },
+]
+        "#;
+
+        let config: Config = toml::from_str(toml_content).unwrap();
+
+        assert_eq!(config.migrations.paths.len(), 2);
+        assert_eq!(config.migrations.paths[0].path(), "./migrations/core");
+        assert_eq!(config.migrations.paths[0].namespace(), None);
+        assert_eq!(config.migrations.paths[1].path(), "./migrations/analytics");
+        assert_eq!(config.migrations.paths[1].namespace(), Some("analytics"));
+    }
+
+    #[test]
+    fn test_config_odbc_attributes() {
+        let toml_content = r#"
+[database.odbc]
+login_timeout_secs = 10
+autocommit = true
+isolation_level = "read_committed"
+
+[database.query_tag]
+comment = true
+        "#;
+
+        let config: Config = toml::from_str(toml_content).unwrap();
+        let odbc = &config.database.odbc;
+        assert_eq!(odbc.login_timeout_secs, Some(10));
+        assert_eq!(odbc.packet_size, None);
+        assert_eq!(odbc.autocommit, Some(true));
+        assert_eq!(odbc.isolation_level.map(IsolationLevel::as_sql), Some("READ COMMITTED"));
+        assert_eq!(config.database.query_tag, QueryTagConfig { comment: true, session: false });
+    }
+
+    #[test]
+    fn test_config_reports_unknown_keys() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config_content = r#"
+[migrations]
+dialetc = "mysql"
+
+[dialect.postgres]
+aplication_name = "deriddl"
+        "#;
+        fs::write(temp_file.path(), config_content).unwrap();
+
+        let (config, unknown) = Config::parse_file(temp_file.path().to_str().unwrap()).unwrap();
+        assert_eq!(config.migrations.dialect, "postgres");
+        assert_eq!(unknown, vec!["migrations.dialetc", "dialect.postgres.aplication_name"]);
+
+        let path = temp_file.path().to_str().unwrap();
+        assert!(Config::load(ConfigFiles { path: Some(path), strict: false }, None).is_ok());
+        assert!(matches!(
+            Config::load(ConfigFiles { path: Some(path), strict: true }, None),
+            Err(ConfigError::UnknownKeys(..))
+        ));
+    }
+
+    #[test]
+    fn test_config_overrides() {
+        let overrides = [
+            "migrations.validate_sql=false",
+            "migrations.dialect=mysql",
+            "database.lock_timeout_ms=5000",
+            "migrations.skip=[\"7\", \"analytics:3\"]",
+            "dialect.postgres.application_name=deriddl-ci",
+            "baseline.default_description=2024",
+        ]
+        .map(String::from);
+        let config = Config::default().with_overrides(&overrides).unwrap();
+        assert!(!config.migrations.validate_sql);
+        assert_eq!(config.migrations.dialect, "mysql");
+        assert_eq!(config.database.lock_timeout_ms, Some(5000));
+        assert_eq!(config.migrations.skip, vec!["7", "analytics:3"]);
+        assert_eq!(config.dialect.postgres.unwrap().application_name.as_deref(), Some("deriddl-ci"));
+        assert_eq!(config.baseline.default_description, "2024");
+        // Untouched settings keep their values
+        assert_eq!(config.database.timeout, 30);
+
+        for invalid in ["migrations.dialetc=mysql", "database.timeout=soon", "migrations.path", "migrations..path=x", "migrations.path.sub=x"] {
+            let error = Config::default().with_overrides(&[invalid.to_string()]).unwrap_err();
+            assert!(matches!(error, ConfigError::Override(..)), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_config_json_schema() {
+        let schema = serde_json::to_value(Config::json_schema()).unwrap();
+        assert_eq!(schema["additionalProperties"], false);
+        assert!(schema["properties"]["migrations"].is_object());
+
+        let migrations = &schema["$defs"]["MigrationsConfig"];
+        assert_eq!(migrations["additionalProperties"], false);
+        assert_eq!(migrations["properties"]["dialect"]["default"], "postgres");
+        assert!(migrations["properties"]["path"]["description"].is_null());
+        assert_eq!(schema["$defs"]["FailurePolicy"]["oneOf"].as_array().map(Vec::len), Some(3));
+    }
+
     #[test]
     fn test_config_load_from_file() {
         let temp_file = NamedTempFile::new().unwrap();
@@ -511,12 +1297,22 @@ path = "./test-migrations"
                 connection_string: Some("base-connection".to_string()),
                 timeout: 30,
                 max_retries: 3,
+                session_setup: vec!["SET ROLE base".to_string()],
+                statement_timeout_ms: Some(600_000),
+                ..DatabaseConfig::default()
             },
             migrations: MigrationsConfig {
                 path: "./base-migrations".to_string(),
                 dialect: "postgres".to_string(),
+                dialect_strict: false,
                 validate_sql: true,
                 file_pattern: "base-pattern".to_string(),
+                recursive: false,
+                paths: Vec::new(),
+                repeatable_history: false,
+                skip: Vec::new(),
+                templates_dir: default_templates_dir(),
+                id_strategy: MigrationIdScheme::Version,
             },
             ..Config::default()
         };
@@ -526,12 +1322,21 @@ path = "./test-migrations"
                 connection_string: Some("override-connection".to_string()),
                 timeout: 60,
                 max_retries: 5,
+                session_setup: vec!["SET ROLE migrator".to_string()],
+                ..DatabaseConfig::default()
             },
             migrations: MigrationsConfig {
                 path: "./override-migrations".to_string(),
                 dialect: "mysql".to_string(),
+                dialect_strict: true,
                 validate_sql: false,
                 file_pattern: "override-pattern".to_string(),
+                recursive: true,
+                paths: vec![MigrationSource::Path("./override-migrations".to_string())],
+                repeatable_history: true,
+                skip: vec!["7".to_string()],
+                templates_dir: "./db/templates".to_string(),
+                id_strategy: MigrationIdScheme::Filename,
             },
             ..Config::default()
         };
@@ -545,10 +1350,17 @@ path = "./test-migrations"
         );
         assert_eq!(merged.database.timeout, 60);
         assert_eq!(merged.database.max_retries, 5);
+        assert_eq!(merged.database.session_setup, vec!["SET ROLE migrator"]);
+        // Unset in the override, so the base timeout is kept
+        assert_eq!(merged.database.statement_timeout_ms, Some(600_000));
         assert_eq!(merged.migrations.path, "./override-migrations");
         assert_eq!(merged.migrations.dialect, "mysql");
+        assert!(merged.migrations.dialect_strict);
         assert!(!merged.migrations.validate_sql);
         assert_eq!(merged.migrations.file_pattern, "override-pattern");
+        assert!(merged.migrations.recursive);
+        assert_eq!(merged.migrations.paths.len(), 1);
+        assert_eq!(merged.migrations.id_strategy, MigrationIdScheme::Filename);
     }
 
     #[test]
@@ -558,6 +1370,8 @@ path = "./test-migrations"
                 connection_string: Some("base-connection".to_string()),
                 timeout: 30,
                 max_retries: 3,
+                session_setup: vec!["SET ROLE base".to_string()],
+                ..DatabaseConfig::default()
             },
             ..Config::default()
         };
@@ -567,6 +1381,8 @@ path = "./test-migrations"
                 connection_string: None,
                 timeout: 60,
                 max_retries: 5,
+                session_setup: Vec::new(),
+                ..DatabaseConfig::default()
             },
             ..Config::default()
         };
@@ -580,6 +1396,8 @@ path = "./test-migrations"
         );
         assert_eq!(merged.database.timeout, 60);
         assert_eq!(merged.database.max_retries, 5);
+        // An empty session setup keeps the base statements
+        assert_eq!(merged.database.session_setup, vec!["SET ROLE base"]);
     }
 
     #[test]
@@ -614,7 +1432,7 @@ path = "./test-migrations"
         std::env::set_current_dir(&temp_dir).unwrap();
 
         // Load config with no files present
-        let config = Config::load(None, None).unwrap();
+        let config = Config::load(ConfigFiles::default(), None).unwrap();
 
         // Should get default config
         let default_config = Config::default();
@@ -644,6 +1462,46 @@ dialect = "mysql"
         assert_eq!(config.migrations.path, "./migrations"); // default
     }
 
+    #[test]
+    fn test_dialect_sections() {
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        fs::write(
+            &config_path,
+            r#"
+[dialect.postgres]
+lock_timeout_ms = 5000
+application_name = "deriddl"
+
+[dialect.databricks]
+catalog = "main"
+
+[dialect.databricks.odbc]
+host = "dbc-1234.cloud.databricks.com"
+warehouse_id = "abcd1234"
+"#,
+        )
+        .unwrap();
+
+        let config = Config::load_from_file(config_path.to_str().unwrap()).unwrap();
+        let postgres = config.dialect.postgres.as_ref().unwrap();
+        assert_eq!(postgres.lock_timeout_ms, Some(5000));
+        assert_eq!(postgres.session_setup_sql(), vec!["SET application_name = 'deriddl'"]);
+        let databricks = config.dialect.databricks.as_ref().unwrap();
+        assert_eq!(databricks.odbc.warehouse_id.as_deref(), Some("abcd1234"));
+        assert_eq!(databricks.odbc.port, 443);
+        assert_eq!(databricks.catalog.as_deref(), Some("main"));
+        assert_eq!(databricks.schema, None);
+
+        // An override without a [dialect.postgres] section keeps the base one
+        let merged = config.merge(Config {
+            dialect: DialectSettings { mysql: Some(Default::default()), ..Default::default() },
+            ..Config::default()
+        });
+        assert!(merged.dialect.postgres.is_some());
+        assert!(merged.dialect.mysql.is_some());
+    }
+
     #[test]
     fn test_config_error_display() {
         let errors = vec![
@@ -651,6 +1509,10 @@ dialect = "mysql"
             ConfigError::Parse("test.toml".to_string(), "Invalid syntax".to_string()),
             ConfigError::FileWrite("test.toml".to_string(), "Permission denied".to_string()),
             ConfigError::Serialize("Invalid value".to_string()),
+            ConfigError::UnknownKeys("test.toml".to_string(), "migrations.dialetc".to_string()),
+            ConfigError::Override("migrations.dialetc=mysql".to_string(), "unknown key".to_string()),
+            ConfigError::InvalidRequiredVersion(">=x".to_string(), "unexpected character".to_string()),
+            ConfigError::UnsupportedVersion(">=0.6".to_string(), "0.1.0".to_string()),
         ];
 
         for error in errors {
@@ -662,8 +1524,50 @@ dialect = "mysql"
                 ConfigError::Parse(path, _) => assert!(error_string.contains(&path)),
                 ConfigError::FileWrite(path, _) => assert!(error_string.contains(&path)),
                 ConfigError::Serialize(_) => assert!(error_string.contains("serialize")),
+                ConfigError::UnknownKeys(path, keys) => assert!(error_string.contains(&path) && error_string.contains(&keys)),
+                ConfigError::Override(setting, _) => assert!(error_string.contains(&setting)),
+                ConfigError::InvalidRequiredVersion(required, _) => assert!(error_string.contains(&required)),
+                ConfigError::UnsupportedVersion(required, running) => {
+                    assert!(error_string.contains(&required) && error_string.contains(&running))
+                }
             }
         }
     }
-}
 
+    #[test]
+    fn test_required_version() {
+        let running = env!("CARGO_PKG_VERSION");
+        let config = |required: &str| Config { required_version: Some(required.to_string()), ..Config::default() };
+
+        assert!(Config::default().check_required_version().is_ok());
+        assert!(config(&format!(">={}", running)).check_required_version().is_ok());
+        assert!(config(&format!("={}, <1000", running)).check_required_version().is_ok());
+        assert!(matches!(
+            config(">=1000.0").check_required_version(),
+            Err(ConfigError::UnsupportedVersion(required, version)) if required == ">=1000.0" && version == running
+        ));
+        assert!(matches!(config(">= banana").check_required_version(), Err(ConfigError::InvalidRequiredVersion(..))));
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let config_content = r#"
+required_version = ">=0.6, <0.8"
+
+[migrations]
+path = "./sql"
+        "#;
+        fs::write(temp_file.path(), config_content).unwrap();
+        let loaded = Config::load_from_file(temp_file.path().to_str().unwrap()).unwrap();
+        assert_eq!(loaded.required_version.as_deref(), Some(">=0.6, <0.8"));
+        assert!(toml::to_string_pretty(&loaded).unwrap().starts_with("required_version = "));
+    }
+
+    #[test]
+    fn test_native_path_handles_windows_paths() {
+        assert_eq!(native_path(r"\\?\C:\db\migrations"), PathBuf::from(r"C:\db\migrations"));
+        assert_eq!(native_path(r"\\?\UNC\server\share\migrations"), PathBuf::from(r"\\server\share\migrations"));
+        assert_eq!(native_path(r"\\server\share"), PathBuf::from(r"\\server\share"));
+        if !cfg!(windows) {
+            assert_eq!(native_path(r"db\migrations"), PathBuf::from("db/migrations"));
+        }
+    }
+}
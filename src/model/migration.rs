@@ -1,8 +1,22 @@
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
+use std::borrow::Cow;
+use std::fs;
+use std::io;
 use std::path::PathBuf;
 
+use super::checksum::{self, ChecksumNormalization, SharedChecksumStrategy};
+use super::migration_id::SharedIdStrategy;
+
 use chrono::{DateTime, Utc};
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// Up/down section marker pairs, matched case-insensitively
+const SECTION_MARKERS: [(&str, &str); 4] = [
+    ("-- +migrate Up", "-- +migrate Down"),
+    ("-- UP", "-- DOWN"),
+    ("-- +goose Up", "-- +goose Down"), // Compatible with goose migrations
+    ("-- @@UP@@", "-- @@DOWN@@"),
+];
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum MigrationType {
@@ -24,13 +38,113 @@ pub struct Migration {
     pub applied_at: Option<DateTime<Utc>>,
     pub execution_time_ms: Option<u32>,
     pub success: bool,
+    pub requires: Vec<u32>, // Versions declared via `-- deriddl:requires` headers
+    pub environments: Vec<String>, // Empty means the migration runs in every environment
+    pub namespace: Option<String>, // Set when loaded from a namespaced migration source
+    pub owner: Option<String>, // Team from the source's owners.toml, set by the loader
+    pub lock_timeout_ms: Option<u64>, // Set via `-- deriddl:lock_timeout 5s`
+    pub lock_retries: Option<u32>, // Set via `-- deriddl:lock_retries 3`
+    pub expect_rows: Option<RowCountExpectation>, // Set via `-- deriddl:expect_rows >= 1`
+    pub guards: Vec<ExecutionGuard>, // Set via `-- deriddl:skip-if <query>` / `-- deriddl:run-if <query>`
+    pub raw: bool, // Set via `-- deriddl:raw`; the SQL is sent to the driver as one batch
+    pub no_transaction: bool, // Set via `-- deriddl:no-transaction`, e.g. for CREATE INDEX CONCURRENTLY
+    pub priority: Option<u32>, // Repeatable ordering prefix, e.g. 10 for `R010__views.sql`
+    pub after: Vec<String>, // Repeatable names declared via `-- deriddl:after R__other`
+    pub depends_on_tables: Vec<String>, // Tables declared via `-- deriddl:depends-on-table users`
+    pub streamed: Option<StreamedContent>, // Set when the up SQL is too large to load and is read from disk
+    pub declared_id: Option<String>, // Set via `-- deriddl:id <uuid>`, used by the uuid id strategy
+    pub checksum_strategy: SharedChecksumStrategy, // Set by the loader from `validation.checksum`
+    pub id_strategy: SharedIdStrategy, // Set by the loader from `migrations.id_strategy`
+}
+
+/// Rows a data migration must affect in total, checked before its transaction commits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowCountExpectation {
+    Exactly(u64),
+    AtLeast(u64),
+    AtMost(u64),
+}
+
+impl RowCountExpectation {
+    pub fn matches(self, rows: u64) -> bool {
+        match self {
+            RowCountExpectation::Exactly(expected) => rows == expected,
+            RowCountExpectation::AtLeast(expected) => rows >= expected,
+            RowCountExpectation::AtMost(expected) => rows <= expected,
+        }
+    }
+}
+
+impl std::fmt::Display for RowCountExpectation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RowCountExpectation::Exactly(expected) => write!(f, "exactly {}", expected),
+            RowCountExpectation::AtLeast(expected) => write!(f, "at least {}", expected),
+            RowCountExpectation::AtMost(expected) => write!(f, "at most {}", expected),
+        }
+    }
+}
+
+/// Query deciding at apply time whether a migration runs; a skipped migration is
+/// recorded as applied without executing it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecutionGuard {
+    /// Skip when the query returns any rows
+    SkipIf(String),
+    /// Skip when the query returns no rows
+    RunIf(String),
+}
+
+impl ExecutionGuard {
+    pub fn query(&self) -> &str {
+        match self {
+            ExecutionGuard::SkipIf(query) | ExecutionGuard::RunIf(query) => query,
+        }
+    }
+
+    /// Whether a guard whose query returned `has_rows` skips the migration
+    pub fn skips(&self, has_rows: bool) -> bool {
+        match self {
+            ExecutionGuard::SkipIf(_) => has_rows,
+            ExecutionGuard::RunIf(_) => !has_rows,
+        }
+    }
+}
+
+impl std::fmt::Display for ExecutionGuard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExecutionGuard::SkipIf(query) => write!(f, "skip-if {}", query),
+            ExecutionGuard::RunIf(query) => write!(f, "run-if {}", query),
+        }
+    }
+}
+
+/// Metadata for a migration whose up SQL stays on disk; `sql_content` is left empty
+/// and the statements are streamed from `file_path` when it is applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamedContent {
+    pub size_bytes: u64,
+    /// Strict checksums of the up SQL accepted alongside the normalized one: the configured
+    /// strategy's and deriddl's original hash
+    pub accepted_checksums: Vec<String>,
 }
 
 impl Migration {
     /// Constructs a new versioned `Migration` with computed checksum and default metadata.
     pub fn new(version: u32, name: String, file_path: PathBuf, sql_content: String) -> Self {
         let (up_sql, down_sql) = Self::parse_migration_content(&sql_content);
-        let checksum = Self::compute_checksum(&up_sql);
+        let checksum = SharedChecksumStrategy::default().checksum(&up_sql, ChecksumNormalization::Strict);
+        let requires = parse_requires(&sql_content);
+        let environments = parse_environments(&sql_content);
+        let (lock_timeout_ms, lock_retries) = parse_lock_settings(&sql_content);
+        let expect_rows = parse_expect_rows(&sql_content);
+        let guards = parse_guards(&sql_content);
+        let raw = !directive_values(&sql_content, "raw").is_empty();
+        let no_transaction = !directive_values(&sql_content, "no-transaction").is_empty();
+        let after = parse_after(&sql_content);
+        let depends_on_tables = parse_depends_on_tables(&sql_content);
+        let declared_id = directive_values(&sql_content, "id").into_iter().next();
 
         Self {
             migration_type: MigrationType::Versioned,
@@ -43,6 +157,23 @@ impl Migration {
             applied_at: None,
             execution_time_ms: None,
             success: true,
+            requires,
+            environments,
+            namespace: None,
+            owner: None,
+            lock_timeout_ms,
+            lock_retries,
+            expect_rows,
+            guards,
+            raw,
+            no_transaction,
+            priority: None,
+            after,
+            depends_on_tables,
+            streamed: None,
+            declared_id,
+            checksum_strategy: SharedChecksumStrategy::default(),
+            id_strategy: SharedIdStrategy::default(),
         }
     }
 
@@ -54,7 +185,18 @@ impl Migration {
         sql_content: String,
     ) -> Self {
         let (up_sql, down_sql) = Self::parse_migration_content(&sql_content);
-        
+        let requires = parse_requires(&sql_content);
+        let environments = parse_environments(&sql_content);
+        let (lock_timeout_ms, lock_retries) = parse_lock_settings(&sql_content);
+        let expect_rows = parse_expect_rows(&sql_content);
+        let guards = parse_guards(&sql_content);
+        let raw = !directive_values(&sql_content, "raw").is_empty();
+        let no_transaction = !directive_values(&sql_content, "no-transaction").is_empty();
+        let after = parse_after(&sql_content);
+        let depends_on_tables = parse_depends_on_tables(&sql_content);
+        let declared_id = directive_values(&sql_content, "id").into_iter().next();
+        let priority = parse_repeatable_filename(&applied.filename).and_then(|(priority, _)| priority);
+
         Self {
             migration_type: applied.migration_type.clone(),
             version: applied.version,
@@ -64,8 +206,25 @@ impl Migration {
             rollback_sql: down_sql,
             checksum: applied.checksum.clone(),
             applied_at: Some(applied.applied_at),
-            execution_time_ms: Some(applied.execution_time_ms as u32),
+            execution_time_ms: u32::try_from(applied.execution_time_ms).ok(),
             success: applied.success,
+            requires,
+            environments,
+            namespace: applied.migration_id.split_once(':').map(|(ns, _)| ns.to_string()),
+            owner: None,
+            lock_timeout_ms,
+            lock_retries,
+            expect_rows,
+            guards,
+            raw,
+            no_transaction,
+            priority,
+            after,
+            depends_on_tables,
+            streamed: None,
+            declared_id,
+            checksum_strategy: SharedChecksumStrategy::default(),
+            id_strategy: SharedIdStrategy::default(),
         }
     }
 
@@ -87,7 +246,17 @@ impl Migration {
     /// Constructs a new repeatable `Migration` with computed checksum and default metadata.
     pub fn new_repeatable(name: String, file_path: PathBuf, sql_content: String) -> Self {
         let (up_sql, down_sql) = Self::parse_migration_content(&sql_content);
-        let checksum = Self::compute_checksum(&up_sql);
+        let checksum = SharedChecksumStrategy::default().checksum(&up_sql, ChecksumNormalization::Strict);
+        let requires = parse_requires(&sql_content);
+        let environments = parse_environments(&sql_content);
+        let (lock_timeout_ms, lock_retries) = parse_lock_settings(&sql_content);
+        let expect_rows = parse_expect_rows(&sql_content);
+        let guards = parse_guards(&sql_content);
+        let raw = !directive_values(&sql_content, "raw").is_empty();
+        let no_transaction = !directive_values(&sql_content, "no-transaction").is_empty();
+        let after = parse_after(&sql_content);
+        let depends_on_tables = parse_depends_on_tables(&sql_content);
+        let declared_id = directive_values(&sql_content, "id").into_iter().next();
 
         Self {
             migration_type: MigrationType::Repeatable,
@@ -100,6 +269,32 @@ impl Migration {
             applied_at: None,
             execution_time_ms: None,
             success: true,
+            requires,
+            environments,
+            namespace: None,
+            owner: None,
+            lock_timeout_ms,
+            lock_retries,
+            expect_rows,
+            guards,
+            raw,
+            no_transaction,
+            priority: None,
+            after,
+            depends_on_tables,
+            streamed: None,
+            declared_id,
+            checksum_strategy: SharedChecksumStrategy::default(),
+            id_strategy: SharedIdStrategy::default(),
+        }
+    }
+
+    /// The identifier of the default `version` id strategy: the version number, or the
+    /// name with R__ prefix for repeatable migrations
+    pub fn version_identifier(&self) -> String {
+        match &self.migration_type {
+            MigrationType::Versioned => self.version.unwrap_or(0).to_string(),
+            MigrationType::Repeatable => format!("R__{}", self.name),
         }
     }
 
@@ -109,19 +304,22 @@ impl Migration {
             MigrationType::Versioned => {
                 format!("{:04}_{}.sql", self.version.unwrap_or(0), self.name)
             }
-            MigrationType::Repeatable => {
-                format!("R__{}.sql", self.name)
-            }
+            MigrationType::Repeatable => match self.priority {
+                Some(priority) => format!("R{:03}__{}.sql", priority, self.name),
+                None => format!("R__{}.sql", self.name),
+            },
         }
     }
     
-    /// Returns a unique identifier for this migration in the database.
-    /// For versioned migrations, this is the version number.
-    /// For repeatable migrations, this is the name with R__ prefix.
+    /// Returns a unique identifier for this migration in the database, as given by its id
+    /// strategy (by default [`Self::version_identifier`]); migrations the strategy has no
+    /// id for fall back to that, and validation reports them.
+    /// Namespaced migrations are prefixed with `<namespace>:`.
     pub fn identifier(&self) -> String {
-        match &self.migration_type {
-            MigrationType::Versioned => self.version.unwrap_or(0).to_string(),
-            MigrationType::Repeatable => format!("R__{}", self.name),
+        let id = self.id_strategy.migration_id(self).unwrap_or_else(|_| self.version_identifier());
+        match &self.namespace {
+            Some(namespace) => format!("{}:{}", namespace, id),
+            None => id,
         }
     }
     
@@ -130,50 +328,111 @@ impl Migration {
         self.migration_type == MigrationType::Repeatable
     }
 
+    /// Returns true if this migration should run in the given environment.
+    /// Unscoped migrations run everywhere; scoped ones only when a matching `--env` is active.
+    pub fn runs_in_environment(&self, environment: Option<&str>) -> bool {
+        if self.environments.is_empty() {
+            return true;
+        }
+        match environment {
+            Some(env) => self.environments.iter().any(|e| e.eq_ignore_ascii_case(env)),
+            None => false,
+        }
+    }
+
+    /// Returns the statements that drop or irreversibly delete data: DROP, TRUNCATE,
+    /// ALTER TABLE ... DROP and DELETE without a WHERE clause.
+    pub fn destructive_statements(&self) -> Vec<String> {
+        checksum::split_statements(&self.sql_content)
+            .into_iter()
+            .filter(|statement| {
+                let upper = statement.to_uppercase();
+                upper.starts_with("DROP ")
+                    || upper.starts_with("TRUNCATE ")
+                    || (upper.starts_with("ALTER TABLE ") && upper.contains(" DROP "))
+                    || (upper.starts_with("DELETE ") && !upper.contains(" WHERE "))
+            })
+            .collect()
+    }
+
+    /// Returns the tables this migration creates, alters, renames, truncates or drops,
+    /// lowercased with identifier quotes removed.
+    pub fn altered_tables(&self) -> Vec<String> {
+        let mut tables: Vec<String> = checksum::split_statements(&self.sql_content)
+            .iter()
+            .filter_map(|statement| statement_table(statement))
+            .collect();
+        tables.sort();
+        tables.dedup();
+        tables
+    }
+
+    /// Returns the existing tables whose rows this migration locks or rewrites: targets of
+    /// ALTER TABLE, UPDATE and DELETE, normalized like [`altered_tables`](Self::altered_tables).
+    pub fn rewritten_tables(&self) -> Vec<String> {
+        let mut tables: Vec<String> = checksum::split_statements(&self.sql_content)
+            .iter()
+            .filter_map(|statement| rewritten_table_re().captures(statement))
+            .map(|captures| normalize_table_name(&captures[1]))
+            .collect();
+        tables.sort();
+        tables.dedup();
+        tables
+    }
+
+    /// Returns true if any of the given altered tables is listed in `depends-on-table`.
+    /// Unqualified dependencies also match schema-qualified tables (`users` matches `app.users`).
+    pub fn depends_on_any_table(&self, altered: &[String]) -> bool {
+        self.depends_on_tables.iter().any(|dependency| {
+            altered.iter().any(|table| {
+                table == dependency
+                    || (!dependency.contains('.')
+                        && table.rsplit('.').next() == Some(dependency.as_str()))
+            })
+        })
+    }
+
+    /// Returns true if this migration declares explicit dependencies on other versions.
+    pub fn has_requirements(&self) -> bool {
+        !self.requires.is_empty()
+    }
+
     /// Parses migration content to separate up/down SQL sections
     /// Supports two formats:
     /// 1. Separator-based: -- +migrate Up / -- +migrate Down
     /// 2. Section-based: -- UP / -- DOWN
+    ///
+    /// Markers must be on their own line; case, spacing and CRLF line endings don't matter.
     fn parse_migration_content(content: &str) -> (String, Option<String>) {
         let content = content.trim();
         
-        // Try different separator patterns
-        let separators = [
-            ("-- +migrate Up", "-- +migrate Down"),
-            ("-- UP", "-- DOWN"),
-            ("-- +goose Up", "-- +goose Down"), // Compatible with goose migrations
-            ("-- @@UP@@", "-- @@DOWN@@"),
-        ];
-        
-        for (up_marker, down_marker) in &separators {
-            if let Some((up_sql, down_sql)) = Self::split_by_markers(content, up_marker, down_marker) {
-                return (up_sql.trim().to_string(), Some(down_sql.trim().to_string()));
-            }
+        if let Some((up_sql, down_sql)) = Self::split_by_markers(content) {
+            return (up_sql.trim().to_string(), Some(down_sql.trim().to_string()));
         }
         
         // If no separators found, treat entire content as up migration
         (content.to_string(), None)
     }
     
-    /// Helper function to split content by up/down markers
-    fn split_by_markers(content: &str, up_marker: &str, down_marker: &str) -> Option<(String, String)> {
-        // Find the up marker (case insensitive)
-        let up_pos = content.to_lowercase().find(&up_marker.to_lowercase())?;
-        
-        // Find the down marker after the up marker
-        let search_start = up_pos + up_marker.len();
-        let remaining_content = &content[search_start..];
-        let down_pos = remaining_content.to_lowercase().find(&down_marker.to_lowercase())?;
-        
-        // Extract up SQL (everything after up marker until down marker)
-        let up_end = search_start + down_pos;
-        let up_sql = &content[up_pos + up_marker.len()..up_end];
-        
-        // Extract down SQL (everything after down marker)
-        let down_start = search_start + down_pos + down_marker.len();
-        let down_sql = &content[down_start..];
-        
-        Some((up_sql.to_string(), down_sql.to_string()))
+    /// Helper function to split content into the lines between the up and down marker
+    /// lines and the lines after the down marker
+    fn split_by_markers(content: &str) -> Option<(&str, &str)> {
+        let mut offset = 0;
+        let mut up_start = None;
+
+        for line in content.split_inclusive('\n') {
+            let line_end = offset + line.len();
+            match up_start {
+                None if is_section_marker(line, false) => up_start = Some(line_end),
+                Some(start) if is_section_marker(line, true) => {
+                    return Some((&content[start..offset], &content[line_end..]));
+                }
+                _ => {}
+            }
+            offset = line_end;
+        }
+
+        None
     }
     
     /// Returns true if this migration has rollback SQL available
@@ -186,11 +445,53 @@ impl Migration {
         self.rollback_sql.as_deref()
     }
 
-    /// Computes a stable checksum based on the SQL content.
-    fn compute_checksum(content: &str) -> String {
-        let mut hasher = DefaultHasher::new();
-        content.hash(&mut hasher);
-        format!("{:x}", hasher.finish())
+    /// Recomputes the checksum with `strategy` and the given normalization mode, and keeps
+    /// the strategy for [`Self::matches_checksum`].
+    /// Streamed migrations are checksummed by the loader while the file is read.
+    pub fn use_checksum_strategy(&mut self, strategy: SharedChecksumStrategy, normalization: ChecksumNormalization) {
+        self.checksum_strategy = strategy;
+        if self.streamed.is_none() {
+            self.checksum = self.checksum_strategy.checksum(&self.sql_content, normalization);
+        }
+    }
+
+    /// Returns true if a recorded checksum matches this migration, accepting strict
+    /// checksums recorded before a normalization mode was configured and checksums of
+    /// deriddl's original hash, recorded before checksum strategies existed.
+    pub fn matches_checksum(&self, recorded: &str) -> bool {
+        if recorded == self.checksum {
+            return true;
+        }
+        match &self.streamed {
+            Some(streamed) => streamed.accepted_checksums.iter().any(|accepted| accepted == recorded),
+            None => {
+                recorded == self.checksum_strategy.checksum(&self.sql_content, ChecksumNormalization::Strict)
+                    || recorded == checksum::compute_checksum(&self.sql_content, ChecksumNormalization::Strict)
+            }
+        }
+    }
+
+    /// The batch a `-- deriddl:raw` migration sends for `sql` (its up or down SQL):
+    /// everything after the directive line, unsplit
+    pub fn raw_batch<'s>(&self, sql: &'s str) -> &'s str {
+        let mut rest = sql;
+        while let Some((line, remaining)) = rest.split_once('\n') {
+            rest = remaining;
+            if !directive_values(line, "raw").is_empty() {
+                return rest.trim();
+            }
+        }
+        sql.trim()
+    }
+
+    /// Returns the up SQL, reading it from disk for streamed migrations
+    pub fn up_sql(&self) -> io::Result<Cow<'_, str>> {
+        if self.streamed.is_none() {
+            return Ok(Cow::Borrowed(&self.sql_content));
+        }
+        let content = fs::read_to_string(&self.file_path)?;
+        let (up_sql, _) = Self::parse_migration_content(content.trim_start_matches('\u{feff}'));
+        Ok(Cow::Owned(up_sql))
     }
 }
 
@@ -198,9 +499,9 @@ impl Migration {
 fn extract_name_from_filename(filename: &str) -> String {
     let stem = filename.strip_suffix(".sql").unwrap_or(filename);
     
-    if stem.starts_with("R__") {
-        // Repeatable migration: R__create_view.sql -> create_view
-        stem.strip_prefix("R__").unwrap_or(stem).to_string()
+    if let Some((_, name)) = split_repeatable_stem(stem) {
+        // Repeatable migration: R__create_view.sql / R010__create_view.sql -> create_view
+        name.to_string()
     } else if let Some(underscore_pos) = stem.find('_') {
         // Versioned migration: 0001_create_users.sql -> create_users
         stem[underscore_pos + 1..].to_string()
@@ -210,3 +511,239 @@ fn extract_name_from_filename(filename: &str) -> String {
     }
 }
 
+/// Returns the values of all `-- deriddl:<name> <value>` header directives in the content
+/// Returns true if the line is an up (or, with `down`, a down) section marker such as
+/// `-- +migrate Up`. Matching ignores case, surrounding whitespace and spacing between
+/// words, so `--+migrate  up\r` is accepted; trailing text after the marker is ignored.
+pub(crate) fn is_section_marker(line: &str, down: bool) -> bool {
+    let Some(comment) = line.trim().strip_prefix("--") else {
+        return false;
+    };
+
+    SECTION_MARKERS.iter().any(|(up_marker, down_marker)| {
+        let marker = if down { down_marker } else { up_marker };
+        let mut rest = comment.trim_start();
+        for word in marker.trim_start_matches("--").split_whitespace() {
+            match rest.get(..word.len()) {
+                Some(head) if head.eq_ignore_ascii_case(word) => rest = &rest[word.len()..],
+                _ => return false,
+            }
+            if !(rest.is_empty() || rest.starts_with(char::is_whitespace)) {
+                return false;
+            }
+            rest = rest.trim_start();
+        }
+        true
+    })
+}
+
+pub(crate) fn directive_values(content: &str, name: &str) -> Vec<String> {
+    let prefix = format!("deriddl:{}", name);
+
+    content
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("--"))
+        .map(str::trim)
+        .filter_map(|directive| {
+            let rest = directive.strip_prefix(&prefix)?;
+            // Require a separator so `deriddl:requires` doesn't match `deriddl:requiresfoo`
+            if rest.is_empty() || rest.starts_with(char::is_whitespace) {
+                Some(rest.trim().to_string())
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Parses `-- deriddl:requires 0005,0009` headers into a sorted list of required versions
+fn parse_requires(content: &str) -> Vec<u32> {
+    let mut requires: Vec<u32> = directive_values(content, "requires")
+        .iter()
+        .flat_map(|value| {
+            value
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter_map(|v| v.trim().parse::<u32>().ok())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    requires.sort_unstable();
+    requires.dedup();
+    requires
+}
+
+/// Parses `-- deriddl:lock_timeout <duration>` and `-- deriddl:lock_retries <n>` headers.
+/// Durations accept `ms`, `s` and `m` suffixes; a bare number is seconds.
+fn parse_lock_settings(content: &str) -> (Option<u64>, Option<u32>) {
+    let timeout = directive_values(content, "lock_timeout")
+        .last()
+        .and_then(|value| parse_duration_ms(value));
+    let retries = directive_values(content, "lock_retries")
+        .last()
+        .and_then(|value| value.parse::<u32>().ok());
+    (timeout, retries)
+}
+
+/// Parses `-- deriddl:expect_rows <n>`, `>= <n>` or `<= <n>` headers
+fn parse_expect_rows(content: &str) -> Option<RowCountExpectation> {
+    let value = directive_values(content, "expect_rows").pop()?;
+    let (constructor, count): (fn(u64) -> RowCountExpectation, &str) = if let Some(count) = value.strip_prefix(">=") {
+        (RowCountExpectation::AtLeast, count)
+    } else if let Some(count) = value.strip_prefix("<=") {
+        (RowCountExpectation::AtMost, count)
+    } else {
+        (RowCountExpectation::Exactly, value.strip_prefix('=').unwrap_or(&value))
+    };
+    count.trim().parse().ok().map(constructor)
+}
+
+/// Parses durations like `500ms`, `5s`, `2m` or `10` (seconds) into milliseconds
+pub(crate) fn parse_duration_ms(value: &str) -> Option<u64> {
+    let value = value.trim().to_ascii_lowercase();
+    if let Some(ms) = value.strip_suffix("ms") {
+        ms.trim().parse().ok()
+    } else if let Some(s) = value.strip_suffix('s') {
+        s.trim().parse::<u64>().ok().map(|s| s * 1000)
+    } else if let Some(m) = value.strip_suffix('m') {
+        m.trim().parse::<u64>().ok().map(|m| m * 60_000)
+    } else {
+        value.parse::<u64>().ok().map(|s| s * 1000)
+    }
+}
+
+/// Parses `-- deriddl:skip-if <query>` and `-- deriddl:run-if <query>` headers, in file order
+fn parse_guards(content: &str) -> Vec<ExecutionGuard> {
+    // A trailing `;` is allowed but not sent with the query
+    let query = |line: &str, name: &str| {
+        directive_values(line, name)
+            .pop()
+            .map(|query| query.trim_end_matches(';').trim_end().to_string())
+            .filter(|query| !query.is_empty())
+    };
+    content
+        .lines()
+        .filter_map(|line| {
+            query(line, "skip-if")
+                .map(ExecutionGuard::SkipIf)
+                .or_else(|| query(line, "run-if").map(ExecutionGuard::RunIf))
+        })
+        .collect()
+}
+
+/// Parses `-- deriddl:after R__base_views,R__other` headers into repeatable migration names
+fn parse_after(content: &str) -> Vec<String> {
+    let mut after: Vec<String> = directive_values(content, "after")
+        .iter()
+        .flat_map(|value| {
+            value
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|name| !name.is_empty())
+                .map(repeatable_name)
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    after.sort();
+    after.dedup();
+    after
+}
+
+/// Strips the `R__` / `R010__` prefix and `.sql` suffix from a repeatable reference
+fn repeatable_name(reference: &str) -> String {
+    let stem = reference.strip_suffix(".sql").unwrap_or(reference);
+    match split_repeatable_stem(stem) {
+        Some((_, name)) => name.to_string(),
+        None => stem.to_string(),
+    }
+}
+
+/// Parses a repeatable migration filename (`R__name.sql` or `R010__name.sql`) into its
+/// optional priority and name
+pub(crate) fn parse_repeatable_filename(filename: &str) -> Option<(Option<u32>, &str)> {
+    split_repeatable_stem(filename.strip_suffix(".sql")?)
+}
+
+fn split_repeatable_stem(stem: &str) -> Option<(Option<u32>, &str)> {
+    let (prefix, name) = stem.split_once("__")?;
+    let digits = prefix.strip_prefix('R')?;
+    if name.is_empty() {
+        return None;
+    }
+    if digits.is_empty() {
+        return Some((None, name));
+    }
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok().map(|priority| (Some(priority), name))
+}
+
+/// Parses `-- deriddl:depends-on-table users,orders` headers into normalized table names
+fn parse_depends_on_tables(content: &str) -> Vec<String> {
+    let mut tables: Vec<String> = directive_values(content, "depends-on-table")
+        .iter()
+        .flat_map(|value| {
+            value
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|table| !table.is_empty())
+                .map(normalize_table_name)
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    tables.sort();
+    tables.dedup();
+    tables
+}
+
+fn altered_table_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r#"(?i)^(?:ALTER\s+TABLE(?:\s+ONLY)?|DROP\s+TABLE|TRUNCATE(?:\s+TABLE)?|RENAME\s+TABLE|CREATE(?:\s+OR\s+REPLACE)?\s+TABLE)\s+(?:IF\s+(?:NOT\s+)?EXISTS\s+)?([\w."`\[\]]+)"#,
+        )
+        .expect("valid altered table regex")
+    })
+}
+
+fn rewritten_table_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r#"(?i)^(?:ALTER\s+TABLE(?:\s+ONLY)?(?:\s+IF\s+EXISTS)?|UPDATE(?:\s+ONLY)?|DELETE\s+FROM(?:\s+ONLY)?)\s+([\w."`\[\]]+)"#)
+            .expect("valid rewritten table regex")
+    })
+}
+
+/// The table a CREATE/ALTER/RENAME/TRUNCATE/DROP TABLE statement targets, normalized
+/// like [`Migration::altered_tables`]
+pub fn statement_table(statement: &str) -> Option<String> {
+    altered_table_re()
+        .captures(statement)
+        .map(|captures| normalize_table_name(&captures[1]))
+}
+
+/// Lowercases a table name and strips identifier quoting ("x", `x`, [x])
+fn normalize_table_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, '"' | '`' | '[' | ']'))
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Parses `-- deriddl:environments dev,staging` headers into a list of environment names
+fn parse_environments(content: &str) -> Vec<String> {
+    let mut environments: Vec<String> = directive_values(content, "environments")
+        .iter()
+        .flat_map(|value| {
+            value
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|e| !e.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    environments.dedup();
+    environments
+}
@@ -0,0 +1,146 @@
+use super::Migration;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::Arc;
+
+/// How migrations are identified in schema_migrations (`migrations.id_strategy`, or a
+/// custom strategy), shared by the loader and the migrations it loads. Versions by default.
+#[derive(Clone)]
+pub struct SharedIdStrategy(Arc<dyn MigrationIdStrategy>);
+
+impl SharedIdStrategy {
+    pub fn new(strategy: impl MigrationIdStrategy + 'static) -> Self {
+        Self(Arc::new(strategy))
+    }
+}
+
+impl Default for SharedIdStrategy {
+    fn default() -> Self {
+        Self::new(VersionId)
+    }
+}
+
+impl Deref for SharedIdStrategy {
+    type Target = dyn MigrationIdStrategy;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
+
+impl std::fmt::Debug for SharedIdStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// How a migration's id in schema_migrations is derived, so projects moving from another
+/// tool can keep the ids it recorded
+pub trait MigrationIdStrategy: Send + Sync {
+    /// Name in `migrations.id_strategy`
+    fn name(&self) -> &'static str;
+
+    /// The id of `migration`, before its namespace prefix. Fails with the reason when the
+    /// migration has none under this strategy.
+    fn migration_id(&self, migration: &Migration) -> Result<String, String>;
+}
+
+/// Built-in id strategies, as named in `migrations.id_strategy`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MigrationIdScheme {
+    #[default]
+    Version,
+    Filename,
+    Uuid,
+}
+
+impl MigrationIdScheme {
+    pub fn strategy(self) -> SharedIdStrategy {
+        match self {
+            MigrationIdScheme::Version => SharedIdStrategy::new(VersionId),
+            MigrationIdScheme::Filename => SharedIdStrategy::new(FilenameId),
+            MigrationIdScheme::Uuid => SharedIdStrategy::new(UuidId),
+        }
+    }
+}
+
+/// The version number, or `R__<name>` for repeatable migrations (the default)
+pub struct VersionId;
+
+impl MigrationIdStrategy for VersionId {
+    fn name(&self) -> &'static str {
+        "version"
+    }
+
+    fn migration_id(&self, migration: &Migration) -> Result<String, String> {
+        Ok(migration.version_identifier())
+    }
+}
+
+/// The file name without `.sql`, e.g. `V3__add_orders` for a file kept from Flyway
+pub struct FilenameId;
+
+impl MigrationIdStrategy for FilenameId {
+    fn name(&self) -> &'static str {
+        "filename"
+    }
+
+    fn migration_id(&self, migration: &Migration) -> Result<String, String> {
+        let filename = match migration.file_path.file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => migration.filename(),
+        };
+        Ok(Path::new(&filename).file_stem().map_or(filename.clone(), |stem| stem.to_string_lossy().to_string()))
+    }
+}
+
+/// The UUID declared with `-- deriddl:id <uuid>`, which `new` adds to the files it creates
+pub struct UuidId;
+
+impl MigrationIdStrategy for UuidId {
+    fn name(&self) -> &'static str {
+        "uuid"
+    }
+
+    fn migration_id(&self, migration: &Migration) -> Result<String, String> {
+        match &migration.declared_id {
+            Some(id) if is_uuid(id) => Ok(id.to_lowercase()),
+            Some(id) => Err(format!("'-- deriddl:id {}' is not a UUID", id)),
+            None => Err("no '-- deriddl:id <uuid>' header".to_string()),
+        }
+    }
+}
+
+/// `8-4-4-4-12` hex digits
+fn is_uuid(value: &str) -> bool {
+    let groups: Vec<&str> = value.split('-').collect();
+    groups.len() == 5
+        && groups.iter().zip([8, 4, 4, 4, 12]).all(|(group, len)| group.len() == len && group.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_id_strategies() {
+        let migration = Migration::new(
+            3,
+            "add_orders".to_string(),
+            PathBuf::from("db/V3__add_orders.sql"),
+            "-- deriddl:id 7C9E6679-7425-40DE-944B-E07FC1F90AE7\nCREATE TABLE orders (id INT);".to_string(),
+        );
+        assert_eq!(VersionId.migration_id(&migration).unwrap(), "3");
+        assert_eq!(FilenameId.migration_id(&migration).unwrap(), "V3__add_orders");
+        assert_eq!(UuidId.migration_id(&migration).unwrap(), "7c9e6679-7425-40de-944b-e07fc1f90ae7");
+
+        let undeclared = Migration::new(4, "m".to_string(), PathBuf::from("0004_m.sql"), "SELECT 1;".to_string());
+        assert!(UuidId.migration_id(&undeclared).is_err());
+        assert!(!is_uuid("7c9e6679-7425-40de-944b"));
+        assert_eq!(MigrationIdScheme::default().strategy().name(), "version");
+    }
+}
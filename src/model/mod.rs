@@ -1,5 +1,10 @@
 pub mod migration;
 pub mod config;
+pub mod checksum;
+pub mod migration_id;
+pub mod run;
 
 pub use migration::{Migration, MigrationType};
-pub use config::Config;
\ No newline at end of file
+pub use config::{BehaviorConfig, Config, ConfigFiles, LogRotation, LoggingConfig, MigrationSource};
+pub use checksum::{ChecksumAlgorithm, ChecksumNormalization, SharedChecksumStrategy};
+pub use migration_id::{MigrationIdScheme, SharedIdStrategy};
\ No newline at end of file
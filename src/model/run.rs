@@ -0,0 +1,30 @@
+/// A new random UUID (version 4), e.g. for run ids and `-- deriddl:id` headers
+pub(crate) fn random_uuid() -> String {
+    let mut bytes = [0u8; 16];
+    // Without an entropy source the run is still tagged, only not uniquely
+    if let Err(e) = getrandom::getrandom(&mut bytes) {
+        log::debug!("Could not generate a random UUID: {}", e);
+    }
+    format_uuid(bytes)
+}
+
+fn format_uuid(mut bytes: [u8; 16]) -> String {
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = hex::encode(bytes);
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_uuids_are_v4() {
+        assert_eq!(format_uuid([0xff; 16]), "ffffffff-ffff-4fff-bfff-ffffffffffff");
+        let id = random_uuid();
+        assert_eq!(id.len(), 36);
+        assert_eq!(&id[14..15], "4");
+        assert_ne!(random_uuid(), id);
+    }
+}
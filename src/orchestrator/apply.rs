@@ -1,22 +1,128 @@
-use crate::orchestrator::{MigrationLoader, Validator};
-use crate::tracker::{schema_init, VersionStore};
-use crate::executor::{ConnectionManager, DatabaseExecutor, ConnectionError};
-use log::{info, debug, error};
-use std::time::Instant;
+use crate::logger::SUMMARY_TARGET;
+use crate::orchestrator::{MigrationLoader, Planner, Validator};
+use crate::orchestrator::planner::PlannerError;
+use crate::orchestrator::migration_loader::LoaderOptions;
+use crate::orchestrator::approval::ApprovalError;
+use crate::orchestrator::guard::{GuardError, ProtectionGuard};
+use crate::orchestrator::info::find_migration;
+use crate::orchestrator::capture::{capture_requests, log_capture, result_lines};
+use crate::orchestrator::masking::{expand_masks, MaskError};
+use crate::orchestrator::summary::{tally, Tally};
+use crate::orchestrator::table_size::find_large_tables;
+use crate::tracker::content_archive::record_content;
+use crate::tracker::repeatable_history::record_definition;
+use crate::orchestrator::lock::{is_lock_timeout, LockPolicy};
+use crate::orchestrator::lockfile::LockfileError;
+use crate::orchestrator::signature::SignatureError;
+use crate::orchestrator::script::{write_apply_script, ScriptOptions};
+use crate::dialects::{self, DatabaseDialect};
+use crate::model::config::FailurePolicy;
+use crate::model::migration::ExecutionGuard;
+use crate::tracker::run_progress::{RunCheckpoint, RunStatus};
+use crate::tracker::{schema_init, RunProgress, SqlLog, Target, VersionStore};
+use crate::executor::{is_interrupted, DatabaseExecutor, ConnectionError, StatementOutcome, StatementReader};
+use log::{info, debug, error, warn};
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
 
-pub fn run_apply(conn: &str, path: &str, dry_run: bool) -> Result<(), ApplyError> {
+/// `apply --only`: the pending migrations to apply out of the pending set
+#[derive(Debug, Clone, Default)]
+pub struct OnlySelection {
+    /// Versions, identifiers (e.g. `analytics:3`, `R__views`) or file names
+    pub targets: Vec<String>,
+    /// Apply even when earlier versions are still pending
+    pub allow_gaps: bool,
+}
+
+/// How `apply` runs. [`ApplyOptions::new`] gives a plain apply of every pending migration;
+/// set the other fields for the command line flags.
+#[derive(Clone, Copy)]
+pub struct ApplyOptions<'a> {
+    pub loader: &'a LoaderOptions,
+    pub guard: &'a ProtectionGuard,
+    pub lock: &'a LockPolicy,
+    pub dry_run: bool,
+    /// `--env`, which selects the migrations scoped to an environment
+    pub environment: Option<&'a str>,
+    pub sql_log: Option<&'a Path>,
+    /// `--script-only`: write the SQL to a file instead of running it
+    pub script: Option<&'a ScriptOptions<'a>>,
+    pub release: Option<&'a str>,
+    /// `--only-owner`
+    pub owner: Option<&'a str>,
+    pub only: Option<&'a OnlySelection>,
+    /// Id of the interrupted run `--resume` continues
+    pub resume: Option<&'a str>,
+    pub atomic: bool,
+    /// `behavior.on_failure`; `atomic` always rolls the batch back
+    pub on_failure: FailurePolicy,
+    /// `migrations.repeatable_history`: keep every applied definition of repeatables
+    pub repeatable_history: bool,
+    /// `tracker.store_content`: archive the content of every applied migration
+    pub store_content: bool,
+}
+
+impl<'a> ApplyOptions<'a> {
+    pub fn new(loader: &'a LoaderOptions, guard: &'a ProtectionGuard, lock: &'a LockPolicy) -> Self {
+        Self {
+            loader,
+            guard,
+            lock,
+            dry_run: false,
+            environment: None,
+            sql_log: None,
+            script: None,
+            release: None,
+            owner: None,
+            only: None,
+            resume: None,
+            atomic: false,
+            on_failure: FailurePolicy::default(),
+            repeatable_history: false,
+            store_content: false,
+        }
+    }
+}
+
+pub fn run_apply(target: &Target, path: &str, options: &ApplyOptions) -> Result<ApplyOutcome, ApplyError> {
+    let ApplyOptions { loader, guard, lock, dry_run, environment, sql_log, script, release, owner, only, resume, atomic, .. } = *options;
     info!("Running migration apply");
-    debug!("Connection string length: {}", conn.len());
+    debug!("Connection string length: {}", target.conn.len());
     debug!("Migrations path: {}", path);
     debug!("Dry run mode: {}", dry_run);
+    debug!("Environment: {:?}", environment);
     
     // Load migrations
-    let migrations = MigrationLoader::load_migrations(path)
+    let loaded = MigrationLoader::load_migrations_with_report(path, loader)
         .map_err(|e| ApplyError::LoadFailed(e.to_string()))?;
-        
+
+    if !loaded.invalid.is_empty() {
+        let problems: Vec<String> = loaded
+            .invalid
+            .iter()
+            .map(|f| format!("{}: {}", f.path.display(), f.reason))
+            .collect();
+        if loader.fail_on_invalid_files {
+            error!("Invalid migration files found:");
+            for problem in &problems {
+                error!("  - {}", problem);
+            }
+            return Err(ApplyError::InvalidFiles(problems));
+        }
+        for problem in &problems {
+            warn!("⚠️  Skipping invalid migration file {}", problem);
+        }
+    }
+    let migrations = loaded.migrations;
+
     if migrations.is_empty() {
         info!("No migrations found in {}", path);
-        return Ok(());
+        return Ok(ApplyOutcome::NothingToDo);
     }
     
     info!("Loaded {} migrations", migrations.len());
@@ -30,93 +136,878 @@ pub fn run_apply(conn: &str, path: &str, dry_run: bool) -> Result<(), ApplyError
         }
         return Err(ApplyError::ValidationFailed(validation_issues));
     }
+
+    // Edited or rewritten history must be caught before anything touches the database
+    loader.lockfile.verify(&migrations)?;
+    let plan = guard.ensure_planned_files(&migrations)?;
     
     // Test connection first
-    let connection_manager = ConnectionManager::new()?;
-    connection_manager.test_connection(conn)
+    let connection_manager = target.connection_manager()?;
+    connection_manager.test_connection(&target.conn)
         .map_err(ApplyError::Connection)?;
     info!("✅ Database connection verified");
+
+    // `--resume` continues the plan recorded by an interrupted run
+    let checkpoint = match resume {
+        Some(run_id) => match RunProgress::new(target).load(run_id)? {
+            None => return Err(ApplyError::Resume(run_id.to_string(), "no such run was recorded".to_string())),
+            Some(checkpoint) if checkpoint.status == RunStatus::Completed => {
+                return Err(ApplyError::Resume(run_id.to_string(), "the run already completed".to_string()))
+            }
+            Some(checkpoint) if checkpoint.status == RunStatus::RolledBack => {
+                return Err(ApplyError::Resume(run_id.to_string(), "the run was rolled back; run apply again".to_string()))
+            }
+            Some(checkpoint) => Some(checkpoint),
+        },
+        None => None,
+    };
     
-    // Ensure schema_migrations table exists
-    if !schema_init::check_migration_table_exists(conn)? {
-        info!("schema_migrations table does not exist, creating it");
-        schema_init::init_migration_table(conn)?;
+    let mut table_exists = schema_init::check_migration_table_exists(target)?;
+    
+    // Skip migrations scoped to other environments
+    let (migrations, skipped): (Vec<_>, Vec<_>) = migrations
+        .into_iter()
+        .partition(|m| m.runs_in_environment(environment));
+    for migration in &skipped {
+        info!("⏭️  Skipping {} (environments: {})", migration.filename(), migration.environments.join(", "));
     }
     
     // Get pending migrations
-    let mut version_store = VersionStore::new(conn)?;
-    let pending_migrations = version_store.get_pending_migrations(&migrations)?;
+    let pending_migrations = if table_exists {
+        let mut version_store = target.version_store()?;
+        // A renumbered file would otherwise be pending and run its change a second time
+        let renamed = Validator::detect_renamed_migrations(&version_store.get_applied_migrations()?, &migrations);
+        if !renamed.is_empty() {
+            let problems: Vec<String> = renamed.iter().map(|r| r.to_string()).collect();
+            for problem in &problems {
+                error!("  - {}", problem);
+            }
+            return Err(ApplyError::RenamedMigrations(problems));
+        }
+        version_store.get_pending_migrations(&migrations)?
+    } else {
+        migrations.clone()
+    };
+    let pending_migrations = match owner {
+        Some(owner) => owned_pending(pending_migrations, owner)?,
+        None => pending_migrations,
+    };
+    let pending_migrations = match only {
+        Some(only) => only_pending(pending_migrations, &migrations, only)?,
+        None => pending_migrations,
+    };
+
+    let (skip_listed, pending_migrations): (Vec<_>, Vec<_>) =
+        pending_migrations.into_iter().partition(|m| loader.is_skip_listed(m));
+    let pending_migrations = Planner::order_migrations(pending_migrations)?;
+    let pending_migrations = match &checkpoint {
+        Some(checkpoint) => resumed_pending(pending_migrations, checkpoint),
+        None => pending_migrations,
+    };
+
+    // `--plan-file` runs what was reviewed or nothing at all
+    if let Some(plan) = &plan {
+        plan.ensure_matches(&pending_migrations)?;
+        info!("✅ Pending migrations match the plan ({} migration(s))", plan.migrations.len());
+    }
+
+    // Unsigned or tampered files must be rejected before anything is written to the database
+    if !dry_run {
+        loader.signatures.verify_all(&pending_migrations)?;
+    }
+
+    // Ensure schema_migrations table exists (script-only mode writes the DDL instead)
+    if !table_exists && script.is_none() && !dry_run {
+        info!("schema_migrations table does not exist, creating it");
+        schema_init::init_migration_table(target)?;
+        table_exists = true;
+    }
+
+    // Skip-listed migrations never run here; recording them keeps them out of later runs
+    if !skip_listed.is_empty() {
+        if dry_run || script.is_some() {
+            for migration in &skip_listed {
+                info!("⏭️  {} is on the skip list and would be recorded as skipped", migration.filename());
+            }
+        } else {
+            let mut version_store = target.version_store()?;
+            for migration in &skip_listed {
+                version_store.record_migration_skip_listed(migration)?;
+            }
+            tally(Tally::Skipped, skip_listed.len());
+        }
+    }
     
     if pending_migrations.is_empty() {
-        info!("✅ No pending migrations to apply");
-        return Ok(());
+        info!(target: SUMMARY_TARGET, "✅ No pending migrations to apply");
+        return Ok(ApplyOutcome::NothingToDo);
     }
     
     info!("Found {} pending migrations", pending_migrations.len());
+
+    if atomic {
+        let transactional_ddl = lock.resolve_dialect().is_some_and(|d| d.config().features.supports_transactional_ddl);
+        let problems = atomic_problems(&pending_migrations, transactional_ddl);
+        if !problems.is_empty() {
+            for problem in &problems {
+                error!("  - {}", problem);
+            }
+            return Err(ApplyError::NotAtomic(problems));
+        }
+    }
     
     if dry_run {
-        return run_dry_run(&pending_migrations);
+        run_dry_run(&pending_migrations);
+        return Ok(ApplyOutcome::DryRun(pending_migrations.len()));
+    }
+
+    if let Some(script) = script {
+        write_script(script, &pending_migrations, !table_exists)?;
+        return Ok(ApplyOutcome::ScriptWritten(pending_migrations.len()));
+    }
+
+    guard.ensure_approved(&pending_migrations)?;
+
+    // A resumed run was confirmed and checked for large tables when it started
+    if checkpoint.is_none() {
+        let destructive: Vec<String> = pending_migrations
+            .iter()
+            .filter(|m| !m.destructive_statements().is_empty())
+            .map(|m| m.filename())
+            .collect();
+        if !destructive.is_empty() {
+            guard.ensure_confirmed(&format!("apply destructive migrations: {}", destructive.join(", ")))?;
+        }
+
+        // Locking or rewriting a big table can stall production traffic for a long time
+        let large_tables = find_large_tables(target, lock, &pending_migrations)?;
+        if !large_tables.is_empty() {
+            warn!("⚠️  Pending migrations lock or rewrite {} large table(s):", large_tables.len());
+            for table in &large_tables {
+                warn!("  - {}", table);
+            }
+            guard.ensure_acknowledged("Apply to these large tables now?")?;
+        }
     }
     
     // Apply migrations
-    apply_migrations(conn, &pending_migrations)
+    let release = release.map(str::to_string).or_else(|| git_release_tag(Path::new(path)));
+    apply_migrations(target, &pending_migrations, SqlLog::open_optional(sql_log), options, release)?;
+    Ok(ApplyOutcome::Applied(pending_migrations.len()))
+}
+
+/// The pending migrations `checkpoint` still had to apply, in its order. Pending
+/// migrations added since the run started are left for a later apply.
+fn resumed_pending(pending: Vec<crate::model::Migration>, checkpoint: &RunCheckpoint) -> Vec<crate::model::Migration> {
+    let remaining = checkpoint.remaining();
+    let (mut planned, others): (Vec<_>, Vec<_>) =
+        pending.into_iter().partition(|m| remaining.contains(&m.identifier()));
+    planned.sort_by_key(|m| remaining.iter().position(|id| *id == m.identifier()));
+    info!(
+        "⏯️  Resuming run {}: {} of {} planned migrations remain{}",
+        checkpoint.run_id,
+        planned.len(),
+        checkpoint.planned.len(),
+        checkpoint.last_completed.as_ref().map(|id| format!(" after {}", id)).unwrap_or_default()
+    );
+    if !others.is_empty() {
+        warn!("⚠️  Leaving {} pending migrations that were not part of run {}", others.len(), checkpoint.run_id);
+    }
+    planned
+}
+
+/// The pending migrations `owner` deploys. Fails when one of them requires a pending
+/// migration owned by another team, which has to be applied first.
+fn owned_pending(pending: Vec<crate::model::Migration>, owner: &str) -> Result<Vec<crate::model::Migration>, ApplyError> {
+    let (owned, others): (Vec<_>, Vec<_>) = pending
+        .into_iter()
+        .partition(|m| m.owner.as_deref() == Some(owner));
+    if !others.is_empty() {
+        info!("⏭️  Leaving {} pending migrations of other owners", others.len());
+    }
+
+    let blocked: Vec<String> = owned
+        .iter()
+        .flat_map(|m| {
+            others
+                .iter()
+                .filter(|other| other.namespace == m.namespace && other.version.is_some_and(|v| m.requires.contains(&v)))
+                .map(move |other| {
+                    format!(
+                        "{} requires {}, which is pending and owned by {}",
+                        m.filename(),
+                        other.filename(),
+                        other.owner.as_deref().unwrap_or("nobody")
+                    )
+                })
+        })
+        .collect();
+    if !blocked.is_empty() {
+        return Err(ApplyError::ValidationFailed(blocked));
+    }
+    Ok(owned)
 }
 
-fn run_dry_run(pending_migrations: &[crate::model::Migration]) -> Result<(), ApplyError> {
+/// The pending migrations named by `--only`. Every name must match a pending migration,
+/// and a selected migration must not require one left pending. Earlier versions of the
+/// same namespace that are still pending are gaps, refused unless `allow_gaps` is set.
+fn only_pending(
+    pending: Vec<crate::model::Migration>,
+    all: &[crate::model::Migration],
+    only: &OnlySelection,
+) -> Result<Vec<crate::model::Migration>, ApplyError> {
+    let mut problems = Vec::new();
+    let mut selected_ids = Vec::new();
+    for target in &only.targets {
+        match find_migration(&pending, target) {
+            Some(migration) => selected_ids.push(migration.identifier()),
+            None if find_migration(all, target).is_some() => {
+                problems.push(format!("{} is not pending (already applied or not for this environment)", target))
+            }
+            None => problems.push(format!("no migration matches {}", target)),
+        }
+    }
+    if !problems.is_empty() {
+        return Err(ApplyError::ValidationFailed(problems));
+    }
+
+    let (selected, others): (Vec<_>, Vec<_>) = pending
+        .into_iter()
+        .partition(|m| selected_ids.contains(&m.identifier()));
+    let mut gaps = Vec::new();
+    for migration in &selected {
+        let Some(version) = migration.version else {
+            continue;
+        };
+        for other in others.iter().filter(|o| o.namespace == migration.namespace) {
+            let Some(other_version) = other.version else {
+                continue;
+            };
+            if migration.requires.contains(&other_version) {
+                problems.push(format!("{} requires {}, which is pending and not selected", migration.filename(), other.filename()));
+            } else if other_version < version {
+                gaps.push(format!("{} is pending before {}", other.filename(), migration.filename()));
+            }
+        }
+    }
+    if !problems.is_empty() {
+        return Err(ApplyError::ValidationFailed(problems));
+    }
+    if !gaps.is_empty() {
+        if !only.allow_gaps {
+            gaps.push("apply the earlier migrations first or pass --allow-gaps".to_string());
+            return Err(ApplyError::ValidationFailed(gaps));
+        }
+        for gap in &gaps {
+            warn!("⚠️  Leaving a gap: {}", gap);
+        }
+    }
+    info!("Applying only {} of {} pending migrations", selected.len(), selected.len() + others.len());
+    Ok(selected)
+}
+
+fn run_dry_run(pending_migrations: &[crate::model::Migration]) {
     info!("🔍 DRY RUN: Would apply {} migrations", pending_migrations.len());
     
     for migration in pending_migrations {
+        if let Some(streamed) = &migration.streamed {
+            info!("  📄 {} - streamed ({:.1} MB)", migration.filename(), streamed.size_bytes as f64 / (1024.0 * 1024.0));
+            continue;
+        }
         info!("  📄 {} - {}", migration.filename(), migration.sql_content.lines().count());
+        for guard in &migration.guards {
+            info!("      guarded by {}", guard);
+        }
+        if migration.raw {
+            info!("      sent as a single raw batch");
+        }
         debug!("Migration SQL preview: {}", 
             migration.sql_content.chars().take(100).collect::<String>());
     }
     
-    info!("✅ Dry run completed successfully");
+    info!(target: SUMMARY_TARGET, "✅ Dry run completed successfully");
+}
+
+fn write_script(
+    script: &ScriptOptions,
+    migrations: &[crate::model::Migration],
+    create_migrations_table: bool,
+) -> Result<(), ApplyError> {
+    let dialect = dialects::get_dialect_with_config(None, None, script.dialect)
+        .map_err(|e| ApplyError::ScriptFailed(e.to_string()))?;
+    for migration in migrations.iter().filter(|m| !m.guards.is_empty()) {
+        warn!("⚠️  {} has guards, which the script runs unconditionally", migration.filename());
+    }
+
+    write_apply_script(script, migrations, &dialect, create_migrations_table)
+        .map_err(|e| ApplyError::ScriptFailed(format!("{}: {}", script.output.display(), e)))?;
+
+    info!(
+        target: SUMMARY_TARGET,
+        "📝 Wrote SQL for {} migrations to {} (nothing was executed)",
+        migrations.len(),
+        script.output.display()
+    );
     Ok(())
 }
 
-fn apply_migrations(conn: &str, migrations: &[crate::model::Migration]) -> Result<(), ApplyError> {
-    info!("🚀 Applying {} migrations", migrations.len());
+fn apply_migrations(
+    target: &Target,
+    migrations: &[crate::model::Migration],
+    mut sql_log: Option<SqlLog>,
+    options: &ApplyOptions,
+    release: Option<String>,
+) -> Result<(), ApplyError> {
+    let ApplyOptions { lock, atomic, on_failure, repeatable_history, store_content, .. } = *options;
+    info!("🚀 Applying {} migrations (run {})", migrations.len(), target.run_id());
     
-    let connection_manager = ConnectionManager::new()?;
-    let connection = connection_manager.connect(conn)?;
-    let mut executor = DatabaseExecutor::new(connection);
-    let mut version_store = VersionStore::new(conn)?;
+    let connection_manager = target.connection_manager()?;
+    let mut executor = connection_manager.executor(&target.conn)?;
+    executor.stop_on_interrupt();
+    let mut version_store = target.version_store()?.with_release(release.clone());
+    if let Some(release) = &release {
+        if version_store.records_releases()? {
+            info!("Recording migrations as release {}", release);
+        } else {
+            warn!(
+                "⚠️  schema_migrations has no '{}' column, release {} is not recorded",
+                schema_init::RELEASE_COLUMN,
+                release
+            );
+        }
+    }
+    let dialect = lock.resolve_dialect();
+    let mut session_lock_timeout = None;
+    let mut skipped = 0;
+    // Without transactional DDL, `--atomic` undoes a failed batch with the down SQL
+    let policy = if atomic { FailurePolicy::RollbackBatch } else { on_failure };
+    let mut applied: Vec<&crate::model::Migration> = Vec::new();
+    // With skip-and-continue: the failed migrations, and those left pending because they depend on one
+    let mut failed: Vec<&crate::model::Migration> = Vec::new();
+    let mut failures: Vec<String> = Vec::new();
+
+    let progress = RunProgress::new(target);
+    let planned: Vec<String> = migrations.iter().map(|m| m.identifier()).collect();
+    record_progress(progress.start(&planned));
+
+    // Expand `-- deriddl:mask` lines up front, so a bad one stops apply before anything runs
+    let up_sql = migrations
+        .iter()
+        .map(|migration| {
+            if migration.streamed.is_some() || migration.raw {
+                Ok(Cow::Borrowed(migration.sql_content.as_str()))
+            } else {
+                expand_masks(&migration.sql_content, dialect.as_deref())
+                    .map_err(|e| ApplyError::Masking(migration.filename(), e))
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if let Some(dialect) = dialect.as_deref().filter(|d| atomic && d.config().features.supports_transactional_ddl) {
+        let batch: Vec<_> = migrations.iter().zip(up_sql.iter().map(|sql| sql.as_ref())).collect();
+        return apply_in_one_transaction(&mut executor, &mut version_store, &batch, &mut sql_log, options, dialect, &progress);
+    }
     
-    for migration in migrations {
+    for (migration, up_sql) in migrations.iter().zip(&up_sql) {
+        if is_interrupted() {
+            record_progress(progress.finish(RunStatus::Interrupted));
+            return Err(ApplyError::Interrupted(format!("before {}", migration.filename()), target.run_id().to_string()));
+        }
+        if depends_on_failed(migration, &failed) {
+            warn!("⏭️  Leaving {} pending, it depends on a failed migration", migration.filename());
+            failed.push(migration);
+            continue;
+        }
         info!("Applying migration: {}", migration.filename());
+
+        if let Some(guard) = skipping_guard(&mut executor, migration)? {
+            info!("⏭️  Skipping {} ({})", migration.filename(), guard);
+            version_store.record_migration_skipped(migration)?;
+            tally(Tally::Skipped, 1);
+            // After a failure the count no longer covers a prefix of the plan; `--resume`
+            // then retries whatever is still pending
+            if failed.is_empty() {
+                record_progress(progress.record_completed(&migration.identifier()));
+            }
+            skipped += 1;
+            continue;
+        }
+
+        set_lock_timeout(&mut executor, dialect.as_deref(), migration, lock, &mut session_lock_timeout)?;
+        let lock_retries = migration.lock_retries.unwrap_or(lock.retries);
         
         let start_time = Instant::now();
         
+        executor.tag_migration(Some(&migration.filename()), dialect.as_deref())?;
+
         // Record migration start
         version_store.record_migration_start(migration)?;
         
-        // Execute migration in a transaction
-        let result = executor.execute_transaction(|exec| {
-            exec.execute_query(&migration.sql_content)
-                .map_err(|e| ConnectionError::QueryFailed(format!("Migration {}: {}", migration.filename(), e)))
-        });
+        // `-- deriddl:capture` results are logged; streamed files are never held in memory to find them
+        if migration.streamed.is_none() && !migration.raw {
+            executor.capture_results(capture_requests(&migration.sql_content, executor.splitter()));
+        }
+
+        // Execute migration in a transaction, retrying when it could not acquire a lock
+        let mut attempt = 0;
+        let result = loop {
+            let mut run = |exec: &mut DatabaseExecutor| run_up_sql(exec, migration, up_sql, &mut sql_log);
+            // `-- deriddl:no-transaction` statements (e.g. CREATE INDEX CONCURRENTLY) can't run in one
+            let result = if migration.no_transaction { run(&mut executor) } else { executor.execute_transaction(run) };
+
+            match result {
+                Err(e) if attempt < lock_retries && is_lock_timeout(&e.to_string()) && !is_interrupted() => {
+                    attempt += 1;
+                    let delay = lock.retry_delay_ms * u64::from(attempt);
+                    warn!(
+                        "⚠️  Migration {} timed out waiting for a lock, retrying in {}ms (attempt {}/{})",
+                        migration.filename(), delay, attempt, lock_retries
+                    );
+                    thread::sleep(Duration::from_millis(delay));
+                }
+                result => break result,
+            }
+        };
         
         let execution_time = start_time.elapsed().as_millis() as i32;
         
         match result {
             Ok(()) => {
                 version_store.record_migration_success(migration, execution_time)?;
+                if migration.is_repeatable()
+                    && repeatable_history
+                    && let Err(e) = record_definition(&mut executor, version_store.sql(), migration)
+                {
+                    warn!("⚠️  Could not store the definition of {} for rollback: {}", migration.filename(), e);
+                }
+                if store_content && let Err(e) = record_content(&mut executor, version_store.sql(), migration) {
+                    warn!("⚠️  Could not archive the content of {}: {}", migration.filename(), e);
+                }
+                if failed.is_empty() {
+                    record_progress(progress.record_completed(&migration.identifier()));
+                }
+                applied.push(migration);
+                tally(Tally::Applied, 1);
                 info!("✅ Migration {} applied successfully in {}ms", 
                     migration.filename(), execution_time);
             }
+            Err(_) if is_interrupted() => {
+                version_store.record_migration_failure(migration, execution_time)?;
+                tally(Tally::Failed, 1);
+                record_progress(progress.finish(RunStatus::Interrupted));
+                let kept = if migration.no_transaction { "statements already run were kept" } else { "rolled back" };
+                error!("❌ Migration {} interrupted ({}), recorded as failed", migration.filename(), kept);
+                return Err(ApplyError::Interrupted(format!("during {} ({})", migration.filename(), kept), target.run_id().to_string()));
+            }
             Err(e) => {
                 version_store.record_migration_failure(migration, execution_time)?;
+                tally(Tally::Failed, 1);
                 error!("❌ Migration {} failed: {}", migration.filename(), e);
+                match policy {
+                    FailurePolicy::SkipAndContinue => {
+                        warn!("⚠️  Continuing with the migrations that don't depend on {}", migration.filename());
+                        failed.push(migration);
+                        failures.push(format!("{}: {}", migration.filename(), e));
+                        continue;
+                    }
+                    FailurePolicy::RollbackBatch if rollback_batch(&mut executor, &mut version_store, &applied) => {
+                        record_progress(progress.finish(RunStatus::RolledBack));
+                    }
+                    FailurePolicy::RollbackBatch | FailurePolicy::Halt => record_progress(progress.finish(RunStatus::Failed)),
+                }
                 return Err(ApplyError::MigrationFailed(migration.filename(), e.to_string()));
             }
         }
     }
-    
-    info!("🎉 All {} migrations applied successfully!", migrations.len());
+
+    if !failures.is_empty() {
+        record_progress(progress.finish(RunStatus::Failed));
+        warn!(
+            target: SUMMARY_TARGET,
+            "⚠️  {} migrations applied, {} failed, {} left pending behind them",
+            applied.len(),
+            failures.len(),
+            failed.len() - failures.len()
+        );
+        return Err(ApplyError::MigrationsFailed(failures));
+    }
+    record_progress(progress.finish(RunStatus::Completed));
+    if skipped > 0 {
+        info!("🎉 {} migrations applied successfully, {} skipped by their guards", migrations.len() - skipped, skipped);
+    } else {
+        info!("🎉 All {} migrations applied successfully!", migrations.len());
+    }
+    Ok(())
+}
+
+/// The checkpoint only makes `--resume` possible, so failing to write it never stops apply
+fn record_progress(result: Result<(), ConnectionError>) {
+    if let Err(e) = result {
+        warn!("⚠️  Could not record apply progress: {}", e);
+    }
+}
+
+/// Sets the lock timeout of `migration` on the session, only touching the setting when it
+/// needs a different timeout than `session_lock_timeout`
+fn set_lock_timeout(
+    executor: &mut DatabaseExecutor,
+    dialect: Option<&dyn DatabaseDialect>,
+    migration: &crate::model::Migration,
+    lock: &LockPolicy,
+    session_lock_timeout: &mut Option<u64>,
+) -> Result<(), ConnectionError> {
+    let lock_timeout = migration.lock_timeout_ms.or(lock.timeout_ms);
+    if lock_timeout != *session_lock_timeout {
+        match dialect.and_then(|d| d.lock_timeout_sql(lock_timeout)) {
+            Some(sql) => {
+                debug!("Setting lock timeout: {}", sql);
+                executor.execute_query(&sql)?;
+            }
+            None if lock_timeout.is_some() => {
+                warn!("⚠️  Lock timeouts are not supported by this dialect, ignoring for {}", migration.filename());
+            }
+            None => {}
+        }
+        *session_lock_timeout = lock_timeout;
+    }
     Ok(())
 }
 
+/// `apply --atomic` on a dialect with transactional DDL: runs every migration and its
+/// schema_migrations records in one transaction, so a failure leaves nothing applied. The
+/// records are written in batches: all starts up front, all successes before the commit.
+/// `batch` pairs each migration with its up SQL.
+fn apply_in_one_transaction(
+    executor: &mut DatabaseExecutor,
+    version_store: &mut VersionStore,
+    batch: &[(&crate::model::Migration, &str)],
+    sql_log: &mut Option<SqlLog>,
+    options: &ApplyOptions,
+    dialect: &dyn DatabaseDialect,
+    progress: &RunProgress,
+) -> Result<(), ApplyError> {
+    let ApplyOptions { lock, repeatable_history, store_content, .. } = *options;
+    info!("Applying all {} migrations in one transaction", batch.len());
+    // Guards may record skips inside the transaction, which cannot alter the table
+    version_store.upgrade()?;
+    let migrations: Vec<&crate::model::Migration> = batch.iter().map(|(migration, _)| *migration).collect();
+    let start_sql = version_store.migrations_start_sql(&migrations)?;
+
+    let mut session_lock_timeout = None;
+    // Ids of the migrations skipped by their guards
+    let mut skipped = Vec::new();
+    // The migration running when the transaction failed; None once all of them ran
+    let mut current = None;
+    let started = Instant::now();
+    // Checkpoints are not written from inside the transaction: on SQLite the extra
+    // connection would wait on its lock, and a rolled back batch has nothing to resume
+    let result = executor.execute_transaction(|exec| {
+        for query in &start_sql {
+            exec.execute_query(query)?;
+        }
+        for (i, &(migration, up_sql)) in batch.iter().enumerate() {
+            if is_interrupted() {
+                return Err(ConnectionError::Interrupted);
+            }
+            current = Some((i, Instant::now()));
+            info!("Applying migration: {}", migration.filename());
+
+            if let Some(guard) = skipping_guard(exec, migration).map_err(|e| ConnectionError::QueryFailed(e.to_string()))? {
+                info!("⏭️  Skipping {} ({})", migration.filename(), guard);
+                version_store.defer_migration_skipped(migration)?;
+                skipped.push(migration.identifier());
+                continue;
+            }
+
+            set_lock_timeout(exec, Some(dialect), migration, lock, &mut session_lock_timeout)?;
+            let start_time = Instant::now();
+            exec.tag_migration(Some(&migration.filename()), Some(dialect))?;
+            if migration.streamed.is_none() && !migration.raw {
+                exec.capture_results(capture_requests(&migration.sql_content, exec.splitter()));
+            }
+            run_up_sql(exec, migration, up_sql, sql_log)?;
+            let execution_time = start_time.elapsed().as_millis() as i32;
+            version_store.defer_migration_success(migration, execution_time);
+            info!("✅ Migration {} applied in {}ms, committing with the rest", migration.filename(), execution_time);
+        }
+        current = None;
+        version_store.flush_deferred(exec).map(|_| ())
+    });
+
+    let e = match result {
+        Ok(()) => {
+            if repeatable_history {
+                for migration in migrations.iter().filter(|m| m.is_repeatable()) {
+                    if let Err(e) = record_definition(executor, version_store.sql(), migration) {
+                        warn!("⚠️  Could not store the definition of {} for rollback: {}", migration.filename(), e);
+                    }
+                }
+            }
+            if store_content {
+                for migration in migrations.iter().filter(|m| !skipped.contains(&m.identifier())) {
+                    if let Err(e) = record_content(executor, version_store.sql(), migration) {
+                        warn!("⚠️  Could not archive the content of {}: {}", migration.filename(), e);
+                    }
+                }
+            }
+            record_progress(progress.finish(RunStatus::Completed));
+            tally(Tally::Applied, migrations.len() - skipped.len());
+            tally(Tally::Skipped, skipped.len());
+            info!(
+                "🎉 All {} migrations applied in one transaction in {}ms{}",
+                migrations.len(),
+                started.elapsed().as_millis(),
+                if skipped.is_empty() { String::new() } else { format!(" ({} skipped by their guards)", skipped.len()) }
+            );
+            return Ok(());
+        }
+        Err(e) => e,
+    };
+
+    // The rollback also removed the records, so the failure is recorded on its own connection
+    tally(Tally::Failed, 1);
+    let failed = current.map(|(i, start_time)| (migrations[i], start_time.elapsed().as_millis() as i32));
+    if let Some((migration, execution_time)) = failed {
+        version_store.record_migration_start(migration)?;
+        version_store.record_migration_failure(migration, execution_time)?;
+    }
+    let filename = failed.map_or_else(|| "COMMIT".to_string(), |(migration, _)| migration.filename());
+    if is_interrupted() {
+        record_progress(progress.finish(RunStatus::Interrupted));
+        error!("❌ Interrupted during {}, the whole batch was rolled back", filename);
+        return Err(ApplyError::Interrupted(format!("during {} (the whole batch was rolled back)", filename), executor.settings().run_id.clone()));
+    }
+    record_progress(progress.finish(RunStatus::RolledBack));
+    error!("❌ Migration {} failed, the whole batch was rolled back: {}", filename, e);
+    Err(ApplyError::MigrationFailed(filename, format!("{} (the whole batch was rolled back)", e)))
+}
+
+/// Why `migrations` can't be applied all or nothing: with transactional DDL every migration
+/// has to run in the transaction, otherwise each one needs down SQL to be undone
+fn atomic_problems(migrations: &[crate::model::Migration], transactional_ddl: bool) -> Vec<String> {
+    migrations
+        .iter()
+        .filter_map(|migration| {
+            if transactional_ddl {
+                migration.no_transaction.then(|| format!("{} runs outside a transaction (-- deriddl:no-transaction)", migration.filename()))
+            } else if migration.is_repeatable() {
+                Some(format!("{} is repeatable and has no down SQL to undo it", migration.filename()))
+            } else if !migration.has_rollback() {
+                Some(format!("{} has no down SQL and the dialect has no transactional DDL", migration.filename()))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Runs the up SQL of `migration` on `exec`, logging row counts, captured results and the
+/// SQL log, and fails when its row-count expectation is not met
+fn run_up_sql(
+    exec: &mut DatabaseExecutor,
+    migration: &crate::model::Migration,
+    up_sql: &str,
+    sql_log: &mut Option<SqlLog>,
+) -> Result<(), ConnectionError> {
+    let operation = format!("apply {}", migration.filename());
+    let mut rows_affected = 0;
+    let on_executed = |statement: &str, outcome: &StatementOutcome| {
+        if let Some(rows) = outcome.rows_affected {
+            rows_affected += rows as u64;
+            log_row_count(statement, rows);
+        }
+        if let Some(sql_log) = sql_log.as_mut() {
+            sql_log.record(&operation, statement);
+        }
+        if let Some(result) = &outcome.captured {
+            log_capture(&migration.filename(), statement, result);
+            if let Some(sql_log) = sql_log.as_mut() {
+                sql_log.record_result(&result_lines(result));
+            }
+        }
+    };
+    match &migration.streamed {
+        // Streaming only bounds memory per statement, so a raw batch is read whole
+        _ if migration.raw => migration
+            .up_sql()
+            .map_err(|e| ConnectionError::Other(format!("Failed to read {}: {}", migration.file_path.display(), e)))
+            .and_then(|sql| exec.execute_batch_with(migration.raw_batch(&sql), on_executed)),
+        Some(_) => File::open(&migration.file_path)
+            .map_err(|e| ConnectionError::Other(format!("Failed to open {}: {}", migration.file_path.display(), e)))
+            .and_then(|file| exec.execute_statements_with(StatementReader::new(BufReader::new(file), exec.splitter()), on_executed)),
+        None => exec.execute_query_with(up_sql, on_executed),
+    }
+        .map_err(|e| ConnectionError::QueryFailed(format!("Migration {}: {}", migration.filename(), e)))?;
+
+    // Failing here rolls the transaction back before the unexpected change is committed
+    match migration.expect_rows {
+        Some(expected) if !expected.matches(rows_affected) => Err(ConnectionError::QueryFailed(format!(
+            "Migration {}: expected {} rows to be affected, got {}",
+            migration.filename(),
+            expected,
+            rows_affected
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Whether `migration` requires, runs after or reads a table altered by one of `failed`,
+/// so skip-and-continue leaves it pending too
+fn depends_on_failed(migration: &crate::model::Migration, failed: &[&crate::model::Migration]) -> bool {
+    failed.iter().any(|failed| {
+        let declared = failed.namespace == migration.namespace
+            && match failed.version {
+                Some(version) if !failed.is_repeatable() => migration.requires.contains(&version),
+                _ => migration.after.contains(&failed.name),
+            };
+        declared || migration.depends_on_any_table(&failed.altered_tables())
+    })
+}
+
+/// rollback-batch: runs the down SQL of the migrations this run applied, newest first, and
+/// removes their records. Stops at the first one that can't be rolled back, since the older
+/// ones may be what it builds on. Returns whether all of them were rolled back.
+fn rollback_batch(
+    executor: &mut DatabaseExecutor,
+    version_store: &mut VersionStore,
+    applied: &[&crate::model::Migration],
+) -> bool {
+    if applied.is_empty() {
+        return true;
+    }
+    warn!("↩️  Rolling back the {} migrations applied in this run", applied.len());
+    for migration in applied.iter().rev() {
+        let down_sql = migration.get_rollback_sql().filter(|sql| !sql.trim().is_empty());
+        let Some(down_sql) = down_sql.filter(|_| migration.version.is_some() && !migration.is_repeatable()) else {
+            error!("❌ Cannot roll back {} without down SQL; it and the migrations before it stay applied", migration.filename());
+            return false;
+        };
+        let run = |exec: &mut DatabaseExecutor| {
+            if migration.raw {
+                exec.execute_batch_with(down_sql, |_, _| {})
+            } else {
+                exec.execute_query(down_sql)
+            }
+        };
+        let result = if migration.no_transaction { run(executor) } else { executor.execute_transaction(run) };
+        match result.and_then(|()| version_store.remove_migration(&migration.identifier())) {
+            Ok(()) => {
+                tally(Tally::RolledBack, 1);
+                info!("↩️  Rolled back {}", migration.filename());
+            }
+            Err(e) => {
+                error!("❌ Rolling back {} failed: {}; it and the migrations before it stay applied", migration.filename(), e);
+                return false;
+            }
+        }
+    }
+    info!("↩️  Rolled back all {} migrations applied in this run", applied.len());
+    true
+}
+
+/// Runs the dialect's post-apply maintenance of schema_migrations (see
+/// [`crate::tracker::TrackerSql::maintenance`]). Migrations are already recorded, so a
+/// failing statement is only reported.
+pub fn run_maintenance(target: &Target, statements: &[String]) {
+    if statements.is_empty() {
+        return;
+    }
+    info!("🧹 Running {} maintenance statements on schema_migrations", statements.len());
+    let manager = match target.connection_manager() {
+        Ok(manager) => manager,
+        Err(e) => {
+            warn!("⚠️  Skipping schema_migrations maintenance: {}", e);
+            return;
+        }
+    };
+    let mut executor = match manager.executor(&target.conn) {
+        Ok(executor) => executor,
+        Err(e) => {
+            warn!("⚠️  Skipping schema_migrations maintenance, could not connect: {}", e);
+            return;
+        }
+    };
+    for statement in statements {
+        debug!("Maintenance: {}", statement);
+        if let Err(e) = executor.execute_query(statement) {
+            warn!("⚠️  Maintenance statement failed: {}: {}", statement_summary(statement), e);
+        }
+    }
+}
+
+/// The first `skip-if`/`run-if` guard of `migration` that decides it should not run
+fn skipping_guard<'m>(
+    executor: &mut DatabaseExecutor,
+    migration: &'m crate::model::Migration,
+) -> Result<Option<&'m ExecutionGuard>, ApplyError> {
+    for guard in &migration.guards {
+        debug!("Evaluating guard of {}: {}", migration.filename(), guard);
+        let rows = executor
+            .query_rows(guard.query())
+            .map_err(|e| ApplyError::GuardFailed(migration.filename(), e.to_string()))?;
+        if guard.skips(!rows.is_empty()) {
+            return Ok(Some(guard));
+        }
+    }
+    Ok(None)
+}
+
+/// The tag pointing at the commit the migrations are checked out at, if any
+fn git_release_tag(path: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .args(["describe", "--tags", "--exact-match", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let tag = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    debug!("Detected release from git tag: {:?}", tag);
+    (!tag.is_empty()).then_some(tag)
+}
+
+/// Logs the affected-row count of data-changing statements at info level, others at debug
+fn log_row_count(statement: &str, rows: usize) {
+    let keyword = statement.split_whitespace().next().unwrap_or_default().to_ascii_uppercase();
+    let line = format!("  {} ({} row{})", statement_summary(statement), format_count(rows), if rows == 1 { "" } else { "s" });
+    if matches!(keyword.as_str(), "INSERT" | "UPDATE" | "DELETE" | "MERGE") {
+        info!("{}", line);
+    } else {
+        debug!("{}", line);
+    }
+}
+
+/// The statement on one line, cut after 60 characters
+pub(crate) fn statement_summary(statement: &str) -> String {
+    let line = statement.split_whitespace().collect::<Vec<_>>().join(" ");
+    match line.char_indices().nth(60) {
+        Some((cut, _)) => format!("{} …", line[..cut].trim_end()),
+        None => line,
+    }
+}
+
+/// Groups digits in threes, e.g. `15 324`
+pub(crate) fn format_count(count: usize) -> String {
+    let digits = count.to_string();
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(' ');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// What a successful `apply` did, so callers can tell "nothing to do" from real work
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApplyOutcome {
+    NothingToDo,
+    Applied(usize),
+    DryRun(usize),
+    ScriptWritten(usize),
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ApplyError {
     #[error("Failed to load migrations: {0}")]
@@ -124,10 +1015,156 @@ pub enum ApplyError {
     
     #[error("Migration validation failed: {0:?}")]
     ValidationFailed(Vec<String>),
+
+    #[error("Invalid migration files: {0:?}")]
+    InvalidFiles(Vec<String>),
     
     #[error("Connection error: {0}")]
     Connection(#[from] ConnectionError),
     
+    #[error("Planning failed: {0}")]
+    Planning(#[from] PlannerError),
+    
+    #[error("{0}")]
+    Unconfirmed(#[from] GuardError),
+
+    #[error("{0}")]
+    Signature(#[from] SignatureError),
+
+    #[error("{0}")]
+    Lockfile(#[from] LockfileError),
+
+    #[error("Plan file check failed: {0}")]
+    Plan(#[from] ApprovalError),
+
+    #[error("Applied migrations were renamed or renumbered: {}", .0.join("; "))]
+    RenamedMigrations(Vec<String>),
+
+    #[error("Failed to write migration script: {0}")]
+    ScriptFailed(String),
+
     #[error("Migration {0} failed: {1}")]
     MigrationFailed(String, String),
-}
\ No newline at end of file
+
+    #[error("{} migrations failed: {}", .0.len(), .0.join("; "))]
+    MigrationsFailed(Vec<String>),
+
+    #[error("Guard query of migration {0} failed: {1}")]
+    GuardFailed(String, String),
+
+    #[error("Migration {0}: {1}")]
+    Masking(String, MaskError),
+
+    #[error("Cannot resume run {0}: {1}")]
+    Resume(String, String),
+
+    #[error("Cannot apply atomically: {}", .0.join("; "))]
+    NotAtomic(Vec<String>),
+
+    #[error("Interrupted by a signal {0}; continue with: deriddl apply --resume {1}")]
+    Interrupted(String, String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_maintenance_failures_do_not_fail_apply() {
+        // Nothing to run needs no connection
+        run_maintenance(&Target::new("Driver={No Such Driver};"), &[]);
+        // An unreachable target is warned about, the migrations stay recorded as applied
+        run_maintenance(&Target::new("Driver={No Such Driver};"), &["OPTIMIZE `schema_migrations`".to_string()]);
+    }
+
+    #[test]
+    fn test_row_count_formatting() {
+        assert_eq!(format_count(15324), "15 324");
+        assert_eq!(format_count(999), "999");
+        assert_eq!(format_count(1234567), "1 234 567");
+        assert_eq!(statement_summary("UPDATE users\n   SET active = 1"), "UPDATE users SET active = 1");
+        assert!(statement_summary(&format!("UPDATE t SET v = '{}'", "x".repeat(80))).ends_with(" …"));
+    }
+
+    #[test]
+    fn test_only_selection_refuses_gaps_unless_allowed() {
+        use crate::model::Migration;
+        use std::path::PathBuf;
+
+        let migration = |version: u32, name: &str| {
+            Migration::new(version, name.to_string(), PathBuf::from(format!("{:04}_{}.sql", version, name)), "SELECT 1;".to_string())
+        };
+        let pending = vec![migration(6, "users"), migration(7, "hotfix"), migration(8, "orders")];
+        let all = [vec![migration(5, "init")], pending.clone()].concat();
+        let only = |targets: &[&str], allow_gaps| OnlySelection { targets: targets.iter().map(|t| t.to_string()).collect(), allow_gaps };
+
+        assert!(matches!(only_pending(pending.clone(), &all, &only(&["7"], false)), Err(ApplyError::ValidationFailed(_))));
+        let selected = only_pending(pending.clone(), &all, &only(&["0007_hotfix.sql"], true)).unwrap();
+        assert_eq!(selected.iter().map(|m| m.version).collect::<Vec<_>>(), vec![Some(7)]);
+        assert_eq!(only_pending(pending.clone(), &all, &only(&["6", "7"], false)).unwrap().len(), 2);
+        assert!(only_pending(pending.clone(), &all, &only(&["5"], true)).is_err());
+        assert!(only_pending(pending, &all, &only(&["42"], true)).is_err());
+    }
+
+    #[test]
+    fn test_resume_keeps_the_remaining_planned_migrations() {
+        use crate::model::Migration;
+        use std::path::PathBuf;
+
+        let migration = |version: u32| Migration::new(version, "m".to_string(), PathBuf::from(format!("{:04}_m.sql", version)), "SELECT 1;".to_string());
+        let checkpoint = RunCheckpoint {
+            run_id: "run".to_string(),
+            status: RunStatus::Running,
+            planned: ["1", "2", "3"].iter().map(|id| id.to_string()).collect(),
+            completed: 1,
+            last_completed: Some("1".to_string()),
+            started_at: None,
+            updated_at: None,
+        };
+        // 4 was added after the run started
+        let resumed = resumed_pending(vec![migration(2), migration(3), migration(4)], &checkpoint);
+        assert_eq!(resumed.iter().map(|m| m.version).collect::<Vec<_>>(), vec![Some(2), Some(3)]);
+    }
+
+    #[test]
+    fn test_skip_and_continue_leaves_dependents_of_failed_migrations_pending() {
+        use crate::model::Migration;
+        use std::path::PathBuf;
+
+        let migration = |version: u32, sql: &str| {
+            Migration::new(version, "m".to_string(), PathBuf::from(format!("{:04}_m.sql", version)), sql.to_string())
+        };
+        let failed = migration(3, "ALTER TABLE orders ADD COLUMN total INT;");
+        let requiring = migration(4, "-- deriddl:requires 3\nCREATE TABLE a (id INT);");
+        let reading = migration(5, "-- deriddl:depends-on-table orders\nCREATE VIEW v AS SELECT total FROM orders;");
+        let independent = migration(6, "CREATE TABLE audit (id INT);");
+
+        assert!(depends_on_failed(&requiring, &[&failed]));
+        assert!(depends_on_failed(&reading, &[&failed]));
+        assert!(!depends_on_failed(&independent, &[&failed]));
+        // Dependents of migrations left pending are left pending too
+        let transitive = migration(7, "-- deriddl:requires 4\nSELECT 1;");
+        assert!(depends_on_failed(&transitive, &[&failed, &requiring]));
+    }
+
+    #[test]
+    fn test_atomic_needs_a_transaction_or_down_sql() {
+        use crate::model::Migration;
+        use std::path::PathBuf;
+
+        let migration = |version: u32, sql: &str| {
+            Migration::new(version, "m".to_string(), PathBuf::from(format!("{:04}_m.sql", version)), sql.to_string())
+        };
+        let reversible = migration(1, "-- UP\nCREATE TABLE a (id INT);\n-- DOWN\nDROP TABLE a;");
+        let concurrent = migration(2, "-- deriddl:no-transaction\n-- UP\nCREATE INDEX CONCURRENTLY i ON a (id);\n-- DOWN\nDROP INDEX i;");
+        let one_way = migration(3, "CREATE TABLE b (id INT);");
+
+        let pending = [reversible, concurrent, one_way];
+        let problems = atomic_problems(&pending, true);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("0002_m.sql"));
+        let problems = atomic_problems(&pending, false);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("0003_m.sql"));
+    }
+}
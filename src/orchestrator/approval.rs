@@ -0,0 +1,359 @@
+use crate::model::Migration;
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+
+/// A reviewable snapshot of the pending migrations, written by `plan --export-plan`
+/// and signed by a reviewer with `approve`. Apply refuses to run against protected
+/// targets unless the pending set matches a plan signed by a trusted key, and
+/// `apply --plan-file` runs exactly the plan, signed or not.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalPlan {
+    pub created_at: DateTime<Utc>,
+    pub environment: Option<String>,
+    pub migrations: Vec<PlannedMigration>,
+    /// Every migration file in the directory when the plan was exported
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<PlannedMigration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval: Option<PlanApproval>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlannedMigration {
+    pub id: String,
+    pub filename: String,
+    pub checksum: String,
+}
+
+/// Reviewer signature over the plan contents
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanApproval {
+    /// Hex-encoded Ed25519 public key of the reviewer
+    pub public_key: String,
+    /// Hex-encoded Ed25519 signature over the plan payload
+    pub signature: String,
+    pub approved_at: DateTime<Utc>,
+}
+
+impl ApprovalPlan {
+    pub fn from_migrations(migrations: &[Migration], environment: Option<&str>) -> Self {
+        Self {
+            created_at: Utc::now(),
+            environment: environment.map(str::to_string),
+            migrations: planned(migrations),
+            files: Vec::new(),
+            approval: None,
+        }
+    }
+
+    /// Records the whole migration directory so applying the plan can refuse a changed one
+    pub fn with_files(mut self, migrations: &[Migration]) -> Self {
+        self.files = planned(migrations);
+        self.files.sort_by(|a, b| a.filename.cmp(&b.filename));
+        self
+    }
+
+    pub fn read(path: &Path) -> Result<Self, ApprovalError> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| ApprovalError::Io(path.display().to_string(), e.to_string()))?;
+        serde_json::from_str(&content)
+            .map_err(|e| ApprovalError::InvalidPlan(path.display().to_string(), e.to_string()))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), ApprovalError> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| ApprovalError::InvalidPlan(path.display().to_string(), e.to_string()))?;
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)
+                .map_err(|e| ApprovalError::Io(parent.display().to_string(), e.to_string()))?;
+        }
+        fs::write(path, content + "\n")
+            .map_err(|e| ApprovalError::Io(path.display().to_string(), e.to_string()))
+    }
+
+    /// The bytes that are signed: everything in the plan except the approval itself
+    fn payload(&self) -> Vec<u8> {
+        let mut payload = format!(
+            "deriddl-plan-v1\ncreated_at={}\nenvironment={}\n",
+            self.created_at.to_rfc3339(),
+            self.environment.as_deref().unwrap_or_default()
+        );
+        for migration in &self.migrations {
+            payload.push_str(&format!(
+                "{}\t{}\t{}\n",
+                migration.id, migration.filename, migration.checksum
+            ));
+        }
+        // Plans exported before files were recorded keep their original payload
+        if !self.files.is_empty() {
+            payload.push_str("files\n");
+            for file in &self.files {
+                payload.push_str(&format!("{}\t{}\t{}\n", file.id, file.filename, file.checksum));
+            }
+        }
+        payload.into_bytes()
+    }
+
+    pub fn sign(&mut self, key: &SigningKey) {
+        let signature = key.sign(&self.payload());
+        self.approval = Some(PlanApproval {
+            public_key: hex::encode(key.verifying_key().to_bytes()),
+            signature: hex::encode(signature.to_bytes()),
+            approved_at: Utc::now(),
+        });
+    }
+
+    /// Checks that the plan is signed by one of the trusted public keys
+    pub fn verify(&self, trusted_keys: &[String]) -> Result<(), ApprovalError> {
+        let approval = self.approval.as_ref().ok_or(ApprovalError::Unsigned)?;
+
+        if !trusted_keys
+            .iter()
+            .any(|k| k.trim().eq_ignore_ascii_case(&approval.public_key))
+        {
+            return Err(ApprovalError::UntrustedKey(approval.public_key.clone()));
+        }
+
+        let key_bytes: [u8; 32] = decode_hex(&approval.public_key)?;
+        let key = VerifyingKey::from_bytes(&key_bytes)
+            .map_err(|e| ApprovalError::InvalidKey(e.to_string()))?;
+        let signature = Signature::from_bytes(&decode_hex(&approval.signature)?);
+
+        key.verify(&self.payload(), &signature)
+            .map_err(|_| ApprovalError::BadSignature)
+    }
+
+    /// Checks that the migrations about to run are exactly the approved ones, in order
+    pub fn ensure_matches(&self, pending: &[Migration]) -> Result<(), ApprovalError> {
+        let current = Self::from_migrations(pending, None).migrations;
+        if current == self.migrations {
+            return Ok(());
+        }
+
+        let mut differences = Vec::new();
+        for planned in &self.migrations {
+            match current.iter().find(|m| m.id == planned.id) {
+                None => differences.push(format!("{} was approved but is not pending", planned.filename)),
+                Some(m) if m.checksum != planned.checksum => {
+                    differences.push(format!("{} changed since it was approved", planned.filename))
+                }
+                Some(_) => {}
+            }
+        }
+        for migration in &current {
+            if !self.migrations.iter().any(|p| p.id == migration.id) {
+                differences.push(format!("{} is pending but was not approved", migration.filename));
+            }
+        }
+        if differences.is_empty() {
+            differences.push("pending migrations are in a different order than approved".to_string());
+        }
+        Err(ApprovalError::PlanMismatch(differences))
+    }
+
+    /// Checks that the migration directory holds exactly the files the plan was exported
+    /// from. Plans without a recorded directory only check the pending set.
+    pub fn ensure_same_files(&self, migrations: &[Migration]) -> Result<(), ApprovalError> {
+        if self.files.is_empty() {
+            return Ok(());
+        }
+        let current = Self::from_migrations(&[], None).with_files(migrations).files;
+        let mut differences = Vec::new();
+        for planned in &self.files {
+            match current.iter().find(|m| m.filename == planned.filename) {
+                None => differences.push(format!("{} was removed", planned.filename)),
+                Some(m) if m.checksum != planned.checksum => differences.push(format!("{} was edited", planned.filename)),
+                Some(_) => {}
+            }
+        }
+        for file in &current {
+            if !self.files.iter().any(|p| p.filename == file.filename) {
+                differences.push(format!("{} was added", file.filename));
+            }
+        }
+        if differences.is_empty() {
+            return Ok(());
+        }
+        Err(ApprovalError::DirectoryChanged(differences))
+    }
+}
+
+fn planned(migrations: &[Migration]) -> Vec<PlannedMigration> {
+    migrations
+        .iter()
+        .map(|m| PlannedMigration {
+            id: m.identifier(),
+            filename: m.filename(),
+            checksum: m.checksum.clone(),
+        })
+        .collect()
+}
+
+/// Reads a hex-encoded Ed25519 private key (32-byte seed) from `path`
+pub fn read_signing_key(path: &Path) -> Result<SigningKey, ApprovalError> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| ApprovalError::Io(path.display().to_string(), e.to_string()))?;
+    Ok(SigningKey::from_bytes(&decode_hex(content.trim())?))
+}
+
+/// Writes a new random private key to `path`, readable by the owner only, and returns
+/// it. An existing file is never overwritten.
+pub fn generate_signing_key(path: &Path) -> Result<SigningKey, ApprovalError> {
+    let io = |e: std::io::Error| ApprovalError::Io(path.display().to_string(), e.to_string());
+    let mut seed = [0u8; 32];
+    getrandom::getrandom(&mut seed).map_err(|e| ApprovalError::InvalidKey(e.to_string()))?;
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path).map_err(|e| match e.kind() {
+        ErrorKind::AlreadyExists => ApprovalError::Io(path.display().to_string(), "key file already exists".to_string()),
+        _ => io(e),
+    })?;
+    file.write_all((hex::encode(seed) + "\n").as_bytes()).map_err(io)?;
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+/// Signs an exported plan file in place with the reviewer's key
+pub fn run_approve(plan_path: &Path, key_path: &Path, generate_key: bool) -> Result<(), ApprovalError> {
+    let key = if generate_key {
+        let key = generate_signing_key(key_path)?;
+        info!("🔑 Generated new approval key at {}", key_path.display());
+        key
+    } else {
+        read_signing_key(key_path)?
+    };
+    let public_key = hex::encode(key.verifying_key().to_bytes());
+
+    let mut plan = ApprovalPlan::read(plan_path)?;
+    info!("Approving plan {} ({} migration(s))", plan_path.display(), plan.migrations.len());
+    for migration in &plan.migrations {
+        info!("  📄 {} ({})", migration.filename, migration.id);
+    }
+
+    plan.sign(&key);
+    plan.write(plan_path)?;
+
+    info!("✅ Plan signed");
+    info!("Public key: {}", public_key);
+    info!("💡 Add this key to [behavior] approval_keys so apply trusts it");
+    Ok(())
+}
+
+pub(crate) fn decode_hex<const N: usize>(value: &str) -> Result<[u8; N], ApprovalError> {
+    let bytes = hex::decode(value.trim()).map_err(|e| ApprovalError::InvalidKey(e.to_string()))?;
+    bytes
+        .try_into()
+        .map_err(|_| ApprovalError::InvalidKey(format!("expected {} bytes", N)))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ApprovalError {
+    #[error("Failed to access {0}: {1}")]
+    Io(String, String),
+
+    #[error("Invalid plan file {0}: {1}")]
+    InvalidPlan(String, String),
+
+    #[error("Invalid key or signature: {0}")]
+    InvalidKey(String),
+
+    #[error("Plan has not been approved (run 'deriddl approve')")]
+    Unsigned,
+
+    #[error("Plan was signed by untrusted key {0}")]
+    UntrustedKey(String),
+
+    #[error("Plan signature does not match its contents")]
+    BadSignature,
+
+    #[error("Pending migrations do not match the approved plan: {}", .0.join("; "))]
+    PlanMismatch(Vec<String>),
+
+    #[error("Migration directory changed since the plan was exported: {}", .0.join("; "))]
+    DirectoryChanged(Vec<String>),
+
+    #[error("Protected target requires an approved plan (pass --approved-plan)")]
+    ApprovalRequired,
+
+    #[error("Plan was approved for {} but this is {}", .plan.as_deref().unwrap_or("no environment"), .target.as_deref().unwrap_or("no environment"))]
+    EnvironmentMismatch { plan: Option<String>, target: Option<String> },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan() -> ApprovalPlan {
+        ApprovalPlan {
+            created_at: Utc::now(),
+            environment: Some("prod".to_string()),
+            migrations: vec![PlannedMigration {
+                id: "1".to_string(),
+                filename: "0001_init.sql".to_string(),
+                checksum: "abc".to_string(),
+            }],
+            files: Vec::new(),
+            approval: None,
+        }
+    }
+
+    #[test]
+    fn test_signed_plan_verifies_against_trusted_key() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let trusted = vec![hex::encode(key.verifying_key().to_bytes())];
+
+        let mut plan = plan();
+        assert!(matches!(plan.verify(&trusted), Err(ApprovalError::Unsigned)));
+
+        plan.sign(&key);
+        assert!(plan.verify(&trusted).is_ok());
+        assert!(matches!(plan.verify(&[]), Err(ApprovalError::UntrustedKey(_))));
+
+        plan.migrations[0].checksum = "def".to_string();
+        assert!(matches!(plan.verify(&trusted), Err(ApprovalError::BadSignature)));
+    }
+
+    #[test]
+    fn test_plan_file_refuses_a_changed_directory() {
+        let init = Migration::new(1, "init".to_string(), "0001_init.sql".into(), "CREATE TABLE t (id INT);".to_string());
+        let users = Migration::new(2, "users".to_string(), "0002_users.sql".into(), "CREATE TABLE u (id INT);".to_string());
+        let plan = ApprovalPlan::from_migrations(std::slice::from_ref(&users), None).with_files(&[init.clone(), users.clone()]);
+        assert!(plan.ensure_same_files(&[init.clone(), users.clone()]).is_ok());
+        assert!(plan.ensure_matches(std::slice::from_ref(&users)).is_ok());
+
+        let edited = Migration::new(2, "users".to_string(), "0002_users.sql".into(), "DROP TABLE u;".to_string());
+        let added = Migration::new(3, "orders".to_string(), "0003_orders.sql".into(), String::new());
+        match plan.ensure_same_files(&[init, edited, added]) {
+            Err(ApprovalError::DirectoryChanged(differences)) => {
+                assert_eq!(differences, vec!["0002_users.sql was edited", "0003_orders.sql was added"])
+            }
+            other => panic!("expected a changed directory, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_generated_key_is_private_and_never_overwritten() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("approval.key");
+        let key = generate_signing_key(&path).unwrap();
+        assert_eq!(read_signing_key(&path).unwrap().to_bytes(), key.to_bytes());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+
+        assert!(matches!(generate_signing_key(&path), Err(ApprovalError::Io(..))));
+        assert_eq!(read_signing_key(&path).unwrap().to_bytes(), key.to_bytes());
+    }
+}
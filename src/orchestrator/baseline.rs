@@ -1,40 +1,41 @@
-use crate::executor::{ConnectionError, ConnectionManager, DatabaseExecutor};
-use crate::tracker::{schema_init, VersionStore};
+use crate::logger::SUMMARY_TARGET;
+use crate::executor::ConnectionError;
+use crate::tracker::{schema_init, Target, VersionStore};
 use log::{debug, error, info, warn};
-use std::io::{self, Write};
+use crate::orchestrator::prompt::{Prompt, PromptError};
 
 pub fn run_baseline(
-    conn: &str, 
+    target: &Target,
     version: u32, 
     description: &str,
     from_schema: bool,
     dry_run: bool,
-    require_confirmation: bool,
+    prompt: &dyn Prompt,
 ) -> Result<(), BaselineError> {
     info!("Running baseline creation");
-    debug!("Connection string length: {}", conn.len());
+    debug!("Connection string length: {}", target.conn.len());
     debug!("Baseline version: {}", version);
     debug!("Description: {}", description);
     debug!("From schema: {}", from_schema);
     debug!("Dry run: {}", dry_run);
 
     // Test connection first
-    let connection_manager = ConnectionManager::new()?;
-    connection_manager.test_connection(conn)?;
+    let connection_manager = target.connection_manager()?;
+    connection_manager.test_connection(&target.conn)?;
     info!("✅ Database connection verified");
 
     // Ensure schema_migrations table exists
-    if !schema_init::check_migration_table_exists(conn)? {
+    if !schema_init::check_migration_table_exists(target)? {
         if dry_run {
             info!("🔍 DRY RUN: Would create schema_migrations table");
         } else {
             info!("Creating schema_migrations table");
-            schema_init::init_migration_table(conn)?;
+            schema_init::init_migration_table(target)?;
         }
     }
 
     // Check for existing migrations
-    let mut version_store = VersionStore::new(conn)?;
+    let mut version_store = target.version_store()?;
     let applied_migrations = version_store.get_applied_migrations()?;
     
     if !applied_migrations.is_empty() {
@@ -83,24 +84,15 @@ pub fn run_baseline(
         return Ok(());
     }
 
-    // Require confirmation if configured
-    if require_confirmation {
-        print!("Are you sure you want to create baseline version {} (y/N)? ", version);
-        io::stdout().flush().unwrap();
-        
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
-        
-        if !matches!(input.trim().to_lowercase().as_str(), "y" | "yes") {
-            info!("Baseline creation cancelled");
-            return Ok(());
-        }
+    if !prompt.confirm(&format!("Are you sure you want to create baseline version {}?", version))? {
+        info!("Baseline creation cancelled");
+        return Ok(());
     }
 
     // Create the baseline
-    create_baseline(&mut version_store, version, description, from_schema, conn)?;
+    create_baseline(&mut version_store, version, description, from_schema, target)?;
     
-    info!("🎉 Baseline version {} created successfully!", version);
+    info!(target: SUMMARY_TARGET, "🎉 Baseline version {} created successfully!", version);
     info!("Future migrations with version > {} will be applied", version);
     
     Ok(())
@@ -111,7 +103,7 @@ fn create_baseline(
     version: u32,
     description: &str,
     from_schema: bool,
-    conn: &str,
+    target: &Target,
 ) -> Result<(), BaselineError> {
     debug!("Creating baseline record in database");
     
@@ -120,7 +112,7 @@ fn create_baseline(
     
     // Generate schema dump if requested
     if from_schema {
-        match generate_schema_dump(conn, version) {
+        match generate_schema_dump(target, version) {
             Ok(schema_file) => {
                 info!("📄 Schema dump generated: {}", schema_file);
             }
@@ -134,23 +126,14 @@ fn create_baseline(
     Ok(())
 }
 
-fn generate_schema_dump(conn: &str, version: u32) -> Result<String, BaselineError> {
+fn generate_schema_dump(target: &Target, version: u32) -> Result<String, BaselineError> {
     debug!("Generating schema dump for baseline version {}", version);
     
-    let connection_manager = ConnectionManager::new()?;
-    let connection = connection_manager.connect(conn)?;
-    let mut executor = DatabaseExecutor::new(connection);
+    let connection_manager = target.connection_manager()?;
+    let mut executor = connection_manager.executor(&target.conn)?;
     
-    // Get database dialect (will be updated to use config later)
-    let dialect = match crate::dialects::get_dialect_with_config(None, Some(conn), None) {
-        Ok(dialect) => {
-            info!("Using dialect {} for schema introspection", dialect.name());
-            dialect
-        }
-        Err(e) => {
-            return Err(BaselineError::Connection(ConnectionError::Other(format!("Failed to get dialect: {}", e))));
-        }
-    };
+    let dialect = target.sql.dialect();
+    info!("Using dialect {} for schema introspection", dialect.name());
     
     let schema_queries = dialect.schema_introspection_queries();
     
@@ -207,4 +190,7 @@ pub enum BaselineError {
 
     #[error("Invalid baseline version: {0}")]
     InvalidVersion(String),
+
+    #[error("{0}")]
+    Prompt(#[from] PromptError),
 }
\ No newline at end of file
@@ -0,0 +1,273 @@
+use crate::executor::ConnectionError;
+use crate::logger::SUMMARY_TARGET;
+use crate::model::checksum;
+use crate::model::migration::statement_table;
+use crate::model::Migration;
+use crate::orchestrator::apply::statement_summary;
+use crate::orchestrator::migration_loader::LoaderOptions;
+use crate::orchestrator::MigrationLoader;
+use crate::tracker::version_store::AppliedMigration;
+use crate::tracker::{schema_init, Target};
+use log::{debug, info, warn};
+use std::collections::HashMap;
+use std::fmt;
+
+/// What a statement did to the blamed table or column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Created,
+    Altered,
+    Renamed,
+    Truncated,
+    Dropped,
+    ColumnAdded,
+    ColumnModified,
+    ColumnRenamed,
+    ColumnDropped,
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            ChangeKind::Created => "created table",
+            ChangeKind::Altered => "altered table",
+            ChangeKind::Renamed => "renamed table",
+            ChangeKind::Truncated => "truncated table",
+            ChangeKind::Dropped => "dropped table",
+            ChangeKind::ColumnAdded => "added column",
+            ChangeKind::ColumnModified => "modified column",
+            ChangeKind::ColumnRenamed => "renamed column",
+            ChangeKind::ColumnDropped => "dropped column",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+/// A statement of an applied migration that changed the blamed table or column
+#[derive(Debug, Clone)]
+pub struct BlameEntry {
+    pub applied: AppliedMigration,
+    pub kind: ChangeKind,
+    pub statement: String,
+    /// The file no longer matches the recorded checksum, so the statement may differ
+    /// from what actually ran
+    pub file_changed: bool,
+}
+
+/// Lists the applied migrations that created or altered `table` (or only `column` of it),
+/// oldest first
+pub fn run_blame(
+    target: &Target,
+    path: &str,
+    loader: &LoaderOptions,
+    table: &str,
+    column: Option<&str>,
+) -> Result<(), BlameError> {
+    let subject = match column {
+        Some(column) => format!("{}.{}", table, column),
+        None => table.to_string(),
+    };
+    info!("Looking up the history of {}", subject);
+    debug!("Migrations path: {}", path);
+
+    let migrations = MigrationLoader::load_migrations_with_options(path, loader)
+        .map_err(|e| BlameError::LoadFailed(e.to_string()))?;
+    if !schema_init::check_migration_table_exists(target)? {
+        return Err(BlameError::NoHistory);
+    }
+    let applied = target.version_store()?.get_applied_migrations()?;
+
+    let entries = blame(&applied, &migrations, table, column);
+    if entries.is_empty() {
+        info!(target: SUMMARY_TARGET, "No applied migration changed {}", subject);
+        return Ok(());
+    }
+
+    info!("🔎 Changes to {}", subject);
+    for entry in &entries {
+        let mut provenance = Vec::new();
+        if let Some(release) = &entry.applied.release {
+            provenance.push(format!("release {}", release));
+        }
+        if let Some(commit) = &entry.applied.git_commit {
+            provenance.push(format!("commit {:.7}", commit));
+        }
+        let provenance = if provenance.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", provenance.join(", "))
+        };
+        info!(
+            "  {}  {}  {}{}",
+            entry.applied.applied_at.format("%Y-%m-%d %H:%M:%S"),
+            entry.applied.filename,
+            entry.kind,
+            provenance
+        );
+        info!("      {}", statement_summary(&entry.statement));
+        if entry.file_changed {
+            warn!("      ⚠️  {} changed since it was applied", entry.applied.filename);
+        }
+    }
+
+    let first = &entries[0];
+    if matches!(first.kind, ChangeKind::Created | ChangeKind::ColumnAdded) {
+        info!(
+            target: SUMMARY_TARGET,
+            "{} first appeared with {} at {}",
+            subject,
+            first.applied.filename,
+            first.applied.applied_at.format("%Y-%m-%d %H:%M:%S")
+        );
+    }
+    Ok(())
+}
+
+/// Parses the statements of each successfully applied migration that has a file and
+/// keeps those that changed `table`/`column`, ordered by when they were applied
+pub fn blame(
+    applied: &[AppliedMigration],
+    migrations: &[Migration],
+    table: &str,
+    column: Option<&str>,
+) -> Vec<BlameEntry> {
+    let files: HashMap<String, &Migration> = migrations.iter().map(|m| (m.identifier(), m)).collect();
+    let table = unquote(table);
+    let column = column.map(unquote);
+
+    let mut history: Vec<&AppliedMigration> = applied.iter().filter(|a| a.success).collect();
+    history.sort_by_key(|a| a.applied_at);
+
+    let mut entries = Vec::new();
+    for record in history {
+        let Some(migration) = files.get(&record.migration_id) else {
+            debug!("No file for applied migration {}, skipping", record.filename);
+            continue;
+        };
+        let sql = match migration.up_sql() {
+            Ok(sql) => sql,
+            Err(e) => {
+                warn!("Could not read {}: {}", migration.file_path.display(), e);
+                continue;
+            }
+        };
+        for statement in checksum::split_statements(&sql) {
+            if let Some(kind) = classify(&statement, &table, column.as_deref()) {
+                entries.push(BlameEntry {
+                    applied: record.clone(),
+                    kind,
+                    statement,
+                    file_changed: !migration.matches_checksum(&record.checksum),
+                });
+            }
+        }
+    }
+    entries
+}
+
+/// What `statement` does to `table`, or to `column` of it when given
+fn classify(statement: &str, table: &str, column: Option<&str>) -> Option<ChangeKind> {
+    let target = statement_table(statement)?;
+    let matches_table = target == table || (!table.contains('.') && target.rsplit('.').next() == Some(table));
+    if !matches_table {
+        return None;
+    }
+
+    let tokens: Vec<String> = unquote(statement)
+        .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+        .filter(|token| !token.is_empty())
+        .map(str::to_string)
+        .collect();
+    let verb = tokens.first()?.as_str();
+    let table_kind = match verb {
+        "create" => ChangeKind::Created,
+        "drop" => ChangeKind::Dropped,
+        "truncate" => ChangeKind::Truncated,
+        "rename" => ChangeKind::Renamed,
+        _ if tokens.windows(2).any(|pair| pair[0] == "rename" && pair[1] == "to") => ChangeKind::Renamed,
+        _ => ChangeKind::Altered,
+    };
+
+    let Some(column) = column else {
+        return Some(table_kind);
+    };
+    // Dropping, renaming or emptying the table affects every column of it
+    if matches!(table_kind, ChangeKind::Dropped | ChangeKind::Truncated | ChangeKind::Renamed) {
+        return Some(table_kind);
+    }
+
+    // Only look after the table name, which may equal the column name
+    let after_table = tokens.iter().position(|token| *token == target).map_or(0, |i| i + 1);
+    let position = tokens[after_table..].iter().position(|token| token == column)? + after_table;
+    if table_kind == ChangeKind::Created {
+        return Some(ChangeKind::Created);
+    }
+
+    let action = tokens[after_table..position]
+        .iter()
+        .rev()
+        .find(|token| !matches!(token.as_str(), "column" | "if" | "not" | "exists"))
+        .map(String::as_str);
+    Some(match action {
+        Some("add") => ChangeKind::ColumnAdded,
+        Some("drop") => ChangeKind::ColumnDropped,
+        Some("rename") | Some("to") | Some("change") => ChangeKind::ColumnRenamed,
+        Some("alter") | Some("modify") => ChangeKind::ColumnModified,
+        _ => ChangeKind::Altered,
+    })
+}
+
+/// Lowercases and strips identifier quoting, matching how table names are normalized
+fn unquote(identifier: &str) -> String {
+    identifier
+        .chars()
+        .filter(|c| !matches!(c, '"' | '`' | '[' | ']'))
+        .collect::<String>()
+        .to_lowercase()
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BlameError {
+    #[error("Failed to load migrations: {0}")]
+    LoadFailed(String),
+
+    #[error("schema_migrations does not exist, so there is no history to search")]
+    NoHistory,
+
+    #[error("Connection error: {0}")]
+    Connection(#[from] ConnectionError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+    use std::path::PathBuf;
+
+    fn migration(version: u32, name: &str, sql: &str) -> Migration {
+        Migration::new(version, name.to_string(), PathBuf::from(format!("{:04}_{}.sql", version, name)), sql.to_string())
+    }
+
+    fn applied(migration: &Migration, days_ago: i64) -> AppliedMigration {
+        AppliedMigration { applied_at: Utc::now() - Duration::days(days_ago), ..AppliedMigration::of(migration) }
+    }
+
+    #[test]
+    fn test_blames_table_and_column_changes_in_apply_order() {
+        let create = migration(1, "create_users", "CREATE TABLE \"Users\" (id INTEGER PRIMARY KEY);");
+        let add = migration(2, "add_email", "ALTER TABLE app.users ADD COLUMN email VARCHAR(255); CREATE TABLE orders (email TEXT);");
+        let rename = migration(3, "rename_email", "ALTER TABLE users RENAME COLUMN email TO login_email;");
+        let history = vec![applied(&rename, 1), applied(&create, 3), applied(&add, 2)];
+        let migrations = vec![create, add, rename];
+
+        let kinds: Vec<_> = blame(&history, &migrations, "users", None).into_iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, vec![ChangeKind::Created, ChangeKind::Altered, ChangeKind::Altered]);
+
+        let entries = blame(&history, &migrations, "users", Some("email"));
+        let kinds: Vec<_> = entries.iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, vec![ChangeKind::ColumnAdded, ChangeKind::ColumnRenamed]);
+        assert_eq!(entries[0].applied.filename, "0002_add_email.sql");
+
+        assert!(blame(&history, &migrations, "accounts", Some("email")).is_empty());
+    }
+}
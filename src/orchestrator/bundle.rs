@@ -0,0 +1,288 @@
+use crate::logger::SUMMARY_TARGET;
+use crate::model::config::native_path;
+use crate::orchestrator::migration_loader::LoaderOptions;
+use crate::orchestrator::source::{self, FetchedSource, SourceError};
+use crate::orchestrator::MigrationLoader;
+use chrono::{DateTime, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Manifest at the root of a bundle listing every file it contains
+pub const BUNDLE_MANIFEST: &str = "deriddl-bundle.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub deriddl_version: String,
+    pub created_at: DateTime<Utc>,
+    /// Checksum of each file by `/`-separated path relative to the bundle root
+    pub files: BTreeMap<String, String>,
+}
+
+/// Packs the migration directory at `path` into a tar archive at `output`, with a manifest
+/// of file checksums. The compression follows the extension (`.tar.zst`, `.tar.gz`, `.tar`);
+/// an `s3://` or `gs://` output is uploaded once written.
+pub fn run_bundle(path: &str, loader: &LoaderOptions, output: &str) -> Result<(), BundleError> {
+    info!("Bundling migrations from {}", path);
+    let format = ArchiveFormat::for_name(output)?;
+
+    // Refuse to ship a directory the loader would reject
+    let migrations = MigrationLoader::load_migrations_with_options(path, loader)
+        .map_err(|e| BundleError::LoadFailed(e.to_string()))?;
+
+    let root = native_path(path);
+    let mut files = Vec::new();
+    collect_files(&root, &mut files)?;
+    let mut manifest = BundleManifest {
+        deriddl_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: Utc::now(),
+        files: BTreeMap::new(),
+    };
+    for file in &files {
+        manifest.files.insert(relative_name(&root, file), file_checksum(file)?);
+    }
+
+    if source::is_bucket_url(output) {
+        let scratch = source::scratch_dir()?;
+        let archive = scratch.join("bundle.tar");
+        let result = write_archive(&root, &files, &manifest, format, &archive)
+            .and_then(|()| Ok(source::copy_object(&archive, output)?));
+        if let Err(e) = fs::remove_dir_all(&scratch) {
+            debug!("Could not remove {}: {}", scratch.display(), e);
+        }
+        result?;
+    } else {
+        write_archive(&root, &files, &manifest, format, Path::new(output))?;
+    }
+
+    info!(
+        target: SUMMARY_TARGET,
+        "📦 Wrote {} ({} files, {} migrations)",
+        output,
+        manifest.files.len(),
+        migrations.len()
+    );
+    Ok(())
+}
+
+/// How a bundle archive is compressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zstd,
+    Gzip,
+    Tar,
+}
+
+impl ArchiveFormat {
+    /// The format named by the extension of `name`
+    pub fn for_name(name: &str) -> Result<Self, BundleError> {
+        let name = name.to_ascii_lowercase();
+        if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+            Ok(Self::Zstd)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Ok(Self::Gzip)
+        } else if name.ends_with(".tar") {
+            Ok(Self::Tar)
+        } else {
+            Err(BundleError::UnsupportedFormat(name))
+        }
+    }
+}
+
+/// Writes `files` (below `root`) and the manifest as a `format` archive at `to`
+fn write_archive(root: &Path, files: &[PathBuf], manifest: &BundleManifest, format: ArchiveFormat, to: &Path) -> Result<(), BundleError> {
+    debug!("Writing {:?} archive {}", format, to.display());
+    let file = File::create(to)?;
+    match format {
+        ArchiveFormat::Zstd => {
+            pack(zstd::Encoder::new(file, 0)?, root, files, manifest)?.finish()?;
+        }
+        ArchiveFormat::Gzip => {
+            pack(GzEncoder::new(file, Compression::default()), root, files, manifest)?.finish()?;
+        }
+        ArchiveFormat::Tar => {
+            pack(file, root, files, manifest)?;
+        }
+    }
+    Ok(())
+}
+
+/// Appends every file and then the manifest, returning the writer for compressors to finish
+fn pack<W: Write>(writer: W, root: &Path, files: &[PathBuf], manifest: &BundleManifest) -> io::Result<W> {
+    let mut builder = tar::Builder::new(writer);
+    for file in files {
+        builder.append_path_with_name(file, file.strip_prefix(root).unwrap_or(file))?;
+    }
+    let json = serde_json::to_vec_pretty(manifest).map_err(io::Error::other)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(json.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(manifest.created_at.timestamp().max(0) as u64);
+    header.set_cksum();
+    builder.append_data(&mut header, BUNDLE_MANIFEST, json.as_slice())?;
+    builder.into_inner()
+}
+
+/// Downloads and unpacks the bundle at `spec` (a path, `https://`, `s3://` or `gs://` URL)
+/// and checks its files against the manifest before anything is loaded from it
+pub fn open_bundle(spec: &str) -> Result<FetchedSource, BundleError> {
+    let provider = source::provider_for(spec)?;
+    info!("Fetching bundle {}", provider.describe());
+    let fetched = provider.fetch()?;
+    verify_bundle(fetched.root())?;
+    Ok(fetched)
+}
+
+/// Fails when a file is missing, changed or not listed in the manifest
+pub fn verify_bundle(root: &Path) -> Result<BundleManifest, BundleError> {
+    let content = fs::read_to_string(root.join(BUNDLE_MANIFEST)).map_err(|_| BundleError::ManifestMissing)?;
+    let manifest: BundleManifest =
+        serde_json::from_str(&content).map_err(|e| BundleError::InvalidManifest(e.to_string()))?;
+
+    let mut files = Vec::new();
+    collect_files(root, &mut files)?;
+    let mut problems = Vec::new();
+    let mut seen = Vec::new();
+    for file in &files {
+        let name = relative_name(root, file);
+        if name == BUNDLE_MANIFEST {
+            continue;
+        }
+        match manifest.files.get(&name) {
+            Some(expected) if *expected == file_checksum(file)? => {}
+            Some(_) => problems.push(format!("{} does not match the manifest", name)),
+            None => problems.push(format!("{} is not listed in the manifest", name)),
+        }
+        seen.push(name);
+    }
+    problems.extend(
+        manifest
+            .files
+            .keys()
+            .filter(|name| !seen.contains(name))
+            .map(|name| format!("{} is missing", name)),
+    );
+    if !problems.is_empty() {
+        return Err(BundleError::Mismatch(problems));
+    }
+
+    info!(
+        "✅ Bundle verified: {} files, created {} by deriddl {}",
+        manifest.files.len(),
+        manifest.created_at.format("%Y-%m-%d %H:%M:%S"),
+        manifest.deriddl_version
+    );
+    Ok(manifest)
+}
+
+/// Every regular file below `dir`, in a stable order
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<_>>()?;
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() {
+            collect_files(&entry, files)?;
+        } else if entry.is_file() {
+            files.push(entry);
+        }
+    }
+    Ok(())
+}
+
+fn relative_name(root: &Path, file: &Path) -> String {
+    let relative = file.strip_prefix(root).unwrap_or(file);
+    relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/")
+}
+
+/// SHA-256 of the raw bytes, so any change to a bundled file is caught whatever its encoding
+fn file_checksum(file: &Path) -> io::Result<String> {
+    Ok(hex::encode(Sha256::digest(fs::read(file)?)))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BundleError {
+    #[error("Failed to load migrations: {0}")]
+    LoadFailed(String),
+
+    #[error("Unsupported bundle format {0}: use .tar.zst, .tar.gz or .tar")]
+    UnsupportedFormat(String),
+
+    #[error("Bundle has no {}", BUNDLE_MANIFEST)]
+    ManifestMissing,
+
+    #[error("Invalid bundle manifest: {0}")]
+    InvalidManifest(String),
+
+    #[error("Bundle does not match its manifest: {}", .0.join("; "))]
+    Mismatch(Vec<String>),
+
+    #[error(transparent)]
+    Source(#[from] SourceError),
+
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundle_round_trip_detects_tampering() {
+        let dir = tempfile::tempdir().unwrap();
+        let migrations = dir.path().join("migrations");
+        fs::create_dir_all(migrations.join("2024")).unwrap();
+        fs::write(migrations.join("0001_init.sql"), "CREATE TABLE t (id INTEGER);").unwrap();
+        fs::write(migrations.join("2024").join("0002_more.sql"), "CREATE TABLE u (id INTEGER);").unwrap();
+        let loader = LoaderOptions { recursive: true, ..LoaderOptions::default() };
+        for name in ["migrations.tar.gz", "migrations.tar"] {
+            let output = dir.path().join(name);
+            run_bundle(migrations.to_str().unwrap(), &loader, output.to_str().unwrap()).unwrap();
+            let fetched = open_bundle(output.to_str().unwrap()).unwrap();
+            assert!(fetched.root().join("2024").join("0002_more.sql").is_file(), "{}", name);
+        }
+
+        let output = dir.path().join("migrations.tar.zst");
+        run_bundle(migrations.to_str().unwrap(), &loader, output.to_str().unwrap()).unwrap();
+        assert!(fs::read(&output).unwrap().starts_with(&[0x28, 0xb5, 0x2f, 0xfd]), "zstd frame");
+        let fetched = open_bundle(output.to_str().unwrap()).unwrap();
+        assert!(fetched.root().join("2024").join("0002_more.sql").is_file());
+
+        fs::write(fetched.root().join("0001_init.sql"), "DROP TABLE t;").unwrap();
+        fs::write(fetched.root().join("0003_extra.sql"), "SELECT 1;").unwrap();
+        match verify_bundle(fetched.root()) {
+            Err(BundleError::Mismatch(problems)) => assert_eq!(problems.len(), 2),
+            other => panic!("Expected a mismatch, got {:?}", other.map(|m| m.files)),
+        }
+    }
+
+    #[test]
+    fn test_archive_format_follows_the_extension() {
+        assert_eq!(ArchiveFormat::for_name("s3://artifacts/app/migrations.tar.zst").unwrap(), ArchiveFormat::Zstd);
+        assert_eq!(ArchiveFormat::for_name("release.TGZ").unwrap(), ArchiveFormat::Gzip);
+        assert_eq!(ArchiveFormat::for_name("release.tar").unwrap(), ArchiveFormat::Tar);
+        assert!(matches!(ArchiveFormat::for_name("release.zip"), Err(BundleError::UnsupportedFormat(_))));
+    }
+
+    #[test]
+    fn test_file_checksum_hashes_raw_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("0001_init.sql");
+        fs::write(&file, "abc").unwrap();
+        assert_eq!(file_checksum(&file).unwrap(), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+
+        // Both bytes decode to U+FFFD lossily, but are different files
+        fs::write(&file, [b'a', 0xff]).unwrap();
+        let first = file_checksum(&file).unwrap();
+        fs::write(&file, [b'a', 0xfe]).unwrap();
+        assert_ne!(file_checksum(&file).unwrap(), first);
+    }
+}
@@ -0,0 +1,77 @@
+use crate::executor::splitter::StatementSplitter;
+use crate::executor::QueryResult;
+use crate::orchestrator::apply::statement_summary;
+use log::info;
+
+/// Rows kept of a captured result when `-- deriddl:capture` gives no limit
+pub const DEFAULT_CAPTURE_ROWS: usize = 20;
+/// Upper bound on `-- deriddl:capture <rows>`, so a capture can't copy a table into the log
+pub const MAX_CAPTURE_ROWS: usize = 1000;
+
+/// Statements of `sql` preceded by a `-- deriddl:capture [rows]` line, with the number of
+/// rows to keep, split with the executor's `splitter` so it can recognize them
+pub fn capture_requests(sql: &str, mut splitter: StatementSplitter) -> Vec<(String, usize)> {
+    let mut pending = None;
+    let mut requests = Vec::new();
+    for line in sql.lines() {
+        if let Some(max_rows) = capture_directive(line) {
+            pending = Some(max_rows);
+            continue;
+        }
+        for statement in splitter.push_line(line) {
+            if let Some(max_rows) = pending.take() {
+                requests.push((statement, max_rows));
+            }
+        }
+    }
+    if let (Some(max_rows), Some(statement)) = (pending, splitter.finish()) {
+        requests.push((statement, max_rows));
+    }
+    requests
+}
+
+fn capture_directive(line: &str) -> Option<usize> {
+    let rest = line.trim().strip_prefix("--")?.trim().strip_prefix("deriddl:capture")?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let max_rows = rest.trim().parse().unwrap_or(DEFAULT_CAPTURE_ROWS);
+    Some(max_rows.clamp(1, MAX_CAPTURE_ROWS))
+}
+
+/// Logs a captured result as a small table, so it lands in the log file and JSON log
+pub fn log_capture(migration: &str, statement: &str, result: &QueryResult) {
+    info!("📸 {} captured {} row(s): {}", migration, result.rows.len(), statement_summary(statement));
+    for line in result_lines(result) {
+        info!("   {}", line);
+    }
+}
+
+/// The column names followed by one line per row, values separated by ` | `
+pub fn result_lines(result: &QueryResult) -> Vec<String> {
+    std::iter::once(result.columns.join(" | "))
+        .chain(result.rows.iter().map(|row| row.join(" | ")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_directive_marks_the_next_statement() {
+        let sql = "-- deriddl:capture\nSELECT COUNT(*) AS before_count\n  FROM users;\nUPDATE users SET active = 1;\n-- deriddl:capture 5000\nSELECT COUNT(*) FROM users WHERE active = 1";
+        assert_eq!(
+            capture_requests(sql, StatementSplitter::new(Default::default())),
+            vec![
+                ("SELECT COUNT(*) AS before_count FROM users".to_string(), DEFAULT_CAPTURE_ROWS),
+                ("SELECT COUNT(*) FROM users WHERE active = 1".to_string(), MAX_CAPTURE_ROWS),
+            ]
+        );
+        assert_eq!(capture_directive("-- deriddl:capture 3"), Some(3));
+        assert_eq!(capture_directive("-- deriddl:captures"), None);
+
+        let result = QueryResult { columns: vec!["n".into()], rows: vec![vec!["42".into()]] };
+        assert_eq!(result_lines(&result), vec!["n", "42"]);
+    }
+}
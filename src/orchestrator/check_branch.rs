@@ -0,0 +1,206 @@
+use crate::executor::ConnectionError;
+use crate::logger::SUMMARY_TARGET;
+use crate::model::{Migration, MigrationType};
+use crate::orchestrator::{MigrationLoader, Planner};
+use crate::orchestrator::migration_loader::LoaderOptions;
+use crate::orchestrator::planner::PlannerError;
+use crate::tracker::version_store::AppliedMigration;
+use crate::tracker::{schema_init, Target};
+use log::{debug, error, info};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// A problem merging this branch's migrations onto a target would cause
+#[derive(Debug, Clone, PartialEq)]
+pub enum BranchIssue {
+    /// A local file uses a version the target already applied from a different file
+    Collision { file: String, applied: String },
+    /// A pending migration is older than the newest version applied on the target
+    OutOfOrder { file: String, latest_applied: String },
+    /// Versions between the target's newest version and a pending migration are missing
+    Gap { file: String, missing: Vec<u32> },
+    /// The target applied a migration this branch does not have
+    MissingLocally { applied: String },
+    /// Pending migrations cannot be ordered
+    Dependency(String),
+}
+
+impl fmt::Display for BranchIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BranchIssue::Collision { file, applied } => {
+                write!(f, "Version collision: {} uses the version of applied {}", file, applied)
+            }
+            BranchIssue::OutOfOrder { file, latest_applied } => {
+                write!(f, "Out of order: {} is older than {}, already applied", file, latest_applied)
+            }
+            BranchIssue::Gap { file, missing } => {
+                let missing: Vec<String> = missing.iter().map(|v| format!("{:04}", v)).collect();
+                write!(f, "Gap: {} skips version(s) {}", file, missing.join(", "))
+            }
+            BranchIssue::MissingLocally { applied } => {
+                write!(f, "Behind target: {} is applied but not on this branch; rebase first", applied)
+            }
+            BranchIssue::Dependency(problem) => write!(f, "Dependency: {}", problem),
+        }
+    }
+}
+
+/// Simulates applying this branch's migrations to `target` and reports every
+/// collision, gap and ordering problem before the branch is merged. Nothing is written.
+pub fn run_check_branch(target: &Target, path: &str, loader: &LoaderOptions) -> Result<Vec<BranchIssue>, CheckBranchError> {
+    info!("Checking branch migrations against the target");
+    debug!("Migrations path: {}", path);
+
+    let migrations = MigrationLoader::load_migrations_with_options(path, loader)
+        .map_err(|e| CheckBranchError::LoadFailed(e.to_string()))?;
+
+    let connection_manager = target.connection_manager()?;
+    connection_manager.test_connection(&target.conn)?;
+    let (applied, baseline) = if schema_init::check_migration_table_exists(target)? {
+        let mut version_store = target.version_store()?;
+        (version_store.get_applied_migrations()?, version_store.get_baseline_version()?)
+    } else {
+        info!("schema_migrations does not exist on the target, every migration is new");
+        (Vec::new(), None)
+    };
+
+    let issues = check_against(&migrations, &applied, baseline);
+    let pending = migrations
+        .iter()
+        .filter(|m| !m.is_repeatable() && !applied.iter().any(|a| a.migration_id == m.identifier()))
+        .count();
+    info!("{} migration(s) on this branch are not applied on the target yet", pending);
+
+    if issues.is_empty() {
+        info!(target: SUMMARY_TARGET, "✅ Branch merges cleanly onto the target's migration history");
+    } else {
+        error!("❌ Merging this branch would cause {} problem(s):", issues.len());
+        for issue in &issues {
+            error!("  - {}", issue);
+        }
+    }
+    Ok(issues)
+}
+
+/// Compares local versioned migrations with the target's successful history. Versions at
+/// or below the target's baseline count as applied.
+pub fn check_against(migrations: &[Migration], applied: &[AppliedMigration], baseline: Option<u32>) -> Vec<BranchIssue> {
+    let applied: Vec<&AppliedMigration> = applied
+        .iter()
+        .filter(|a| a.success && a.migration_type == MigrationType::Versioned && !a.filename.starts_with("baseline_"))
+        .collect();
+    let versioned: Vec<&Migration> = migrations.iter().filter(|m| !m.is_repeatable()).collect();
+    let mut issues = Vec::new();
+
+    for a in &applied {
+        match versioned.iter().find(|m| m.identifier() == a.migration_id) {
+            Some(m) if m.filename() != a.filename || !m.matches_checksum(&a.checksum) => {
+                issues.push(BranchIssue::Collision { file: m.filename(), applied: a.filename.clone() })
+            }
+            Some(_) => {}
+            None => issues.push(BranchIssue::MissingLocally { applied: a.filename.clone() }),
+        }
+    }
+
+    let mut pending: Vec<&Migration> = versioned
+        .iter()
+        .copied()
+        .filter(|m| !applied.iter().any(|a| a.migration_id == m.identifier()))
+        .filter(|m| m.namespace.is_some() || baseline.is_none_or(|b| m.version.unwrap_or(0) > b))
+        .collect();
+    pending.sort_by_key(|m| m.version);
+
+    // Each namespace continues its own sequence from its newest applied version
+    let mut latest: BTreeMap<Option<&str>, (u32, &str)> = BTreeMap::new();
+    for a in &applied {
+        let namespace = a.migration_id.split_once(':').map(|(namespace, _)| namespace);
+        let version = a.version.unwrap_or(0);
+        if latest.get(&namespace).is_none_or(|(v, _)| version > *v) {
+            latest.insert(namespace, (version, a.filename.as_str()));
+        }
+    }
+    if let Some(baseline) = baseline
+        && latest.get(&None).is_none_or(|(v, _)| baseline > *v)
+    {
+        latest.insert(None, (baseline, "the baseline"));
+    }
+
+    let local_versions: BTreeSet<(Option<&str>, u32)> =
+        versioned.iter().filter_map(|m| m.version.map(|v| (m.namespace.as_deref(), v))).collect();
+    let mut next: BTreeMap<Option<&str>, u32> = latest.iter().map(|(namespace, (v, _))| (*namespace, v + 1)).collect();
+    for m in &pending {
+        let namespace = m.namespace.as_deref();
+        let version = m.version.unwrap_or(0);
+        match latest.get(&namespace) {
+            Some((latest_version, latest_file)) if version < *latest_version => {
+                issues.push(BranchIssue::OutOfOrder { file: m.filename(), latest_applied: latest_file.to_string() });
+                continue;
+            }
+            _ => {}
+        }
+        let expected = *next.get(&namespace).unwrap_or(&1);
+        let missing: Vec<u32> = (expected..version).filter(|v| !local_versions.contains(&(namespace, *v))).collect();
+        if !missing.is_empty() {
+            issues.push(BranchIssue::Gap { file: m.filename(), missing });
+        }
+        next.insert(namespace, version + 1);
+    }
+
+    let pending: Vec<Migration> = pending.into_iter().cloned().collect();
+    if let Err(PlannerError::DependencyCycle(cyclic)) = Planner::order_migrations(pending) {
+        issues.push(BranchIssue::Dependency(format!("cycle between {}", cyclic.join(", "))));
+    }
+    issues
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CheckBranchError {
+    #[error("Failed to load migrations: {0}")]
+    LoadFailed(String),
+
+    #[error("Connection error: {0}")]
+    Connection(#[from] ConnectionError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn migration(version: u32, name: &str) -> Migration {
+        let file = format!("{:04}_{}.sql", version, name);
+        Migration::new(version, name.to_string(), PathBuf::from(file), format!("-- {}", name))
+    }
+
+    #[test]
+    fn test_reports_collisions_gaps_and_out_of_order_migrations() {
+        let history = vec![
+            AppliedMigration::of(&migration(1, "init")),
+            AppliedMigration::of(&migration(2, "users")),
+            AppliedMigration::of(&migration(4, "orders")),
+            AppliedMigration::of(&migration(5, "merged_elsewhere")),
+        ];
+        let branch = vec![
+            migration(1, "init"),
+            migration(2, "accounts"),
+            migration(3, "late"),
+            migration(4, "orders"),
+            migration(8, "new_feature"),
+        ];
+
+        let issues = check_against(&branch, &history, None);
+        assert_eq!(
+            issues,
+            vec![
+                BranchIssue::Collision { file: "0002_accounts.sql".into(), applied: "0002_users.sql".into() },
+                BranchIssue::MissingLocally { applied: "0005_merged_elsewhere.sql".into() },
+                BranchIssue::OutOfOrder { file: "0003_late.sql".into(), latest_applied: "0005_merged_elsewhere.sql".into() },
+                BranchIssue::Gap { file: "0008_new_feature.sql".into(), missing: vec![6, 7] },
+            ]
+        );
+
+        let clean = check_against(&[migration(1, "init"), migration(2, "users")], &history[..2], None);
+        assert!(clean.is_empty());
+    }
+}
@@ -0,0 +1,120 @@
+use crate::logger::colored;
+use crate::model::{ChecksumNormalization, Migration};
+use crate::orchestrator::report::{applied_revision, SqlDiff};
+use crate::tracker::content_archive::archived_content;
+use crate::tracker::repeatable_history::definitions;
+use crate::tracker::VersionStore;
+use log::{debug, info};
+use similar::TextDiff;
+use std::fmt::Write as _;
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const CYAN: &str = "\x1b[36m";
+const RESET: &str = "\x1b[0m";
+
+/// What was applied and what the file holds now, for a migration whose checksum no
+/// longer matches `recorded`. The applied content is looked up in the content archive,
+/// then in the recorded definitions of repeatable migrations and last in the file's git
+/// history; None when none of them has it.
+pub(crate) fn applied_change(
+    version_store: &mut VersionStore,
+    migration: &Migration,
+    recorded: &str,
+    normalization: ChecksumNormalization,
+) -> Option<SqlDiff> {
+    let sql = version_store.sql().clone();
+    let archived = version_store
+        .executor()
+        .and_then(|mut executor| archived_content(&mut executor, &sql, &migration.identifier(), recorded))
+        .unwrap_or_else(|e| {
+            debug!("No archived content readable for {}: {}", migration.identifier(), e);
+            None
+        });
+    if let Some(archived) = archived {
+        return Some(SqlDiff { old: Some(archived.content), new: migration.sql_content.clone() });
+    }
+    if migration.is_repeatable() {
+        let sql = version_store.sql().clone();
+        let stored = version_store
+            .executor()
+            .and_then(|mut executor| definitions(&mut executor, &sql, &migration.identifier()))
+            .unwrap_or_else(|e| {
+                debug!("No recorded definitions readable for {}: {}", migration.identifier(), e);
+                Vec::new()
+            });
+        if let Some(definition) = stored.into_iter().find(|d| d.checksum == recorded) {
+            let new = migration.up_sql().map(|sql| sql.into_owned()).unwrap_or_default();
+            return Some(SqlDiff { old: Some(definition.sql), new });
+        }
+    }
+    applied_revision(migration, recorded, normalization)
+        .map(|old| SqlDiff { old: Some(old), new: migration.sql_content.clone() })
+}
+
+/// Unified diff from the applied content to the current file, red and green with `color`
+pub fn unified_diff(diff: &SqlDiff, filename: &str, color: bool) -> String {
+    let old = diff.old.as_deref().unwrap_or_default();
+    let text_diff = TextDiff::from_lines(old, diff.new.as_str());
+    let applied = format!("{} (applied)", filename);
+    let current = format!("{} (current)", filename);
+    let unified = text_diff.unified_diff().context_radius(3).header(&applied, &current).to_string();
+
+    let mut rendered = String::new();
+    for line in unified.lines() {
+        let header = line.starts_with("---") || line.starts_with("+++");
+        let paint = match line.chars().next() {
+            _ if !color || header => None,
+            Some('-') => Some(RED),
+            Some('+') => Some(GREEN),
+            Some('@') => Some(CYAN),
+            _ => None,
+        };
+        let _ = match paint {
+            Some(paint) => writeln!(rendered, "{}{}{}", paint, line, RESET),
+            None => writeln!(rendered, "{}", line),
+        };
+    }
+    rendered
+}
+
+/// Logs what changed in a migration since it was applied, when the applied content is found
+pub(crate) fn log_checksum_diff(
+    version_store: &mut VersionStore,
+    migration: &Migration,
+    recorded: &str,
+    normalization: ChecksumNormalization,
+) -> bool {
+    let Some(diff) = applied_change(version_store, migration, recorded, normalization) else {
+        return false;
+    };
+    info!("         Changes since it was applied:");
+    for line in unified_diff(&diff, &migration.filename(), colored()).lines() {
+        info!("         {}", line);
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff_of_applied_and_current_content() {
+        let diff = SqlDiff {
+            old: Some("CREATE TABLE users (\n    id INT\n);\n".to_string()),
+            new: "CREATE TABLE users (\n    id BIGINT\n);\n".to_string(),
+        };
+        let plain = unified_diff(&diff, "V1__users.sql", false);
+        assert_eq!(
+            plain,
+            "--- V1__users.sql (applied)\n+++ V1__users.sql (current)\n@@ -1,3 +1,3 @@\n \
+             CREATE TABLE users (\n-    id INT\n+    id BIGINT\n );\n"
+        );
+
+        let colored = unified_diff(&diff, "V1__users.sql", true);
+        assert!(colored.contains("\x1b[31m-    id INT\x1b[0m"));
+        assert!(colored.contains("\x1b[32m+    id BIGINT\x1b[0m"));
+        assert!(colored.contains("--- V1__users.sql (applied)\n"));
+    }
+}
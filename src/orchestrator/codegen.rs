@@ -0,0 +1,302 @@
+use crate::dialects;
+use crate::executor::introspect::TableInfo;
+use crate::executor::{introspect_schema, ConnectionError, ConnectionSettings, SchemaModel};
+use crate::logger::SUMMARY_TARGET;
+use crate::orchestrator::apply::{run_apply, ApplyError, ApplyOptions};
+use crate::orchestrator::guard::ProtectionGuard;
+use crate::orchestrator::lock::LockPolicy;
+use crate::orchestrator::migration_loader::LoaderOptions;
+use crate::orchestrator::sandbox::{Sandbox, SandboxEngine, SandboxError, SandboxOptions};
+use crate::tracker::tracker_sql::MIGRATION_TABLE;
+use crate::tracker::Target;
+use log::{debug, info};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+/// Prefix of deriddl's own bookkeeping tables (lock, repeatable history)
+const TRACKER_TABLE_PREFIX: &str = "deriddl_";
+
+/// Header of every generated file
+const GENERATED_HEADER: &str = "Generated by `deriddl gen types` from the migrated schema. Do not edit.";
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "false", "fn",
+    "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "static",
+    "struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while", "abstract", "become", "box",
+    "do", "final", "gen", "macro", "override", "priv", "try", "typeof", "unsized", "virtual", "yield",
+];
+
+/// Language of `deriddl gen types`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum TypesLanguage {
+    /// One struct per table, with chrono and serde_json types for dates and JSON
+    Rust,
+    /// One exported interface per table
+    Typescript,
+}
+
+#[derive(Debug, Clone)]
+pub struct GenTypesOptions {
+    pub lang: TypesLanguage,
+    /// Database to read the schema from; ignored when `shadow` is set
+    pub conn: Option<String>,
+    /// Dialect of `conn`, for catalog queries
+    pub dialect: Option<String>,
+    /// Build the schema by applying the migrations to a scratch database of this engine
+    pub shadow: Option<SandboxEngine>,
+}
+
+/// Reads the schema of the target (or of a scratch database the migrations were applied
+/// to) and writes a type definition per table to `output`, or stdout. deriddl's own
+/// tracking tables are left out.
+pub fn run_gen_types(
+    path: &str,
+    loader: &LoaderOptions,
+    environment: Option<&str>,
+    options: &GenTypesOptions,
+    run: &ConnectionSettings,
+    output: Option<&Path>,
+) -> Result<(), CodegenError> {
+    info!("Generating {:?} types from the schema", options.lang);
+
+    let sandbox = match options.shadow {
+        Some(engine) => {
+            let sandbox = Sandbox::start(&SandboxOptions { engine, print_conn: false, keep: false })?;
+            let guard = ProtectionGuard { non_interactive: true, ..Default::default() };
+            let lock = LockPolicy::default();
+            run_apply(&sandbox.target(run), path, &ApplyOptions { environment, ..ApplyOptions::new(loader, &guard, &lock) })?;
+            Some(sandbox)
+        }
+        None => None,
+    };
+    let (target, dialect_name) = match (&sandbox, &options.conn) {
+        (Some(sandbox), _) => (sandbox.target(run), Some(sandbox.engine.dialect())),
+        (None, Some(conn)) => (Target::new(conn).with_connection(run.clone()), options.dialect.as_deref()),
+        (None, None) => return Err(CodegenError::NoSource),
+    };
+    let dialect = dialects::get_dialect_with_config(None, Some(&target.conn), dialect_name)
+        .map_err(|e| CodegenError::Dialect(e.to_string()))?;
+    debug!("Using dialect {} for schema introspection", dialect.name());
+
+    let model = introspect_schema(&target.connection_manager()?, &target.conn, dialect.as_ref())?;
+    let rendered = render_types(&model, options.lang);
+    let tables = application_tables(&model).count();
+
+    match output {
+        Some(path) => {
+            fs::write(path, rendered).map_err(|e| CodegenError::Io(path.display().to_string(), e.to_string()))?;
+            info!(target: SUMMARY_TARGET, "📝 Generated types for {} tables in {}", tables, path.display());
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+/// Renders one type definition per table, in catalog order
+pub fn render_types(model: &SchemaModel, lang: TypesLanguage) -> String {
+    let first_schema = model.tables.first().map(|t| t.schema.as_str());
+    let qualified = model.tables.iter().any(|t| Some(t.schema.as_str()) != first_schema);
+    let type_name = |table: &TableInfo| {
+        if qualified {
+            pascal_case(&format!("{}_{}", table.schema, table.name))
+        } else {
+            pascal_case(&table.name)
+        }
+    };
+
+    let mut out = format!("// {}\n", GENERATED_HEADER);
+    for table in application_tables(model) {
+        out.push('\n');
+        match lang {
+            TypesLanguage::Rust => render_rust(&mut out, &type_name(table), table),
+            TypesLanguage::Typescript => render_typescript(&mut out, &type_name(table), table),
+        }
+    }
+    out
+}
+
+fn application_tables(model: &SchemaModel) -> impl Iterator<Item = &TableInfo> {
+    model.tables.iter().filter(|table| {
+        !table.name.eq_ignore_ascii_case(MIGRATION_TABLE) && !table.name.to_lowercase().starts_with(TRACKER_TABLE_PREFIX)
+    })
+}
+
+fn render_rust(out: &mut String, name: &str, table: &TableInfo) {
+    let _ = writeln!(out, "/// Row of `{}`", table.name);
+    out.push_str("#[derive(Debug, Clone, PartialEq)]\n");
+    let _ = writeln!(out, "pub struct {} {{", name);
+    for column in &table.columns {
+        let field = rust_field(&column.name);
+        let ty = rust_type(&column.data_type);
+        if column.nullable {
+            let _ = writeln!(out, "    pub {}: Option<{}>,", field, ty);
+        } else {
+            let _ = writeln!(out, "    pub {}: {},", field, ty);
+        }
+    }
+    out.push_str("}\n");
+}
+
+fn render_typescript(out: &mut String, name: &str, table: &TableInfo) {
+    let _ = writeln!(out, "/** Row of `{}` */", table.name);
+    let _ = writeln!(out, "export interface {} {{", name);
+    for column in &table.columns {
+        let field = if is_identifier(&column.name) {
+            column.name.clone()
+        } else {
+            format!("\"{}\"", column.name.replace('"', "\\\""))
+        };
+        let ty = typescript_type(&column.data_type);
+        if column.nullable {
+            let _ = writeln!(out, "  {}: {} | null;", field, ty);
+        } else {
+            let _ = writeln!(out, "  {}: {};", field, ty);
+        }
+    }
+    out.push_str("}\n");
+}
+
+/// The column type without length or precision, lowercased
+/// (`VARCHAR(255)` → `varchar`, `timestamp(3) with time zone` → `timestamp with time zone`)
+fn base_type(data_type: &str) -> String {
+    let mut base = String::new();
+    let mut depth = 0;
+    for c in data_type.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ if depth == 0 => base.push(c),
+            _ => {}
+        }
+    }
+    base.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+fn rust_type(data_type: &str) -> &'static str {
+    match base_type(data_type).as_str() {
+        "tinyint" | "int1" => "i8",
+        "smallint" | "int2" | "smallserial" => "i16",
+        "integer" | "int" | "int4" | "mediumint" | "serial" => "i32",
+        "bigint" | "int8" | "bigserial" => "i64",
+        "boolean" | "bool" | "bit" => "bool",
+        "real" | "float4" | "float" => "f32",
+        "double" | "double precision" | "float8" => "f64",
+        "date" => "chrono::NaiveDate",
+        "time" | "time without time zone" => "chrono::NaiveTime",
+        "timestamp" | "timestamp without time zone" | "datetime" => "chrono::NaiveDateTime",
+        "timestamptz" | "timestamp with time zone" => "chrono::DateTime<chrono::Utc>",
+        "json" | "jsonb" => "serde_json::Value",
+        "bytea" | "blob" | "binary" | "varbinary" | "longblob" => "Vec<u8>",
+        // Text, decimals (exact values don't fit a float), UUIDs and anything unrecognized
+        _ => "String",
+    }
+}
+
+fn typescript_type(data_type: &str) -> &'static str {
+    match base_type(data_type).as_str() {
+        "tinyint" | "int1" | "smallint" | "int2" | "smallserial" | "integer" | "int" | "int4" | "mediumint"
+        | "serial" | "bigint" | "int8" | "bigserial" | "real" | "float4" | "float" | "double"
+        | "double precision" | "float8" => "number",
+        "boolean" | "bool" | "bit" => "boolean",
+        "date" | "timestamp" | "timestamp without time zone" | "datetime" | "timestamptz"
+        | "timestamp with time zone" => "Date",
+        "json" | "jsonb" => "unknown",
+        "bytea" | "blob" | "binary" | "varbinary" | "longblob" => "Uint8Array",
+        _ => "string",
+    }
+}
+
+fn pascal_case(name: &str) -> String {
+    let name: String = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars.next().map(|c| c.to_ascii_uppercase()).into_iter().chain(chars).collect::<String>()
+        })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("T{}", name)
+    } else {
+        name
+    }
+}
+
+fn rust_field(name: &str) -> String {
+    let field: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    if field.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", field)
+    } else if RUST_KEYWORDS.contains(&field.as_str()) {
+        format!("r#{}", field)
+    } else {
+        field
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CodegenError {
+    #[error("No schema to read: give --conn or --shadow")]
+    NoSource,
+
+    #[error("Failed to build the shadow schema: {0}")]
+    Sandbox(#[from] SandboxError),
+
+    #[error("Failed to apply migrations to the shadow database: {0}")]
+    Apply(#[from] ApplyError),
+
+    #[error("Dialect error: {0}")]
+    Dialect(String),
+
+    #[error("Failed to read the schema: {0}")]
+    Connection(#[from] ConnectionError),
+
+    #[error("Failed to write {0}: {1}")]
+    Io(String, String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_render_rust_and_typescript_types() {
+        let model = SchemaModel::from_rows(
+            &[
+                row(&["public", "order_items", "id", "bigint", "NO", "1"]),
+                row(&["public", "order_items", "type", "character varying(20)", "NO", "0"]),
+                row(&["public", "order_items", "shipped_at", "timestamp with time zone", "YES", "0"]),
+                row(&["public", "order_items", "Unit Price", "numeric(10,2)", "NO", "0"]),
+                row(&["public", "schema_migrations", "migration_id", "text", "NO", "1"]),
+                row(&["public", "deriddl_lock", "lock_id", "integer", "NO", "1"]),
+            ],
+            &[],
+        );
+
+        let rust = render_types(&model, TypesLanguage::Rust);
+        assert!(rust.contains(
+            "#[derive(Debug, Clone, PartialEq)]\npub struct OrderItems {\n    pub id: i64,\n    pub r#type: String,\n    \
+             pub shipped_at: Option<chrono::DateTime<chrono::Utc>>,\n    pub unit_price: String,\n}\n"
+        ));
+        assert!(!rust.contains("SchemaMigrations") && !rust.contains("DeriddlLock"));
+
+        let typescript = render_types(&model, TypesLanguage::Typescript);
+        assert!(typescript.contains(
+            "export interface OrderItems {\n  id: number;\n  type: string;\n  shipped_at: Date | null;\n  \
+             \"Unit Price\": string;\n}\n"
+        ));
+    }
+}
@@ -0,0 +1,320 @@
+use crate::dialects::{self, DatabaseDialect};
+use crate::executor::{ConnectionManager, ConnectionSettings, SessionSettings};
+use crate::logger::SUMMARY_TARGET;
+use crate::model::{Config, ConfigFiles};
+use crate::orchestrator::apply::format_count;
+use crate::orchestrator::masking::is_identifier_path;
+use chrono::{DateTime, Utc};
+use log::{debug, error, info, warn};
+use serde::Serialize;
+use std::path::Path;
+
+/// A database `compare-data` reads
+#[derive(Debug, Clone)]
+pub struct DataTarget {
+    /// `--env` name, or `current`/`against` for plain connection strings
+    pub name: String,
+    pub conn: String,
+    pub dialect: String,
+    /// How it is connected to, built from this target's own configuration
+    pub connection: ConnectionSettings,
+}
+
+impl DataTarget {
+    /// `--against` names a target with a `config/<target>.toml`, or is an ODBC connection
+    /// string (anything with `=`) for a database of `dialect`. It is read as part of run `run_id`.
+    pub fn resolve(files: ConfigFiles, against: &str, dialect: &str, run_id: &str) -> Result<Self, CompareDataError> {
+        if against.contains('=') {
+            // No configuration describes it, so only the dialect's own session setup applies
+            let mut config = Config::default();
+            config.migrations.dialect = dialect.to_string();
+            let resolved = dialects::get_dialect_with_config(None, Some(against), Some(dialect))
+                .map_err(|e| CompareDataError::Config("against".to_string(), e.to_string()))?;
+            return Ok(Self {
+                name: "against".to_string(),
+                conn: against.to_string(),
+                dialect: dialect.to_string(),
+                connection: connection_for(&config, resolved.as_ref(), run_id),
+            });
+        }
+        // A missing override would silently compare the base configuration's database
+        let override_path = Config::environment_config_path(against);
+        if !Path::new(&override_path).is_file() {
+            return Err(CompareDataError::Config(against.to_string(), format!("{} not found", override_path)));
+        }
+        let config = Config::load(files, Some(against))
+            .map_err(|e| CompareDataError::Config(against.to_string(), e.to_string()))?;
+        let conn = config
+            .database
+            .connection_string
+            .clone()
+            .ok_or_else(|| CompareDataError::Config(against.to_string(), "no database.connection_string configured".to_string()))?;
+        // Named after the resolved dialect, so `auto` is detected from the target's own connection
+        let dialect = dialects::get_dialect_with_config(None, Some(&conn), Some(&config.migrations.dialect))
+            .map_err(|e| CompareDataError::Config(against.to_string(), e.to_string()))?;
+        let connection = connection_for(&config, dialect.as_ref(), run_id);
+        Ok(Self { name: against.to_string(), conn, dialect: dialect.name().to_string(), connection })
+    }
+}
+
+/// Read-only settings for the target described by `config`
+fn connection_for(config: &Config, dialect: &dyn DatabaseDialect, run_id: &str) -> ConnectionSettings {
+    let session = SessionSettings::for_config(config, Some(dialect), None, true, run_id);
+    ConnectionSettings::for_config(config, Some(dialect), session.statements, true, run_id)
+}
+
+/// Row count and checksum of one table on one target
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TableData {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rows: Option<u64>,
+    /// None when the dialect has no checksum query or the table is empty
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// One table on both targets
+#[derive(Debug, Clone, Serialize)]
+pub struct TableComparison {
+    pub table: String,
+    pub current: TableData,
+    pub against: TableData,
+    pub matches: bool,
+}
+
+/// Everything `compare-data` found, in the shape printed by `--json`
+#[derive(Debug, Clone, Serialize)]
+pub struct DataComparison {
+    pub generated_at: DateTime<Utc>,
+    pub current: String,
+    pub against: String,
+    /// Checksums are only compared between targets of the same dialect
+    pub checksums_compared: bool,
+    pub matches: bool,
+    pub tables: Vec<TableComparison>,
+}
+
+/// Counts the rows of `tables` on both targets and checksums them where the dialect can,
+/// e.g. to check that a backfill produced the same data in every environment
+pub fn run_compare_data(
+    current: &DataTarget,
+    against: &DataTarget,
+    tables: &[String],
+    json: bool,
+) -> Result<DataComparison, CompareDataError> {
+    if let Some(table) = tables.iter().find(|table| !is_identifier_path(table)) {
+        return Err(CompareDataError::InvalidTable(table.clone()));
+    }
+    info!("Comparing {} between {} and {}", tables.join(", "), current.name, against.name);
+
+    let current_data = read_tables(current, tables)?;
+    let against_data = read_tables(against, tables)?;
+    let report = compare(current, against, tables, current_data, against_data);
+
+    if json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{}", json),
+            Err(e) => error!("Failed to serialize data comparison: {}", e),
+        }
+    } else {
+        print_report(&report);
+    }
+    Ok(report)
+}
+
+/// Row count and checksum of each table on `target`. Failures on one table are recorded
+/// in its entry; only failing to connect is an error.
+fn read_tables(target: &DataTarget, tables: &[String]) -> Result<Vec<TableData>, CompareDataError> {
+    let fail = |e: String| CompareDataError::Connection(target.name.clone(), e);
+    let dialect = dialects::get_dialect_with_config(None, Some(&target.conn), Some(&target.dialect)).map_err(|e| fail(e.to_string()))?;
+    let manager = ConnectionManager::with_settings(target.connection.clone()).map_err(|e| fail(e.to_string()))?;
+    let mut executor = manager.executor(&target.conn).map_err(|e| fail(e.to_string()))?;
+
+    let column_rows = executor.query_rows(&dialect.columns_sql()).unwrap_or_else(|e| {
+        warn!("⚠️  Could not list columns on {}, comparing row counts only: {}", target.name, e);
+        Vec::new()
+    });
+
+    Ok(tables
+        .iter()
+        .map(|table| {
+            let mut data = TableData::default();
+            match executor.query_single_value(&format!("SELECT COUNT(*) FROM {}", table)) {
+                Ok(count) => data.rows = count.and_then(|count| count.trim().parse().ok()),
+                Err(e) => {
+                    data.error = Some(e.to_string());
+                    return data;
+                }
+            }
+            let columns = table_columns(&column_rows, table, dialect.as_ref());
+            if let Some(query) = dialect.table_checksum_sql(table, &columns).filter(|_| !columns.is_empty()) {
+                match executor.query_single_value(&query) {
+                    Ok(checksum) => data.checksum = checksum.filter(|checksum| !checksum.is_empty()),
+                    Err(e) => data.error = Some(format!("checksum failed: {}", e)),
+                }
+            }
+            debug!("{} on {}: {:?}", table, target.name, data);
+            data
+        })
+        .collect())
+}
+
+/// Quoted columns of `table` (`name` or `schema.name`) from the rows of
+/// [`DatabaseDialect::columns_sql`], taken from the first schema holding it
+fn table_columns(column_rows: &[Vec<String>], table: &str, dialect: &dyn DatabaseDialect) -> Vec<String> {
+    let (schema, name) = match table.rsplit_once('.') {
+        Some((schema, name)) => (Some(schema), name),
+        None => (None, table),
+    };
+    let mut matching = column_rows
+        .iter()
+        .filter(|row| row.len() >= 3 && row[1].eq_ignore_ascii_case(name))
+        .filter(|row| schema.is_none_or(|schema| row[0].eq_ignore_ascii_case(schema)));
+    let Some(first) = matching.next() else {
+        return Vec::new();
+    };
+    std::iter::once(first)
+        .chain(matching.filter(|row| row[0] == first[0]))
+        .map(|row| dialect.quote_identifier(&row[2]))
+        .collect()
+}
+
+/// Pairs up both targets' results. Tables match when both sides were read, the row counts
+/// agree and, for targets of the same dialect, so do the checksums.
+pub fn compare(
+    current: &DataTarget,
+    against: &DataTarget,
+    tables: &[String],
+    current_data: Vec<TableData>,
+    against_data: Vec<TableData>,
+) -> DataComparison {
+    // Resolved through the registry, so aliases such as `pg` and `postgres` compare equal
+    let resolve = |dialect: &str| dialects::get_dialect(dialect).map(|d| d.name().to_string());
+    let checksums_compared = resolve(&current.dialect).is_some_and(|name| Some(name) == resolve(&against.dialect));
+    let tables: Vec<TableComparison> = tables
+        .iter()
+        .zip(current_data.into_iter().zip(against_data))
+        .map(|(table, (current, against))| {
+            let matches = current.error.is_none()
+                && against.error.is_none()
+                && current.rows == against.rows
+                && (!checksums_compared || current.checksum == against.checksum);
+            TableComparison { table: table.clone(), current, against, matches }
+        })
+        .collect();
+
+    DataComparison {
+        generated_at: Utc::now(),
+        current: current.name.clone(),
+        against: against.name.clone(),
+        checksums_compared,
+        matches: tables.iter().all(|table| table.matches),
+        tables,
+    }
+}
+
+fn describe(data: &TableData) -> String {
+    match (&data.error, data.rows) {
+        (Some(e), _) => format!("error: {}", e),
+        (None, Some(rows)) => match &data.checksum {
+            Some(checksum) => format!("{} rows, checksum {}", format_count(rows as usize), checksum),
+            None => format!("{} rows", format_count(rows as usize)),
+        },
+        (None, None) => "unknown".to_string(),
+    }
+}
+
+fn print_report(report: &DataComparison) {
+    if !report.checksums_compared {
+        warn!("⚠️  {} and {} use different dialects, comparing row counts only", report.current, report.against);
+    }
+    for table in &report.tables {
+        let line = format!(
+            "{}: {} {} / {} {}",
+            table.table,
+            report.current,
+            describe(&table.current),
+            report.against,
+            describe(&table.against)
+        );
+        if table.matches {
+            info!("✅ {}", line);
+        } else {
+            warn!("⚠️  {}", line);
+        }
+    }
+
+    if report.matches {
+        info!(target: SUMMARY_TARGET, "✅ All {} tables match between {} and {}", report.tables.len(), report.current, report.against);
+    } else {
+        let differing: Vec<&str> = report.tables.iter().filter(|t| !t.matches).map(|t| t.table.as_str()).collect();
+        warn!(target: SUMMARY_TARGET, "⚠️  Tables differ: {}", differing.join(", "));
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CompareDataError {
+    #[error("Invalid table name '{0}'; expected <table> or <schema>.<table>")]
+    InvalidTable(String),
+
+    #[error("Failed to load configuration for {0}: {1}")]
+    Config(String, String),
+
+    #[error("Failed to connect to {0}: {1}")]
+    Connection(String, String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(name: &str, dialect: &str) -> DataTarget {
+        DataTarget { name: name.to_string(), conn: String::new(), dialect: dialect.to_string(), connection: ConnectionSettings::default() }
+    }
+
+    fn data(rows: u64, checksum: &str) -> TableData {
+        TableData { rows: Some(rows), checksum: Some(checksum.to_string()), error: None }
+    }
+
+    #[test]
+    fn test_tables_match_on_counts_and_same_dialect_checksums() {
+        let tables = vec!["users".to_string(), "orders".to_string(), "audit".to_string()];
+        let failed = TableData { error: Some("relation does not exist".to_string()), ..Default::default() };
+        let report = compare(
+            &target("prod", "postgres"),
+            &target("staging", "pg"),
+            &tables,
+            vec![data(10, "42"), data(5, "7"), data(1, "1")],
+            vec![data(10, "42"), data(5, "8"), failed],
+        );
+        assert!(report.checksums_compared);
+        assert!(!report.matches);
+        assert_eq!(report.tables.iter().map(|t| t.matches).collect::<Vec<_>>(), vec![true, false, false]);
+
+        // Checksums of different dialects are not comparable
+        let report = compare(&target("oltp", "postgres"), &target("lake", "databricks"), &tables[..1], vec![data(10, "42")], vec![data(10, "99")]);
+        assert!(!report.checksums_compared);
+        assert!(report.matches);
+    }
+
+    #[test]
+    fn test_plain_connection_strings_get_only_the_dialects_session_setup() {
+        let against = DataTarget::resolve(ConfigFiles::default(), "Driver=PostgreSQL;Server=replica", "postgres", "run").unwrap();
+        assert_eq!(against.name, "against");
+        assert_eq!(against.connection.session_setup, vec!["SET SESSION CHARACTERISTICS AS TRANSACTION READ ONLY"]);
+        assert!(against.connection.read_only);
+        assert_eq!(against.connection.run_id, "run");
+    }
+
+    #[test]
+    fn test_table_columns_come_from_one_schema() {
+        let row = |values: [&str; 3]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        let rows = vec![row(["app", "users", "id"]), row(["app", "users", "email"]), row(["audit", "users", "id"])];
+        let mysql = dialects::get_dialect_with_config(Some("mysql"), None, None).unwrap();
+        assert_eq!(table_columns(&rows, "users", mysql.as_ref()), vec!["`id`", "`email`"]);
+        assert_eq!(table_columns(&rows, "audit.users", mysql.as_ref()), vec!["`id`"]);
+        assert!(table_columns(&rows, "orders", mysql.as_ref()).is_empty());
+    }
+}
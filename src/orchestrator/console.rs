@@ -0,0 +1,181 @@
+use crate::dialects::{self, DatabaseDialect};
+use crate::executor::{introspect_schema, ConnectionError};
+use crate::logger::SUMMARY_TARGET;
+use crate::orchestrator::sandbox::format_table;
+use crate::tracker::Target;
+use log::{debug, info, warn};
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Opens the SQL console on the configured target
+pub fn run_console(target: &Target, config_dialect: Option<&str>) -> Result<(), ConsoleError> {
+    let dialect = dialects::get_dialect_with_config(None, Some(&target.conn), config_dialect)
+        .map_err(|e| ConsoleError::Dialect(e.to_string()))?;
+    info!(target: SUMMARY_TARGET, "Connected; enter SQL terminated by ';', Tab completes table names, \\q quits");
+    sql_console(target, dialect, "deriddl")
+}
+
+/// Line-edited SQL REPL: statements may span lines and run once a line ends with `;`.
+/// Table names from introspection are offered on Tab, quoted as the dialect requires.
+pub(crate) fn sql_console(target: &Target, dialect: Arc<dyn DatabaseDialect>, name: &str) -> Result<(), ConsoleError> {
+    let manager = target.connection_manager()?;
+    let mut executor = manager.executor(&target.conn)?;
+
+    let tables = match introspect_schema(&manager, &target.conn, dialect.as_ref()) {
+        Ok(model) => model.tables.into_iter().map(|table| table.name).collect(),
+        Err(e) => {
+            warn!("Could not read table names, completion is disabled: {}", e);
+            Vec::new()
+        }
+    };
+    debug!("Completing {} table names", tables.len());
+
+    let mut editor: Editor<TableCompleter, DefaultHistory> =
+        Editor::new().map_err(|e| ConsoleError::Terminal(e.to_string()))?;
+    editor.set_helper(Some(TableCompleter { tables, dialect }));
+    let history = history_path();
+    if let Some(history) = &history {
+        let _ = editor.load_history(history);
+    }
+
+    let prompt = format!("{}> ", name);
+    let continuation = format!("{:>width$}> ", "...", width = name.len());
+    let mut statement = String::new();
+
+    loop {
+        let line = match editor.readline(if statement.is_empty() { &prompt } else { &continuation }) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => {
+                // Ctrl-C discards the statement being typed, like psql
+                statement.clear();
+                continue;
+            }
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(ConsoleError::Terminal(e.to_string())),
+        };
+        let trimmed = line.trim();
+        if statement.is_empty() && matches!(trimmed, "\\q" | "exit" | "quit" | ".quit") {
+            break;
+        }
+
+        statement.push_str(&line);
+        statement.push('\n');
+        if !trimmed.ends_with(';') {
+            continue;
+        }
+
+        let sql = statement.trim().trim_end_matches(';').to_string();
+        statement.clear();
+        let _ = editor.add_history_entry(sql.as_str());
+        match executor.query_with_columns(&sql) {
+            Ok(Some(result)) => print!("{}", format_table(&result)),
+            Ok(None) => println!("OK"),
+            Err(e) => println!("Error: {}", e),
+        }
+    }
+
+    if let Some(history) = &history
+        && let Err(e) = editor.save_history(history)
+    {
+        debug!("Failed to save console history to {}: {}", history.display(), e);
+    }
+    Ok(())
+}
+
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".deriddl_history"))
+}
+
+struct TableCompleter {
+    tables: Vec<String>,
+    dialect: Arc<dyn DatabaseDialect>,
+}
+
+impl TableCompleter {
+    /// Start of the word ending at `pos` and the table names it is a prefix of
+    fn candidates(&self, line: &str, pos: usize) -> (usize, Vec<String>) {
+        let start = line[..pos]
+            .rfind(|c: char| !(c.is_alphanumeric() || c == '_' || c == '"' || c == '`'))
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let prefix = line[start..pos].trim_start_matches(['"', '`']).to_lowercase();
+
+        let mut candidates: Vec<String> = self
+            .tables
+            .iter()
+            .filter(|table| table.to_lowercase().starts_with(&prefix))
+            .map(|table| self.identifier(table))
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+        (start, candidates)
+    }
+
+    /// Lowercase names are used bare; anything else needs the dialect's quoting to resolve
+    fn identifier(&self, table: &str) -> String {
+        let bare = table.chars().next().is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+            && table.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        if bare {
+            table.to_string()
+        } else {
+            self.dialect.quote_identifier(table)
+        }
+    }
+}
+
+impl Completer for TableCompleter {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(self.candidates(line, pos))
+    }
+}
+
+impl Hinter for TableCompleter {
+    type Hint = String;
+}
+
+impl Highlighter for TableCompleter {}
+
+impl Validator for TableCompleter {}
+
+impl Helper for TableCompleter {}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConsoleError {
+    #[error("Connection error: {0}")]
+    Connection(#[from] ConnectionError),
+
+    #[error("Dialect error: {0}")]
+    Dialect(String),
+
+    #[error("Terminal error: {0}")]
+    Terminal(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completes_table_names_with_dialect_quoting() {
+        let completer = TableCompleter {
+            tables: vec!["users".to_string(), "UserEvents".to_string(), "orders".to_string()],
+            dialect: dialects::get_dialect_with_config(Some("mysql"), None, None).unwrap(),
+        };
+
+        assert_eq!(
+            completer.candidates("SELECT * FROM us", 16),
+            (14, vec!["`UserEvents`".to_string(), "users".to_string()])
+        );
+        assert_eq!(completer.candidates("SELECT * FROM o", 15), (14, vec!["orders".to_string()]));
+        assert_eq!(completer.candidates("SELECT * FROM x", 15).1, Vec::<String>::new());
+    }
+}
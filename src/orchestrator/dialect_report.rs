@@ -0,0 +1,170 @@
+use crate::dialects::registry::{DialectRegistry, MIN_DETECTION_CONFIDENCE};
+use crate::dialects::{get_registry, DatabaseDialect, DialectError, AUTO_DIALECT};
+use crate::logger::SUMMARY_TARGET;
+use log::{info, warn};
+
+/// What one registered dialect supports, as listed by `dialects --detail`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DialectSummary {
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub description: String,
+    /// Feature flags from the dialect's TOML
+    pub features: Vec<(&'static str, bool)>,
+    /// Optional SQL the dialect provides, e.g. `lock_timeout`
+    pub capabilities: Vec<&'static str>,
+    /// Column types used for schema_migrations
+    pub types: Vec<(&'static str, String)>,
+}
+
+impl DialectSummary {
+    pub fn of(dialect: &dyn DatabaseDialect) -> Self {
+        let config = dialect.config();
+        let features = &config.features;
+        let types = &config.types;
+        let capabilities = [
+            ("lock_timeout", dialect.lock_timeout_sql(None).is_some()),
+            ("use_schema", dialect.use_schema_sql("s").is_some()),
+            ("use_catalog", dialect.use_catalog_sql("c").is_some()),
+            ("query_tag", dialect.query_tag_sql("k", "v").is_some()),
+            ("isolation_level", dialect.isolation_level_sql("READ COMMITTED").is_some()),
+            ("read_only", dialect.read_only_sql().is_some()),
+            ("explain", dialect.explain_sql("SELECT 1").is_some()),
+            ("table_size", dialect.table_size_sql("t").is_some()),
+            ("table_checksum", dialect.table_checksum_sql("t", &["c".to_string()]).is_some()),
+            ("hash", dialect.hash_sql("c").is_some()),
+        ];
+        Self {
+            name: dialect.name().to_string(),
+            aliases: dialect.aliases().to_vec(),
+            description: config.metadata.description.clone(),
+            features: vec![
+                ("transactions", features.supports_transactions),
+                ("transactional_ddl", features.supports_transactional_ddl),
+                ("savepoints", features.supports_savepoints),
+                ("schemas", features.supports_schemas),
+                ("sequences", features.supports_sequences),
+                ("arrays", features.supports_arrays),
+                ("case_sensitive", features.case_sensitive),
+            ],
+            capabilities: capabilities.iter().filter(|(_, supported)| *supported).map(|(name, _)| *name).collect(),
+            types: vec![
+                ("migration_id", types.migration_id.clone()),
+                ("migration_type", types.migration_type.clone()),
+                ("version", types.version.clone()),
+                ("filename", types.filename.clone()),
+                ("checksum", types.checksum.clone()),
+                ("applied_at", types.applied_at.clone()),
+                ("execution_time_ms", types.execution_time_ms.clone()),
+                ("success", types.success.clone()),
+                ("release", types.release.clone()),
+                ("git_commit", types.git_commit.clone()),
+                ("git_branch", types.git_branch.clone()),
+                ("run_id", types.run_id.clone()),
+                ("status", types.status.clone()),
+                ("text", dialect.text_type().to_string()),
+            ],
+        }
+    }
+}
+
+/// Lists the registered dialects and, with `conn`, which one detection picks for it.
+/// `configured` is `migrations.dialect`, reported when it resolves to no dialect and
+/// commands would silently fall back to generic.
+pub fn run_dialects(detail: bool, conn: Option<&str>, configured: &str) {
+    let registry = get_registry().lock().unwrap();
+
+    info!("🗃️  Registered dialects");
+    for dialect in registry.all() {
+        let summary = DialectSummary::of(dialect.as_ref());
+        info!("{} (aliases: {}) - {}", summary.name, summary.aliases.join(", "), summary.description);
+        if detail {
+            print_detail(&summary);
+        }
+    }
+
+    match registry.get(configured) {
+        None if configured.eq_ignore_ascii_case(AUTO_DIALECT) => {
+            info!(target: SUMMARY_TARGET, "migrations.dialect 'auto' detects the dialect from the connection string")
+        }
+        Some(dialect) => info!(target: SUMMARY_TARGET, "migrations.dialect '{}' resolves to {}", configured, dialect.name()),
+        None => warn!(
+            target: SUMMARY_TARGET,
+            "⚠️  migrations.dialect '{}' is not a registered dialect or alias; commands fall back to generic",
+            configured
+        ),
+    }
+
+    if let Some(conn) = conn {
+        report_detection(&registry, conn);
+    }
+}
+
+fn print_detail(summary: &DialectSummary) {
+    let flags = |flags: &[(&str, bool)]| {
+        flags
+            .iter()
+            .map(|(name, enabled)| format!("{}{}", if *enabled { "+" } else { "-" }, name))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    info!("  features: {}", flags(&summary.features));
+    info!("  capabilities: {}", if summary.capabilities.is_empty() { "none".to_string() } else { summary.capabilities.join(", ") });
+    info!("  types:");
+    for (column, sql_type) in &summary.types {
+        info!("    {:<18} {}", column, sql_type);
+    }
+}
+
+/// Logs every dialect whose detection matches `conn` and the one detection settles on
+fn report_detection(registry: &DialectRegistry, conn: &str) {
+    info!("🔎 Detection for the given connection string");
+    for (dialect, result) in registry.detection_candidates(conn) {
+        info!("  {:<12} confidence {:.1}, matched '{}'", dialect.name(), result.confidence, result.matched_pattern);
+    }
+    match registry.detect_with_result(conn) {
+        Ok((dialect, result)) if result.confidence < MIN_DETECTION_CONFIDENCE => warn!(
+            target: SUMMARY_TARGET,
+            "⚠️  No dialect matches with enough confidence ({} at {:.1}); set migrations.dialect explicitly",
+            dialect.name(),
+            result.confidence
+        ),
+        Ok((dialect, result)) => info!(
+            target: SUMMARY_TARGET,
+            "Detected dialect: {} (pattern '{}', confidence {:.1})",
+            dialect.name(),
+            result.matched_pattern,
+            result.confidence
+        ),
+        Err(DialectError::Ambiguous(names)) => warn!(
+            target: SUMMARY_TARGET,
+            "⚠️  Ambiguous detection between {}; set migrations.dialect explicitly",
+            names.join(", ")
+        ),
+        Err(e) => warn!(target: SUMMARY_TARGET, "⚠️  {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialects;
+
+    #[test]
+    fn test_summary_lists_features_capabilities_and_types() {
+        let postgres = dialects::get_dialect("postgres").unwrap();
+        let summary = DialectSummary::of(postgres.as_ref());
+        assert!(summary.aliases.contains(&"pg".to_string()));
+        assert!(summary.features.contains(&("savepoints", true)));
+        assert!(summary.features.contains(&("transactional_ddl", true)));
+        assert!(summary.capabilities.contains(&"lock_timeout"));
+        assert!(summary.types.contains(&("success", "BOOLEAN".to_string())));
+
+        let databricks = dialects::get_dialect("databricks").unwrap();
+        assert_eq!(databricks.query_tag_sql("deriddl_run_id", "o'k").as_deref(), Some("SET QUERY_TAGS['deriddl_run_id'] = 'o''k'"));
+        assert!(DialectSummary::of(databricks.as_ref()).capabilities.contains(&"query_tag"));
+
+        let generic = dialects::get_dialect("generic").unwrap();
+        assert!(!DialectSummary::of(generic.as_ref()).capabilities.contains(&"hash"));
+    }
+}
@@ -0,0 +1,393 @@
+use crate::dialects;
+use crate::executor::ConnectionManager;
+use crate::logger::SUMMARY_TARGET;
+use crate::model::{Config, ConfigFiles};
+use crate::model::config::LOCAL_CONFIG_PATH;
+use crate::orchestrator::health::{collect_health_checks, HealthCheckResult, HealthStatus};
+use crate::orchestrator::migration_loader::LoaderOptions;
+use crate::tracker::schema_init::{check_migration_table_layout, MigrationTableLayout};
+use crate::tracker::Target;
+use chrono::{DateTime, Utc};
+use log::{error, info, warn};
+use serde::Serialize;
+use std::path::Path;
+
+/// Inputs for `deriddl doctor`. Paths and dialect fall back to the configuration
+/// when not given on the command line.
+#[derive(Debug, Clone, Default)]
+pub struct DoctorOptions<'a> {
+    pub config_path: Option<&'a str>,
+    pub environment: Option<&'a str>,
+    pub conn: Option<&'a str>,
+    pub path: Option<&'a str>,
+    pub dialect: Option<&'a str>,
+    pub json: bool,
+}
+
+/// A single diagnosis with a suggested fix when something is wrong
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorFinding {
+    pub check: String,
+    pub status: HealthStatus,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<String>,
+}
+
+/// Everything `doctor` found, in the shape printed by `--json`
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorReport {
+    pub deriddl_version: String,
+    pub generated_at: DateTime<Utc>,
+    pub status: HealthStatus,
+    pub findings: Vec<DoctorFinding>,
+}
+
+impl DoctorFinding {
+    fn pass(check: &str, message: impl Into<String>) -> Self {
+        Self { check: check.to_string(), status: HealthStatus::Pass, message: message.into(), remediation: None }
+    }
+
+    fn problem(check: &str, status: HealthStatus, message: impl Into<String>, remediation: impl Into<String>) -> Self {
+        Self {
+            check: check.to_string(),
+            status,
+            message: message.into(),
+            remediation: Some(remediation.into()),
+        }
+    }
+}
+
+impl From<HealthCheckResult> for DoctorFinding {
+    fn from(check: HealthCheckResult) -> Self {
+        let remediation = (check.status != HealthStatus::Pass)
+            .then(|| health_remediation(&check.name))
+            .flatten()
+            .map(str::to_string);
+        Self { check: check.name, status: check.status, message: check.message, remediation }
+    }
+}
+
+/// Runs every diagnosis, prints the report and returns it so the caller can set the exit code
+pub fn run_doctor(options: &DoctorOptions) -> DoctorReport {
+    info!("Running deriddl doctor");
+
+    let (mut findings, config) = check_config_files(options.config_path, options.environment);
+    let loader = LoaderOptions::from_config(&config);
+    let path = options.path.unwrap_or(&config.migrations.path);
+    let dialect = options.dialect.unwrap_or(&config.migrations.dialect);
+
+    findings.push(check_dialect(dialect));
+    findings.extend(collect_health_checks(path, dialect, &loader).into_iter().map(DoctorFinding::from));
+    findings.push(check_driver_manager());
+
+    match options.conn.or(config.database.connection_string.as_deref()) {
+        Some(conn) => findings.extend(check_database(&Target::new(conn))),
+        None => findings.push(DoctorFinding::problem(
+            "Database",
+            HealthStatus::Warn,
+            "No connection string configured; database checks were skipped",
+            "Pass --conn or set database.connection_string",
+        )),
+    }
+
+    let status = findings.iter().map(|f| f.status).fold(HealthStatus::Pass, |worst, status| {
+        match (worst, status) {
+            (HealthStatus::Fail, _) | (_, HealthStatus::Fail) => HealthStatus::Fail,
+            (HealthStatus::Warn, _) | (_, HealthStatus::Warn) => HealthStatus::Warn,
+            _ => HealthStatus::Pass,
+        }
+    });
+    let report = DoctorReport {
+        deriddl_version: env!("CARGO_PKG_VERSION").to_string(),
+        generated_at: Utc::now(),
+        status,
+        findings,
+    };
+
+    if options.json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{}", json),
+            Err(e) => error!("Failed to serialize doctor report: {}", e),
+        }
+    } else {
+        print_report(&report);
+    }
+    report
+}
+
+fn print_report(report: &DoctorReport) {
+    for finding in &report.findings {
+        match finding.status {
+            HealthStatus::Pass => info!("✅ {}: {}", finding.check, finding.message),
+            HealthStatus::Warn => warn!("{}: {}", finding.check, finding.message),
+            HealthStatus::Fail => error!("{}: {}", finding.check, finding.message),
+        }
+        if let Some(remediation) = &finding.remediation {
+            info!("   💡 {}", remediation);
+        }
+    }
+
+    match report.status {
+        HealthStatus::Pass => info!(target: SUMMARY_TARGET, "🎉 No problems found"),
+        HealthStatus::Warn => warn!(target: SUMMARY_TARGET, "Doctor found warnings"),
+        HealthStatus::Fail => error!(target: SUMMARY_TARGET, "Doctor found problems that need fixing"),
+    }
+}
+
+/// Parses each configuration file that would be loaded, then returns the effective
+/// configuration (or the defaults when it cannot be loaded)
+fn check_config_files(config_path: Option<&str>, environment: Option<&str>) -> (Vec<DoctorFinding>, Config) {
+    let mut files: Vec<(String, bool)> = match config_path {
+        Some(path) => vec![(path.to_string(), true)],
+        None => Config::standard_config_paths()
+            .into_iter()
+            .find(|path| path.exists())
+            .map(|path| vec![(path.display().to_string(), true)])
+            .unwrap_or_default(),
+    };
+    if let Some(environment) = environment {
+        files.push((Config::environment_config_path(environment), true));
+    }
+    if Path::new(LOCAL_CONFIG_PATH).exists() {
+        files.push((LOCAL_CONFIG_PATH.to_string(), false));
+    }
+
+    let mut findings = Vec::new();
+    if files.is_empty() {
+        findings.push(DoctorFinding::pass("Config", "No config file found, using defaults"));
+    }
+    for (path, expected) in files {
+        let finding = match Config::parse_file(&path) {
+            Ok((_, unknown)) if !unknown.is_empty() => DoctorFinding::problem(
+                "Config",
+                HealthStatus::Warn,
+                format!("{} has unknown keys: {}", path, unknown.join(", ")),
+                "Fix the misspelled keys; `deriddl config --schema` lists every setting",
+            ),
+            Ok(_) => DoctorFinding::pass("Config", format!("{} parsed successfully", path)),
+            Err(e) if expected && !Path::new(&path).exists() => DoctorFinding::problem(
+                "Config",
+                HealthStatus::Warn,
+                e.to_string(),
+                format!("Create {} or drop the option that points to it", path),
+            ),
+            Err(e) => DoctorFinding::problem(
+                "Config",
+                HealthStatus::Fail,
+                e.to_string(),
+                format!("Fix the TOML syntax or unknown values in {}", path),
+            ),
+        };
+        findings.push(finding);
+    }
+
+    let config = Config::load(ConfigFiles { path: config_path, strict: false }, environment).unwrap_or_default();
+    findings.extend(check_required_version(&config));
+    (findings, config)
+}
+
+/// Whether this binary satisfies `required_version`. Doctor runs regardless, so it is the
+/// place a mismatch shows up with the fix; None when no version is required.
+fn check_required_version(config: &Config) -> Option<DoctorFinding> {
+    let required = config.required_version.as_deref()?;
+    Some(match config.check_required_version() {
+        Ok(()) => DoctorFinding::pass(
+            "Version",
+            format!("deriddl {} satisfies required_version {}", env!("CARGO_PKG_VERSION"), required),
+        ),
+        Err(e) => DoctorFinding::problem(
+            "Version",
+            HealthStatus::Fail,
+            e.to_string(),
+            "Install a deriddl version matching required_version (`deriddl self-update` updates this binary), or fix required_version",
+        ),
+    })
+}
+
+fn check_dialect(dialect: &str) -> DoctorFinding {
+    match dialects::get_dialect(dialect) {
+        Some(d) => DoctorFinding::pass("Dialect", format!("Dialect '{}' is available", d.name())),
+        None => DoctorFinding::problem(
+            "Dialect",
+            HealthStatus::Fail,
+            format!("Dialect '{}' is not supported", dialect),
+            format!("Set migrations.dialect to one of: {}", dialects::list_dialects().join(", ")),
+        ),
+    }
+}
+
+fn check_driver_manager() -> DoctorFinding {
+    let manager = match ConnectionManager::new() {
+        Ok(manager) => manager,
+        Err(e) => {
+            return DoctorFinding::problem(
+                "ODBC Driver Manager",
+                HealthStatus::Fail,
+                format!("Could not initialize the ODBC driver manager: {}", e),
+                "Install unixODBC (Linux/macOS) or check the Windows ODBC Data Source Administrator",
+            );
+        }
+    };
+
+    match manager.installed_drivers() {
+        Ok(drivers) if drivers.is_empty() => DoctorFinding::problem(
+            "ODBC Driver Manager",
+            HealthStatus::Warn,
+            "Driver manager found, but no ODBC drivers are registered",
+            "Install the ODBC driver for your database and register it (e.g. in odbcinst.ini)",
+        ),
+        Ok(drivers) => DoctorFinding::pass(
+            "ODBC Driver Manager",
+            format!("{} driver(s) registered: {}", drivers.len(), drivers.join(", ")),
+        ),
+        Err(e) => DoctorFinding::problem(
+            "ODBC Driver Manager",
+            HealthStatus::Warn,
+            format!("Could not list ODBC drivers: {}", e),
+            "Check the driver manager configuration (odbcinst.ini)",
+        ),
+    }
+}
+
+/// Connection, schema_migrations layout, interrupted runs and the last failed migration
+fn check_database(target: &Target) -> Vec<DoctorFinding> {
+    let layout = match check_migration_table_layout(target) {
+        Ok(layout) => layout,
+        Err(e) => {
+            return vec![DoctorFinding::problem(
+                "Database",
+                HealthStatus::Fail,
+                format!("Could not connect: {}", e),
+                "Check the connection string and that the database accepts connections (see 'deriddl health')",
+            )];
+        }
+    };
+
+    let mut findings = vec![DoctorFinding::pass("Database", "Connected successfully")];
+    match layout {
+        MigrationTableLayout::Missing => {
+            findings.push(DoctorFinding::problem(
+                "Migration Table",
+                HealthStatus::Warn,
+                "schema_migrations does not exist",
+                "Run 'deriddl init' (or 'deriddl baseline' for an existing database)",
+            ));
+            return findings;
+        }
+        MigrationTableLayout::Current => {
+            findings.push(DoctorFinding::pass("Migration Table", "schema_migrations has the current layout"));
+        }
+        MigrationTableLayout::Outdated(missing) => {
+            findings.push(DoctorFinding::problem(
+                "Migration Table",
+                HealthStatus::Fail,
+                format!("schema_migrations has an outdated layout, missing columns: {}", missing.join(", ")),
+                "Add the missing columns to match the table created by 'deriddl init'",
+            ));
+            return findings;
+        }
+    }
+
+    let rows = match target.version_store().and_then(|mut store| store.get_applied_migrations()) {
+        Ok(rows) => rows,
+        Err(e) => {
+            findings.push(DoctorFinding::problem(
+                "Migration History",
+                HealthStatus::Fail,
+                format!("Could not read schema_migrations: {}", e),
+                "Check that the connecting user can SELECT from schema_migrations",
+            ));
+            return findings;
+        }
+    };
+
+    // A started run records success = 0 with no execution time until it finishes
+    let in_progress: Vec<String> = rows
+        .iter()
+        .filter(|row| !row.success && row.execution_time_ms == 0)
+        .map(|row| format!("{} (started {})", row.filename, row.applied_at.format("%Y-%m-%d %H:%M:%S")))
+        .collect();
+    findings.push(if in_progress.is_empty() {
+        DoctorFinding::pass("Migration Lock", "No migration run is in progress")
+    } else {
+        DoctorFinding::problem(
+            "Migration Lock",
+            HealthStatus::Warn,
+            format!("Unfinished migration run(s): {}", in_progress.join(", ")),
+            "If no other deriddl process is running, the run was interrupted: check the schema, then remove the row or re-run apply",
+        )
+    });
+
+    let last_failed = rows
+        .iter()
+        .filter(|row| !row.success && row.execution_time_ms != 0)
+        .max_by_key(|row| row.applied_at);
+    findings.push(match last_failed {
+        None => DoctorFinding::pass("Last Failure", "No failed migrations recorded"),
+        Some(row) => DoctorFinding::problem(
+            "Last Failure",
+            HealthStatus::Warn,
+            format!("{} failed at {}", row.filename, row.applied_at.format("%Y-%m-%d %H:%M:%S")),
+            format!("Fix the migration and re-run apply; 'deriddl info {}' shows its SQL", row.migration_id),
+        ),
+    });
+
+    findings
+}
+
+/// Suggested fixes for the checks shared with `health`
+fn health_remediation(check: &str) -> Option<&'static str> {
+    match check {
+        "Python" => Some("Install Python 3 and make sure 'python' or 'python3' is on PATH"),
+        "SQLGlot" => Some("Install SQLGlot with 'pip install sqlglot' or set validation.enable_sqlglot = false"),
+        "Migrations Directory" | "File Permissions" => {
+            Some("Create the migrations directory or point migrations.path (or --path) at it")
+        }
+        "File Pattern" => Some("Rename the files or adjust migrations.file_pattern"),
+        "File Validity" => Some("Re-save the files as UTF-8 or raise validation.max_file_size_mb"),
+        "Migration Sequence" => Some("Renumber the migrations so versions are unique and ordered"),
+        "Migration Loading" => Some("Check that the migrations path exists and is readable"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failed_health_checks_get_remediation() {
+        let finding = DoctorFinding::from(HealthCheckResult {
+            name: "SQLGlot".to_string(),
+            status: HealthStatus::Warn,
+            message: "SQLGlot not found".to_string(),
+        });
+        assert!(finding.remediation.is_some());
+
+        let json = serde_json::to_value(&finding).unwrap();
+        assert_eq!(json["status"], "warn");
+        assert_eq!(json["check"], "SQLGlot");
+
+        let passed = DoctorFinding::from(HealthCheckResult {
+            name: "SQLGlot".to_string(),
+            status: HealthStatus::Pass,
+            message: "found".to_string(),
+        });
+        assert!(passed.remediation.is_none());
+        assert!(serde_json::to_value(&passed).unwrap().get("remediation").is_none());
+    }
+    #[test]
+    fn test_required_version_mismatch_is_reported() {
+        assert!(check_required_version(&Config::default()).is_none());
+
+        let config = |required: &str| Config { required_version: Some(required.to_string()), ..Config::default() };
+        let finding = check_required_version(&config(">=1000.0")).unwrap();
+        assert_eq!(finding.status, HealthStatus::Fail);
+        assert!(finding.message.contains(">=1000.0"));
+        assert!(finding.remediation.unwrap().contains("self-update"));
+
+        let finding = check_required_version(&config(&format!(">={}", env!("CARGO_PKG_VERSION")))).unwrap();
+        assert_eq!(finding.status, HealthStatus::Pass);
+    }
+}
@@ -0,0 +1,183 @@
+use crate::model::Migration;
+use crate::tracker::version_store::{AppliedMigration, RecordStatus};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+/// Why a migration got the estimate it did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EstimateBasis {
+    /// Earlier runs of the same migration (repeatables, or a re-apply after rollback)
+    PreviousRuns,
+    /// Migrations whose name starts with the same word, e.g. `backfill`
+    SamePrefix(String),
+    /// Migrations made of the same kinds of statements, e.g. `CREATE INDEX`
+    SameStatements(String),
+}
+
+impl fmt::Display for EstimateBasis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EstimateBasis::PreviousRuns => write!(f, "previous runs"),
+            EstimateBasis::SamePrefix(prefix) => write!(f, "'{}' migrations", prefix),
+            EstimateBasis::SameStatements(kinds) => write!(f, "{} migrations", kinds),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Estimate {
+    pub duration_ms: u64,
+    /// Historical runs the estimate is the median of
+    pub samples: usize,
+    pub basis: EstimateBasis,
+}
+
+/// Estimates how long pending migrations take from the recorded `execution_time_ms`
+/// of similar, already applied ones
+pub struct DurationEstimator {
+    by_id: HashMap<String, Vec<u64>>,
+    by_prefix: HashMap<String, Vec<u64>>,
+    by_statements: HashMap<String, Vec<u64>>,
+}
+
+impl DurationEstimator {
+    /// `migrations` are the loaded files, used to find the statements of applied migrations
+    pub fn from_history(history: &[AppliedMigration], migrations: &[Migration]) -> Self {
+        let files: HashMap<String, &Migration> = migrations.iter().map(|m| (m.identifier(), m)).collect();
+        let mut estimator = Self {
+            by_id: HashMap::new(),
+            by_prefix: HashMap::new(),
+            by_statements: HashMap::new(),
+        };
+
+        // Only migrations deriddl ran have a measured time
+        for applied in history.iter().filter(|a| a.success && a.status == RecordStatus::Ran) {
+            let time = applied.execution_time_ms as u64;
+            estimator.by_id.entry(applied.migration_id.clone()).or_default().push(time);
+            let Some(migration) = files.get(&applied.migration_id) else {
+                continue;
+            };
+            if let Some(prefix) = name_prefix(migration) {
+                estimator.by_prefix.entry(prefix).or_default().push(time);
+            }
+            if let Some(kinds) = statement_kinds(&migration.sql_content) {
+                estimator.by_statements.entry(kinds).or_default().push(time);
+            }
+        }
+        estimator
+    }
+
+    /// None when no applied migration resembles this one
+    pub fn estimate(&self, migration: &Migration) -> Option<Estimate> {
+        let candidates = [
+            self.by_id.get(&migration.identifier()).map(|times| (times, EstimateBasis::PreviousRuns)),
+            name_prefix(migration)
+                .and_then(|prefix| self.by_prefix.get(&prefix).map(|times| (times, EstimateBasis::SamePrefix(prefix)))),
+            statement_kinds(&migration.sql_content).and_then(|kinds| {
+                self.by_statements
+                    .get(&kinds)
+                    .map(|times| (times, EstimateBasis::SameStatements(kinds)))
+            }),
+        ];
+        let (times, basis) = candidates.into_iter().flatten().next()?;
+        Some(Estimate {
+            duration_ms: median(times),
+            samples: times.len(),
+            basis,
+        })
+    }
+}
+
+/// First word of the migration name: `backfill` for `0012_backfill_user_emails.sql`
+fn name_prefix(migration: &Migration) -> Option<String> {
+    migration
+        .name
+        .split(['_', '-', ' '])
+        .find(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// The distinct kinds of statements in `sql`, e.g. `ALTER TABLE + CREATE INDEX`
+fn statement_kinds(sql: &str) -> Option<String> {
+    let kinds: BTreeSet<String> = sql
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("--"))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .split(';')
+        .filter_map(statement_kind)
+        .collect();
+    (!kinds.is_empty()).then(|| kinds.into_iter().collect::<Vec<_>>().join(" + "))
+}
+
+fn statement_kind(statement: &str) -> Option<String> {
+    let mut words = statement.split_whitespace().map(str::to_uppercase);
+    let verb = words.next()?;
+    if !matches!(verb.as_str(), "CREATE" | "ALTER" | "DROP") {
+        return Some(verb);
+    }
+    // CREATE UNIQUE INDEX, CREATE OR REPLACE VIEW, ... are grouped by the object they create
+    let object = words.find(|word| !matches!(word.as_str(), "OR" | "REPLACE" | "UNIQUE" | "TEMP" | "TEMPORARY" | "MATERIALIZED"))?;
+    Some(format!("{} {}", verb, object))
+}
+
+fn median(times: &[u64]) -> u64 {
+    let mut sorted = times.to_vec();
+    sorted.sort_unstable();
+    sorted[sorted.len() / 2]
+}
+
+/// Renders milliseconds as `850ms`, `4.2s`, `3m 05s` or `1h 02m`
+pub fn format_duration_ms(ms: u64) -> String {
+    let seconds = ms / 1000;
+    if ms < 1000 {
+        format!("{}ms", ms)
+    } else if seconds < 60 {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    } else if seconds < 3600 {
+        format!("{}m {:02}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{}h {:02}m", seconds / 3600, seconds % 3600 / 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn migration(version: u32, name: &str, sql: &str) -> Migration {
+        Migration::new(version, name.to_string(), PathBuf::from(format!("{:04}_{}.sql", version, name)), sql.to_string())
+    }
+
+    fn applied(migration: &Migration, execution_time_ms: i32) -> AppliedMigration {
+        AppliedMigration { execution_time_ms, ..AppliedMigration::of(migration) }
+    }
+
+    #[test]
+    fn test_estimates_from_similar_migrations() {
+        let backfill_a = migration(1, "backfill_emails", "UPDATE users SET email = lower(email);");
+        let backfill_b = migration(2, "backfill_names", "UPDATE users SET name = trim(name);");
+        let index = migration(3, "users_email_index", "CREATE UNIQUE INDEX idx ON users (email);");
+        let history = vec![applied(&backfill_a, 4000), applied(&backfill_b, 6000), applied(&index, 900)];
+        let estimator = DurationEstimator::from_history(&history, &[backfill_a, backfill_b, index]);
+
+        let estimate = estimator.estimate(&migration(4, "backfill_phones", "UPDATE users SET phone = NULL;")).unwrap();
+        assert_eq!(estimate.basis, EstimateBasis::SamePrefix("backfill".to_string()));
+        assert_eq!((estimate.duration_ms, estimate.samples), (6000, 2));
+
+        let estimate = estimator.estimate(&migration(5, "orders_index", "CREATE INDEX idx2 ON orders (id);")).unwrap();
+        assert_eq!(estimate.basis, EstimateBasis::SameStatements("CREATE INDEX".to_string()));
+
+        assert_eq!(estimator.estimate(&migration(6, "seed", "INSERT INTO t VALUES (1);")), None);
+        assert_eq!(format_duration_ms(185_000), "3m 05s");
+    }
+
+    #[test]
+    fn test_records_deriddl_did_not_run_are_no_samples() {
+        let backfill = migration(1, "backfill_emails", "UPDATE users SET email = lower(email);");
+        let skipped = AppliedMigration { status: RecordStatus::SkipListed, ..applied(&backfill, 0) };
+        let estimator = DurationEstimator::from_history(&[skipped], std::slice::from_ref(&backfill));
+        assert_eq!(estimator.estimate(&backfill), None);
+    }
+}
@@ -0,0 +1,155 @@
+use crate::dialects::{self, DatabaseDialect, ExplainEstimate};
+use crate::executor::{ConnectionError, DatabaseExecutor};
+use crate::model::checksum::split_statements;
+use crate::model::Migration;
+use crate::orchestrator::apply::{format_count, statement_summary};
+use crate::tracker::Target;
+use log::{debug, info, warn};
+
+/// A DML statement of a pending migration and what EXPLAIN estimates for it
+#[derive(Debug, Clone)]
+pub struct ExplainedStatement {
+    pub statement: String,
+    /// The estimate, or why the statement could not be explained
+    pub estimate: Result<ExplainEstimate, String>,
+}
+
+impl ExplainedStatement {
+    /// An UPDATE or DELETE that reads the whole table
+    pub fn is_full_table_write(&self) -> bool {
+        let upper = self.statement.to_uppercase();
+        (upper.starts_with("UPDATE ") || upper.starts_with("DELETE "))
+            && self.estimate.as_ref().is_ok_and(|estimate| estimate.full_scan)
+    }
+}
+
+/// Runs EXPLAIN (never EXPLAIN ANALYZE) on the DML of each pending migration and reports
+/// estimated rows and costs, warning about UPDATEs and DELETEs that scan a whole table.
+/// Statements on tables an earlier pending migration creates cannot be planned yet and
+/// are reported as not explained.
+pub fn run_explain(target: &Target, dialect: Option<&str>, pending: &[Migration]) -> Result<(), ConnectionError> {
+    let dialect = dialects::get_dialect_with_config(None, Some(&target.conn), dialect)
+        .map_err(|e| ConnectionError::Other(format!("Failed to get dialect: {}", e)))?;
+    if dialect.explain_sql("SELECT 1").is_none() {
+        warn!("⚠️  EXPLAIN is not supported for the {} dialect, skipping --explain", dialect.name());
+        return Ok(());
+    }
+
+    let manager = target.connection_manager()?;
+    let mut executor = manager.executor(&target.conn)?;
+    info!("🔍 EXPLAIN of pending DML ({})", dialect.name());
+    let mut full_table_writes = 0;
+    for migration in pending {
+        let explained = explain_migration(&mut executor, dialect.as_ref(), migration);
+        if explained.is_empty() {
+            continue;
+        }
+        info!("📄 {}", migration.filename());
+        for statement in &explained {
+            display_explained(statement);
+            if statement.is_full_table_write() {
+                full_table_writes += 1;
+            }
+        }
+    }
+    if full_table_writes > 0 {
+        warn!("⚠️  {} statement(s) would rewrite a whole table; check their WHERE clauses", full_table_writes);
+    }
+    info!("");
+    Ok(())
+}
+
+/// Plans every INSERT, UPDATE, DELETE and MERGE in the migration's up SQL
+pub fn explain_migration(
+    executor: &mut DatabaseExecutor,
+    dialect: &dyn DatabaseDialect,
+    migration: &Migration,
+) -> Vec<ExplainedStatement> {
+    dml_statements(migration)
+        .into_iter()
+        .filter_map(|statement| {
+            let explain = dialect.explain_sql(&statement)?;
+            debug!("Explaining: {}", explain);
+            let estimate = executor
+                .query_rows(&explain)
+                .map(|plan| dialect.explain_estimate(&plan))
+                .map_err(|e| e.to_string());
+            Some(ExplainedStatement { statement, estimate })
+        })
+        .collect()
+}
+
+fn dml_statements(migration: &Migration) -> Vec<String> {
+    let sql = match migration.up_sql() {
+        Ok(sql) => sql,
+        Err(e) => {
+            warn!("⚠️  Cannot read {}: {}", migration.file_path.display(), e);
+            return Vec::new();
+        }
+    };
+    split_statements(&sql).into_iter().filter(|statement| is_dml(statement)).collect()
+}
+
+fn is_dml(statement: &str) -> bool {
+    let upper = statement.trim_start().to_uppercase();
+    ["INSERT ", "UPDATE ", "DELETE ", "MERGE "].iter().any(|keyword| upper.starts_with(keyword))
+}
+
+fn display_explained(explained: &ExplainedStatement) {
+    let summary = statement_summary(&explained.statement);
+    match &explained.estimate {
+        Ok(estimate) => {
+            let mut details = Vec::new();
+            if let Some(rows) = estimate.rows {
+                details.push(format!("~{} rows", format_count(rows as usize)));
+            }
+            if let Some(cost) = estimate.cost {
+                details.push(format!("cost {:.2}", cost));
+            }
+            if estimate.full_scan {
+                details.push("full table scan".to_string());
+            }
+            if details.is_empty() {
+                details.push("no estimate".to_string());
+            }
+            if explained.is_full_table_write() {
+                warn!("   ⚠️  {} → {}", summary, details.join(", "));
+            } else {
+                info!("   {} → {}", summary, details.join(", "));
+            }
+        }
+        Err(e) => info!("   {} → not explained: {}", summary, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_estimates_from_explain_output() {
+        let postgres = dialects::get_dialect("postgres").unwrap();
+        let plan = vec![
+            vec!["Update on users  (cost=0.00..35.50 rows=2550 width=10)".to_string()],
+            vec!["  ->  Seq Scan on users  (cost=0.00..35.50 rows=2550 width=10)".to_string()],
+        ];
+        assert_eq!(
+            postgres.explain_estimate(&plan),
+            ExplainEstimate { rows: Some(2550), cost: Some(35.5), full_scan: true }
+        );
+
+        let sqlite = dialects::get_dialect("sqlite").unwrap();
+        let scan = vec![vec!["2".into(), "0".into(), "0".into(), "SCAN users".into()]];
+        let search = vec![vec!["2".into(), "0".into(), "0".into(), "SEARCH users USING INTEGER PRIMARY KEY (rowid=?)".into()]];
+        assert!(sqlite.explain_estimate(&scan).full_scan);
+        assert!(!sqlite.explain_estimate(&search).full_scan);
+
+        let explained = ExplainedStatement {
+            statement: "UPDATE users SET active = 1".to_string(),
+            estimate: Ok(postgres.explain_estimate(&plan)),
+        };
+        assert!(explained.is_full_table_write());
+        assert!(is_dml("DELETE FROM users WHERE id = 1"));
+        assert!(!is_dml("CREATE TABLE users (id INT)"));
+    }
+}
@@ -0,0 +1,223 @@
+use crate::model::Migration;
+use crate::orchestrator::approval::{ApprovalError, ApprovalPlan};
+use crate::orchestrator::prompt::{prompt_for, PromptError};
+use log::{info, warn};
+use std::path::PathBuf;
+
+/// Confirmation guard for destructive operations against protected targets
+/// (`[behavior] protected_targets`). The target is the `--env` name.
+#[derive(Debug, Clone, Default)]
+pub struct ProtectionGuard {
+    pub target: Option<String>,
+    pub protected_targets: Vec<String>,
+    pub confirm: Option<String>,
+    /// Signed plan passed with `--approved-plan`
+    pub approved_plan: Option<PathBuf>,
+    /// Exported plan passed with `--plan-file`; apply runs exactly this plan or nothing
+    pub plan_file: Option<PathBuf>,
+    /// Reviewer public keys trusted to approve plans (`[behavior] approval_keys`)
+    pub approval_keys: Vec<String>,
+    /// Never prompt, even on a terminal; confirmation must come from `--confirm`
+    pub non_interactive: bool,
+    /// `--yes`: questions are answered yes, but protected targets still need `--confirm`
+    pub assume_yes: bool,
+}
+
+impl ProtectionGuard {
+    /// Returns true if the current target is listed as protected
+    pub fn is_protected(&self) -> bool {
+        match &self.target {
+            Some(target) => self
+                .protected_targets
+                .iter()
+                .any(|p| p.eq_ignore_ascii_case(target)),
+            None => false,
+        }
+    }
+
+    /// Requires `--confirm <target>` or an interactively typed target name before
+    /// `action` may run against a protected target. Unprotected targets pass through.
+    pub fn ensure_confirmed(&self, action: &str) -> Result<(), GuardError> {
+        if !self.is_protected() {
+            return Ok(());
+        }
+        let target = self.target.as_deref().unwrap_or_default();
+
+        if let Some(confirm) = &self.confirm {
+            if confirm == target {
+                info!("Confirmed {} against protected target '{}'", action, target);
+                return Ok(());
+            }
+            return Err(GuardError::Mismatch(target.to_string(), confirm.clone()));
+        }
+
+        // `--yes` never stands in for the target name; only `--confirm` or typing it does
+        warn!("⚠️  '{}' is a protected target", target);
+        warn!("You are about to {}", action);
+        match prompt_for(false, self.non_interactive).ask(&format!("Type the target name '{}' to continue", target)) {
+            Ok(answer) if answer == target => Ok(()),
+            Ok(_) => Err(GuardError::NotConfirmed(target.to_string())),
+            Err(PromptError::NoAnswer(_)) => Err(GuardError::ConfirmationRequired(target.to_string(), action.to_string())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Asks `question` before going on, unless `--yes` was given; runs that cannot ask
+    /// proceed unasked
+    pub fn ensure_acknowledged(&self, question: &str) -> Result<(), GuardError> {
+        match prompt_for(self.assume_yes, self.non_interactive).confirm(question) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(GuardError::Declined(question.to_string())),
+            Err(PromptError::NonInteractive(_)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Verifies `--approved-plan` when given. Protected targets require one as soon as
+    /// approval keys are configured; the pending set must match the signed plan exactly.
+    pub fn ensure_approved(&self, pending: &[Migration]) -> Result<(), GuardError> {
+        let Some(path) = &self.approved_plan else {
+            if self.is_protected() && !self.approval_keys.is_empty() {
+                return Err(ApprovalError::ApprovalRequired.into());
+            }
+            return Ok(());
+        };
+
+        let plan = ApprovalPlan::read(path)?;
+        plan.verify(&self.approval_keys)?;
+        let same_target = match (&plan.environment, &self.target) {
+            (Some(planned), Some(target)) => planned.eq_ignore_ascii_case(target),
+            (planned, target) => planned == target,
+        };
+        if !same_target {
+            return Err(ApprovalError::EnvironmentMismatch { plan: plan.environment, target: self.target.clone() }.into());
+        }
+        plan.ensure_matches(pending)?;
+
+        if let Some(approval) = &plan.approval {
+            info!(
+                "✅ Pending migrations match plan approved at {} by key {}",
+                approval.approved_at.format("%Y-%m-%d %H:%M:%S UTC"),
+                &approval.public_key[..16.min(approval.public_key.len())]
+            );
+        }
+        Ok(())
+    }
+
+    /// Reads `--plan-file` and checks that the migration directory is the one it was
+    /// exported from
+    pub fn ensure_planned_files(&self, migrations: &[Migration]) -> Result<Option<ApprovalPlan>, ApprovalError> {
+        let Some(path) = &self.plan_file else {
+            return Ok(None);
+        };
+        let plan = ApprovalPlan::read(path)?;
+        plan.ensure_same_files(migrations)?;
+        info!("📝 Migration directory matches plan {}", path.display());
+        Ok(Some(plan))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GuardError {
+    #[error("'{0}' is a protected target: pass --confirm {0} to {1}")]
+    ConfirmationRequired(String, String),
+
+    #[error("Confirmation '{1}' does not match protected target '{0}'")]
+    Mismatch(String, String),
+
+    #[error("Operation against protected target '{0}' was not confirmed")]
+    NotConfirmed(String),
+
+    #[error("Cancelled: answered no to '{0}'")]
+    Declined(String),
+
+    #[error("{0}")]
+    Prompt(#[from] PromptError),
+
+    #[error("Approval check failed: {0}")]
+    Approval(#[from] ApprovalError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guard(target: Option<&str>, confirm: Option<&str>) -> ProtectionGuard {
+        ProtectionGuard {
+            target: target.map(str::to_string),
+            protected_targets: vec!["prod".to_string()],
+            confirm: confirm.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_unprotected_targets_pass() {
+        assert!(guard(None, None).ensure_confirmed("rollback").is_ok());
+        assert!(guard(Some("dev"), None).ensure_confirmed("rollback").is_ok());
+    }
+
+    #[test]
+    fn test_protected_target_requires_matching_token() {
+        assert!(guard(Some("prod"), Some("prod")).ensure_confirmed("rollback").is_ok());
+        assert!(matches!(
+            guard(Some("prod"), Some("staging")).ensure_confirmed("rollback"),
+            Err(GuardError::Mismatch(..))
+        ));
+    }
+
+    #[test]
+    fn test_non_interactive_never_prompts() {
+        let mut guard = guard(Some("prod"), None);
+        guard.non_interactive = true;
+        assert!(matches!(
+            guard.ensure_confirmed("rollback"),
+            Err(GuardError::ConfirmationRequired(..))
+        ));
+    }
+
+    #[test]
+    fn test_yes_answers_questions_but_never_confirms_a_protected_target() {
+        let mut guard = guard(Some("prod"), None);
+        guard.assume_yes = true;
+        guard.non_interactive = true;
+        assert!(guard.ensure_acknowledged("Apply to these large tables now?").is_ok());
+        assert!(matches!(
+            guard.ensure_confirmed("rollback"),
+            Err(GuardError::ConfirmationRequired(..))
+        ));
+    }
+
+    #[test]
+    fn test_protected_target_requires_approved_plan_when_keys_configured() {
+        let mut guard = guard(Some("prod"), Some("prod"));
+        assert!(guard.ensure_approved(&[]).is_ok());
+
+        guard.approval_keys = vec!["00".repeat(32)];
+        assert!(matches!(
+            guard.ensure_approved(&[]),
+            Err(GuardError::Approval(ApprovalError::ApprovalRequired))
+        ));
+    }
+
+    #[test]
+    fn test_approved_plan_must_target_this_environment() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = ed25519_dalek::SigningKey::from_bytes(&[7u8; 32]);
+        let path = dir.path().join("plan.json");
+        let mut plan = ApprovalPlan::from_migrations(&[], Some("staging"));
+        plan.sign(&key);
+        plan.write(&path).unwrap();
+
+        let mut guard = guard(Some("prod"), Some("prod"));
+        guard.approval_keys = vec![hex::encode(key.verifying_key().to_bytes())];
+        guard.approved_plan = Some(path.clone());
+        assert!(matches!(
+            guard.ensure_approved(&[]),
+            Err(GuardError::Approval(ApprovalError::EnvironmentMismatch { .. }))
+        ));
+
+        guard.target = Some("staging".to_string());
+        assert!(guard.ensure_approved(&[]).is_ok());
+    }
+}
@@ -1,4 +1,6 @@
+use crate::orchestrator::migration_loader::{InvalidFile, LoaderOptions};
 use log::{info, warn, error, debug};
+use serde::Serialize;
 use std::process::Command;
 use std::path::Path;
 use std::fs;
@@ -10,44 +12,22 @@ pub struct HealthCheckResult {
     pub message: String,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum HealthStatus {
     Pass,
     Warn,
     Fail,
 }
 
-pub fn run_health(path: &str, dialect: &str) {
+pub fn run_health(path: &str, dialect: &str, loader: &LoaderOptions) {
     info!("Running system health check");
     debug!("Migrations path: {}", path);
     debug!("SQL dialect: {}", dialect);
 
-    let mut checks = Vec::new();
+    let checks = collect_health_checks(path, dialect, loader);
     let mut overall_status = HealthStatus::Pass;
 
-    // Check Python installation
-    checks.push(check_python());
-    
-    // Check SQLGlot availability
-    checks.push(check_sqlglot(dialect));
-    
-    // Check migrations directory
-    checks.push(check_migrations_directory(path));
-    
-    // Check migration file permissions
-    checks.push(check_file_permissions(path));
-    
-    // Check for migration sequence issues
-    if let Ok(migrations) = crate::orchestrator::MigrationLoader::load_migrations(path) {
-        checks.push(check_migration_sequence(&migrations));
-    } else {
-        checks.push(HealthCheckResult {
-            name: "Migration Loading".to_string(),
-            status: HealthStatus::Fail,
-            message: "Failed to load migrations".to_string(),
-        });
-    }
-
     // Display results
     info!("Health Check Results:");
     info!("===================");
@@ -78,6 +58,36 @@ pub fn run_health(path: &str, dialect: &str) {
     }
 }
 
+/// Runs the local environment and migration file checks shared by `health` and `doctor`
+pub fn collect_health_checks(path: &str, dialect: &str, loader: &LoaderOptions) -> Vec<HealthCheckResult> {
+    let mut checks = vec![
+        // Check Python installation
+        check_python(),
+        // Check SQLGlot availability
+        check_sqlglot(dialect),
+        // Check migrations directory
+        check_migrations_directory(path),
+        // Check migration file permissions
+        check_file_permissions(path),
+    ];
+    
+    // Check for migration sequence issues
+    match crate::orchestrator::MigrationLoader::load_migrations_with_report(path, loader) {
+        Ok(loaded) => {
+            checks.push(check_file_pattern(&loaded.excluded, loader));
+            checks.push(check_file_validity(&loaded.invalid));
+            checks.push(check_migration_sequence(&loaded.migrations));
+        }
+        Err(e) => checks.push(HealthCheckResult {
+            name: "Migration Loading".to_string(),
+            status: HealthStatus::Fail,
+            message: format!("Failed to load migrations: {}", e),
+        }),
+    }
+
+    checks
+}
+
 fn check_python() -> HealthCheckResult {
     match Command::new("python").arg("--version").output() {
         Ok(output) if output.status.success() => {
@@ -216,6 +226,53 @@ fn check_file_permissions(path: &str) -> HealthCheckResult {
     }
 }
 
+fn check_file_pattern(excluded: &[std::path::PathBuf], loader: &LoaderOptions) -> HealthCheckResult {
+    let pattern = loader.file_pattern.as_deref().unwrap_or_default();
+
+    if excluded.is_empty() {
+        return HealthCheckResult {
+            name: "File Pattern".to_string(),
+            status: HealthStatus::Pass,
+            message: format!("All SQL files match file pattern '{}'", pattern),
+        };
+    }
+
+    let files: Vec<String> = excluded
+        .iter()
+        .filter_map(|f| f.file_name().map(|n| n.to_string_lossy().to_string()))
+        .collect();
+    HealthCheckResult {
+        name: "File Pattern".to_string(),
+        status: HealthStatus::Warn,
+        message: format!(
+            "{} SQL files excluded by file pattern '{}': {}",
+            files.len(),
+            pattern,
+            files.join(", ")
+        ),
+    }
+}
+
+fn check_file_validity(invalid: &[InvalidFile]) -> HealthCheckResult {
+    if invalid.is_empty() {
+        return HealthCheckResult {
+            name: "File Validity".to_string(),
+            status: HealthStatus::Pass,
+            message: "All migration files are within size limits and valid UTF-8".to_string(),
+        };
+    }
+
+    let problems: Vec<String> = invalid
+        .iter()
+        .map(|f| format!("{} ({})", f.path.display(), f.reason))
+        .collect();
+    HealthCheckResult {
+        name: "File Validity".to_string(),
+        status: HealthStatus::Warn,
+        message: format!("{} invalid migration files: {}", problems.len(), problems.join(", ")),
+    }
+}
+
 fn check_migration_sequence(migrations: &[crate::model::Migration]) -> HealthCheckResult {
     let issues = crate::orchestrator::Validator::validate_migration_sequence(migrations);
     
@@ -0,0 +1,171 @@
+use crate::executor::ConnectionError;
+use crate::logger::SUMMARY_TARGET;
+use crate::tracker::version_store::AppliedMigration;
+use crate::tracker::{schema_init, Target};
+use chrono::{DateTime, NaiveDate, Utc};
+use log::info;
+use std::fmt;
+
+/// What happened to a migration at a point in its history
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryEventKind {
+    Applied,
+    Failed,
+    RolledBack,
+}
+
+impl fmt::Display for HistoryEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            HistoryEventKind::Applied => "✅ applied",
+            HistoryEventKind::Failed => "❌ failed",
+            HistoryEventKind::RolledBack => "↩️  rolled back",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HistoryEvent {
+    pub at: DateTime<Utc>,
+    pub kind: HistoryEventKind,
+    pub record: AppliedMigration,
+}
+
+/// Which events `history` shows
+#[derive(Debug, Clone, Default)]
+pub struct HistoryFilter {
+    /// Only rollbacks
+    pub rolled_back: bool,
+    pub version: Option<u32>,
+    /// Only events on or after this day (`YYYY-MM-DD`)
+    pub since: Option<String>,
+}
+
+/// Lists applies, failures and rollbacks recorded in schema_migrations, oldest first.
+/// Rolled back records are kept in schema_migrations_rollbacks with `rolled_back_at` set,
+/// so both the original apply and each rollback are shown.
+pub fn run_history(target: &Target, filter: &HistoryFilter) -> Result<(), HistoryError> {
+    if !schema_init::check_migration_table_exists(target)? {
+        return Err(HistoryError::NoHistory);
+    }
+    let since = filter
+        .since
+        .as_deref()
+        .map(|day| {
+            NaiveDate::parse_from_str(day, "%Y-%m-%d")
+                .map(|date| date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc())
+                .map_err(|_| HistoryError::InvalidDate(day.to_string()))
+        })
+        .transpose()?;
+
+    let mut version_store = target.version_store()?;
+    let applied = version_store.get_applied_migrations()?;
+    let rolled_back = version_store.get_rolled_back_migrations()?;
+
+    let events = history_events(&applied, &rolled_back, filter.rolled_back, filter.version, since);
+    if events.is_empty() {
+        info!(target: SUMMARY_TARGET, "No migration history matches");
+        return Ok(());
+    }
+
+    info!("📜 Migration history");
+    for event in &events {
+        let mut details = Vec::new();
+        if let Some(release) = &event.record.release {
+            details.push(format!("release {}", release));
+        }
+        if let Some(commit) = &event.record.git_commit {
+            details.push(format!("commit {:.7}", commit));
+        }
+        if let Some(run_id) = &event.record.run_id {
+            details.push(format!("run {}", run_id));
+        }
+        let details = if details.is_empty() { String::new() } else { format!(" ({})", details.join(", ")) };
+        info!(
+            "  {}  {:<16} {}{}",
+            event.at.format("%Y-%m-%d %H:%M:%S"),
+            event.kind.to_string(),
+            event.record.filename,
+            details
+        );
+    }
+    let rollbacks = events.iter().filter(|e| e.kind == HistoryEventKind::RolledBack).count();
+    info!(target: SUMMARY_TARGET, "{} event(s), {} rollback(s)", events.len(), rollbacks);
+    Ok(())
+}
+
+/// Turns records into events and applies the filters. A rolled back record yields its
+/// apply and its rollback; baselines and repeatables are listed as applied.
+pub fn history_events(
+    applied: &[AppliedMigration],
+    rolled_back: &[AppliedMigration],
+    rolled_back_only: bool,
+    version: Option<u32>,
+    since: Option<DateTime<Utc>>,
+) -> Vec<HistoryEvent> {
+    let mut events = Vec::new();
+    for record in applied.iter().chain(rolled_back) {
+        let kind = if record.success { HistoryEventKind::Applied } else { HistoryEventKind::Failed };
+        events.push(HistoryEvent { at: record.applied_at, kind, record: record.clone() });
+        if let Some(at) = record.rolled_back_at {
+            events.push(HistoryEvent { at, kind: HistoryEventKind::RolledBack, record: record.clone() });
+        }
+    }
+    events.retain(|event| {
+        (!rolled_back_only || event.kind == HistoryEventKind::RolledBack)
+            && (version.is_none() || event.record.version == version)
+            && since.is_none_or(|since| event.at >= since)
+    });
+    events.sort_by_key(|event| event.at);
+    events
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum HistoryError {
+    #[error("schema_migrations does not exist, so there is no history to show")]
+    NoHistory,
+
+    #[error("Invalid date '{0}', expected YYYY-MM-DD")]
+    InvalidDate(String),
+
+    #[error("Connection error: {0}")]
+    Connection(#[from] ConnectionError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn record(version: u32, days_ago: i64, rolled_back_days_ago: Option<i64>) -> AppliedMigration {
+        AppliedMigration {
+            applied_at: Utc::now() - Duration::days(days_ago),
+            rolled_back_at: rolled_back_days_ago.map(|days| Utc::now() - Duration::days(days)),
+            ..AppliedMigration::of_version(version)
+        }
+    }
+
+    #[test]
+    fn test_rolled_back_records_show_apply_and_rollback() {
+        let applied = vec![record(1, 10, None), record(2, 1, None)];
+        let rolled_back = vec![record(2, 5, Some(3))];
+
+        let all = history_events(&applied, &rolled_back, false, None, None);
+        let kinds: Vec<(Option<u32>, HistoryEventKind)> = all.iter().map(|e| (e.record.version, e.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (Some(1), HistoryEventKind::Applied),
+                (Some(2), HistoryEventKind::Applied),
+                (Some(2), HistoryEventKind::RolledBack),
+                (Some(2), HistoryEventKind::Applied),
+            ]
+        );
+
+        let rollbacks = history_events(&applied, &rolled_back, true, None, None);
+        assert_eq!(rollbacks.len(), 1);
+        assert_eq!(history_events(&applied, &rolled_back, false, Some(1), None).len(), 1);
+        assert_eq!(history_events(&applied, &rolled_back, false, None, Some(Utc::now() - Duration::days(4))).len(), 2);
+    }
+}
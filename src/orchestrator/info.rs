@@ -0,0 +1,183 @@
+use crate::executor::ConnectionError;
+use crate::model::Migration;
+use crate::orchestrator::{MigrationLoader, Validator};
+use crate::orchestrator::migration_loader::LoaderOptions;
+use crate::tracker::{schema_init, Target};
+use log::{debug, info, warn};
+
+pub fn run_info(
+    database: Option<&Target>,
+    path: &str,
+    loader: &LoaderOptions,
+    target: &str,
+) -> Result<(), InfoError> {
+    info!("Showing migration details for '{}'", target);
+    debug!("Migrations path: {}", path);
+
+    let migrations = MigrationLoader::load_migrations_with_options(path, loader)
+        .map_err(|e| InfoError::LoadFailed(e.to_string()))?;
+
+    let migration = find_migration(&migrations, target)
+        .ok_or_else(|| InfoError::NotFound(target.to_string()))?;
+
+    info!("📄 Migration {}", migration.identifier());
+    info!("==================");
+    info!("File: {}", migration.file_path.display());
+    info!("Type: {:?}", migration.migration_type);
+    if let Some(version) = migration.version {
+        info!("Version: {}", version);
+    }
+    info!("Name: {}", migration.name);
+    if let Some(namespace) = &migration.namespace {
+        info!("Namespace: {}", namespace);
+    }
+    if let Some(owner) = &migration.owner {
+        info!("Owner: {}", owner);
+    }
+    info!("Checksum: {}", migration.checksum);
+    if migration.has_requirements() {
+        let requires: Vec<String> = migration.requires.iter().map(|v| format!("{:04}", v)).collect();
+        info!("Requires: {}", requires.join(", "));
+    }
+    if !migration.environments.is_empty() {
+        info!("Environments: {}", migration.environments.join(", "));
+    }
+    for guard in &migration.guards {
+        info!("Guard: {}", guard);
+    }
+
+    // Validation results that concern this migration
+    let filename = migration.filename();
+    let issues: Vec<String> = Validator::validate_migration_sequence(&migrations)
+        .into_iter()
+        .filter(|issue| issue.contains(&filename))
+        .collect();
+    info!("");
+    if issues.is_empty() {
+        info!("Validation: ✅ no issues");
+    } else {
+        warn!("Validation: ⚠️  {} issue(s)", issues.len());
+        for issue in &issues {
+            warn!("  - {}", issue);
+        }
+    }
+
+    info!("");
+    match database {
+        Some(database) => display_applied_state(database, migration)?,
+        None => info!("Applied status: unknown (no connection string provided)"),
+    }
+
+    info!("");
+    info!("Up SQL ({} lines):", migration.sql_content.lines().count());
+    for line in migration.sql_content.lines() {
+        info!("  {}", line);
+    }
+
+    info!("");
+    match migration.get_rollback_sql() {
+        Some(down_sql) if migration.has_rollback() => {
+            info!("Down SQL ({} lines):", down_sql.lines().count());
+            for line in down_sql.lines() {
+                info!("  {}", line);
+            }
+        }
+        _ => info!("Down SQL: none"),
+    }
+
+    Ok(())
+}
+
+/// Finds a migration by version number, identifier (e.g. `analytics:3`, `R__views`),
+/// filename or file path.
+pub fn find_migration<'a>(migrations: &'a [Migration], target: &str) -> Option<&'a Migration> {
+    let target = target.trim();
+    let version = target.parse::<u32>().ok();
+
+    migrations.iter().find(|m| {
+        (version.is_some() && m.version == version && m.namespace.is_none())
+            || m.identifier() == target
+            || m.filename() == target
+            || m.file_path.file_name().is_some_and(|name| name == target)
+            || m.file_path.ends_with(target)
+    })
+}
+
+fn display_applied_state(database: &Target, migration: &Migration) -> Result<(), InfoError> {
+    if !schema_init::check_migration_table_exists(database)? {
+        info!("Applied status: not applied (schema_migrations table does not exist)");
+        return Ok(());
+    }
+
+    let mut version_store = database.version_store()?;
+    let baseline_version = version_store.get_baseline_version()?;
+    let applied = version_store
+        .get_applied_migrations()?
+        .into_iter()
+        .find(|a| a.migration_id == migration.identifier());
+
+    match applied {
+        Some(applied) => {
+            let state = if applied.success { "✅ applied" } else { "❌ failed" };
+            info!("Applied status: {}", state);
+            info!("Applied at: {}", applied.applied_at.format("%Y-%m-%d %H:%M:%S UTC"));
+            info!("Applied by: not recorded");
+            info!("Release: {}", applied.release.as_deref().unwrap_or("not recorded"));
+            info!("Run: {}", applied.run_id.as_deref().unwrap_or("not recorded"));
+            match (&applied.git_commit, &applied.git_branch) {
+                (Some(commit), Some(branch)) => info!("Git commit: {} (branch {})", commit, branch),
+                (Some(commit), None) => info!("Git commit: {}", commit),
+                _ => info!("Git commit: not recorded"),
+            }
+            if applied.applied_externally() {
+                info!("Execution time: n/a (marked as applied externally)");
+            } else if applied.skipped_by_guard() {
+                info!("Execution time: n/a (skipped by its guard)");
+            } else if applied.skip_listed() {
+                info!("Execution time: n/a (skipped, on the skip list)");
+            } else {
+                info!("Execution time: {}ms", applied.execution_time_ms);
+            }
+            if migration.matches_checksum(&applied.checksum) {
+                info!("Recorded checksum: {} (matches)", applied.checksum);
+            } else {
+                warn!("Recorded checksum: {} (⚠️  differs from file)", applied.checksum);
+            }
+        }
+        None => {
+            let rolled_back = version_store
+                .get_rolled_back_migrations()?
+                .into_iter()
+                .filter(|a| a.migration_id == migration.identifier())
+                .filter_map(|a| a.rolled_back_at)
+                .max();
+            match rolled_back {
+                Some(at) => info!("Applied status: ⏳ pending (rolled back at {})", at.format("%Y-%m-%d %H:%M:%S UTC")),
+                None => info!("Applied status: ⏳ pending"),
+            }
+        }
+    }
+
+    match (baseline_version, migration.version) {
+        (Some(baseline), Some(version)) if version <= baseline => {
+            info!("Baseline: at or below baseline version {} (will not be applied)", baseline);
+        }
+        (Some(baseline), Some(_)) => info!("Baseline: above baseline version {}", baseline),
+        (Some(_), None) => info!("Baseline: not applicable to repeatable migrations"),
+        (None, _) => info!("Baseline: none"),
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum InfoError {
+    #[error("Failed to load migrations: {0}")]
+    LoadFailed(String),
+
+    #[error("No migration matches '{0}'")]
+    NotFound(String),
+
+    #[error("Connection error: {0}")]
+    Connection(#[from] ConnectionError),
+}
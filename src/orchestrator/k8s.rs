@@ -0,0 +1,199 @@
+use crate::model::Config;
+use crate::orchestrator::apply::{ApplyError, ApplyOutcome};
+use crate::tracker::lease::{LeaseError, LeaseGuard};
+use crate::tracker::{MigrationLease, Target};
+use chrono::Utc;
+use log::{debug, error, info, warn};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Settings for `apply --k8s-mode`, taken from the `[kubernetes]` config section
+#[derive(Debug, Clone)]
+pub struct K8sOptions {
+    pub wait_for_db: Duration,
+    pub lock_lease: Duration,
+    pub lock_wait: Duration,
+    pub heartbeat: Duration,
+    pub stale_lock: Duration,
+    /// Take over a lock whose holder stopped sending heartbeats (`--steal-lock`)
+    pub steal_lock: bool,
+    pub liveness_file: PathBuf,
+    pub liveness_interval: Duration,
+    pub termination_log: Option<PathBuf>,
+    pub exit_code_applied: i32,
+    pub exit_code_nothing_to_do: i32,
+    pub exit_code_failed: i32,
+}
+
+impl K8sOptions {
+    pub fn from_config(config: &Config) -> Self {
+        let k8s = &config.kubernetes;
+        Self {
+            wait_for_db: Duration::from_secs(k8s.wait_for_db_secs),
+            lock_lease: Duration::from_secs(k8s.lock_lease_secs.max(1)),
+            lock_wait: Duration::from_secs(k8s.lock_wait_secs),
+            heartbeat: Duration::from_secs(k8s.heartbeat_secs.max(1)),
+            stale_lock: Duration::from_secs(k8s.stale_lock_secs.max(1)),
+            steal_lock: false,
+            liveness_file: PathBuf::from(&k8s.liveness_file),
+            liveness_interval: Duration::from_secs(k8s.liveness_interval_secs.max(1)),
+            termination_log: k8s.termination_log.as_ref().map(PathBuf::from),
+            exit_code_applied: k8s.exit_code_applied,
+            exit_code_nothing_to_do: k8s.exit_code_nothing_to_do,
+            exit_code_failed: k8s.exit_code_failed,
+        }
+    }
+
+    /// Process exit code for the result of a k8s-mode apply
+    pub fn exit_code(&self, result: &Result<ApplyOutcome, K8sError>) -> i32 {
+        match result {
+            Ok(ApplyOutcome::NothingToDo) => self.exit_code_nothing_to_do,
+            Ok(_) => self.exit_code_applied,
+            Err(_) => self.exit_code_failed,
+        }
+    }
+
+    /// Writes a one-line outcome to the termination log, which Kubernetes shows
+    /// as the container's termination message, with the id of the run
+    pub fn write_termination_log(&self, result: &Result<ApplyOutcome, K8sError>, run_id: &str) {
+        let Some(path) = &self.termination_log else {
+            return;
+        };
+        let message = match result {
+            Ok(ApplyOutcome::NothingToDo) => "nothing to do: database is up to date".to_string(),
+            Ok(ApplyOutcome::Applied(count)) => format!("applied {} migration(s)", count),
+            Ok(ApplyOutcome::DryRun(count)) | Ok(ApplyOutcome::ScriptWritten(count)) => {
+                format!("{} migration(s) pending", count)
+            }
+            Err(e) => format!("failed: {}", e),
+        };
+        // The default path only exists inside a pod, so this is best effort
+        if let Err(e) = fs::write(path, format!("{} (run {})\n", message, run_id)) {
+            debug!("Could not write termination log {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Runs `apply` the way a Kubernetes Job or init container needs it: waits for the
+/// database to come up, holds a lease lock so only one replica migrates, and keeps
+/// the liveness file fresh and the lease renewed until `apply` returns.
+pub fn run_k8s_apply<F>(target: &Target, options: &K8sOptions, apply: F) -> Result<ApplyOutcome, K8sError>
+where
+    F: FnOnce() -> Result<ApplyOutcome, ApplyError>,
+{
+    info!("Running in Kubernetes mode");
+    touch_liveness_file(&options.liveness_file);
+
+    wait_for_database(target, options.wait_for_db)?;
+
+    let lease = MigrationLease::new(target, options.lock_lease)
+        .with_stale_after(options.stale_lock)
+        .with_steal_stale(options.steal_lock);
+    lease.acquire(options.lock_wait)?;
+    let _guard = LeaseGuard(&lease);
+
+    let (stop, stopped) = mpsc::channel::<()>();
+    let heartbeat = {
+        let lease = lease.clone();
+        let liveness_file = options.liveness_file.clone();
+        let interval = options.liveness_interval;
+        // Renewing also records the heartbeat, and must happen before the lease runs out
+        let renew_every = options.heartbeat.min(options.lock_lease / 3);
+        thread::spawn(move || {
+            let mut last_renewal = Instant::now();
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                touch_liveness_file(&liveness_file);
+                if last_renewal.elapsed() >= renew_every {
+                    if let Err(e) = lease.renew() {
+                        error!("Failed to renew migration lock: {}", e);
+                    }
+                    last_renewal = Instant::now();
+                }
+            }
+        })
+    };
+
+    let result = apply();
+
+    let _ = stop.send(());
+    if heartbeat.join().is_err() {
+        warn!("Liveness heartbeat thread panicked");
+    }
+    touch_liveness_file(&options.liveness_file);
+
+    Ok(result?)
+}
+
+/// Retries a test connection with backoff until the database answers or `timeout` passes
+fn wait_for_database(target: &Target, timeout: Duration) -> Result<(), K8sError> {
+    let manager = target.connection_manager().map_err(|e| K8sError::DatabaseUnavailable(e.to_string()))?;
+    let started = Instant::now();
+    let mut delay = Duration::from_secs(1);
+
+    loop {
+        match manager.test_connection(&target.conn) {
+            Ok(()) => return Ok(()),
+            Err(e) if started.elapsed() + delay > timeout => {
+                return Err(K8sError::DatabaseUnavailable(e.to_string()));
+            }
+            Err(e) => {
+                info!("Database not reachable yet, retrying in {}s: {}", delay.as_secs(), e);
+                thread::sleep(delay);
+                delay = (delay * 2).min(Duration::from_secs(10));
+            }
+        }
+    }
+}
+
+/// Writes the current time to the liveness file, updating its mtime for probes
+fn touch_liveness_file(path: &Path) {
+    if let Err(e) = fs::write(path, format!("{}\n", Utc::now().timestamp())) {
+        debug!("Could not touch liveness file {}: {}", path.display(), e);
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum K8sError {
+    #[error("Database did not become available: {0}")]
+    DatabaseUnavailable(String),
+
+    #[error("{0}")]
+    Lock(#[from] LeaseError),
+
+    #[error("{0}")]
+    Apply(#[from] ApplyError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_exit_codes_distinguish_outcomes() {
+        let mut config = Config::default();
+        config.kubernetes.exit_code_nothing_to_do = 3;
+        let options = K8sOptions::from_config(&config);
+
+        assert_eq!(options.exit_code(&Ok(ApplyOutcome::NothingToDo)), 3);
+        assert_eq!(options.exit_code(&Ok(ApplyOutcome::Applied(2))), 0);
+        assert_eq!(
+            options.exit_code(&Err(K8sError::DatabaseUnavailable("down".to_string()))),
+            1
+        );
+    }
+
+    #[test]
+    fn test_termination_log_records_outcome() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut options = K8sOptions::from_config(&Config::default());
+        options.termination_log = Some(temp_dir.path().join("termination-log"));
+
+        options.write_termination_log(&Ok(ApplyOutcome::Applied(2)), "8f0c");
+        let message = fs::read_to_string(temp_dir.path().join("termination-log")).unwrap();
+        assert_eq!(message, "applied 2 migration(s) (run 8f0c)\n");
+    }
+}
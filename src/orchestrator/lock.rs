@@ -0,0 +1,84 @@
+use crate::dialects::{self, DatabaseDialect};
+use crate::model::Config;
+use std::sync::Arc;
+
+/// Lock wait timeout and retry behavior for `apply`. Migrations can override the
+/// timeout and retry count with `-- deriddl:lock_timeout` and `-- deriddl:lock_retries`.
+#[derive(Debug, Clone, Default)]
+pub struct LockPolicy {
+    /// Dialect used to render the lock timeout statement
+    pub dialect: Option<String>,
+    /// Default lock wait timeout; None leaves the server default in place
+    pub timeout_ms: Option<u64>,
+    /// How often a migration that failed to acquire a lock is retried
+    pub retries: u32,
+    /// Delay before the first retry, growing linearly with each attempt
+    pub retry_delay_ms: u64,
+    /// Estimated rows above which a locked or rewritten table is reported before apply
+    pub large_table_rows: Option<u64>,
+    /// Size in MB above which a locked or rewritten table is reported before apply
+    pub large_table_mb: Option<u64>,
+}
+
+impl LockPolicy {
+    pub fn from_config(config: &Config) -> Self {
+        // `[dialect.<name>]` may hold a default for the configured dialect
+        let dialect_timeout_ms = || {
+            dialects::get_dialect_with_config(None, None, Some(&config.migrations.dialect))
+                .ok()
+                .and_then(|dialect| dialect.default_lock_timeout_ms(&config.dialect))
+        };
+        Self {
+            dialect: Some(config.migrations.dialect.clone()),
+            timeout_ms: config.database.lock_timeout_ms.or_else(dialect_timeout_ms),
+            retries: config.database.lock_retries,
+            retry_delay_ms: config.database.lock_retry_delay_ms,
+            large_table_rows: config.database.large_table_rows,
+            large_table_mb: config.database.large_table_mb,
+        }
+    }
+
+    /// Whether `large_table_rows` or `large_table_mb` asks for table sizes before apply
+    pub fn checks_table_sizes(&self) -> bool {
+        self.large_table_rows.is_some() || self.large_table_mb.is_some()
+    }
+
+    /// Whether a table of this estimated size is over either threshold
+    pub(crate) fn exceeds(&self, rows: Option<u64>, bytes: Option<u64>) -> bool {
+        let too_many_rows = matches!((rows, self.large_table_rows), (Some(rows), Some(max)) if rows > max);
+        let too_large = matches!((bytes, self.large_table_mb), (Some(bytes), Some(max)) if bytes > max * 1024 * 1024);
+        too_many_rows || too_large
+    }
+
+    pub(crate) fn resolve_dialect(&self) -> Option<Arc<dyn DatabaseDialect>> {
+        dialects::get_dialect_with_config(None, None, self.dialect.as_deref()).ok()
+    }
+}
+
+/// Returns true if a database error message reports a lock wait timeout
+pub fn is_lock_timeout(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    [
+        "lock timeout",                // PostgreSQL: canceling statement due to lock timeout
+        "55p03",                       // PostgreSQL SQLSTATE lock_not_available
+        "could not obtain lock",       // PostgreSQL NOWAIT
+        "lock wait timeout exceeded",  // MySQL 1205
+        "lock request time out",       // SQL Server 1222
+        "database is locked",          // SQLite
+    ]
+    .iter()
+    .any(|pattern| message.contains(pattern))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_lock_timeouts() {
+        assert!(is_lock_timeout("ERROR: canceling statement due to lock timeout"));
+        assert!(is_lock_timeout("[MySQL][ODBC 8.0 Driver]Lock wait timeout exceeded; try restarting transaction"));
+        assert!(is_lock_timeout("State: 55P03, Native error: 7"));
+        assert!(!is_lock_timeout("ERROR: relation \"users\" does not exist"));
+    }
+}
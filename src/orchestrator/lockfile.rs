@@ -0,0 +1,199 @@
+use crate::logger::SUMMARY_TARGET;
+use crate::model::{Migration, MigrationType};
+use crate::orchestrator::MigrationLoader;
+use crate::orchestrator::migration_loader::LoaderOptions;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const LOCKFILE_FORMAT: u32 = 1;
+const LOCKFILE_HEADER: &str = "# Generated by `deriddl lock`. Commit this file and regenerate it when adding migrations.\n";
+
+/// Versioned migrations as they were when `deriddl lock` last ran. Repeatable migrations
+/// are meant to change and are not locked.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lockfile {
+    pub format: u32,
+    #[serde(default, rename = "migration")]
+    pub migrations: Vec<LockedMigration>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockedMigration {
+    pub id: String,
+    pub version: u32,
+    pub filename: String,
+    pub checksum: String,
+}
+
+/// Where the lockfile lives and whether apply and validate may run without one
+#[derive(Debug, Clone, Default)]
+pub struct LockfilePolicy {
+    pub path: PathBuf,
+    pub required: bool,
+}
+
+impl Lockfile {
+    pub fn from_migrations(migrations: &[Migration]) -> Self {
+        let mut migrations: Vec<LockedMigration> = migrations
+            .iter()
+            .filter(|m| m.migration_type == MigrationType::Versioned)
+            .map(|m| LockedMigration {
+                id: m.identifier(),
+                version: m.version.unwrap_or(0),
+                filename: file_name(m),
+                checksum: m.checksum.clone(),
+            })
+            .collect();
+        migrations.sort_by(|a, b| (a.version, &a.id).cmp(&(b.version, &b.id)));
+        Self { format: LOCKFILE_FORMAT, migrations }
+    }
+
+    pub fn read(path: &Path) -> Result<Self, LockfileError> {
+        let content = fs::read_to_string(path).map_err(|e| LockfileError::Io(path.display().to_string(), e.to_string()))?;
+        let lockfile: Self = toml::from_str(&content).map_err(|e| LockfileError::Invalid(e.to_string()))?;
+        if lockfile.format != LOCKFILE_FORMAT {
+            return Err(LockfileError::Invalid(format!("unsupported format {}", lockfile.format)));
+        }
+        Ok(lockfile)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), LockfileError> {
+        let body = toml::to_string(self).map_err(|e| LockfileError::Invalid(e.to_string()))?;
+        fs::write(path, format!("{}{}", LOCKFILE_HEADER, body))
+            .map_err(|e| LockfileError::Io(path.display().to_string(), e.to_string()))
+    }
+
+    /// Locked migrations that were edited, renamed or removed since the lockfile was
+    /// written, and versioned migrations it does not list yet
+    pub fn compare(&self, migrations: &[Migration]) -> (Vec<String>, Vec<String>) {
+        let current = Self::from_migrations(migrations);
+        let mut problems = Vec::new();
+        for locked in &self.migrations {
+            match current.migrations.iter().find(|m| m.id == locked.id) {
+                None => problems.push(format!("{} (version {}) was removed or renumbered", locked.filename, locked.id)),
+                Some(m) if m.filename != locked.filename => {
+                    problems.push(format!("version {} was renamed from {} to {}", locked.id, locked.filename, m.filename))
+                }
+                // Checksums locked before the checksum strategy changed still match
+                Some(m) if !migrations.iter().any(|file| file.filename() == m.filename && file.matches_checksum(&locked.checksum)) => {
+                    problems.push(format!("{} was edited after it was locked", m.filename))
+                }
+                Some(_) => {}
+            }
+        }
+        let unlocked = current
+            .migrations
+            .iter()
+            .filter(|m| !self.migrations.iter().any(|locked| locked.id == m.id))
+            .map(|m| m.filename.clone())
+            .collect();
+        (problems, unlocked)
+    }
+}
+
+impl LockfilePolicy {
+    /// Checks the loaded migrations against the lockfile, when there is one, before
+    /// anything is executed
+    pub fn verify(&self, migrations: &[Migration]) -> Result<(), LockfileError> {
+        if !self.path.is_file() {
+            if self.required {
+                return Err(LockfileError::Missing(self.path.display().to_string()));
+            }
+            debug!("No lockfile at {}, skipping lockfile check", self.path.display());
+            return Ok(());
+        }
+
+        let lockfile = Lockfile::read(&self.path)?;
+        let (problems, unlocked) = lockfile.compare(migrations);
+        for filename in &unlocked {
+            warn!("⚠️  {} is not in {}; run `deriddl lock` to record it", filename, self.path.display());
+        }
+        if !problems.is_empty() {
+            return Err(LockfileError::Mismatch(problems));
+        }
+        info!("🔒 Migrations match {} ({} locked)", self.path.display(), lockfile.migrations.len());
+        Ok(())
+    }
+}
+
+/// Records the filename, version and checksum of every versioned migration in `output`
+pub fn run_lock(path: &str, loader: &LoaderOptions, output: &Path) -> Result<(), LockfileError> {
+    let migrations = MigrationLoader::load_migrations_with_options(path, loader)
+        .map_err(|e| LockfileError::Io(path.to_string(), e.to_string()))?;
+    let lockfile = Lockfile::from_migrations(&migrations);
+
+    if output.is_file()
+        && let Ok(previous) = Lockfile::read(output)
+    {
+        let (problems, _) = previous.compare(&migrations);
+        for problem in &problems {
+            warn!("⚠️  Rewriting history: {}", problem);
+        }
+    }
+    lockfile.write(output)?;
+
+    info!(target: SUMMARY_TARGET, "🔒 Locked {} migration(s) in {}", lockfile.migrations.len(), output.display());
+    Ok(())
+}
+
+fn file_name(migration: &Migration) -> String {
+    migration
+        .file_path
+        .file_name()
+        .map_or_else(|| migration.filename(), |name| name.to_string_lossy().to_string())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LockfileError {
+    #[error("Lockfile {0} not found; run `deriddl lock` and commit it")]
+    Missing(String),
+
+    #[error("Invalid lockfile: {0}")]
+    Invalid(String),
+
+    #[error("Migrations do not match the lockfile: {}", .0.join("; "))]
+    Mismatch(Vec<String>),
+
+    #[error("Failed to read {0}: {1}")]
+    Io(String, String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn migration(version: u32, name: &str, sql: &str) -> Migration {
+        Migration::new(version, name.to_string(), PathBuf::from(format!("{:04}_{}.sql", version, name)), sql.to_string())
+    }
+
+    #[test]
+    fn test_lockfile_detects_edits_renames_and_removals() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("deriddl.lock");
+        let locked = vec![
+            migration(1, "init", "CREATE TABLE t (id INTEGER);"),
+            migration(2, "users", "CREATE TABLE u (id INTEGER);"),
+            migration(3, "orders", "CREATE TABLE o (id INTEGER);"),
+        ];
+        Lockfile::from_migrations(&locked).write(&path).unwrap();
+        assert_eq!(Lockfile::read(&path).unwrap(), Lockfile::from_migrations(&locked));
+
+        let policy = LockfilePolicy { path: path.clone(), required: true };
+        policy.verify(&locked).unwrap();
+
+        let current = vec![
+            migration(1, "init", "DROP TABLE t;"),
+            migration(2, "accounts", "CREATE TABLE u (id INTEGER);"),
+            migration(4, "items", "CREATE TABLE i (id INTEGER);"),
+        ];
+        let (problems, unlocked) = Lockfile::read(&path).unwrap().compare(&current);
+        assert_eq!(problems.len(), 3);
+        assert_eq!(unlocked, vec!["0004_items.sql"]);
+        assert!(matches!(policy.verify(&current), Err(LockfileError::Mismatch(_))));
+
+        let missing = LockfilePolicy { path: dir.path().join("other.lock"), required: true };
+        assert!(matches!(missing.verify(&locked), Err(LockfileError::Missing(_))));
+    }
+}
@@ -0,0 +1,104 @@
+use crate::executor::ConnectionError;
+use crate::logger::SUMMARY_TARGET;
+use crate::model::Migration;
+use crate::orchestrator::migration_loader::LoaderOptions;
+use crate::orchestrator::{MigrationLoader, Planner};
+use crate::orchestrator::planner::PlannerError;
+use crate::tracker::{schema_init, Target};
+use log::{debug, info};
+
+/// Record migrations as applied without running them, for changes executed outside deriddl.
+/// With `version` only that pending migration is marked, otherwise every pending migration.
+/// With `skip` they are recorded as permanently skipped, for migrations superseded here.
+pub fn run_mark_applied(
+    target: &Target,
+    path: &str,
+    loader: &LoaderOptions,
+    version: Option<u32>,
+    dry_run: bool,
+    environment: Option<&str>,
+    skip: bool,
+) -> Result<(), MarkAppliedError> {
+    info!("Marking migrations as applied");
+    debug!("Migrations path: {}", path);
+    debug!("Version: {:?}", version);
+    debug!("Dry run mode: {}", dry_run);
+
+    let migrations = MigrationLoader::load_migrations_with_options(path, loader)
+        .map_err(|e| MarkAppliedError::LoadFailed(e.to_string()))?;
+    let migrations: Vec<Migration> = migrations
+        .into_iter()
+        .filter(|m| m.runs_in_environment(environment))
+        .collect();
+
+    if !schema_init::check_migration_table_exists(target)? {
+        info!("schema_migrations table does not exist, creating it");
+        schema_init::init_migration_table(target)?;
+    }
+
+    let mut version_store = target.version_store()?;
+    let pending = version_store.get_pending_migrations(&migrations)?;
+    let pending = Planner::order_migrations(pending)?;
+
+    let to_mark: Vec<&Migration> = match version {
+        Some(version) => {
+            let matches: Vec<&Migration> =
+                pending.iter().filter(|m| m.version == Some(version)).collect();
+            match matches.len() {
+                0 => return Err(MarkAppliedError::NotPending(version)),
+                1 => matches,
+                _ => {
+                    let ids = matches.iter().map(|m| m.identifier()).collect();
+                    return Err(MarkAppliedError::Ambiguous(version, ids));
+                }
+            }
+        }
+        None => pending.iter().collect(),
+    };
+
+    if to_mark.is_empty() {
+        info!(target: SUMMARY_TARGET, "✅ No pending migrations to mark as applied");
+        return Ok(());
+    }
+
+    let action = if dry_run { "Would mark" } else { "Marking" };
+    let status = if skip { "skipped" } else { "applied" };
+    info!("{} {} migration(s) as {} without running them:", action, to_mark.len(), status);
+    for migration in &to_mark {
+        info!("  📌 {}", migration.filename());
+    }
+
+    if dry_run {
+        return Ok(());
+    }
+
+    for migration in &to_mark {
+        if skip {
+            version_store.record_migration_skip_listed(migration)?;
+        } else {
+            version_store.mark_migration_applied(migration)?;
+        }
+    }
+
+    let status = if skip { "skipped" } else { "applied externally" };
+    info!(target: SUMMARY_TARGET, "✅ Marked {} migration(s) as {}", to_mark.len(), status);
+    Ok(())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MarkAppliedError {
+    #[error("Failed to load migrations: {0}")]
+    LoadFailed(String),
+
+    #[error("Version {0} is not a pending migration")]
+    NotPending(u32),
+
+    #[error("Version {0} matches several pending migrations: {1:?}")]
+    Ambiguous(u32, Vec<String>),
+
+    #[error("Connection error: {0}")]
+    Connection(#[from] ConnectionError),
+
+    #[error("Planning failed: {0}")]
+    Planning(#[from] PlannerError),
+}
@@ -0,0 +1,181 @@
+use crate::dialects::DatabaseDialect;
+use crate::model::migration::directive_values;
+use std::borrow::Cow;
+
+/// Domain of the addresses `hash_email` produces; `.invalid` never resolves
+const MASKED_EMAIL_DOMAIN: &str = "example.invalid";
+/// Hex digits of the hash kept in `hash_email` addresses
+const MASKED_EMAIL_HASH_LENGTH: usize = 12;
+/// Value written by the `redact` strategy
+const REDACTED: &str = "REDACTED";
+
+/// How `-- deriddl:mask` replaces a column's values. NULLs are left as they are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaskStrategy {
+    /// `user_<hash>@example.invalid`: still unique and shaped like an address
+    HashEmail,
+    /// Hex digest of the value; equal values stay equal, so joins on it still work
+    Hash,
+    /// The literal `REDACTED`
+    Redact,
+    /// NULL
+    Null,
+}
+
+impl MaskStrategy {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "hash_email" => Some(MaskStrategy::HashEmail),
+            "hash" => Some(MaskStrategy::Hash),
+            "redact" => Some(MaskStrategy::Redact),
+            "null" => Some(MaskStrategy::Null),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            MaskStrategy::HashEmail => "hash_email",
+            MaskStrategy::Hash => "hash",
+            MaskStrategy::Redact => "redact",
+            MaskStrategy::Null => "null",
+        }
+    }
+}
+
+/// A `-- deriddl:mask <table>.<column> strategy=<strategy>` line
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaskDirective {
+    /// Table as written, optionally schema-qualified
+    pub table: String,
+    pub column: String,
+    pub strategy: MaskStrategy,
+}
+
+impl MaskDirective {
+    /// Parses the directive on `line`; None when the line holds no mask directive
+    pub fn parse(line: &str) -> Option<Result<Self, MaskError>> {
+        let value = directive_values(line, "mask").pop()?;
+        Some(Self::parse_value(&value))
+    }
+
+    fn parse_value(value: &str) -> Result<Self, MaskError> {
+        let invalid = |reason: &str| MaskError::Invalid(value.to_string(), reason.to_string());
+        let mut parts = value.split_whitespace();
+        let target = parts.next().ok_or_else(|| invalid("expected <table>.<column>"))?;
+        let (table, column) = target
+            .rsplit_once('.')
+            .filter(|(table, column)| is_identifier_path(table) && is_identifier_path(column))
+            .ok_or_else(|| invalid("expected <table>.<column>"))?;
+
+        let mut strategy = None;
+        for option in parts {
+            match option.split_once('=') {
+                Some(("strategy", name)) => {
+                    strategy = Some(MaskStrategy::parse(name).ok_or_else(|| {
+                        invalid(&format!("unknown strategy '{}'; use hash_email, hash, redact or null", name))
+                    })?);
+                }
+                _ => return Err(invalid(&format!("unknown option '{}'", option))),
+            }
+        }
+        Ok(Self {
+            table: table.to_string(),
+            column: column.to_string(),
+            strategy: strategy.ok_or_else(|| invalid("missing strategy=<strategy>"))?,
+        })
+    }
+
+    /// The UPDATE masking the column. Hash strategies need a dialect with a hash function.
+    pub fn update_sql(&self, dialect: Option<&dyn DatabaseDialect>) -> Result<String, MaskError> {
+        let column = self.column.as_str();
+        let hash = || {
+            dialect.and_then(|d| d.hash_sql(column)).ok_or_else(|| {
+                MaskError::Unsupported(self.strategy.name(), dialect.map_or("generic", |d| d.name()).to_string())
+            })
+        };
+        let value = match self.strategy {
+            MaskStrategy::HashEmail => {
+                let parts = [
+                    "'user_'".to_string(),
+                    format!("SUBSTR({}, 1, {})", hash()?, MASKED_EMAIL_HASH_LENGTH),
+                    format!("'@{}'", MASKED_EMAIL_DOMAIN),
+                ];
+                match dialect {
+                    Some(dialect) => dialect.concat_sql(&parts),
+                    None => parts.join(" || "),
+                }
+            }
+            MaskStrategy::Hash => hash()?,
+            MaskStrategy::Redact => format!("'{}'", REDACTED),
+            MaskStrategy::Null => "NULL".to_string(),
+        };
+        Ok(format!("UPDATE {} SET {} = {} WHERE {} IS NOT NULL", self.table, column, value, column))
+    }
+}
+
+/// Replaces each `-- deriddl:mask` line of `sql` with itself followed by its UPDATE, so
+/// the masking runs in file order with the rest of the migration
+pub fn expand_masks<'s>(sql: &'s str, dialect: Option<&dyn DatabaseDialect>) -> Result<Cow<'s, str>, MaskError> {
+    if directive_values(sql, "mask").is_empty() {
+        return Ok(Cow::Borrowed(sql));
+    }
+    let mut expanded = String::with_capacity(sql.len());
+    for line in sql.lines() {
+        expanded.push_str(line);
+        expanded.push('\n');
+        if let Some(directive) = MaskDirective::parse(line) {
+            expanded.push_str(&directive?.update_sql(dialect)?);
+            expanded.push_str(";\n");
+        }
+    }
+    Ok(Cow::Owned(expanded))
+}
+
+/// Letters, digits, `_` and `.` separators only, so a directive can't smuggle in SQL
+pub(crate) fn is_identifier_path(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .split('.')
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MaskError {
+    #[error("Invalid mask directive '{0}': {1}")]
+    Invalid(String, String),
+
+    #[error("Mask strategy {0} needs a hash function, which the {1} dialect does not have")]
+    Unsupported(&'static str, String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialects;
+
+    #[test]
+    fn test_mask_directives_expand_per_dialect() {
+        let sql = "-- deriddl:mask app.users.email strategy=hash_email\n-- deriddl:mask users.phone strategy=null\nDELETE FROM sessions;";
+        let postgres = dialects::get_dialect_with_config(Some("postgres"), None, None).unwrap();
+        assert_eq!(
+            expand_masks(sql, Some(postgres.as_ref())).unwrap(),
+            "-- deriddl:mask app.users.email strategy=hash_email\n\
+             UPDATE app.users SET email = 'user_' || SUBSTR(md5(email::text), 1, 12) || '@example.invalid' WHERE email IS NOT NULL;\n\
+             -- deriddl:mask users.phone strategy=null\n\
+             UPDATE users SET phone = NULL WHERE phone IS NOT NULL;\n\
+             DELETE FROM sessions;\n"
+        );
+
+        let mysql = dialects::get_dialect_with_config(Some("mysql"), None, None).unwrap();
+        let directive = MaskDirective::parse("-- deriddl:mask users.ssn strategy=hash").unwrap().unwrap();
+        assert_eq!(directive.update_sql(Some(mysql.as_ref())).unwrap(), "UPDATE users SET ssn = MD5(ssn) WHERE ssn IS NOT NULL");
+        let sqlite = dialects::get_dialect_with_config(Some("sqlite"), None, None).unwrap();
+        assert!(matches!(directive.update_sql(Some(sqlite.as_ref())), Err(MaskError::Unsupported("hash", _))));
+
+        assert!(matches!(MaskDirective::parse("-- deriddl:mask users strategy=hash"), Some(Err(MaskError::Invalid(..)))));
+        assert!(matches!(MaskDirective::parse("-- deriddl:mask users.name strategy=shuffle"), Some(Err(_))));
+        assert!(matches!(MaskDirective::parse("-- deriddl:mask users.name;DROP strategy=null"), Some(Err(_))));
+        assert!(matches!(expand_masks("SELECT 1;", None), Ok(Cow::Borrowed(_))));
+    }
+}
@@ -0,0 +1,94 @@
+use crate::tracker::version_store::AppliedMigration;
+use chrono::{DateTime, Utc};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+/// Migration state exported by `status --metrics`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatusMetrics {
+    pub pending: usize,
+    pub failed: usize,
+    pub last_applied_version: Option<u32>,
+    pub last_applied_at: Option<DateTime<Utc>>,
+}
+
+impl StatusMetrics {
+    /// Metrics from the pending count and the schema_migrations records
+    pub fn from_records(pending: usize, applied: &[AppliedMigration]) -> Self {
+        let succeeded = || applied.iter().filter(|a| a.success);
+        Self {
+            pending,
+            failed: applied.iter().filter(|a| !a.success).count(),
+            last_applied_version: succeeded().filter_map(|a| a.version).max(),
+            last_applied_at: succeeded().map(|a| a.applied_at).max(),
+        }
+    }
+
+    /// Renders the metrics in the Prometheus text format read by node_exporter's textfile
+    /// collector. Metrics without a value (nothing applied yet) are left out.
+    pub fn to_textfile(&self) -> String {
+        let mut out = String::new();
+        let mut gauge = |name: &str, help: &str, value: Option<String>| {
+            if let Some(value) = value {
+                let _ = writeln!(out, "# HELP {} {}\n# TYPE {} gauge\n{} {}", name, help, name, name, value);
+            }
+        };
+        gauge("deriddl_pending_migrations", "Migrations apply would run.", Some(self.pending.to_string()));
+        gauge("deriddl_failed_migrations", "Migrations recorded as failed.", Some(self.failed.to_string()));
+        gauge(
+            "deriddl_last_applied_version",
+            "Highest applied migration version.",
+            self.last_applied_version.map(|v| v.to_string()),
+        );
+        gauge(
+            "deriddl_last_apply_timestamp_seconds",
+            "Unix time the most recent migration was applied.",
+            self.last_applied_at.map(|at| at.timestamp().to_string()),
+        );
+        out
+    }
+
+    /// Writes the textfile through a temporary file and a rename, so the collector never
+    /// reads a half-written file
+    pub fn write_textfile(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let temp = path.with_file_name(format!(
+            ".{}.tmp",
+            path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default()
+        ));
+        fs::write(&temp, self.to_textfile())?;
+        fs::rename(&temp, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn record(version: u32, success: bool, at: i64) -> AppliedMigration {
+        AppliedMigration { success, applied_at: Utc.timestamp_opt(at, 0).unwrap(), ..AppliedMigration::of_version(version) }
+    }
+
+    #[test]
+    fn test_textfile_metrics() {
+        let metrics = StatusMetrics::from_records(2, &[record(1, true, 1_700_000_000), record(2, true, 1_700_000_100), record(3, false, 1_700_000_200)]);
+        let text = metrics.to_textfile();
+        assert!(text.contains("# TYPE deriddl_pending_migrations gauge\nderiddl_pending_migrations 2\n"));
+        assert!(text.contains("\nderiddl_failed_migrations 1\n"));
+        assert!(text.contains("\nderiddl_last_applied_version 2\n"));
+        assert!(text.contains("\nderiddl_last_apply_timestamp_seconds 1700000100\n"));
+
+        let empty = StatusMetrics::from_records(4, &[]).to_textfile();
+        assert!(!empty.contains("deriddl_last_applied_version"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("deriddl.prom");
+        metrics.write_textfile(&file).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), text);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
@@ -1,100 +1,650 @@
-use crate::model::Migration;
-use log::{info, debug, warn};
-use std::fs;
+use crate::model::{ChecksumNormalization, Config, Migration, MigrationSource, MigrationType, SharedChecksumStrategy, SharedIdStrategy};
+use crate::model::checksum::{ChecksumStrategy, HashChecksum, StreamingChecksum};
+use crate::model::config::native_path;
+use crate::model::migration::{parse_repeatable_filename, is_section_marker, StreamedContent};
+use crate::orchestrator::lockfile::LockfilePolicy;
+use crate::orchestrator::ownership::OwnershipManifest;
+use crate::orchestrator::signature::SignaturePolicy;
+use log::{debug, warn};
+use rayon::prelude::*;
+use regex::Regex;
+use std::fs::{self, File};
 use std::path::{Path, PathBuf};
-use std::io;
+use std::io::{self, BufRead, BufReader, Read};
+
+/// Options controlling how migration files are discovered
+#[derive(Debug, Clone, Default)]
+pub struct LoaderOptions {
+    /// Descend into subdirectories (e.g. `2024/`, `billing/`) when loading
+    pub recursive: bool,
+
+    /// Migration sources applied in order; when non-empty these replace the single path
+    pub sources: Vec<MigrationSource>,
+
+    /// Regex versioned migration filenames must match. Optional `version` and `name`
+    /// capture groups drive parsing; repeatable `R__` files are not subject to it.
+    pub file_pattern: Option<String>,
+
+    /// Files larger than this are rejected instead of loaded
+    pub max_file_size_mb: Option<u32>,
+
+    /// Files larger than this are checksummed while being read and streamed from
+    /// disk during apply instead of being held in memory
+    pub stream_threshold_mb: Option<u32>,
+
+    /// Abort `apply` when any migration file is rejected, rather than warning and skipping it
+    pub fail_on_invalid_files: bool,
+
+    /// Normalization applied to migration content before checksumming
+    pub checksum_normalization: ChecksumNormalization,
+
+    /// How checksums are computed (`validation.checksum`), kept by every loaded migration
+    pub checksum: SharedChecksumStrategy,
+
+    /// How loaded migrations are identified in schema_migrations (`migrations.id_strategy`)
+    pub id_strategy: SharedIdStrategy,
+
+    /// Detached signature checks `apply` runs before executing anything
+    pub signatures: SignaturePolicy,
+
+    /// Lockfile `apply` and `validate` check the loaded migrations against
+    pub lockfile: LockfilePolicy,
+
+    /// Migrations `apply` records as skipped instead of running (`migrations.skip`)
+    pub skip: Vec<String>,
+}
+
+/// Result of loading migrations, including files left out by the file pattern
+/// and files rejected by size or encoding validation
+#[derive(Debug, Default)]
+pub struct LoadedMigrations {
+    pub migrations: Vec<Migration>,
+    pub excluded: Vec<PathBuf>,
+    pub invalid: Vec<InvalidFile>,
+}
+
+/// A migration file that could not be loaded
+#[derive(Debug, Clone)]
+pub struct InvalidFile {
+    pub path: PathBuf,
+    pub reason: InvalidFileReason,
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum InvalidFileReason {
+    #[error("file is {size_mb:.1} MB, exceeding the {limit_mb} MB limit")]
+    TooLarge { size_mb: f64, limit_mb: u32 },
+
+    #[error("file appears to be binary")]
+    Binary,
+
+    #[error("file is not valid UTF-8 (invalid byte at offset {0})")]
+    InvalidUtf8(usize),
+
+    #[error("file has a UTF-16 byte order mark but is not valid UTF-16")]
+    InvalidUtf16,
+}
+
+/// What became of a single `.sql` file
+enum FileOutcome {
+    Loaded(Box<Migration>),
+    Excluded,
+    Invalid(InvalidFileReason),
+    Skipped,
+}
+
+/// Per-load state shared by every directory that is scanned
+struct Scan<'a> {
+    options: &'a LoaderOptions,
+    pattern: Option<Regex>,
+}
+
+impl LoaderOptions {
+    /// Build loader options from the `[migrations]` section of the configuration
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            recursive: config.migrations.recursive,
+            sources: config.migrations.paths.clone(),
+            file_pattern: Some(config.migrations.file_pattern.clone()),
+            max_file_size_mb: Some(config.validation.max_file_size_mb),
+            stream_threshold_mb: config.validation.stream_threshold_mb,
+            fail_on_invalid_files: config.validation.fail_on_invalid_files,
+            checksum_normalization: config.validation.checksum_normalization,
+            checksum: config.validation.checksum.strategy(),
+            id_strategy: config.migrations.id_strategy.strategy(),
+            signatures: SignaturePolicy {
+                required: config.validation.require_signatures,
+                trusted_keys: config.validation.signature_keys.clone(),
+                gpg_keyring: config.validation.gpg_keyring.clone(),
+            },
+            lockfile: LockfilePolicy {
+                path: native_path(&config.validation.lockfile),
+                required: config.validation.require_lockfile,
+            },
+            skip: config.migrations.skip.clone(),
+        }
+    }
+
+    /// Whether `migrations.skip` names this migration by version (unnamespaced migrations
+    /// only), identifier or file name
+    pub fn is_skip_listed(&self, migration: &Migration) -> bool {
+        let file_name = migration.file_path.file_name().map(|name| name.to_string_lossy());
+        self.skip.iter().map(|entry| entry.trim()).any(|entry| {
+            (migration.namespace.is_none() && entry.parse::<u32>().ok().is_some_and(|v| migration.version == Some(v)))
+                || migration.identifier() == entry
+                || file_name.as_deref() == Some(entry)
+        })
+    }
+}
 
 pub struct MigrationLoader;
 
 impl MigrationLoader {
     pub fn load_migrations(migrations_path: &str) -> io::Result<Vec<Migration>> {
-        info!("Loading migrations from: {}", migrations_path);
+        Self::load_migrations_with_options(migrations_path, &LoaderOptions::default())
+    }
+
+    pub fn load_migrations_with_options(
+        migrations_path: &str,
+        options: &LoaderOptions,
+    ) -> io::Result<Vec<Migration>> {
+        Ok(Self::load_migrations_with_report(migrations_path, options)?.migrations)
+    }
+
+    /// Load migrations and report which `.sql` files were excluded by the file pattern
+    /// or rejected as invalid
+    pub fn load_migrations_with_report(
+        migrations_path: &str,
+        options: &LoaderOptions,
+    ) -> io::Result<LoadedMigrations> {
+        let pattern = match &options.file_pattern {
+            Some(pattern) => Some(Regex::new(pattern).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid migrations.file_pattern '{}': {}", pattern, e),
+                )
+            })?),
+            None => None,
+        };
+
+        let scan = Scan { options, pattern };
+        let mut loaded = LoadedMigrations::default();
+        if options.sources.is_empty() {
+            loaded.migrations = Self::load_source(migrations_path, None, &scan, &mut loaded)?;
+            return Ok(loaded);
+        }
+
+        for source in &options.sources {
+            let migrations = Self::load_source(source.path(), source.namespace(), &scan, &mut loaded)?;
+            loaded.migrations.extend(migrations);
+        }
+
+        // Keep configured source order, but run all versioned migrations before repeatables
+        loaded.migrations.sort_by_key(|m| m.migration_type == MigrationType::Repeatable);
+
+        debug!("Loaded {} migrations from {} sources", loaded.migrations.len(), options.sources.len());
+        Ok(loaded)
+    }
+
+    /// Load a single migration directory, tagging each migration with the source namespace
+    fn load_source(
+        migrations_path: &str,
+        namespace: Option<&str>,
+        scan: &Scan,
+        report: &mut LoadedMigrations,
+    ) -> io::Result<Vec<Migration>> {
+        debug!("Loading migrations from: {}", migrations_path);
         
-        let path = Path::new(migrations_path);
+        let path = native_path(migrations_path);
+        let path = path.as_path();
         if !path.exists() {
             warn!("Migrations directory does not exist: {}", migrations_path);
             return Ok(Vec::new());
         }
 
-        let mut migrations = Vec::new();
-        let entries = fs::read_dir(path)?;
+        let mut files = Vec::new();
+        Self::collect_files(path, true, scan, &mut files)?;
+        let mut migrations = Self::load_files(&files, scan, report)?;
 
-        for entry in entries {
-            let entry = entry?;
-            let file_path = entry.path();
-            
-            if let Some(extension) = file_path.extension() {
-                if extension == "sql" {
-                    if let Some(migration) = Self::parse_migration_file(&file_path)? {
-                        debug!("Loaded migration: {} (version {:?})", migration.name, migration.version);
-                        migrations.push(migration);
+        // Migrations under envs/<name>/ only run in that environment
+        let envs_path = path.join("envs");
+        if envs_path.is_dir() {
+            for entry in fs::read_dir(&envs_path)? {
+                let env_dir = entry?.path();
+                if !env_dir.is_dir() {
+                    continue;
+                }
+                let env_name = match env_dir.file_name().and_then(|n| n.to_str()) {
+                    Some(name) => name.to_string(),
+                    None => continue,
+                };
+
+                let mut env_files = Vec::new();
+                Self::collect_files(&env_dir, false, scan, &mut env_files)?;
+                for mut migration in Self::load_files(&env_files, scan, report)? {
+                    if !migration.environments.iter().any(|e| e == &env_name) {
+                        migration.environments.push(env_name.clone());
                     }
+                    debug!("Migration {} scoped to environment '{}'", migration.filename(), env_name);
+                    migrations.push(migration);
                 }
             }
         }
 
-        // Sort migrations: versioned first (by version), then repeatable (by name)
+        if let Some(namespace) = namespace {
+            for migration in &mut migrations {
+                migration.namespace = Some(namespace.to_string());
+            }
+        }
+
+        if let Some(manifest) = OwnershipManifest::load(path)? {
+            for migration in &mut migrations {
+                let relative = migration.file_path.strip_prefix(path).unwrap_or(&migration.file_path);
+                let relative = relative.to_string_lossy().replace('\\', "/");
+                migration.owner = manifest.owner_of(&relative).map(str::to_string);
+            }
+        }
+
+        // Sort migrations: versioned first (by version), then repeatable (by priority, then name;
+        // repeatables without a priority prefix run last).
+        // Ties (e.g. duplicates across subfolders) fall back to the file path for stable output.
         migrations.sort_by(|a, b| {
-            use crate::model::MigrationType;
-            match (&a.migration_type, &b.migration_type) {
+            let by_type = match (&a.migration_type, &b.migration_type) {
                 (MigrationType::Versioned, MigrationType::Versioned) => {
                     a.version.cmp(&b.version)
                 }
                 (MigrationType::Repeatable, MigrationType::Repeatable) => {
-                    a.name.cmp(&b.name)
+                    let priority = |m: &Migration| m.priority.unwrap_or(u32::MAX);
+                    priority(a).cmp(&priority(b)).then_with(|| a.name.cmp(&b.name))
                 }
                 (MigrationType::Versioned, MigrationType::Repeatable) => std::cmp::Ordering::Less,
                 (MigrationType::Repeatable, MigrationType::Versioned) => std::cmp::Ordering::Greater,
-            }
+            };
+            by_type.then_with(|| a.file_path.cmp(&b.file_path))
         });
         
         let versioned_count = migrations.iter().filter(|m| !m.is_repeatable()).count();
         let repeatable_count = migrations.iter().filter(|m| m.is_repeatable()).count();
-        info!("Loaded {} migrations ({} versioned, {} repeatable)", 
+        debug!("Loaded {} migrations ({} versioned, {} repeatable)", 
               migrations.len(), versioned_count, repeatable_count);
         
         Ok(migrations)
     }
 
-    fn parse_migration_file(file_path: &PathBuf) -> io::Result<Option<Migration>> {
+    /// Collect all .sql files inside a directory in sorted order, optionally descending into
+    /// subdirectories. The top-level `envs/` folder is skipped here since it is loaded with
+    /// environment scoping.
+    fn collect_files(
+        dir: &Path,
+        is_root: bool,
+        scan: &Scan,
+        files: &mut Vec<PathBuf>,
+    ) -> io::Result<()> {
+        let mut entries: Vec<PathBuf> = fs::read_dir(dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<io::Result<_>>()?;
+        entries.sort();
+
+        for file_path in entries {
+            if file_path.is_dir() {
+                let is_envs_dir = is_root && file_path.file_name().is_some_and(|n| n == "envs");
+                if scan.options.recursive && !is_envs_dir {
+                    debug!("Descending into migration subdirectory: {}", file_path.display());
+                    Self::collect_files(&file_path, false, scan, files)?;
+                }
+                continue;
+            }
+            
+            if file_path.extension().is_some_and(|extension| extension == "sql") {
+                files.push(file_path);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Read, parse and checksum files in parallel. Results are gathered in the order of
+    /// `files`, so the loaded migrations and the report do not depend on thread scheduling.
+    fn load_files(
+        files: &[PathBuf],
+        scan: &Scan,
+        report: &mut LoadedMigrations,
+    ) -> io::Result<Vec<Migration>> {
+        let outcomes: Vec<io::Result<FileOutcome>> = files
+            .par_iter()
+            .map(|file_path| Self::parse_migration_file(file_path, scan))
+            .collect();
+
+        let mut migrations = Vec::new();
+        for (file_path, outcome) in files.iter().zip(outcomes) {
+            match outcome? {
+                FileOutcome::Loaded(migration) => {
+                    debug!("Loaded migration: {} (version {:?})", migration.name, migration.version);
+                    migrations.push(*migration);
+                }
+                FileOutcome::Excluded => report.excluded.push(file_path.clone()),
+                FileOutcome::Invalid(reason) => {
+                    warn!("Skipping invalid migration file {}: {}", file_path.display(), reason);
+                    report.invalid.push(InvalidFile { path: file_path.clone(), reason });
+                }
+                FileOutcome::Skipped => {}
+            }
+        }
+
+        Ok(migrations)
+    }
+
+    fn parse_migration_file(file_path: &Path, scan: &Scan) -> io::Result<FileOutcome> {
         let filename = file_path.file_name()
             .and_then(|name| name.to_str())
             .unwrap_or("");
+        let repeatable = parse_repeatable_filename(filename);
+        let is_repeatable = repeatable.is_some();
+
+        if let Some(pattern) = &scan.pattern
+            && !is_repeatable
+            && !pattern.is_match(filename)
+        {
+            debug!("Excluding {}: does not match file pattern '{}'", filename, pattern.as_str());
+            return Ok(FileOutcome::Excluded);
+        }
 
-        let sql_content = fs::read_to_string(file_path)?;
+        let size = match Self::check_file_size(file_path, scan.options.max_file_size_mb)? {
+            Ok(size) => size,
+            Err(reason) => return Ok(FileOutcome::Invalid(reason)),
+        };
+        // UTF-16 files must be decoded as a whole, so they are never streamed
+        let stream = scan
+            .options
+            .stream_threshold_mb
+            .is_some_and(|threshold_mb| size > megabytes(threshold_mb))
+            && !Self::has_utf16_bom(file_path)?;
 
-        // Check for repeatable migration pattern: "R__description.sql"
-        if filename.starts_with("R__") && filename.ends_with(".sql") {
-            let name = filename
-                .strip_prefix("R__")
-                .and_then(|s| s.strip_suffix(".sql"))
-                .unwrap_or("unknown")
-                .to_string();
-            
+        // Streamed files are parsed from their directive headers only; the up SQL stays on disk
+        let (sql_content, streamed) = if stream {
+            match Self::read_streamed_file(file_path, size, &*scan.options.checksum, scan.options.checksum_normalization)? {
+                Ok(file) => (file.directives.clone(), Some(file)),
+                Err(reason) => return Ok(FileOutcome::Invalid(reason)),
+            }
+        } else {
+            match Self::read_sql_file(file_path)? {
+                Ok(content) => (content, None),
+                Err(reason) => return Ok(FileOutcome::Invalid(reason)),
+            }
+        };
+
+        // Check for repeatable migration pattern: "R__description.sql" or "R010__description.sql"
+        let migration = if let Some((priority, name)) = repeatable {
             debug!("Found repeatable migration: {}", filename);
-            return Ok(Some(Migration::new_repeatable(
+            let mut migration = Migration::new_repeatable(
+                name.to_string(),
+                file_path.to_path_buf(),
+                sql_content,
+            );
+            migration.priority = priority;
+            Some(migration)
+        } else if let Some(pattern) = &scan.pattern {
+            Self::parse_with_pattern(file_path, filename, pattern, sql_content)
+        } else {
+            Self::parse_versioned(file_path, filename, sql_content)
+        };
+
+        let Some(mut migration) = migration else {
+            return Ok(FileOutcome::Skipped);
+        };
+        migration.id_strategy = scan.options.id_strategy.clone();
+        match streamed {
+            Some(file) => {
+                debug!("Streaming {} ({} bytes) from disk", filename, file.content.size_bytes);
+                migration.sql_content = String::new();
+                migration.rollback_sql = file.rollback_sql;
+                migration.checksum = file.checksum;
+                migration.checksum_strategy = scan.options.checksum.clone();
+                migration.streamed = Some(file.content);
+            }
+            None => migration.use_checksum_strategy(scan.options.checksum.clone(), scan.options.checksum_normalization),
+        }
+        Ok(FileOutcome::Loaded(Box::new(migration)))
+    }
+
+    /// Parse a versioned migration filename like "0001_init_schema.sql"
+    fn parse_versioned(file_path: &Path, filename: &str, sql_content: String) -> Option<Migration> {
+        if let Some((version_str, name_part)) = filename.split_once('_')
+            && let Ok(version) = version_str.parse::<u32>()
+        {
+            let name = name_part.strip_suffix(".sql").unwrap_or(name_part).to_string();
+            
+            debug!("Found versioned migration: {} (version {})", filename, version);
+            return Some(Migration::new(
+                version,
                 name,
-                file_path.clone(),
+                file_path.to_path_buf(),
                 sql_content,
-            )));
+            ));
         }
 
-        // Parse versioned migration filename like "0001_init_schema.sql"
-        if let Some((version_str, name_part)) = filename.split_once('_') {
-            if let Ok(version) = version_str.parse::<u32>() {
-                let name = name_part.strip_suffix(".sql").unwrap_or(name_part).to_string();
-                
+        warn!("Skipping file with invalid name format: {} (expected 'NNNN_name.sql', 'R__name.sql' or 'R010__name.sql')", filename);
+        None
+    }
+
+    /// Parse a versioned migration filename using the configured file pattern. The `version`
+    /// and `name` capture groups are used when present, otherwise the `NNNN_name.sql` split.
+    fn parse_with_pattern(
+        file_path: &Path,
+        filename: &str,
+        pattern: &Regex,
+        sql_content: String,
+    ) -> Option<Migration> {
+        let captures = pattern.captures(filename)?;
+
+        let (default_version, default_name) = filename
+            .split_once('_')
+            .map(|(v, n)| (v, n.strip_suffix(".sql").unwrap_or(n)))
+            .unwrap_or(("", filename));
+        let version_str = captures.name("version").map_or(default_version, |m| m.as_str());
+        let name = captures.name("name").map_or(default_name, |m| m.as_str());
+
+        match version_str.parse::<u32>() {
+            Ok(version) => {
                 debug!("Found versioned migration: {} (version {})", filename, version);
-                return Ok(Some(Migration::new(
-                    version,
-                    name,
-                    file_path.clone(),
-                    sql_content,
-                )));
+                Some(Migration::new(version, name.to_string(), file_path.to_path_buf(), sql_content))
             }
+            Err(_) => {
+                warn!("Skipping {}: matches file pattern but has no numeric version", filename);
+                None
+            }
+        }
+    }
+
+    /// Returns the file size, or the rejection reason when it exceeds the size limit
+    fn check_file_size(
+        file_path: &Path,
+        max_file_size_mb: Option<u32>,
+    ) -> io::Result<Result<u64, InvalidFileReason>> {
+        let size = fs::metadata(file_path)?.len();
+        if let Some(limit_mb) = max_file_size_mb
+            && size > megabytes(limit_mb)
+        {
+            let size_mb = size as f64 / (1024.0 * 1024.0);
+            return Ok(Err(InvalidFileReason::TooLarge { size_mb, limit_mb }));
+        }
+        Ok(Ok(size))
+    }
+
+    fn has_utf16_bom(file_path: &Path) -> io::Result<bool> {
+        let mut bom = Vec::with_capacity(2);
+        File::open(file_path)?.take(2).read_to_end(&mut bom)?;
+        Ok(bom == b"\xFF\xFE" || bom == b"\xFE\xFF")
+    }
+
+    /// Read a migration file, rejecting binary or non-UTF-8 content.
+    /// A leading UTF-8 byte order mark is stripped, and files starting with a UTF-16
+    /// byte order mark (as saved by some Windows editors) are decoded.
+    fn read_sql_file(file_path: &Path) -> io::Result<Result<String, InvalidFileReason>> {
+        let bytes = fs::read(file_path)?;
+        if let Some(utf16) = decode_utf16(&bytes) {
+            return Ok(utf16.ok_or(InvalidFileReason::InvalidUtf16));
+        }
+        let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&bytes);
+        if bytes.contains(&0) {
+            return Ok(Err(InvalidFileReason::Binary));
         }
 
-        warn!("Skipping file with invalid name format: {} (expected 'NNNN_name.sql' or 'R__name.sql')", filename);
-        Ok(None)
+        match std::str::from_utf8(bytes) {
+            Ok(content) => Ok(Ok(content.to_string())),
+            Err(e) => Ok(Err(InvalidFileReason::InvalidUtf8(e.valid_up_to()))),
+        }
     }
-}
\ No newline at end of file
+
+    /// Scan a large migration file line by line, applying the same validation as
+    /// `read_sql_file` and computing the up SQL checksum without holding the file in memory.
+    /// Only the directive headers and the down section are kept.
+    fn read_streamed_file(
+        file_path: &Path,
+        size: u64,
+        strategy: &dyn ChecksumStrategy,
+        normalization: ChecksumNormalization,
+    ) -> io::Result<Result<StreamedFile, InvalidFileReason>> {
+        let normalization = if normalization == ChecksumNormalization::Semantic {
+            warn!(
+                "Semantic checksums are not supported for streamed file {}, using whitespace normalization",
+                file_path.display()
+            );
+            ChecksumNormalization::Whitespace
+        } else {
+            normalization
+        };
+
+        let mut reader = BufReader::new(File::open(file_path)?);
+        let mut line = Vec::new();
+        let mut offset = 0;
+        let mut directives = String::new();
+        // Checksums of the whole file and of the text after an up marker; which one
+        // applies depends on whether a down marker follows
+        let mut whole = ChecksumPair::new(strategy, normalization);
+        let mut up_section: Option<ChecksumPair> = None;
+        let mut rollback_sql: Option<String> = None;
+
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            let mut bytes = line.as_slice();
+            if offset == 0 {
+                bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+            }
+            if bytes.contains(&0) {
+                return Ok(Err(InvalidFileReason::Binary));
+            }
+            let text = match std::str::from_utf8(bytes) {
+                Ok(text) => text,
+                Err(e) => return Ok(Err(InvalidFileReason::InvalidUtf8(offset + e.valid_up_to()))),
+            };
+            offset += bytes.len();
+
+            let trimmed = text.trim();
+            if trimmed
+                .strip_prefix("--")
+                .is_some_and(|comment| comment.trim_start().starts_with("deriddl:"))
+            {
+                directives.push_str(trimmed);
+                directives.push('\n');
+            }
+
+            if let Some(rollback_sql) = rollback_sql.as_mut() {
+                rollback_sql.push_str(text);
+                continue;
+            }
+            if up_section.is_some() && is_section_marker(text, true) {
+                rollback_sql = Some(String::new());
+                continue;
+            }
+
+            whole.update(text);
+            match up_section.as_mut() {
+                Some(section) => section.update(text),
+                None if is_section_marker(text, false) => {
+                    up_section = Some(ChecksumPair::new(strategy, normalization));
+                }
+                None => {}
+            }
+        }
+
+        let checksums = match (up_section, rollback_sql.is_some()) {
+            (Some(section), true) => section,
+            _ => whole,
+        };
+        let (accepted_checksums, checksum) = checksums.finish();
+
+        Ok(Ok(StreamedFile {
+            directives,
+            rollback_sql: rollback_sql.map(|sql| sql.trim().to_string()),
+            checksum,
+            content: StreamedContent { size_bytes: size, accepted_checksums },
+        }))
+    }
+}
+
+/// What is kept in memory from a file loaded by `read_streamed_file`
+struct StreamedFile {
+    directives: String,
+    rollback_sql: Option<String>,
+    checksum: String,
+    content: StreamedContent,
+}
+
+/// Normalized checksum and the strict ones accepted alongside it (see
+/// [`Migration::matches_checksum`]) computed side by side
+struct ChecksumPair {
+    strict: StreamingChecksum,
+    legacy: StreamingChecksum,
+    normalized: StreamingChecksum,
+}
+
+impl ChecksumPair {
+    fn new(strategy: &dyn ChecksumStrategy, normalization: ChecksumNormalization) -> Self {
+        Self {
+            strict: StreamingChecksum::new(strategy, ChecksumNormalization::Strict).expect("strict checksums stream"),
+            legacy: StreamingChecksum::new(&HashChecksum, ChecksumNormalization::Strict).expect("strict checksums stream"),
+            normalized: StreamingChecksum::new(strategy, normalization).expect("semantic checksums are replaced before streaming"),
+        }
+    }
+
+    fn update(&mut self, text: &str) {
+        self.strict.update(text);
+        self.legacy.update(text);
+        self.normalized.update(text);
+    }
+
+    /// The accepted strict checksums and the normalized one
+    fn finish(self) -> (Vec<String>, String) {
+        (vec![self.strict.finish(), self.legacy.finish()], self.normalized.finish())
+    }
+}
+
+/// Decodes content starting with a UTF-16 byte order mark. Returns `None` when there is
+/// no such mark, and `Some(None)` when the content after it is not valid UTF-16.
+fn decode_utf16(bytes: &[u8]) -> Option<Option<String>> {
+    let (units, little_endian) = match bytes {
+        [0xFF, 0xFE, rest @ ..] => (rest, true),
+        [0xFE, 0xFF, rest @ ..] => (rest, false),
+        _ => return None,
+    };
+    if units.len() % 2 != 0 {
+        return Some(None);
+    }
+
+    let units = units.chunks_exact(2).map(|pair| {
+        if little_endian {
+            u16::from_le_bytes([pair[0], pair[1]])
+        } else {
+            u16::from_be_bytes([pair[0], pair[1]])
+        }
+    });
+    Some(char::decode_utf16(units).collect::<Result<String, _>>().ok())
+}
+
+fn megabytes(mb: u32) -> u64 {
+    u64::from(mb) * 1024 * 1024
+}
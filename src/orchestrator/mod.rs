@@ -1,6 +1,25 @@
 pub mod apply;
+pub mod approval;
 pub mod baseline;
+pub mod blame;
+pub mod bundle;
+pub mod capture;
+pub mod checksum_diff;
+pub mod check_branch;
+pub mod codegen;
+pub mod compare_data;
+pub mod console;
+pub mod dialect_report;
+pub mod doctor;
+pub mod estimate;
+pub mod explain;
+pub mod history;
 pub mod plan;
+pub mod prompt;
+pub mod report;
+pub mod renumber;
+pub mod restore_files;
+pub mod reversibility;
 pub mod rollback;
 pub mod status;
 pub mod validate;
@@ -8,13 +27,53 @@ pub mod planner;
 pub mod migration_loader;
 pub mod validator;
 pub mod health;
+pub mod guard;
+pub mod info;
+pub mod k8s;
+pub mod lock;
+pub mod lockfile;
+pub mod mark_applied;
+pub mod masking;
+pub mod metrics;
+pub mod multi_target;
+pub mod ownership;
+pub mod sandbox;
+pub mod scaffold;
+pub mod schema_docs;
+pub mod script;
+pub mod self_update;
+pub mod signature;
+pub mod source;
+pub mod sql;
+pub mod summary;
+pub mod sync_status;
+pub mod table_size;
 
 pub use apply::run_apply;
+pub use approval::run_approve;
 pub use baseline::run_baseline;
+pub use blame::run_blame;
+pub use codegen::run_gen_types;
+pub use compare_data::run_compare_data;
+pub use console::run_console;
+pub use dialect_report::run_dialects;
 pub use plan::run_plan;
+pub use report::run_report;
+pub use restore_files::run_restore_files;
+pub use reversibility::run_check_reversibility;
+pub use sandbox::run_sandbox;
+pub use self_update::run_self_update;
+pub use schema_docs::run_docs;
+pub use sql::run_sql;
+pub use sync_status::run_sync_status;
 pub use rollback::run_rollback;
 pub use status::run_status;
 pub use validate::run_validate;
 pub use migration_loader::MigrationLoader;
 pub use validator::Validator;
+pub use planner::Planner;
 pub use health::run_health;
+pub use history::run_history;
+pub use info::run_info;
+pub use mark_applied::run_mark_applied;
+pub use signature::run_sign;
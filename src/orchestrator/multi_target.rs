@@ -0,0 +1,199 @@
+use crate::logger::SUMMARY_TARGET;
+use log::{debug, info};
+use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Environment files in the config directory that are not targets
+const NON_TARGET_CONFIGS: &[&str] = &["default", "local"];
+
+/// Flags consumed by the parent run, with whether they take a value
+const PARENT_FLAGS: &[(&str, bool)] = &[("--all-targets", false), ("--parallel", true), ("--env", true)];
+
+/// How one target's apply ended
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetRun {
+    pub target: String,
+    /// Exit code of the target's run; None when it was killed by a signal
+    pub exit_code: Option<i32>,
+    pub duration: Duration,
+    /// Set when the run could not be started
+    pub error: Option<String>,
+}
+
+impl TargetRun {
+    pub fn succeeded(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// Targets of a multi-target setup: every `<config_dir>/<target>.toml` except the base
+/// `default.toml` and the git-ignored `local.toml`, sorted by name
+pub fn discover_targets(config_dir: &Path) -> Result<Vec<String>, MultiTargetError> {
+    let entries = fs::read_dir(config_dir)
+        .map_err(|e| MultiTargetError::Discovery(config_dir.display().to_string(), e.to_string()))?;
+    let mut targets: Vec<String> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().to_string()))
+        .filter(|name| !NON_TARGET_CONFIGS.contains(&name.as_str()))
+        .collect();
+    targets.sort();
+    if targets.is_empty() {
+        return Err(MultiTargetError::NoTargets(config_dir.display().to_string()));
+    }
+    Ok(targets)
+}
+
+/// The arguments of this run without the flags that select and schedule targets, to
+/// pass on to each target's run
+pub fn child_args(args: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let flag = arg.split('=').next().unwrap_or_default();
+        match PARENT_FLAGS.iter().find(|(name, _)| *name == flag) {
+            Some((_, true)) if !arg.contains('=') => {
+                args.next();
+            }
+            Some(_) => {}
+            None => result.push(arg),
+        }
+    }
+    result
+}
+
+/// Runs `deriddl --env <target> <args>` for every target, at most `parallel` at a time.
+/// Each run takes its target's migration lock itself; their output is streamed with a
+/// `[target]` prefix. Returns the runs in target order.
+pub fn run_all_targets(targets: &[String], parallel: usize, args: &[String]) -> Result<Vec<TargetRun>, MultiTargetError> {
+    let program = std::env::current_exe().map_err(|e| MultiTargetError::Executable(e.to_string()))?;
+    let workers = parallel.clamp(1, targets.len().max(1));
+    let width = targets.iter().map(String::len).max().unwrap_or(0);
+    info!("Applying to {} target(s), {} at a time: {}", targets.len(), workers, targets.join(", "));
+
+    let next = AtomicUsize::new(0);
+    let runs = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                while let Some(target) = targets.get(next.fetch_add(1, Ordering::SeqCst)) {
+                    let run = run_target(&program, target, args, width);
+                    runs.lock().unwrap_or_else(|e| e.into_inner()).push(run);
+                }
+            });
+        }
+    });
+
+    let mut runs = runs.into_inner().unwrap_or_else(|e| e.into_inner());
+    runs.sort_by_key(|run| targets.iter().position(|t| *t == run.target));
+    print_summary(&runs);
+    Ok(runs)
+}
+
+fn run_target(program: &Path, target: &str, args: &[String], width: usize) -> TargetRun {
+    let started = Instant::now();
+    let prefix = format!("[{:<width$}]", target, width = width);
+    debug!("Starting {} --env {} {}", program.display(), target, args.join(" "));
+    let child = Command::new(program)
+        .args(["--env", target])
+        .args(args)
+        // Prompts can't be answered per target; they fail unless --yes was given
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            return TargetRun {
+                target: target.to_string(),
+                exit_code: None,
+                duration: started.elapsed(),
+                error: Some(e.to_string()),
+            };
+        }
+    };
+
+    let stdout = child.stdout.take().expect("piped stdout");
+    let stderr = child.stderr.take().expect("piped stderr");
+    thread::scope(|scope| {
+        scope.spawn(|| stream_lines(stderr, &prefix, true));
+        stream_lines(stdout, &prefix, false);
+    });
+    let status = child.wait();
+    TargetRun {
+        target: target.to_string(),
+        exit_code: status.as_ref().ok().and_then(|s| s.code()),
+        duration: started.elapsed(),
+        error: status.err().map(|e| e.to_string()),
+    }
+}
+
+fn stream_lines(output: impl Read, prefix: &str, to_stderr: bool) {
+    for line in BufReader::new(output).lines().map_while(Result::ok) {
+        if to_stderr {
+            eprintln!("{} {}", prefix, line);
+        } else {
+            println!("{} {}", prefix, line);
+        }
+    }
+}
+
+fn print_summary(runs: &[TargetRun]) {
+    let width = runs.iter().map(|run| run.target.len()).max().unwrap_or(0).max("Target".len());
+    info!(target: SUMMARY_TARGET, "");
+    info!(target: SUMMARY_TARGET, "{:<width$}  {:<24}  Duration", "Target", "Result", width = width);
+    for run in runs {
+        let result = match (&run.error, run.exit_code) {
+            (Some(error), _) => format!("❌ {}", error),
+            (None, Some(0)) => "✅ success".to_string(),
+            (None, Some(code)) => format!("❌ exit code {}", code),
+            (None, None) => "❌ killed".to_string(),
+        };
+        info!(
+            target: SUMMARY_TARGET,
+            "{:<width$}  {:<24}  {:.1}s",
+            run.target,
+            result,
+            run.duration.as_secs_f64(),
+            width = width
+        );
+    }
+    let failed = runs.iter().filter(|run| !run.succeeded()).count();
+    info!(target: SUMMARY_TARGET, "{} of {} target(s) succeeded", runs.len() - failed, runs.len());
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MultiTargetError {
+    #[error("Failed to list targets in {0}: {1}")]
+    Discovery(String, String),
+
+    #[error("No targets found: {0} has no <target>.toml files")]
+    NoTargets(String),
+
+    #[error("Failed to locate the deriddl executable: {0}")]
+    Executable(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_targets_and_child_args() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["default.toml", "local.toml", "prod-us.toml", "prod-eu.toml", "notes.md"] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+        assert_eq!(discover_targets(dir.path()).unwrap(), vec!["prod-eu", "prod-us"]);
+
+        let args = ["--env", "dev", "apply", "--all-targets", "--parallel", "4", "--path", "db", "--env=x", "--yes"];
+        assert_eq!(child_args(args.map(String::from)), vec!["apply", "--path", "db", "--yes"]);
+    }
+}
@@ -0,0 +1,72 @@
+use log::debug;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// File in a migration source's root mapping path prefixes to owning teams
+pub const OWNERSHIP_MANIFEST: &str = "owners.toml";
+
+/// Owning team per path prefix, read from `owners.toml`:
+///
+/// ```toml
+/// [owners]
+/// "analytics/" = "analytics"      # a directory
+/// "0100_billing" = "billing"     # file names starting with a prefix
+/// "" = "core"                     # everything else
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OwnershipManifest {
+    #[serde(default)]
+    owners: BTreeMap<String, String>,
+}
+
+impl OwnershipManifest {
+    /// Reads the manifest in `dir`; None when there is none
+    pub fn load(dir: &Path) -> io::Result<Option<Self>> {
+        let path = dir.join(OWNERSHIP_MANIFEST);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)?;
+        let manifest: Self = toml::from_str(&content).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("Invalid {}: {}", path.display(), e))
+        })?;
+        debug!("Loaded {} ownership rules from {}", manifest.owners.len(), path.display());
+        Ok(Some(manifest))
+    }
+
+    /// Team owning the file at `relative_path` (relative to the source root, `/`-separated):
+    /// the one with the longest matching prefix
+    pub fn owner_of(&self, relative_path: &str) -> Option<&str> {
+        self.owners
+            .iter()
+            .filter(|(prefix, _)| relative_path.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, team)| team.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_longest_prefix_decides_owner() {
+        let manifest: OwnershipManifest = toml::from_str(
+            r#"
+            [owners]
+            "analytics/" = "analytics"
+            "analytics/billing_" = "billing"
+            "" = "core"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.owner_of("analytics/0003_events.sql"), Some("analytics"));
+        assert_eq!(manifest.owner_of("analytics/billing_0004.sql"), Some("billing"));
+        assert_eq!(manifest.owner_of("0001_init.sql"), Some("core"));
+        assert_eq!(OwnershipManifest::default().owner_of("0001_init.sql"), None);
+    }
+}
@@ -1,16 +1,36 @@
+use crate::logger::SUMMARY_TARGET;
 use crate::executor::ConnectionError;
-use crate::orchestrator::MigrationLoader;
-use crate::tracker::{schema_init, VersionStore};
+use crate::model::Migration;
+use crate::orchestrator::{MigrationLoader, Planner};
+use crate::orchestrator::approval::{ApprovalError, ApprovalPlan};
+use crate::orchestrator::explain::run_explain;
+use crate::orchestrator::estimate::{format_duration_ms, DurationEstimator, Estimate};
+use crate::orchestrator::planner::PlannerError;
+use crate::orchestrator::migration_loader::LoaderOptions;
+use crate::tracker::{schema_init, Target};
 use log::{debug, info, warn};
+use std::path::Path;
 
-pub fn run_plan(conn: &str, path: &str) -> Result<(), PlanError> {
+/// Shows the pending migrations with estimated run times. `explain` is the dialect to
+/// run EXPLAIN on their DML with (`--explain`).
+pub fn run_plan(
+    target: &Target,
+    path: &str,
+    loader: &LoaderOptions,
+    environment: Option<&str>,
+    export_plan: Option<&Path>,
+    explain: Option<&str>,
+) -> Result<(), PlanError> {
     info!("Running migration plan");
-    debug!("Connection string length: {}", conn.len());
+    debug!("Connection string length: {}", target.conn.len());
     debug!("Migrations path: {}", path);
+    debug!("Environment: {:?}", environment);
 
     // Load migrations from filesystem
-    let migrations =
-        MigrationLoader::load_migrations(path).map_err(|e| PlanError::LoadFailed(e.to_string()))?;
+    let loaded = MigrationLoader::load_migrations_with_report(path, loader)
+        .map_err(|e| PlanError::LoadFailed(e.to_string()))?;
+    display_excluded(&loaded.excluded, loader);
+    let migrations = loaded.migrations;
 
     if migrations.is_empty() {
         info!("📋 No migrations found in {}", path);
@@ -18,19 +38,30 @@ pub fn run_plan(conn: &str, path: &str) -> Result<(), PlanError> {
     }
 
     // Test connection first
-    let connection_manager = crate::executor::ConnectionManager::new()?;
-    connection_manager.test_connection(conn)?;
+    let connection_manager = target.connection_manager()?;
+    connection_manager.test_connection(&target.conn)?;
     debug!("Database connection verified");
     
+    // An exported plan records every file so applying it can refuse a changed directory
+    let files = migrations.clone();
+
+    // Migrations scoped to other environments are reported separately
+    let (migrations, skipped): (Vec<_>, Vec<_>) = migrations
+        .into_iter()
+        .partition(|m| m.runs_in_environment(environment));
+
     // Check if schema_migrations table exists
-    let table_exists = schema_init::check_migration_table_exists(conn)?;
+    let table_exists = schema_init::check_migration_table_exists(target)?;
 
     if !table_exists {
+        let (skip_listed, migrations): (Vec<_>, Vec<_>) =
+            migrations.into_iter().partition(|m| loader.is_skip_listed(m));
+        let migrations = Planner::order_migrations(migrations)?;
         info!("📋 Migration Plan");
         info!("================");
         warn!("⚠️  schema_migrations table does not exist. All migrations will be applied.");
         info!("");
-        info!("Migrations to apply ({}):", migrations.len());
+        info!(target: SUMMARY_TARGET, "Migrations to apply ({}):", migrations.len());
         for (i, migration) in migrations.iter().enumerate() {
             info!(
                 "{}. 📄 {} ({} lines)",
@@ -39,56 +70,187 @@ pub fn run_plan(conn: &str, path: &str) -> Result<(), PlanError> {
                 migration.sql_content.lines().count()
             );
         }
-        return Ok(());
+        display_skip_listed(&skip_listed);
+        display_skipped(&skipped, environment);
+        if let Some(dialect) = explain {
+            run_explain(target, Some(dialect), &migrations)?;
+        }
+        return export(export_plan, &migrations, &files, environment);
     }
 
     // Get pending migrations
-    let mut version_store = VersionStore::new(conn)?;
+    let mut version_store = target.version_store()?;
     let pending_migrations = version_store.get_pending_migrations(&migrations)?;
+    let (skip_listed, pending_migrations): (Vec<_>, Vec<_>) =
+        pending_migrations.into_iter().partition(|m| loader.is_skip_listed(m));
+    let pending_migrations = Planner::order_migrations(pending_migrations)?;
 
     info!("📋 Migration Plan");
     info!("================");
 
     if pending_migrations.is_empty() {
-        info!("✅ No pending migrations to apply. Database is up to date!");
-        return Ok(());
+        info!(target: SUMMARY_TARGET, "✅ No pending migrations to apply. Database is up to date!");
+        display_skip_listed(&skip_listed);
+        display_skipped(&skipped, environment);
+        return export(export_plan, &pending_migrations, &files, environment);
     }
 
     info!("Pending migrations ({}):", pending_migrations.len());
     info!("");
 
-    for (i, migration) in pending_migrations.iter().enumerate() {
-        info!("{}. 📄 {}", i + 1, migration.filename());
-        match migration.version {
-            Some(v) => info!("   Version: {}", v),
-            None => info!("   Type: Repeatable"),
+    let estimator = DurationEstimator::from_history(&version_store.get_applied_migrations()?, &migrations);
+    let estimates: Vec<Option<Estimate>> = pending_migrations.iter().map(|m| estimator.estimate(m)).collect();
+    let batches = Planner::execution_batches(&pending_migrations);
+    let mut number = 0;
+
+    for (b, batch) in batches.iter().enumerate() {
+        if batches.len() > 1 {
+            let batch_ms: u64 = batch.iter().filter_map(|&i| estimates[i].as_ref()).map(|e| e.duration_ms).sum();
+            info!(
+                "── Batch {}/{}: {} migration(s), ~{} ──",
+                b + 1,
+                batches.len(),
+                batch.len(),
+                format_duration_ms(batch_ms)
+            );
         }
-        info!("   File: {}", migration.file_path.display());
-        info!("   Lines: {}", migration.sql_content.lines().count());
-        info!("   Checksum: {}...", &migration.checksum[..8]);
-
-        // Show SQL preview (first few lines)
-        let sql_lines: Vec<&str> = migration.sql_content.lines().take(3).collect();
-        if !sql_lines.is_empty() {
-            info!("   Preview:");
-            for line in sql_lines {
-                if !line.trim().is_empty() {
-                    info!("     {}", line.chars().take(60).collect::<String>());
-                }
-            }
-            if migration.sql_content.lines().count() > 3 {
-                info!("     ...");
-            }
+        for &i in batch {
+            number += 1;
+            display_pending(number, &pending_migrations[i], estimates[i].as_ref());
         }
-        info!("");
+    }
+
+    let total_ms: u64 = estimates.iter().flatten().map(|e| e.duration_ms).sum();
+    let unknown = estimates.iter().filter(|e| e.is_none()).count();
+    if unknown == 0 {
+        info!(target: SUMMARY_TARGET, "⏱️  Estimated run time: ~{}", format_duration_ms(total_ms));
+    } else {
+        info!(
+            target: SUMMARY_TARGET,
+            "⏱️  Estimated run time: ~{} plus {} migration(s) without comparable history",
+            format_duration_ms(total_ms),
+            unknown
+        );
+    }
+
+    display_skip_listed(&skip_listed);
+    display_skipped(&skipped, environment);
+    if let Some(dialect) = explain {
+        run_explain(target, Some(dialect), &pending_migrations)?;
     }
 
     info!("💡 Run with the 'apply' command to execute these migrations.");
     info!("💡 Use '--dry-run' flag to see what would be executed without applying changes.");
 
+    export(export_plan, &pending_migrations, &files, environment)
+}
+
+fn display_pending(number: usize, migration: &Migration, estimate: Option<&Estimate>) {
+    info!("{}. 📄 {}", number, migration.filename());
+    match migration.version {
+        Some(v) => info!("   Version: {}", v),
+        None => info!("   Type: Repeatable"),
+    }
+    if migration.has_requirements() {
+        let requires: Vec<String> = migration.requires.iter().map(|v| format!("{:04}", v)).collect();
+        info!("   Requires: {}", requires.join(", "));
+    }
+    info!("   File: {}", migration.file_path.display());
+    info!("   Lines: {}", migration.sql_content.lines().count());
+    info!("   Checksum: {}...", &migration.checksum[..8]);
+    match estimate {
+        Some(estimate) => info!(
+            "   Estimated: ~{} (median of {} run(s) of {})",
+            format_duration_ms(estimate.duration_ms),
+            estimate.samples,
+            estimate.basis
+        ),
+        None => info!("   Estimated: unknown (no similar migration applied yet)"),
+    }
+
+    // Show SQL preview (first few lines)
+    let sql_lines: Vec<&str> = migration.sql_content.lines().take(3).collect();
+    if !sql_lines.is_empty() {
+        info!("   Preview:");
+        for line in sql_lines {
+            if !line.trim().is_empty() {
+                info!("     {}", line.chars().take(60).collect::<String>());
+            }
+        }
+        if migration.sql_content.lines().count() > 3 {
+            info!("     ...");
+        }
+    }
+    info!("");
+}
+
+/// Writes the planned migrations to an approval plan file when `--export-plan` is given
+fn export(
+    path: Option<&Path>,
+    migrations: &[Migration],
+    files: &[Migration],
+    environment: Option<&str>,
+) -> Result<(), PlanError> {
+    let Some(path) = path else {
+        return Ok(());
+    };
+    ApprovalPlan::from_migrations(migrations, environment).with_files(files).write(path)?;
+    info!(target: SUMMARY_TARGET, "📝 Exported plan with {} migration(s) to {}", migrations.len(), path.display());
+    info!("💡 Apply exactly this plan with 'deriddl apply --plan-file {}'", path.display());
+    info!("💡 Have a reviewer sign it with 'deriddl approve {} --key <file>'", path.display());
     Ok(())
 }
 
+/// Show migrations that are excluded by their environment scope
+fn display_excluded(excluded: &[std::path::PathBuf], loader: &LoaderOptions) {
+    if excluded.is_empty() {
+        return;
+    }
+
+    warn!(
+        "⚠️  {} file(s) excluded by file_pattern '{}':",
+        excluded.len(),
+        loader.file_pattern.as_deref().unwrap_or_default()
+    );
+    for file in excluded {
+        warn!("  🚫 {}", file.display());
+    }
+    info!("");
+}
+
+fn display_skip_listed(skip_listed: &[Migration]) {
+    if skip_listed.is_empty() {
+        return;
+    }
+
+    info!("");
+    info!("On the skip list, recorded as skipped without running ({}):", skip_listed.len());
+    for migration in skip_listed {
+        info!("  ⏭️  {} - skipped (skip list)", migration.filename());
+    }
+}
+
+fn display_skipped(skipped: &[Migration], environment: Option<&str>) {
+    if skipped.is_empty() {
+        return;
+    }
+
+    info!("");
+    info!(
+        "Skipped for environment '{}' ({}):",
+        environment.unwrap_or("<none>"),
+        skipped.len()
+    );
+    for migration in skipped {
+        info!(
+            "  ⏭️  {} - skipped (env) [{}]",
+            migration.filename(),
+            migration.environments.join(", ")
+        );
+    }
+    info!("");
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum PlanError {
     #[error("Failed to load migrations: {0}")]
@@ -96,5 +258,11 @@ pub enum PlanError {
 
     #[error("Connection error: {0}")]
     Connection(#[from] ConnectionError),
+
+    #[error("Planning failed: {0}")]
+    Planning(#[from] PlannerError),
+
+    #[error("Failed to export plan: {0}")]
+    Export(#[from] ApprovalError),
 }
 
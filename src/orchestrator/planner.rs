@@ -1 +1,120 @@
-// Determines pending migrations
\ No newline at end of file
+use crate::model::Migration;
+use log::debug;
+use std::collections::{BTreeSet, HashMap};
+
+pub struct Planner;
+
+impl Planner {
+    /// Orders migrations so that declared `requires` dependencies, and repeatables named in
+    /// `after` directives, run before their dependents. Without dependencies the loader order is
+    /// kept (source order, versioned by version, then repeatable by priority and name).
+    /// Dependencies resolve within the migration's own namespace, and dependencies outside the
+    /// given set are assumed to be already satisfied.
+    pub fn order_migrations(migrations: Vec<Migration>) -> Result<Vec<Migration>, PlannerError> {
+        // Build the dependency graph: edge from requirement -> dependent
+        let mut in_degree = vec![0usize; migrations.len()];
+        let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); migrations.len()];
+        for (i, dependencies) in Self::dependencies(&migrations).into_iter().enumerate() {
+            for dep in dependencies {
+                dependents[dep].push(i);
+                in_degree[i] += 1;
+            }
+        }
+
+        // Kahn's algorithm, always picking the earliest migration in loader order
+        let mut ready: BTreeSet<usize> = (0..migrations.len())
+            .filter(|&i| in_degree[i] == 0)
+            .collect();
+        let mut order = Vec::with_capacity(migrations.len());
+
+        while let Some(next) = ready.pop_first() {
+            order.push(next);
+            for &dependent in &dependents[next] {
+                in_degree[dependent] -= 1;
+                if in_degree[dependent] == 0 {
+                    ready.insert(dependent);
+                }
+            }
+        }
+
+        if order.len() != migrations.len() {
+            let cyclic = (0..migrations.len())
+                .filter(|i| in_degree[*i] > 0)
+                .map(|i| migrations[i].filename())
+                .collect();
+            return Err(PlannerError::DependencyCycle(cyclic));
+        }
+
+        let mut slots: Vec<Option<Migration>> = migrations.into_iter().map(Some).collect();
+        Ok(order
+            .into_iter()
+            .filter_map(|i| slots[i].take())
+            .collect())
+    }
+
+    /// Indices of the migrations each migration must run after, from `requires` and `after`
+    fn dependencies(migrations: &[Migration]) -> Vec<Vec<usize>> {
+        let version_index: HashMap<(Option<&str>, u32), usize> = migrations
+            .iter()
+            .enumerate()
+            .filter_map(|(i, m)| m.version.map(|v| ((m.namespace.as_deref(), v), i)))
+            .collect();
+        let repeatable_index: HashMap<(Option<&str>, &str), usize> = migrations
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| m.is_repeatable())
+            .map(|(i, m)| ((m.namespace.as_deref(), m.name.as_str()), i))
+            .collect();
+
+        let mut dependencies = vec![Vec::new(); migrations.len()];
+        for (i, migration) in migrations.iter().enumerate() {
+            for required in &migration.requires {
+                if let Some(&dep) = version_index.get(&(migration.namespace.as_deref(), *required)) {
+                    if dep != i {
+                        dependencies[i].push(dep);
+                    }
+                } else {
+                    debug!(
+                        "{} requires version {} which is not part of this plan, assuming satisfied",
+                        migration.filename(),
+                        required
+                    );
+                }
+            }
+            for name in &migration.after {
+                match repeatable_index.get(&(migration.namespace.as_deref(), name.as_str())) {
+                    Some(&dep) if dep != i => dependencies[i].push(dep),
+                    Some(_) => {}
+                    None => debug!(
+                        "{} runs after R__{} which is not part of this plan",
+                        migration.filename(),
+                        name
+                    ),
+                }
+            }
+        }
+        dependencies
+    }
+
+    /// Splits an ordered plan into batches: each migration goes in the batch after the last
+    /// one holding a migration it depends on, so migrations within a batch are independent.
+    /// `apply` still runs them one by one, each in its own transaction.
+    pub fn execution_batches(ordered: &[Migration]) -> Vec<Vec<usize>> {
+        let mut level = vec![0usize; ordered.len()];
+        let mut batches: Vec<Vec<usize>> = Vec::new();
+        for (i, dependencies) in Self::dependencies(ordered).into_iter().enumerate() {
+            level[i] = dependencies.iter().map(|&dep| level[dep] + 1).max().unwrap_or(0);
+            if batches.len() <= level[i] {
+                batches.resize_with(level[i] + 1, Vec::new);
+            }
+            batches[level[i]].push(i);
+        }
+        batches
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PlannerError {
+    #[error("Dependency cycle detected between migrations: {0:?}")]
+    DependencyCycle(Vec<String>),
+}
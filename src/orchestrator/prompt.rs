@@ -0,0 +1,100 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
+/// Asks the operator to confirm an operation before it runs
+pub trait Prompt {
+    /// Returns whether `question` was answered yes
+    fn confirm(&self, question: &str) -> Result<bool, PromptError>;
+
+    /// Returns the answer to `question`, trimmed
+    fn ask(&self, question: &str) -> Result<String, PromptError>;
+}
+
+/// Asks on the terminal; only `y` or `yes` agree
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TerminalPrompt;
+
+impl Prompt for TerminalPrompt {
+    fn confirm(&self, question: &str) -> Result<bool, PromptError> {
+        print!("{} (y/N): ", question);
+        io::stdout().flush().map_err(|e| PromptError::Io(e.to_string()))?;
+
+        let mut input = String::new();
+        io::stdin()
+            .lock()
+            .read_line(&mut input)
+            .map_err(|e| PromptError::Io(e.to_string()))?;
+        Ok(matches!(input.trim().to_lowercase().as_str(), "y" | "yes"))
+    }
+
+    fn ask(&self, question: &str) -> Result<String, PromptError> {
+        print!("{}: ", question);
+        io::stdout().flush().map_err(|e| PromptError::Io(e.to_string()))?;
+
+        let mut input = String::new();
+        io::stdin()
+            .lock()
+            .read_line(&mut input)
+            .map_err(|e| PromptError::Io(e.to_string()))?;
+        Ok(input.trim().to_string())
+    }
+}
+
+/// Never reads stdin: agrees to everything with `assume_yes`, otherwise fails so
+/// unattended runs stop instead of waiting for input that never comes
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NonInteractivePrompt {
+    pub assume_yes: bool,
+}
+
+impl Prompt for NonInteractivePrompt {
+    fn confirm(&self, question: &str) -> Result<bool, PromptError> {
+        if self.assume_yes {
+            return Ok(true);
+        }
+        Err(PromptError::NonInteractive(question.to_string()))
+    }
+
+    fn ask(&self, question: &str) -> Result<String, PromptError> {
+        Err(PromptError::NoAnswer(question.to_string()))
+    }
+}
+
+/// The prompt for this run. `assume_yes` (`--yes`, `--force`, or confirmation turned off
+/// in the configuration) agrees without asking; `--non-interactive` or a stdin that is
+/// not a terminal fails instead of asking.
+pub fn prompt_for(assume_yes: bool, non_interactive: bool) -> Box<dyn Prompt> {
+    if assume_yes || non_interactive || !io::stdin().is_terminal() {
+        Box::new(NonInteractivePrompt { assume_yes })
+    } else {
+        Box::new(TerminalPrompt)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PromptError {
+    #[error("'{0}' needs confirmation but no terminal is available; pass --yes to confirm")]
+    NonInteractive(String),
+
+    #[error("'{0}' needs an answer but no terminal is available")]
+    NoAnswer(String),
+
+    #[error("Failed to read confirmation: {0}")]
+    Io(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_non_interactive_prompt_never_waits() {
+        assert!(NonInteractivePrompt { assume_yes: true }.confirm("Roll back?").unwrap());
+        assert!(matches!(
+            NonInteractivePrompt { assume_yes: false }.confirm("Roll back?"),
+            Err(PromptError::NonInteractive(_))
+        ));
+        assert!(prompt_for(false, true).confirm("Roll back?").is_err());
+        assert!(prompt_for(true, true).confirm("Roll back?").unwrap());
+        assert!(matches!(prompt_for(true, true).ask("table"), Err(PromptError::NoAnswer(_))));
+    }
+}
@@ -0,0 +1,263 @@
+use crate::logger::SUMMARY_TARGET;
+use crate::model::Migration;
+use crate::orchestrator::MigrationLoader;
+use crate::orchestrator::migration_loader::LoaderOptions;
+use crate::orchestrator::prompt::{Prompt, PromptError};
+use crate::orchestrator::signature::signature_path;
+use log::{debug, info, warn};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::UNIX_EPOCH;
+
+/// How `renumber` deals with the renames it proposes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenumberMode {
+    /// Only list the proposed renames
+    Preview,
+    /// Ask before each rename
+    Interactive,
+    /// Rename without asking
+    Auto,
+}
+
+/// Moving a migration that shares its version with an earlier-authored file to a free version
+#[derive(Debug, Clone, PartialEq)]
+pub struct Renumbering {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub old_version: u32,
+    pub new_version: u32,
+    /// File that keeps the version
+    pub kept: String,
+}
+
+/// Resolves duplicate version numbers by moving every file but the earliest-authored one
+/// of each version to the next free version. The file content, and so its checksum, is
+/// unchanged; only the file (and its `.sig`, if any) is renamed.
+pub fn run_renumber(path: &str, loader: &LoaderOptions, mode: RenumberMode, prompt: &dyn Prompt) -> Result<usize, RenumberError> {
+    let migrations = MigrationLoader::load_migrations_with_options(path, loader)
+        .map_err(|e| RenumberError::LoadFailed(e.to_string()))?;
+
+    let renumberings = plan_renumbering(&migrations, authored_at);
+    if renumberings.is_empty() {
+        info!(target: SUMMARY_TARGET, "✅ No duplicate version numbers");
+        return Ok(0);
+    }
+
+    let mut renamed = 0;
+    for renumbering in &renumberings {
+        let from = display_name(&renumbering.from);
+        let to = display_name(&renumbering.to);
+        info!(
+            "🔀 Version {} is shared with {}: {} → {}",
+            renumbering.old_version, renumbering.kept, from, to
+        );
+        let rename = match mode {
+            RenumberMode::Preview => false,
+            RenumberMode::Auto => true,
+            RenumberMode::Interactive => match confirm(prompt, &format!("Rename {} to {}? [y/N/q]", from, to))? {
+                Answer::Yes => true,
+                Answer::No => false,
+                Answer::Quit => break,
+            },
+        };
+        if rename {
+            apply_renumbering(renumbering)?;
+            renamed += 1;
+        }
+    }
+
+    if mode == RenumberMode::Preview {
+        info!(
+            target: SUMMARY_TARGET,
+            "{} file(s) would be renumbered; run with --interactive or --auto to rename them",
+            renumberings.len()
+        );
+    } else {
+        info!(target: SUMMARY_TARGET, "✅ Renumbered {} of {} file(s)", renamed, renumberings.len());
+    }
+    Ok(renamed)
+}
+
+/// Proposes a new version for every versioned migration whose version is already used by
+/// an earlier-authored file in the same namespace. `authored_at` orders files sharing a
+/// version; ties fall back to the file name.
+pub fn plan_renumbering(migrations: &[Migration], authored_at: impl Fn(&Path) -> i64) -> Vec<Renumbering> {
+    let mut by_version: BTreeMap<(Option<&str>, u32), Vec<&Migration>> = BTreeMap::new();
+    for migration in migrations.iter().filter(|m| !m.is_repeatable()) {
+        if let Some(version) = migration.version {
+            by_version.entry((migration.namespace.as_deref(), version)).or_default().push(migration);
+        }
+    }
+
+    let mut next_free: BTreeMap<Option<&str>, u32> = BTreeMap::new();
+    for (namespace, version) in by_version.keys() {
+        let next = next_free.entry(*namespace).or_insert(0);
+        *next = (*next).max(version + 1);
+    }
+
+    let mut renumberings = Vec::new();
+    for ((namespace, version), mut files) in by_version {
+        if files.len() < 2 {
+            continue;
+        }
+        files.sort_by_key(|m| (authored_at(&m.file_path), m.file_path.clone()));
+        let kept = display_name(&files[0].file_path);
+        for migration in &files[1..] {
+            let next = next_free.get_mut(&namespace).expect("every namespace has a next version");
+            let new_version = *next;
+            *next += 1;
+            match renamed_path(&migration.file_path, version, new_version) {
+                Some(to) => renumberings.push(Renumbering {
+                    from: migration.file_path.clone(),
+                    to,
+                    old_version: version,
+                    new_version,
+                    kept: kept.clone(),
+                }),
+                None => warn!("⚠️  Cannot find version {} in the name of {}", version, migration.file_path.display()),
+            }
+        }
+    }
+    renumberings
+}
+
+fn apply_renumbering(renumbering: &Renumbering) -> Result<(), RenumberError> {
+    if renumbering.to.exists() {
+        return Err(RenumberError::TargetExists(renumbering.to.display().to_string()));
+    }
+    fs::rename(&renumbering.from, &renumbering.to)?;
+    let signature = signature_path(&renumbering.from);
+    if signature.exists() {
+        fs::rename(&signature, signature_path(&renumbering.to))?;
+    }
+    info!("✏️  Renamed {} to {}", display_name(&renumbering.from), display_name(&renumbering.to));
+    Ok(())
+}
+
+/// Replaces the first number in the file name that equals `version`, keeping its zero padding
+fn renamed_path(path: &Path, version: u32, new_version: u32) -> Option<PathBuf> {
+    let name = path.file_name()?.to_str()?;
+    let mut start = None;
+    for (i, c) in name.char_indices().chain(std::iter::once((name.len(), ' '))) {
+        match (c.is_ascii_digit(), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                let digits = &name[s..i];
+                if digits.parse::<u32>().ok() == Some(version) {
+                    let renamed = format!("{}{:0width$}{}", &name[..s], new_version, &name[i..], width = digits.len());
+                    return Some(path.with_file_name(renamed));
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Author time of the commit that added the file, else its modification time. Files not
+/// committed yet are newer than any committed one.
+fn authored_at(path: &Path) -> i64 {
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let added = path.file_name().and_then(|file_name| {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["log", "--diff-filter=A", "--format=%at", "--"])
+            .arg(file_name)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        String::from_utf8_lossy(&output.stdout).lines().last()?.trim().parse().ok()
+    });
+    if let Some(added) = added {
+        debug!("{} was added at {}", path.display(), added);
+        return added;
+    }
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(i64::MAX, |age| age.as_secs() as i64)
+}
+
+enum Answer {
+    Yes,
+    No,
+    Quit,
+}
+
+fn confirm(prompt: &dyn Prompt, question: &str) -> Result<Answer, RenumberError> {
+    Ok(match prompt.ask(question)?.to_lowercase().as_str() {
+        "y" | "yes" => Answer::Yes,
+        "q" | "quit" => Answer::Quit,
+        _ => Answer::No,
+    })
+}
+
+fn display_name(path: &Path) -> String {
+    path.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().to_string())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RenumberError {
+    #[error("Failed to load migrations: {0}")]
+    LoadFailed(String),
+
+    #[error("{0} already exists")]
+    TargetExists(String),
+
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("{0}")]
+    Prompt(#[from] PromptError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn migration(version: u32, file: &str) -> Migration {
+        Migration::new(version, file.to_string(), PathBuf::from("migrations").join(file), String::new())
+    }
+
+    #[test]
+    fn test_moves_later_authored_duplicates_to_next_free_versions() {
+        let migrations = vec![
+            migration(1, "0001_init.sql"),
+            migration(2, "0002_orders.sql"),
+            migration(2, "0002_users.sql"),
+            migration(2, "0002_items.sql"),
+            migration(3, "0003_index.sql"),
+        ];
+        let authored = |path: &Path| match display_name(path).as_str() {
+            "0002_users.sql" => 100,
+            "0002_orders.sql" => 200,
+            _ => 300,
+        };
+
+        let renumberings = plan_renumbering(&migrations, authored);
+        let renames: Vec<(String, String)> =
+            renumberings.iter().map(|r| (display_name(&r.from), display_name(&r.to))).collect();
+        assert_eq!(
+            renames,
+            vec![
+                ("0002_orders.sql".to_string(), "0004_orders.sql".to_string()),
+                ("0002_items.sql".to_string(), "0005_items.sql".to_string()),
+            ]
+        );
+        assert!(renumberings.iter().all(|r| r.kept == "0002_users.sql"));
+        assert_eq!(renamed_path(Path::new("V12__add_col.sql"), 12, 104), Some(PathBuf::from("V104__add_col.sql")));
+    }
+
+    #[test]
+    fn test_interactive_renumbering_never_waits_without_a_terminal() {
+        let prompt = crate::orchestrator::prompt::NonInteractivePrompt { assume_yes: false };
+        assert!(matches!(confirm(&prompt, "Rename a to b? [y/N/q]"), Err(RenumberError::Prompt(PromptError::NoAnswer(_)))));
+    }
+}
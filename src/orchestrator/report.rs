@@ -0,0 +1,437 @@
+use crate::executor::ConnectionError;
+use crate::logger::SUMMARY_TARGET;
+use crate::model::{ChecksumNormalization, Migration, MigrationType};
+use crate::orchestrator::migration_loader::LoaderOptions;
+use crate::orchestrator::MigrationLoader;
+use crate::tracker::version_store::AppliedMigration;
+use crate::tracker::{schema_init, Target};
+use chrono::{DateTime, Utc};
+use log::{debug, info};
+use similar::{ChangeTag, TextDiff};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// How many revisions of a file are searched for the content that was applied
+const MAX_GIT_REVISIONS: usize = 50;
+
+/// Everything shown in the HTML report, gathered from the migration files and schema_migrations
+#[derive(Debug, Clone)]
+pub struct MigrationReport {
+    pub generated_at: DateTime<Utc>,
+    pub environment: Option<String>,
+    pub history: Vec<AppliedMigration>,
+    pub pending: Vec<PendingChange>,
+    pub drift: Vec<DriftFinding>,
+}
+
+/// A migration apply would run, with the SQL it changes
+#[derive(Debug, Clone)]
+pub struct PendingChange {
+    pub filename: String,
+    /// Set for repeatables that ran before and whose content changed since
+    pub changed: bool,
+    pub diff: SqlDiff,
+}
+
+#[derive(Debug, Clone)]
+pub struct DriftFinding {
+    pub filename: String,
+    pub kind: DriftKind,
+    /// Diff from the applied content to the current file, for checksum mismatches
+    pub diff: Option<SqlDiff>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DriftKind {
+    /// The file was modified after it was applied
+    ChecksumMismatch,
+    /// The migration is recorded as failed
+    Failed,
+    /// schema_migrations has a row for a file that no longer exists
+    Orphaned,
+}
+
+impl DriftKind {
+    fn describe(self) -> &'static str {
+        match self {
+            DriftKind::ChecksumMismatch => "modified after it was applied",
+            DriftKind::Failed => "recorded as failed",
+            DriftKind::Orphaned => "applied, but the file no longer exists",
+        }
+    }
+}
+
+/// Old and new SQL of a change. `old` is None when the previous content is unknown,
+/// e.g. a new migration or a modified file whose applied revision is not in git.
+#[derive(Debug, Clone)]
+pub struct SqlDiff {
+    pub old: Option<String>,
+    pub new: String,
+}
+
+impl MigrationReport {
+    /// Compares the migration files with the schema_migrations rows
+    pub fn build(
+        migrations: &[Migration],
+        applied: Vec<AppliedMigration>,
+        baseline: Option<u32>,
+        environment: Option<&str>,
+        normalization: ChecksumNormalization,
+    ) -> Self {
+        let applied_map: HashMap<&str, &AppliedMigration> =
+            applied.iter().map(|a| (a.migration_id.as_str(), a)).collect();
+        let mut pending = Vec::new();
+        let mut drift = Vec::new();
+
+        for migration in migrations {
+            let current = current_sql(migration);
+            match applied_map.get(migration.identifier().as_str()) {
+                None => {
+                    let below_baseline = matches!((migration.version, baseline), (Some(v), Some(b)) if v <= b);
+                    if migration.runs_in_environment(environment) && !below_baseline {
+                        pending.push(PendingChange {
+                            filename: migration.filename(),
+                            changed: false,
+                            diff: SqlDiff { old: None, new: current },
+                        });
+                    }
+                }
+                Some(record) if !record.success => drift.push(DriftFinding {
+                    filename: migration.filename(),
+                    kind: DriftKind::Failed,
+                    diff: None,
+                }),
+                Some(record) if !migration.matches_checksum(&record.checksum) => {
+                    let diff = SqlDiff {
+                        old: applied_revision(migration, &record.checksum, normalization),
+                        new: current,
+                    };
+                    if migration.migration_type == MigrationType::Repeatable {
+                        pending.push(PendingChange {
+                            filename: migration.filename(),
+                            changed: true,
+                            diff,
+                        });
+                    } else {
+                        drift.push(DriftFinding {
+                            filename: migration.filename(),
+                            kind: DriftKind::ChecksumMismatch,
+                            diff: Some(diff),
+                        });
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+
+        for record in &applied {
+            let file_exists = migrations.iter().any(|m| m.identifier() == record.migration_id);
+            if !file_exists && record.migration_type == MigrationType::Versioned && record.version != baseline {
+                drift.push(DriftFinding {
+                    filename: record.filename.clone(),
+                    kind: DriftKind::Orphaned,
+                    diff: None,
+                });
+            }
+        }
+
+        let mut history = applied;
+        history.sort_by_key(|a| a.applied_at);
+
+        Self {
+            generated_at: Utc::now(),
+            environment: environment.map(str::to_string),
+            history,
+            pending,
+            drift,
+        }
+    }
+
+    /// Renders the report as a self-contained HTML page
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        let title = match &self.environment {
+            Some(env) => format!("Migration report: {}", escape(env)),
+            None => "Migration report".to_string(),
+        };
+
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n<p class=\"meta\">Generated {} by deriddl {}</p>\n",
+            self.generated_at.format("%Y-%m-%d %H:%M:%S UTC"),
+            env!("CARGO_PKG_VERSION"),
+        );
+        let _ = writeln!(
+            html,
+            "<ul class=\"summary\"><li>{} applied</li><li>{} pending</li><li class=\"{}\">{} drift finding(s)</li></ul>",
+            self.history.len(),
+            self.pending.len(),
+            if self.drift.is_empty() { "ok" } else { "bad" },
+            self.drift.len()
+        );
+
+        html.push_str("<h2>Pending changes</h2>\n");
+        if self.pending.is_empty() {
+            html.push_str("<p>Nothing to apply: the database is up to date.</p>\n");
+        }
+        for change in &self.pending {
+            let label = if change.changed { "changed repeatable" } else { "new" };
+            let _ = writeln!(
+                html,
+                "<details open><summary>{} <span class=\"tag\">{}</span></summary>",
+                escape(&change.filename),
+                label
+            );
+            render_diff(&mut html, &change.diff);
+            html.push_str("</details>\n");
+        }
+
+        html.push_str("<h2>Drift</h2>\n");
+        if self.drift.is_empty() {
+            html.push_str("<p>No drift: every applied migration matches its file.</p>\n");
+        }
+        for finding in &self.drift {
+            let _ = writeln!(
+                html,
+                "<details open><summary>{} <span class=\"tag bad\">{}</span></summary>",
+                escape(&finding.filename),
+                finding.kind.describe()
+            );
+            if let Some(diff) = &finding.diff {
+                render_diff(&mut html, diff);
+            }
+            html.push_str("</details>\n");
+        }
+
+        html.push_str("<h2>History</h2>\n");
+        if self.history.is_empty() {
+            html.push_str("<p>No migrations have been applied yet.</p>\n");
+        } else {
+            html.push_str("<ol class=\"timeline\">\n");
+            for record in &self.history {
+                let duration = if record.applied_externally() {
+                    "applied externally".to_string()
+                } else if record.skipped_by_guard() {
+                    "skipped by guard".to_string()
+                } else if record.skip_listed() {
+                    "skipped (skip list)".to_string()
+                } else {
+                    format!("{} ms", record.execution_time_ms)
+                };
+                let _ = writeln!(
+                    html,
+                    "<li class=\"{}\"><time>{}</time> {} <span class=\"meta\">{}{}</span></li>",
+                    if record.success { "ok" } else { "bad" },
+                    record.applied_at.format("%Y-%m-%d %H:%M:%S"),
+                    escape(&record.filename),
+                    duration,
+                    if record.success { "" } else { ", failed" }
+                );
+            }
+            html.push_str("</ol>\n");
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+pub fn run_report(
+    target: &Target,
+    path: &str,
+    loader: &LoaderOptions,
+    environment: Option<&str>,
+    output: &Path,
+) -> Result<(), ReportError> {
+    info!("Generating migration report");
+    debug!("Migrations path: {}", path);
+
+    let migrations = MigrationLoader::load_migrations_with_options(path, loader)
+        .map_err(|e| ReportError::LoadFailed(e.to_string()))?;
+
+    let (applied, baseline) = if schema_init::check_migration_table_exists(target)? {
+        let mut version_store = target.version_store()?;
+        (version_store.get_applied_migrations()?, version_store.get_baseline_version()?)
+    } else {
+        (Vec::new(), None)
+    };
+
+    let report = MigrationReport::build(
+        &migrations,
+        applied,
+        baseline,
+        environment,
+        loader.checksum_normalization,
+    );
+
+    if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| ReportError::Io(parent.display().to_string(), e.to_string()))?;
+    }
+    fs::write(output, report.to_html())
+        .map_err(|e| ReportError::Io(output.display().to_string(), e.to_string()))?;
+
+    info!(
+        target: SUMMARY_TARGET,
+        "📝 Wrote report to {} ({} applied, {} pending, {} drift finding(s))",
+        output.display(),
+        report.history.len(),
+        report.pending.len(),
+        report.drift.len()
+    );
+    Ok(())
+}
+
+fn current_sql(migration: &Migration) -> String {
+    migration
+        .up_sql()
+        .map(|sql| sql.into_owned())
+        .unwrap_or_default()
+}
+
+/// Searches the file's git history for the revision whose checksum matches the
+/// recorded one, returning its up SQL
+pub(crate) fn applied_revision(migration: &Migration, recorded: &str, normalization: ChecksumNormalization) -> Option<String> {
+    let dir = migration.file_path.parent()?;
+    let file_name = migration.file_path.file_name()?.to_str()?;
+
+    let log = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["log", "--format=%H", "-n", &MAX_GIT_REVISIONS.to_string(), "--", file_name])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    for revision in String::from_utf8_lossy(&log.stdout).lines() {
+        let Some(content) = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["show", &format!("{}:./{}", revision, file_name)])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+        else {
+            continue;
+        };
+        let content = String::from_utf8_lossy(&content.stdout).into_owned();
+        let mut candidate = Migration::new(0, migration.name.clone(), migration.file_path.clone(), content);
+        candidate.use_checksum_strategy(migration.checksum_strategy.clone(), normalization);
+        if candidate.matches_checksum(recorded) {
+            debug!("Found applied content of {} at revision {}", migration.filename(), revision);
+            return Some(candidate.sql_content);
+        }
+    }
+    None
+}
+
+fn render_diff(html: &mut String, diff: &SqlDiff) {
+    html.push_str("<pre class=\"diff\">");
+    match &diff.old {
+        Some(old) => {
+            for change in TextDiff::from_lines(old.as_str(), diff.new.as_str()).iter_all_changes() {
+                let (class, sign) = match change.tag() {
+                    ChangeTag::Delete => ("del", '-'),
+                    ChangeTag::Insert => ("ins", '+'),
+                    ChangeTag::Equal => ("eq", ' '),
+                };
+                let _ = writeln!(
+                    html,
+                    "<span class=\"{}\">{}{}</span>",
+                    class,
+                    sign,
+                    escape(change.value().trim_end_matches('\n'))
+                );
+            }
+        }
+        None => {
+            for line in diff.new.lines() {
+                let _ = writeln!(html, "<span class=\"ins\">+{}</span>", escape(line));
+            }
+        }
+    }
+    html.push_str("</pre>\n");
+    if diff.old.is_none() && !diff.new.is_empty() {
+        html.push_str("<p class=\"meta\">Previous content unknown; showing the current SQL.</p>\n");
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+const STYLE: &str = "
+body { font-family: system-ui, sans-serif; margin: 2rem auto; max-width: 60rem; color: #222; }
+.meta { color: #666; font-size: 0.9em; }
+.summary { display: flex; gap: 2rem; list-style: none; padding: 0; font-weight: bold; }
+.ok { color: #1a7f37; } .bad { color: #cf222e; }
+details { border: 1px solid #ddd; border-radius: 4px; margin: 0.5rem 0; padding: 0.5rem; }
+summary { cursor: pointer; font-family: monospace; }
+.tag { font-family: system-ui, sans-serif; font-size: 0.8em; background: #eee; border-radius: 3px; padding: 0 0.4em; }
+pre.diff { overflow-x: auto; background: #f6f8fa; padding: 0.5rem; }
+pre.diff span { display: block; }
+.ins { background: #e6ffec; } .del { background: #ffebe9; }
+.timeline { border-left: 2px solid #ddd; padding-left: 1.5rem; }
+.timeline li { margin: 0.3rem 0; font-family: monospace; }
+.timeline time { color: #666; margin-right: 0.5rem; }
+";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReportError {
+    #[error("Failed to load migrations: {0}")]
+    LoadFailed(String),
+
+    #[error("Connection error: {0}")]
+    Connection(#[from] ConnectionError),
+
+    #[error("Failed to write {0}: {1}")]
+    Io(String, String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn applied(migration: &Migration, checksum: &str, success: bool) -> AppliedMigration {
+        AppliedMigration { checksum: checksum.to_string(), success, ..AppliedMigration::of(migration) }
+    }
+
+    #[test]
+    fn test_report_lists_pending_and_drift() {
+        let init = Migration::new(1, "init".to_string(), PathBuf::from("0001_init.sql"), "CREATE TABLE a (id INT);".to_string());
+        let users = Migration::new(2, "users".to_string(), PathBuf::from("0002_users.sql"), "CREATE TABLE <users> (id INT);".to_string());
+        let gone = Migration::new(3, "gone".to_string(), PathBuf::from("0003_gone.sql"), String::new());
+
+        let report = MigrationReport::build(
+            &[init.clone(), users],
+            vec![applied(&init, "stale", true), applied(&gone, "x", true)],
+            None,
+            None,
+            ChecksumNormalization::Strict,
+        );
+
+        assert_eq!(report.pending.len(), 1);
+        assert_eq!(report.pending[0].filename, "0002_users.sql");
+        let kinds: Vec<DriftKind> = report.drift.iter().map(|d| d.kind).collect();
+        assert_eq!(kinds, vec![DriftKind::ChecksumMismatch, DriftKind::Orphaned]);
+
+        let html = report.to_html();
+        assert!(html.contains("CREATE TABLE &lt;users&gt;"));
+        assert!(html.contains("modified after it was applied"));
+        assert!(html.contains("0003_gone.sql"));
+    }
+}
@@ -0,0 +1,153 @@
+use crate::executor::ConnectionError;
+use crate::logger::SUMMARY_TARGET;
+use crate::tracker::content_archive::{archived, ArchivedContent, CONTENT_TABLE};
+use crate::tracker::{schema_init, Target};
+use log::{debug, info, warn};
+use std::fs;
+use std::path::Path;
+
+/// Versions selected with `--versions`: `1..50`, `..50`, `10..` or a single `7`, inclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VersionRange {
+    pub start: Option<u32>,
+    pub end: Option<u32>,
+}
+
+impl VersionRange {
+    /// Parses a `--versions` argument
+    pub fn parse(arg: &str) -> Result<Self, String> {
+        let bound = |value: &str| -> Result<Option<u32>, String> {
+            let value = value.trim();
+            if value.is_empty() {
+                return Ok(None);
+            }
+            value.parse().map(Some).map_err(|_| format!("'{}' is not a version", value))
+        };
+        let range = match arg.split_once("..") {
+            Some((start, end)) => Self { start: bound(start)?, end: bound(end)? },
+            None => {
+                let version = bound(arg)?.ok_or_else(|| "expected a version or a range like 1..50".to_string())?;
+                Self { start: Some(version), end: Some(version) }
+            }
+        };
+        match range {
+            Self { start: Some(start), end: Some(end) } if start > end => Err(format!("empty range {}", arg)),
+            range => Ok(range),
+        }
+    }
+
+    pub fn contains(&self, version: u32) -> bool {
+        self.start.is_none_or(|start| version >= start) && self.end.is_none_or(|end| version <= end)
+    }
+}
+
+/// Writes the archived content of applied migrations to `out`, for when the files were
+/// lost. With `versions` only those versioned migrations are restored, otherwise every
+/// applied migration, repeatable ones included. Existing files are kept unless `overwrite`.
+pub fn run_restore_files(
+    target: &Target,
+    versions: Option<VersionRange>,
+    out: &Path,
+    overwrite: bool,
+) -> Result<(), RestoreFilesError> {
+    info!("Restoring migration files from {}", CONTENT_TABLE);
+    debug!("Versions: {:?}", versions);
+    debug!("Output directory: {}", out.display());
+
+    if !schema_init::check_migration_table_exists(target)? {
+        return Err(RestoreFilesError::NothingApplied);
+    }
+    let mut version_store = target.version_store()?;
+    let applied = version_store.get_applied_migrations()?;
+    let sql = version_store.sql().clone();
+    let contents = archived(&mut version_store.executor()?, &sql)?;
+    if contents.is_empty() {
+        return Err(RestoreFilesError::NoArchive);
+    }
+
+    let selected: Vec<_> = applied
+        .iter()
+        .filter(|a| a.success && a.rolled_back_at.is_none())
+        .filter(|a| match versions {
+            Some(range) => a.version.is_some_and(|version| range.contains(version)),
+            None => true,
+        })
+        .collect();
+    if selected.is_empty() {
+        info!(target: SUMMARY_TARGET, "No applied migrations match; nothing to restore");
+        return Ok(());
+    }
+
+    fs::create_dir_all(out).map_err(|e| RestoreFilesError::Write(out.display().to_string(), e))?;
+    let mut restored = 0;
+    let mut missing = 0;
+    for migration in selected {
+        let Some(content) = applied_content(&contents, &migration.migration_id, &migration.checksum) else {
+            warn!("⚠️  {} was not archived (applied before tracker.store_content was enabled, or streamed)", migration.filename);
+            missing += 1;
+            continue;
+        };
+        // Only the file name is used, so a tampered row cannot write outside `out`
+        let Some(name) = Path::new(&content.filename).file_name() else {
+            warn!("⚠️  {} has no usable file name in {}", migration.migration_id, CONTENT_TABLE);
+            missing += 1;
+            continue;
+        };
+        let file = out.join(name);
+        if file.exists() && !overwrite {
+            warn!("⚠️  {} already exists, keeping it (use --overwrite to replace it)", file.display());
+            continue;
+        }
+        fs::write(&file, &content.content).map_err(|e| RestoreFilesError::Write(file.display().to_string(), e))?;
+        info!("  📄 {}", file.display());
+        restored += 1;
+    }
+
+    info!(target: SUMMARY_TARGET, "✅ Restored {} migration file(s) to {}", restored, out.display());
+    if missing > 0 {
+        warn!(target: SUMMARY_TARGET, "⚠️  {} applied migration(s) could not be restored", missing);
+    }
+    Ok(())
+}
+
+/// The archived content matching what schema_migrations records as applied
+fn applied_content<'a>(contents: &'a [ArchivedContent], migration_id: &str, checksum: &str) -> Option<&'a ArchivedContent> {
+    contents.iter().find(|c| c.migration_id == migration_id && c.checksum == checksum)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RestoreFilesError {
+    #[error("No migrations have been applied to this database")]
+    NothingApplied,
+
+    #[error("{} is empty or missing; enable tracker.store_content to archive applied migrations", CONTENT_TABLE)]
+    NoArchive,
+
+    #[error("Failed to write {0}: {1}")]
+    Write(String, std::io::Error),
+
+    #[error("Connection error: {0}")]
+    Connection(#[from] ConnectionError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_version_ranges() {
+        let range = VersionRange::parse("1..50").unwrap();
+        assert_eq!(range, VersionRange { start: Some(1), end: Some(50) });
+        assert!(range.contains(1) && range.contains(50));
+        assert!(!range.contains(51));
+
+        assert!(VersionRange::parse("..5").unwrap().contains(0));
+        assert!(VersionRange::parse("10..").unwrap().contains(u32::MAX));
+        let single = VersionRange::parse("7").unwrap();
+        assert!(single.contains(7) && !single.contains(8));
+
+        assert!(VersionRange::parse("9..3").is_err());
+        assert!(VersionRange::parse("a..3").is_err());
+        assert!(VersionRange::parse("").is_err());
+    }
+}
@@ -0,0 +1,201 @@
+use crate::dialects;
+use crate::executor::{introspect_schema, ConnectionError, ConnectionManager, ConnectionSettings, DatabaseExecutor, SchemaModel};
+use crate::logger::SUMMARY_TARGET;
+use crate::model::Migration;
+use crate::orchestrator::migration_loader::LoaderOptions;
+use crate::orchestrator::planner::PlannerError;
+use crate::orchestrator::sandbox::{Sandbox, SandboxEngine, SandboxError, SandboxOptions};
+use crate::orchestrator::{MigrationLoader, Planner};
+use crate::tracker::Target;
+use log::{debug, error, info, warn};
+
+#[derive(Debug, Clone)]
+pub struct ReversibilityOptions {
+    /// Engine of the scratch database created when `conn` is not given
+    pub engine: SandboxEngine,
+    /// Empty database to run the check against instead of a scratch one
+    pub conn: Option<String>,
+    /// Dialect of `conn`, for catalog queries
+    pub dialect: Option<String>,
+    /// Treat migrations without down SQL as failures
+    pub require_down: bool,
+}
+
+/// Result of applying a migration's up SQL, then its down SQL
+#[derive(Debug, Clone, PartialEq)]
+pub enum RoundTrip {
+    Reversible,
+    NoDownSql,
+    /// The schema after down differs from the schema before up
+    Mismatch(Vec<String>),
+    Failed(String),
+}
+
+impl RoundTrip {
+    pub fn compare(before: &SchemaModel, after_down: &SchemaModel) -> Self {
+        let differences = before.differences(after_down);
+        if differences.is_empty() {
+            RoundTrip::Reversible
+        } else {
+            RoundTrip::Mismatch(differences)
+        }
+    }
+}
+
+/// Applies each versioned migration to a scratch database followed by its down SQL,
+/// snapshotting the schema around the pair to catch down scripts that don't restore it.
+/// Each migration is re-applied after its check so the next one starts from the right schema.
+pub fn run_check_reversibility(
+    path: &str,
+    loader: &LoaderOptions,
+    environment: Option<&str>,
+    options: &ReversibilityOptions,
+    run: &ConnectionSettings,
+) -> Result<(), ReversibilityError> {
+    info!("Checking that down migrations restore the previous schema");
+
+    let migrations = MigrationLoader::load_migrations_with_options(path, loader)
+        .map_err(|e| ReversibilityError::LoadFailed(e.to_string()))?;
+    let migrations: Vec<Migration> = migrations
+        .into_iter()
+        .filter(|m| !m.is_repeatable() && m.runs_in_environment(environment))
+        .collect();
+    let migrations = Planner::order_migrations(migrations)?;
+
+    let sandbox = match &options.conn {
+        Some(_) => None,
+        None => Some(Sandbox::start(&SandboxOptions {
+            engine: options.engine,
+            print_conn: false,
+            keep: false,
+        })?),
+    };
+    let (target, dialect_name) = match (&options.conn, &sandbox) {
+        (Some(conn), _) => (Target::new(conn).with_connection(run.clone()), options.dialect.as_deref()),
+        (None, Some(sandbox)) => (sandbox.target(run), Some(sandbox.engine.dialect())),
+        (None, None) => unreachable!("a sandbox is started when no connection string is given"),
+    };
+    let dialect = dialects::get_dialect_with_config(None, Some(&target.conn), dialect_name)
+        .map_err(|e| ReversibilityError::Dialect(e.to_string()))?;
+
+    let manager = target.connection_manager()?;
+    let mut executor = manager.executor(&target.conn)?;
+    let mut results = Vec::new();
+
+    for migration in &migrations {
+        let result = check_migration(&mut executor, &manager, &target.conn, dialect.as_ref(), migration)?;
+        let stop = matches!(result, RoundTrip::Failed(_));
+        results.push((migration.filename(), result));
+        if stop {
+            // Later migrations would run against an unknown schema
+            break;
+        }
+    }
+
+    let mut problems = Vec::new();
+    for (filename, result) in &results {
+        match result {
+            RoundTrip::Reversible => info!("  ✅ {}", filename),
+            RoundTrip::NoDownSql if options.require_down => {
+                error!("  {}: no down SQL", filename);
+                problems.push(format!("{}: no down SQL", filename));
+            }
+            RoundTrip::NoDownSql => info!("  ➖ {} (no down SQL)", filename),
+            RoundTrip::Mismatch(differences) => {
+                error!("  {}: down SQL does not restore the schema", filename);
+                for difference in differences {
+                    error!("      {}", difference);
+                }
+                problems.push(format!("{}: {}", filename, differences.join(", ")));
+            }
+            RoundTrip::Failed(reason) => {
+                error!("  {}: {}", filename, reason);
+                problems.push(format!("{}: {}", filename, reason));
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        info!(target: SUMMARY_TARGET, "✅ {} migration(s) checked, all down scripts restore the schema", results.len());
+        Ok(())
+    } else {
+        Err(ReversibilityError::NotReversible(problems))
+    }
+}
+
+fn check_migration(
+    executor: &mut DatabaseExecutor,
+    manager: &ConnectionManager,
+    conn: &str,
+    dialect: &dyn dialects::DatabaseDialect,
+    migration: &Migration,
+) -> Result<RoundTrip, ReversibilityError> {
+    debug!("Checking {}", migration.filename());
+    let up_sql = migration
+        .up_sql()
+        .map_err(|e| ReversibilityError::LoadFailed(format!("{}: {}", migration.filename(), e)))?;
+
+    let before = introspect_schema(manager, conn, dialect)?;
+    if let Err(e) = executor.execute_query(&up_sql) {
+        return Ok(RoundTrip::Failed(format!("up SQL failed: {}", e)));
+    }
+
+    let Some(down_sql) = migration.get_rollback_sql() else {
+        return Ok(RoundTrip::NoDownSql);
+    };
+    if let Err(e) = executor.execute_query(down_sql) {
+        return Ok(RoundTrip::Failed(format!("down SQL failed: {}", e)));
+    }
+    let after_down = introspect_schema(manager, conn, dialect)?;
+
+    if let Err(e) = executor.execute_query(&up_sql) {
+        warn!("{} could not be re-applied after its down SQL", migration.filename());
+        return Ok(RoundTrip::Failed(format!("up SQL failed when re-applied after down: {}", e)));
+    }
+    Ok(RoundTrip::compare(&before, &after_down))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReversibilityError {
+    #[error("Failed to load migrations: {0}")]
+    LoadFailed(String),
+
+    #[error("{0}")]
+    Planner(#[from] PlannerError),
+
+    #[error("{0}")]
+    Sandbox(#[from] SandboxError),
+
+    #[error("Dialect error: {0}")]
+    Dialect(String),
+
+    #[error("Connection error: {0}")]
+    Connection(#[from] ConnectionError),
+
+    #[error("{} migration(s) are not reversible: {}", .0.len(), .0.join("; "))]
+    NotReversible(Vec<String>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_down_that_leaves_a_column_is_a_mismatch() {
+        let row = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        let before = SchemaModel::from_rows(&[row(&["main", "users", "id", "INTEGER", "NO", "1"])], &[]);
+        let after_down = SchemaModel::from_rows(
+            &[
+                row(&["main", "users", "id", "INTEGER", "NO", "1"]),
+                row(&["main", "users", "email", "TEXT", "YES", "0"]),
+            ],
+            &[],
+        );
+
+        assert_eq!(RoundTrip::compare(&before, &before), RoundTrip::Reversible);
+        assert_eq!(
+            RoundTrip::compare(&before, &after_down),
+            RoundTrip::Mismatch(vec!["column users.email was added".to_string()])
+        );
+    }
+}
@@ -1,9 +1,14 @@
-use crate::executor::ConnectionError;
+use crate::executor::{is_interrupted, ConnectionError, DatabaseExecutor};
 use crate::model::migration::{Migration, MigrationType};
 use crate::tracker::version_store::{AppliedMigration, VersionStore};
-use crate::orchestrator::migration_loader::MigrationLoader;
+use crate::tracker::{SqlLog, Target};
+use crate::orchestrator::guard::{GuardError, ProtectionGuard};
+use crate::orchestrator::migration_loader::{LoaderOptions, MigrationLoader};
+use crate::orchestrator::prompt::{Prompt, PromptError};
+use crate::orchestrator::summary::{tally, Tally};
+use crate::tracker::repeatable_history::definitions;
 use log::{debug, error, info, warn};
-use std::io::{self, Write};
+use std::path::Path;
 
 /// Error types for rollback operations
 #[derive(Debug, thiserror::Error)]
@@ -22,12 +27,30 @@ pub enum RollbackError {
     
     #[error("Cannot rollback to version {0}: migration not found or not applied")]
     InvalidTargetVersion(u32),
+
+    #[error("No applied migrations are recorded for release {0}")]
+    UnknownRelease(String),
     
     #[error("Rollback cancelled by user")]
     Cancelled,
     
     #[error("Repeatable migration {0} cannot be rolled back")]
     RepeatableMigrationRollback(String),
+
+    #[error("No applied repeatable migration named {0}")]
+    UnknownRepeatable(String),
+
+    #[error("No earlier definition of {0} is stored; enable migrations.repeatable_history before the next deploy")]
+    NoPreviousDefinition(String),
+
+    #[error("{0}")]
+    Unconfirmed(#[from] GuardError),
+
+    #[error("{0}")]
+    Prompt(#[from] PromptError),
+
+    #[error("Interrupted by a signal {0}")]
+    Interrupted(String),
 }
 
 /// Rollback strategy
@@ -37,6 +60,8 @@ pub enum RollbackStrategy {
     Steps(u32),
     /// Roll back to specific version (inclusive)
     ToVersion(u32),
+    /// Roll back the migrations applied as one release
+    Release(String),
 }
 
 /// Information about a migration rollback operation
@@ -47,30 +72,52 @@ pub struct RollbackPlan {
     pub total_migrations: usize,
 }
 
+impl RollbackStrategy {
+    /// The strategy `rollback` flags select: `--release` over `--to-version` over `--steps`
+    pub fn from_flags(steps: u32, to_version: Option<u32>, release: Option<&str>) -> Self {
+        match (release, to_version) {
+            (Some(release), _) => RollbackStrategy::Release(release.to_string()),
+            (None, Some(version)) => RollbackStrategy::ToVersion(version),
+            (None, None) => RollbackStrategy::Steps(steps),
+        }
+    }
+}
+
+/// How `rollback` runs. [`RollbackOptions::new`] gives a confirmed rollback without an SQL
+/// log; set the other fields for the command line flags.
+#[derive(Clone, Copy)]
+pub struct RollbackOptions<'a> {
+    pub loader: &'a LoaderOptions,
+    pub guard: &'a ProtectionGuard,
+    pub prompt: &'a dyn Prompt,
+    pub dry_run: bool,
+    pub sql_log: Option<&'a Path>,
+}
+
+impl<'a> RollbackOptions<'a> {
+    pub fn new(loader: &'a LoaderOptions, guard: &'a ProtectionGuard, prompt: &'a dyn Prompt) -> Self {
+        Self { loader, guard, prompt, dry_run: false, sql_log: None }
+    }
+}
+
 /// Run migration rollback with the specified strategy
 pub fn run_rollback(
-    connection_string: &str,
+    target: &Target,
     migrations_path: &str,
-    steps: u32,
-    to_version: Option<u32>,
-    dry_run: bool,
-    require_confirmation: bool,
+    strategy: RollbackStrategy,
+    options: &RollbackOptions,
 ) -> Result<(), RollbackError> {
+    let RollbackOptions { loader, guard, prompt, dry_run, sql_log } = *options;
     info!("Starting rollback operation");
-    debug!("Connection string length: {}", connection_string.len());
+    debug!("Connection string length: {}", target.conn.len());
     debug!("Migrations path: {}", migrations_path);
     debug!("Dry run: {}", dry_run);
-
-    let strategy = match to_version {
-        Some(version) => RollbackStrategy::ToVersion(version),
-        None => RollbackStrategy::Steps(steps),
-    };
     
     // Create version store
-    let mut version_store = VersionStore::new(connection_string)?;
+    let mut version_store = target.version_store()?;
 
     // Load migrations from filesystem
-    let mut migrations = MigrationLoader::load_migrations(migrations_path)
+    let mut migrations = MigrationLoader::load_migrations_with_options(migrations_path, loader)
         .map_err(|e| RollbackError::Migration(e.to_string()))?;
 
     // Get applied migrations from database
@@ -87,9 +134,10 @@ pub fn run_rollback(
     // Display rollback plan
     display_rollback_plan(&plan, dry_run);
 
-    // Get confirmation if required
-    if require_confirmation && !dry_run {
-        if !get_user_confirmation(&plan)? {
+    if !dry_run {
+        warn!("⚠️  DESTRUCTIVE OPERATION");
+        warn!("Rolling back {} migration(s) will permanently modify your database!", plan.total_migrations);
+        if !prompt.confirm("Do you want to continue?")? {
             return Err(RollbackError::Cancelled);
         }
     }
@@ -104,13 +152,115 @@ pub fn run_rollback(
         return Ok(());
     }
 
+    guard.ensure_confirmed(&format!("roll back {} migration(s)", plan.total_migrations))?;
+
     // Execute rollbacks
-    execute_rollbacks(&mut version_store, &plan, &migration_map)?;
+    let mut sql_log = SqlLog::open_optional(sql_log);
+    execute_rollbacks(&mut version_store, &plan, &migration_map, sql_log.as_mut())?;
     
     info!("✅ Rollback completed successfully");
     Ok(())
 }
 
+/// Restores the definition a repeatable migration had before its current one, from the
+/// history kept with `migrations.repeatable_history`. `name` is the migration's id
+/// (`R__reporting_view`), name or file name. The restored checksum is recorded, so the
+/// next apply runs the file again once it is fixed.
+pub fn run_rollback_repeatable(
+    target: &Target,
+    migrations_path: &str,
+    name: &str,
+    options: &RollbackOptions,
+) -> Result<(), RollbackError> {
+    let RollbackOptions { loader, guard, prompt, dry_run, sql_log } = *options;
+    info!("Restoring the previous definition of {}", name);
+    let migrations = MigrationLoader::load_migrations_with_options(migrations_path, loader)
+        .map_err(|e| RollbackError::Migration(e.to_string()))?;
+    let migration = migrations
+        .iter()
+        .filter(|m| m.is_repeatable())
+        .find(|m| {
+            let file_name = m.file_path.file_name().map(|f| f.to_string_lossy().to_string());
+            m.identifier() == name || m.name == name || file_name.as_deref() == Some(name)
+        })
+        .ok_or_else(|| RollbackError::UnknownRepeatable(name.to_string()))?;
+    let id = migration.identifier();
+
+    let mut version_store = target.version_store()?;
+    let current = version_store
+        .get_applied_migrations()?
+        .into_iter()
+        .find(|a| a.migration_id == id && a.success)
+        .ok_or_else(|| RollbackError::UnknownRepeatable(name.to_string()))?;
+    let previous = definitions(&mut version_store.executor()?, &target.sql, &id)?
+        .into_iter()
+        .find(|d| d.checksum != current.checksum)
+        .ok_or_else(|| RollbackError::NoPreviousDefinition(id.clone()))?;
+
+    let recorded_at = chrono::DateTime::from_timestamp(previous.recorded_at, 0).unwrap_or_default();
+    info!(
+        "{} {} to the definition applied at {} (checksum {}...)",
+        if dry_run { "Would restore" } else { "Will restore" },
+        migration.filename(),
+        recorded_at.format("%Y-%m-%d %H:%M:%S UTC"),
+        &previous.checksum[..8.min(previous.checksum.len())]
+    );
+    for line in previous.sql.lines().filter(|l| !l.trim().is_empty()).take(3) {
+        info!("     {}", line.chars().take(60).collect::<String>());
+    }
+    if dry_run {
+        info!("🔍 Dry run mode - no changes will be applied");
+        return Ok(());
+    }
+    if !prompt.confirm(&format!("Restore the previous definition of {}?", migration.filename()))? {
+        return Err(RollbackError::Cancelled);
+    }
+    guard.ensure_confirmed(&format!("restore the previous definition of {}", migration.filename()))?;
+
+    let restored = Migration {
+        sql_content: previous.sql.clone(),
+        checksum: previous.checksum.clone(),
+        streamed: None,
+        ..migration.clone()
+    };
+    let mut sql_log = SqlLog::open_optional(sql_log);
+    let start_time = std::time::Instant::now();
+    version_store.record_migration_start(&restored)?;
+    let operation = format!("restore {}", restored.filename());
+    let mut run = |exec: &mut DatabaseExecutor| {
+        let on_executed = |statement: &str, _: &_| {
+            if let Some(sql_log) = sql_log.as_mut() {
+                sql_log.record(&operation, statement);
+            }
+        };
+        if restored.raw {
+            exec.execute_batch_with(restored.raw_batch(&restored.sql_content), on_executed)
+        } else {
+            exec.execute_query_with(&restored.sql_content, on_executed)
+        }
+    };
+    let mut executor = version_store.executor()?;
+    executor.stop_on_interrupt();
+    let result = if restored.no_transaction { run(&mut executor) } else { executor.execute_transaction(run) };
+    drop(executor);
+    let execution_time = start_time.elapsed().as_millis() as i32;
+    if let Err(e) = result {
+        version_store.record_migration_failure(&restored, execution_time)?;
+        tally(Tally::Failed, 1);
+        if is_interrupted() {
+            return Err(RollbackError::Interrupted(format!("while restoring {}, recorded as failed", restored.filename())));
+        }
+        error!("❌ Failed to restore {}: {}", restored.filename(), e);
+        return Err(RollbackError::Migration(e.to_string()));
+    }
+    version_store.record_migration_success(&restored, execution_time)?;
+    tally(Tally::RolledBack, 1);
+
+    info!("✅ Restored the previous definition of {}", restored.filename());
+    info!("💡 Fix {} before the next apply; it runs again because its checksum differs", migration.file_path.display());
+    Ok(())
+}
+
 /// Create a rollback plan based on the strategy
 pub fn create_rollback_plan(
     applied_migrations: &[AppliedMigration],
@@ -154,6 +304,29 @@ pub fn create_rollback_plan(
             
             rollback_migrations
         }
+        RollbackStrategy::Release(release) => {
+            let (in_release, others): (Vec<_>, Vec<_>) = versioned_migrations
+                .into_iter()
+                .partition(|m| m.release.as_deref() == Some(release.as_str()));
+            let Some(oldest) = in_release.iter().filter_map(|m| m.version).min() else {
+                return Err(RollbackError::UnknownRelease(release.clone()));
+            };
+
+            // Later releases stay applied, which only works if they don't build on this one
+            let later: Vec<&str> = others
+                .iter()
+                .filter(|m| m.version.is_some_and(|v| v > oldest))
+                .map(|m| m.filename.as_str())
+                .collect();
+            if !later.is_empty() {
+                warn!(
+                    "⚠️  Migrations applied after release {} stay applied: {}",
+                    release,
+                    later.join(", ")
+                );
+            }
+            in_release.into_iter().cloned().collect()
+        }
     };
 
     Ok(RollbackPlan {
@@ -174,6 +347,9 @@ fn display_rollback_plan(plan: &RollbackPlan, dry_run: bool) {
         RollbackStrategy::ToVersion(version) => {
             info!("{} migrations back to version {}:", action, version);
         }
+        RollbackStrategy::Release(release) => {
+            info!("{} {} migration(s) of release {}:", action, plan.total_migrations, release);
+        }
     }
 
     println!();
@@ -185,42 +361,30 @@ fn display_rollback_plan(plan: &RollbackPlan, dry_run: bool) {
     println!();
 }
 
-/// Get user confirmation for rollback
-fn get_user_confirmation(plan: &RollbackPlan) -> Result<bool, RollbackError> {
-    warn!("⚠️  DESTRUCTIVE OPERATION");
-    warn!("Rolling back {} migration(s) will permanently modify your database!", plan.total_migrations);
-    print!("Do you want to continue? (y/N): ");
-    io::stdout().flush().map_err(|_| RollbackError::Migration("Failed to flush stdout".to_string()))?;
-
-    let mut input = String::new();
-    io::stdin().read_line(&mut input)
-        .map_err(|_| RollbackError::Migration("Failed to read user input".to_string()))?;
-
-    Ok(input.trim().to_lowercase() == "y" || input.trim().to_lowercase() == "yes")
-}
-
-/// Create a map of migration versions to Migration objects
-fn create_migration_map(migrations: &mut [Migration]) -> std::collections::HashMap<u32, &Migration> {
+/// Create a map of versioned migrations by the id they are recorded under, so namespaces
+/// sharing a version number keep their own files
+fn create_migration_map(migrations: &mut [Migration]) -> std::collections::HashMap<String, &Migration> {
     migrations.iter()
-        .filter_map(|m| m.version.map(|v| (v, m)))
+        .filter(|m| m.version.is_some() && !m.is_repeatable())
+        .map(|m| (m.identifier(), m))
         .collect()
 }
 
 /// Validate that all migrations in the rollback plan have rollback SQL
 pub fn validate_rollback_plan(
     plan: &RollbackPlan,
-    migration_map: &std::collections::HashMap<u32, &Migration>,
+    migration_map: &std::collections::HashMap<String, &Migration>,
 ) -> Result<(), RollbackError> {
     for applied_migration in &plan.migrations_to_rollback {
-        if let Some(version) = applied_migration.version {
-            if let Some(migration) = migration_map.get(&version) {
-                if !migration.has_rollback() {
-                    return Err(RollbackError::NoRollbackSql(applied_migration.filename.clone()));
-                }
-            } else {
-                error!("Migration file not found for version {}", version);
+        match migration_map.get(&applied_migration.migration_id) {
+            Some(migration) if !migration.has_rollback() => {
+                return Err(RollbackError::NoRollbackSql(applied_migration.filename.clone()));
+            }
+            Some(_) => {}
+            None => {
+                error!("Migration file not found for version {}", applied_migration.migration_id);
                 return Err(RollbackError::Migration(
-                    format!("Migration file not found for version {}", version)
+                    format!("Migration file not found for version {}", applied_migration.migration_id)
                 ));
             }
         }
@@ -232,49 +396,69 @@ pub fn validate_rollback_plan(
 fn execute_rollbacks(
     version_store: &mut VersionStore,
     plan: &RollbackPlan,
-    migration_map: &std::collections::HashMap<u32, &Migration>,
+    migration_map: &std::collections::HashMap<String, &Migration>,
+    mut sql_log: Option<&mut SqlLog>,
 ) -> Result<(), RollbackError> {
     let total = plan.migrations_to_rollback.len();
     
     for (i, applied_migration) in plan.migrations_to_rollback.iter().enumerate() {
+        if is_interrupted() {
+            return Err(RollbackError::Interrupted(format!("before rolling back {}", applied_migration.filename)));
+        }
         info!("Rolling back migration {}/{}: {}", i + 1, total, applied_migration.filename);
         
-        if let Some(version) = applied_migration.version {
-            if let Some(migration) = migration_map.get(&version) {
-                // Validate rollback SQL exists
-                let rollback_sql = migration.get_rollback_sql()
-                    .ok_or_else(|| RollbackError::NoRollbackSql(applied_migration.filename.clone()))?;
-
-                debug!("Executing rollback SQL for migration {}", version);
-                debug!("Rollback SQL: {}", rollback_sql);
-
-                // Execute rollback SQL
-                let start_time = std::time::Instant::now();
-                let rollback_result = {
-                    let mut executor = version_store.executor()?;
-                    executor.execute_query(rollback_sql)
-                };
-                
-                match rollback_result {
-                    Ok(_) => {
-                        let execution_time = start_time.elapsed().as_millis() as u32;
-                        info!("✅ Successfully rolled back migration {} in {}ms", 
-                              applied_migration.filename, execution_time);
-                        
-                        // Remove from schema_migrations table
-                        version_store.remove_migration(version)?;
-                        
-                    }
-                    Err(e) => {
-                        error!("❌ Failed to rollback migration {}: {}", 
-                               applied_migration.filename, e);
-                        return Err(RollbackError::Connection(e));
-                    }
+        let migration = migration_map.get(&applied_migration.migration_id).ok_or_else(|| {
+            RollbackError::Migration(format!("Migration file not found for version {}", applied_migration.migration_id))
+        })?;
+
+        // Validate rollback SQL exists
+        let rollback_sql = migration.get_rollback_sql()
+            .ok_or_else(|| RollbackError::NoRollbackSql(applied_migration.filename.clone()))?;
+
+        debug!("Executing rollback SQL for migration {}", applied_migration.migration_id);
+        debug!("Rollback SQL: {}", rollback_sql);
+
+        // Execute rollback SQL
+        let start_time = std::time::Instant::now();
+        let rollback_result = {
+            let operation = format!("rollback {}", applied_migration.filename);
+            let mut executor = version_store.executor()?;
+            executor.stop_on_interrupt();
+            let on_executed = |statement: &str, _: &_| {
+                if let Some(sql_log) = sql_log.as_deref_mut() {
+                    sql_log.record(&operation, statement);
                 }
+            };
+            if migration.raw {
+                executor.execute_batch_with(rollback_sql, on_executed)
             } else {
-                return Err(RollbackError::Migration(
-                    format!("Migration file not found for version {}", version)
-                ));
+                executor.execute_query_with(rollback_sql, on_executed)
+            }
+        };
+        
+        match rollback_result {
+            Ok(_) => {
+                let execution_time = start_time.elapsed().as_millis() as u32;
+                info!("✅ Successfully rolled back migration {} in {}ms", 
+                      applied_migration.filename, execution_time);
+                
+                // Remove from schema_migrations table
+                version_store.remove_migration(&applied_migration.migration_id)?;
+                tally(Tally::RolledBack, 1);
+            }
+            Err(_) if is_interrupted() => {
+                tally(Tally::Failed, 1);
+                error!("❌ Rollback of {} interrupted, statements already run were kept", applied_migration.filename);
+                return Err(RollbackError::Interrupted(format!(
+                    "while rolling back {}; it is still recorded as applied",
+                    applied_migration.filename
+                )));
+            }
+            Err(e) => {
+                tally(Tally::Failed, 1);
+                error!("❌ Failed to rollback migration {}: {}", 
+                       applied_migration.filename, e);
+                return Err(RollbackError::Connection(e));
             }
         }
     }
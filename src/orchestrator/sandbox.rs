@@ -0,0 +1,261 @@
+use crate::dialects;
+use crate::executor::{ConnectionSettings, QueryResult};
+use crate::logger::SUMMARY_TARGET;
+use crate::orchestrator::apply::{run_apply, ApplyError, ApplyOptions};
+use crate::orchestrator::console::{sql_console, ConsoleError};
+use crate::orchestrator::guard::ProtectionGuard;
+use crate::orchestrator::lock::LockPolicy;
+use crate::orchestrator::migration_loader::LoaderOptions;
+use crate::tracker::{Target, TrackerSql};
+use log::{debug, info, warn};
+use std::fs;
+use std::io::{self, BufRead};
+use std::path::PathBuf;
+
+/// ODBC driver used for SQLite sandboxes
+const SQLITE_DRIVER: &str = "SQLite3";
+
+/// Database engine backing `deriddl sandbox`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SandboxEngine {
+    /// Scratch SQLite file in the temp directory
+    Sqlite,
+    /// Disposable Postgres container (requires the `sandbox-postgres` feature and Docker)
+    Postgres,
+}
+
+#[derive(Debug, Clone)]
+pub struct SandboxOptions {
+    pub engine: SandboxEngine,
+    /// Print the connection string and wait instead of opening the SQL prompt
+    pub print_conn: bool,
+    /// Leave the SQLite file behind on exit
+    pub keep: bool,
+}
+
+/// A scratch database that is removed when dropped unless `keep` is set
+pub(crate) struct Sandbox {
+    pub(crate) connection_string: String,
+    pub(crate) engine: SandboxEngine,
+    sqlite_file: Option<PathBuf>,
+    keep: bool,
+    #[cfg(feature = "sandbox-postgres")]
+    _container: Option<
+        testcontainers_modules::testcontainers::Container<testcontainers_modules::postgres::Postgres>,
+    >,
+}
+
+impl Sandbox {
+    pub(crate) fn start(options: &SandboxOptions) -> Result<Self, SandboxError> {
+        match options.engine {
+            SandboxEngine::Sqlite => {
+                let file = std::env::temp_dir().join(format!("deriddl-sandbox-{}.db", std::process::id()));
+                // A leftover file from a crashed run with the same pid would hold old migrations
+                let _ = fs::remove_file(&file);
+                Ok(Self {
+                    connection_string: format!("Driver={{{}}};Database={};", SQLITE_DRIVER, file.display()),
+                    engine: SandboxEngine::Sqlite,
+                    sqlite_file: Some(file),
+                    keep: options.keep,
+                    #[cfg(feature = "sandbox-postgres")]
+                    _container: None,
+                })
+            }
+            SandboxEngine::Postgres => Self::start_postgres(options),
+        }
+    }
+
+    #[cfg(feature = "sandbox-postgres")]
+    fn start_postgres(options: &SandboxOptions) -> Result<Self, SandboxError> {
+        use testcontainers_modules::postgres::Postgres;
+        use testcontainers_modules::testcontainers::runners::SyncRunner;
+        use testcontainers_modules::testcontainers::ImageExt;
+
+        info!("Starting Postgres container");
+        if options.keep {
+            warn!("--keep only applies to SQLite sandboxes; the container is removed on exit");
+        }
+        let container = Postgres::default()
+            .with_container_name(format!("deriddl-sandbox-{}", std::process::id()))
+            .start()
+            .map_err(|e| SandboxError::Start(e.to_string()))?;
+        let host = container.get_host().map_err(|e| SandboxError::Start(e.to_string()))?;
+        let port = container
+            .get_host_port_ipv4(5432)
+            .map_err(|e| SandboxError::Start(e.to_string()))?;
+
+        Ok(Self {
+            connection_string: format!(
+                "Driver={{PostgreSQL Unicode}};Server={};Port={};Database=postgres;Uid=postgres;Pwd=postgres;",
+                host, port
+            ),
+            engine: SandboxEngine::Postgres,
+            sqlite_file: None,
+            keep: options.keep,
+            _container: Some(container),
+        })
+    }
+
+    #[cfg(not(feature = "sandbox-postgres"))]
+    fn start_postgres(_options: &SandboxOptions) -> Result<Self, SandboxError> {
+        Err(SandboxError::FeatureDisabled)
+    }
+
+    /// The sandbox as a database of `run`: same run id and statement timeout, but the
+    /// engine's dialect and none of the configured target's session setup
+    pub(crate) fn target(&self, run: &ConnectionSettings) -> Target {
+        let sql = TrackerSql::for_dialect(Some(self.engine.dialect()));
+        let connection = ConnectionSettings {
+            statement_timeout: run.statement_timeout,
+            statement_splitting: sql.dialect().statement_splitting(),
+            run_id: run.run_id.clone(),
+            ..ConnectionSettings::default()
+        };
+        Target::new(&self.connection_string).with_connection(connection).with_sql(sql)
+    }
+}
+
+impl SandboxEngine {
+    /// Dialect matching the engine, for catalog queries against the sandbox
+    pub(crate) fn dialect(self) -> &'static str {
+        match self {
+            SandboxEngine::Sqlite => "sqlite",
+            SandboxEngine::Postgres => "postgres",
+        }
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let Some(file) = &self.sqlite_file else {
+            return;
+        };
+        if self.keep {
+            info!("Keeping sandbox database at {}", file.display());
+        } else if let Err(e) = fs::remove_file(file) {
+            warn!("Failed to remove sandbox database {}: {}", file.display(), e);
+        } else {
+            debug!("Removed sandbox database {}", file.display());
+        }
+    }
+}
+
+/// Creates a scratch database, applies every migration to it, then opens a SQL prompt
+/// (or prints the connection string) and removes the database on exit
+pub fn run_sandbox(
+    path: &str,
+    loader: &LoaderOptions,
+    environment: Option<&str>,
+    options: &SandboxOptions,
+    run: &ConnectionSettings,
+) -> Result<(), SandboxError> {
+    info!("Starting migration sandbox");
+    let sandbox = Sandbox::start(options)?;
+    let target = sandbox.target(run);
+
+    let guard = ProtectionGuard {
+        non_interactive: true,
+        ..Default::default()
+    };
+    let lock = LockPolicy::default();
+    run_apply(&target, path, &ApplyOptions { environment, ..ApplyOptions::new(loader, &guard, &lock) })?;
+
+    if options.print_conn {
+        info!(target: SUMMARY_TARGET, "🧪 Sandbox ready");
+        println!("{}", target.conn);
+        eprint!("Press Enter to stop the sandbox... ");
+        let mut line = String::new();
+        io::stdin().lock().read_line(&mut line).map_err(|e| SandboxError::Io(e.to_string()))?;
+    } else {
+        info!(target: SUMMARY_TARGET, "🧪 Sandbox ready; enter SQL terminated by ';', Tab completes table names, \\q quits");
+        let dialect = dialects::get_dialect_with_config(Some(sandbox.engine.dialect()), None, None)
+            .map_err(|e| SandboxError::Console(ConsoleError::Dialect(e.to_string())))?;
+        sql_console(&target, dialect, "sandbox")?;
+    }
+    Ok(())
+}
+
+/// Renders a result set as an aligned text table followed by the row count
+pub(crate) fn format_table(result: &QueryResult) -> String {
+    let mut widths: Vec<usize> = result.columns.iter().map(|c| c.chars().count()).collect();
+    for row in &result.rows {
+        for (i, value) in row.iter().enumerate() {
+            if let Some(width) = widths.get_mut(i) {
+                *width = (*width).max(value.chars().count());
+            }
+        }
+    }
+
+    let line = |values: &[String]| {
+        values
+            .iter()
+            .zip(&widths)
+            .map(|(value, width)| format!("{:<width$}", value, width = *width))
+            .collect::<Vec<_>>()
+            .join(" | ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut out = line(&result.columns) + "\n";
+    out.push_str(&widths.iter().map(|w| "-".repeat(*w)).collect::<Vec<_>>().join("-+-"));
+    out.push('\n');
+    for row in &result.rows {
+        out.push_str(&line(row));
+        out.push('\n');
+    }
+    out.push_str(&format!("({} row{})\n", result.rows.len(), if result.rows.len() == 1 { "" } else { "s" }));
+    out
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SandboxError {
+    #[error("Postgres sandboxes need deriddl built with the 'sandbox-postgres' feature")]
+    FeatureDisabled,
+
+    #[cfg(feature = "sandbox-postgres")]
+    #[error("Failed to start sandbox database: {0}")]
+    Start(String),
+
+    #[error("Failed to apply migrations to the sandbox: {0}")]
+    Apply(#[from] ApplyError),
+
+    #[error("{0}")]
+    Console(#[from] ConsoleError),
+
+    #[error("I/O error: {0}")]
+    Io(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_table_aligns_columns() {
+        let result = QueryResult {
+            columns: vec!["id".to_string(), "email".to_string()],
+            rows: vec![vec!["1".to_string(), "a@example.com".to_string()]],
+        };
+        assert_eq!(
+            format_table(&result),
+            "id | email\n---+--------------\n1  | a@example.com\n(1 row)\n"
+        );
+    }
+
+    #[test]
+    fn test_sqlite_sandbox_file_is_removed_on_drop() {
+        let options = SandboxOptions {
+            engine: SandboxEngine::Sqlite,
+            print_conn: false,
+            keep: false,
+        };
+        let sandbox = Sandbox::start(&options).unwrap();
+        let file = sandbox.sqlite_file.clone().unwrap();
+        fs::write(&file, b"").unwrap();
+        assert!(sandbox.connection_string.contains(&file.display().to_string()));
+
+        drop(sandbox);
+        assert!(!file.exists());
+    }
+}
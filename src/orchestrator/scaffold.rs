@@ -0,0 +1,253 @@
+use crate::model::run::random_uuid;
+use crate::orchestrator::migration_loader::LoaderOptions;
+use crate::orchestrator::prompt::{Prompt, PromptError};
+use crate::orchestrator::MigrationLoader;
+use handlebars::{handlebars_helper, no_escape, Handlebars, RenderErrorReason};
+use log::info;
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Templates shipped with deriddl, used when the templates directory has none of that name
+const BUILTIN_TEMPLATES: &[(&str, &str)] = &[
+    ("create_table", include_str!("../../templates/create_table.sql")),
+    ("add_column", include_str!("../../templates/add_column.sql")),
+    ("add_index_concurrently", include_str!("../../templates/add_index_concurrently.sql")),
+    ("backfill", include_str!("../../templates/backfill.sql")),
+];
+
+/// Extensions of template files in the templates directory, in order of precedence
+const TEMPLATE_EXTENSIONS: &[&str] = &["sql.hbs", "sql"];
+
+/// Variables filled in without asking
+const NAME_VARIABLE: &str = "name";
+const VERSION_VARIABLE: &str = "version";
+const DIALECT_VARIABLE: &str = "dialect";
+
+handlebars_helper!(upper: |value: str| value.to_uppercase());
+handlebars_helper!(lower: |value: str| value.to_lowercase());
+
+/// Body of a migration created without a template
+const EMPTY_MIGRATION: &str = "-- +migrate Up\n\n-- +migrate Down\n";
+
+/// What `new` creates
+pub struct NewMigration<'a> {
+    /// Directory the file is written to
+    pub path: &'a str,
+    /// Description, turned into the snake_case part of the file name
+    pub name: &'a str,
+    pub template: Option<&'a str>,
+    pub templates_dir: &'a Path,
+    /// Variable values given up front (`--set table=users`); the rest are asked for
+    pub vars: &'a [(String, String)],
+    /// Configured dialect, available to templates as `{{dialect}}`
+    pub dialect: &'a str,
+}
+
+/// Creates `<next version>_<name>.sql` in the migrations directory, from a template when
+/// one is given, and returns its path. Templates are rendered with handlebars; variables
+/// without a value are asked for.
+pub fn run_new(new: &NewMigration, loader: &LoaderOptions, prompt: &dyn Prompt) -> Result<PathBuf, ScaffoldError> {
+    let name = snake_case(new.name);
+    if name.is_empty() {
+        return Err(ScaffoldError::InvalidName(new.name.to_string()));
+    }
+    let template = match new.template {
+        Some(template) => load_template(template, new.templates_dir)?,
+        None => EMPTY_MIGRATION.to_string(),
+    };
+
+    fs::create_dir_all(new.path).map_err(|e| ScaffoldError::Io(new.path.to_string(), e.to_string()))?;
+    let mut loader = loader.clone();
+    loader.sources.clear();
+    let version = MigrationLoader::load_migrations_with_options(new.path, &loader)
+        .map_err(|e| ScaffoldError::Io(new.path.to_string(), e.to_string()))?
+        .iter()
+        .filter_map(|m| m.version)
+        .max()
+        .unwrap_or(0)
+        + 1;
+
+    let mut values = BTreeMap::from([
+        (NAME_VARIABLE.to_string(), name.clone()),
+        (VERSION_VARIABLE.to_string(), format!("{:04}", version)),
+        (DIALECT_VARIABLE.to_string(), new.dialect.to_string()),
+    ]);
+    values.extend(new.vars.iter().cloned());
+    let mut content = render(new.template.unwrap_or("migration"), &template, &mut values, prompt)?;
+    // The uuid id strategy identifies migrations by a header only `new` can pick
+    if loader.id_strategy.name() == "uuid" {
+        content = format!("-- deriddl:id {}\n{}", random_uuid(), content);
+    }
+
+    let file = Path::new(new.path).join(format!("{:04}_{}.sql", version, name));
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&file)
+        .and_then(|mut f| f.write_all(content.as_bytes()))
+        .map_err(|e| ScaffoldError::Io(file.display().to_string(), e.to_string()))?;
+    info!("📝 Created {}", file.display());
+    Ok(file)
+}
+
+/// Names of the available templates: the templates directory's and the built-in ones
+pub fn template_names(templates_dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = BUILTIN_TEMPLATES.iter().map(|(name, _)| name.to_string()).collect();
+    if let Ok(entries) = fs::read_dir(templates_dir) {
+        names.extend(entries.filter_map(|entry| {
+            let file_name = entry.ok()?.file_name().to_string_lossy().to_string();
+            TEMPLATE_EXTENSIONS
+                .iter()
+                .find_map(|ext| file_name.strip_suffix(&format!(".{}", ext)).map(str::to_string))
+        }));
+    }
+    names.sort();
+    names.dedup();
+    names
+}
+
+fn load_template(name: &str, templates_dir: &Path) -> Result<String, ScaffoldError> {
+    for ext in TEMPLATE_EXTENSIONS {
+        let file = templates_dir.join(format!("{}.{}", name, ext));
+        if file.is_file() {
+            return fs::read_to_string(&file).map_err(|e| ScaffoldError::Io(file.display().to_string(), e.to_string()));
+        }
+    }
+    BUILTIN_TEMPLATES
+        .iter()
+        .find(|(builtin, _)| *builtin == name)
+        .map(|(_, content)| content.to_string())
+        .ok_or_else(|| ScaffoldError::UnknownTemplate(name.to_string(), template_names(templates_dir).join(", ")))
+}
+
+/// Renders `template` in strict mode, so every variable it uses needs a value: each
+/// missing one is asked for and rendering starts over. Output is not HTML-escaped.
+fn render(
+    name: &str,
+    template: &str,
+    values: &mut BTreeMap<String, String>,
+    prompt: &dyn Prompt,
+) -> Result<String, ScaffoldError> {
+    let mut registry = Handlebars::new();
+    registry.set_strict_mode(true);
+    registry.register_escape_fn(no_escape);
+    registry.register_helper("upper", Box::new(upper));
+    registry.register_helper("lower", Box::new(lower));
+    registry
+        .register_template_string(name, template)
+        .map_err(|e| ScaffoldError::Template(name.to_string(), e.to_string()))?;
+    loop {
+        match registry.render(name, values) {
+            Ok(content) => return Ok(content),
+            Err(e) => match e.reason() {
+                RenderErrorReason::MissingVariable(Some(variable)) if !values.contains_key(variable) => {
+                    let value = prompt.ask(variable)?;
+                    values.insert(variable.clone(), value);
+                }
+                _ => return Err(ScaffoldError::Template(name.to_string(), e.to_string())),
+            },
+        }
+    }
+}
+
+fn snake_case(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScaffoldError {
+    #[error("Invalid migration name '{0}': use letters, digits and underscores")]
+    InvalidName(String),
+
+    #[error("Unknown template '{0}'; available: {1}")]
+    UnknownTemplate(String, String),
+
+    #[error("Failed to render template '{0}': {1}")]
+    Template(String, String),
+
+    #[error("{0}")]
+    Prompt(#[from] PromptError),
+
+    #[error("Failed to write {0}: {1}")]
+    Io(String, String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orchestrator::prompt::NonInteractivePrompt;
+
+    #[test]
+    fn test_new_migration_from_template() {
+        let dir = tempfile::tempdir().unwrap();
+        let migrations = dir.path().join("migrations");
+        fs::create_dir(&migrations).unwrap();
+        fs::write(migrations.join("0007_users.sql"), "CREATE TABLE users (id INT);").unwrap();
+        let vars = [
+            ("table".to_string(), "users".to_string()),
+            ("index".to_string(), "idx_users_email".to_string()),
+            ("columns".to_string(), "email".to_string()),
+        ];
+        let new = NewMigration {
+            path: migrations.to_str().unwrap(),
+            name: "Add index",
+            template: Some("add_index_concurrently"),
+            templates_dir: &dir.path().join("templates"),
+            vars: &vars,
+            dialect: "postgres",
+        };
+        let prompt = NonInteractivePrompt { assume_yes: true };
+
+        let file = run_new(&new, &LoaderOptions::default(), &prompt).unwrap();
+        assert_eq!(file.file_name().unwrap(), "0008_add_index.sql");
+        let loaded = MigrationLoader::load_migrations(migrations.to_str().unwrap()).unwrap();
+        let created = loaded.iter().find(|m| m.version == Some(8)).unwrap();
+        assert!(created.no_transaction);
+        assert_eq!(created.sql_content, "CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_users_email ON users (email);");
+        assert_eq!(created.get_rollback_sql(), Some("DROP INDEX CONCURRENTLY IF EXISTS idx_users_email;"));
+
+        // A value that isn't given needs a terminal to ask for it
+        let new = NewMigration { template: Some("add_column"), vars: &vars[..1], ..new };
+        assert!(matches!(run_new(&new, &LoaderOptions::default(), &prompt), Err(ScaffoldError::Prompt(_))));
+        assert!(matches!(
+            run_new(&NewMigration { template: Some("drop_everything"), ..new }, &LoaderOptions::default(), &prompt),
+            Err(ScaffoldError::UnknownTemplate(..))
+        ));
+    }
+
+    #[test]
+    fn test_user_handlebars_template() {
+        let dir = tempfile::tempdir().unwrap();
+        let templates = dir.path().join("templates");
+        fs::create_dir(&templates).unwrap();
+        fs::write(
+            templates.join("audited_table.sql.hbs"),
+            "-- +migrate Up\nCREATE TABLE {{table}} (id BIGINT);\n-- +migrate Down\nDROP TABLE {{table}};\n",
+        )
+        .unwrap();
+        assert_eq!(
+            template_names(&templates),
+            vec!["add_column", "add_index_concurrently", "audited_table", "backfill", "create_table"]
+        );
+
+        let mut values = BTreeMap::from([
+            ("table".to_string(), "orders".to_string()),
+            ("owner".to_string(), "billing".to_string()),
+            ("dialect".to_string(), "postgres".to_string()),
+        ]);
+        let template = "CREATE TABLE {{table}} ({{#if (eq dialect \"postgres\")}}created_at TIMESTAMPTZ{{/if}}); -- {{upper owner}} <&>";
+        let prompt = NonInteractivePrompt { assume_yes: true };
+        assert_eq!(
+            render("audited_table", template, &mut values, &prompt).unwrap(),
+            "CREATE TABLE orders (created_at TIMESTAMPTZ); -- BILLING <&>"
+        );
+        values.remove("table");
+        assert!(matches!(render("audited_table", template, &mut values, &prompt), Err(ScaffoldError::Prompt(_))));
+    }
+}
@@ -0,0 +1,313 @@
+use crate::dialects;
+use crate::executor::introspect::{ForeignKey, TableInfo};
+use crate::executor::{introspect_schema, ConnectionError, SchemaModel};
+use crate::tracker::Target;
+use crate::logger::SUMMARY_TARGET;
+use log::{debug, info};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+/// Output format of `deriddl docs`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum DocsFormat {
+    /// Mermaid `erDiagram`
+    Mermaid,
+    /// Graphviz digraph with one record per table
+    Dot,
+    /// Markdown with an embedded Mermaid diagram and a column table per table
+    Markdown,
+}
+
+/// Introspects the database and writes schema documentation to `output`, or stdout
+pub fn run_docs(
+    target: &Target,
+    config_dialect: Option<&str>,
+    format: DocsFormat,
+    output: Option<&Path>,
+) -> Result<(), DocsError> {
+    info!("Generating schema documentation");
+    let dialect = dialects::get_dialect_with_config(None, Some(&target.conn), config_dialect)
+        .map_err(|e| DocsError::Dialect(e.to_string()))?;
+    debug!("Using dialect {} for schema introspection", dialect.name());
+
+    let model = introspect_schema(&target.connection_manager()?, &target.conn, dialect.as_ref())?;
+    let rendered = render(&model, format);
+
+    match output {
+        Some(path) => {
+            fs::write(path, rendered).map_err(|e| DocsError::Io(path.display().to_string(), e.to_string()))?;
+            info!(
+                target: SUMMARY_TARGET,
+                "📝 Documented {} tables and {} relationships in {}",
+                model.tables.len(),
+                model.foreign_keys.len(),
+                path.display()
+            );
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+pub fn render(model: &SchemaModel, format: DocsFormat) -> String {
+    match format {
+        DocsFormat::Mermaid => render_mermaid(model),
+        DocsFormat::Dot => render_dot(model),
+        DocsFormat::Markdown => render_markdown(model),
+    }
+}
+
+/// Tables are named without their schema unless the database has several
+struct Naming {
+    qualified: bool,
+}
+
+impl Naming {
+    fn new(model: &SchemaModel) -> Self {
+        let first = model.tables.first().map(|t| t.schema.as_str());
+        Self {
+            qualified: model.tables.iter().any(|t| Some(t.schema.as_str()) != first),
+        }
+    }
+
+    fn name(&self, schema: &str, table: &str) -> String {
+        if self.qualified {
+            format!("{}.{}", schema, table)
+        } else {
+            table.to_string()
+        }
+    }
+
+    fn table(&self, table: &TableInfo) -> String {
+        self.name(&table.schema, &table.name)
+    }
+}
+
+fn render_mermaid(model: &SchemaModel) -> String {
+    let naming = Naming::new(model);
+    let mut out = String::from("erDiagram\n");
+
+    for table in &model.tables {
+        let _ = writeln!(out, "    {} {{", mermaid_id(&naming.table(table)));
+        for column in &table.columns {
+            let key = if column.primary_key {
+                " PK"
+            } else if is_foreign_key_column(model, table, &column.name) {
+                " FK"
+            } else {
+                ""
+            };
+            let _ = writeln!(
+                out,
+                "        {} {}{}",
+                mermaid_id(&column.data_type),
+                mermaid_id(&column.name),
+                key
+            );
+        }
+        out.push_str("    }\n");
+    }
+
+    for key in &model.foreign_keys {
+        // A nullable foreign key means the referenced row is optional
+        let optional = model
+            .tables
+            .iter()
+            .find(|t| t.schema == key.schema && t.name == key.table)
+            .is_some_and(|t| {
+                t.columns
+                    .iter()
+                    .any(|c| c.nullable && key.columns.contains(&c.name))
+            });
+        let _ = writeln!(
+            out,
+            "    {} }}o--{} {} : \"{}\"",
+            mermaid_id(&naming.name(&key.schema, &key.table)),
+            if optional { "o|" } else { "||" },
+            mermaid_id(&naming.name(&key.referenced_schema, &key.referenced_table)),
+            key.columns.join(", ").replace('"', "'")
+        );
+    }
+    out
+}
+
+fn render_dot(model: &SchemaModel) -> String {
+    let naming = Naming::new(model);
+    let mut out = String::from(
+        "digraph schema {\n    rankdir=LR;\n    node [shape=plaintext, fontname=\"Helvetica\"];\n",
+    );
+
+    for table in &model.tables {
+        let name = naming.table(table);
+        let _ = write!(
+            out,
+            "    \"{}\" [label=<<table border=\"0\" cellborder=\"1\" cellspacing=\"0\"><tr><td bgcolor=\"lightgrey\"><b>{}</b></td></tr>",
+            dot_escape(&name),
+            html_escape(&name)
+        );
+        for (index, column) in table.columns.iter().enumerate() {
+            let _ = write!(
+                out,
+                "<tr><td port=\"c{}\" align=\"left\">{}{}: {}{}</td></tr>",
+                index,
+                if column.primary_key { "<u>" } else { "" },
+                html_escape(&column.name),
+                html_escape(&column.data_type),
+                if column.primary_key { "</u>" } else { "" }
+            );
+        }
+        out.push_str("</table>>];\n");
+    }
+
+    for key in &model.foreign_keys {
+        let from = naming.name(&key.schema, &key.table);
+        let to = naming.name(&key.referenced_schema, &key.referenced_table);
+        let from_port = column_port(model, &key.schema, &key.table, key.columns.first());
+        let to_port = column_port(model, &key.referenced_schema, &key.referenced_table, key.referenced_columns.first());
+        let _ = writeln!(
+            out,
+            "    \"{}\"{} -> \"{}\"{} [label=\"{}\"];",
+            dot_escape(&from),
+            from_port,
+            dot_escape(&to),
+            to_port,
+            dot_escape(&key.name)
+        );
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn render_markdown(model: &SchemaModel) -> String {
+    let naming = Naming::new(model);
+    let mut out = String::from("# Database schema\n\n");
+    out.push_str("<!-- Generated by `deriddl docs`; do not edit by hand -->\n\n");
+    out.push_str("```mermaid\n");
+    out.push_str(&render_mermaid(model));
+    out.push_str("```\n");
+
+    for table in &model.tables {
+        let _ = writeln!(out, "\n## {}\n", naming.table(table));
+        out.push_str("| Column | Type | Nullable | Key |\n|---|---|---|---|\n");
+        for column in &table.columns {
+            let mut keys = Vec::new();
+            if column.primary_key {
+                keys.push("PK".to_string());
+            }
+            for key in referencing_keys(model, table, &column.name) {
+                let position = key.columns.iter().position(|c| c == &column.name).unwrap_or(0);
+                keys.push(format!(
+                    "FK → {}.{}",
+                    naming.name(&key.referenced_schema, &key.referenced_table),
+                    key.referenced_columns.get(position).map(String::as_str).unwrap_or("?")
+                ));
+            }
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} |",
+                markdown_escape(&column.name),
+                markdown_escape(&column.data_type),
+                if column.nullable { "yes" } else { "no" },
+                markdown_escape(&keys.join(", "))
+            );
+        }
+    }
+    out
+}
+
+fn referencing_keys<'a>(model: &'a SchemaModel, table: &'a TableInfo, column: &'a str) -> impl Iterator<Item = &'a ForeignKey> {
+    model.foreign_keys.iter().filter(move |key| {
+        key.schema == table.schema && key.table == table.name && key.columns.iter().any(|c| c == column)
+    })
+}
+
+fn is_foreign_key_column(model: &SchemaModel, table: &TableInfo, column: &str) -> bool {
+    referencing_keys(model, table, column).next().is_some()
+}
+
+fn column_port(model: &SchemaModel, schema: &str, table: &str, column: Option<&String>) -> String {
+    model
+        .tables
+        .iter()
+        .find(|t| t.schema == schema && t.name == table)
+        .and_then(|t| t.columns.iter().position(|c| Some(&c.name) == column))
+        .map(|index| format!(":c{}", index))
+        .unwrap_or_default()
+}
+
+/// Mermaid identifiers allow letters, digits, `_`, `-` and brackets; anything else becomes `_`
+fn mermaid_id(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            c if c.is_ascii_alphanumeric() => c,
+            '-' | '_' | '(' | ')' | '[' | ']' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+fn dot_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn markdown_escape(value: &str) -> String {
+    value.replace('|', "\\|")
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DocsError {
+    #[error("Connection error: {0}")]
+    Connection(#[from] ConnectionError),
+
+    #[error("Dialect error: {0}")]
+    Dialect(String),
+
+    #[error("Failed to write {0}: {1}")]
+    Io(String, String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model() -> SchemaModel {
+        let row = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        SchemaModel::from_rows(
+            &[
+                row(&["public", "orders", "id", "integer", "NO", "1"]),
+                row(&["public", "orders", "user_id", "integer", "YES", "0"]),
+                row(&["public", "users", "id", "integer", "NO", "1"]),
+                row(&["public", "users", "email", "character varying", "NO", "0"]),
+            ],
+            &[row(&["orders_user_fk", "public", "orders", "user_id", "public", "users", "id"])],
+        )
+    }
+
+    #[test]
+    fn test_renders_each_format() {
+        let model = model();
+
+        let mermaid = render(&model, DocsFormat::Mermaid);
+        assert!(mermaid.starts_with("erDiagram\n"));
+        assert!(mermaid.contains("        character_varying email\n"));
+        assert!(mermaid.contains("        integer user_id FK\n"));
+        assert!(mermaid.contains("    orders }o--o| users : \"user_id\"\n"));
+
+        let dot = render(&model, DocsFormat::Dot);
+        assert!(dot.contains("\"orders\":c1 -> \"users\":c0 [label=\"orders_user_fk\"];"));
+
+        let markdown = render(&model, DocsFormat::Markdown);
+        assert!(markdown.contains("## users"));
+        assert!(markdown.contains("| user_id | integer | yes | FK → users.id |"));
+    }
+}
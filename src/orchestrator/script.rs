@@ -0,0 +1,105 @@
+use crate::dialects::DatabaseDialect;
+use crate::model::Migration;
+use crate::orchestrator::masking::expand_masks;
+use crate::tracker::TrackerSql;
+use chrono::Utc;
+use std::borrow::Cow;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Where and for which dialect `apply --script-only` writes its script
+#[derive(Debug, Clone)]
+pub struct ScriptOptions<'a> {
+    pub output: &'a Path,
+    pub dialect: Option<&'a str>,
+}
+
+/// Renders the SQL `apply` would run for the given migrations: optional schema_migrations
+/// creation, the bookkeeping INSERT/UPDATE around each migration, and transaction wrappers
+/// when the dialect supports them. Streamed migrations are read from disk.
+pub fn render_apply_script(
+    migrations: &[Migration],
+    dialect: &Arc<dyn DatabaseDialect>,
+    create_migrations_table: bool,
+) -> io::Result<String> {
+    let tracker = TrackerSql::new(dialect.clone());
+    let transactional = dialect.config().features.supports_transactions;
+    let (begin, commit) = if dialect.name().eq_ignore_ascii_case("mysql") {
+        ("START TRANSACTION;", "COMMIT;")
+    } else {
+        ("BEGIN TRANSACTION;", "COMMIT;")
+    };
+
+    let mut script = String::new();
+    script.push_str("-- Generated by deriddl apply --script-only\n");
+    script.push_str(&format!("-- Generated at: {}\n", Utc::now().format("%Y-%m-%dT%H:%M:%SZ")));
+    script.push_str(&format!("-- Dialect: {}\n", dialect.name()));
+    script.push_str(&format!("-- Migrations: {}\n\n", migrations.len()));
+
+    if create_migrations_table {
+        script.push_str("-- Create migration tracking table\n");
+        script.push_str(&terminated(&dialect.create_migrations_table_sql()));
+        for index_sql in dialect.create_migrations_indexes_sql() {
+            script.push_str(&terminated(&index_sql));
+        }
+        script.push('\n');
+    }
+
+    for migration in migrations {
+        script.push_str(&format!(
+            "-- ============================================================\n-- {} ({})\n-- ============================================================\n",
+            migration.filename(),
+            migration.identifier()
+        ));
+        for statement in tracker.migration_start(migration) {
+            script.push_str(&terminated(&statement));
+        }
+        if transactional {
+            script.push_str(begin);
+            script.push('\n');
+        }
+        let up_sql = migration.up_sql()?;
+        let up_sql = if migration.raw {
+            Cow::Borrowed(up_sql.as_ref())
+        } else {
+            expand_masks(&up_sql, Some(dialect.as_ref()))
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", migration.filename(), e)))?
+        };
+        script.push_str(&terminated(&up_sql));
+        if transactional {
+            script.push_str(commit);
+            script.push('\n');
+        }
+        script.push_str(&terminated(&tracker.migration_success(migration, 0)));
+        script.push('\n');
+    }
+
+    Ok(script)
+}
+
+pub fn write_apply_script(
+    options: &ScriptOptions,
+    migrations: &[Migration],
+    dialect: &Arc<dyn DatabaseDialect>,
+    create_migrations_table: bool,
+) -> io::Result<()> {
+    if let Some(parent) = options.output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(
+        options.output,
+        render_apply_script(migrations, dialect, create_migrations_table)?,
+    )
+}
+
+/// Ensures a SQL block ends with a semicolon and newline
+fn terminated(sql: &str) -> String {
+    let sql = sql.trim();
+    if sql.ends_with(';') {
+        format!("{}\n", sql)
+    } else {
+        format!("{};\n", sql)
+    }
+}
@@ -0,0 +1,298 @@
+use crate::orchestrator::approval::decode_hex;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use log::{debug, info};
+use semver::Version;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Hex ed25519 key the release binaries are signed with, embedded when the release is
+/// built (`DERIDDL_RELEASE_PUBLIC_KEY`). `--public-key` overrides it.
+const RELEASE_PUBLIC_KEY: Option<&str> = option_env!("DERIDDL_RELEASE_PUBLIC_KEY");
+
+/// Largest download accepted, so a broken mirror cannot fill the disk
+const MAX_DOWNLOAD_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Which releases `self-update` considers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReleaseChannel {
+    /// Published releases only
+    Stable,
+    /// Pre-releases too, e.g. 0.7.0-rc.1
+    Prerelease,
+}
+
+/// A GitHub release, as listed by the releases API
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub draft: bool,
+    #[serde(default)]
+    pub prerelease: bool,
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+}
+
+impl Release {
+    /// The tag as a version; tags may carry a leading `v`
+    pub fn version(&self) -> Option<Version> {
+        Version::parse(self.tag_name.trim_start_matches('v')).ok()
+    }
+
+    fn asset(&self, name: &str) -> Option<&ReleaseAsset> {
+        self.assets.iter().find(|asset| asset.name == name)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+/// Name of the release binary for this platform, e.g. `deriddl-x86_64-linux` or
+/// `deriddl-x86_64-windows.exe`
+pub fn asset_name() -> String {
+    format!("deriddl-{}-{}{}", std::env::consts::ARCH, std::env::consts::OS, std::env::consts::EXE_SUFFIX)
+}
+
+/// The newest release on `channel`; drafts and tags that are not versions are ignored
+pub fn latest_release(releases: &[Release], channel: ReleaseChannel) -> Option<(&Release, Version)> {
+    releases
+        .iter()
+        .filter(|release| !release.draft)
+        .filter(|release| channel == ReleaseChannel::Prerelease || !release.prerelease)
+        .filter_map(|release| release.version().map(|version| (release, version)))
+        .filter(|(_, version)| channel == ReleaseChannel::Prerelease || version.pre.is_empty())
+        .max_by(|(_, a), (_, b)| a.cmp(b))
+}
+
+/// The key downloads are verified with: `public_key` when given, else the embedded release key
+pub fn release_public_key(public_key: Option<&str>) -> Result<&str, SelfUpdateError> {
+    public_key.or(RELEASE_PUBLIC_KEY).filter(|key| !key.trim().is_empty()).ok_or(SelfUpdateError::NoPublicKey)
+}
+
+/// Replaces the running binary with the newest release on `channel`. The download must
+/// match the release's `<asset>.sha256` and its `<asset>.sig` must be an ed25519 signature
+/// by the release key; the binary is left alone otherwise. With `check` only reports
+/// whether an update exists.
+pub fn run_self_update(channel: ReleaseChannel, public_key: Option<&str>, check: bool) -> Result<(), SelfUpdateError> {
+    let running = Version::parse(env!("CARGO_PKG_VERSION")).expect("package version is semver");
+    info!("Checking {} for {} releases", releases_url(), channel_name(channel));
+
+    let listing = fetch(&releases_url())?;
+    let releases: Vec<Release> =
+        serde_json::from_slice(&listing).map_err(|e| SelfUpdateError::InvalidListing(e.to_string()))?;
+    let Some((release, latest)) = latest_release(&releases, channel) else {
+        return Err(SelfUpdateError::NoRelease(channel_name(channel)));
+    };
+    if latest <= running {
+        info!("✅ deriddl {} is up to date (latest {} release: {})", running, channel_name(channel), latest);
+        return Ok(());
+    }
+    if check {
+        info!("⬆️  deriddl {} is available (running {}); run `deriddl self-update` to install it", latest, running);
+        return Ok(());
+    }
+
+    let public_key = release_public_key(public_key)?;
+    let name = asset_name();
+    let binary_asset = release.asset(&name).ok_or_else(|| SelfUpdateError::MissingAsset(name.clone(), release.tag_name.clone()))?;
+    let checksum_name = format!("{}.sha256", name);
+    let checksum_asset =
+        release.asset(&checksum_name).ok_or_else(|| SelfUpdateError::MissingAsset(checksum_name, release.tag_name.clone()))?;
+
+    let installed = download_and_install(release, binary_asset, checksum_asset, public_key)?;
+    info!("✅ Updated deriddl {} to {} at {}", running, latest, installed.display());
+    Ok(())
+}
+
+fn download_and_install(
+    release: &Release,
+    binary_asset: &ReleaseAsset,
+    checksum_asset: &ReleaseAsset,
+    public_key: &str,
+) -> Result<PathBuf, SelfUpdateError> {
+    let sig_name = format!("{}.sig", binary_asset.name);
+    let sig_asset = release.asset(&sig_name).ok_or_else(|| SelfUpdateError::MissingAsset(sig_name, release.tag_name.clone()))?;
+
+    info!("Downloading {} from release {}", binary_asset.name, release.tag_name);
+    let binary = fetch(&binary_asset.browser_download_url)?;
+
+    let checksums = fetch(&checksum_asset.browser_download_url)?;
+    verify_checksum(&binary_asset.name, &binary, &String::from_utf8_lossy(&checksums))?;
+    info!("🔒 Checksum of {} verified", binary_asset.name);
+
+    let signature = fetch(&sig_asset.browser_download_url)?;
+    verify_signature(&binary_asset.name, &binary, &String::from_utf8_lossy(&signature), public_key)?;
+    info!("🔏 Signature of {} verified", binary_asset.name);
+
+    let current = std::env::current_exe().map_err(|e| SelfUpdateError::Io("current executable".to_string(), e.to_string()))?;
+    replace_binary(&current, &binary)?;
+    Ok(current)
+}
+
+/// Checks `content` against the first hash in a `sha256sum`-style checksum file
+pub fn verify_checksum(name: &str, content: &[u8], checksums: &str) -> Result<(), SelfUpdateError> {
+    let expected = checksums
+        .split_whitespace()
+        .next()
+        .filter(|hash| hash.len() == 64)
+        .ok_or_else(|| SelfUpdateError::InvalidChecksum(name.to_string(), "no SHA-256 hash in the checksum file".to_string()))?;
+    let actual = hex::encode(Sha256::digest(content));
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(SelfUpdateError::InvalidChecksum(name.to_string(), format!("expected {}, got {}", expected, actual)));
+    }
+    Ok(())
+}
+
+/// Checks a hex-encoded detached ed25519 signature, as written by `deriddl sign`
+pub fn verify_signature(name: &str, content: &[u8], signature: &str, public_key: &str) -> Result<(), SelfUpdateError> {
+    let invalid = |reason: String| SelfUpdateError::InvalidSignature(name.to_string(), reason);
+    let signature = decode_hex::<64>(signature).map_err(|e| invalid(e.to_string()))?;
+    let key = decode_hex::<32>(public_key)
+        .map_err(|e| invalid(e.to_string()))
+        .and_then(|bytes| VerifyingKey::from_bytes(&bytes).map_err(|e| invalid(e.to_string())))?;
+    key.verify(content, &Signature::from_bytes(&signature))
+        .map_err(|_| invalid("not signed by the given key".to_string()))
+}
+
+/// Swaps in the new binary next to the current one. The running file is moved aside
+/// first, which Windows allows for an executable in use, and removed afterwards.
+fn replace_binary(current: &Path, binary: &[u8]) -> Result<(), SelfUpdateError> {
+    let io = |path: &Path, e: std::io::Error| SelfUpdateError::Io(path.display().to_string(), e.to_string());
+    let staged = current.with_extension("new");
+    let previous = current.with_extension("old");
+
+    fs::write(&staged, binary).map_err(|e| io(&staged, e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&staged, fs::Permissions::from_mode(0o755)).map_err(|e| io(&staged, e))?;
+    }
+    fs::rename(current, &previous).map_err(|e| io(current, e))?;
+    if let Err(e) = fs::rename(&staged, current) {
+        // Put the old binary back so the installation keeps working
+        let _ = fs::rename(&previous, current);
+        return Err(io(current, e));
+    }
+    if let Err(e) = fs::remove_file(&previous) {
+        debug!("Could not remove {}: {}", previous.display(), e);
+    }
+    Ok(())
+}
+
+fn releases_url() -> String {
+    let repository = env!("CARGO_PKG_REPOSITORY").trim_end_matches('/').trim_start_matches("https://github.com/");
+    format!("https://api.github.com/repos/{}/releases", repository)
+}
+
+fn channel_name(channel: ReleaseChannel) -> &'static str {
+    match channel {
+        ReleaseChannel::Stable => "stable",
+        ReleaseChannel::Prerelease => "prerelease",
+    }
+}
+
+/// GETs `url`, following redirects; the GitHub API requires a user agent
+fn fetch(url: &str) -> Result<Vec<u8>, SelfUpdateError> {
+    debug!("Fetching {}", url);
+    let failed = |reason: String| SelfUpdateError::Download(url.to_string(), reason);
+    let response = ureq::get(url)
+        .set("Accept", "application/vnd.github+json")
+        .set("User-Agent", concat!("deriddl/", env!("CARGO_PKG_VERSION")))
+        .call()
+        .map_err(|e| failed(e.to_string()))?;
+    let mut body = Vec::new();
+    response
+        .into_reader()
+        .take(MAX_DOWNLOAD_BYTES + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| failed(e.to_string()))?;
+    if body.len() as u64 > MAX_DOWNLOAD_BYTES {
+        return Err(failed(format!("larger than {} bytes", MAX_DOWNLOAD_BYTES)));
+    }
+    Ok(body)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SelfUpdateError {
+    #[error("Failed to download {0}: {1}")]
+    Download(String, String),
+
+    #[error("Unexpected release listing: {0}")]
+    InvalidListing(String),
+
+    #[error("No {0} release found")]
+    NoRelease(&'static str),
+
+    #[error("Release {1} has no asset {0}")]
+    MissingAsset(String, String),
+
+    #[error("Checksum of {0} does not match: {1}")]
+    InvalidChecksum(String, String),
+
+    #[error("No release public key: this build has none embedded, so pass --public-key")]
+    NoPublicKey,
+
+    #[error("Signature of {0} is invalid: {1}")]
+    InvalidSignature(String, String),
+
+    #[error("Failed to access {0}: {1}")]
+    Io(String, String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn release(tag: &str, prerelease: bool, draft: bool) -> Release {
+        Release { tag_name: tag.to_string(), draft, prerelease, assets: Vec::new() }
+    }
+
+    #[test]
+    fn test_latest_release_per_channel() {
+        let releases = vec![
+            release("v0.6.2", false, false),
+            release("v0.7.0-rc.1", true, false),
+            release("v0.8.0", false, true),
+            release("nightly", true, false),
+            release("v0.6.10", false, false),
+        ];
+        let (stable, version) = latest_release(&releases, ReleaseChannel::Stable).unwrap();
+        assert_eq!(stable.tag_name, "v0.6.10");
+        assert_eq!(version, Version::new(0, 6, 10));
+        let (pre, _) = latest_release(&releases, ReleaseChannel::Prerelease).unwrap();
+        assert_eq!(pre.tag_name, "v0.7.0-rc.1");
+        assert!(latest_release(&releases[1..2], ReleaseChannel::Stable).is_none());
+    }
+
+    #[test]
+    fn test_download_must_match_checksum_and_signature() {
+        let binary = b"new deriddl binary";
+        let hash = hex::encode(Sha256::digest(binary));
+        assert!(verify_checksum("deriddl", binary, &format!("{}  deriddl\n", hash)).is_ok());
+        assert!(verify_checksum("deriddl", b"tampered", &format!("{}  deriddl\n", hash)).is_err());
+        assert!(verify_checksum("deriddl", binary, "").is_err());
+
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let signature = hex::encode(key.sign(binary).to_bytes());
+        let public_key = hex::encode(key.verifying_key().to_bytes());
+        assert!(verify_signature("deriddl", binary, &signature, &public_key).is_ok());
+        assert!(verify_signature("deriddl", b"tampered", &signature, &public_key).is_err());
+        let other_key = hex::encode(SigningKey::from_bytes(&[8; 32]).verifying_key().to_bytes());
+        assert!(verify_signature("deriddl", binary, &signature, &other_key).is_err());
+        assert!(verify_signature("deriddl", binary, "", &public_key).is_err());
+    }
+
+    #[test]
+    fn test_given_public_key_overrides_the_embedded_one() {
+        assert_eq!(release_public_key(Some("ab12")).unwrap(), "ab12");
+        assert!(release_public_key(Some(" ")).is_err());
+        assert_eq!(release_public_key(None).ok(), RELEASE_PUBLIC_KEY);
+    }
+}
@@ -0,0 +1,181 @@
+use crate::logger::SUMMARY_TARGET;
+use crate::model::Migration;
+use crate::orchestrator::MigrationLoader;
+use crate::orchestrator::migration_loader::LoaderOptions;
+use crate::orchestrator::approval::{decode_hex, read_signing_key, ApprovalError};
+use ed25519_dalek::{Signature, Signer, Verifier, VerifyingKey};
+use log::{debug, info};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// How detached `.sig` files next to migrations are checked before apply executes anything.
+/// A `.sig` holding a hex Ed25519 signature is verified against `trusted_keys`; any other
+/// content is treated as a GPG signature and checked with `gpg --verify` against `gpg_keyring`.
+#[derive(Debug, Clone, Default)]
+pub struct SignaturePolicy {
+    /// Fail when a migration has no `.sig` file
+    pub required: bool,
+    /// Hex-encoded Ed25519 public keys trusted to sign migrations
+    pub trusted_keys: Vec<String>,
+    /// Keyring file used to verify GPG signatures
+    pub gpg_keyring: Option<String>,
+}
+
+/// Path of the detached signature for a migration file, e.g. `0001_init.sql.sig`
+pub fn signature_path(file_path: &Path) -> PathBuf {
+    let mut name = file_path.as_os_str().to_owned();
+    name.push(".sig");
+    PathBuf::from(name)
+}
+
+impl SignaturePolicy {
+    /// Verifies every migration that has a signature, and requires one when `required`
+    /// is set. Returns one problem per migration that failed.
+    pub fn verify_all(&self, migrations: &[Migration]) -> Result<(), SignatureError> {
+        let problems: Vec<String> = migrations
+            .iter()
+            .filter_map(|m| self.verify(&m.file_path).err())
+            .map(|e| e.to_string())
+            .collect();
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(SignatureError::Rejected(problems))
+        }
+    }
+
+    /// Verifies the detached signature of a single migration file
+    pub fn verify(&self, file_path: &Path) -> Result<(), SignatureError> {
+        let sig_path = signature_path(file_path);
+        let display = file_path.display().to_string();
+
+        if !sig_path.exists() {
+            if self.required {
+                return Err(SignatureError::Missing(display));
+            }
+            return Ok(());
+        }
+
+        let signature = fs::read(&sig_path).map_err(|e| SignatureError::Io(display.clone(), e.to_string()))?;
+        let content = fs::read(file_path).map_err(|e| SignatureError::Io(display.clone(), e.to_string()))?;
+
+        match std::str::from_utf8(&signature)
+            .ok()
+            .and_then(|s| decode_hex::<64>(s).ok())
+        {
+            Some(signature) => self.verify_ed25519(&display, &content, &signature),
+            None => self.verify_gpg(&display, file_path, &sig_path),
+        }
+    }
+
+    fn verify_ed25519(&self, display: &str, content: &[u8], signature: &[u8; 64]) -> Result<(), SignatureError> {
+        let signature = Signature::from_bytes(signature);
+        for key in &self.trusted_keys {
+            let Ok(key_bytes) = decode_hex::<32>(key) else {
+                continue;
+            };
+            let Ok(key) = VerifyingKey::from_bytes(&key_bytes) else {
+                continue;
+            };
+            if key.verify(content, &signature).is_ok() {
+                debug!("Verified signature of {}", display);
+                return Ok(());
+            }
+        }
+        Err(SignatureError::Invalid(display.to_string()))
+    }
+
+    fn verify_gpg(&self, display: &str, file_path: &Path, sig_path: &Path) -> Result<(), SignatureError> {
+        let keyring = self
+            .gpg_keyring
+            .as_deref()
+            .ok_or_else(|| SignatureError::NoGpgKeyring(display.to_string()))?;
+
+        let output = Command::new("gpg")
+            .args(["--batch", "--no-default-keyring", "--keyring", keyring, "--verify"])
+            .arg(sig_path)
+            .arg(file_path)
+            .output()
+            .map_err(|e| SignatureError::Io(display.to_string(), format!("failed to run gpg: {}", e)))?;
+
+        if output.status.success() {
+            debug!("Verified GPG signature of {}", display);
+            Ok(())
+        } else {
+            Err(SignatureError::Invalid(display.to_string()))
+        }
+    }
+}
+
+/// Writes an Ed25519 `.sig` file next to each migration, signed with the key in `key_path`
+pub fn run_sign(path: &str, loader: &LoaderOptions, key_path: &Path) -> Result<(), SignatureError> {
+    let migrations = MigrationLoader::load_migrations_with_options(path, loader)
+        .map_err(|e| SignatureError::Io(path.to_string(), e.to_string()))?;
+    let key = read_signing_key(key_path)?;
+
+    for migration in &migrations {
+        let display = migration.file_path.display().to_string();
+        let content = fs::read(&migration.file_path)
+            .map_err(|e| SignatureError::Io(display.clone(), e.to_string()))?;
+        let signature = key.sign(&content);
+        fs::write(signature_path(&migration.file_path), hex::encode(signature.to_bytes()) + "\n")
+            .map_err(|e| SignatureError::Io(display.clone(), e.to_string()))?;
+        info!("🔏 Signed {}", migration.filename());
+    }
+
+    info!(target: SUMMARY_TARGET, "✅ Signed {} migration(s)", migrations.len());
+    info!("Public key: {}", hex::encode(key.verifying_key().to_bytes()));
+    Ok(())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SignatureError {
+    #[error("{0} has no .sig signature file")]
+    Missing(String),
+
+    #[error("{0} is not signed by a trusted key")]
+    Invalid(String),
+
+    #[error("{0} has a GPG signature but no validation.gpg_keyring is configured")]
+    NoGpgKeyring(String),
+
+    #[error("Failed to read {0}: {1}")]
+    Io(String, String),
+
+    #[error("Signature verification failed: {}", .0.join("; "))]
+    Rejected(Vec<String>),
+
+    #[error("{0}")]
+    Key(#[from] ApprovalError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::SigningKey;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_detached_signature_detects_tampering() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("0001_init.sql");
+        fs::write(&file, "CREATE TABLE a (id INT);").unwrap();
+
+        let key = SigningKey::from_bytes(&[3u8; 32]);
+        let policy = SignaturePolicy {
+            required: true,
+            trusted_keys: vec![hex::encode(key.verifying_key().to_bytes())],
+            gpg_keyring: None,
+        };
+        assert!(matches!(policy.verify(&file), Err(SignatureError::Missing(_))));
+
+        let signature = key.sign(b"CREATE TABLE a (id INT);");
+        fs::write(signature_path(&file), hex::encode(signature.to_bytes())).unwrap();
+        assert!(policy.verify(&file).is_ok());
+
+        fs::write(&file, "DROP TABLE a;").unwrap();
+        assert!(matches!(policy.verify(&file), Err(SignatureError::Invalid(_))));
+    }
+}
@@ -0,0 +1,358 @@
+use git2::{FetchOptions, ObjectType, Repository, TreeWalkMode, TreeWalkResult};
+use log::{debug, info};
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Where migration files come from. Every source is made available as a local directory,
+/// so loading, checksumming and validation work the same whatever the origin.
+pub trait SourceProvider {
+    /// Origin as shown in logs, e.g. `git ./repo at v1.4.0`
+    fn describe(&self) -> String;
+
+    /// Makes the files available locally
+    fn fetch(&self) -> Result<FetchedSource, SourceError>;
+}
+
+/// A source's files on local disk. Scratch copies are removed when this is dropped.
+#[derive(Debug)]
+pub struct FetchedSource {
+    root: PathBuf,
+    scratch: Option<PathBuf>,
+}
+
+impl FetchedSource {
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl Drop for FetchedSource {
+    fn drop(&mut self) {
+        if let Some(scratch) = &self.scratch
+            && let Err(e) = fs::remove_dir_all(scratch)
+        {
+            debug!("Could not remove {}: {}", scratch.display(), e);
+        }
+    }
+}
+
+/// Files in a directory on disk, read in place
+#[derive(Debug, Clone)]
+pub struct LocalDirectory {
+    pub root: PathBuf,
+}
+
+impl SourceProvider for LocalDirectory {
+    fn describe(&self) -> String {
+        format!("directory {}", self.root.display())
+    }
+
+    fn fetch(&self) -> Result<FetchedSource, SourceError> {
+        if !self.root.is_dir() {
+            return Err(SourceError::InvalidSpec(format!("{} is not a directory", self.root.display())));
+        }
+        Ok(FetchedSource { root: self.root.clone(), scratch: None })
+    }
+}
+
+/// Files as of a tag, branch or commit of a git repository, read with libgit2 without
+/// touching any checkout. Remote repositories are shallow-fetched, which requires a tag
+/// or branch.
+#[derive(Debug, Clone)]
+pub struct GitRef {
+    pub repository: String,
+    pub reference: String,
+}
+
+impl SourceProvider for GitRef {
+    fn describe(&self) -> String {
+        format!("git {} at {}", self.repository, self.reference)
+    }
+
+    fn fetch(&self) -> Result<FetchedSource, SourceError> {
+        check_reference(&self.reference)?;
+        let scratch = scratch_dir()?;
+        let fetched = FetchedSource { root: scratch.join("files"), scratch: Some(scratch.clone()) };
+
+        let repository = if Path::new(&self.repository).exists() {
+            Repository::open(&self.repository)?
+        } else {
+            shallow_fetch(&self.repository, &self.reference, &scratch.join("repository.git"))?
+        };
+
+        let commit = repository.revparse_single(&self.reference)?.peel_to_commit()?;
+        info!("Resolved {} to commit {}", self.reference, commit.id());
+        write_tree(&repository, &commit.tree()?, fetched.root())?;
+        Ok(fetched)
+    }
+}
+
+/// Fetches only tag or branch `reference` from `url` into a new bare repository; just its
+/// tip, except over `file://` where libgit2 cannot fetch shallowly
+fn shallow_fetch(url: &str, reference: &str, into: &Path) -> Result<Repository, SourceError> {
+    let repository = Repository::init_bare(into)?;
+    let refspecs = [format!("+refs/tags/{0}:refs/tags/{0}", reference), format!("+refs/heads/{0}:refs/heads/{0}", reference)];
+    let mut options = FetchOptions::new();
+    if !url.starts_with("file://") {
+        options.depth(1);
+    }
+    debug!("Fetching {} from {}", reference, url);
+    repository.remote_anonymous(url)?.fetch(&refspecs, Some(&mut options), None)?;
+    Ok(repository)
+}
+
+/// Writes every file of `tree` below `root`. Submodules are left out and symbolic links
+/// are written as files holding their target, as on a checkout without symlink support.
+fn write_tree(repository: &Repository, tree: &git2::Tree, root: &Path) -> Result<(), SourceError> {
+    fs::create_dir_all(root)?;
+    let mut result = Ok(());
+    tree.walk(TreeWalkMode::PreOrder, |dir, entry| {
+        if entry.kind() != Some(ObjectType::Blob) {
+            return TreeWalkResult::Ok;
+        }
+        let Some(name) = entry.name().filter(|name| !matches!(*name, "" | "." | "..")) else {
+            return TreeWalkResult::Ok;
+        };
+        let path = root.join(dir).join(name);
+        result = repository
+            .find_blob(entry.id())
+            .map_err(SourceError::from)
+            .and_then(|blob| {
+                fs::create_dir_all(path.parent().unwrap_or(root))?;
+                fs::write(&path, blob.content())?;
+                Ok(())
+            });
+        if result.is_ok() { TreeWalkResult::Ok } else { TreeWalkResult::Abort }
+    })?;
+    result
+}
+
+/// A tar archive (optionally compressed) downloaded over HTTPS, from S3 with the `aws` CLI
+/// or from GCS with `gcloud`
+#[derive(Debug, Clone)]
+pub struct RemoteArchive {
+    pub url: String,
+}
+
+impl SourceProvider for RemoteArchive {
+    fn describe(&self) -> String {
+        format!("archive {}", self.url)
+    }
+
+    fn fetch(&self) -> Result<FetchedSource, SourceError> {
+        let scratch = scratch_dir()?;
+        let fetched = FetchedSource { root: scratch.join("files"), scratch: Some(scratch.clone()) };
+
+        let archive = scratch.join("source.tar");
+        if is_bucket_url(&self.url) {
+            copy_object(&self.url, &archive)?;
+        } else {
+            download(&self.url, &archive)?;
+        }
+        extract(&archive, fetched.root())?;
+        Ok(fetched)
+    }
+}
+
+/// True for the `s3://` and `gs://` URLs [`copy_object`] handles
+pub(crate) fn is_bucket_url(url: &str) -> bool {
+    url.starts_with("s3://") || url.starts_with("gs://")
+}
+
+/// Copies to or from an S3 or GCS object, whichever of `from` and `to` is a bucket URL,
+/// with the `aws` CLI or `gcloud` so their usual credentials apply
+pub(crate) fn copy_object(from: impl AsRef<OsStr>, to: impl AsRef<OsStr>) -> Result<(), SourceError> {
+    let gcs = [from.as_ref(), to.as_ref()].iter().any(|path| path.to_string_lossy().starts_with("gs://"));
+    let mut command = if gcs {
+        let mut command = Command::new("gcloud");
+        command.args(["storage", "cp", "--quiet"]);
+        command
+    } else {
+        let mut command = Command::new("aws");
+        command.args(["s3", "cp", "--only-show-errors"]);
+        command
+    };
+    run(command.arg(from.as_ref()).arg(to.as_ref()))
+}
+
+/// Streams `url` to `to`
+fn download(url: &str, to: &Path) -> Result<(), SourceError> {
+    debug!("Downloading {} to {}", url, to.display());
+    let failed = |reason: String| SourceError::DownloadFailed(url.to_string(), reason);
+    let response = ureq::get(url).call().map_err(|e| failed(e.to_string()))?;
+    io::copy(&mut response.into_reader(), &mut File::create(to)?).map_err(|e| failed(e.to_string()))?;
+    Ok(())
+}
+
+/// A tar archive (optionally compressed) on local disk
+#[derive(Debug, Clone)]
+pub struct ArchiveFile {
+    pub path: PathBuf,
+}
+
+impl SourceProvider for ArchiveFile {
+    fn describe(&self) -> String {
+        format!("archive {}", self.path.display())
+    }
+
+    fn fetch(&self) -> Result<FetchedSource, SourceError> {
+        let scratch = scratch_dir()?;
+        let fetched = FetchedSource { root: scratch.join("files"), scratch: Some(scratch) };
+        extract(&self.path, fetched.root())?;
+        Ok(fetched)
+    }
+}
+
+/// Parses a `--source` value:
+/// - `git://<repository>#<ref>`, where the repository is a local path or a clone URL
+///   (`git://https://github.com/org/db.git#v1.4.0`)
+/// - `https://...`, `http://...`, `s3://...` or `gs://...` pointing at a tar archive
+/// - `file://<path>` or a plain path to a directory or a tar archive
+pub fn provider_for(spec: &str) -> Result<Box<dyn SourceProvider>, SourceError> {
+    if let Some(rest) = spec.strip_prefix("git://") {
+        let (repository, reference) = rest
+            .rsplit_once('#')
+            .filter(|(repository, reference)| !repository.is_empty() && !reference.is_empty())
+            .ok_or_else(|| SourceError::InvalidSpec(format!("{} must name a ref, e.g. git://./repo#v1.0.0", spec)))?;
+        return Ok(Box::new(GitRef { repository: repository.to_string(), reference: reference.to_string() }));
+    }
+    if ["https://", "http://", "s3://", "gs://"].iter().any(|scheme| spec.starts_with(scheme)) {
+        return Ok(Box::new(RemoteArchive { url: spec.to_string() }));
+    }
+    let path = PathBuf::from(spec.strip_prefix("file://").unwrap_or(spec));
+    if path.is_file() {
+        return Ok(Box::new(ArchiveFile { path }));
+    }
+    Ok(Box::new(LocalDirectory { root: path }))
+}
+
+/// Rejects refs git could read as an option or a revision expression rather than a
+/// tag, branch or commit name
+fn check_reference(reference: &str) -> Result<(), SourceError> {
+    let valid = !reference.is_empty()
+        && !reference.starts_with('-')
+        && !reference.contains("..")
+        && !reference.contains("@{")
+        && !reference.chars().any(|c| c.is_whitespace() || c.is_control() || "~^:?*[\\".contains(c));
+    if !valid {
+        return Err(SourceError::InvalidSpec(format!("'{}' is not a valid git ref", reference)));
+    }
+    Ok(())
+}
+
+/// Fresh directory under the system temp dir
+pub(crate) fn scratch_dir() -> io::Result<PathBuf> {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos());
+    let dir = std::env::temp_dir().join(format!("deriddl-source-{}-{}", std::process::id(), nanos));
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Unpacks a tar archive, plain or compressed with zstd or gzip (told apart by their magic
+/// bytes). Entries that would land outside `into` are refused by the tar crate.
+fn extract(archive: &Path, into: &Path) -> Result<(), SourceError> {
+    fs::create_dir_all(into)?;
+    let mut file = BufReader::new(File::open(archive)?);
+    let magic = file.fill_buf()?;
+    let reader: Box<dyn Read> = if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        Box::new(zstd::Decoder::with_buffer(file)?)
+    } else if magic.starts_with(&[0x1f, 0x8b]) {
+        Box::new(flate2::bufread::GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    debug!("Unpacking {} into {}", archive.display(), into.display());
+    tar::Archive::new(reader).unpack(into)?;
+    Ok(())
+}
+
+fn run(command: &mut Command) -> Result<(), SourceError> {
+    output(command).map(|_| ())
+}
+
+/// Trimmed stdout of a successful command
+fn output(command: &mut Command) -> Result<String, SourceError> {
+    let program = command.get_program().to_string_lossy().to_string();
+    debug!("Running {:?}", command);
+    let output = command
+        .output()
+        .map_err(|e| SourceError::CommandFailed(program.clone(), e.to_string()))?;
+    if !output.status.success() {
+        return Err(SourceError::CommandFailed(program, String::from_utf8_lossy(&output.stderr).trim().to_string()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SourceError {
+    #[error("Invalid migration source: {0}")]
+    InvalidSpec(String),
+
+    #[error("Git error: {0}")]
+    Git(#[from] git2::Error),
+
+    #[error("Failed to download {0}: {1}")]
+    DownloadFailed(String, String),
+
+    #[error("{0} failed: {1}")]
+    CommandFailed(String, String),
+
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_git_ref_source_reads_tagged_files_not_working_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("migrations")).unwrap();
+        let file = dir.path().join("migrations").join("0001_init.sql");
+        fs::write(&file, "CREATE TABLE t (id INTEGER);").unwrap();
+        for args in [
+            &["init", "-q"][..],
+            &["add", "."],
+            &["-c", "user.name=test", "-c", "user.email=test@example.com", "commit", "-q", "-m", "init"],
+            &["tag", "v1.0.0"],
+        ] {
+            assert!(Command::new("git").arg("-C").arg(dir.path()).args(args).status().unwrap().success());
+        }
+        fs::write(&file, "DROP TABLE t;").unwrap();
+
+        let spec = format!("git://{}#v1.0.0", dir.path().display());
+        let provider = provider_for(&spec).unwrap();
+        assert!(provider.describe().ends_with("at v1.0.0"));
+        let fetched = provider.fetch().unwrap();
+        let content = fs::read_to_string(fetched.root().join("migrations").join("0001_init.sql")).unwrap();
+        assert_eq!(content, "CREATE TABLE t (id INTEGER);");
+
+        let scratch = fetched.scratch.clone().unwrap();
+        drop(fetched);
+        assert!(!scratch.exists());
+        assert!(matches!(provider_for("git://./repo"), Err(SourceError::InvalidSpec(_))));
+
+        // A clone URL fetches just the tag
+        let remote = GitRef { repository: format!("file://{}", dir.path().display()), reference: "v1.0.0".to_string() };
+        let fetched = remote.fetch().unwrap();
+        let content = fs::read_to_string(fetched.root().join("migrations").join("0001_init.sql")).unwrap();
+        assert_eq!(content, "CREATE TABLE t (id INTEGER);");
+        let missing = GitRef { reference: "v9.9.9".to_string(), ..remote };
+        assert!(matches!(missing.fetch(), Err(SourceError::Git(_))));
+    }
+
+    #[test]
+    fn test_git_ref_rejects_option_like_refs() {
+        for reference in ["--upload-pack=touch /tmp/pwned", "-v", "main..evil", "HEAD~1", "a b", "v1^{tree}"] {
+            let source = GitRef { repository: "./repo".to_string(), reference: reference.to_string() };
+            assert!(matches!(source.fetch(), Err(SourceError::InvalidSpec(_))), "{}", reference);
+        }
+        assert!(check_reference("release/v1.4.0").is_ok());
+        assert!(check_reference("3f2a9c1").is_ok());
+    }
+}
@@ -0,0 +1,94 @@
+use crate::executor::{ConnectionError, QueryResult, StatementReader};
+use crate::orchestrator::sandbox::format_table;
+use crate::tracker::Target;
+use log::debug;
+use serde_json::{json, Map, Value};
+
+/// Output format of `deriddl sql`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SqlOutputFormat {
+    /// Aligned text table per result set
+    Table,
+    /// JSON array with one entry per statement
+    Json,
+}
+
+/// Runs each statement in `sql` over ODBC and prints its result set, stopping at the first failure
+pub fn run_sql(target: &Target, sql: &str, format: SqlOutputFormat) -> Result<(), SqlError> {
+    let manager = target.connection_manager()?;
+    let mut executor = manager.executor(&target.conn)?;
+    let mut results = Vec::new();
+
+    for statement in StatementReader::new(sql.as_bytes(), executor.splitter()) {
+        let statement = statement.map_err(|e| SqlError::Read(e.to_string()))?;
+        debug!("Running statement: {}", statement);
+        let result = executor.query_with_columns(&statement)?;
+
+        match format {
+            SqlOutputFormat::Table => match &result {
+                Some(result) => print!("{}", format_table(result)),
+                None => println!("OK"),
+            },
+            SqlOutputFormat::Json => results.push(statement_json(&statement, result.as_ref())),
+        }
+    }
+
+    if format == SqlOutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&results).unwrap_or_default());
+    }
+    Ok(())
+}
+
+/// `{"statement", "columns", "rows"}` with each row keyed by column name;
+/// statements without a result set have no columns or rows
+fn statement_json(statement: &str, result: Option<&QueryResult>) -> Value {
+    let Some(result) = result else {
+        return json!({ "statement": statement });
+    };
+    let rows: Vec<Value> = result
+        .rows
+        .iter()
+        .map(|row| {
+            let object: Map<String, Value> = result
+                .columns
+                .iter()
+                .cloned()
+                .zip(row.iter().map(|value| Value::String(value.clone())))
+                .collect();
+            Value::Object(object)
+        })
+        .collect();
+    json!({ "statement": statement, "columns": result.columns, "rows": rows })
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SqlError {
+    #[error("Connection error: {0}")]
+    Connection(#[from] ConnectionError),
+
+    #[error("Failed to read SQL: {0}")]
+    Read(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statement_json_keys_rows_by_column() {
+        let result = QueryResult {
+            columns: vec!["migration_id".to_string(), "success".to_string()],
+            rows: vec![vec!["1".to_string(), "1".to_string()]],
+        };
+
+        assert_eq!(
+            statement_json("SELECT migration_id, success FROM schema_migrations", Some(&result)),
+            json!({
+                "statement": "SELECT migration_id, success FROM schema_migrations",
+                "columns": ["migration_id", "success"],
+                "rows": [{ "migration_id": "1", "success": "1" }],
+            })
+        );
+        assert_eq!(statement_json("DELETE FROM t", None), json!({ "statement": "DELETE FROM t" }));
+    }
+}
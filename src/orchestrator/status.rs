@@ -1,21 +1,42 @@
+use crate::logger::SUMMARY_TARGET;
 use crate::executor::ConnectionError;
 use crate::orchestrator::{MigrationLoader, Validator};
-use crate::tracker::{schema_init, VersionStore};
-use log::{debug, error, info, warn};
+use crate::orchestrator::checksum_diff::log_checksum_diff;
+use crate::orchestrator::metrics::StatusMetrics;
+use crate::orchestrator::migration_loader::LoaderOptions;
+use crate::tracker::run_progress::RunStatus;
+use crate::tracker::{schema_init, RunProgress, Target};
+use log::{debug, info, warn};
 use std::collections::HashMap;
+use std::path::Path;
 
-pub fn run_status(conn: &str, path: &str) -> Result<(), StatusError> {
+/// Prints the state of every migration and returns how many are pending. With `metrics`,
+/// also writes them to that file for node_exporter's textfile collector.
+pub fn run_status(
+    target: &Target,
+    path: &str,
+    loader: &LoaderOptions,
+    environment: Option<&str>,
+    owner: Option<&str>,
+    metrics: Option<&Path>,
+) -> Result<usize, StatusError> {
     info!("Running migration status check");
-    debug!("Connection string length: {}", conn.len());
+    debug!("Connection string length: {}", target.conn.len());
     debug!("Migrations path: {}", path);
+    debug!("Environment: {:?}", environment);
 
     // Load migrations from filesystem
-    let migrations = MigrationLoader::load_migrations(path)
+    let mut migrations = MigrationLoader::load_migrations_with_options(path, loader)
         .map_err(|e| StatusError::LoadFailed(e.to_string()))?;
+    if let Some(owner) = owner {
+        migrations.retain(|m| m.owner.as_deref() == Some(owner));
+        info!("Showing migrations owned by '{}'", owner);
+    }
 
     if migrations.is_empty() {
         info!("📊 No migrations found in {}", path);
-        return Ok(());
+        write_metrics(metrics, StatusMetrics::default())?;
+        return Ok(0);
     }
 
     info!("Loaded {} migrations from {}", migrations.len(), path);
@@ -30,7 +51,7 @@ pub fn run_status(conn: &str, path: &str) -> Result<(), StatusError> {
     }
 
     // Check if schema_migrations table exists
-    let table_exists = schema_init::check_migration_table_exists(conn)?;
+    let table_exists = schema_init::check_migration_table_exists(target)?;
 
     if !table_exists {
         info!("📊 Migration Status");
@@ -38,15 +59,30 @@ pub fn run_status(conn: &str, path: &str) -> Result<(), StatusError> {
         warn!("⚠️  schema_migrations table does not exist. Run 'init' command first.");
         info!("");
         info!("Available migrations ({}): ", migrations.len());
-        for migration in migrations {
-            info!("  📄 {} (PENDING)", migration.filename());
+        for migration in &migrations {
+            if !migration.runs_in_environment(environment) {
+                info!("  📄 {} (skipped (env))", migration.filename());
+            } else if loader.is_skip_listed(migration) {
+                info!("  📄 {} (skipped (skip list))", migration.filename());
+            } else {
+                info!("  📄 {} (PENDING)", migration.filename());
+            }
         }
-        return Ok(());
+        let pending_count = migrations
+            .iter()
+            .filter(|m| m.runs_in_environment(environment) && !loader.is_skip_listed(m))
+            .count();
+        write_metrics(metrics, StatusMetrics::from_records(pending_count, &[]))?;
+        return Ok(pending_count);
     }
 
     // Get applied migrations and baseline info
-    let mut version_store = VersionStore::new(conn)?;
-    let applied_migrations = version_store.get_applied_migrations()?;
+    let mut version_store = target.version_store()?;
+    let mut applied_migrations = version_store.get_applied_migrations()?;
+    if owner.is_some() {
+        // Other teams' records share the table; keep the ones for this owner's files
+        applied_migrations.retain(|a| migrations.iter().any(|m| m.identifier() == a.migration_id));
+    }
     let applied_versions = version_store.get_applied_versions()?;
     let baseline_version = version_store.get_baseline_version()?;
     let applied_map: HashMap<String, _> =
@@ -56,9 +92,30 @@ pub fn run_status(conn: &str, path: &str) -> Result<(), StatusError> {
     info!("📊 Migration Status");
     info!("==================");
     info!("Database: Connected ✅");
-    info!("Total migrations: {}", migrations.len());
-    info!("Applied: {}", applied_migrations.len());
-    info!("Pending: {}", migrations.len() - applied_migrations.len());
+    info!(target: SUMMARY_TARGET, "Total migrations: {}", migrations.len());
+    let env_skipped_count = migrations
+        .iter()
+        .filter(|m| !m.runs_in_environment(environment) && !applied_map.contains_key(&m.identifier()))
+        .count();
+    info!(target: SUMMARY_TARGET, "Applied: {}", applied_migrations.len());
+    let skip_listed_count = migrations
+        .iter()
+        .filter(|m| m.runs_in_environment(environment) && loader.is_skip_listed(m) && !applied_map.contains_key(&m.identifier()))
+        .count();
+    let pending_count = migrations
+        .len()
+        .saturating_sub(applied_migrations.len() + env_skipped_count + skip_listed_count);
+    info!(target: SUMMARY_TARGET, "Pending: {}", pending_count);
+    if skip_listed_count > 0 {
+        info!("Skipped (skip list, not yet recorded): {}", skip_listed_count);
+    }
+    if env_skipped_count > 0 {
+        info!(
+            "Skipped (env '{}'): {}",
+            environment.unwrap_or("<none>"),
+            env_skipped_count
+        );
+    }
     
     // Show baseline information
     if let Some(baseline) = baseline_version {
@@ -91,17 +148,37 @@ pub fn run_status(conn: &str, path: &str) -> Result<(), StatusError> {
                     migration.sql_content.clone()
                 );
                 
-                let status_icon = if migration_with_applied.is_applied() { "✅" } else { "❌" };
+                let status_icon = if applied.skip_listed() {
+                    "⏭️ "
+                } else if migration_with_applied.is_applied() {
+                    "✅"
+                } else {
+                    "❌"
+                };
                 let migration_type_display = match applied.migration_type {
                     crate::model::MigrationType::Versioned => "V",
                     crate::model::MigrationType::Repeatable => "R",
                 };
                 
-                let timing_info = if let Some(exec_time) = migration_with_applied.execution_time() {
+                let timing_info = if applied.applied_externally() {
+                    "applied externally".to_string()
+                } else if applied.skipped_by_guard() {
+                    "skipped by guard".to_string()
+                } else if applied.skip_listed() {
+                    "skipped (skip list)".to_string()
+                } else if let Some(exec_time) = migration_with_applied.execution_time() {
                     format!("{}ms", exec_time)
                 } else {
                     "unknown".to_string()
                 };
+                let timing_info = match &applied.release {
+                    Some(release) => format!("{}, release {}", timing_info, release),
+                    None => timing_info,
+                };
+                let timing_info = match &applied.git_commit {
+                    Some(commit) => format!("{}, commit {:.7}", timing_info, commit),
+                    None => timing_info,
+                };
                 
                 info!(
                     "  {} [{}] {} (applied: {}, {})", 
@@ -121,9 +198,11 @@ pub fn run_status(conn: &str, path: &str) -> Result<(), StatusError> {
                 }
 
                 // Check for checksum mismatch using the applied migration data
-                if applied.checksum != migration.checksum {
+                if !migration.matches_checksum(&applied.checksum) {
                     warn!("      ⚠️  Checksum mismatch! File may have been modified after application.");
-                    debug!("         Stored: {}, Current: {}", applied.checksum, migration.checksum);
+                    if !log_checksum_diff(&mut version_store, migration, &applied.checksum, loader.checksum_normalization) {
+                        debug!("         Stored: {}, Current: {}", applied.checksum, migration.checksum);
+                    }
                 }
             }
             None => {
@@ -131,6 +210,23 @@ pub fn run_status(conn: &str, path: &str) -> Result<(), StatusError> {
                     crate::model::MigrationType::Versioned => "V",
                     crate::model::MigrationType::Repeatable => "R",
                 };
+                if !migration.runs_in_environment(environment) {
+                    info!(
+                        "  ⏭️  [{}] {} (skipped (env): {})",
+                        migration_type_display,
+                        migration.filename(),
+                        migration.environments.join(", ")
+                    );
+                    continue;
+                }
+                if loader.is_skip_listed(migration) {
+                    info!(
+                        "  ⏭️  [{}] {} (skipped (skip list), recorded by the next apply)",
+                        migration_type_display,
+                        migration.filename()
+                    );
+                    continue;
+                }
                 info!("  ⏳ [{}] {} (PENDING)", migration_type_display, migration.filename());
                 debug!("      File: {}", migration.file_path.display());
             }
@@ -153,6 +249,33 @@ pub fn run_status(conn: &str, path: &str) -> Result<(), StatusError> {
         }
     }
 
+    // A run without an outcome is still applying, or was killed or interrupted and can be resumed
+    for run in RunProgress::new(target).in_progress() {
+        info!("");
+        warn!(
+            target: SUMMARY_TARGET,
+            "🔄 {} run {}: {}/{} migrations applied{}, last update {}",
+            if run.status == RunStatus::Interrupted { "Interrupted" } else { "In-progress" },
+            run.run_id,
+            run.completed.min(run.planned.len()),
+            run.planned.len(),
+            run.last_completed.as_ref().map(|id| format!(" (last {})", id)).unwrap_or_default(),
+            run.updated_at.map_or("unknown".to_string(), |at| at.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        );
+        info!("  If it is no longer running, continue it with: deriddl apply --resume {}", run.run_id);
+    }
+
+    write_metrics(metrics, StatusMetrics::from_records(pending_count, &applied_migrations))?;
+    Ok(pending_count)
+}
+
+fn write_metrics(path: Option<&Path>, metrics: StatusMetrics) -> Result<(), StatusError> {
+    if let Some(path) = path {
+        metrics
+            .write_textfile(path)
+            .map_err(|e| StatusError::Metrics(path.display().to_string(), e.to_string()))?;
+        info!("📈 Wrote metrics to {}", path.display());
+    }
     Ok(())
 }
 
@@ -161,6 +284,9 @@ pub enum StatusError {
     #[error("Failed to load migrations: {0}")]
     LoadFailed(String),
 
+    #[error("Failed to write metrics to {0}: {1}")]
+    Metrics(String, String),
+
     #[error("Connection error: {0}")]
     Connection(#[from] ConnectionError),
 }
@@ -0,0 +1,159 @@
+use crate::executor::is_interrupted;
+use crate::logger::log_summary;
+use serde::Serialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+/// What happened to migrations during a command, counted for its summary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Tally {
+    Applied,
+    RolledBack,
+    /// Skipped by a guard or the skip list, and recorded as skipped
+    Skipped,
+    /// Failed migrations, or the problems validate found
+    Failed,
+}
+
+static COUNTS: [AtomicUsize; 4] = [const { AtomicUsize::new(0) }; 4];
+
+/// Counts `count` migrations towards the summary of the running command
+pub(crate) fn tally(tally: Tally, count: usize) {
+    COUNTS[tally as usize].fetch_add(count, Ordering::Relaxed);
+}
+
+fn counted(tally: Tally) -> usize {
+    COUNTS[tally as usize].load(Ordering::Relaxed)
+}
+
+/// How a command ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SummaryStatus {
+    Succeeded,
+    Failed,
+    /// Stopped by SIGINT or SIGTERM
+    Interrupted,
+}
+
+impl SummaryStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SummaryStatus::Succeeded => "succeeded",
+            SummaryStatus::Failed => "failed",
+            SummaryStatus::Interrupted => "interrupted",
+        }
+    }
+}
+
+/// The footer ending `apply`, `rollback` and `validate`: the same fields for every
+/// command, as `key=value` text or, with `logging.format = "json"`, a `summary` object
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommandSummary {
+    pub command: &'static str,
+    pub status: SummaryStatus,
+    /// `--env` name; `default` without one
+    pub target: String,
+    pub run_id: String,
+    pub applied: usize,
+    pub rolled_back: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub duration_ms: u64,
+}
+
+impl CommandSummary {
+    /// The footer as one line of `key=value` pairs
+    pub fn line(&self) -> String {
+        format!(
+            "📋 Summary: command={} status={} target={} run_id={} applied={} rolled_back={} skipped={} failed={} duration_ms={}",
+            self.command,
+            self.status.as_str(),
+            self.target,
+            self.run_id,
+            self.applied,
+            self.rolled_back,
+            self.skipped,
+            self.failed,
+            self.duration_ms
+        )
+    }
+}
+
+/// Started with a command, so its summary covers the whole run
+pub struct SummaryTimer {
+    command: &'static str,
+    target: String,
+    run_id: String,
+    started: Instant,
+}
+
+impl SummaryTimer {
+    pub fn start(command: &'static str, target: Option<&str>, run_id: &str) -> Self {
+        for count in &COUNTS {
+            count.store(0, Ordering::Relaxed);
+        }
+        Self {
+            command,
+            target: target.unwrap_or("default").to_string(),
+            run_id: run_id.to_string(),
+            started: Instant::now(),
+        }
+    }
+
+    /// Logs the summary of the command that ended with `result`
+    pub fn finish<T, E>(self, result: &Result<T, E>) -> CommandSummary {
+        let status = match result {
+            Ok(_) => SummaryStatus::Succeeded,
+            Err(_) if is_interrupted() => SummaryStatus::Interrupted,
+            Err(_) => SummaryStatus::Failed,
+        };
+        let summary = CommandSummary {
+            command: self.command,
+            status,
+            target: self.target,
+            run_id: self.run_id,
+            applied: counted(Tally::Applied),
+            rolled_back: counted(Tally::RolledBack),
+            skipped: counted(Tally::Skipped),
+            failed: counted(Tally::Failed),
+            duration_ms: self.started.elapsed().as_millis() as u64,
+        };
+        log_summary(&summary.line(), &summary);
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_has_the_same_fields_as_text_and_json() {
+        let summary = CommandSummary {
+            command: "apply",
+            status: SummaryStatus::Failed,
+            target: "prod".to_string(),
+            run_id: "8f0c".to_string(),
+            applied: 3,
+            rolled_back: 0,
+            skipped: 1,
+            failed: 1,
+            duration_ms: 1250,
+        };
+        assert_eq!(
+            summary.line(),
+            "📋 Summary: command=apply status=failed target=prod run_id=8f0c applied=3 rolled_back=0 skipped=1 failed=1 duration_ms=1250"
+        );
+
+        let json = serde_json::to_value(&summary).unwrap();
+        let line = summary.line();
+        let pairs: Vec<(&str, &str)> =
+            line.trim_start_matches("📋 Summary: ").split(' ').filter_map(|pair| pair.split_once('=')).collect();
+        assert_eq!(pairs.len(), json.as_object().unwrap().len());
+        for (key, value) in pairs {
+            let field = &json[key];
+            assert_eq!(field.as_str().map(str::to_string).unwrap_or_else(|| field.to_string()), value, "{}", key);
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use crate::dialects;
+use crate::executor::{ConnectionSettings, SessionSettings};
+use crate::logger::SUMMARY_TARGET;
+use crate::model::{Config, ConfigFiles, MigrationType};
+use crate::tracker::version_store::AppliedMigration;
+use crate::tracker::{Target, TrackerSql};
+use chrono::{DateTime, Utc};
+use log::{error, info, warn};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// Where one target stands relative to the union of versions applied anywhere
+#[derive(Debug, Clone, Serialize)]
+pub struct TargetSyncState {
+    pub target: String,
+    pub dialect: String,
+    pub applied: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latest_version: Option<u32>,
+    /// Migration ids applied on another target but not on this one, oldest first
+    pub missing: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl TargetSyncState {
+    pub fn in_sync(&self) -> bool {
+        self.error.is_none() && self.missing.is_empty()
+    }
+}
+
+/// Everything `sync-status` found, in the shape printed by `--json`
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncReport {
+    pub generated_at: DateTime<Utc>,
+    pub in_sync: bool,
+    pub targets: Vec<TargetSyncState>,
+}
+
+/// Compares the versioned migrations applied on each target (an `--env` name whose
+/// `config/<target>.toml` holds its connection string and dialect) and reports the
+/// targets that lag behind the others. Each target is read as part of run `run_id`.
+pub fn run_sync_status(files: ConfigFiles, targets: &[String], json: bool, run_id: &str) -> Result<SyncReport, SyncStatusError> {
+    if targets.len() < 2 {
+        return Err(SyncStatusError::TooFewTargets);
+    }
+    info!("Comparing applied migrations across {}", targets.join(", "));
+
+    let mut histories = Vec::new();
+    for target in targets {
+        // A missing override would silently compare the base configuration's database
+        let override_path = Config::environment_config_path(target);
+        if !Path::new(&override_path).is_file() {
+            return Err(SyncStatusError::Config(target.clone(), format!("{} not found", override_path)));
+        }
+        let config = Config::load(files, Some(target))
+            .map_err(|e| SyncStatusError::Config(target.clone(), e.to_string()))?;
+        let history = read_history(&config, run_id).map_err(|e| {
+            warn!("⚠️  Could not read schema_migrations on {}: {}", target, e);
+            e
+        });
+        histories.push((target.clone(), config.migrations.dialect, history));
+    }
+
+    let report = compare(histories);
+    if json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{}", json),
+            Err(e) => error!("Failed to serialize sync report: {}", e),
+        }
+    } else {
+        print_report(&report);
+    }
+    Ok(report)
+}
+
+/// Applied rows and baseline version of one target, or why they could not be read
+pub type TargetHistory = Result<(Vec<AppliedMigration>, Option<u32>), String>;
+
+/// Applied rows and baseline version of the target described by `config`, read with that
+/// target's own session setup, catalog and schema rather than the current environment's
+fn read_history(config: &Config, run_id: &str) -> TargetHistory {
+    let conn = config
+        .database
+        .connection_string
+        .as_deref()
+        .ok_or_else(|| "no database.connection_string configured".to_string())?;
+    let dialect = dialects::get_dialect_with_config(None, Some(conn), Some(&config.migrations.dialect)).map_err(|e| e.to_string())?;
+    let session = SessionSettings::for_config(config, Some(dialect.as_ref()), None, true, run_id);
+    let connection = ConnectionSettings::for_config(config, Some(dialect.as_ref()), session.statements, true, run_id);
+    let sql = TrackerSql::new(dialect).with_location(session.catalog, session.schema);
+    let mut store = Target::new(conn).with_connection(connection).with_sql(sql).version_store().map_err(|e| e.to_string())?;
+    let applied = store.get_applied_migrations().map_err(|e| e.to_string())?;
+    let baseline = store.get_baseline_version().map_err(|e| e.to_string())?;
+    Ok((applied, baseline))
+}
+
+/// Builds the report from each target's history. A baseline at version N counts as
+/// having applied every non-namespaced version up to N.
+pub fn compare(histories: Vec<(String, String, TargetHistory)>) -> SyncReport {
+    let applied_sets: Vec<Option<(BTreeSet<MigrationKey>, Option<u32>)>> = histories
+        .iter()
+        .map(|(_, _, history)| {
+            history.as_ref().ok().map(|(applied, baseline)| {
+                let keys = applied
+                    .iter()
+                    .filter(|a| a.success && a.migration_type == MigrationType::Versioned && !a.filename.starts_with("baseline_"))
+                    .map(|a| MigrationKey::parse(&a.migration_id))
+                    .collect();
+                (keys, *baseline)
+            })
+        })
+        .collect();
+    let union: BTreeSet<MigrationKey> = applied_sets.iter().flatten().flat_map(|(keys, _)| keys.iter().cloned()).collect();
+
+    let targets: Vec<TargetSyncState> = histories
+        .into_iter()
+        .zip(applied_sets)
+        .map(|((target, dialect, history), applied)| match applied {
+            Some((keys, baseline)) => TargetSyncState {
+                target,
+                dialect,
+                applied: keys.len(),
+                latest_version: keys.iter().map(|key| key.version).chain(baseline).max(),
+                missing: union
+                    .iter()
+                    .filter(|key| !keys.contains(key) && !key.covered_by_baseline(baseline))
+                    .map(MigrationKey::to_string)
+                    .collect(),
+                error: None,
+            },
+            None => TargetSyncState {
+                target,
+                dialect,
+                applied: 0,
+                latest_version: None,
+                missing: Vec::new(),
+                error: history.err(),
+            },
+        })
+        .collect();
+
+    SyncReport {
+        generated_at: Utc::now(),
+        in_sync: targets.iter().all(TargetSyncState::in_sync),
+        targets,
+    }
+}
+
+/// A versioned migration id (`12` or `analytics:12`), ordered by namespace then version
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct MigrationKey {
+    namespace: Option<String>,
+    version: u32,
+    id: String,
+}
+
+impl MigrationKey {
+    fn parse(migration_id: &str) -> Self {
+        let (namespace, version) = match migration_id.split_once(':') {
+            Some((namespace, version)) => (Some(namespace.to_string()), version),
+            None => (None, migration_id),
+        };
+        Self { namespace, version: version.parse().unwrap_or(0), id: migration_id.to_string() }
+    }
+
+    fn covered_by_baseline(&self, baseline: Option<u32>) -> bool {
+        self.namespace.is_none() && baseline.is_some_and(|baseline| self.version <= baseline)
+    }
+}
+
+impl std::fmt::Display for MigrationKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.id)
+    }
+}
+
+fn print_report(report: &SyncReport) {
+    for target in &report.targets {
+        let latest = target.latest_version.map_or_else(|| "none".to_string(), |v| format!("{:04}", v));
+        if let Some(e) = &target.error {
+            error!("❌ {} ({}): {}", target.target, target.dialect, e);
+        } else if target.missing.is_empty() {
+            info!("✅ {} ({}): {} applied, latest {}", target.target, target.dialect, target.applied, latest);
+        } else {
+            warn!(
+                "⚠️  {} ({}): {} applied, latest {}, behind by {}: {}",
+                target.target,
+                target.dialect,
+                target.applied,
+                latest,
+                target.missing.len(),
+                target.missing.join(", ")
+            );
+        }
+    }
+
+    if report.in_sync {
+        info!(target: SUMMARY_TARGET, "✅ All {} targets have the same migrations applied", report.targets.len());
+    } else {
+        let lagging: Vec<&str> = report.targets.iter().filter(|t| !t.in_sync()).map(|t| t.target.as_str()).collect();
+        warn!(target: SUMMARY_TARGET, "⚠️  Out of sync: {}", lagging.join(", "));
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SyncStatusError {
+    #[error("sync-status needs at least two targets")]
+    TooFewTargets,
+
+    #[error("Failed to load configuration for {0}: {1}")]
+    Config(String, String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn applied(migration_id: &str, success: bool) -> AppliedMigration {
+        let version = migration_id.rsplit(':').next().and_then(|v| v.parse().ok()).unwrap_or(0);
+        AppliedMigration { migration_id: migration_id.to_string(), success, ..AppliedMigration::of_version(version) }
+    }
+
+    #[test]
+    fn test_reports_targets_missing_versions_applied_elsewhere() {
+        let report = compare(vec![
+            ("oltp".into(), "postgres".into(), Ok((vec![applied("1", true), applied("2", true), applied("lake:3", true)], None))),
+            ("lake".into(), "databricks".into(), Ok((vec![applied("2", true), applied("10", false)], Some(1)))),
+            ("replica".into(), "postgres".into(), Err("connection refused".into())),
+        ]);
+
+        assert!(!report.in_sync);
+        assert!(report.targets[0].in_sync());
+        assert_eq!(report.targets[0].latest_version, Some(3));
+        // Version 1 is covered by the baseline, the failed version 10 does not count
+        assert_eq!(report.targets[1].missing, vec!["lake:3"]);
+        assert_eq!(report.targets[2].error.as_deref(), Some("connection refused"));
+    }
+}
@@ -0,0 +1,108 @@
+use crate::executor::ConnectionError;
+use crate::model::Migration;
+use crate::orchestrator::apply::format_count;
+use crate::orchestrator::lock::LockPolicy;
+use crate::tracker::Target;
+use log::{debug, info};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A table that pending migrations lock or rewrite and that exceeds `large_table_rows`
+/// or `large_table_mb`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LargeTable {
+    pub table: String,
+    pub rows: Option<u64>,
+    pub bytes: Option<u64>,
+    /// Pending migrations that ALTER, UPDATE or DELETE from it
+    pub migrations: Vec<String>,
+}
+
+impl fmt::Display for LargeTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut size = Vec::new();
+        if let Some(rows) = self.rows {
+            size.push(format!("~{} rows", format_count(rows as usize)));
+        }
+        if let Some(bytes) = self.bytes {
+            size.push(format!("{} MB", bytes / (1024 * 1024)));
+        }
+        write!(f, "{} ({}), changed by {}", self.table, size.join(", "), self.migrations.join(", "))
+    }
+}
+
+/// Looks up the size of every existing table the pending migrations ALTER, UPDATE or
+/// DELETE from, using the dialect's catalog, and returns those over the thresholds.
+/// Tables the catalog does not know yet (created by a pending migration) are skipped.
+pub fn find_large_tables(target: &Target, lock: &LockPolicy, pending: &[Migration]) -> Result<Vec<LargeTable>, ConnectionError> {
+    if !lock.checks_table_sizes() {
+        return Ok(Vec::new());
+    }
+    let Some(dialect) = lock.resolve_dialect() else {
+        return Ok(Vec::new());
+    };
+
+    let mut tables: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for migration in pending {
+        for table in migration.rewritten_tables() {
+            tables.entry(table).or_default().push(migration.filename());
+        }
+    }
+    if tables.is_empty() || dialect.table_size_sql("t").is_none() {
+        return Ok(Vec::new());
+    }
+
+    let manager = target.connection_manager()?;
+    let mut executor = manager.executor(&target.conn)?;
+    let mut large = Vec::new();
+    for (table, migrations) in tables {
+        let Some(query) = dialect.table_size_sql(&table) else {
+            continue;
+        };
+        let row = match executor.query_rows(&query) {
+            Ok(rows) => rows.into_iter().next(),
+            Err(e) => {
+                debug!("No size for {}: {}", table, e);
+                None
+            }
+        };
+        let Some(row) = row else {
+            continue;
+        };
+        let rows = row.first().and_then(|value| value.parse().ok());
+        let bytes = row.get(1).and_then(|value| value.parse().ok());
+        debug!("{}: {:?} rows, {:?} bytes", table, rows, bytes);
+        if lock.exceeds(rows, bytes) {
+            large.push(LargeTable { table, rows, bytes, migrations });
+        }
+    }
+    if large.is_empty() {
+        info!("Tables changed by pending migrations are below the large table thresholds");
+    }
+    Ok(large)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_thresholds_and_rewritten_tables() {
+        let lock = LockPolicy { large_table_rows: Some(1_000_000), large_table_mb: Some(500), ..Default::default() };
+        assert!(lock.checks_table_sizes());
+        assert!(lock.exceeds(Some(2_000_000), None));
+        assert!(lock.exceeds(Some(10), Some(600 * 1024 * 1024)));
+        assert!(!lock.exceeds(Some(10), Some(1024)));
+        assert!(!lock.exceeds(None, None));
+        assert!(!LockPolicy::default().checks_table_sizes());
+
+        let migration = Migration::new(
+            3,
+            "backfill".to_string(),
+            PathBuf::from("0003_backfill.sql"),
+            "ALTER TABLE \"Orders\" ADD COLUMN note TEXT;\nUPDATE app.users SET active = 1;\nCREATE TABLE audit (id INT);\nDELETE FROM orders WHERE id < 10;".to_string(),
+        );
+        assert_eq!(migration.rewritten_tables(), vec!["app.users", "orders"]);
+    }
+}
@@ -1,21 +1,25 @@
-use crate::executor::{ConnectionError, ConnectionManager};
+use crate::executor::ConnectionError;
 use crate::orchestrator::{MigrationLoader, Validator};
-use crate::tracker::{schema_init, VersionStore};
+use crate::orchestrator::checksum_diff::log_checksum_diff;
+use crate::orchestrator::lockfile::LockfileError;
+use crate::orchestrator::migration_loader::LoaderOptions;
+use crate::orchestrator::summary::{tally, Tally};
+use crate::tracker::{schema_init, Target};
 use log::{debug, error, info, warn};
 use std::collections::HashMap;
 
-pub fn run_validate(conn: &str, path: &str) -> Result<(), ValidateError> {
+pub fn run_validate(target: &Target, path: &str, loader: &LoaderOptions) -> Result<(), ValidateError> {
     info!("Running migration validation");
-    debug!("Connection string length: {}", conn.len());
+    debug!("Connection string length: {}", target.conn.len());
     debug!("Migrations path: {}", path);
 
     // Test connection first
-    let connection_manager = ConnectionManager::new()?;
-    connection_manager.test_connection(conn)?;
+    let connection_manager = target.connection_manager()?;
+    connection_manager.test_connection(&target.conn)?;
     info!("✅ Database connection verified");
 
     // Load migrations from filesystem
-    let migrations = MigrationLoader::load_migrations(path)
+    let migrations = MigrationLoader::load_migrations_with_options(path, loader)
         .map_err(|e| ValidateError::LoadFailed(e.to_string()))?;
 
     if migrations.is_empty() {
@@ -24,6 +28,7 @@ pub fn run_validate(conn: &str, path: &str) -> Result<(), ValidateError> {
     }
 
     info!("Loaded {} migrations from {}", migrations.len(), path);
+    loader.lockfile.verify(&migrations)?;
 
     // Validate migration sequence
     let sequence_issues = Validator::validate_migration_sequence(&migrations);
@@ -35,7 +40,7 @@ pub fn run_validate(conn: &str, path: &str) -> Result<(), ValidateError> {
     }
 
     // Check if schema_migrations table exists
-    let table_exists = schema_init::check_migration_table_exists(conn)?;
+    let table_exists = schema_init::check_migration_table_exists(target)?;
 
     if !table_exists {
         info!("🔍 Migration Validation Results");
@@ -64,7 +69,7 @@ pub fn run_validate(conn: &str, path: &str) -> Result<(), ValidateError> {
     }
 
     // Get applied migrations and versions
-    let mut version_store = VersionStore::new(conn)?;
+    let mut version_store = target.version_store()?;
     let applied_migrations = version_store.get_applied_migrations()?;
     let applied_versions = version_store.get_applied_versions()?;
     
@@ -110,7 +115,7 @@ pub fn run_validate(conn: &str, path: &str) -> Result<(), ValidateError> {
                 let stored_checksum = version_store.get_migration_checksum(&migration.identifier())?
                     .unwrap_or_else(|| applied.checksum.clone());
                 
-                if applied.checksum != migration.checksum || stored_checksum != migration.checksum {
+                if !migration.matches_checksum(&applied.checksum) || !migration.matches_checksum(&stored_checksum) {
                     checksum_mismatches += 1;
                     warn!(
                         "      ⚠️  CHECKSUM MISMATCH! File may have been modified after application."
@@ -118,6 +123,9 @@ pub fn run_validate(conn: &str, path: &str) -> Result<(), ValidateError> {
                     warn!("         Applied record: {}", applied.checksum);
                     warn!("         Stored checksum: {}", stored_checksum);
                     warn!("         Current file: {}", migration.checksum);
+                    if !log_checksum_diff(&mut version_store, migration, &applied.checksum, loader.checksum_normalization) {
+                        debug!("         Applied content not found in the archive, recorded definitions or git history");
+                    }
                     validation_errors.push(format!(
                         "Checksum mismatch for {}: stored={}, current={}",
                         migration.filename(),
@@ -146,12 +154,19 @@ pub fn run_validate(conn: &str, path: &str) -> Result<(), ValidateError> {
     }
 
     // Check for orphaned database migrations (migrations in DB but not in files)
+    let renamed = Validator::detect_renamed_migrations(&applied_migrations, &migrations);
     for applied in &applied_migrations {
         let file_exists = migrations
             .iter()
             .any(|m| m.identifier() == applied.migration_id);
         
-        if !file_exists {
+        if let Some(renamed) = renamed.iter().find(|r| r.migration_id == applied.migration_id) {
+            warn!("  🚨 RENAMED: {}", renamed);
+            validation_errors.push(format!(
+                "{} (applied as {}); restore the original file name",
+                renamed, renamed.applied_filename
+            ));
+        } else if !file_exists {
             orphaned_db_migrations += 1;
             warn!(
                 "  🚨 ORPHANED: {} exists in database but not in files", 
@@ -171,6 +186,7 @@ pub fn run_validate(conn: &str, path: &str) -> Result<(), ValidateError> {
     info!("Total validation errors: {}", validation_errors.len());
     info!("Checksum mismatches: {}", checksum_mismatches);
     info!("Orphaned DB migrations: {}", orphaned_db_migrations);
+    tally(Tally::Failed, validation_errors.len());
 
     if validation_errors.is_empty() {
         info!("✅ All migrations validated successfully!");
@@ -195,4 +211,7 @@ pub enum ValidateError {
 
     #[error("Validation failed with {} errors", .0.len())]
     ValidationFailed(Vec<String>),
+
+    #[error("{0}")]
+    Lockfile(#[from] LockfileError),
 }
\ No newline at end of file
@@ -1,7 +1,29 @@
-use crate::model::Migration;
+use crate::model::{Migration, MigrationType};
+use crate::orchestrator::planner::PlannerError;
+use crate::orchestrator::Planner;
+use crate::tracker::version_store::AppliedMigration;
 
 pub struct Validator;
 
+/// An applied migration whose file is gone while a file under another version or name
+/// has exactly its content
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenamedMigration {
+    pub migration_id: String,
+    pub applied_filename: String,
+    pub renamed_to: String,
+}
+
+impl std::fmt::Display for RenamedMigration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self.migration_id.rsplit_once(':') {
+            Some((namespace, version)) => format!("{}:{:0>4}", namespace, version),
+            None => format!("{:0>4}", self.migration_id),
+        };
+        write!(f, "Migration {} appears renamed to {}", label, self.renamed_to)
+    }
+}
+
 impl Validator {
     /// Check for common migration issues (gaps, duplicates, etc.)
     pub fn validate_migration_sequence(migrations: &[Migration]) -> Vec<String> {
@@ -11,22 +33,37 @@ impl Validator {
         let versioned_migrations: Vec<_> = migrations.iter().filter(|m| !m.is_repeatable()).collect();
         let repeatable_migrations: Vec<_> = migrations.iter().filter(|m| m.is_repeatable()).collect();
 
-        // Check for version gaps in versioned migrations only
-        for (i, migration) in versioned_migrations.iter().enumerate() {
-            let expected_version = (i + 1) as u32;
-            if migration.version != Some(expected_version) {
-                issues.push(format!(
-                    "Version gap detected: expected {}, found {:?} in {}",
-                    expected_version, migration.version, migration.filename()
-                ));
+        // Each namespace has its own independent version sequence
+        let mut namespaces: Vec<Option<&str>> = Vec::new();
+        for migration in &versioned_migrations {
+            if !namespaces.contains(&migration.namespace.as_deref()) {
+                namespaces.push(migration.namespace.as_deref());
             }
         }
 
-        // Check for duplicate versions in versioned migrations
-        let mut versions = std::collections::HashSet::new();
-        for migration in &versioned_migrations {
-            if let Some(version) = migration.version {
-                if !versions.insert(version) {
+        for namespace in namespaces {
+            let namespace_migrations: Vec<_> = versioned_migrations
+                .iter()
+                .filter(|m| m.namespace.as_deref() == namespace)
+                .collect();
+
+            // Check for version gaps in versioned migrations only
+            for (i, migration) in namespace_migrations.iter().enumerate() {
+                let expected_version = (i + 1) as u32;
+                if migration.version != Some(expected_version) {
+                    issues.push(format!(
+                        "Version gap detected: expected {}, found {:?} in {}",
+                        expected_version, migration.version, migration.filename()
+                    ));
+                }
+            }
+
+            // Check for duplicate versions in versioned migrations
+            let mut versions = std::collections::HashSet::new();
+            for migration in &namespace_migrations {
+                if let Some(version) = migration.version
+                    && !versions.insert(version)
+                {
                     issues.push(format!(
                         "Duplicate version {} found in {}",
                         version, migration.filename()
@@ -38,7 +75,7 @@ impl Validator {
         // Check for duplicate names in repeatable migrations
         let mut repeatable_names = std::collections::HashSet::new();
         for migration in &repeatable_migrations {
-            if !repeatable_names.insert(&migration.name) {
+            if !repeatable_names.insert(migration.identifier()) {
                 issues.push(format!(
                     "Duplicate repeatable migration name '{}' found in {}",
                     migration.name, migration.filename()
@@ -46,6 +83,100 @@ impl Validator {
             }
         }
 
+        // Other id strategies may have no id for a migration, or give two the same one
+        let mut ids = std::collections::HashSet::new();
+        for migration in migrations {
+            let strategy = &migration.id_strategy;
+            if strategy.name() != "version" {
+                match strategy.migration_id(migration) {
+                    Err(reason) => issues.push(format!("{} has no {} id: {}", migration.filename(), strategy.name(), reason)),
+                    Ok(_) if !ids.insert(migration.identifier()) => {
+                        issues.push(format!("Duplicate migration id '{}' found in {}", migration.identifier(), migration.filename()))
+                    }
+                    Ok(_) => {}
+                }
+            }
+        }
+
+        issues.extend(Self::validate_dependencies(migrations));
+
+        issues
+    }
+
+    /// Check that declared `-- deriddl:requires` and `-- deriddl:after` dependencies exist
+    /// and are acyclic
+    pub fn validate_dependencies(migrations: &[Migration]) -> Vec<String> {
+        let mut issues = Vec::new();
+
+        let known_versions: std::collections::HashSet<(Option<&str>, u32)> = migrations
+            .iter()
+            .filter_map(|m| m.version.map(|v| (m.namespace.as_deref(), v)))
+            .collect();
+        let known_repeatables: std::collections::HashSet<(Option<&str>, &str)> = migrations
+            .iter()
+            .filter(|m| m.is_repeatable())
+            .map(|m| (m.namespace.as_deref(), m.name.as_str()))
+            .collect();
+
+        for migration in migrations {
+            for name in &migration.after {
+                if migration.is_repeatable() && &migration.name == name {
+                    issues.push(format!(
+                        "Migration {} declares it runs after itself",
+                        migration.filename()
+                    ));
+                } else if !known_repeatables.contains(&(migration.namespace.as_deref(), name.as_str())) {
+                    issues.push(format!(
+                        "Unsatisfiable dependency: {} runs after R__{} which does not exist",
+                        migration.filename(), name
+                    ));
+                }
+            }
+            for required in &migration.requires {
+                if migration.version == Some(*required) {
+                    issues.push(format!(
+                        "Migration {} declares a dependency on itself",
+                        migration.filename()
+                    ));
+                } else if !known_versions.contains(&(migration.namespace.as_deref(), *required)) {
+                    issues.push(format!(
+                        "Unsatisfiable dependency: {} requires version {} which does not exist",
+                        migration.filename(), required
+                    ));
+                }
+            }
+        }
+
+        if let Err(PlannerError::DependencyCycle(cyclic)) = Planner::order_migrations(migrations.to_vec()) {
+            issues.push(format!(
+                "Dependency cycle detected between migrations: {}",
+                cyclic.join(", ")
+            ));
+        }
+
         issues
     }
-}
\ No newline at end of file
+
+    /// Find applied versioned migrations that no longer have a file of their own but whose
+    /// recorded checksum matches a file under a different version or name. Applying that
+    /// file would run the same change a second time.
+    pub fn detect_renamed_migrations(applied: &[AppliedMigration], migrations: &[Migration]) -> Vec<RenamedMigration> {
+        applied
+            .iter()
+            .filter(|a| a.success && a.migration_type == MigrationType::Versioned && !a.filename.starts_with("baseline_"))
+            .filter(|a| !migrations.iter().any(|m| m.identifier() == a.migration_id))
+            .filter_map(|a| {
+                migrations
+                    .iter()
+                    .filter(|m| !m.is_repeatable())
+                    .filter(|m| !applied.iter().any(|other| other.migration_id == m.identifier()))
+                    .find(|m| m.matches_checksum(&a.checksum))
+                    .map(|m| RenamedMigration {
+                        migration_id: a.migration_id.clone(),
+                        applied_filename: a.filename.clone(),
+                        renamed_to: m.filename(),
+                    })
+            })
+            .collect()
+    }
+}
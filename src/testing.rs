@@ -0,0 +1,159 @@
+//! Helpers for applications that want to test their code against the schema their
+//! migrations produce.
+//!
+//! ```no_run
+//! use deriddl_rs::testing::TestDatabase;
+//!
+//! #[test]
+//! fn inserts_user() {
+//!     let db = TestDatabase::sqlite("migrations").unwrap();
+//!     db.in_rollback_transaction(|executor| {
+//!         executor.execute_query("INSERT INTO users (id, name) VALUES (1, 'a')")
+//!     })
+//!     .unwrap();
+//! }
+//! ```
+
+use crate::executor::{ConnectionError, DatabaseExecutor};
+use crate::orchestrator::apply::{run_apply, ApplyError, ApplyOptions, ApplyOutcome};
+use crate::orchestrator::guard::ProtectionGuard;
+use crate::orchestrator::lock::LockPolicy;
+use crate::orchestrator::migration_loader::LoaderOptions;
+use crate::tracker::Target;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// ODBC driver used for scratch SQLite databases
+pub const SQLITE_DRIVER: &str = "SQLite3";
+
+static NEXT_DATABASE: AtomicUsize = AtomicUsize::new(0);
+
+/// A database with every migration applied. Scratch SQLite databases are deleted when
+/// the value is dropped, so each test can create its own.
+pub struct TestDatabase {
+    target: Target,
+    sqlite_file: Option<PathBuf>,
+    applied: usize,
+}
+
+impl TestDatabase {
+    /// Creates a fresh SQLite file in the temp directory and applies the migrations in `migrations`
+    pub fn sqlite(migrations: impl AsRef<Path>) -> Result<Self, TestingError> {
+        Self::sqlite_with_options(migrations, &LoaderOptions::default())
+    }
+
+    /// Like [`sqlite`](Self::sqlite), loading migrations with the given options
+    /// (recursive sources, checksum normalization, ...)
+    pub fn sqlite_with_options(migrations: impl AsRef<Path>, loader: &LoaderOptions) -> Result<Self, TestingError> {
+        let file = scratch_sqlite_path();
+        let _ = fs::remove_file(&file);
+        let mut database = Self {
+            target: Target::new(&format!("Driver={{{}}};Database={};", SQLITE_DRIVER, file.display())),
+            sqlite_file: Some(file),
+            applied: 0,
+        };
+        database.applied = database.migrate(migrations.as_ref(), loader)?;
+        Ok(database)
+    }
+
+    /// Applies the migrations to an existing database, e.g. a Postgres service in CI.
+    /// The database is left in place on drop; combine with
+    /// [`in_rollback_transaction`](Self::in_rollback_transaction) to keep tests isolated.
+    pub fn connect(connection_string: &str, migrations: impl AsRef<Path>) -> Result<Self, TestingError> {
+        let mut database = Self {
+            target: Target::new(connection_string),
+            sqlite_file: None,
+            applied: 0,
+        };
+        database.applied = database.migrate(migrations.as_ref(), &LoaderOptions::default())?;
+        Ok(database)
+    }
+
+    fn migrate(&self, migrations: &Path, loader: &LoaderOptions) -> Result<usize, TestingError> {
+        let path = migrations
+            .to_str()
+            .ok_or_else(|| TestingError::InvalidPath(migrations.display().to_string()))?;
+        let guard = ProtectionGuard {
+            non_interactive: true,
+            ..Default::default()
+        };
+        let lock = LockPolicy::default();
+        let outcome = run_apply(&self.target, path, &ApplyOptions::new(loader, &guard, &lock))?;
+        Ok(match outcome {
+            ApplyOutcome::Applied(count) => count,
+            _ => 0,
+        })
+    }
+
+    pub fn connection_string(&self) -> &str {
+        &self.target.conn
+    }
+
+    /// Number of migrations applied when the database was created
+    pub fn applied(&self) -> usize {
+        self.applied
+    }
+
+    /// Runs `test` with a new connection to the database
+    pub fn with_executor<T, F>(&self, test: F) -> Result<T, ConnectionError>
+    where
+        F: FnOnce(&mut DatabaseExecutor) -> Result<T, ConnectionError>,
+    {
+        let manager = self.target.connection_manager()?;
+        let mut executor = manager.executor(&self.target.conn)?;
+        test(&mut executor)
+    }
+
+    /// Runs `test` inside a transaction that is always rolled back, so the database
+    /// is unchanged for the next test
+    pub fn in_rollback_transaction<T, F>(&self, test: F) -> Result<T, ConnectionError>
+    where
+        F: FnOnce(&mut DatabaseExecutor) -> Result<T, ConnectionError>,
+    {
+        self.with_executor(|executor| {
+            executor
+                .execute_query("BEGIN TRANSACTION")
+                .or_else(|_| executor.execute_query("START TRANSACTION"))?;
+            let result = test(executor);
+            executor.execute_query("ROLLBACK")?;
+            result
+        })
+    }
+}
+
+impl Drop for TestDatabase {
+    fn drop(&mut self) {
+        if let Some(file) = &self.sqlite_file {
+            let _ = fs::remove_file(file);
+        }
+    }
+}
+
+/// Unique per process and per database, so parallel tests never share a file
+fn scratch_sqlite_path() -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "deriddl-test-{}-{}.db",
+        std::process::id(),
+        NEXT_DATABASE.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TestingError {
+    #[error("Migrations path is not valid UTF-8: {0}")]
+    InvalidPath(String),
+
+    #[error("Failed to apply migrations: {0}")]
+    Apply(#[from] ApplyError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scratch_databases_do_not_collide() {
+        assert_ne!(scratch_sqlite_path(), scratch_sqlite_path());
+    }
+}
@@ -0,0 +1,224 @@
+use crate::executor::{ConnectionError, DatabaseExecutor};
+use crate::model::Migration;
+use crate::tracker::repeatable_history::{compress, decompress};
+use crate::tracker::tracker_sql::{string_literal, TrackerSql};
+use chrono::Utc;
+use log::debug;
+
+/// Table holding the content of every applied migration
+pub const CONTENT_TABLE: &str = "schema_migrations_content";
+/// Columns of [`CONTENT_TABLE`], in the order they are selected
+const CONTENT_COLUMNS: [&str; 6] = ["migration_id", "filename", "checksum", "recorded_at", "compression", "content"];
+
+/// The content of a migration file as it was applied
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchivedContent {
+    pub migration_id: String,
+    /// Name of the file the migration was applied from
+    pub filename: String,
+    pub checksum: String,
+    /// Unix time it was archived
+    pub recorded_at: i64,
+    pub content: String,
+}
+
+/// Archives the file content of an applied migration, gzip-compressed. Nothing is stored
+/// when the same content was archived before; streamed migrations are too large to keep.
+pub fn record_content(executor: &mut DatabaseExecutor, sql: &TrackerSql, migration: &Migration) -> Result<(), ConnectionError> {
+    if migration.streamed.is_some() {
+        debug!("Not archiving streamed migration {}", migration.filename());
+        return Ok(());
+    }
+    ensure_table(executor, sql)?;
+    let existing = executor.query_single_value(&format!(
+        "SELECT COUNT(*) FROM {} {}",
+        sql.tracker_table(CONTENT_TABLE),
+        matching(sql, &migration.identifier(), &migration.checksum)
+    ))?;
+    if existing.is_some_and(|count| count.trim() != "0") {
+        debug!("Content of {} is already archived", migration.identifier());
+        return Ok(());
+    }
+
+    let (compression, content) = compress(&migration.sql_content);
+    executor.execute_query(&insert_sql(sql, migration, Utc::now().timestamp(), compression, &content))?;
+    debug!("Archived content of {} ({})", migration.identifier(), compression);
+    Ok(())
+}
+
+/// Archives `content`, the migration's file content as compressed with `compression`
+fn insert_sql(sql: &TrackerSql, migration: &Migration, recorded_at: i64, compression: &str, content: &str) -> String {
+    format!(
+        "INSERT INTO {} ({}) VALUES ({}, {}, {}, {}, {}, {})",
+        sql.tracker_table(CONTENT_TABLE),
+        sql.columns(&CONTENT_COLUMNS),
+        string_literal(&migration.identifier()),
+        string_literal(&file_name(migration)),
+        string_literal(&migration.checksum),
+        recorded_at,
+        string_literal(compression),
+        string_literal(content)
+    )
+}
+
+/// Condition selecting the content of `migration_id` whose checksum is `checksum`
+fn matching(sql: &TrackerSql, migration_id: &str, checksum: &str) -> String {
+    format!(
+        "WHERE {} = {} AND {} = {}",
+        sql.column("migration_id"),
+        string_literal(migration_id),
+        sql.column("checksum"),
+        string_literal(checksum)
+    )
+}
+
+/// The archived content of `migration_id` whose checksum is `checksum`; None when it was
+/// not archived. Never creates the table, so it is safe in read-only sessions.
+pub fn archived_content(
+    executor: &mut DatabaseExecutor,
+    sql: &TrackerSql,
+    migration_id: &str,
+    checksum: &str,
+) -> Result<Option<ArchivedContent>, ConnectionError> {
+    Ok(query(executor, sql, &matching(sql, migration_id, checksum))?.into_iter().next())
+}
+
+/// Everything archived, newest first. Never creates the table.
+pub fn archived(executor: &mut DatabaseExecutor, sql: &TrackerSql) -> Result<Vec<ArchivedContent>, ConnectionError> {
+    query(executor, sql, "")
+}
+
+fn query(executor: &mut DatabaseExecutor, sql: &TrackerSql, condition: &str) -> Result<Vec<ArchivedContent>, ConnectionError> {
+    if executor.query_single_value(&count_sql(sql)).is_err() {
+        return Ok(Vec::new());
+    }
+    let rows = executor.query_rows(&select_sql(sql, condition))?;
+    rows.into_iter()
+        .map(|row| {
+            Ok(ArchivedContent {
+                migration_id: row[0].clone(),
+                filename: row[1].clone(),
+                checksum: row[2].clone(),
+                recorded_at: row[3].parse().unwrap_or_default(),
+                content: decompress(&row[4], &row[5])?,
+            })
+        })
+        .collect()
+}
+
+fn count_sql(sql: &TrackerSql) -> String {
+    format!("SELECT COUNT(*) FROM {}", sql.tracker_table(CONTENT_TABLE))
+}
+
+/// Archived rows matching `condition` (empty for all), newest first
+fn select_sql(sql: &TrackerSql, condition: &str) -> String {
+    format!(
+        "SELECT {} FROM {} {} ORDER BY {} DESC",
+        sql.columns(&CONTENT_COLUMNS),
+        sql.tracker_table(CONTENT_TABLE),
+        condition,
+        sql.column("recorded_at")
+    )
+}
+
+fn ensure_table(executor: &mut DatabaseExecutor, sql: &TrackerSql) -> Result<(), ConnectionError> {
+    if executor.query_single_value(&count_sql(sql)).is_ok() {
+        return Ok(());
+    }
+    debug!("Creating {} table", CONTENT_TABLE);
+    executor.execute_query(&create_table_sql(sql))
+}
+
+fn create_table_sql(sql: &TrackerSql) -> String {
+    let dialect = sql.dialect();
+    let types = &dialect.config().types;
+    let column_types = [
+        types.migration_id.as_str(),
+        &types.filename,
+        &types.checksum,
+        "BIGINT",
+        &types.migration_type,
+        dialect.text_type(),
+    ];
+    let columns: Vec<String> = CONTENT_COLUMNS
+        .iter()
+        .zip(column_types)
+        .map(|(name, sql_type)| format!("{} {} NOT NULL", sql.column(name), sql_type))
+        .collect();
+    format!("CREATE TABLE {} ({})", sql.tracker_table(CONTENT_TABLE), columns.join(", "))
+}
+
+/// Name of the migration's file on disk, kept so `restore-files` recreates it as it was
+fn file_name(migration: &Migration) -> String {
+    migration
+        .file_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map_or_else(|| migration.filename(), str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialects;
+
+    fn tracker_sql(dialect: &str) -> TrackerSql {
+        TrackerSql::new(dialects::get_dialect_with_config(Some(dialect), None, None).unwrap())
+    }
+
+    #[test]
+    fn test_content_archive_statements() {
+        let sql = tracker_sql("mysql");
+        let migration = Migration::new(
+            7,
+            "o'brien".to_string(),
+            "migrations/0007_o'brien.sql".into(),
+            "INSERT INTO notes VALUES ('hi');".to_string(),
+        );
+        let checksum = migration.checksum.clone();
+        assert_eq!(
+            insert_sql(&sql, &migration, 1718000000, "none", &migration.sql_content),
+            format!(
+                "INSERT INTO `schema_migrations_content` (`migration_id`, `filename`, `checksum`, `recorded_at`, `compression`, `content`) \
+                 VALUES ('{}', '0007_o''brien.sql', '{}', 1718000000, 'none', 'INSERT INTO notes VALUES (''hi'');')",
+                migration.identifier().replace('\'', "''"),
+                checksum
+            )
+        );
+        assert_eq!(
+            matching(&sql, "R__o'views", "abc"),
+            "WHERE `migration_id` = 'R__o''views' AND `checksum` = 'abc'"
+        );
+        assert_eq!(
+            select_sql(&sql, &matching(&sql, "7", "abc")),
+            "SELECT `migration_id`, `filename`, `checksum`, `recorded_at`, `compression`, `content` FROM `schema_migrations_content` \
+             WHERE `migration_id` = '7' AND `checksum` = 'abc' ORDER BY `recorded_at` DESC"
+        );
+
+        let databricks = tracker_sql("databricks").with_location(Some("main".to_string()), Some("ops".to_string()));
+        let create = create_table_sql(&databricks);
+        assert!(create.starts_with("CREATE TABLE `main`.`ops`.`schema_migrations_content` (`migration_id` STRING NOT NULL, "));
+        assert!(create.contains("`recorded_at` BIGINT NOT NULL"));
+        assert!(create.ends_with("`content` STRING NOT NULL)"));
+        assert_eq!(count_sql(&databricks), "SELECT COUNT(*) FROM `main`.`ops`.`schema_migrations_content`");
+    }
+
+    #[test]
+    fn test_archived_content_round_trips_through_the_insert() {
+        let sql = "-- +migrate Up\nCREATE TABLE notes (body TEXT DEFAULT 'none');\n".repeat(20);
+        let migration = Migration::new(1, "notes".to_string(), "0001_notes.sql".into(), sql.clone());
+        let (compression, content) = compress(&sql);
+        let insert = insert_sql(&tracker_sql("generic"), &migration, 0, compression, &content);
+
+        // The last literal of the INSERT, unescaped, is what the table stores
+        let (_, stored) = insert.rsplit_once(", '").unwrap();
+        let stored = stored.strip_suffix("')").unwrap().replace("''", "'");
+        assert_eq!(decompress(compression, &stored).unwrap(), sql);
+    }
+
+    #[test]
+    fn test_archives_only_the_file_name() {
+        let migration = Migration::new(3, "users".to_string(), "/deploy/migrations/0003_users.sql".into(), String::new());
+        assert_eq!(file_name(&migration), "0003_users.sql");
+    }
+}
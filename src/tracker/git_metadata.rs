@@ -0,0 +1,87 @@
+use git2::{Commit, Oid, Repository, Sort};
+use log::debug;
+use std::path::Path;
+
+/// Where a migration file came from in git, recorded alongside its schema_migrations row
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitMetadata {
+    /// Last commit that changed the file
+    pub commit: String,
+    /// Branch checked out at apply time; None on a detached HEAD (typical in CI)
+    pub branch: Option<String>,
+}
+
+impl GitMetadata {
+    /// None when the file is outside a repository or not committed yet
+    pub fn for_file(path: &Path) -> Option<Self> {
+        let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        let repository = Repository::discover(dir).ok()?;
+        let workdir = repository.workdir()?.canonicalize().ok()?;
+        let file = path.canonicalize().ok()?;
+        let relative = file.strip_prefix(&workdir).ok()?;
+
+        let commit = last_commit(&repository, relative)?;
+        let head = repository.head().ok()?;
+        let branch = head.is_branch().then(|| head.shorthand().map(str::to_string)).flatten();
+        debug!("Git metadata for {}: commit {}, branch {:?}", path.display(), commit, branch);
+        Some(Self { commit, branch })
+    }
+}
+
+/// The newest commit reachable from HEAD that changed `file` (relative to the work tree),
+/// like `git log -n 1 -- <file>`: one whose content of the file differs from every parent's
+fn last_commit(repository: &Repository, file: &Path) -> Option<String> {
+    let blob = |commit: &Commit| -> Option<Oid> { commit.tree().ok()?.get_path(file).ok().map(|entry| entry.id()) };
+    let mut walk = repository.revwalk().ok()?;
+    walk.push_head().ok()?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME).ok()?;
+    for oid in walk.flatten() {
+        let commit = repository.find_commit(oid).ok()?;
+        let Some(id) = blob(&commit) else { continue };
+        if commit.parents().all(|parent| blob(&parent) != Some(id)) {
+            return Some(commit.id().to_string());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn test_reads_last_commit_of_file_and_branch() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("0001_init.sql");
+        std::fs::write(&file, "CREATE TABLE t (id INTEGER);").unwrap();
+        assert_eq!(GitMetadata::for_file(&file), None);
+
+        for args in [
+            &["init", "-q", "-b", "release-2024"][..],
+            &["add", "0001_init.sql"],
+            &["-c", "user.name=test", "-c", "user.email=test@example.com", "commit", "-q", "-m", "init"],
+        ] {
+            assert!(Command::new("git").arg("-C").arg(dir.path()).args(args).status().unwrap().success());
+        }
+
+        let git = |args: &[&str]| {
+            let output = Command::new("git").arg("-C").arg(dir.path()).args(args).output().unwrap();
+            String::from_utf8(output.stdout).unwrap().trim().to_string()
+        };
+        let first = git(&["rev-parse", "HEAD"]);
+        std::fs::write(dir.path().join("0002_users.sql"), "CREATE TABLE users (id INTEGER);").unwrap();
+        git(&["add", "0002_users.sql"]);
+        git(&["-c", "user.name=test", "-c", "user.email=test@example.com", "commit", "-q", "-m", "users"]);
+
+        // The later commit did not touch the file
+        let metadata = GitMetadata::for_file(&file).unwrap();
+        assert_eq!(metadata.commit, first);
+        assert_eq!(metadata.branch.as_deref(), Some("release-2024"));
+
+        git(&["checkout", "-q", "--detach"]);
+        assert_eq!(GitMetadata::for_file(&file).unwrap().branch, None);
+        std::fs::write(dir.path().join("0003_new.sql"), "").unwrap();
+        assert_eq!(GitMetadata::for_file(&dir.path().join("0003_new.sql")), None);
+    }
+}
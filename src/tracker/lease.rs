@@ -0,0 +1,380 @@
+use crate::executor::{ConnectionError, ConnectionManager, DatabaseExecutor};
+use crate::tracker::tracker_sql::string_literal;
+use crate::tracker::Target;
+use chrono::Utc;
+use log::{debug, info, warn};
+use std::time::{Duration, Instant};
+
+/// Table holding the migration lock
+pub const LOCK_TABLE: &str = "deriddl_lock";
+
+/// Row id of the single migration lock in deriddl_lock
+const LOCK_ID: i32 = 1;
+
+/// A time-limited lock on the deriddl_lock table, so only one replica of a Job or
+/// Deployment runs migrations at a time. The holder renews the lease while it works;
+/// a lease left behind by a crashed pod expires after `ttl` and can be taken over.
+/// Plain SQL is used instead of session advisory locks so it works on every dialect.
+/// Every acquisition and renewal is also a heartbeat; a holder whose heartbeat is older
+/// than `stale_after` probably crashed and can be taken over with `steal_stale`.
+#[derive(Clone)]
+pub struct MigrationLease {
+    /// Keeps deriddl_lock next to the target's schema_migrations and records its run
+    target: Target,
+    owner: String,
+    ttl: Duration,
+    stale_after: Option<Duration>,
+    steal_stale: bool,
+}
+
+/// Who holds the lease, as read from deriddl_lock
+#[derive(Debug, Clone, PartialEq)]
+pub struct LockHolder {
+    pub owner: String,
+    /// Run id of the holder, when its deriddl records one
+    pub run_id: Option<String>,
+    pub lease_until: i64,
+    /// Unix time of the last heartbeat, when its deriddl records one
+    pub heartbeat_at: Option<i64>,
+}
+
+impl LockHolder {
+    /// Seconds since the last heartbeat at `now`
+    pub fn silent_for(&self, now: i64) -> Option<i64> {
+        self.heartbeat_at.map(|at| (now - at).max(0))
+    }
+
+    /// Whether the lease is still valid but the holder stopped sending heartbeats
+    pub fn is_stale(&self, now: i64, stale_after: Duration) -> bool {
+        self.lease_until >= now && self.silent_for(now).is_some_and(|secs| secs > stale_after.as_secs() as i64)
+    }
+
+    fn describe(&self) -> String {
+        match &self.run_id {
+            Some(run_id) => format!("{} (run {})", self.owner, run_id),
+            None => self.owner.clone(),
+        }
+    }
+}
+
+impl MigrationLease {
+    pub fn new(target: &Target, ttl: Duration) -> Self {
+        Self {
+            target: target.clone(),
+            owner: default_owner(),
+            ttl,
+            stale_after: None,
+            steal_stale: false,
+        }
+    }
+
+    /// Treats a holder without a heartbeat for longer than `stale_after` as crashed
+    pub fn with_stale_after(mut self, stale_after: Duration) -> Self {
+        self.stale_after = Some(stale_after);
+        self
+    }
+
+    /// Takes over a stale lease (`--steal-lock`) instead of waiting for it to expire
+    pub fn with_steal_stale(mut self, steal: bool) -> Self {
+        self.steal_stale = steal;
+        self
+    }
+
+    fn executor<'a>(&self, manager: &'a ConnectionManager) -> Result<DatabaseExecutor<'a>, ConnectionError> {
+        manager.executor(&self.target.conn)
+    }
+
+    fn lease_until(&self) -> i64 {
+        Utc::now().timestamp() + self.ttl.as_secs() as i64
+    }
+
+    fn table(&self) -> String {
+        self.target.sql.tracker_table(LOCK_TABLE)
+    }
+
+    fn column(&self, name: &str) -> String {
+        self.target.sql.column(name)
+    }
+
+    /// `lock_id = 1`, plus `extra` conditions
+    fn where_lock(&self, extra: &str) -> String {
+        format!("WHERE {} = {}{}", self.column("lock_id"), LOCK_ID, extra)
+    }
+
+    /// Makes us the holder, with a fresh lease and heartbeat at `now`
+    fn take_over(&self, now: i64) -> String {
+        format!(
+            "UPDATE {} SET {} = {}, {} = {}, {} = {}, {} = {}",
+            self.table(),
+            self.column("owner"),
+            string_literal(&self.owner),
+            self.column("lease_until"),
+            self.lease_until(),
+            self.column("heartbeat_at"),
+            now,
+            self.column("run_id"),
+            string_literal(self.target.run_id())
+        )
+    }
+
+    fn ensure_table(&self, executor: &mut DatabaseExecutor) -> Result<(), ConnectionError> {
+        if executor.query_single_value(&format!("SELECT COUNT(*) FROM {}", self.table())).is_ok() {
+            // Tables created before heartbeats were recorded get the columns added
+            let probe = format!("SELECT COUNT({}) FROM {}", self.column("heartbeat_at"), self.table());
+            if executor.query_single_value(&probe).is_err() {
+                debug!("Adding heartbeat columns to {}", LOCK_TABLE);
+                for (column, sql_type) in [("heartbeat_at", "BIGINT"), ("run_id", "VARCHAR(36)")] {
+                    executor.execute_query(&format!("ALTER TABLE {} ADD COLUMN {} {}", self.table(), self.column(column), sql_type))?;
+                }
+            }
+            return Ok(());
+        }
+        debug!("Creating {} table", LOCK_TABLE);
+        executor.execute_query(&self.create_table_sql())
+    }
+
+    fn create_table_sql(&self) -> String {
+        format!(
+            "CREATE TABLE {} ({} INTEGER PRIMARY KEY, {} VARCHAR(255) NOT NULL, {} BIGINT NOT NULL, {} BIGINT, {} VARCHAR(36))",
+            self.table(),
+            self.column("lock_id"),
+            self.column("owner"),
+            self.column("lease_until"),
+            self.column("heartbeat_at"),
+            self.column("run_id")
+        )
+    }
+
+    /// Takes the lease if it is free, expired or already ours. Returns false when
+    /// another owner holds an unexpired lease.
+    pub fn try_acquire(&self) -> Result<bool, ConnectionError> {
+        let manager = self.target.connection_manager()?;
+        let mut executor = self.executor(&manager)?;
+        self.ensure_table(&mut executor)?;
+
+        let now = Utc::now().timestamp();
+        executor.execute_query(&format!(
+            "{} {}",
+            self.take_over(now),
+            self.where_lock(&format!(
+                " AND ({} < {} OR {} = {})",
+                self.column("lease_until"),
+                now,
+                self.column("owner"),
+                string_literal(&self.owner)
+            ))
+        ))?;
+        // Fails on the primary key when the row already exists, which is fine
+        let _ = executor.execute_query(&self.insert_sql(now));
+
+        let holder = executor.query_single_value(&format!(
+            "SELECT {} FROM {} {}",
+            self.column("owner"),
+            self.table(),
+            self.where_lock("")
+        ))?;
+        debug!("Migration lock holder: {:?}", holder);
+        Ok(holder.as_deref() == Some(self.owner.as_str()))
+    }
+
+    fn insert_sql(&self, now: i64) -> String {
+        format!(
+            "INSERT INTO {} ({}, {}, {}, {}, {}) VALUES ({}, {}, {}, {}, {})",
+            self.table(),
+            self.column("lock_id"),
+            self.column("owner"),
+            self.column("lease_until"),
+            self.column("heartbeat_at"),
+            self.column("run_id"),
+            LOCK_ID,
+            string_literal(&self.owner),
+            self.lease_until(),
+            now,
+            string_literal(self.target.run_id())
+        )
+    }
+
+    /// The current holder of the lease, if any
+    pub fn holder(&self) -> Result<Option<LockHolder>, ConnectionError> {
+        let manager = self.target.connection_manager()?;
+        let mut executor = self.executor(&manager)?;
+        let rows = executor.query_rows(&format!(
+            "SELECT {}, {}, {}, {} FROM {} {}",
+            self.column("owner"),
+            self.column("run_id"),
+            self.column("lease_until"),
+            self.column("heartbeat_at"),
+            self.table(),
+            self.where_lock("")
+        ))?;
+        Ok(rows.first().and_then(|row| parse_holder(row)))
+    }
+
+    /// Takes the lease over from `holder`, unless someone else got to it first
+    fn steal(&self, holder: &LockHolder) -> Result<bool, ConnectionError> {
+        let manager = self.target.connection_manager()?;
+        let mut executor = self.executor(&manager)?;
+        executor.execute_query(&format!(
+            "{} {}",
+            self.take_over(Utc::now().timestamp()),
+            self.where_lock(&format!(" AND {} = {}", self.column("owner"), string_literal(&holder.owner)))
+        ))?;
+        self.try_acquire()
+    }
+
+    /// Polls until the lease is acquired or `wait` has passed. A stale holder is taken
+    /// over with `steal_stale`, and otherwise reported once while waiting for its lease
+    /// to expire.
+    pub fn acquire(&self, wait: Duration) -> Result<(), LeaseError> {
+        let started = Instant::now();
+        let mut announced = false;
+        let mut stale = None;
+        loop {
+            if self.try_acquire()? {
+                info!("🔒 Acquired migration lock as {}", self.owner);
+                return Ok(());
+            }
+            if let Some(stale_after) = self.stale_after {
+                let now = Utc::now().timestamp();
+                stale = self.holder()?.filter(|holder| holder.is_stale(now, stale_after));
+                if let Some(holder) = &stale {
+                    let silent_for = holder.silent_for(now).unwrap_or_default();
+                    if self.steal_stale {
+                        warn!(
+                            "⚠️  Stealing the migration lock from {}, silent for {}s. If it is still running, both will migrate at once.",
+                            holder.describe(),
+                            silent_for
+                        );
+                        if self.steal(holder)? {
+                            info!("🔒 Acquired migration lock as {}", self.owner);
+                            return Ok(());
+                        }
+                    } else if !announced {
+                        warn!(
+                            "⚠️  The migration lock holder {} has sent no heartbeat for {}s and probably crashed; \
+                             waiting for its lease to expire, or re-run with --steal-lock to take it over now",
+                            holder.describe(),
+                            silent_for
+                        );
+                        announced = true;
+                    }
+                }
+            }
+            if started.elapsed() >= wait {
+                return Err(match stale {
+                    Some(holder) => LeaseError::StaleHolder(holder.describe(), wait.as_secs()),
+                    None => LeaseError::Timeout(wait.as_secs()),
+                });
+            }
+            if !announced {
+                info!("Waiting for another deriddl instance to release the migration lock");
+                announced = true;
+            }
+            std::thread::sleep(Duration::from_secs(2).min(self.ttl));
+        }
+    }
+
+    /// Extends the lease; fails if it was lost to another owner in the meantime
+    pub fn renew(&self) -> Result<(), LeaseError> {
+        if self.try_acquire()? {
+            debug!("Renewed migration lock");
+            Ok(())
+        } else {
+            Err(LeaseError::Lost)
+        }
+    }
+
+    pub fn release(&self) -> Result<(), ConnectionError> {
+        let manager = self.target.connection_manager()?;
+        let mut executor = self.executor(&manager)?;
+        executor.execute_query(&format!(
+            "DELETE FROM {} {}",
+            self.table(),
+            self.where_lock(&format!(" AND {} = {}", self.column("owner"), string_literal(&self.owner)))
+        ))?;
+        info!("🔓 Released migration lock");
+        Ok(())
+    }
+}
+
+/// Releases the lease when dropped, including when apply fails
+pub struct LeaseGuard<'a>(pub &'a MigrationLease);
+
+impl Drop for LeaseGuard<'_> {
+    fn drop(&mut self) {
+        if let Err(e) = self.0.release() {
+            warn!("Failed to release migration lock: {}", e);
+        }
+    }
+}
+
+/// Pod name (`HOSTNAME` in Kubernetes) plus process id
+fn default_owner() -> String {
+    let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "deriddl".to_string());
+    format!("{}:{}", host, std::process::id())
+}
+
+fn parse_holder(row: &[String]) -> Option<LockHolder> {
+    let [owner, run_id, lease_until, heartbeat_at] = row else {
+        return None;
+    };
+    let present = |value: &String| Some(value.trim().to_string()).filter(|v| !v.is_empty() && v != "NULL");
+    Some(LockHolder {
+        owner: owner.clone(),
+        run_id: present(run_id),
+        lease_until: lease_until.trim().parse().ok()?,
+        heartbeat_at: present(heartbeat_at).and_then(|at| at.parse().ok()),
+    })
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LeaseError {
+    #[error("Timed out after {0}s waiting for the migration lock")]
+    Timeout(u64),
+
+    #[error("Timed out after {1}s waiting for the migration lock held by {0}, which sends no heartbeats; re-run with --steal-lock if it is no longer running")]
+    StaleHolder(String, u64),
+
+    #[error("Migration lock was taken over by another instance")]
+    Lost,
+
+    #[error("Database error: {0}")]
+    Connection(#[from] ConnectionError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tracker::TrackerSql;
+
+    #[test]
+    fn test_stale_holders_have_a_valid_lease_but_no_recent_heartbeat() {
+        let row = |heartbeat_at: &str| {
+            ["pod-a:7", "NULL", "1000", heartbeat_at].iter().map(|v| v.to_string()).collect::<Vec<_>>()
+        };
+        let holder = parse_holder(&row("900")).unwrap();
+        assert_eq!(holder.run_id, None);
+        assert!(holder.is_stale(950, Duration::from_secs(40)));
+        assert!(!holder.is_stale(930, Duration::from_secs(40)));
+        // An expired lease is simply taken over
+        assert!(!holder.is_stale(1001, Duration::from_secs(40)));
+        // Holders from before heartbeats were recorded are never considered stale
+        assert!(!parse_holder(&row("")).unwrap().is_stale(950, Duration::from_secs(40)));
+    }
+
+    #[test]
+    fn test_lock_table_is_quoted_and_qualified_like_schema_migrations() {
+        let dialect = crate::dialects::get_dialect_with_config(Some("mysql"), None, None).unwrap();
+        let target = Target::new("Driver=x").with_sql(TrackerSql::new(dialect).with_location(None, Some("ops".to_string())));
+        let mut lease = MigrationLease::new(&target, Duration::from_secs(60));
+        lease.owner = "pod-a:7".to_string();
+        assert_eq!(
+            lease.create_table_sql(),
+            "CREATE TABLE `ops`.`deriddl_lock` (`lock_id` INTEGER PRIMARY KEY, `owner` VARCHAR(255) NOT NULL, \
+             `lease_until` BIGINT NOT NULL, `heartbeat_at` BIGINT, `run_id` VARCHAR(36))"
+        );
+        assert!(lease.insert_sql(1000).starts_with("INSERT INTO `ops`.`deriddl_lock` (`lock_id`, `owner`, "));
+        assert!(lease.take_over(1000).starts_with("UPDATE `ops`.`deriddl_lock` SET `owner` = 'pod-a:7', `lease_until` = "));
+        assert_eq!(lease.where_lock(""), "WHERE `lock_id` = 1");
+    }
+}
@@ -1,4 +1,17 @@
+pub mod content_archive;
+pub mod git_metadata;
+pub mod lease;
+pub mod repeatable_history;
+pub mod run_progress;
 pub mod schema_init;
+pub mod sql_log;
+pub mod target;
+pub mod tracker_sql;
 pub mod version_store;
 
+pub use lease::MigrationLease;
+pub use run_progress::RunProgress;
+pub use sql_log::SqlLog;
+pub use target::Target;
+pub use tracker_sql::TrackerSql;
 pub use version_store::VersionStore;
\ No newline at end of file
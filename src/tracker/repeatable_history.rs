@@ -0,0 +1,143 @@
+use crate::executor::{ConnectionError, DatabaseExecutor};
+use crate::model::Migration;
+use crate::tracker::tracker_sql::TrackerSql;
+use chrono::Utc;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use log::debug;
+use std::io::{Read, Write};
+
+/// Table holding every applied definition of repeatable migrations
+pub const HISTORY_TABLE: &str = "deriddl_repeatable_history";
+
+/// A definition of a repeatable migration as it was applied
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredDefinition {
+    pub migration_id: String,
+    pub checksum: String,
+    /// Unix time it was recorded
+    pub recorded_at: i64,
+    pub sql: String,
+}
+
+/// Stores the up SQL of an applied repeatable migration, gzip-compressed. Nothing is
+/// stored when the newest definition has the same checksum.
+pub fn record_definition(executor: &mut DatabaseExecutor, sql: &TrackerSql, migration: &Migration) -> Result<(), ConnectionError> {
+    ensure_table(executor, sql)?;
+    let table = sql.tracker_table(HISTORY_TABLE);
+    let id = escape(&migration.identifier());
+    let newest = executor.query_single_value(&format!(
+        "SELECT checksum FROM {} WHERE migration_id = '{}' ORDER BY recorded_at DESC",
+        table, id
+    ))?;
+    if newest.as_deref() == Some(migration.checksum.as_str()) {
+        debug!("Definition of {} is already recorded", migration.identifier());
+        return Ok(());
+    }
+
+    let up_sql = migration
+        .up_sql()
+        .map_err(|e| ConnectionError::Other(format!("Failed to read {}: {}", migration.file_path.display(), e)))?;
+    let (compression, content) = compress(&up_sql);
+    executor.execute_query(&format!(
+        "INSERT INTO {} (migration_id, checksum, recorded_at, compression, content) VALUES ('{}', '{}', {}, '{}', '{}')",
+        table,
+        id,
+        escape(&migration.checksum),
+        Utc::now().timestamp(),
+        compression,
+        escape(&content)
+    ))?;
+    debug!("Recorded definition of {} ({})", migration.identifier(), compression);
+    Ok(())
+}
+
+/// Recorded definitions of a repeatable migration, newest first
+pub fn definitions(
+    executor: &mut DatabaseExecutor,
+    sql: &TrackerSql,
+    migration_id: &str,
+) -> Result<Vec<StoredDefinition>, ConnectionError> {
+    let table = sql.tracker_table(HISTORY_TABLE);
+    if executor.query_single_value(&format!("SELECT COUNT(*) FROM {}", table)).is_err() {
+        return Ok(Vec::new());
+    }
+    let rows = executor.query_rows(&format!(
+        "SELECT migration_id, checksum, recorded_at, compression, content FROM {} WHERE migration_id = '{}' ORDER BY recorded_at DESC",
+        table,
+        escape(migration_id)
+    ))?;
+    rows.into_iter()
+        .map(|row| {
+            Ok(StoredDefinition {
+                migration_id: row[0].clone(),
+                checksum: row[1].clone(),
+                recorded_at: row[2].parse().unwrap_or_default(),
+                sql: decompress(&row[3], &row[4])?,
+            })
+        })
+        .collect()
+}
+
+fn ensure_table(executor: &mut DatabaseExecutor, sql: &TrackerSql) -> Result<(), ConnectionError> {
+    let table = sql.tracker_table(HISTORY_TABLE);
+    if executor.query_single_value(&format!("SELECT COUNT(*) FROM {}", table)).is_ok() {
+        return Ok(());
+    }
+    let dialect = sql.dialect();
+    let types = &dialect.config().types;
+    debug!("Creating {} table", HISTORY_TABLE);
+    executor.execute_query(&format!(
+        "CREATE TABLE {} (migration_id {} NOT NULL, checksum {} NOT NULL, recorded_at BIGINT NOT NULL, compression {} NOT NULL, content {} NOT NULL)",
+        table,
+        types.migration_id,
+        types.checksum,
+        types.migration_type,
+        dialect.text_type()
+    ))
+}
+
+/// gzip, hex-encoded so it fits a text column
+pub(crate) fn compress(sql: &str) -> (&'static str, String) {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    let gzipped = encoder.write_all(sql.as_bytes()).and_then(|_| encoder.finish());
+    match gzipped {
+        Ok(bytes) => ("gzip", hex::encode(bytes)),
+        Err(_) => ("none", sql.to_string()),
+    }
+}
+
+/// Reverses [`compress`]; `none` is the SQL as is
+pub(crate) fn decompress(compression: &str, content: &str) -> Result<String, ConnectionError> {
+    if compression == "none" {
+        return Ok(content.to_string());
+    }
+    let bytes = hex::decode(content.trim())
+        .map_err(|e| ConnectionError::Other(format!("Corrupt stored content: {}", e)))?;
+    let mut sql = String::new();
+    GzDecoder::new(bytes.as_slice())
+        .read_to_string(&mut sql)
+        .map_err(|e| ConnectionError::Other(format!("Corrupt stored content: {}", e)))?;
+    Ok(sql)
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\'', "''")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stored_definitions_round_trip() {
+        let sql = "CREATE OR REPLACE VIEW reporting AS SELECT 'it''s' AS note, id FROM orders;\n".repeat(20);
+        let (compression, content) = compress(&sql);
+        assert_eq!(compression, "gzip");
+        assert!(content.len() < sql.len());
+        assert_eq!(decompress(compression, &content).unwrap(), sql);
+        assert!(decompress("gzip", "00ff").is_err());
+        assert_eq!(decompress("none", "SELECT 1").unwrap(), "SELECT 1");
+    }
+}
@@ -0,0 +1,285 @@
+use crate::executor::{ConnectionError, ConnectionManager, DatabaseExecutor};
+use crate::tracker::tracker_sql::string_literal;
+use crate::tracker::Target;
+use chrono::{DateTime, Utc};
+use log::debug;
+
+/// Table holding one checkpoint row per `apply` run
+pub const RUNS_TABLE: &str = "deriddl_runs";
+/// Columns of [`RUNS_TABLE`], in the order they are selected
+const RUNS_COLUMNS: [&str; 7] = ["run_id", "status", "planned", "completed", "last_completed", "started_at", "updated_at"];
+
+/// Separates the migration ids of a run's plan; ids never contain a newline
+const PLANNED_SEPARATOR: char = '\n';
+
+/// Where an `apply` run stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunStatus {
+    /// Applying, or killed before it could record an outcome
+    Running,
+    Failed,
+    /// Stopped by SIGINT or SIGTERM
+    Interrupted,
+    /// Failed and undone with `behavior.on_failure = "rollback-batch"`
+    RolledBack,
+    Completed,
+}
+
+impl RunStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RunStatus::Running => "running",
+            RunStatus::Failed => "failed",
+            RunStatus::Interrupted => "interrupted",
+            RunStatus::RolledBack => "rolled_back",
+            RunStatus::Completed => "completed",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value.trim() {
+            "completed" => RunStatus::Completed,
+            "failed" => RunStatus::Failed,
+            "interrupted" => RunStatus::Interrupted,
+            "rolled_back" => RunStatus::RolledBack,
+            _ => RunStatus::Running,
+        }
+    }
+}
+
+/// Progress of one `apply` run, as recorded in deriddl_runs
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunCheckpoint {
+    pub run_id: String,
+    pub status: RunStatus,
+    /// Ids of the migrations the run set out to apply, in order
+    pub planned: Vec<String>,
+    /// How many of them were applied
+    pub completed: usize,
+    /// Id of the last migration applied
+    pub last_completed: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl RunCheckpoint {
+    /// The planned migrations not applied yet
+    pub fn remaining(&self) -> &[String] {
+        &self.planned[self.completed.min(self.planned.len())..]
+    }
+}
+
+/// Checkpoints of `apply` runs in the deriddl_runs table, so an interrupted run can be
+/// resumed with `apply --resume <run_id>` and `status` can show runs still in progress.
+/// Like the migration lease, plain SQL is used so it works on every dialect.
+#[derive(Clone)]
+pub struct RunProgress {
+    /// Keeps deriddl_runs next to the target's schema_migrations and records its run
+    target: Target,
+}
+
+impl RunProgress {
+    pub fn new(target: &Target) -> Self {
+        Self { target: target.clone() }
+    }
+
+    fn executor<'a>(&self, manager: &'a ConnectionManager) -> Result<DatabaseExecutor<'a>, ConnectionError> {
+        manager.executor(&self.target.conn)
+    }
+
+    fn table(&self) -> String {
+        self.target.sql.tracker_table(RUNS_TABLE)
+    }
+
+    /// `run_id = '<id>'`
+    fn where_run_id(&self, run_id: &str) -> String {
+        format!("WHERE {} = {}", self.target.sql.column("run_id"), string_literal(run_id))
+    }
+
+    fn ensure_table(&self, executor: &mut DatabaseExecutor) -> Result<(), ConnectionError> {
+        if executor.query_single_value(&format!("SELECT COUNT(*) FROM {}", self.table())).is_ok() {
+            return Ok(());
+        }
+        debug!("Creating {} table", RUNS_TABLE);
+        executor.execute_query(&self.create_table_sql())
+    }
+
+    fn create_table_sql(&self) -> String {
+        let column_types = [
+            "VARCHAR(36) PRIMARY KEY",
+            "VARCHAR(16) NOT NULL",
+            &format!("{} NOT NULL", self.target.sql.dialect().text_type()),
+            "INTEGER NOT NULL",
+            "VARCHAR(255)",
+            "BIGINT NOT NULL",
+            "BIGINT NOT NULL",
+        ];
+        let columns: Vec<String> = RUNS_COLUMNS
+            .iter()
+            .zip(column_types)
+            .map(|(name, sql_type)| format!("{} {}", self.target.sql.column(name), sql_type))
+            .collect();
+        format!("CREATE TABLE {} ({})", self.table(), columns.join(", "))
+    }
+
+    /// Sets the run's status and moves its `updated_at`, plus any `assignments`
+    fn update_sql(&self, status: Option<RunStatus>, assignments: &[String]) -> String {
+        let mut set: Vec<String> = status
+            .map(|status| format!("{} = {}", self.target.sql.column("status"), string_literal(status.as_str())))
+            .into_iter()
+            .collect();
+        set.extend_from_slice(assignments);
+        set.push(format!("{} = {}", self.target.sql.column("updated_at"), Utc::now().timestamp()));
+        format!("UPDATE {} SET {} {}", self.table(), set.join(", "), self.where_run_id(self.target.run_id()))
+    }
+
+    /// Records the start of this run, or of its resumption, with the migrations it applies
+    pub fn start(&self, planned: &[String]) -> Result<(), ConnectionError> {
+        let manager = self.target.connection_manager()?;
+        let mut executor = self.executor(&manager)?;
+        self.ensure_table(&mut executor)?;
+
+        let resumed = executor.query_single_value(&format!(
+            "SELECT {} FROM {} {}",
+            self.target.sql.column("status"),
+            self.table(),
+            self.where_run_id(self.target.run_id())
+        ))?;
+        if resumed.is_some() {
+            return executor.execute_query(&self.update_sql(Some(RunStatus::Running), &[]));
+        }
+        executor.execute_query(&self.insert_sql(planned, Utc::now().timestamp()))
+    }
+
+    fn insert_sql(&self, planned: &[String], now: i64) -> String {
+        let planned: Vec<&str> = planned.iter().map(String::as_str).collect();
+        format!(
+            "INSERT INTO {} ({}) VALUES ({}, {}, {}, 0, NULL, {}, {})",
+            self.table(),
+            self.target.sql.columns(&RUNS_COLUMNS),
+            string_literal(self.target.run_id()),
+            string_literal(RunStatus::Running.as_str()),
+            string_literal(&planned.join(&PLANNED_SEPARATOR.to_string())),
+            now,
+            now
+        )
+    }
+
+    /// Moves the checkpoint past `migration_id`
+    pub fn record_completed(&self, migration_id: &str) -> Result<(), ConnectionError> {
+        let manager = self.target.connection_manager()?;
+        let mut executor = self.executor(&manager)?;
+        let completed = self.target.sql.column("completed");
+        executor.execute_query(&self.update_sql(
+            None,
+            &[
+                format!("{} = {} + 1", completed, completed),
+                format!("{} = {}", self.target.sql.column("last_completed"), string_literal(migration_id)),
+            ],
+        ))
+    }
+
+    /// Records how this run ended
+    pub fn finish(&self, status: RunStatus) -> Result<(), ConnectionError> {
+        let manager = self.target.connection_manager()?;
+        let mut executor = self.executor(&manager)?;
+        executor.execute_query(&self.update_sql(Some(status), &[]))
+    }
+
+    /// The checkpoint of `run_id`; None when no such run was recorded
+    pub fn load(&self, run_id: &str) -> Result<Option<RunCheckpoint>, ConnectionError> {
+        Ok(self.query(&self.where_run_id(run_id))?.into_iter().next())
+    }
+
+    /// Runs that have not finished: live, killed mid-batch or stopped by a signal. Never
+    /// creates the table, so it is safe in read-only sessions; without one there are none.
+    pub fn in_progress(&self) -> Vec<RunCheckpoint> {
+        self.query(&format!(
+            "WHERE {} IN ({}, {}) ORDER BY {}",
+            self.target.sql.column("status"),
+            string_literal(RunStatus::Running.as_str()),
+            string_literal(RunStatus::Interrupted.as_str()),
+            self.target.sql.column("started_at")
+        ))
+            .unwrap_or_else(|e| {
+                debug!("No run checkpoints readable: {}", e);
+                Vec::new()
+            })
+    }
+
+    fn query(&self, condition: &str) -> Result<Vec<RunCheckpoint>, ConnectionError> {
+        let manager = self.target.connection_manager()?;
+        let mut executor = self.executor(&manager)?;
+        let rows = executor.query_rows(&self.select_sql(condition))?;
+        Ok(rows.iter().filter_map(|row| parse_checkpoint(row)).collect())
+    }
+
+    fn select_sql(&self, condition: &str) -> String {
+        format!("SELECT {} FROM {} {}", self.target.sql.columns(&RUNS_COLUMNS), self.table(), condition)
+    }
+}
+
+fn parse_checkpoint(row: &[String]) -> Option<RunCheckpoint> {
+    let [run_id, status, planned, completed, last_completed, started_at, updated_at] = row else {
+        return None;
+    };
+    let timestamp = |value: &str| value.trim().parse().ok().and_then(|secs| DateTime::from_timestamp(secs, 0));
+    Some(RunCheckpoint {
+        run_id: run_id.clone(),
+        status: RunStatus::parse(status),
+        planned: planned.split(PLANNED_SEPARATOR).filter(|id| !id.is_empty()).map(str::to_string).collect(),
+        completed: completed.trim().parse().unwrap_or(0),
+        last_completed: Some(last_completed.clone()).filter(|id| !id.is_empty() && id != "NULL"),
+        started_at: timestamp(started_at),
+        updated_at: timestamp(updated_at),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tracker::TrackerSql;
+
+    #[test]
+    fn test_parses_checkpoint_rows() {
+        let row: Vec<String> = ["8f0c", "running", "0001\n0002\nR__views", "1", "0001", "1718000000", "1718000060"]
+            .iter()
+            .map(|v| v.to_string())
+            .collect();
+        let checkpoint = parse_checkpoint(&row).unwrap();
+        assert_eq!(checkpoint.status, RunStatus::Running);
+        assert_eq!(checkpoint.remaining(), ["0002", "R__views"]);
+        assert_eq!(checkpoint.last_completed.as_deref(), Some("0001"));
+        assert_eq!(checkpoint.updated_at.unwrap().timestamp(), 1_718_000_060);
+
+        let mut fresh = row.clone();
+        fresh[3] = "0".to_string();
+        fresh[4] = "NULL".to_string();
+        let checkpoint = parse_checkpoint(&fresh).unwrap();
+        assert_eq!(checkpoint.last_completed, None);
+        assert_eq!(checkpoint.remaining().len(), 3);
+        assert!(parse_checkpoint(&row[..3]).is_none());
+
+        fresh[1] = "interrupted".to_string();
+        assert_eq!(parse_checkpoint(&fresh).unwrap().status, RunStatus::Interrupted);
+    }
+
+    #[test]
+    fn test_runs_table_is_quoted_and_qualified_like_schema_migrations() {
+        let dialect = crate::dialects::get_dialect_with_config(Some("databricks"), None, None).unwrap();
+        let progress = RunProgress::new(
+            &Target::new("Driver=x")
+                .with_sql(TrackerSql::new(dialect).with_location(Some("main".to_string()), Some("ops".to_string()))),
+        );
+        assert!(progress.create_table_sql().starts_with("CREATE TABLE `main`.`ops`.`deriddl_runs` (`run_id` VARCHAR(36) PRIMARY KEY, "));
+        assert!(progress.create_table_sql().ends_with("`updated_at` BIGINT NOT NULL)"));
+        assert_eq!(
+            progress.select_sql(&progress.where_run_id("o'k")),
+            "SELECT `run_id`, `status`, `planned`, `completed`, `last_completed`, `started_at`, `updated_at` \
+             FROM `main`.`ops`.`deriddl_runs` WHERE `run_id` = 'o''k'"
+        );
+        let insert = progress.insert_sql(&["0001".to_string(), "0002".to_string()], 1718000000);
+        assert!(insert.starts_with("INSERT INTO `main`.`ops`.`deriddl_runs` (`run_id`, "));
+        assert!(insert.ends_with(", 'running', '0001\n0002', 0, NULL, 1718000000, 1718000000)"));
+    }
+}
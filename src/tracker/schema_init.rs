@@ -1,51 +1,98 @@
-use crate::dialects;
-use crate::executor::{ConnectionError, ConnectionManager, DatabaseExecutor};
-use log::{debug, error, info};
+use crate::dialects::{self, DatabaseDialect, DialectError};
+use crate::executor::{ConnectionError, DatabaseExecutor};
+use crate::tracker::{Target, TrackerSql};
+use log::{debug, error, info, warn};
+use std::sync::Arc;
 
-pub fn init_migration_table(conn_string: &str) -> Result<(), ConnectionError> {
-    init_migration_table_with_config(conn_string, None)
+/// Columns of the current schema_migrations layout, which deriddl reads and writes
+pub const MIGRATION_TABLE_COLUMNS: [&str; 8] = [
+    "migration_id",
+    "migration_type",
+    "version",
+    "filename",
+    "checksum",
+    "applied_at",
+    "execution_time_ms",
+    "success",
+];
+
+/// Optional column naming the release a migration was applied in (`apply --release`)
+pub const RELEASE_COLUMN: &str = "release";
+/// Optional columns holding the git commit that last changed the migration file and the
+/// branch checked out when it was applied
+pub const GIT_COMMIT_COLUMN: &str = "git_commit";
+pub const GIT_BRANCH_COLUMN: &str = "git_branch";
+/// Column of the rollbacks table holding when a record was rolled back, see
+/// [`crate::tracker::tracker_sql::ROLLBACKS_TABLE`]
+pub const ROLLED_BACK_AT_COLUMN: &str = "rolled_back_at";
+/// Optional column holding the id of the deriddl run that applied the migration
+pub const RUN_ID_COLUMN: &str = "run_id";
+/// Optional column telling how a record was made when deriddl did not run the migration,
+/// see [`crate::tracker::version_store::RecordStatus`]. Added by [`upgrade_migration_table`].
+pub const STATUS_COLUMN: &str = "status";
+
+/// Columns added after the original layout. Tables created before they existed keep
+/// working; the values are just not recorded there.
+pub const OPTIONAL_MIGRATION_TABLE_COLUMNS: [&str; 5] =
+    [RELEASE_COLUMN, GIT_COMMIT_COLUMN, GIT_BRANCH_COLUMN, RUN_ID_COLUMN, STATUS_COLUMN];
+
+/// Shape of an existing schema_migrations table compared to the current layout
+#[derive(Debug, Clone, PartialEq)]
+pub enum MigrationTableLayout {
+    Missing,
+    Current,
+    /// Created by an older release; lists the columns that are missing
+    Outdated(Vec<String>),
+}
+
+/// Creates schema_migrations and its indexes with the target's dialect
+pub fn init_migration_table(target: &Target) -> Result<(), ConnectionError> {
+    info!("Initializing schema_migrations table");
+    create_migration_table(target, &target.sql)
 }
 
+/// Creates schema_migrations and its indexes on the target with the dialect named by
+/// `config_dialect`, or the one detected from the connection string when there is none.
+/// When `strict` (`migrations.dialect_strict`), detecting only the generic dialect fails.
 pub fn init_migration_table_with_config(
-    conn_string: &str,
+    target: &Target,
     config_dialect: Option<&str>,
+    strict: bool,
 ) -> Result<(), ConnectionError> {
     info!("Initializing schema_migrations table");
-    debug!("Connection string length: {}", conn_string.len());
-
-    let connection_manager = ConnectionManager::new()?;
-    let connection = connection_manager.connect(conn_string)?;
-    let mut executor = DatabaseExecutor::new(connection);
-
-    // Get dialect with config support
-    let dialect = match dialects::get_dialect_with_config(None, Some(conn_string), config_dialect) {
-        Ok(dialect) => {
-            info!(
-                "Using database dialect: {} (source: {})",
-                dialect.name(),
-                if config_dialect.is_some() {
-                    "config"
-                } else {
-                    "auto-detected"
-                }
-            );
-            dialect
-        }
-        Err(e) => {
-            error!("Failed to get dialect: {}", e);
-            return Err(ConnectionError::Other(format!("Dialect error: {}", e)));
-        }
-    };
+    let (dialect, source) = resolve_dialect(&target.conn, config_dialect, strict)
+        .map_err(|e| ConnectionError::Other(format!("Dialect error: {}", e)))?;
+    info!("Using database dialect: {} (source: {})", dialect.name(), source);
+    create_migration_table(target, &target.sql.clone().with_dialect(dialect))
+}
 
-    let create_table_sql = dialect.create_migrations_table_sql();
-    debug!(
-        "Creating schema_migrations table with dialect: {}",
-        dialect.name()
-    );
-    executor.execute_query(&create_table_sql)?;
+fn create_migration_table(target: &Target, tracker_sql: &TrackerSql) -> Result<(), ConnectionError> {
+    debug!("Connection string length: {}", target.conn.len());
+    let dialect = tracker_sql.dialect();
+    let connection_manager = target.connection_manager()?;
+    let mut executor = connection_manager.executor(&target.conn)?;
+    let existed = executor.query_single_value(&tracker_sql.count()).is_ok();
+
+    debug!("Creating schema_migrations table with dialect: {}", dialect.name());
+    executor.execute_query(&dialect.create_migrations_table_sql())?;
+    if existed {
+        upgrade_migration_table(&mut executor, tracker_sql)?;
+    }
+
+    for index_sql in dialect.create_migrations_indexes_sql() {
+        debug!("Creating index: {}", index_sql);
+        if let Err(e) = executor.execute_query(&index_sql) {
+            // Re-running init on dialects without CREATE INDEX IF NOT EXISTS lands here
+            if existed {
+                debug!("Index not created, it probably exists already: {}", e);
+            } else {
+                warn!("⚠️  Could not create schema_migrations index: {}", e);
+            }
+        }
+    }
 
     // Verify table was created by querying it
-    match executor.query_single_value("SELECT COUNT(*) FROM schema_migrations") {
+    match executor.query_single_value(&tracker_sql.count()) {
         Ok(_) => {
             info!("✅ schema_migrations table initialized successfully");
             Ok(())
@@ -57,15 +104,52 @@ pub fn init_migration_table_with_config(
     }
 }
 
-pub fn check_migration_table_exists(conn_string: &str) -> Result<bool, ConnectionError> {
+/// Brings a schema_migrations created by deriddl 0.1, which had no status column, up to
+/// date by adding it. Does nothing on a current table; returns whether it upgraded.
+pub fn upgrade_migration_table(executor: &mut DatabaseExecutor, sql: &TrackerSql) -> Result<bool, ConnectionError> {
+    if executor.query_rows(&sql.probe_column(STATUS_COLUMN)).is_ok() {
+        return Ok(false);
+    }
+    info!("Upgrading schema_migrations: adding the {} column", STATUS_COLUMN);
+    executor.execute_query(&sql.add_status_column())?;
+    Ok(true)
+}
+
+/// The configured dialect, else the one the registry detects from the connection string,
+/// else the generic dialect, along with where it came from
+fn resolve_dialect(
+    conn_string: &str,
+    config_dialect: Option<&str>,
+    strict: bool,
+) -> Result<(Arc<dyn DatabaseDialect>, &'static str), DialectError> {
+    if let Some(name) = config_dialect {
+        if let Some(dialect) = dialects::get_dialect(name) {
+            return Ok((dialect, "config"));
+        }
+        warn!("⚠️  Unknown dialect '{}' in config", name);
+    }
+    match dialects::get_registry().lock().unwrap().detect(conn_string) {
+        // Generic matches every connection string, so it is no more confident than the fallback
+        Ok(dialect) if strict && dialect.name() == "Generic" => Err(DialectError::NotFound(
+            "no dialect detected from the connection string; set migrations.dialect (migrations.dialect_strict forbids the generic fallback)"
+                .to_string(),
+        )),
+        Ok(dialect) => Ok((dialect, "detected from connection string")),
+        Err(e) => {
+            debug!("Dialect detection failed: {}", e);
+            dialects::get_dialect_with_config(None, None, None).map(|dialect| (dialect, "generic fallback"))
+        }
+    }
+}
+
+pub fn check_migration_table_exists(target: &Target) -> Result<bool, ConnectionError> {
     debug!("Checking if schema_migrations table exists");
 
-    let connection_manager = ConnectionManager::new()?;
-    let connection = connection_manager.connect(conn_string)?;
-    let mut executor = DatabaseExecutor::new(connection);
+    let connection_manager = target.connection_manager()?;
+    let mut executor = connection_manager.executor(&target.conn)?;
 
     // Try to query the table - if it fails, it probably doesn't exist
-    match executor.query_single_value("SELECT COUNT(*) FROM schema_migrations") {
+    match executor.query_single_value(&target.sql.count()) {
         Ok(_) => {
             debug!("schema_migrations table exists");
             Ok(true)
@@ -76,3 +160,62 @@ pub fn check_migration_table_exists(conn_string: &str) -> Result<bool, Connectio
         }
     }
 }
+
+/// Inspects schema_migrations column by column to tell a current table from an outdated one
+pub fn check_migration_table_layout(target: &Target) -> Result<MigrationTableLayout, ConnectionError> {
+    debug!("Checking schema_migrations layout");
+
+    let connection_manager = target.connection_manager()?;
+    let mut executor = connection_manager.executor(&target.conn)?;
+
+    let sql = &target.sql;
+    if executor.query_single_value(&sql.count()).is_err() {
+        return Ok(MigrationTableLayout::Missing);
+    }
+
+    let missing: Vec<String> = MIGRATION_TABLE_COLUMNS
+        .iter()
+        .filter(|column| {
+            executor
+                .query_rows(&sql.probe_column(column))
+                .is_err()
+        })
+        .map(|column| column.to_string())
+        .collect();
+
+    if missing.is_empty() {
+        Ok(MigrationTableLayout::Current)
+    } else {
+        Ok(MigrationTableLayout::Outdated(missing))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolves_configured_dialect_and_its_indexes() {
+        let (dialect, source) = resolve_dialect("Driver={Whatever}", Some("mysql"), false).unwrap();
+        assert_eq!((dialect.name(), source), ("MySQL", "config"));
+        assert_eq!(
+            dialect.create_migrations_indexes_sql()[0],
+            "CREATE INDEX `idx_schema_migrations_version` ON `schema_migrations` (`version`)"
+        );
+
+        let (dialect, _) = resolve_dialect("Driver={Whatever}", Some("postgres"), true).unwrap();
+        assert!(dialect.create_migrations_indexes_sql().iter().all(|sql| sql.starts_with("CREATE INDEX IF NOT EXISTS")));
+        let (dialect, _) = resolve_dialect("Driver={Whatever}", Some("sqlite"), false).unwrap();
+        assert!(dialect
+            .create_migrations_indexes_sql()
+            .contains(&r#"CREATE INDEX IF NOT EXISTS "idx_schema_migrations_type_version" ON "schema_migrations" ("migration_type", "version")"#.to_string()));
+    }
+
+    #[test]
+    fn test_strict_resolution_refuses_the_generic_fallback() {
+        assert_eq!(dialects::resolve_dialect(None, None, Some("oracle"), false).unwrap().name(), "Generic");
+        assert!(dialects::resolve_dialect(None, None, Some("oracle"), true).is_err());
+        assert_eq!(resolve_dialect("Driver={Whatever}", None, false).unwrap().0.name(), "Generic");
+        assert!(resolve_dialect("Driver={Whatever}", None, true).is_err());
+    }
+}
@@ -0,0 +1,79 @@
+use chrono::Utc;
+use log::{debug, warn};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Append-only audit log of SQL statements executed against the database.
+/// Each statement is preceded by a comment naming the migration and time, so the
+/// file can be reviewed or replayed as-is.
+pub struct SqlLog {
+    file: File,
+}
+
+impl SqlLog {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        debug!("Appending executed SQL to {}", path.display());
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { file })
+    }
+
+    /// Opens the log if a path is configured, warning instead of failing when it can't be opened
+    pub fn open_optional(path: Option<&Path>) -> Option<Self> {
+        let path = path?;
+        match Self::open(path) {
+            Ok(log) => Some(log),
+            Err(e) => {
+                warn!("Could not open SQL log {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Records one executed statement and the operation it belonged to (e.g. `apply 0003_add_index.sql`)
+    pub fn record(&mut self, operation: &str, statement: &str) {
+        let entry = format!(
+            "-- {} {}\n{};\n\n",
+            Utc::now().format("%Y-%m-%dT%H:%M:%SZ"),
+            operation,
+            statement.trim().trim_end_matches(';')
+        );
+        if let Err(e) = self.file.write_all(entry.as_bytes()) {
+            warn!("Failed to write to SQL log: {}", e);
+        }
+    }
+
+    /// Records the result of a captured statement as comment lines after it
+    pub fn record_result(&mut self, lines: &[String]) {
+        let entry: String = lines.iter().map(|line| format!("-- result: {}\n", line)).collect();
+        if let Err(e) = self.file.write_all(format!("{}\n", entry).as_bytes()) {
+            warn!("Failed to write to SQL log: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_record_appends_replayable_statements() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("audit").join("executed.sql");
+
+        let mut log = SqlLog::open(&path).unwrap();
+        log.record("apply 0001_init.sql", "CREATE TABLE a (id INT)");
+        log.record("apply 0002_seed.sql", "INSERT INTO a VALUES (1);");
+        log.record("apply 0002_seed.sql", "SELECT COUNT(*) AS n FROM a");
+        log.record_result(&["n".to_string(), "1".to_string()]);
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("apply 0001_init.sql\nCREATE TABLE a (id INT);\n"));
+        assert!(content.contains("apply 0002_seed.sql\nINSERT INTO a VALUES (1);\n"));
+        assert!(content.contains("SELECT COUNT(*) AS n FROM a;\n\n-- result: n\n-- result: 1\n"));
+    }
+}
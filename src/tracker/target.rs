@@ -0,0 +1,46 @@
+use crate::executor::{ConnectionError, ConnectionManager, ConnectionSettings};
+use crate::tracker::{TrackerSql, VersionStore};
+
+/// The database a command works on: its connection string, how the run connects and sends
+/// statements there, and the SQL for deriddl's tables in it. Built once per command from
+/// the configuration.
+#[derive(Clone)]
+pub struct Target {
+    pub conn: String,
+    pub connection: ConnectionSettings,
+    pub sql: TrackerSql,
+}
+
+impl Target {
+    /// A run of its own on `conn` with the driver's defaults and the generic dialect
+    pub fn new(conn: &str) -> Self {
+        Self { conn: conn.to_string(), connection: ConnectionSettings::default(), sql: TrackerSql::for_dialect(None) }
+    }
+
+    pub fn with_connection(mut self, connection: ConnectionSettings) -> Self {
+        self.connection = connection;
+        self
+    }
+
+    pub fn with_sql(mut self, sql: TrackerSql) -> Self {
+        self.sql = sql;
+        self
+    }
+
+    /// The same run on another database, e.g. a sandbox
+    pub fn on(&self, conn: &str) -> Self {
+        Self { conn: conn.to_string(), ..self.clone() }
+    }
+
+    pub fn run_id(&self) -> &str {
+        &self.connection.run_id
+    }
+
+    pub fn connection_manager(&self) -> Result<ConnectionManager, ConnectionError> {
+        ConnectionManager::with_settings(self.connection.clone())
+    }
+
+    pub fn version_store(&self) -> Result<VersionStore, ConnectionError> {
+        Ok(VersionStore::for_target(&self.conn, self.connection_manager()?, self.sql.clone()))
+    }
+}
@@ -0,0 +1,596 @@
+use crate::dialects::{self, DatabaseDialect, DialectSettings};
+use crate::model::{Migration, MigrationType};
+use std::sync::Arc;
+
+/// Name of the table deriddl records applied migrations in
+pub const MIGRATION_TABLE: &str = "schema_migrations";
+/// Table keeping each rolled back record, with when it was rolled back. It lives next to
+/// schema_migrations and is created by the first rollback.
+pub const ROLLBACKS_TABLE: &str = "schema_migrations_rollbacks";
+/// Columns [`TrackerSql::select_rollbacks`] selects after the fixed ones: every optional
+/// column of schema_migrations, then when the record was rolled back
+pub const ROLLBACK_OPTIONAL_COLUMNS: [&str; 6] = [
+    super::schema_init::RELEASE_COLUMN,
+    super::schema_init::GIT_COMMIT_COLUMN,
+    super::schema_init::GIT_BRANCH_COLUMN,
+    super::schema_init::RUN_ID_COLUMN,
+    super::schema_init::STATUS_COLUMN,
+    super::schema_init::ROLLED_BACK_AT_COLUMN,
+];
+
+/// Most rows one batched statement writes, see [`TrackerSql::migrations_start`]
+const BATCH_ROWS: usize = 100;
+
+/// Builds the statements that read and write schema_migrations, quoting identifiers
+/// and formatting boolean and timestamp literals the way the dialect expects
+#[derive(Clone)]
+pub struct TrackerSql {
+    dialect: Arc<dyn DatabaseDialect>,
+    catalog: Option<String>,
+    schema: Option<String>,
+}
+
+impl TrackerSql {
+    /// Leaves schema_migrations unqualified, see [`Self::with_location`]
+    pub fn new(dialect: Arc<dyn DatabaseDialect>) -> Self {
+        Self { dialect, catalog: None, schema: None }
+    }
+
+    /// The SQL of the dialect named `name` (`migrations.dialect`), or of the generic
+    /// dialect when there is no such dialect
+    pub fn for_dialect(name: Option<&str>) -> Self {
+        let dialect = dialects::get_dialect_with_config(None, None, name).expect("the generic dialect is always registered");
+        Self::new(dialect)
+    }
+
+    /// The same location with another dialect's SQL
+    pub fn with_dialect(mut self, dialect: Arc<dyn DatabaseDialect>) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Qualifies schema_migrations with `catalog` (`--catalog` or `[dialect.databricks]
+    /// catalog`) and `schema` (`database.schema`)
+    pub fn with_location(mut self, catalog: Option<String>, schema: Option<String>) -> Self {
+        self.catalog = catalog;
+        self.schema = schema;
+        self
+    }
+
+    fn table(&self) -> String {
+        self.qualified_table(MIGRATION_TABLE, self.catalog.as_deref(), self.schema.as_deref())
+    }
+
+    fn rollbacks_table(&self) -> String {
+        self.qualified_table(ROLLBACKS_TABLE, self.catalog.as_deref(), self.schema.as_deref())
+    }
+
+    /// `name`, a table deriddl keeps next to schema_migrations, quoted and qualified the
+    /// same way
+    pub fn tracker_table(&self, name: &str) -> String {
+        self.qualified_table(name, self.catalog.as_deref(), self.schema.as_deref())
+    }
+
+    pub(crate) fn dialect(&self) -> &dyn DatabaseDialect {
+        self.dialect.as_ref()
+    }
+
+    /// A catalog without a schema is left to the session's `USE CATALOG`, since
+    /// `catalog.schema_migrations` would read as a schema name
+    fn qualified_table(&self, name: &str, catalog: Option<&str>, schema: Option<&str>) -> String {
+        let table = self.dialect.quote_identifier(name);
+        match (catalog, schema) {
+            (Some(catalog), Some(schema)) => {
+                format!("{}.{}.{}", self.dialect.quote_identifier(catalog), self.dialect.quote_identifier(schema), table)
+            }
+            (None, Some(schema)) => format!("{}.{}", self.dialect.quote_identifier(schema), table),
+            (_, None) => table,
+        }
+    }
+
+    /// The dialect's post-apply maintenance of schema_migrations, e.g. Delta OPTIMIZE/VACUUM
+    pub fn maintenance(&self, settings: &DialectSettings) -> Vec<String> {
+        self.dialect.post_run_maintenance_sql(settings, &self.table())
+    }
+
+    pub(crate) fn column(&self, name: &str) -> String {
+        self.dialect.quote_identifier(name)
+    }
+
+    pub(crate) fn columns(&self, names: &[&str]) -> String {
+        names.iter().map(|name| self.column(name)).collect::<Vec<_>>().join(", ")
+    }
+
+    fn boolean(&self, value: bool) -> &str {
+        if value {
+            self.dialect.boolean_true()
+        } else {
+            self.dialect.boolean_false()
+        }
+    }
+
+    /// Condition matching records whose run did not succeed
+    fn failed(&self) -> String {
+        format!("NOT ({})", self.dialect.is_true_sql(&self.column("success")))
+    }
+
+    /// `"migration_id" = '<id>'`, the condition every per-migration statement ends with
+    fn where_migration_id(&self, migration_id: &str) -> String {
+        format!("{} = {}", self.column("migration_id"), string_literal(migration_id))
+    }
+
+    /// Every row, with `success` evaluated through the dialect's boolean predicate so it
+    /// arrives as 1 or 0 whatever the column type. `optional` columns follow it, in order.
+    pub fn select_all(&self, optional: &[&str]) -> String {
+        let migration_type = self.column("migration_type");
+        format!(
+            "{} FROM {} ORDER BY CASE WHEN {} = 'versioned' THEN 0 ELSE 1 END, CASE WHEN {} = 'versioned' THEN {} ELSE 0 END, {}",
+            self.select_columns(optional),
+            self.table(),
+            migration_type,
+            migration_type,
+            self.column("version"),
+            self.column("filename")
+        )
+    }
+
+    fn select_columns(&self, optional: &[&str]) -> String {
+        let optional: String = optional.iter().map(|name| format!(", {}", self.column(name))).collect();
+        format!(
+            "SELECT {}, CASE WHEN {} THEN 1 ELSE 0 END{}",
+            self.columns(&super::schema_init::MIGRATION_TABLE_COLUMNS[..7]),
+            self.dialect.is_true_sql(&self.column("success")),
+            optional
+        )
+    }
+
+    pub fn count(&self) -> String {
+        format!("SELECT COUNT(*) FROM {}", self.table())
+    }
+
+    /// Query that fails when `column` does not exist, without reading any rows
+    pub fn probe_column(&self, column: &str) -> String {
+        format!("SELECT {} FROM {} WHERE 1 = 0", self.column(column), self.table())
+    }
+
+    /// SQL recording the start of a migration run. The first statement deletes the previous
+    /// record of a repeatable migration, or the record a failed run of a versioned one left
+    /// behind, so retrying it does not collide with the old row.
+    pub fn migration_start(&self, migration: &Migration) -> Vec<String> {
+        let mut queries = Vec::new();
+        if migration.is_repeatable() {
+            queries.push(format!(
+                "DELETE FROM {} WHERE {}",
+                self.table(),
+                self.where_migration_id(&migration.identifier())
+            ));
+        } else {
+            queries.push(format!(
+                "DELETE FROM {} WHERE {} AND {}",
+                self.table(),
+                self.where_migration_id(&migration.identifier()),
+                self.failed()
+            ));
+        }
+        queries.push(format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            self.table(),
+            self.columns(&super::schema_init::MIGRATION_TABLE_COLUMNS),
+            self.start_row(migration)
+        ));
+        queries
+    }
+
+    /// Values of a migration's record when it starts, not yet successful
+    fn start_row(&self, migration: &Migration) -> String {
+        let migration_type = match migration.migration_type {
+            MigrationType::Versioned => "versioned",
+            MigrationType::Repeatable => "repeatable",
+        };
+        let version = match migration.version {
+            Some(v) => v.to_string(),
+            None => "NULL".to_string(),
+        };
+        self.row(&migration.identifier(), migration_type, &version, &migration.filename(), &migration.checksum, false)
+    }
+
+    /// SQL marking a migration run as successful
+    pub fn migration_success(&self, migration: &Migration, execution_time_ms: i32) -> String {
+        format!(
+            "UPDATE {} SET {} = {}, {} = {}, {} = {} WHERE {}",
+            self.table(),
+            self.column("execution_time_ms"),
+            execution_time_ms,
+            self.column("success"),
+            self.boolean(true),
+            self.column("applied_at"),
+            self.dialect.current_timestamp(),
+            self.where_migration_id(&migration.identifier())
+        )
+    }
+
+    /// SQL recording the start of several migrations in as few statements as possible: one
+    /// DELETE of the previous records of the repeatables among them, one of the failed
+    /// records of the versioned ones, then multi-row INSERTs. Each migration comes with
+    /// its values for the `optional` columns, NULL where it has none.
+    pub fn migrations_start(&self, migrations: &[(&Migration, Vec<Option<String>>)], optional: &[&str]) -> Vec<String> {
+        let mut queries = Vec::new();
+        let (repeatables, versioned): (Vec<_>, Vec<_>) = migrations
+            .iter()
+            .map(|(migration, _)| (migration.is_repeatable(), string_literal(&migration.identifier())))
+            .partition(|(repeatable, _)| *repeatable);
+        let ids = |rows: Vec<(bool, String)>| rows.into_iter().map(|(_, id)| id).collect::<Vec<_>>().join(", ");
+        if !repeatables.is_empty() {
+            queries.push(format!("DELETE FROM {} WHERE {} IN ({})", self.table(), self.column("migration_id"), ids(repeatables)));
+        }
+        if !versioned.is_empty() {
+            queries.push(format!(
+                "DELETE FROM {} WHERE {} IN ({}) AND {}",
+                self.table(),
+                self.column("migration_id"),
+                ids(versioned),
+                self.failed()
+            ));
+        }
+
+        let mut columns = super::schema_init::MIGRATION_TABLE_COLUMNS.to_vec();
+        columns.extend(optional);
+        for chunk in migrations.chunks(BATCH_ROWS) {
+            let rows: Vec<String> = chunk
+                .iter()
+                .map(|(migration, values)| {
+                    let values: String = values
+                        .iter()
+                        .map(|value| format!(", {}", value.as_deref().map_or_else(|| "NULL".to_string(), string_literal)))
+                        .collect();
+                    format!("({}{})", self.start_row(migration), values)
+                })
+                .collect();
+            queries.push(format!("INSERT INTO {} ({}) VALUES {}", self.table(), self.columns(&columns), rows.join(", ")));
+        }
+        queries
+    }
+
+    /// SQL marking several migration runs as successful, one UPDATE per batch with each
+    /// run's execution time picked by a CASE on its id
+    pub fn migrations_success(&self, results: &[(String, i32)]) -> Vec<String> {
+        let migration_id = self.column("migration_id");
+        results
+            .chunks(BATCH_ROWS)
+            .map(|chunk| {
+                let times: String = chunk
+                    .iter()
+                    .map(|(id, execution_time_ms)| format!(" WHEN {} THEN {}", string_literal(id), execution_time_ms))
+                    .collect();
+                let ids: Vec<String> = chunk.iter().map(|(id, _)| string_literal(id)).collect();
+                format!(
+                    "UPDATE {} SET {} = CASE {}{} END, {} = {}, {} = {} WHERE {} IN ({})",
+                    self.table(),
+                    self.column("execution_time_ms"),
+                    migration_id,
+                    times,
+                    self.column("success"),
+                    self.boolean(true),
+                    self.column("applied_at"),
+                    self.dialect.current_timestamp(),
+                    migration_id,
+                    ids.join(", ")
+                )
+            })
+            .collect()
+    }
+
+    pub fn migration_failure(&self, migration: &Migration, execution_time_ms: i32) -> String {
+        format!(
+            "UPDATE {} SET {} = {}, {} = {} WHERE {}",
+            self.table(),
+            self.column("execution_time_ms"),
+            execution_time_ms,
+            self.column("success"),
+            self.boolean(false),
+            self.where_migration_id(&migration.identifier())
+        )
+    }
+
+    /// Fills optional columns of a migration's record, e.g. the release it is applied in
+    pub fn annotate(&self, migration: &Migration, values: &[(&str, String)]) -> String {
+        let assignments: Vec<String> = values
+            .iter()
+            .map(|(column, value)| format!("{} = {}", self.column(column), string_literal(value)))
+            .collect();
+        format!(
+            "UPDATE {} SET {} WHERE {}",
+            self.table(),
+            assignments.join(", "),
+            self.where_migration_id(&migration.identifier())
+        )
+    }
+
+    /// Adds the status column to a schema_migrations created before it existed
+    pub fn add_status_column(&self) -> String {
+        format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            self.table(),
+            self.column(super::schema_init::STATUS_COLUMN),
+            self.dialect.config().types.status
+        )
+    }
+
+    pub fn baseline(&self, version: u32, filename: &str) -> String {
+        self.insert(&version.to_string(), "baseline", &version.to_string(), filename, "baseline", true)
+    }
+
+    /// Deletes the record of one migration, after [`Self::archive_rollback`] kept a copy
+    pub fn remove(&self, migration_id: &str) -> String {
+        format!("DELETE FROM {} WHERE {}", self.table(), self.where_migration_id(migration_id))
+    }
+
+    /// Creates the table rollbacks are archived in: the columns of schema_migrations,
+    /// optional ones included, and when the record was rolled back
+    pub fn create_rollbacks_table(&self) -> String {
+        let types = &self.dialect.config().types;
+        let columns = [
+            ("migration_id", types.migration_id.as_str(), " NOT NULL"),
+            ("migration_type", &types.migration_type, " NOT NULL"),
+            ("version", &types.version, ""),
+            ("filename", &types.filename, " NOT NULL"),
+            ("checksum", &types.checksum, " NOT NULL"),
+            ("applied_at", &types.applied_at, " NOT NULL"),
+            ("execution_time_ms", &types.execution_time_ms, " NOT NULL"),
+            ("success", &types.success, " NOT NULL"),
+            (super::schema_init::RELEASE_COLUMN, &types.release, ""),
+            (super::schema_init::GIT_COMMIT_COLUMN, &types.git_commit, ""),
+            (super::schema_init::GIT_BRANCH_COLUMN, &types.git_branch, ""),
+            (super::schema_init::RUN_ID_COLUMN, &types.run_id, ""),
+            (super::schema_init::STATUS_COLUMN, &types.status, ""),
+            (super::schema_init::ROLLED_BACK_AT_COLUMN, &types.applied_at, " NOT NULL"),
+        ];
+        let columns: Vec<String> =
+            columns.iter().map(|(name, sql_type, null)| format!("{} {}{}", self.column(name), sql_type, null)).collect();
+        format!("CREATE TABLE {} ({})", self.rollbacks_table(), columns.join(", "))
+    }
+
+    pub fn count_rollbacks(&self) -> String {
+        format!("SELECT COUNT(*) FROM {}", self.rollbacks_table())
+    }
+
+    /// Copies the record of a migration being rolled back into the rollbacks table, with
+    /// the current time as its `rolled_back_at`. `optional` lists the optional columns
+    /// schema_migrations has.
+    pub fn archive_rollback(&self, migration_id: &str, optional: &[&str]) -> String {
+        let mut columns = super::schema_init::MIGRATION_TABLE_COLUMNS.to_vec();
+        columns.extend(optional);
+        format!(
+            "INSERT INTO {} ({}, {}) SELECT {}, {} FROM {} WHERE {}",
+            self.rollbacks_table(),
+            self.columns(&columns),
+            self.column(super::schema_init::ROLLED_BACK_AT_COLUMN),
+            self.columns(&columns),
+            self.dialect.current_timestamp(),
+            self.table(),
+            self.where_migration_id(migration_id)
+        )
+    }
+
+    /// Every archived rollback, oldest first, in the shape of [`Self::select_all`] with
+    /// [`ROLLBACK_OPTIONAL_COLUMNS`]
+    pub fn select_rollbacks(&self) -> String {
+        format!(
+            "{} FROM {} ORDER BY {}",
+            self.select_columns(&ROLLBACK_OPTIONAL_COLUMNS),
+            self.rollbacks_table(),
+            self.column(super::schema_init::ROLLED_BACK_AT_COLUMN)
+        )
+    }
+
+    fn insert(
+        &self,
+        migration_id: &str,
+        migration_type: &str,
+        version: &str,
+        filename: &str,
+        checksum: &str,
+        success: bool,
+    ) -> String {
+        format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            self.table(),
+            self.columns(&super::schema_init::MIGRATION_TABLE_COLUMNS),
+            self.row(migration_id, migration_type, version, filename, checksum, success)
+        )
+    }
+
+    /// Values for [`super::schema_init::MIGRATION_TABLE_COLUMNS`], without parentheses
+    fn row(
+        &self,
+        migration_id: &str,
+        migration_type: &str,
+        version: &str,
+        filename: &str,
+        checksum: &str,
+        success: bool,
+    ) -> String {
+        format!(
+            "{}, '{}', {}, {}, {}, {}, 0, {}",
+            string_literal(migration_id),
+            migration_type,
+            version,
+            string_literal(filename),
+            string_literal(checksum),
+            self.dialect.current_timestamp(),
+            self.boolean(success)
+        )
+    }
+}
+
+pub(crate) fn string_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker_sql(dialect: &str) -> TrackerSql {
+        TrackerSql::new(dialects::get_dialect_with_config(Some(dialect), None, None).unwrap())
+    }
+
+    #[test]
+    fn test_statements_follow_dialect_quoting_and_literals() {
+        let migration = Migration::new_repeatable(
+            "o'brien_view".to_string(),
+            "R__o'brien_view.sql".into(),
+            "SELECT 1".to_string(),
+        );
+
+        let postgres = tracker_sql("postgres");
+        assert_eq!(
+            postgres.migration_start(&migration)[0],
+            "DELETE FROM \"schema_migrations\" WHERE \"migration_id\" = 'R__o''brien_view'"
+        );
+        assert!(postgres
+            .migration_success(&migration, 5)
+            .starts_with("UPDATE \"schema_migrations\" SET \"execution_time_ms\" = 5, \"success\" = TRUE, \"applied_at\" = CURRENT_TIMESTAMP"));
+
+        let versioned = Migration::new(2, "orders".to_string(), "0002_orders.sql".into(), "CREATE TABLE o (id INT)".to_string());
+        let start = postgres.migration_start(&versioned);
+        assert_eq!(start[0], "DELETE FROM \"schema_migrations\" WHERE \"migration_id\" = '2' AND NOT (\"success\" = TRUE)");
+        assert!(start[1].starts_with("INSERT INTO \"schema_migrations\""));
+
+        let sqlite = tracker_sql("sqlite");
+        assert!(sqlite.select_all(&[]).contains("\"execution_time_ms\", CASE WHEN \"success\" = 1 THEN 1 ELSE 0 END FROM"));
+        assert!(sqlite.select_all(&["release", "git_commit"]).contains("CASE WHEN \"success\" = 1 THEN 1 ELSE 0 END, \"release\", \"git_commit\" FROM"));
+        assert!(sqlite.migration_failure(&migration, 5).contains("\"success\" = 0"));
+        assert!(sqlite.baseline(3, "baseline_0003_init").ends_with("CURRENT_TIMESTAMP, 0, 1)"));
+
+        let mysql = tracker_sql("mysql");
+        assert_eq!(
+            mysql.remove("analytics:2"),
+            "DELETE FROM `schema_migrations` WHERE `migration_id` = 'analytics:2'"
+        );
+        assert_eq!(
+            mysql.annotate(&migration, &[("release", "2024.06".to_string()), ("git_branch", "main".to_string())]),
+            "UPDATE `schema_migrations` SET `release` = '2024.06', `git_branch` = 'main' WHERE `migration_id` = 'R__o''brien_view'"
+        );
+        assert_eq!(mysql.qualified_table(MIGRATION_TABLE, None, Some("billing")), "`billing`.`schema_migrations`");
+        let databricks = TrackerSql::new(dialects::get_dialect("databricks").unwrap());
+        assert_eq!(databricks.qualified_table(MIGRATION_TABLE, Some("main"), Some("ops")), "`main`.`ops`.`schema_migrations`");
+        assert_eq!(databricks.qualified_table(MIGRATION_TABLE, Some("main"), None), "`schema_migrations`");
+        assert_eq!(databricks.dialect.use_catalog_sql("main").as_deref(), Some("USE CATALOG `main`"));
+        assert_eq!(postgres.dialect.use_catalog_sql("main"), None);
+
+        let mut settings = DialectSettings::default();
+        assert!(databricks.maintenance(&settings).is_empty());
+        settings.databricks = Some(crate::dialects::databricks::DatabricksConfig { optimize_history: true, ..Default::default() });
+        assert_eq!(databricks.maintenance(&settings), vec!["OPTIMIZE `schema_migrations`", "VACUUM `schema_migrations`"]);
+        assert!(postgres.maintenance(&settings).is_empty());
+        assert_eq!(postgres.dialect.use_schema_sql("billing").as_deref(), Some("SET search_path TO \"billing\""));
+        assert_eq!(sqlite.dialect.use_schema_sql("billing"), None);
+    }
+
+    #[test]
+    fn test_upgrade_adds_the_status_column() {
+        let postgres = tracker_sql("postgres");
+        assert_eq!(postgres.add_status_column(), "ALTER TABLE \"schema_migrations\" ADD COLUMN \"status\" VARCHAR(32)");
+        assert_eq!(tracker_sql("databricks").add_status_column(), "ALTER TABLE `schema_migrations` ADD COLUMN `status` STRING");
+    }
+
+    #[test]
+    fn test_rollbacks_are_archived_under_the_same_id() {
+        let mysql = tracker_sql("mysql");
+        assert_eq!(
+            mysql.archive_rollback("0002", &["release", "run_id"]),
+            "INSERT INTO `schema_migrations_rollbacks` (`migration_id`, `migration_type`, `version`, `filename`, `checksum`, \
+             `applied_at`, `execution_time_ms`, `success`, `release`, `run_id`, `rolled_back_at`) SELECT `migration_id`, \
+             `migration_type`, `version`, `filename`, `checksum`, `applied_at`, `execution_time_ms`, `success`, `release`, \
+             `run_id`, CURRENT_TIMESTAMP FROM `schema_migrations` WHERE `migration_id` = '0002'"
+        );
+        assert_eq!(mysql.remove("0002"), "DELETE FROM `schema_migrations` WHERE `migration_id` = '0002'");
+        let create = mysql.create_rollbacks_table();
+        assert!(create.starts_with("CREATE TABLE `schema_migrations_rollbacks` (`migration_id` VARCHAR(255) NOT NULL, "));
+        assert!(create.ends_with("`run_id` VARCHAR(36), `status` VARCHAR(32), `rolled_back_at` TIMESTAMP NOT NULL)"));
+        assert!(mysql
+            .select_rollbacks()
+            .ends_with(", `release`, `git_commit`, `git_branch`, `run_id`, `status`, `rolled_back_at` FROM `schema_migrations_rollbacks` ORDER BY `rolled_back_at`"));
+
+        let located = tracker_sql("postgres").with_location(None, Some("ops".to_string()));
+        assert_eq!(located.count_rollbacks(), "SELECT COUNT(*) FROM \"ops\".\"schema_migrations_rollbacks\"");
+    }
+
+    #[test]
+    fn test_another_targets_location_qualifies_every_statement() {
+        let other = TrackerSql::new(dialects::get_dialect("databricks").unwrap())
+            .with_location(Some("analytics".to_string()), Some("ops".to_string()));
+        assert_eq!(other.count(), "SELECT COUNT(*) FROM `analytics`.`ops`.`schema_migrations`");
+        assert!(other.select_all(&[]).contains(" FROM `analytics`.`ops`.`schema_migrations` ORDER BY "));
+        let unqualified = tracker_sql("postgres").with_location(None, None);
+        assert_eq!(unqualified.count(), "SELECT COUNT(*) FROM \"schema_migrations\"");
+    }
+
+    #[test]
+    fn test_schema_and_catalog_qualify_every_tracker_statement() {
+        let table = Migration::new(2, "orders".to_string(), "0002_orders.sql".into(), "CREATE TABLE o (id INT)".to_string());
+        let statements = |sql: &TrackerSql| {
+            let mut statements = vec![sql.count(), sql.probe_column("release"), sql.select_all(&["release"]), sql.select_rollbacks()];
+            statements.extend(sql.migration_start(&table));
+            statements.extend(sql.migrations_start(&[(&table, Vec::new())], &[]));
+            statements.extend(sql.migrations_success(&[("2".to_string(), 5)]));
+            statements.extend([
+                sql.migration_success(&table, 5),
+                sql.migration_failure(&table, 5),
+                sql.annotate(&table, &[("release", "2024.06".to_string())]),
+                sql.add_status_column(),
+                sql.baseline(3, "baseline_0003_init"),
+                sql.remove("2"),
+                sql.archive_rollback("2", &[]),
+                sql.count_rollbacks(),
+                sql.create_rollbacks_table(),
+            ]);
+            statements
+        };
+
+        let postgres = tracker_sql("postgres").with_location(None, Some("app".to_string()));
+        for statement in statements(&postgres) {
+            let qualified = statement.contains("\"app\".\"schema_migrations\"") || statement.contains("\"app\".\"schema_migrations_rollbacks\"");
+            assert!(qualified, "not qualified with the schema: {}", statement);
+        }
+        let archive = postgres.archive_rollback("2", &[]);
+        assert!(archive.starts_with("INSERT INTO \"app\".\"schema_migrations_rollbacks\" "));
+        assert!(archive.contains(" FROM \"app\".\"schema_migrations\" WHERE "));
+
+        let databricks = TrackerSql::new(dialects::get_dialect("databricks").unwrap())
+            .with_location(Some("main".to_string()), Some("ops".to_string()));
+        for statement in statements(&databricks) {
+            assert!(statement.contains("`main`.`ops`.`schema_migrations"), "not qualified with the catalog: {}", statement);
+        }
+        // A catalog alone is selected by the session, so statements keep the bare name
+        let catalog_only = TrackerSql::new(dialects::get_dialect("databricks").unwrap()).with_location(Some("main".to_string()), None);
+        for statement in statements(&catalog_only) {
+            assert!(!statement.contains("`main`."), "qualified with the catalog alone: {}", statement);
+        }
+    }
+
+    #[test]
+    fn test_batched_statements_cover_every_migration() {
+        let view = Migration::new_repeatable("views".to_string(), "R__views.sql".into(), "SELECT 1".to_string());
+        let table = Migration::new(2, "orders".to_string(), "0002_orders.sql".into(), "CREATE TABLE o (id INT)".to_string());
+        let sqlite = tracker_sql("sqlite");
+
+        let start = sqlite.migrations_start(&[(&table, vec![Some("2024.06".to_string())]), (&view, vec![None])], &["release"]);
+        assert_eq!(start.len(), 3);
+        assert_eq!(start[0], "DELETE FROM \"schema_migrations\" WHERE \"migration_id\" IN ('R__views')");
+        assert_eq!(start[1], "DELETE FROM \"schema_migrations\" WHERE \"migration_id\" IN ('2') AND NOT (\"success\" = 1)");
+        assert!(start[2].contains("\"success\", \"release\") VALUES ('2', 'versioned', 2, "));
+        assert!(start[2].contains("CURRENT_TIMESTAMP, 0, 0, '2024.06'), ('R__views', 'repeatable', NULL, 'R__views.sql', "));
+        assert!(start[2].ends_with("CURRENT_TIMESTAMP, 0, 0, NULL)"));
+
+        let success = sqlite.migrations_success(&[("2".to_string(), 12), ("R__views".to_string(), -2)]);
+        assert_eq!(
+            success,
+            vec!["UPDATE \"schema_migrations\" SET \"execution_time_ms\" = CASE \"migration_id\" WHEN '2' THEN 12 WHEN 'R__views' THEN -2 END, \"success\" = 1, \"applied_at\" = CURRENT_TIMESTAMP WHERE \"migration_id\" IN ('2', 'R__views')"]
+        );
+
+        let many: Vec<(String, i32)> = (0..250).map(|i| (i.to_string(), i)).collect();
+        assert_eq!(sqlite.migrations_success(&many).len(), 3);
+    }
+}
@@ -1,7 +1,11 @@
+use crate::executor::typed::TypedRow;
 use crate::executor::{ConnectionError, ConnectionManager, DatabaseExecutor};
+use crate::tracker::git_metadata::GitMetadata;
+use crate::tracker::{schema_init, tracker_sql, Target, TrackerSql};
 use crate::model::{Migration, MigrationType};
 use chrono::{DateTime, Utc};
-use log::{debug, info};
+use log::{debug, info, warn};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
 pub struct AppliedMigration {
@@ -13,122 +17,334 @@ pub struct AppliedMigration {
     pub applied_at: DateTime<Utc>,
     pub execution_time_ms: i32,
     pub success: bool,
+    /// Release the migration was applied in, if one was given and the table records it
+    pub release: Option<String>,
+    /// Last git commit of the migration file at apply time, when applied from a repository
+    pub git_commit: Option<String>,
+    pub git_branch: Option<String>,
+    /// When the migration was rolled back; such records are kept for auditing only
+    pub rolled_back_at: Option<DateTime<Utc>>,
+    /// Id of the deriddl run that applied the migration, when the table records it
+    pub run_id: Option<String>,
+    /// How the record was made
+    pub status: RecordStatus,
 }
 
+/// How a schema_migrations record was made, kept in its status column
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RecordStatus {
+    /// deriddl ran the migration; the status column is left NULL
+    #[default]
+    Ran,
+    /// Marked as applied by `mark-applied`, i.e. executed outside deriddl (for example
+    /// through a change-management system)
+    AppliedExternally,
+    /// Skipped by its `skip-if`/`run-if` guard
+    SkippedByGuard,
+    /// On the `migrations.skip` list or marked with `mark-applied --skip`; never runs here
+    SkipListed,
+}
+
+impl RecordStatus {
+    /// Value of the status column; None for migrations deriddl ran
+    pub fn as_str(self) -> Option<&'static str> {
+        match self {
+            Self::Ran => None,
+            Self::AppliedExternally => Some("applied_externally"),
+            Self::SkippedByGuard => Some("skipped_by_guard"),
+            Self::SkipListed => Some("skip_listed"),
+        }
+    }
+
+    /// Status of a record from its status column
+    fn of_column(status: Option<&str>) -> Self {
+        [Self::AppliedExternally, Self::SkippedByGuard, Self::SkipListed]
+            .into_iter()
+            .find(|candidate| status.is_some() && candidate.as_str() == status)
+            .unwrap_or_default()
+    }
+}
+
+impl AppliedMigration {
+    /// Returns true if this record was created by `mark-applied` rather than by running the migration
+    pub fn applied_externally(&self) -> bool {
+        self.status == RecordStatus::AppliedExternally
+    }
+
+    /// Returns true if a guard query skipped this migration instead of running it
+    pub fn skipped_by_guard(&self) -> bool {
+        self.status == RecordStatus::SkippedByGuard
+    }
+
+    /// Returns true if this migration was skipped on purpose and never ran here
+    pub fn skip_listed(&self) -> bool {
+        self.status == RecordStatus::SkipListed
+    }
+}
+
+#[cfg(test)]
+impl AppliedMigration {
+    /// A record of `migration` run successfully just now; tests adjust the rest with `..`
+    pub(crate) fn of(migration: &Migration) -> Self {
+        AppliedMigration {
+            migration_id: migration.identifier(),
+            migration_type: migration.migration_type.clone(),
+            version: migration.version,
+            filename: migration.filename(),
+            checksum: migration.checksum.clone(),
+            applied_at: Utc::now(),
+            execution_time_ms: 10,
+            success: true,
+            release: None,
+            git_commit: None,
+            git_branch: None,
+            rolled_back_at: None,
+            run_id: None,
+            status: RecordStatus::Ran,
+        }
+    }
+
+    /// A record of the empty versioned migration `<version>_change.sql`, see [`Self::of`]
+    pub(crate) fn of_version(version: u32) -> Self {
+        let file = format!("{:04}_change.sql", version);
+        Self::of(&Migration::new(version, "change".to_string(), file.into(), String::new()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RowKind {
+    Versioned,
+    Repeatable,
+    Baseline,
+}
+
+#[derive(Debug)]
+struct IndexedRow {
+    kind: RowKind,
+    applied: AppliedMigration,
+}
+
+/// Every schema_migrations row, fetched in a single query and indexed by migration id
+#[derive(Debug, Default)]
+struct AppliedIndex {
+    rows: Vec<IndexedRow>,
+    by_id: HashMap<String, usize>,
+}
+
+impl AppliedIndex {
+    fn get(&self, migration_id: &str) -> Option<&IndexedRow> {
+        self.by_id.get(migration_id).map(|&i| &self.rows[i])
+    }
+}
+
+/// Access to the schema_migrations table. Reads are served from an in-memory snapshot
+/// fetched on first use, so per-migration checks don't each cost a round trip on
+/// high-latency targets; every write discards the snapshot.
 pub struct VersionStore {
     connection_string: String,
     connection_manager: ConnectionManager,
+    sql: TrackerSql,
+    cache: Option<AppliedIndex>,
+    release: Option<String>,
+    /// Optional columns schema_migrations has, probed on first use
+    optional_columns: Option<Vec<&'static str>>,
+    /// Successes queued by [`VersionStore::defer_migration_success`]: migration id and execution time
+    deferred: Vec<(String, i32)>,
+    /// Statuses queued by [`VersionStore::defer_migration_skipped`], as UPDATE statements
+    deferred_annotations: Vec<String>,
 }
 
 impl VersionStore {
+    /// Opens the store as a run of its own with the generic dialect, see [`Target::new`]
     pub fn new(conn_string: &str) -> Result<Self, ConnectionError> {
-        let connection_manager = ConnectionManager::new()?;
-        Ok(Self {
+        Target::new(conn_string).version_store()
+    }
+
+    /// A store whose `connection_manager` connects with the target's settings and whose
+    /// `sql` reads the target's schema_migrations
+    pub fn for_target(conn_string: &str, connection_manager: ConnectionManager, sql: TrackerSql) -> Self {
+        Self {
             connection_string: conn_string.to_string(),
             connection_manager,
-        })
+            sql,
+            cache: None,
+            release: None,
+            optional_columns: None,
+            deferred: Vec::new(),
+            deferred_annotations: Vec::new(),
+        }
     }
 
-    fn get_executor(&self) -> Result<DatabaseExecutor, ConnectionError> {
-        let connection = self.connection_manager.connect(&self.connection_string)?;
-        Ok(DatabaseExecutor::new(connection))
+    /// Tags the migrations this store records as started with `release`
+    pub fn with_release(mut self, release: Option<String>) -> Self {
+        self.release = release;
+        self
     }
 
-    pub fn get_applied_migrations(&mut self) -> Result<Vec<AppliedMigration>, ConnectionError> {
-        debug!("Fetching applied migrations from database");
+    /// False for tables created before the release column was added
+    pub fn records_releases(&mut self) -> Result<bool, ConnectionError> {
+        Ok(self.optional_columns()?.contains(&schema_init::RELEASE_COLUMN))
+    }
+
+    /// Brings schema_migrations up to the current layout, see
+    /// [`schema_init::upgrade_migration_table`]. Only probes the table once it is current.
+    pub fn upgrade(&mut self) -> Result<(), ConnectionError> {
+        if self.optional_columns()?.contains(&schema_init::STATUS_COLUMN) {
+            return Ok(());
+        }
+        self.cache = None;
+        let mut executor = self.get_executor()?;
+        schema_init::upgrade_migration_table(&mut executor, &self.sql)?;
+        drop(executor);
+        self.optional_columns = None;
+        Ok(())
+    }
+
+    fn optional_columns(&mut self) -> Result<Vec<&'static str>, ConnectionError> {
+        if let Some(columns) = &self.optional_columns {
+            return Ok(columns.clone());
+        }
+        let mut executor = self.get_executor()?;
+        let columns: Vec<&'static str> = schema_init::OPTIONAL_MIGRATION_TABLE_COLUMNS
+            .into_iter()
+            .filter(|column| executor.query_rows(&self.sql.probe_column(column)).is_ok())
+            .collect();
+        drop(executor);
+        debug!("schema_migrations has optional columns: {:?}", columns);
+        self.optional_columns = Some(columns.clone());
+        Ok(columns)
+    }
+
+    /// SQL filling the optional columns this table has for `migration`: the release, the
+    /// git commit and branch of its file and the run id. None when there is nothing to record.
+    fn annotation(&mut self, migration: &Migration, status: RecordStatus) -> Result<Option<String>, ConnectionError> {
+        let columns = self.optional_columns()?;
+        if columns.is_empty() {
+            return Ok(None);
+        }
+        let values: Vec<(&str, String)> = columns
+            .iter()
+            .zip(self.annotation_values(migration, &columns, status))
+            .filter_map(|(column, value)| value.map(|value| (*column, value)))
+            .collect();
+        Ok((!values.is_empty()).then(|| self.sql.annotate(migration, &values)))
+    }
+
+    /// Values of `columns` for `migration`, None for columns it has nothing to record in
+    fn annotation_values(&self, migration: &Migration, columns: &[&str], status: RecordStatus) -> Vec<Option<String>> {
+        let git = GitMetadata::for_file(&migration.file_path);
+        columns
+            .iter()
+            .map(|column| match *column {
+                schema_init::RELEASE_COLUMN => self.release.clone(),
+                schema_init::GIT_COMMIT_COLUMN => git.as_ref().map(|git| git.commit.clone()),
+                schema_init::GIT_BRANCH_COLUMN => git.as_ref().and_then(|git| git.branch.clone()),
+                schema_init::RUN_ID_COLUMN => Some(self.connection_manager.settings().run_id.clone()),
+                schema_init::STATUS_COLUMN => status.as_str().map(str::to_string),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn get_executor(&self) -> Result<DatabaseExecutor<'_>, ConnectionError> {
+        self.connection_manager.executor(&self.connection_string)
+    }
+
+    /// Executor for statements that modify schema_migrations
+    fn writer(&mut self) -> Result<DatabaseExecutor<'_>, ConnectionError> {
+        self.cache = None;
+        self.get_executor()
+    }
+
+    fn index(&mut self) -> Result<&AppliedIndex, ConnectionError> {
+        let index = match self.cache.take() {
+            Some(index) => index,
+            None => {
+                let optional = self.optional_columns()?;
+                self.fetch_index(&optional)?
+            }
+        };
+        Ok(self.cache.insert(index))
+    }
 
-        let query = r#"
-            SELECT migration_id, migration_type, version, filename, checksum, applied_at, execution_time_ms, success
-            FROM schema_migrations 
-            ORDER BY 
-                CASE WHEN migration_type = 'versioned' THEN 0 ELSE 1 END,
-                CASE WHEN migration_type = 'versioned' THEN version ELSE 0 END,
-                filename
-        "#;
+    fn fetch_index(&self, optional: &[&str]) -> Result<AppliedIndex, ConnectionError> {
+        debug!("Fetching schema_migrations rows from database");
 
         let mut executor = self.get_executor()?;
-        let rows = executor.query_rows(query)?;
-        let mut migrations = Vec::new();
+        let rows = executor.query_typed(&self.sql.select_all(optional))?;
+        let mut index = AppliedIndex::default();
 
         for row in rows {
-            if row.len() >= 8 {
-                let migration_type = match row[1].as_str() {
-                    "repeatable" => MigrationType::Repeatable,
-                    _ => MigrationType::Versioned,
-                };
-                
-                let version = if migration_type == MigrationType::Versioned {
-                    Some(row[2].parse().unwrap_or(0))
-                } else {
-                    None
-                };
-
-                let migration = AppliedMigration {
-                    migration_id: row[0].clone(),
-                    migration_type,
-                    version,
-                    filename: row[3].clone(),
-                    checksum: row[4].clone(),
-                    applied_at: parse_timestamp(&row[5]),
-                    execution_time_ms: row[6].parse().unwrap_or(0),
-                    success: parse_boolean(&row[7]),
-                };
-                migrations.push(migration);
-            }
+            let Some((kind, applied)) = applied_from_row(&row, optional) else {
+                continue;
+            };
+            index.by_id.insert(applied.migration_id.clone(), index.rows.len());
+            index.rows.push(IndexedRow { kind, applied });
         }
 
-        debug!("Found {} applied migrations", migrations.len());
-        Ok(migrations)
+        debug!("Found {} schema_migrations rows", index.rows.len());
+        Ok(index)
     }
 
-    pub fn get_applied_versions(&mut self) -> Result<Vec<u32>, ConnectionError> {
-        debug!("Fetching applied migration versions");
+    pub fn get_applied_migrations(&mut self) -> Result<Vec<AppliedMigration>, ConnectionError> {
+        Ok(self
+            .index()?
+            .rows
+            .iter()
+            .map(|row| row.applied.clone())
+            .collect())
+    }
 
-        let query = "SELECT version FROM schema_migrations WHERE migration_type = 'versioned' AND success = 1 ORDER BY version ASC";
+    /// Records of rolled back migrations, oldest rollback first
+    pub fn get_rolled_back_migrations(&mut self) -> Result<Vec<AppliedMigration>, ConnectionError> {
         let mut executor = self.get_executor()?;
-        let rows = executor.query_rows(query)?;
+        // Created by the first rollback, so without it nothing was rolled back
+        if executor.query_single_value(&self.sql.count_rollbacks()).is_err() {
+            return Ok(Vec::new());
+        }
+        let rows = executor.query_typed(&self.sql.select_rollbacks())?;
+        Ok(rows
+            .iter()
+            .filter_map(|row| applied_from_row(row, &tracker_sql::ROLLBACK_OPTIONAL_COLUMNS))
+            .map(|(_, applied)| applied)
+            .collect())
+    }
 
-        let versions: Vec<u32> = rows
-            .into_iter()
-            .filter_map(|row| row.first()?.parse().ok())
+    pub fn get_applied_versions(&mut self) -> Result<Vec<u32>, ConnectionError> {
+        let mut versions: Vec<u32> = self
+            .index()?
+            .rows
+            .iter()
+            .filter(|row| row.kind == RowKind::Versioned && row.applied.success)
+            .filter_map(|row| row.applied.version)
             .collect();
+        versions.sort_unstable();
 
         debug!("Found {} applied versions", versions.len());
         Ok(versions)
     }
 
-    pub fn is_migration_applied(&mut self, version: u32) -> Result<bool, ConnectionError> {
-        debug!("Checking if migration version {} is applied", version);
-
-        let query_with_param = format!(
-            "SELECT COUNT(*) FROM schema_migrations WHERE migration_type = 'versioned' AND version = {} AND success = 1",
-            version
-        );
-
-        let mut executor = self.get_executor()?;
-        match executor.query_single_value(&query_with_param)? {
-            Some(count) => {
-                let is_applied = count.parse::<i32>().unwrap_or(0) > 0;
-                debug!("Migration {} is applied: {}", version, is_applied);
-                Ok(is_applied)
-            }
-            None => Ok(false),
-        }
+    /// Check if a versioned migration with the given migration id has been applied successfully
+    pub fn is_migration_id_applied(&mut self, migration_id: &str) -> Result<bool, ConnectionError> {
+        Ok(self
+            .index()?
+            .get(migration_id)
+            .is_some_and(|row| row.kind == RowKind::Versioned && row.applied.success))
     }
-    
+
     /// Check if a repeatable migration needs to be re-run (checksum has changed or never run)
     pub fn should_run_repeatable(&mut self, migration: &Migration) -> Result<bool, ConnectionError> {
         debug!("Checking if repeatable migration '{}' needs to run", migration.name);
-        
-        let query = format!(
-            "SELECT checksum FROM schema_migrations WHERE migration_id = '{}' AND success = 1",
-            migration.identifier().replace("'", "''")
-        );
 
-        let mut executor = self.get_executor()?;
-        match executor.query_single_value(&query)? {
+        let stored_checksum = self
+            .index()?
+            .get(&migration.identifier())
+            .filter(|row| row.applied.success)
+            .map(|row| row.applied.checksum.clone());
+
+        match stored_checksum {
             Some(stored_checksum) => {
-                let should_run = stored_checksum != migration.checksum;
+                let should_run = !migration.matches_checksum(&stored_checksum);
                 debug!("Repeatable migration '{}' checksum changed: {}", migration.name, should_run);
                 Ok(should_run)
             }
@@ -139,43 +355,104 @@ impl VersionStore {
         }
     }
 
-    pub fn record_migration_start(&mut self, migration: &Migration) -> Result<(), ConnectionError> {
-        debug!(
-            "Recording migration start for '{}'", 
-            migration.identifier()
-        );
+    /// Record a migration as applied without executing it
+    pub fn mark_migration_applied(&mut self, migration: &Migration) -> Result<(), ConnectionError> {
+        debug!("Marking migration '{}' as applied externally", migration.identifier());
+        self.record_without_running(migration, RecordStatus::AppliedExternally)?;
+        info!("Migration '{}' marked as applied", migration.identifier());
+        Ok(())
+    }
 
-        let migration_type_str = match migration.migration_type {
-            MigrationType::Versioned => "versioned",
-            MigrationType::Repeatable => "repeatable",
-        };
+    /// Record a migration whose guard decided it should not run, so it is no longer pending
+    pub fn record_migration_skipped(&mut self, migration: &Migration) -> Result<(), ConnectionError> {
+        debug!("Recording migration '{}' as skipped by its guard", migration.identifier());
+        self.record_without_running(migration, RecordStatus::SkippedByGuard)
+    }
 
-        let version_value = match migration.version {
-            Some(v) => v.to_string(),
-            None => "NULL".to_string(),
-        };
+    /// Record a skip-listed migration as permanently skipped, so it is no longer pending
+    pub fn record_migration_skip_listed(&mut self, migration: &Migration) -> Result<(), ConnectionError> {
+        debug!("Recording migration '{}' as skip-listed", migration.identifier());
+        self.record_without_running(migration, RecordStatus::SkipListed)?;
+        info!("⏭️  Migration '{}' recorded as skipped", migration.identifier());
+        Ok(())
+    }
+
+    /// A successful record of a migration deriddl did not run, carrying `status`
+    fn record_without_running(&mut self, migration: &Migration, status: RecordStatus) -> Result<(), ConnectionError> {
+        self.upgrade()?;
+        let mut queries = self.sql.migration_start(migration);
+        queries.extend(self.annotation(migration, status)?);
+        queries.push(self.sql.migration_success(migration, 0));
+
+        let mut executor = self.writer()?;
+        for (i, query) in queries.iter().enumerate() {
+            if migration.is_repeatable() && i == 0 {
+                // Deleting a previous record of a repeatable may find nothing to delete
+                let _ = executor.execute_query(query);
+            } else {
+                executor.execute_query(query)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Statements recording the start of all `migrations` at once, batched by
+    /// [`TrackerSql::migrations_start`], for running them in the caller's transaction
+    pub fn migrations_start_sql(&mut self, migrations: &[&Migration]) -> Result<Vec<String>, ConnectionError> {
+        let columns = self.optional_columns()?;
+        let rows: Vec<(&Migration, Vec<Option<String>>)> = migrations
+            .iter()
+            .map(|migration| (*migration, self.annotation_values(migration, &columns, RecordStatus::Ran)))
+            .collect();
+        self.cache = None;
+        Ok(self.sql.migrations_start(&rows, &columns))
+    }
+
+    /// Queues the success of `migration` until the next flush, which records every queued
+    /// success in one statement
+    pub fn defer_migration_success(&mut self, migration: &Migration, execution_time_ms: i32) {
+        self.deferred.push((migration.identifier(), execution_time_ms));
+    }
+
+    /// Queues `migration` as skipped by its guard until the next flush. Call [`Self::upgrade`]
+    /// before the transaction, so the status column exists.
+    pub fn defer_migration_skipped(&mut self, migration: &Migration) -> Result<(), ConnectionError> {
+        self.deferred.push((migration.identifier(), 0));
+        let annotation = self.annotation(migration, RecordStatus::SkippedByGuard)?;
+        self.deferred_annotations.extend(annotation);
+        Ok(())
+    }
 
-        // For repeatable migrations, delete any existing record first
-        if migration.is_repeatable() {
-            let delete_query = format!(
-                "DELETE FROM schema_migrations WHERE migration_id = '{}'",
-                migration.identifier().replace("'", "''")
-            );
-            let mut executor = self.get_executor()?;
-            let _ = executor.execute_query(&delete_query); // Ignore errors if record doesn't exist
+    /// Writes the deferred successes and statuses through `executor`, so they commit with
+    /// its transaction. Returns how many were written; the queue is emptied even when writing fails.
+    pub fn flush_deferred(&mut self, executor: &mut DatabaseExecutor) -> Result<usize, ConnectionError> {
+        let deferred = std::mem::take(&mut self.deferred);
+        let mut queries = self.sql.migrations_success(&deferred);
+        queries.append(&mut self.deferred_annotations);
+        self.cache = None;
+        for query in &queries {
+            executor.execute_query(query)?;
         }
+        Ok(deferred.len())
+    }
 
-        let query = format!(
-            "INSERT INTO schema_migrations (migration_id, migration_type, version, filename, checksum, applied_at, execution_time_ms, success) VALUES ('{}', '{}', {}, '{}', '{}', CURRENT_TIMESTAMP, 0, 0)",
-            migration.identifier().replace("'", "''"),
-            migration_type_str,
-            version_value,
-            migration.filename().replace("'", "''"),
-            migration.checksum.replace("'", "''")
+    pub fn record_migration_start(&mut self, migration: &Migration) -> Result<(), ConnectionError> {
+        debug!(
+            "Recording migration start for '{}'", 
+            migration.identifier()
         );
 
-        let mut executor = self.get_executor()?;
-        executor.execute_query(&query)?;
+        let mut queries = self.sql.migration_start(migration);
+        queries.extend(self.annotation(migration, RecordStatus::Ran)?);
+        let mut executor = self.writer()?;
+        for (i, query) in queries.iter().enumerate() {
+            if migration.is_repeatable() && i == 0 {
+                // For repeatable migrations, delete any existing record first
+                let _ = executor.execute_query(query); // Ignore errors if record doesn't exist
+            } else {
+                executor.execute_query(query)?;
+            }
+        }
         debug!("Migration start recorded for '{}'", migration.identifier());
         Ok(())
     }
@@ -190,13 +467,9 @@ impl VersionStore {
             migration.identifier(), execution_time_ms
         );
 
-        let query = format!(
-            "UPDATE schema_migrations SET execution_time_ms = {}, success = 1, applied_at = CURRENT_TIMESTAMP WHERE migration_id = '{}'",
-            execution_time_ms,
-            migration.identifier().replace("'", "''")
-        );
+        let query = self.sql.migration_success(migration, execution_time_ms);
 
-        let mut executor = self.get_executor()?;
+        let mut executor = self.writer()?;
         executor.execute_query(&query)?;
         info!(
             "✅ Migration '{}' completed successfully in {}ms",
@@ -215,12 +488,9 @@ impl VersionStore {
             migration.identifier(), execution_time_ms
         );
 
-        let query = format!(
-            "UPDATE schema_migrations SET execution_time_ms = {}, success = 0 WHERE migration_id = '{}'",
-            execution_time_ms, migration.identifier().replace("'", "''")
-        );
+        let query = self.sql.migration_failure(migration, execution_time_ms);
 
-        let mut executor = self.get_executor()?;
+        let mut executor = self.writer()?;
         executor.execute_query(&query)?;
         debug!("Migration '{}' failure recorded", migration.identifier());
         Ok(())
@@ -230,14 +500,10 @@ impl VersionStore {
         &mut self,
         migration_id: &str,
     ) -> Result<Option<String>, ConnectionError> {
-        debug!("Getting checksum for migration '{}'", migration_id);
-
-        let query = format!(
-            "SELECT checksum FROM schema_migrations WHERE migration_id = '{}'",
-            migration_id.replace("'", "''")
-        );
-        let mut executor = self.get_executor()?;
-        executor.query_single_value(&query)
+        Ok(self
+            .index()?
+            .get(migration_id)
+            .map(|row| row.applied.checksum.clone()))
     }
 
     pub fn get_pending_migrations(
@@ -255,16 +521,16 @@ impl VersionStore {
                     // For versioned migrations, check if already applied or below baseline
                     if let Some(version) = migration.version {
                         // Skip if migration is at or below baseline
-                        if let Some(baseline) = baseline_version {
-                            if version <= baseline {
-                                debug!("Skipping migration {} - at or below baseline version {}", 
-                                    version, baseline);
-                                continue;
-                            }
+                        if let Some(baseline) = baseline_version
+                            && version <= baseline
+                        {
+                            debug!("Skipping migration {} - at or below baseline version {}", 
+                                version, baseline);
+                            continue;
                         }
                         
-                        // Check if not already applied
-                        if !self.is_migration_applied(version)? {
+                        // Check if not already applied (by id, so namespaced versions don't collide)
+                        if !self.is_migration_id_applied(&migration.identifier())? {
                             pending.push(migration.clone());
                         }
                     }
@@ -279,6 +545,8 @@ impl VersionStore {
             }
         }
 
+        let pending = Self::include_stale_repeatables(pending, all_migrations);
+
         let versioned_pending = pending.iter().filter(|m| !m.is_repeatable()).count();
         let repeatable_pending = pending.iter().filter(|m| m.is_repeatable()).count();
         debug!(
@@ -289,6 +557,44 @@ impl VersionStore {
         Ok(pending)
     }
 
+    /// Adds repeatables whose `depends-on-table` tables are altered by a pending versioned
+    /// migration, even when their own checksum is unchanged. Loader order is preserved.
+    pub fn include_stale_repeatables(pending: Vec<Migration>, all_migrations: &[Migration]) -> Vec<Migration> {
+        let altered: Vec<String> = pending
+            .iter()
+            .filter(|m| !m.is_repeatable())
+            .flat_map(|m| m.altered_tables())
+            .collect();
+        if altered.is_empty() {
+            return pending;
+        }
+
+        let mut stale = Vec::new();
+        for migration in all_migrations {
+            let already_pending = pending.iter().any(|p| p.identifier() == migration.identifier());
+            if migration.is_repeatable() && !already_pending && migration.depends_on_any_table(&altered) {
+                debug!(
+                    "Repeatable {} is stale: a pending migration alters one of its tables ({})",
+                    migration.filename(),
+                    migration.depends_on_tables.join(", ")
+                );
+                stale.push(migration.identifier());
+            }
+        }
+        if stale.is_empty() {
+            return pending;
+        }
+
+        all_migrations
+            .iter()
+            .filter(|m| {
+                stale.contains(&m.identifier())
+                    || pending.iter().any(|p| p.identifier() == m.identifier())
+            })
+            .cloned()
+            .collect()
+    }
+
     /// Create a baseline record for an existing database
     pub fn create_baseline(&mut self, version: u32, description: &str) -> Result<(), ConnectionError> {
         debug!("Creating baseline record for version {}", version);
@@ -303,13 +609,9 @@ impl VersionStore {
         let baseline_filename = format!("baseline_{:04}_{}", version, 
             description.replace(" ", "_").to_lowercase());
         
-        let query = format!(
-            "INSERT INTO schema_migrations (migration_id, migration_type, version, filename, checksum, applied_at, execution_time_ms, success) 
-             VALUES ('{}', 'baseline', {}, '{}', 'baseline', datetime('now'), 0, 1)",
-            version, version, baseline_filename
-        );
+        let query = self.sql.baseline(version, &baseline_filename);
 
-        let mut executor = self.get_executor()?;
+        let mut executor = self.writer()?;
         executor.execute_query(&query)?;
         
         info!("✅ Baseline version {} created successfully", version);
@@ -318,74 +620,38 @@ impl VersionStore {
 
     /// Check if a version is a baseline
     pub fn is_baseline_version(&mut self, version: u32) -> Result<bool, ConnectionError> {
-        debug!("Checking if version {} is a baseline", version);
-
-        let query = format!(
-            "SELECT COUNT(*) FROM schema_migrations WHERE migration_type = 'baseline' AND version = {}",
-            version
-        );
-
-        let mut executor = self.get_executor()?;
-        match executor.query_single_value(&query)? {
-            Some(count) => {
-                let is_baseline = count.parse::<i32>().unwrap_or(0) > 0;
-                debug!("Version {} is baseline: {}", version, is_baseline);
-                Ok(is_baseline)
-            }
-            None => Ok(false),
-        }
+        let is_baseline = self
+            .index()?
+            .rows
+            .iter()
+            .any(|row| row.kind == RowKind::Baseline && row.applied.version == Some(version));
+        debug!("Version {} is baseline: {}", version, is_baseline);
+        Ok(is_baseline)
     }
 
     /// Get the baseline version if one exists
     pub fn get_baseline_version(&mut self) -> Result<Option<u32>, ConnectionError> {
-        debug!("Getting baseline version");
-
-        let query = "SELECT version FROM schema_migrations WHERE migration_type = 'baseline' ORDER BY version DESC LIMIT 1";
-        let mut executor = self.get_executor()?;
-        
-        match executor.query_single_value(query)? {
-            Some(version_str) => {
-                let version = version_str.parse::<u32>().unwrap_or(0);
-                debug!("Found baseline version: {}", version);
-                Ok(Some(version))
-            }
-            None => {
-                debug!("No baseline version found");
-                Ok(None)
-            }
-        }
+        let version = self
+            .index()?
+            .rows
+            .iter()
+            .filter(|row| row.kind == RowKind::Baseline)
+            .filter_map(|row| row.applied.version)
+            .max();
+        debug!("Baseline version: {:?}", version);
+        Ok(version)
     }
 
     /// Get all baseline records
     pub fn get_baselines(&mut self) -> Result<Vec<AppliedMigration>, ConnectionError> {
-        debug!("Fetching baseline records from database");
-
-        let query = r#"
-            SELECT migration_id, migration_type, version, filename, checksum, applied_at, execution_time_ms, success
-            FROM schema_migrations 
-            WHERE migration_type = 'baseline'
-            ORDER BY version ASC
-        "#;
-
-        let mut executor = self.get_executor()?;
-        let rows = executor.query_rows(query)?;
-        let mut baselines = Vec::new();
-
-        for row in rows {
-            if row.len() >= 8 {
-                let baseline = AppliedMigration {
-                    migration_id: row[0].clone(),
-                    migration_type: MigrationType::Versioned, // Baselines are treated as versioned
-                    version: Some(row[2].parse().unwrap_or(0)),
-                    filename: row[3].clone(),
-                    checksum: row[4].clone(),
-                    applied_at: parse_timestamp(&row[5]),
-                    execution_time_ms: row[6].parse().unwrap_or(0),
-                    success: parse_boolean(&row[7]),
-                };
-                baselines.push(baseline);
-            }
-        }
+        let mut baselines: Vec<AppliedMigration> = self
+            .index()?
+            .rows
+            .iter()
+            .filter(|row| row.kind == RowKind::Baseline)
+            .map(|row| row.applied.clone())
+            .collect();
+        baselines.sort_by_key(|b| b.version);
 
         debug!("Found {} baseline records", baselines.len());
         Ok(baselines)
@@ -407,38 +673,90 @@ impl VersionStore {
         }
     }
 
-    /// Remove a migration record from the database (used for rollbacks)
-    pub fn remove_migration(&mut self, version: u32) -> Result<(), ConnectionError> {
-        debug!("Removing migration record for version {}", version);
-
-        let query = format!(
-            "DELETE FROM schema_migrations WHERE migration_type = 'versioned' AND version = {}",
-            version
-        );
-
-        let mut executor = self.get_executor()?;
-        executor.execute_query(&query)?;
-        
-        info!("Migration version {} removed from schema_migrations", version);
+    /// Moves the record of a rolled back migration to the rollbacks table, stamped with
+    /// `rolled_back_at`, so it counts as pending again while its migration id stays the
+    /// same and every rollback of it is kept. Only `migration_id` is touched, so another
+    /// namespace's migration with the same version stays applied.
+    pub fn remove_migration(&mut self, migration_id: &str) -> Result<(), ConnectionError> {
+        let optional = self.optional_columns()?;
+        debug!("Archiving migration record {} as rolled back", migration_id);
+        let queries = [self.sql.archive_rollback(migration_id, &optional), self.sql.remove(migration_id)];
+        let create = self.sql.create_rollbacks_table();
+        let count = self.sql.count_rollbacks();
+        let mut executor = self.writer()?;
+        if executor.query_single_value(&count).is_err() {
+            debug!("Creating {} table", tracker_sql::ROLLBACKS_TABLE);
+            executor.execute_query(&create)?;
+        }
+        executor.execute_transaction(|executor| queries.iter().try_for_each(|query| executor.execute_query(query)))?;
+        info!("Migration {} moved to {}", migration_id, tracker_sql::ROLLBACKS_TABLE);
         Ok(())
     }
 
-    /// Get access to the database executor for direct SQL execution
-    pub fn executor(&mut self) -> Result<DatabaseExecutor, ConnectionError> {
-        self.get_executor()
+    /// Builds the statements against this store's schema_migrations, for the tables kept
+    /// next to it
+    pub fn sql(&self) -> &TrackerSql {
+        &self.sql
     }
-}
 
-fn parse_timestamp(timestamp_str: &str) -> DateTime<Utc> {
-    // Try to parse various timestamp formats
-    DateTime::parse_from_rfc3339(timestamp_str)
-        .or_else(|_| DateTime::parse_from_str(timestamp_str, "%Y-%m-%d %H:%M:%S"))
-        .or_else(|_| DateTime::parse_from_str(timestamp_str, "%Y-%m-%d %H:%M:%S%.f"))
-        .map(|dt| dt.with_timezone(&Utc))
-        .unwrap_or_else(|_| Utc::now())
+    /// Get access to the database executor for direct SQL execution.
+    /// Cached rows are discarded since the caller may modify schema_migrations.
+    pub fn executor(&mut self) -> Result<DatabaseExecutor<'_>, ConnectionError> {
+        self.writer()
+    }
 }
 
-fn parse_boolean(bool_str: &str) -> bool {
-    matches!(bool_str.to_lowercase().as_str(), "true" | "1" | "t" | "yes" | "y")
+/// The record a row of [`TrackerSql::select_all`] or [`TrackerSql::select_rollbacks`] holds;
+/// None for rows without an id or type
+fn applied_from_row(row: &TypedRow, optional: &[&str]) -> Option<(RowKind, AppliedMigration)> {
+    let (Some(migration_id), Some(migration_type)) = (row.get_string(0), row.get_string(1)) else {
+        return None;
+    };
+    let kind = match migration_type.as_str() {
+        "repeatable" => RowKind::Repeatable,
+        "baseline" => RowKind::Baseline,
+        _ => RowKind::Versioned,
+    };
+    let migration_type = match kind {
+        RowKind::Repeatable => MigrationType::Repeatable,
+        // Baselines are treated as versioned
+        RowKind::Versioned | RowKind::Baseline => MigrationType::Versioned,
+    };
+    let version = match kind {
+        RowKind::Repeatable => None,
+        RowKind::Versioned | RowKind::Baseline => Some(row.get_i64(2).and_then(|v| u32::try_from(v).ok()).unwrap_or(0)),
+    };
+
+    let rolled_back_at = optional
+        .iter()
+        .position(|name| *name == schema_init::ROLLED_BACK_AT_COLUMN)
+        .and_then(|position| row.get_datetime(8 + position));
+
+    let status = RecordStatus::of_column(optional_value(row, optional, schema_init::STATUS_COLUMN).as_deref());
+    let applied = AppliedMigration {
+        migration_id,
+        migration_type,
+        version,
+        filename: row.get_string(3).unwrap_or_default(),
+        checksum: row.get_string(4).unwrap_or_default(),
+        applied_at: row.get_datetime(5).unwrap_or_else(|| {
+            warn!("Unreadable applied_at for '{}', using the current time", row.get_string(0).unwrap_or_default());
+            Utc::now()
+        }),
+        execution_time_ms: row.get_i64(6).and_then(|v| i32::try_from(v).ok()).unwrap_or(0),
+        success: row.get_bool(7).unwrap_or(false),
+        release: optional_value(row, optional, schema_init::RELEASE_COLUMN),
+        git_commit: optional_value(row, optional, schema_init::GIT_COMMIT_COLUMN),
+        git_branch: optional_value(row, optional, schema_init::GIT_BRANCH_COLUMN),
+        rolled_back_at,
+        run_id: optional_value(row, optional, schema_init::RUN_ID_COLUMN),
+        status,
+    };
+    Some((kind, applied))
 }
 
+/// Value of an optional column selected after the 8 fixed ones; empty strings count as unset
+fn optional_value(row: &TypedRow, optional: &[&str], column: &str) -> Option<String> {
+    let position = optional.iter().position(|name| *name == column)?;
+    row.get_string(8 + position).filter(|value| !value.is_empty())
+}
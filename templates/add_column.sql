@@ -0,0 +1,7 @@
+-- ALTER TABLE waits for an exclusive lock; give up quickly instead of blocking other queries
+-- deriddl:lock_timeout 5s
+-- deriddl:lock_retries 3
+-- +migrate Up
+ALTER TABLE {{table}} ADD COLUMN {{column}} {{column_type}};
+-- +migrate Down
+ALTER TABLE {{table}} DROP COLUMN {{column}};
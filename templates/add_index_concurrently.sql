@@ -0,0 +1,7 @@
+-- PostgreSQL builds the index without blocking writes, which can't happen inside a transaction.
+-- If the build fails, the invalid index is dropped by running the down SQL.
+-- deriddl:no-transaction
+-- +migrate Up
+CREATE INDEX CONCURRENTLY IF NOT EXISTS {{index}} ON {{table}} ({{columns}});
+-- +migrate Down
+DROP INDEX CONCURRENTLY IF EXISTS {{index}};
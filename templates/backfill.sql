@@ -0,0 +1,6 @@
+-- Only rows still missing a value are updated, so the migration can be re-run after a failure.
+-- The down SQL clears the column again; values it held before the backfill are not restored.
+-- +migrate Up
+UPDATE {{table}} SET {{column}} = {{value}} WHERE {{column}} IS NULL;
+-- +migrate Down
+UPDATE {{table}} SET {{column}} = NULL;
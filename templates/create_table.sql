@@ -0,0 +1,7 @@
+-- +migrate Up
+CREATE TABLE {{table}} (
+    id BIGINT PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+-- +migrate Down
+DROP TABLE {{table}};
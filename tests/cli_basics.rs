@@ -38,3 +38,120 @@ fn test_missing_subcommand() {
         .failure()
         .stderr(predicate::str::contains("Usage: deriddl_rs")); // updated match
 }
+
+#[test]
+fn test_completions_command() {
+    deri_ddl_cmd()
+        .args(["completions", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("_deriddl()"));
+}
+
+#[test]
+fn test_manpages_command() {
+    let temp_dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    let out_dir = temp_dir.path().join("man");
+
+    deri_ddl_cmd()
+        .args(["manpages", "--out-dir", out_dir.to_str().unwrap()])
+        .assert()
+        .success();
+
+    assert!(out_dir.join("deriddl.1").exists());
+    assert!(out_dir.join("deriddl-apply.1").exists());
+}
+
+#[test]
+fn test_log_file_never_contains_the_password() {
+    let temp_dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    let log_file = temp_dir.path().join("deriddl.log");
+    let config = temp_dir.path().join("deriddl.toml");
+    std::fs::write(
+        &config,
+        format!("[logging]\nlevel = \"debug\"\nfile = \"{}\"\n", log_file.display()),
+    )
+    .unwrap();
+
+    deri_ddl_cmd()
+        .current_dir(temp_dir.path())
+        .args(["--config", config.to_str().unwrap(), "init"])
+        .args(["--conn", "Driver=SQLite3;Database=missing/test.db;Uid=migrator;Pwd=hunter2;"])
+        .output()
+        .unwrap();
+
+    let logged = std::fs::read_to_string(&log_file).expect("log file written");
+    assert!(logged.contains("Pwd=***"), "{}", logged);
+    assert!(!logged.contains("hunter2"), "{}", logged);
+}
+
+/// A configuration no released deriddl satisfies
+fn config_requiring_future_version(dir: &std::path::Path) -> std::path::PathBuf {
+    let config = dir.join("deriddl.toml");
+    std::fs::write(&config, "required_version = \">=1000.0\"\n").unwrap();
+    config
+}
+
+fn combined_output(output: &std::process::Output) -> String {
+    format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr))
+}
+
+#[test]
+fn test_required_version_stops_commands_that_load_the_config() {
+    let temp_dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    let config = config_requiring_future_version(temp_dir.path());
+
+    for command in [vec!["validate"], vec!["config"], vec!["dialects"]] {
+        let output = deri_ddl_cmd()
+            .current_dir(temp_dir.path())
+            .args(["--config", config.to_str().unwrap()])
+            .args(&command)
+            .output()
+            .unwrap();
+        assert!(!output.status.success(), "{:?} ran despite required_version", command);
+        assert!(combined_output(&output).contains("requires deriddl >=1000.0"), "{:?}: {}", command, combined_output(&output));
+    }
+}
+
+#[test]
+fn test_required_version_leaves_config_free_commands_working() {
+    let temp_dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    let config = config_requiring_future_version(temp_dir.path());
+    let out_dir = temp_dir.path().join("man");
+    let with_config = |args: &[&str]| {
+        deri_ddl_cmd()
+            .current_dir(temp_dir.path())
+            .args(["--config", config.to_str().unwrap()])
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    assert!(with_config(&["completions", "bash"]).status.success());
+    assert!(with_config(&["config", "--schema"]).status.success());
+    assert!(with_config(&["manpages", "--out-dir", out_dir.to_str().unwrap()]).status.success());
+
+    // Doctor runs, and reports the mismatch as a failure
+    let doctor = with_config(&["doctor", "--json"]);
+    assert!(!doctor.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&doctor.stdout).expect("doctor prints JSON");
+    let version = report["findings"].as_array().unwrap().iter().find(|f| f["check"] == "Version").expect("version finding");
+    assert_eq!(version["status"], "fail");
+    assert!(version["message"].as_str().unwrap().contains(">=1000.0"));
+}
+
+#[test]
+fn test_source_cannot_be_combined_with_path() {
+    let temp_dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    std::fs::create_dir(temp_dir.path().join("migrations")).unwrap();
+
+    let output = deri_ddl_cmd()
+        .current_dir(temp_dir.path())
+        .args(["--source", ".", "validate", "--path", "./other"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(combined_output(&output).contains("--source cannot be combined with --path ./other"), "{}", combined_output(&output));
+
+    deri_ddl_cmd().current_dir(temp_dir.path()).args(["--source", ".", "lock"]).assert().success();
+}
@@ -1,4 +1,10 @@
+// Each test crate uses only some of these helpers
+#![allow(dead_code)]
+
 use assert_cmd::Command;
+use chrono::Utc;
+use deriddl_rs::model::MigrationType;
+use deriddl_rs::tracker::version_store::{AppliedMigration, RecordStatus};
 use std::fs;
 use tempfile::{tempdir, TempDir};
 
@@ -158,7 +164,8 @@ pub fn test_sqlite_connection() -> String {
 /// Initialize a database with schema_migrations table for testing
 pub fn init_test_database(connection_string: &str) -> Result<(), Box<dyn std::error::Error>> {
     use deriddl_rs::tracker::schema_init::init_migration_table_with_config;
-    init_migration_table_with_config(connection_string, Some("sqlite"))?;
+    use deriddl_rs::tracker::Target;
+    init_migration_table_with_config(&Target::new(connection_string), Some("sqlite"), false)?;
     Ok(())
 }
 
@@ -171,9 +178,36 @@ pub fn setup_database_with_applied_migrations(temp_dir: &TempDir) -> Result<Stri
     
     // Apply some migrations so we have something to rollback
     let migrations_path = temp_dir.path().join("migrations").to_string_lossy().to_string();
+    use deriddl_rs::orchestrator::apply::ApplyOptions;
+    use deriddl_rs::orchestrator::guard::ProtectionGuard;
+    use deriddl_rs::orchestrator::lock::LockPolicy;
+    use deriddl_rs::orchestrator::migration_loader::LoaderOptions;
     use deriddl_rs::orchestrator::run_apply;
-    run_apply(&connection_string, &migrations_path, false)?;
+    use deriddl_rs::tracker::Target;
+    let (loader, guard, lock) = (LoaderOptions::default(), ProtectionGuard::default(), LockPolicy::default());
+    run_apply(&Target::new(&connection_string), &migrations_path, &ApplyOptions::new(&loader, &guard, &lock))?;
     
     Ok(connection_string)
 }
 
+
+/// A record of versioned migration `version` from `filename`, run successfully just now;
+/// tests adjust the rest with `..`
+pub fn applied_migration(version: u32, filename: &str) -> AppliedMigration {
+    AppliedMigration {
+        migration_id: version.to_string(),
+        migration_type: MigrationType::Versioned,
+        version: Some(version),
+        filename: filename.to_string(),
+        checksum: "test_checksum".to_string(),
+        applied_at: Utc::now(),
+        execution_time_ms: 100,
+        success: true,
+        release: None,
+        git_commit: None,
+        git_branch: None,
+        rolled_back_at: None,
+        run_id: None,
+        status: RecordStatus::Ran,
+    }
+}
@@ -0,0 +1,21 @@
+use deriddl_rs::orchestrator::MigrationLoader;
+use deriddl_rs::orchestrator::info::find_migration;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_find_migration_by_version_filename_and_identifier() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    fs::write(temp_dir.path().join("0001_init.sql"), "CREATE TABLE a (id INT);").unwrap();
+    fs::write(temp_dir.path().join("0002_users.sql"), "CREATE TABLE b (id INT);").unwrap();
+    fs::write(temp_dir.path().join("R__views.sql"), "CREATE VIEW v AS SELECT 1;").unwrap();
+
+    let migrations = MigrationLoader::load_migrations(temp_dir.path().to_str().unwrap())
+        .expect("Failed to load migrations");
+
+    assert_eq!(find_migration(&migrations, "0002").unwrap().name, "users");
+    assert_eq!(find_migration(&migrations, "2").unwrap().name, "users");
+    assert_eq!(find_migration(&migrations, "0001_init.sql").unwrap().name, "init");
+    assert_eq!(find_migration(&migrations, "R__views").unwrap().name, "views");
+    assert!(find_migration(&migrations, "0009").is_none());
+}
@@ -0,0 +1,417 @@
+use deriddl_rs::model::migration::{ExecutionGuard, RowCountExpectation};
+use deriddl_rs::model::{ChecksumAlgorithm, ChecksumNormalization, MigrationIdScheme, MigrationSource};
+use deriddl_rs::orchestrator::MigrationLoader;
+use deriddl_rs::orchestrator::migration_loader::{InvalidFileReason, LoaderOptions};
+use std::fs;
+use tempfile::TempDir;
+
+fn write_migration(dir: &std::path::Path, filename: &str, content: &str) {
+    fs::create_dir_all(dir).expect("Failed to create migration directory");
+    fs::write(dir.join(filename), content).expect("Failed to write test migration file");
+}
+
+#[test]
+fn test_environments_header_scopes_migration() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    write_migration(temp_dir.path(), "0001_init.sql", "CREATE TABLE test (id INT);");
+    write_migration(
+        temp_dir.path(),
+        "0002_seed_fixtures.sql",
+        "-- deriddl:environments dev,staging\nINSERT INTO test VALUES (1);",
+    );
+
+    let migrations = MigrationLoader::load_migrations(temp_dir.path().to_str().unwrap())
+        .expect("Failed to load migrations");
+
+    assert_eq!(migrations.len(), 2);
+    assert!(migrations[0].runs_in_environment(Some("prod")));
+    assert_eq!(migrations[1].environments, vec!["dev", "staging"]);
+    assert!(migrations[1].runs_in_environment(Some("dev")));
+    assert!(!migrations[1].runs_in_environment(Some("prod")));
+    assert!(!migrations[1].runs_in_environment(None));
+}
+
+#[test]
+fn test_lock_headers_are_parsed() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    write_migration(temp_dir.path(), "0001_init.sql", "CREATE TABLE test (id INT);");
+    write_migration(
+        temp_dir.path(),
+        "0002_add_index.sql",
+        "-- deriddl:lock_timeout 5s\n-- deriddl:lock_retries 3\nCREATE INDEX idx ON test (id);",
+    );
+
+    let migrations = MigrationLoader::load_migrations(temp_dir.path().to_str().unwrap())
+        .expect("Failed to load migrations");
+
+    assert_eq!(migrations[0].lock_timeout_ms, None);
+    assert_eq!(migrations[0].lock_retries, None);
+    assert_eq!(migrations[1].lock_timeout_ms, Some(5000));
+    assert_eq!(migrations[1].lock_retries, Some(3));
+}
+
+#[test]
+fn test_expect_rows_header_is_parsed() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    write_migration(temp_dir.path(), "0001_backfill.sql", "-- deriddl:expect_rows 15324
+UPDATE users SET active = 1;");
+    write_migration(temp_dir.path(), "0002_cleanup.sql", "-- deriddl:expect_rows >= 1
+DELETE FROM sessions;");
+
+    let migrations = MigrationLoader::load_migrations(temp_dir.path().to_str().unwrap())
+        .expect("Failed to load migrations");
+
+    assert_eq!(migrations[0].expect_rows, Some(RowCountExpectation::Exactly(15324)));
+    let at_least = migrations[1].expect_rows.unwrap();
+    assert!(at_least.matches(3) && !at_least.matches(0));
+}
+
+#[test]
+fn test_owners_manifest_assigns_teams_by_path_prefix() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    write_migration(temp_dir.path(), "0001_init.sql", "CREATE TABLE test (id INT);");
+    write_migration(&temp_dir.path().join("analytics"), "0002_events.sql", "CREATE TABLE events (id INT);");
+    fs::write(
+        temp_dir.path().join("owners.toml"),
+        "[owners]\n\"analytics/\" = \"analytics\"\n\"\" = \"core\"\n",
+    )
+    .unwrap();
+
+    let options = LoaderOptions { recursive: true, ..Default::default() };
+    let migrations = MigrationLoader::load_migrations_with_options(temp_dir.path().to_str().unwrap(), &options)
+        .expect("Failed to load migrations");
+
+    let owners: Vec<_> = migrations.iter().map(|m| m.owner.as_deref()).collect();
+    assert_eq!(owners, vec![Some("core"), Some("analytics")]);
+}
+
+#[test]
+fn test_guard_headers_are_parsed() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    write_migration(temp_dir.path(), "0001_add_email.sql", "-- deriddl:skip-if SELECT 1 FROM information_schema.columns WHERE column_name = 'email';
+-- deriddl:run-if SELECT 1 FROM users
+ALTER TABLE users ADD COLUMN email VARCHAR(255);");
+
+    let migrations = MigrationLoader::load_migrations(temp_dir.path().to_str().unwrap())
+        .expect("Failed to load migrations");
+
+    assert_eq!(
+        migrations[0].guards,
+        vec![
+            ExecutionGuard::SkipIf("SELECT 1 FROM information_schema.columns WHERE column_name = 'email'".to_string()),
+            ExecutionGuard::RunIf("SELECT 1 FROM users".to_string()),
+        ]
+    );
+    assert!(migrations[0].guards[0].skips(true) && migrations[0].guards[1].skips(false));
+}
+
+#[test]
+fn test_raw_directive_sends_rest_of_file_as_one_batch() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    write_migration(temp_dir.path(), "0001_proc.sql", "-- Archive old orders
+-- deriddl:raw
+CREATE PROCEDURE archive() AS $$ BEGIN DELETE FROM orders; END $$;
+SELECT 1;
+");
+    write_migration(temp_dir.path(), "0002_plain.sql", "CREATE TABLE t (id INT);");
+
+    let migrations = MigrationLoader::load_migrations(temp_dir.path().to_str().unwrap())
+        .expect("Failed to load migrations");
+
+    assert!(migrations[0].raw && !migrations[1].raw);
+    assert_eq!(
+        migrations[0].raw_batch(&migrations[0].sql_content),
+        "CREATE PROCEDURE archive() AS $$ BEGIN DELETE FROM orders; END $$;\nSELECT 1;"
+    );
+}
+
+#[test]
+fn test_envs_subfolder_scopes_migration() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    write_migration(temp_dir.path(), "0001_init.sql", "CREATE TABLE test (id INT);");
+    write_migration(
+        &temp_dir.path().join("envs").join("dev"),
+        "0002_seed_fixtures.sql",
+        "INSERT INTO test VALUES (1);",
+    );
+
+    let migrations = MigrationLoader::load_migrations(temp_dir.path().to_str().unwrap())
+        .expect("Failed to load migrations");
+
+    assert_eq!(migrations.len(), 2);
+    assert_eq!(migrations[1].filename(), "0002_seed_fixtures.sql");
+    assert_eq!(migrations[1].environments, vec!["dev"]);
+}
+
+#[test]
+fn test_recursive_loading_sorts_across_subfolders() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    write_migration(&temp_dir.path().join("2024"), "0003_third.sql", "-- third");
+    write_migration(&temp_dir.path().join("2023"), "0002_second.sql", "-- second");
+    write_migration(temp_dir.path(), "0001_first.sql", "-- first");
+    write_migration(&temp_dir.path().join("billing").join("views"), "R__invoices.sql", "-- view");
+
+    let options = LoaderOptions { recursive: true, ..Default::default() };
+    let migrations =
+        MigrationLoader::load_migrations_with_options(temp_dir.path().to_str().unwrap(), &options)
+            .expect("Failed to load migrations");
+
+    let filenames: Vec<_> = migrations.iter().map(|m| m.filename()).collect();
+    assert_eq!(
+        filenames,
+        vec!["0001_first.sql", "0002_second.sql", "0003_third.sql", "R__invoices.sql"]
+    );
+}
+
+#[test]
+fn test_non_recursive_loading_ignores_subfolders() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    write_migration(temp_dir.path(), "0001_first.sql", "-- first");
+    write_migration(&temp_dir.path().join("2024"), "0002_second.sql", "-- second");
+
+    let migrations = MigrationLoader::load_migrations(temp_dir.path().to_str().unwrap())
+        .expect("Failed to load migrations");
+
+    assert_eq!(migrations.len(), 1);
+}
+
+#[test]
+fn test_multiple_sources_keep_order_and_namespace() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let core = temp_dir.path().join("core");
+    let analytics = temp_dir.path().join("analytics");
+    write_migration(&core, "0001_init.sql", "CREATE TABLE users (id INT);");
+    write_migration(&core, "R__user_view.sql", "CREATE VIEW v AS SELECT 1;");
+    write_migration(&analytics, "0001_events.sql", "CREATE TABLE events (id INT);");
+
+    let options = LoaderOptions {
+        sources: vec![
+            MigrationSource::Path(core.to_string_lossy().to_string()),
+            MigrationSource::Namespaced {
+                path: analytics.to_string_lossy().to_string(),
+                namespace: Some("analytics".to_string()),
+            },
+        ],
+        ..Default::default()
+    };
+    let migrations = MigrationLoader::load_migrations_with_options("./unused", &options)
+        .expect("Failed to load migrations");
+
+    let identifiers: Vec<_> = migrations.iter().map(|m| m.identifier()).collect();
+    assert_eq!(identifiers, vec!["1", "analytics:1", "R__user_view"]);
+}
+
+#[test]
+fn test_file_pattern_named_groups_drive_parsing() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    write_migration(temp_dir.path(), "V7__create_users.sql", "CREATE TABLE users (id INT);");
+    write_migration(temp_dir.path(), "0001_legacy.sql", "-- legacy");
+    write_migration(temp_dir.path(), "R__views.sql", "-- view");
+
+    let options = LoaderOptions {
+        file_pattern: Some(r"^V(?P<version>\d+)__(?P<name>.+)\.sql$".to_string()),
+        ..Default::default()
+    };
+    let loaded =
+        MigrationLoader::load_migrations_with_report(temp_dir.path().to_str().unwrap(), &options)
+            .expect("Failed to load migrations");
+
+    assert_eq!(loaded.migrations.len(), 2);
+    assert_eq!(loaded.migrations[0].version, Some(7));
+    assert_eq!(loaded.migrations[0].name, "create_users");
+    assert!(loaded.migrations[1].is_repeatable());
+    assert_eq!(loaded.excluded.len(), 1);
+    assert!(loaded.excluded[0].ends_with("0001_legacy.sql"));
+}
+
+#[test]
+fn test_invalid_file_pattern_is_an_error() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let options = LoaderOptions {
+        file_pattern: Some("([".to_string()),
+        ..Default::default()
+    };
+
+    let result =
+        MigrationLoader::load_migrations_with_options(temp_dir.path().to_str().unwrap(), &options);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_invalid_files_are_reported_not_loaded() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    write_migration(temp_dir.path(), "0001_init.sql", "\u{feff}CREATE TABLE test (id INT);");
+    fs::write(temp_dir.path().join("0002_binary.sql"), [0x43, 0x00, 0x01]).unwrap();
+    fs::write(temp_dir.path().join("0003_latin1.sql"), [0x2d, 0x2d, 0x20, 0xe9]).unwrap();
+    fs::write(temp_dir.path().join("0004_large.sql"), vec![b' '; 1024 * 1024 + 1]).unwrap();
+
+    let options = LoaderOptions {
+        max_file_size_mb: Some(1),
+        ..Default::default()
+    };
+    let loaded =
+        MigrationLoader::load_migrations_with_report(temp_dir.path().to_str().unwrap(), &options)
+            .expect("Failed to load migrations");
+
+    assert_eq!(loaded.migrations.len(), 1);
+    assert_eq!(loaded.migrations[0].sql_content, "CREATE TABLE test (id INT);");
+
+    let reasons: Vec<_> = loaded.invalid.iter().map(|f| f.reason.clone()).collect();
+    assert_eq!(reasons[0], InvalidFileReason::Binary);
+    assert_eq!(reasons[1], InvalidFileReason::InvalidUtf8(3));
+    assert!(matches!(reasons[2], InvalidFileReason::TooLarge { limit_mb: 1, .. }));
+}
+
+#[test]
+fn test_checksum_normalization_ignores_line_endings() {
+    let unix_dir = TempDir::new().expect("Failed to create temp dir");
+    let windows_dir = TempDir::new().expect("Failed to create temp dir");
+    write_migration(unix_dir.path(), "0001_init.sql", "CREATE TABLE t (\n  id INT\n);");
+    write_migration(windows_dir.path(), "0001_init.sql", "CREATE TABLE t (\r\n  id INT\r\n);");
+
+    let options = LoaderOptions {
+        checksum_normalization: ChecksumNormalization::Whitespace,
+        ..Default::default()
+    };
+    let load = |dir: &TempDir| {
+        MigrationLoader::load_migrations_with_options(dir.path().to_str().unwrap(), &options)
+            .expect("Failed to load migrations")
+    };
+    let unix = load(&unix_dir);
+    let windows = load(&windows_dir);
+
+    assert_eq!(unix[0].checksum, windows[0].checksum);
+
+    // Checksums recorded before normalization was enabled still match
+    let strict = MigrationLoader::load_migrations(unix_dir.path().to_str().unwrap()).unwrap();
+    assert!(unix[0].matches_checksum(&strict[0].checksum));
+}
+
+#[test]
+fn test_streamed_files_match_in_memory_loading() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    write_migration(
+        temp_dir.path(),
+        "0001_seed.sql",
+        "-- deriddl:requires 0000\n-- +migrate Up\nCREATE TABLE t (\r\n  id INT\r\n);\nINSERT INTO t VALUES (1);\n\n-- +migrate Down\nDROP TABLE t;\n",
+    );
+    let path = temp_dir.path().to_str().unwrap();
+
+    for normalization in [ChecksumNormalization::Strict, ChecksumNormalization::Whitespace] {
+        let in_memory = LoaderOptions { checksum_normalization: normalization, ..Default::default() };
+        let streaming = LoaderOptions { stream_threshold_mb: Some(0), ..in_memory.clone() };
+        let expected = &MigrationLoader::load_migrations_with_options(path, &in_memory).unwrap()[0];
+        let streamed = &MigrationLoader::load_migrations_with_options(path, &streaming).unwrap()[0];
+
+        assert!(expected.streamed.is_none());
+        assert!(streamed.streamed.is_some());
+        assert!(streamed.sql_content.is_empty());
+        assert_eq!(streamed.checksum, expected.checksum);
+        assert_eq!(streamed.rollback_sql, expected.rollback_sql);
+        assert_eq!(streamed.requires, vec![0]);
+        assert_eq!(streamed.up_sql().unwrap(), expected.sql_content);
+    }
+}
+
+#[test]
+fn test_loaded_migrations_use_the_configured_strategies() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    write_migration(temp_dir.path(), "0001_init.sql", "CREATE TABLE test (id INT);");
+    let path = temp_dir.path().to_str().unwrap();
+
+    let options = LoaderOptions {
+        checksum: ChecksumAlgorithm::Crc32.strategy(),
+        id_strategy: MigrationIdScheme::Filename.strategy(),
+        ..Default::default()
+    };
+    let default = &MigrationLoader::load_migrations_with_options(path, &LoaderOptions::default()).unwrap()[0];
+    let configured = &MigrationLoader::load_migrations_with_options(path, &options).unwrap()[0];
+
+    assert_eq!(default.identifier(), "1");
+    assert_eq!(configured.identifier(), "0001_init");
+    assert_eq!(configured.checksum, options.checksum.checksum(&configured.sql_content, ChecksumNormalization::Strict));
+    assert_ne!(configured.checksum, default.checksum);
+    assert!(configured.matches_checksum(&configured.checksum));
+}
+
+#[test]
+fn test_parallel_loading_keeps_file_order() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    for version in 1..=200 {
+        let content = if version % 10 == 0 { "\0".to_string() } else { format!("SELECT {};", version) };
+        write_migration(temp_dir.path(), &format!("{:04}_m{}.sql", version, version), &content);
+    }
+
+    let loaded = MigrationLoader::load_migrations_with_report(
+        temp_dir.path().to_str().unwrap(),
+        &LoaderOptions::default(),
+    )
+    .expect("Failed to load migrations");
+
+    let versions: Vec<u32> = loaded.migrations.iter().filter_map(|m| m.version).collect();
+    let expected: Vec<u32> = (1..=200).filter(|v| v % 10 != 0).collect();
+    assert_eq!(versions, expected);
+
+    let invalid: Vec<String> = loaded
+        .invalid
+        .iter()
+        .map(|f| f.path.file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+    let expected: Vec<String> = (1..=20).map(|i| format!("{:04}_m{}.sql", i * 10, i * 10)).collect();
+    assert_eq!(invalid, expected);
+}
+
+#[test]
+fn test_crlf_markers_split_up_and_down_sections() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    write_migration(
+        temp_dir.path(),
+        "0001_users.sql",
+        "-- +migrate Up\r\nCREATE TABLE users (id INT);\r\n-- +migrate Down\r\nDROP TABLE users;\r\n",
+    );
+    write_migration(
+        temp_dir.path(),
+        "0002_orders.sql",
+        "--  up\r\n-- update orders in a later migration\r\nCREATE TABLE orders (id INT);\r\n--DOWN \r\nDROP TABLE orders;\r\n",
+    );
+
+    let migrations = MigrationLoader::load_migrations(temp_dir.path().to_str().unwrap()).unwrap();
+
+    assert_eq!(migrations[0].sql_content, "CREATE TABLE users (id INT);");
+    assert_eq!(migrations[0].get_rollback_sql(), Some("DROP TABLE users;"));
+    assert_eq!(
+        migrations[1].sql_content,
+        "-- update orders in a later migration\r\nCREATE TABLE orders (id INT);"
+    );
+    assert_eq!(migrations[1].get_rollback_sql(), Some("DROP TABLE orders;"));
+}
+
+#[test]
+fn test_utf16_files_are_decoded() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let sql = "-- +migrate Up\r\nCREATE TABLE t (id INT);\r\n-- +migrate Down\r\nDROP TABLE t;\r\n";
+    let mut bytes = vec![0xFF, 0xFE];
+    bytes.extend(sql.encode_utf16().flat_map(u16::to_le_bytes));
+    fs::write(temp_dir.path().join("0001_init.sql"), bytes).unwrap();
+
+    let migrations = MigrationLoader::load_migrations(temp_dir.path().to_str().unwrap()).unwrap();
+
+    assert_eq!(migrations.len(), 1);
+    assert_eq!(migrations[0].sql_content, "CREATE TABLE t (id INT);");
+    assert_eq!(migrations[0].get_rollback_sql(), Some("DROP TABLE t;"));
+}
+
+#[test]
+fn test_skip_list_matches_versions_ids_and_file_names() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    write_migration(temp_dir.path(), "0001_init.sql", "CREATE TABLE t (id INT);");
+    write_migration(temp_dir.path(), "0007_superseded.sql", "ALTER TABLE t ADD note TEXT;");
+    write_migration(temp_dir.path(), "R__views.sql", "CREATE VIEW v AS SELECT * FROM t;");
+
+    let migrations = MigrationLoader::load_migrations(temp_dir.path().to_str().unwrap()).unwrap();
+    let options = LoaderOptions { skip: vec!["7".to_string(), "R__views.sql".to_string()], ..Default::default() };
+    let skipped: Vec<String> = migrations.iter().filter(|m| options.is_skip_listed(m)).map(|m| m.filename()).collect();
+
+    assert_eq!(skipped, vec!["0007_superseded.sql", "R__views.sql"]);
+    assert!(!LoaderOptions::default().is_skip_listed(&migrations[1]));
+}
@@ -13,7 +13,7 @@ fn test_status_command_no_connection() {
         .current_dir(&temp_dir)
         .assert()
         .failure()
-        .stdout(contains("No connection string provided"));
+        .stderr(contains("No connection string provided"));
 }
 
 #[test]
@@ -27,5 +27,228 @@ fn test_apply_command_no_connection() {
         .current_dir(&temp_dir)
         .assert()
         .failure()
-        .stdout(contains("No connection string provided"));
+        .stderr(contains("No connection string provided"));
+}
+
+/// A migrations directory whose second migration fails until it is fixed
+fn setup_failing_migrations() -> tempfile::TempDir {
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp directory");
+    let migrations_dir = temp_dir.path().join("migrations");
+    std::fs::create_dir(&migrations_dir).expect("Failed to create migrations directory");
+    std::fs::write(migrations_dir.join("0001_create_users.sql"), "CREATE TABLE users (id INTEGER PRIMARY KEY);").unwrap();
+    std::fs::write(migrations_dir.join("0002_create_posts.sql"), "CREATE TABLE posts (id INTEGER PRIMARY KEY title TEXT);").unwrap();
+    temp_dir
+}
+
+fn fix_failing_migration(temp_dir: &tempfile::TempDir) {
+    std::fs::write(
+        temp_dir.path().join("migrations").join("0002_create_posts.sql"),
+        "CREATE TABLE posts (id INTEGER PRIMARY KEY, title TEXT);",
+    )
+    .unwrap();
+}
+
+fn apply_cmd(temp_dir: &tempfile::TempDir, conn: &str) -> assert_cmd::Command {
+    let mut cmd = deri_ddl_cmd();
+    cmd.args(["apply", "--conn", conn, "--path"])
+        .arg(temp_dir.path().join("migrations"))
+        .current_dir(temp_dir);
+    cmd
+}
+
+#[test]
+fn test_failed_migration_can_be_retried() {
+    let temp_dir = setup_failing_migrations();
+    let conn = common::test_sqlite_connection();
+    common::init_test_database(&conn).expect("Failed to initialize database");
+
+    apply_cmd(&temp_dir, &conn).assert().failure();
+    fix_failing_migration(&temp_dir);
+    apply_cmd(&temp_dir, &conn).assert().success().stdout(contains("0002_create_posts.sql applied successfully"));
+}
+
+#[test]
+fn test_atomic_apply_can_be_retried_after_a_failure() {
+    let temp_dir = setup_failing_migrations();
+    let conn = common::test_sqlite_connection();
+    common::init_test_database(&conn).expect("Failed to initialize database");
+
+    apply_cmd(&temp_dir, &conn).arg("--atomic").assert().failure().stderr(contains("the whole batch was rolled back"));
+    fix_failing_migration(&temp_dir);
+    apply_cmd(&temp_dir, &conn).arg("--atomic").assert().success().stdout(contains("All 2 migrations applied in one transaction"));
+}
+
+#[test]
+fn test_resume_after_a_failed_migration() {
+    let temp_dir = setup_failing_migrations();
+    let conn = common::test_sqlite_connection();
+    common::init_test_database(&conn).expect("Failed to initialize database");
+
+    let output = apply_cmd(&temp_dir, &conn).output().unwrap();
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let run_id = stdout
+        .split_once("(run ")
+        .and_then(|(_, rest)| rest.split_once(')'))
+        .map(|(run_id, _)| run_id.to_string())
+        .expect("apply logs its run id");
+
+    fix_failing_migration(&temp_dir);
+    apply_cmd(&temp_dir, &conn)
+        .args(["--resume", &run_id])
+        .assert()
+        .success()
+        .stdout(contains(format!("Resuming run {}: 1 of 2 planned migrations remain", run_id)))
+        .stdout(contains("0002_create_posts.sql applied successfully"));
+}
+
+/// Runs apply, sends SIGINT once it starts the slow second migration and returns its exit
+/// status and output
+#[cfg(unix)]
+fn apply_interrupted_during_second_migration(temp_dir: &tempfile::TempDir, conn: &str) -> (Option<i32>, String) {
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_deriddl_rs"))
+        .args(["apply", "--conn", conn, "--path"])
+        .arg(temp_dir.path().join("migrations"))
+        .current_dir(temp_dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdout = String::new();
+    let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+    for line in lines.by_ref() {
+        let line = line.unwrap();
+        stdout.push_str(&line);
+        stdout.push('\n');
+        if line.contains("Applying migration: 0002") {
+            let killed = Command::new("kill").args(["-INT", &child.id().to_string()]).status().unwrap();
+            assert!(killed.success());
+            break;
+        }
+    }
+    for line in lines {
+        stdout.push_str(&line.unwrap());
+        stdout.push('\n');
+    }
+    let status = child.wait().unwrap();
+    (status.code(), stdout)
+}
+
+#[test]
+#[cfg(unix)]
+fn test_interrupt_records_the_failure_and_resume_finishes_the_run() {
+    use deriddl_rs::tracker::run_progress::RunStatus;
+    use deriddl_rs::tracker::{RunProgress, Target, VersionStore};
+
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp directory");
+    let migrations_dir = temp_dir.path().join("migrations");
+    std::fs::create_dir(&migrations_dir).unwrap();
+    std::fs::write(migrations_dir.join("0001_create_users.sql"), "CREATE TABLE users (id INTEGER PRIMARY KEY);").unwrap();
+    // Busy for a few seconds, so the signal arrives while the first statement runs
+    std::fs::write(
+        migrations_dir.join("0002_slow_posts.sql"),
+        "CREATE TABLE counted AS WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 30000000) \
+         SELECT COUNT(*) AS total FROM n;\nCREATE TABLE posts (id INTEGER PRIMARY KEY, title TEXT);",
+    )
+    .unwrap();
+    std::fs::write(migrations_dir.join("0003_create_tags.sql"), "CREATE TABLE tags (id INTEGER PRIMARY KEY);").unwrap();
+    let conn = common::test_sqlite_connection();
+    common::init_test_database(&conn).expect("Failed to initialize database");
+
+    let (code, stdout) = apply_interrupted_during_second_migration(&temp_dir, &conn);
+    assert_eq!(code, Some(130), "{}", stdout);
+    let run_id = stdout
+        .split_once("(run ")
+        .and_then(|(_, rest)| rest.split_once(')'))
+        .map(|(run_id, _)| run_id.to_string())
+        .expect("apply logs its run id");
+
+    // The checkpoint marks the run interrupted after the first migration
+    let checkpoint = RunProgress::new(&Target::new(&conn)).load(&run_id).unwrap().expect("run checkpoint recorded");
+    assert_eq!(checkpoint.status, RunStatus::Interrupted);
+    assert_eq!(checkpoint.completed, 1);
+    assert_eq!(checkpoint.last_completed.as_deref(), Some("1"));
+
+    // The interrupted migration is recorded as failed, the one after it not at all
+    let applied = VersionStore::new(&conn).unwrap().get_applied_migrations().unwrap();
+    let record = |version: u32| applied.iter().find(|a| a.version == Some(version));
+    assert!(record(1).is_some_and(|a| a.success));
+    assert!(record(2).is_some_and(|a| !a.success));
+    assert!(record(3).is_none());
+
+    apply_cmd(&temp_dir, &conn)
+        .args(["--resume", &run_id])
+        .assert()
+        .success()
+        .stdout(contains(format!("Resuming run {}: 2 of 3 planned migrations remain", run_id)))
+        .stdout(contains("0003_create_tags.sql applied successfully"));
+    let checkpoint = RunProgress::new(&Target::new(&conn)).load(&run_id).unwrap().unwrap();
+    assert_eq!(checkpoint.status, RunStatus::Completed);
+    let applied = VersionStore::new(&conn).unwrap().get_applied_migrations().unwrap();
+    assert_eq!(applied.iter().filter(|a| a.success).count(), 3);
+}
+
+#[test]
+fn test_statements_past_the_statement_timeout_are_cancelled() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp directory");
+    let migrations_dir = temp_dir.path().join("migrations");
+    std::fs::create_dir(&migrations_dir).unwrap();
+    std::fs::write(
+        migrations_dir.join("0001_slow.sql"),
+        "CREATE TABLE counted AS WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 100000000) \
+         SELECT COUNT(*) AS total FROM n;",
+    )
+    .unwrap();
+    let config = temp_dir.path().join("deriddl.toml");
+    std::fs::write(&config, "[database]\nstatement_timeout_ms = 200\n").unwrap();
+    let conn = common::test_sqlite_connection();
+    common::init_test_database(&conn).expect("Failed to initialize database");
+
+    apply_cmd(&temp_dir, &conn)
+        .args(["--config", config.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(contains("Statement cancelled after exceeding database.statement_timeout_ms (200ms)"));
+    let applied = deriddl_rs::tracker::VersionStore::new(&conn).unwrap().get_applied_migrations().unwrap();
+    assert!(applied.iter().any(|a| a.version == Some(1) && !a.success), "the cancelled migration is recorded as failed");
+}
+
+#[test]
+fn test_archived_content_restores_the_applied_files() {
+    let temp_dir = setup_test_migrations();
+    let migrations_dir = temp_dir.path().join("migrations");
+    let original = std::fs::read_to_string(migrations_dir.join("0002_add_email.sql")).unwrap();
+    let config = temp_dir.path().join("deriddl.toml");
+    std::fs::write(&config, "[tracker]\nstore_content = true\n").unwrap();
+    let conn = common::test_sqlite_connection();
+    common::init_test_database(&conn).expect("Failed to initialize database");
+
+    apply_cmd(&temp_dir, &conn).args(["--config", config.to_str().unwrap()]).assert().success();
+
+    // The archive is looked up by the id and checksum schema_migrations records
+    let mut version_store = deriddl_rs::tracker::VersionStore::new(&conn).unwrap();
+    let applied = version_store.get_applied_migrations().unwrap();
+    let second = applied.iter().find(|a| a.version == Some(2)).expect("0002 is applied");
+    let sql = version_store.sql().clone();
+    let mut executor = version_store.executor().unwrap();
+    let archived = deriddl_rs::tracker::content_archive::archived_content(&mut executor, &sql, &second.migration_id, &second.checksum)
+        .unwrap()
+        .expect("0002 is archived");
+    assert_eq!(archived.filename, "0002_add_email.sql");
+    assert_eq!(archived.content, original);
+    assert!(deriddl_rs::tracker::content_archive::archived_content(&mut executor, &sql, &second.migration_id, "stale").unwrap().is_none());
+
+    let restored = temp_dir.path().join("restored");
+    deri_ddl_cmd()
+        .args(["restore-files", "--conn", &conn, "--versions", "1..2", "--out"])
+        .arg(&restored)
+        .current_dir(&temp_dir)
+        .assert()
+        .success();
+    assert_eq!(std::fs::read_to_string(restored.join("0002_add_email.sql")).unwrap(), original);
+    assert!(restored.join("0001_init_schema.sql").exists());
+    assert!(!restored.join("0003_create_posts.sql").exists());
 }
@@ -1,5 +1,6 @@
 use deriddl_rs::model::{Migration, MigrationType};
-use deriddl_rs::orchestrator::{MigrationLoader, Validator};
+use deriddl_rs::orchestrator::{MigrationLoader, Planner, Validator};
+use deriddl_rs::tracker::VersionStore;
 use std::fs;
 use std::path::PathBuf;
 use tempfile::TempDir;
@@ -168,7 +169,7 @@ fn test_migration_sorting_with_mixed_types() {
         .expect("Failed to load migrations");
     
     // Should be sorted: versioned by version, then repeatable by name
-    let expected_order = vec![
+    let expected_order = [
         "0001_first.sql",
         "0002_second.sql", 
         "0003_third.sql",
@@ -184,7 +185,7 @@ fn test_migration_sorting_with_mixed_types() {
 
 #[test]
 fn test_repeatable_migration_identifiers() {
-    let migrations = vec![
+    let migrations = [
         make_versioned_migration(1, "init"),
         make_versioned_migration(42, "answer"),
         make_repeatable_migration("create_views"),
@@ -225,4 +226,72 @@ fn test_only_repeatable_migrations() {
     assert_eq!(migrations[0].name, "functions");
     assert_eq!(migrations[1].name, "procedures");  
     assert_eq!(migrations[2].name, "views");
-}
\ No newline at end of file
+}
+#[test]
+fn test_repeatable_priority_prefix_orders_loading() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    create_test_migration_file(&temp_dir, "R__a_unprioritized.sql", "SELECT 1;");
+    create_test_migration_file(&temp_dir, "R020__b_reports.sql", "CREATE VIEW b AS SELECT * FROM a;");
+    create_test_migration_file(&temp_dir, "R010__z_base.sql", "CREATE VIEW a AS SELECT 1;");
+
+    let migrations = MigrationLoader::load_migrations(temp_dir.path().to_str().unwrap())
+        .expect("Failed to load migrations");
+
+    let names: Vec<&str> = migrations.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, vec!["z_base", "b_reports", "a_unprioritized"]);
+    assert_eq!(migrations[0].priority, Some(10));
+    assert_eq!(migrations[0].filename(), "R010__z_base.sql");
+    // The priority is not part of the identifier, so it can change without re-running
+    assert_eq!(migrations[0].identifier(), "R__z_base");
+}
+
+#[test]
+fn test_after_directive_orders_and_detects_cycles() {
+    let reports = Migration::new_repeatable(
+        "a_reports".to_string(),
+        PathBuf::from("R__a_reports.sql"),
+        "-- deriddl:after R__b_base\nCREATE VIEW reports AS SELECT * FROM base;".to_string(),
+    );
+    let base = make_repeatable_migration("b_base");
+    assert_eq!(reports.after, vec!["b_base"]);
+
+    let ordered = Planner::order_migrations(vec![reports.clone(), base.clone()])
+        .expect("Failed to order migrations");
+    let names: Vec<&str> = ordered.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, vec!["b_base", "a_reports"]);
+
+    let cyclic_base = Migration::new_repeatable(
+        "b_base".to_string(),
+        PathBuf::from("R__b_base.sql"),
+        "-- deriddl:after a_reports\nCREATE VIEW base AS SELECT 1;".to_string(),
+    );
+    let issues = Validator::validate_migration_sequence(&[reports, cyclic_base]);
+    assert!(issues.iter().any(|msg| msg.contains("Dependency cycle detected")));
+}
+
+#[test]
+fn test_repeatables_depending_on_altered_tables_become_stale() {
+    let alter_users = Migration::new(
+        2,
+        "add_email".to_string(),
+        PathBuf::from("0002_add_email.sql"),
+        "ALTER TABLE \"app\".\"users\" ADD COLUMN email TEXT;".to_string(),
+    );
+    assert_eq!(alter_users.altered_tables(), vec!["app.users"]);
+
+    let user_views = Migration::new_repeatable(
+        "user_views".to_string(),
+        PathBuf::from("R__user_views.sql"),
+        "-- deriddl:depends-on-table users\nCREATE OR REPLACE VIEW v AS SELECT * FROM users;".to_string(),
+    );
+    let order_views = Migration::new_repeatable(
+        "order_views".to_string(),
+        PathBuf::from("R__order_views.sql"),
+        "-- deriddl:depends-on-table orders\nCREATE OR REPLACE VIEW o AS SELECT * FROM orders;".to_string(),
+    );
+    let all = vec![alter_users.clone(), order_views, user_views];
+
+    let pending = VersionStore::include_stale_repeatables(vec![alter_users], &all);
+    let names: Vec<String> = pending.iter().map(|m| m.filename()).collect();
+    assert_eq!(names, vec!["0002_add_email.sql", "R__user_views.sql"]);
+}
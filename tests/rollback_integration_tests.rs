@@ -262,4 +262,50 @@ fn test_rollback_cli_args_validation() {
         .assert()
         .success() // Should handle gracefully
         .stdout(contains("No migrations to roll back"));
-}
\ No newline at end of file
+}
+#[test]
+fn test_rollback_of_one_namespace_keeps_the_other_sharing_its_version() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp directory");
+    for namespace in ["core", "analytics"] {
+        let dir = temp_dir.path().join(namespace);
+        fs::create_dir(&dir).unwrap();
+        fs::write(
+            dir.join("0001_create_items.sql"),
+            format!("-- +migrate Up\nCREATE TABLE {0}_items (id INTEGER);\n\n-- +migrate Down\nDROP TABLE {0}_items;\n", namespace),
+        )
+        .unwrap();
+    }
+    let config = temp_dir.path().join("deriddl.toml");
+    fs::write(
+        &config,
+        "[migrations]\npaths = [\n  { path = \"core\", namespace = \"core\" },\n  { path = \"analytics\", namespace = \"analytics\" },\n]\n",
+    )
+    .unwrap();
+    let connection_string = test_sqlite_connection();
+    init_test_database(&connection_string).expect("Failed to initialize database");
+
+    deri_ddl_cmd()
+        .args(["apply", "--conn", &connection_string, "--config", config.to_str().unwrap()])
+        .current_dir(&temp_dir)
+        .assert()
+        .success();
+    deri_ddl_cmd()
+        .args(["--yes", "rollback", "--conn", &connection_string, "--config", config.to_str().unwrap(), "--steps", "1"])
+        .current_dir(&temp_dir)
+        .assert()
+        .success();
+
+    let mut version_store = deriddl_rs::tracker::VersionStore::new(&connection_string).unwrap();
+    let applied: Vec<String> = version_store.get_applied_migrations().unwrap().into_iter().map(|a| a.migration_id).collect();
+    assert_eq!(applied.len(), 1, "only one namespace's version 1 is rolled back: {:?}", applied);
+    let (kept, rolled_back) = if applied[0] == "core:1" { ("core", "analytics") } else { ("analytics", "core") };
+    assert_eq!(applied[0], format!("{}:1", kept));
+    let mut executor = version_store.executor().unwrap();
+    let table_count = |executor: &mut deriddl_rs::executor::DatabaseExecutor, namespace: &str| {
+        executor
+            .query_single_value(&format!("SELECT COUNT(*) FROM sqlite_master WHERE name = '{}_items'", namespace))
+            .unwrap()
+    };
+    assert_eq!(table_count(&mut executor, kept).as_deref(), Some("1"), "the other namespace's table stays");
+    assert_eq!(table_count(&mut executor, rolled_back).as_deref(), Some("0"), "its own down SQL ran");
+}
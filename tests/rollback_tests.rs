@@ -13,7 +13,7 @@ fn test_rollback_command_no_connection() {
         .current_dir(&temp_dir)
         .assert()
         .failure()
-        .stdout(contains("No connection string provided"));
+        .stderr(contains("No connection string provided"));
 }
 
 #[test]
@@ -96,7 +96,7 @@ fn test_rollback_to_version_validation_no_applied_migrations() {
         .current_dir(&temp_dir)
         .assert()
         .failure()
-        .stdout(contains("Cannot rollback to version 1: migration not found or not applied"));
+        .stderr(contains("Cannot rollback to version 1: migration not found or not applied"));
 }
 
 #[test]
@@ -182,7 +182,7 @@ fn test_rollback_with_applied_migrations() {
         .current_dir(&temp_dir)
         .assert()
         .failure()
-        .stdout(contains("cannot be rolled back: no rollback SQL found"));
+        .stderr(contains("cannot be rolled back: no rollback SQL found"));
 }
 
 #[test]
@@ -211,32 +211,18 @@ fn test_rollback_missing_rollback_sql_error() {
         .current_dir(&temp_dir)
         .assert()
         .failure()
-        .stdout(contains("cannot be rolled back: no rollback SQL found"));
+        .stderr(contains("cannot be rolled back: no rollback SQL found"));
 }
 
 #[cfg(test)]
 mod unit_tests {
-    use super::*;
     use deriddl_rs::model::migration::Migration;
     use deriddl_rs::orchestrator::rollback::{RollbackStrategy, create_rollback_plan, validate_rollback_plan, RollbackError};
     use deriddl_rs::tracker::version_store::AppliedMigration;
     use deriddl_rs::model::migration::MigrationType;
-    use chrono::Utc;
     use std::path::PathBuf;
     use std::collections::HashMap;
-
-    fn create_test_applied_migration(version: u32, filename: &str) -> AppliedMigration {
-        AppliedMigration {
-            migration_id: version.to_string(),
-            migration_type: MigrationType::Versioned,
-            version: Some(version),
-            filename: filename.to_string(),
-            checksum: "test_checksum".to_string(),
-            applied_at: Utc::now(),
-            execution_time_ms: 100,
-            success: true,
-        }
-    }
+    use crate::common::applied_migration;
 
     #[test]
     fn test_migration_parsing_with_rollback_sql() {
@@ -361,9 +347,9 @@ DROP TABLE IF EXISTS users;
     #[test]
     fn test_rollback_strategy_steps() {
         let applied_migrations = vec![
-            create_test_applied_migration(3, "0003_migration.sql"),
-            create_test_applied_migration(2, "0002_migration.sql"),
-            create_test_applied_migration(1, "0001_migration.sql"),
+            applied_migration(3, "0003_migration.sql"),
+            applied_migration(2, "0002_migration.sql"),
+            applied_migration(1, "0001_migration.sql"),
         ];
 
         let strategy = RollbackStrategy::Steps(2);
@@ -379,8 +365,8 @@ DROP TABLE IF EXISTS users;
     #[test]
     fn test_rollback_strategy_steps_exceeds_available() {
         let applied_migrations = vec![
-            create_test_applied_migration(2, "0002_migration.sql"),
-            create_test_applied_migration(1, "0001_migration.sql"),
+            applied_migration(2, "0002_migration.sql"),
+            applied_migration(1, "0001_migration.sql"),
         ];
 
         let strategy = RollbackStrategy::Steps(5); // More than available
@@ -395,11 +381,11 @@ DROP TABLE IF EXISTS users;
     #[test]
     fn test_rollback_strategy_to_version() {
         let applied_migrations = vec![
-            create_test_applied_migration(5, "0005_migration.sql"),
-            create_test_applied_migration(4, "0004_migration.sql"),
-            create_test_applied_migration(3, "0003_migration.sql"),
-            create_test_applied_migration(2, "0002_migration.sql"),
-            create_test_applied_migration(1, "0001_migration.sql"),
+            applied_migration(5, "0005_migration.sql"),
+            applied_migration(4, "0004_migration.sql"),
+            applied_migration(3, "0003_migration.sql"),
+            applied_migration(2, "0002_migration.sql"),
+            applied_migration(1, "0001_migration.sql"),
         ];
 
         let strategy = RollbackStrategy::ToVersion(2);
@@ -415,9 +401,9 @@ DROP TABLE IF EXISTS users;
     #[test]
     fn test_rollback_strategy_to_version_invalid() {
         let applied_migrations = vec![
-            create_test_applied_migration(3, "0003_migration.sql"),
-            create_test_applied_migration(2, "0002_migration.sql"),
-            create_test_applied_migration(1, "0001_migration.sql"),
+            applied_migration(3, "0003_migration.sql"),
+            applied_migration(2, "0002_migration.sql"),
+            applied_migration(1, "0001_migration.sql"),
         ];
 
         // Try to rollback to version 5 (higher than any applied)
@@ -431,16 +417,40 @@ DROP TABLE IF EXISTS users;
         }
     }
 
+    #[test]
+    fn test_rollback_strategy_release() {
+        let applied_migrations: Vec<AppliedMigration> = [(1, "2024.05"), (2, "2024.06"), (3, "2024.06"), (4, "2024.07")]
+            .into_iter()
+            .map(|(version, release)| AppliedMigration {
+                release: Some(release.to_string()),
+                ..applied_migration(version, &format!("{:04}_migration.sql", version))
+            })
+            .collect();
+
+        let strategy = RollbackStrategy::Release("2024.06".to_string());
+        let plan = create_rollback_plan(&applied_migrations, &strategy).unwrap();
+
+        // Only the release's own migrations, newest first
+        let versions: Vec<_> = plan.migrations_to_rollback.iter().map(|m| m.version).collect();
+        assert_eq!(versions, vec![Some(3), Some(2)]);
+
+        let strategy = RollbackStrategy::Release("2023.01".to_string());
+        match create_rollback_plan(&applied_migrations, &strategy) {
+            Err(RollbackError::UnknownRelease(release)) => assert_eq!(release, "2023.01"),
+            _ => panic!("Expected UnknownRelease error"),
+        }
+    }
+
     #[test]
     fn test_rollback_strategy_filters_repeatable_migrations() {
         let mut applied_migrations = vec![
-            create_test_applied_migration(3, "0003_migration.sql"),
-            create_test_applied_migration(2, "0002_migration.sql"),
-            create_test_applied_migration(1, "0001_migration.sql"),
+            applied_migration(3, "0003_migration.sql"),
+            applied_migration(2, "0002_migration.sql"),
+            applied_migration(1, "0001_migration.sql"),
         ];
         
         // Add a repeatable migration (should be filtered out)
-        let mut repeatable = create_test_applied_migration(0, "R__view.sql");
+        let mut repeatable = applied_migration(0, "R__view.sql");
         repeatable.migration_type = MigrationType::Repeatable;
         repeatable.version = None;
         repeatable.migration_id = "R__view".to_string();
@@ -460,9 +470,9 @@ DROP TABLE IF EXISTS users;
     #[test]
     fn test_rollback_strategy_filters_failed_migrations() {
         let mut applied_migrations = vec![
-            create_test_applied_migration(3, "0003_migration.sql"),
-            create_test_applied_migration(2, "0002_migration.sql"),
-            create_test_applied_migration(1, "0001_migration.sql"),
+            applied_migration(3, "0003_migration.sql"),
+            applied_migration(2, "0002_migration.sql"),
+            applied_migration(1, "0001_migration.sql"),
         ];
         
         // Mark one migration as failed
@@ -489,12 +499,12 @@ CREATE TABLE test (id INTEGER);
 DROP TABLE test;"#.to_string()
         );
 
-        let applied_migrations = vec![create_test_applied_migration(1, "0001_test.sql")];
+        let applied_migrations = vec![applied_migration(1, "0001_test.sql")];
         let strategy = RollbackStrategy::Steps(1);
         let plan = create_rollback_plan(&applied_migrations, &strategy).unwrap();
 
         let mut migration_map = HashMap::new();
-        migration_map.insert(1, &migration_with_rollback);
+        migration_map.insert("1".to_string(), &migration_with_rollback);
 
         let result = validate_rollback_plan(&plan, &migration_map);
         assert!(result.is_ok());
@@ -509,12 +519,12 @@ DROP TABLE test;"#.to_string()
             "CREATE TABLE test (id INTEGER);".to_string()
         );
 
-        let applied_migrations = vec![create_test_applied_migration(1, "0001_test.sql")];
+        let applied_migrations = vec![applied_migration(1, "0001_test.sql")];
         let strategy = RollbackStrategy::Steps(1);
         let plan = create_rollback_plan(&applied_migrations, &strategy).unwrap();
 
         let mut migration_map = HashMap::new();
-        migration_map.insert(1, &migration_without_rollback);
+        migration_map.insert("1".to_string(), &migration_without_rollback);
 
         let result = validate_rollback_plan(&plan, &migration_map);
         assert!(result.is_err());
@@ -526,7 +536,7 @@ DROP TABLE test;"#.to_string()
 
     #[test]
     fn test_validate_rollback_plan_missing_migration_file() {
-        let applied_migrations = vec![create_test_applied_migration(1, "0001_test.sql")];
+        let applied_migrations = vec![applied_migration(1, "0001_test.sql")];
         let strategy = RollbackStrategy::Steps(1);
         let plan = create_rollback_plan(&applied_migrations, &strategy).unwrap();
 
@@ -540,6 +550,29 @@ DROP TABLE test;"#.to_string()
         }
     }
 
+    #[test]
+    fn test_validate_rollback_plan_keeps_namespaces_sharing_a_version_apart() {
+        let namespaced = |namespace: &str, content: &str| Migration {
+            namespace: Some(namespace.to_string()),
+            ..Migration::new(3, "items".to_string(), PathBuf::from(format!("{}/0003_items.sql", namespace)), content.to_string())
+        };
+        let core = namespaced("core", "-- +migrate Up\nCREATE TABLE core_items (id INTEGER);\n-- +migrate Down\nDROP TABLE core_items;");
+        let analytics = namespaced("analytics", "CREATE TABLE analytics_items (id INTEGER);");
+        let migration_map: HashMap<String, &Migration> =
+            [(core.identifier(), &core), (analytics.identifier(), &analytics)].into_iter().collect();
+        assert_eq!(migration_map.len(), 2, "both namespaces keep their version 3");
+
+        let applied = |migration_id: &str| AppliedMigration {
+            migration_id: migration_id.to_string(),
+            ..applied_migration(3, "0003_items.sql")
+        };
+        let plan = create_rollback_plan(&[applied("core:3")], &RollbackStrategy::Steps(1)).unwrap();
+        assert!(validate_rollback_plan(&plan, &migration_map).is_ok());
+        // analytics:3 has no down SQL of its own, so core's must not stand in for it
+        let plan = create_rollback_plan(&[applied("analytics:3")], &RollbackStrategy::Steps(1)).unwrap();
+        assert!(matches!(validate_rollback_plan(&plan, &migration_map), Err(RollbackError::NoRollbackSql(_))));
+    }
+
     #[test]
     fn test_repeatable_migration_rollback() {
         let migration = Migration::new_repeatable(
@@ -0,0 +1,32 @@
+use deriddl_rs::dialects;
+use deriddl_rs::model::Migration;
+use deriddl_rs::orchestrator::script::render_apply_script;
+use std::path::PathBuf;
+
+#[test]
+fn test_apply_script_wraps_migrations_with_bookkeeping() {
+    let migrations = vec![
+        Migration::new(
+            1,
+            "create_users".to_string(),
+            PathBuf::from("0001_create_users.sql"),
+            "CREATE TABLE users (id INT)".to_string(),
+        ),
+        Migration::new_repeatable(
+            "views".to_string(),
+            PathBuf::from("R__views.sql"),
+            "CREATE VIEW v AS SELECT 1;".to_string(),
+        ),
+    ];
+    let dialect = dialects::get_dialect("postgres").expect("postgres dialect");
+
+    let script = render_apply_script(&migrations, &dialect, true).unwrap();
+
+    assert!(script.contains("CREATE TABLE IF NOT EXISTS \"schema_migrations\""));
+    let insert = script.find("INSERT INTO \"schema_migrations\"").unwrap();
+    let begin = script.find("BEGIN TRANSACTION;").unwrap();
+    let body = script.find("CREATE TABLE users (id INT);").unwrap();
+    let update = script.find("UPDATE \"schema_migrations\" SET").unwrap();
+    assert!(insert < begin && begin < body && body < update);
+    assert!(script.contains("DELETE FROM \"schema_migrations\" WHERE \"migration_id\" = 'R__views';"));
+}
@@ -1,5 +1,10 @@
+mod common;
+
+use common::applied_migration;
 use deriddl_rs::model::Migration;
+use deriddl_rs::tracker::version_store::AppliedMigration;
 use deriddl_rs::orchestrator::validator::Validator;
+use deriddl_rs::orchestrator::Planner;
 use std::path::PathBuf;
 
 fn make_migration(version: u32, name: &str) -> Migration {
@@ -48,3 +53,95 @@ fn passes_valid_sequence() {
     assert!(issues.is_empty());
 }
 
+
+fn make_migration_with_requires(version: u32, name: &str, requires: &str) -> Migration {
+    let filename = format!("{:04}_{}.sql", version, name);
+    Migration::new(
+        version,
+        name.to_string(),
+        PathBuf::from(&filename),
+        format!("-- deriddl:requires {}\n-- migration {}", requires, version),
+    )
+}
+
+#[test]
+fn parses_requires_header() {
+    let migration = make_migration_with_requires(3, "third", "0001, 0002");
+    assert_eq!(migration.requires, vec![1, 2]);
+}
+
+#[test]
+fn detects_unsatisfiable_dependency() {
+    let migrations = vec![
+        make_migration(1, "first"),
+        make_migration_with_requires(2, "second", "0009"),
+    ];
+
+    let issues = Validator::validate_migration_sequence(&migrations);
+    assert!(issues.iter().any(|msg| msg.contains("Unsatisfiable dependency")));
+}
+
+#[test]
+fn detects_dependency_cycle() {
+    let migrations = vec![
+        make_migration_with_requires(1, "first", "0002"),
+        make_migration_with_requires(2, "second", "0001"),
+    ];
+
+    let issues = Validator::validate_migration_sequence(&migrations);
+    assert!(issues.iter().any(|msg| msg.contains("Dependency cycle detected")));
+}
+
+#[test]
+fn planner_orders_declared_dependencies_first() {
+    let migrations = vec![
+        make_migration(1, "first"),
+        make_migration_with_requires(2, "second", "0003"),
+        make_migration(3, "third"),
+    ];
+
+    let ordered = Planner::order_migrations(migrations).unwrap();
+    let versions: Vec<_> = ordered.iter().map(|m| m.version.unwrap()).collect();
+    assert_eq!(versions, vec![1, 3, 2]);
+}
+
+#[test]
+fn planner_batches_migrations_after_their_dependencies() {
+    let ordered = Planner::order_migrations(vec![
+        make_migration(1, "first"),
+        make_migration(2, "second"),
+        make_migration_with_requires(3, "third", "0001"),
+        make_migration_with_requires(4, "fourth", "0003"),
+    ])
+    .unwrap();
+
+    assert_eq!(Planner::execution_batches(&ordered), vec![vec![0, 1], vec![2], vec![3]]);
+}
+
+#[test]
+fn detects_destructive_statements() {
+    let migration = Migration::new(
+        4,
+        "cleanup".to_string(),
+        PathBuf::from("0004_cleanup.sql"),
+        "-- DROP TABLE in a comment is fine\nDELETE FROM audit WHERE id < 10;\nALTER TABLE users DROP COLUMN legacy;\nTRUNCATE sessions;\nCREATE TABLE x (id INT);".to_string(),
+    );
+
+    assert_eq!(
+        migration.destructive_statements(),
+        vec!["ALTER TABLE users DROP COLUMN legacy", "TRUNCATE sessions"]
+    );
+}
+
+#[test]
+fn detects_applied_migration_renumbered_to_new_version() {
+    let renumbered = make_migration(9, "add_index");
+    let applied = AppliedMigration {
+        checksum: Migration::new(5, "add_index".to_string(), PathBuf::from("0005_add_index.sql"), "-- migration 9".to_string()).checksum,
+        ..applied_migration(5, "0005_add_index.sql")
+    };
+
+    let renamed = Validator::detect_renamed_migrations(&[applied], &[make_migration(1, "init"), renumbered]);
+    assert_eq!(renamed.len(), 1);
+    assert_eq!(renamed[0].to_string(), "Migration 0005 appears renamed to 0009_add_index.sql");
+}